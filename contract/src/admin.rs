@@ -96,6 +96,15 @@ impl Contract {
         }
     }
 
+    /// Set the NEAR price of one priority level (only owner can call)
+    ///
+    /// A request with `priority` p pays `p * priority_fee` on top of its compute cost.
+    pub fn set_priority_fee(&mut self, priority_fee: U128) {
+        self.assert_owner();
+        self.priority_fee = priority_fee.0;
+        log!("Priority fee (NEAR) updated to {}", priority_fee.0);
+    }
+
     /// Set payment token contract for HTTPS API (only owner can call)
     /// This is the stablecoin contract used for Payment Keys (e.g., "usdt.tether-token.near")
     pub fn set_payment_token_contract(&mut self, token_contract: Option<AccountId>) {
@@ -111,22 +120,112 @@ impl Contract {
     pub fn emergency_cancel_execution(&mut self, request_id: u64) {
         self.assert_owner();
 
-        if let Some(request) = self.pending_requests.remove(&request_id) {
+        if let Some(request) = self.remove_pending_request(request_id) {
             // Refund payment to payer
-            near_sdk::Promise::new(request.payer_account_id.clone())
+            near_sdk::Promise::new(request.refund_recipient().clone())
                 .transfer(NearToken::from_yoctonear(request.payment));
+            self.record_finished_request(
+                &request,
+                None,
+                RequestOutcome::Cancelled { reason: "emergency cancel by owner".to_string() },
+            );
 
             log!(
                 "Emergency cancelled execution {} and refunded {} yoctoNEAR to {}",
                 request_id,
                 request.payment,
-                request.payer_account_id
+                request.refund_recipient()
             );
         } else {
             env::panic_str("Execution request not found");
         }
     }
 
+    /// Set global cap on pending execution requests (only owner can call)
+    ///
+    /// Once this many requests are unresolved, `request_execution` rejects
+    /// new submissions with "service at capacity, try later".
+    pub fn set_max_pending_requests(&mut self, max_pending_requests: u64) {
+        self.assert_owner();
+        assert!(max_pending_requests > 0, "max_pending_requests must be positive");
+        self.max_pending_requests = max_pending_requests;
+        log!("Max pending requests set to {}", max_pending_requests);
+    }
+
+    /// Set cap on pending execution requests of one sender (only owner can call)
+    ///
+    /// A sender with this many unresolved requests is rejected until one resolves,
+    /// so a single account cannot fill the global `max_pending_requests`.
+    pub fn set_max_pending_per_account(&mut self, max_pending_per_account: u32) {
+        self.assert_owner();
+        assert!(max_pending_per_account > 0, "max_pending_per_account must be positive");
+        self.max_pending_per_account = max_pending_per_account;
+        log!("Max pending requests per account set to {}", max_pending_per_account);
+    }
+
+    /// Set the largest `input_data` `request_execution` accepts, in bytes (only owner can call)
+    pub fn set_max_input_bytes(&mut self, max_input_bytes: u32) {
+        self.assert_owner();
+        assert!(
+            (1..=MAX_INPUT_BYTES_LIMIT).contains(&max_input_bytes),
+            "max_input_bytes must be between 1 and {}",
+            MAX_INPUT_BYTES_LIMIT
+        );
+        self.max_input_bytes = max_input_bytes;
+        log!("Max input bytes set to {}", max_input_bytes);
+    }
+
+    /// Set or clear (`None`) resource cap overrides for one project (only owner can call)
+    ///
+    /// `project` is a `project_id` ("alice.near/my-app") or a GitHub repo as given in
+    /// the code source. Requests for it are validated against these caps instead of
+    /// MAX_INSTRUCTIONS / MAX_EXECUTION_SECONDS; every other project keeps the global ones.
+    pub fn set_project_limits(&mut self, project: String, limits: Option<ProjectLimits>) {
+        self.assert_owner();
+        assert!(!project.trim().is_empty(), "project cannot be empty");
+        let Some(limits) = limits else {
+            self.project_limits.remove(&project);
+            log!("Project limits cleared for {}", project);
+            return;
+        };
+
+        assert!(
+            limits.max_instructions >= MAX_INSTRUCTIONS && limits.max_execution_seconds >= MAX_EXECUTION_SECONDS,
+            "Project limits cannot be below the global caps ({} instructions, {} seconds)",
+            MAX_INSTRUCTIONS,
+            MAX_EXECUTION_SECONDS
+        );
+        // A longer run would be cancelled as stale before it could finish
+        let max_execution_seconds = EXECUTION_TIMEOUT / 1_000_000_000;
+        assert!(
+            limits.max_execution_seconds <= max_execution_seconds,
+            "max_execution_seconds cannot exceed the {} second execution timeout",
+            max_execution_seconds
+        );
+
+        self.project_limits.insert(&project, &limits);
+        log!(
+            "Project limits for {} set to {} instructions, {} seconds",
+            project,
+            limits.max_instructions,
+            limits.max_execution_seconds
+        );
+    }
+
+    /// Set how many secret versions `set_secrets` retains per profile (only owner can call)
+    ///
+    /// Lowering the limit prunes existing histories on their next `set_secrets`.
+    pub fn set_max_secret_versions(&mut self, max_secret_versions: u32) {
+        self.assert_owner();
+        assert!(
+            (1..=MAX_SECRET_VERSIONS_LIMIT).contains(&max_secret_versions),
+            "max_secret_versions must be between 1 and {}",
+            MAX_SECRET_VERSIONS_LIMIT
+        );
+        self.max_secret_versions = max_secret_versions;
+        log!("Max secret versions set to {}", max_secret_versions);
+    }
+
     /// Set event metadata (only owner can call)
     /// Used to customize event standard name and version for different deployments
     pub fn set_event_metadata(&mut self, standard: Option<String>, version: Option<String>) {
@@ -155,15 +254,20 @@ impl Contract {
         let mut cancelled = 0;
 
         for request_id in request_ids {
-            if let Some(request) = self.pending_requests.remove(&request_id) {
-                near_sdk::Promise::new(request.payer_account_id.clone())
+            if let Some(request) = self.remove_pending_request(request_id) {
+                near_sdk::Promise::new(request.refund_recipient().clone())
                     .transfer(NearToken::from_yoctonear(request.payment));
+                self.record_finished_request(
+                    &request,
+                    None,
+                    RequestOutcome::Cancelled { reason: "cancelled by owner".to_string() },
+                );
 
                 log!(
                     "Cancelled request {} and refunded {} yoctoNEAR to {}",
                     request_id,
                     request.payment,
-                    request.payer_account_id
+                    request.refund_recipient()
                 );
 
                 cancelled += 1;
@@ -193,16 +297,21 @@ impl Contract {
                 break;
             }
 
-            if let Some(request) = self.pending_requests.remove(&request_id) {
+            if let Some(request) = self.remove_pending_request(request_id) {
                 // Refund payment to payer
-                near_sdk::Promise::new(request.payer_account_id.clone())
+                near_sdk::Promise::new(request.refund_recipient().clone())
                     .transfer(NearToken::from_yoctonear(request.payment));
+                self.record_finished_request(
+                    &request,
+                    None,
+                    RequestOutcome::Cancelled { reason: "cleared by owner".to_string() },
+                );
 
                 log!(
                     "Cleared request {} and refunded {} yoctoNEAR to {}",
                     request_id,
                     request.payment,
-                    request.payer_account_id
+                    request.refund_recipient()
                 );
 
                 cleared += 1;
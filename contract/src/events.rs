@@ -1,75 +1,137 @@
 use crate::*;
 use near_sdk::serde_json::json;
 
-pub mod emit {
-    use super::*;
-    use near_sdk::{env, log};
-
-    #[derive(Serialize)]
-    #[serde(crate = "near_sdk::serde")]
-    struct ExecutionRequestedEventData<'a> {
-        pub request_data: &'a String,
-        pub data_id: CryptoHash,
-        pub timestamp: u64,
-    }
-
-    #[derive(Serialize)]
-    #[serde(crate = "near_sdk::serde")]
-    struct ExecutionCompletedEventData<'a> {
-        pub sender_id: &'a AccountId,
-        pub code_source: &'a CodeSource,
-        pub resources_used: &'a ResourceMetrics,
-        pub success: bool,
-        pub error_message: Option<&'a str>,
-        pub payment_charged: U128,    // Actual amount charged (after refund)
-        pub payment_refunded: U128,   // Amount refunded to user
-        pub compilation_note: Option<&'a str>, // e.g., "Cached WASM from 2025-01-10 14:30 UTC"
-        pub timestamp: u64,
-    }
-
-    fn log_event<T: Serialize>(standard: &str, version: &str, event: &str, data: T) {
-        let event = json!({
-            "standard": standard,
-            "version": version,
-            "event": event,
-            "data": [data]
-        });
+/// Key lifecycle transitions, emitted as NEP-297 events for indexers
+///
+/// Each variant logs `EVENT_JSON:{"standard", "version", "event", "data": [..]}`
+/// with the event name from [`ContractEvent::name`]. `execution_requested`
+/// keeps the payload workers already consume. `execution_completed` is
+/// deprecated in favor of `execution_resolved`/`execution_failed` and is only
+/// emitted while the configured event version is below 2.0.0.
+pub enum ContractEvent<'a> {
+    /// Request accepted and waiting for a worker
+    ExecutionRequested {
+        request_data: &'a str,
+        data_id: CryptoHash,
+    },
+    /// Execution finished, successfully or not (deprecated, see [`emits_execution_completed`])
+    ExecutionCompleted {
+        sender_id: &'a AccountId,
+        code_source: &'a CodeSource,
+        resources_used: &'a ResourceMetrics,
+        success: bool,
+        error_message: Option<&'a str>,
+        /// Amount charged after the refund
+        payment_charged: U128,
+        payment_refunded: U128,
+        /// e.g. "Cached WASM from 2025-01-10 14:30 UTC"
+        compilation_note: Option<&'a str>,
+    },
+    /// Execution succeeded and the caller was charged `actual_cost`
+    ExecutionResolved {
+        request_id: u64,
+        sender_id: &'a AccountId,
+        actual_cost: U128,
+        payment_refunded: U128,
+        instructions: u64,
+        time_ms: u64,
+    },
+    /// Execution (or its promise) failed; only the base fee was charged
+    ExecutionFailed {
+        request_id: u64,
+        sender_id: &'a AccountId,
+        error: &'a str,
+        /// `{"code", "message", "details"}` when the WASM failed via `env::fail`
+        error_structured: Option<&'a serde_json::Value>,
+        payment_charged: U128,
+        payment_refunded: U128,
+    },
+    /// A secret profile was stored or rolled back
+    SecretsUpdated {
+        owner: &'a AccountId,
+        accessor: &'a SecretAccessor,
+        profile: &'a str,
+        change: SecretsChange,
+        /// Version created or restored (versioned `set_secrets`/`rollback_secret` only)
+        version: Option<u32>,
+    },
+    /// A secret profile and its retained versions were removed
+    SecretsDeleted {
+        owner: &'a AccountId,
+        accessor: &'a SecretAccessor,
+        profile: &'a str,
+        /// Storage deposit sent back to the owner
+        storage_refunded: U128,
+    },
+    /// Stale request cancelled by its sender or payer; the whole payment was refunded
+    ExecutionCancelled {
+        request_id: u64,
+        sender_id: &'a AccountId,
+        cancelled_by: &'a AccountId,
+        refund_recipient: &'a AccountId,
+        payment_refunded: U128,
+        reason: &'a str,
+    },
+    /// Unused NEAR payment sent back on resolution
+    RefundIssued {
+        request_id: u64,
+        recipient: &'a AccountId,
+        amount: U128,
+        reason: &'a str,
+    },
+    /// A refund transfer failed; the funds stayed with the contract
+    RefundFailed {
+        request_id: u64,
+        recipient: &'a AccountId,
+        amount: U128,
+    },
+    /// Worker's signature over a resolved result
+    ExecutionResultSigned {
+        request_id: u64,
+        result_signature: &'a ResultSignature,
+    },
+    /// Pending requests reached the global cap; submissions are rejected until some resolve
+    PendingCapacityReached {
+        pending_requests: u64,
+        max_pending_requests: u64,
+    },
+}
 
-        log!("EVENT_JSON:{}", event.to_string());
-    }
+/// What happened to a secret profile in [`ContractEvent::SecretsUpdated`]
+#[derive(Serialize, Clone, Copy)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+pub enum SecretsChange {
+    Stored,
+    RolledBack,
+}
 
-    /// Emit event when worker should process execution request
-    pub fn execution_requested(standard: &str, version: &str, request_data: &String, data_id: CryptoHash) {
-        log_event(
-            standard,
-            version,
-            "execution_requested",
-            ExecutionRequestedEventData {
-                request_data,
-                data_id,
-                timestamp: env::block_timestamp(),
-            },
-        );
+impl ContractEvent<'_> {
+    /// NEP-297 `event` field
+    pub fn name(&self) -> &'static str {
+        match self {
+            ContractEvent::ExecutionRequested { .. } => "execution_requested",
+            ContractEvent::ExecutionCompleted { .. } => "execution_completed",
+            ContractEvent::ExecutionResolved { .. } => "execution_resolved",
+            ContractEvent::ExecutionFailed { .. } => "execution_failed",
+            ContractEvent::ExecutionCancelled { .. } => "execution_cancelled",
+            ContractEvent::SecretsUpdated { .. } => "secrets_updated",
+            ContractEvent::SecretsDeleted { .. } => "secrets_deleted",
+            ContractEvent::RefundIssued { .. } => "refund_issued",
+            ContractEvent::RefundFailed { .. } => "refund_failed",
+            ContractEvent::ExecutionResultSigned { .. } => "execution_result_signed",
+            ContractEvent::PendingCapacityReached { .. } => "pending_capacity_reached",
+        }
     }
 
-    /// Emit event when execution is completed (success or failure)
-    pub fn execution_completed(
-        standard: &str,
-        version: &str,
-        sender_id: &AccountId,
-        code_source: &CodeSource,
-        resources_used: &ResourceMetrics,
-        success: bool,
-        error_message: Option<&str>,
-        payment_charged: U128,
-        payment_refunded: U128,
-        compilation_note: Option<&str>,
-    ) {
-        log_event(
-            standard,
-            version,
-            "execution_completed",
-            ExecutionCompletedEventData {
+    fn data(&self) -> near_sdk::serde_json::Value {
+        let timestamp = near_sdk::env::block_timestamp();
+        match self {
+            ContractEvent::ExecutionRequested { request_data, data_id } => json!({
+                "request_data": request_data,
+                "data_id": data_id,
+                "timestamp": timestamp,
+            }),
+            ContractEvent::ExecutionCompleted {
                 sender_id,
                 code_source,
                 resources_used,
@@ -78,12 +140,168 @@ pub mod emit {
                 payment_charged,
                 payment_refunded,
                 compilation_note,
-                timestamp: env::block_timestamp(),
-            },
-        );
+            } => json!({
+                "sender_id": sender_id,
+                "code_source": code_source,
+                "resources_used": resources_used,
+                "success": success,
+                "error_message": error_message,
+                "payment_charged": payment_charged,
+                "payment_refunded": payment_refunded,
+                "compilation_note": compilation_note,
+                "timestamp": timestamp,
+            }),
+            ContractEvent::ExecutionResolved {
+                request_id,
+                sender_id,
+                actual_cost,
+                payment_refunded,
+                instructions,
+                time_ms,
+            } => json!({
+                "request_id": request_id,
+                "sender_id": sender_id,
+                "actual_cost": actual_cost,
+                "payment_refunded": payment_refunded,
+                "instructions": instructions,
+                "time_ms": time_ms,
+                "timestamp": timestamp,
+            }),
+            ContractEvent::ExecutionFailed {
+                request_id,
+                sender_id,
+                error,
+                error_structured,
+                payment_charged,
+                payment_refunded,
+            } => json!({
+                "request_id": request_id,
+                "sender_id": sender_id,
+                "error": error,
+                "error_structured": error_structured,
+                "payment_charged": payment_charged,
+                "payment_refunded": payment_refunded,
+                "timestamp": timestamp,
+            }),
+            ContractEvent::ExecutionCancelled {
+                request_id,
+                sender_id,
+                cancelled_by,
+                refund_recipient,
+                payment_refunded,
+                reason,
+            } => json!({
+                "request_id": request_id,
+                "sender_id": sender_id,
+                "cancelled_by": cancelled_by,
+                "refund_recipient": refund_recipient,
+                "payment_refunded": payment_refunded,
+                "reason": reason,
+                "timestamp": timestamp,
+            }),
+            ContractEvent::SecretsUpdated {
+                owner,
+                accessor,
+                profile,
+                change,
+                version,
+            } => json!({
+                "owner": owner,
+                "accessor": accessor,
+                "profile": profile,
+                "change": change,
+                "version": version,
+                "timestamp": timestamp,
+            }),
+            ContractEvent::SecretsDeleted {
+                owner,
+                accessor,
+                profile,
+                storage_refunded,
+            } => json!({
+                "owner": owner,
+                "accessor": accessor,
+                "profile": profile,
+                "storage_refunded": storage_refunded,
+                "timestamp": timestamp,
+            }),
+            ContractEvent::RefundIssued {
+                request_id,
+                recipient,
+                amount,
+                reason,
+            } => json!({
+                "request_id": request_id,
+                "recipient": recipient,
+                "amount": amount,
+                "reason": reason,
+                "timestamp": timestamp,
+            }),
+            ContractEvent::RefundFailed {
+                request_id,
+                recipient,
+                amount,
+            } => json!({
+                "request_id": request_id,
+                "recipient": recipient,
+                "amount": amount,
+                "timestamp": timestamp,
+            }),
+            ContractEvent::ExecutionResultSigned {
+                request_id,
+                result_signature,
+            } => json!({
+                "request_id": request_id,
+                "worker_public_key": result_signature.worker_public_key,
+                "input_hash": result_signature.input_hash,
+                "output_hash": result_signature.output_hash,
+                "signature": result_signature.signature,
+                "timestamp": timestamp,
+            }),
+            ContractEvent::PendingCapacityReached {
+                pending_requests,
+                max_pending_requests,
+            } => json!({
+                "pending_requests": pending_requests,
+                "max_pending_requests": max_pending_requests,
+                "timestamp": timestamp,
+            }),
+        }
+    }
+
+    /// Log the event under the contract's configured standard and version
+    pub fn emit(&self, standard: &str, version: &str) {
+        if matches!(self, ContractEvent::ExecutionCompleted { .. }) && !emits_execution_completed(version) {
+            return;
+        }
+        log_event(standard, version, self.name(), self.data());
     }
 }
 
+/// Version emitted by new deployments (1.1.0: lifecycle events, `execution_completed` deprecated)
+pub const EVENT_VERSION: &str = "1.1.0";
+
+/// Whether the deprecated `execution_completed` is still emitted under `version`
+///
+/// It goes away from event version 2.0.0; unparseable versions keep it.
+pub fn emits_execution_completed(version: &str) -> bool {
+    version
+        .split('.')
+        .next()
+        .and_then(|major| major.parse::<u32>().ok())
+        .map_or(true, |major| major < 2)
+}
+
+fn log_event(standard: &str, version: &str, event: &str, data: near_sdk::serde_json::Value) {
+    let event = json!({
+        "standard": standard,
+        "version": version,
+        "event": event,
+        "data": [data]
+    });
+
+    near_sdk::log!("EVENT_JSON:{}", event.to_string());
+}
 
 pub fn emit_project_storage_cleanup(
     standard: &str,
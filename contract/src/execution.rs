@@ -7,17 +7,20 @@ impl Contract {
     ///
     /// # Arguments
     /// * `source` - Execution source: GitHub repo, WasmUrl, or Project reference
-    /// * `resource_limits` - Optional resource limits for execution (default: 1B instructions, 128MB, 60s)
+    /// * `resource_limits` - Optional resource limits for execution (default: 1B instructions, 128MB, 60s, 128KB output)
     ///                      If None, only compilation is performed (compile-only mode)
-    /// * `input_data` - Optional input data for the WASM program (default: empty string)
-    /// * `secrets_ref` - Optional reference to secrets (profile + account_id)
-    /// * `response_format` - Optional output format: Bytes, Text, or Json (default: Text)
+    /// * `input_data` - Optional input data for the WASM program (default: empty string), at most
+    ///                  `max_input_bytes` (see `get_limits`); pass larger inputs as a storage key or URL
+    /// * `secrets_ref` - Optional reference to secrets (profile + account_id, optional pinned version)
+    /// * `response_format` - Optional output format: Bytes, Text, Json, Cbor, MessagePack, or Base64 (default: Text)
     /// * `payer_account_id` - Optional account to receive refunds (default: sender)
-    /// * `params` - Optional request parameters (force_rebuild, store_on_fastfs)
+    /// * `params` - Optional request parameters (force_rebuild, store_on_fastfs, refund_account_id, stale_timeout_seconds,
+    ///              idempotency_key, priority, input_content_type)
     ///
     /// # Execution Source
     /// You can specify code in three ways:
-    /// - `GitHub { repo, commit, build_target }` - Compile from GitHub repository
+    /// - `GitHub { repo, commit, build_target, package, features }` - Compile from GitHub repository
+    ///   (`package`/`features` select a workspace member and cargo features, both optional)
     /// - `WasmUrl { url, hash, build_target }` - Use pre-compiled WASM from URL
     /// - `Project { project_id, version_key }` - Use registered project (version_key=None for active version)
     ///
@@ -32,6 +35,21 @@ impl Contract {
     /// 1. Store secrets once: `store_secrets(accessor, profile, encrypted_data, access_rules)`
     /// 2. Reference them in execution: `secrets_ref: { profile: "default", account_id: "alice.near" }`
     /// 3. Worker will fetch and decrypt secrets via keystore
+    ///
+    /// Profiles written with `set_secrets` keep their last versions; set
+    /// `secrets_ref.version` to run against a retained version instead of
+    /// the current one.
+    ///
+    /// # Idempotency
+    /// With `params.idempotency_key`, resubmitting after an unclear outcome is safe: if the
+    /// caller already has a request with that key that is pending or finished less than
+    /// IDEMPOTENCY_WINDOW ago, the call returns that request_id, refunds the attached
+    /// deposit and creates nothing. The other arguments of the resubmission are ignored.
+    ///
+    /// # Priority
+    /// `params.priority` (0 to MAX_PRIORITY) is recorded on the request and counted in
+    /// `get_queue_depth_by_priority`. Each level adds `priority_fee` (zero by default) to
+    /// the cost. Priority never affects resource limits.
     #[payable]
     pub fn request_execution(
         &mut self,
@@ -45,11 +63,39 @@ impl Contract {
     ) {
         self.assert_not_paused();
 
+        // Resubmission of a request that already landed: answer with its id, charge nothing
+        let idempotency_key = params.as_ref().and_then(|p| p.idempotency_key.clone());
+        if let Some(key) = &idempotency_key {
+            assert!(
+                !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LEN,
+                "idempotency_key must be 1 to {} bytes",
+                MAX_IDEMPOTENCY_KEY_LEN
+            );
+            let entry = (env::predecessor_account_id(), key.clone());
+            if let Some(request_id) = self.live_idempotent_request(&entry) {
+                self.return_existing_request(request_id, &entry);
+                return;
+            }
+        }
+
+        // Global backpressure: reject new work while workers are behind
+        assert!(
+            self.pending_requests_count < self.max_pending_requests,
+            "service at capacity, try later"
+        );
+        // Fairness: one sender cannot take the whole queue
+        let sender_pending = self.pending_by_account.get(&env::predecessor_account_id()).unwrap_or(0);
+        assert!(
+            sender_pending < self.max_pending_per_account,
+            "sender has {} pending requests, the maximum per account; try later",
+            sender_pending
+        );
+
         // Resolve ExecutionSource to CodeSource (and get project_uuid if applicable)
         let (resolved_source, project_uuid) = self.resolve_execution_source(&source);
 
         // Use provided limits or defaults (for execute mode)
-        let limits = resource_limits.clone().unwrap_or_default();
+        let mut limits = resource_limits.clone().unwrap_or_default();
 
         // Get params or defaults, but override project_uuid if resolved from Project source
         let mut request_params = params.unwrap_or_default();
@@ -75,31 +121,90 @@ impl Contract {
             env::panic_str("input_data must be empty for compile_only mode - compilation does not use input_data");
         }
 
+        // Validate: input size (checked before anything is charged)
+        let input_len = input_data.as_ref().map_or(0, |d| d.len());
+        assert!(
+            input_len <= self.max_input_bytes as usize,
+            "input_data is {} bytes, exceeds max_input_bytes of {}; pass large inputs as a storage key or URL instead",
+            input_len,
+            self.max_input_bytes
+        );
+
+        // Validate: declared input media type looks like `type/subtype[; params]`
+        if let Some(content_type) = &request_params.input_content_type {
+            assert!(
+                is_valid_content_type(content_type),
+                "input_content_type must be a type/subtype media type of at most {} printable ASCII bytes",
+                MAX_INPUT_CONTENT_TYPE_LEN
+            );
+        }
+
+        // Validate: stale timeout override must stay within [MIN_STALE_TIMEOUT_SECONDS, EXECUTION_TIMEOUT]
+        if let Some(stale_timeout_seconds) = request_params.stale_timeout_seconds {
+            let max_stale_timeout_seconds = EXECUTION_TIMEOUT / 1_000_000_000;
+            assert!(
+                (MIN_STALE_TIMEOUT_SECONDS..=max_stale_timeout_seconds).contains(&stale_timeout_seconds),
+                "stale_timeout_seconds {} must be between {} and {} seconds",
+                stale_timeout_seconds,
+                MIN_STALE_TIMEOUT_SECONDS,
+                max_stale_timeout_seconds
+            );
+        }
+
+        // Validate: priority is a known queue tier
+        let priority = request_params.priority.unwrap_or(0);
+        assert!(
+            priority <= MAX_PRIORITY,
+            "priority {} exceeds maximum of {}",
+            priority,
+            MAX_PRIORITY
+        );
+
+        // Validate: pinned secret versions are numbered from 1
+        if let Some(SecretsReference { version: Some(version), .. }) = &secrets_ref {
+            assert!(*version > 0, "secrets_ref.version must be at least 1");
+        }
+
         // Validate resource limits against hard caps (only in execute mode)
         if !compile_only {
             let max_instructions = limits.max_instructions.unwrap_or_default();
             let max_execution_seconds = limits.max_execution_seconds.unwrap_or_default();
+            let project_id = match &source {
+                ExecutionSource::Project { project_id, .. } => Some(project_id.as_str()),
+                _ => None,
+            };
+            let caps = self.resource_caps(project_id, &resolved_source);
 
             assert!(
-                max_instructions <= MAX_INSTRUCTIONS,
+                max_instructions <= caps.max_instructions,
                 "Requested max_instructions {} exceeds hard limit of {}",
                 max_instructions,
-                MAX_INSTRUCTIONS
+                caps.max_instructions
             );
 
             assert!(
-                max_execution_seconds <= MAX_EXECUTION_SECONDS,
+                max_execution_seconds <= caps.max_execution_seconds,
                 "Requested max_execution_seconds {} exceeds hard limit of {} seconds",
                 max_execution_seconds,
-                MAX_EXECUTION_SECONDS
+                caps.max_execution_seconds
+            );
+
+            // Stored normalized so the worker always sees the cap it must enforce
+            let max_output_bytes = *limits.max_output_bytes.get_or_insert(DEFAULT_MAX_OUTPUT_BYTES);
+            assert!(
+                max_output_bytes <= MAX_OUTPUT_BYTES,
+                "Requested max_output_bytes {} exceeds hard limit of {} bytes",
+                max_output_bytes,
+                MAX_OUTPUT_BYTES
             );
         }
 
-        // Calculate cost: base fee for compile-only, full estimate for execute
+        // Calculate cost: base fee for compile-only, full estimate for execute (both plus priority)
+        let pricing = self.current_pricing();
         let estimated_cost = if compile_only {
-            self.base_fee // Only base fee for compile-only
+            pricing.base_fee + pricing.priority_cost(priority)
         } else {
-            self.estimate_cost(&limits)
+            pricing.estimate_cost(&limits, priority)
         };
 
         // Parse attached_usd for project owner (developer payment in stablecoin)
@@ -183,6 +288,7 @@ impl Contract {
             "resource_limits": limits,
             "input_data": input_data_for_event,
             "input_data_in_state": input_data_in_state,
+            "input_content_type": request_params.input_content_type,
             "secrets_ref": secrets_ref.as_ref(),
             "response_format": format,
             "payment": U128::from(payment),
@@ -192,7 +298,8 @@ impl Contract {
             "force_rebuild": request_params.force_rebuild,
             "store_on_fastfs": request_params.store_on_fastfs,
             "project_uuid": request_params.project_uuid,
-            "project_id": project_id
+            "project_id": project_id,
+            "priority": priority
         });
 
         // Create yield promise to pause execution
@@ -225,17 +332,28 @@ impl Contract {
             secrets_ref,
             response_format: format.clone(),
             input_data,
+            input_content_type: request_params.input_content_type,
             payer_account_id,
             attached_usd,
             pending_output: None,
             output_submitted: false,
+            refund_account_id: request_params.refund_account_id,
+            stale_timeout_seconds: request_params.stale_timeout_seconds,
+            output_submitted_at: None,
+            resolved_at: None,
+            priority,
+            pricing_snapshot: pricing,
         };
 
-        self.pending_requests
-            .insert(&request_id, &execution_request);
+        self.insert_pending_request(request_id, &execution_request);
+        self.record_idempotency_key(request_id, idempotency_key.map(|key| (predecessor_id.clone(), key)));
 
         // Emit event for workers to catch
-        events::emit::execution_requested(&self.event_standard, &self.event_version, &request_data.to_string(), data_id);
+        events::ContractEvent::ExecutionRequested {
+            request_data: &request_data.to_string(),
+            data_id,
+        }
+        .emit(&self.event_standard, &self.event_version);
 
         // Return the promise to pause execution
         env::promise_return(promise_idx)
@@ -266,6 +384,8 @@ impl Contract {
     /// * `success` - Whether execution succeeded
     /// * `error` - Error message if failed
     /// * `resources_used` - Actual resource consumption
+    /// * `result_signature` - Worker signature over the result (optional)
+    #[allow(clippy::too_many_arguments)]
     pub fn submit_execution_output_and_resolve(
         &mut self,
         request_id: u64,
@@ -274,6 +394,8 @@ impl Contract {
         error: Option<String>,
         resources_used: ResourceMetrics,
         compilation_note: Option<String>,
+        result_signature: Option<ResultSignature>,
+        declared_output_format: Option<ResponseFormat>,
     ) {
         // Only operator can submit execution data
         self.assert_operator();
@@ -286,9 +408,12 @@ impl Contract {
             success,
             output: None, // Output already stored above
             error,
+            error_structured: None, // Only failed executions report one
             resources_used,
             compilation_note,
             refund_usd: None, // Large output flow doesn't support refund
+            result_signature,
+            declared_output_format,
         };
 
         log!(
@@ -314,6 +439,23 @@ impl Contract {
         self.resolve_execution_internal(request_id, response);
     }
 
+    /// Worker calls this to resolve several executions in one transaction
+    ///
+    /// Each entry is resolved independently: unknown or duplicate request ids and
+    /// oversized responses are skipped, and the rest of the batch still goes through.
+    /// Charging, refunds and completion events happen per request in
+    /// `on_execution_response`, exactly as for `resolve_execution`.
+    ///
+    /// Returns one status per input entry, in input order.
+    pub fn resolve_batch(&mut self, results: Vec<(u64, ExecutionResponse)>) -> Vec<ResolveBatchEntry> {
+        // Only operator can resolve executions
+        self.assert_operator();
+
+        self.resolve_batch_internal(results, |data_id, payload| {
+            env::promise_yield_resume(data_id, payload)
+        })
+    }
+
     #[allow(unused_variables)]
     #[private]
     /// Callback function to handle execution completion
@@ -327,7 +469,7 @@ impl Contract {
         #[callback_result] response: Result<ExecutionResponse, PromiseError>,
     ) -> Option<serde_json::Value> {
         // Remove the pending request and check if output was submitted separately
-        if let Some(request) = self.pending_requests.remove(&request_id) {
+        if let Some(request) = self.remove_pending_request(request_id) {
             self.total_executions += 1;
 
             match response {
@@ -343,7 +485,7 @@ impl Contract {
 
                     if exec_response.success {
                         // Calculate actual cost (NEAR only)
-                        let cost = self.calculate_cost(&exec_response.resources_used);
+                        let cost = request.pricing_snapshot.calculate_cost(&exec_response.resources_used, request.priority);
 
                         // Handle stablecoin payment with refund support
                         if request.attached_usd > 0 {
@@ -380,12 +522,7 @@ impl Contract {
                         }
 
                         // Refund excess NEAR payment (minus compute cost only, stablecoin is separate)
-                        let refund = payment.0.saturating_sub(cost);
-                        if refund > 0 {
-                            // Transfer refund to payer account
-                            near_sdk::Promise::new(request.payer_account_id.clone())
-                                .transfer(NearToken::from_yoctonear(refund));
-                        }
+                        let refund = self.refund_payment(&request, payment.0.saturating_sub(cost), "overpayment");
 
                         // Collect fee
                         self.total_fees_collected += cost;
@@ -393,31 +530,47 @@ impl Contract {
                         // Log payment charged in easy-to-parse format for worker
                         log!("[[yNEAR charged: \"{}\"]]", cost);
 
-                        // Emit success event
-                        events::emit::execution_completed(
-                            &self.event_standard,
-                            &self.event_version,
-                            &sender_id,
-                            &code_source,
-                            &exec_response.resources_used,
-                            true,
-                            None,
-                            U128(cost),    // payment_charged
-                            U128(refund),  // payment_refunded
-                            exec_response.compilation_note.as_deref(),
+                        self.record_finished_request(
+                            &request,
+                            exec_response.resources_used.compile_time_ms,
+                            RequestOutcome::Completed { payment_charged: cost, payment_refunded: refund },
                         );
 
+                        // Emit success event
+                        events::ContractEvent::ExecutionCompleted {
+                            sender_id: &sender_id,
+                            code_source: &code_source,
+                            resources_used: &exec_response.resources_used,
+                            success: true,
+                            error_message: None,
+                            payment_charged: U128(cost),
+                            payment_refunded: U128(refund),
+                            compilation_note: exec_response.compilation_note.as_deref(),
+                        }
+                        .emit(&self.event_standard, &self.event_version);
+                        events::ContractEvent::ExecutionResolved {
+                            request_id,
+                            sender_id: &sender_id,
+                            actual_cost: U128(cost),
+                            payment_refunded: U128(refund),
+                            instructions: exec_response.resources_used.instructions,
+                            time_ms: exec_response.resources_used.time_ms,
+                        }
+                        .emit(&self.event_standard, &self.event_version);
+
                         // Log the execution result with resources used
                         if let Some(output) = exec_response.output {
                             // Convert ExecutionOutput to plain JSON value (without enum wrapper)
                             let json_value = match &output {
-                                ExecutionOutput::Bytes(bytes) => {
-                                    // For bytes, encode as base64 string
+                                ExecutionOutput::Bytes(bytes)
+                                | ExecutionOutput::Cbor(bytes)
+                                | ExecutionOutput::MessagePack(bytes) => {
+                                    // For bytes (and binary encodings), encode as base64 string
                                     use near_sdk::base64::{engine::general_purpose::STANDARD, Engine};
                                     serde_json::Value::String(STANDARD.encode(bytes))
                                 }
-                                ExecutionOutput::Text(text) => {
-                                    // For text, return as JSON string
+                                ExecutionOutput::Text(text) | ExecutionOutput::Base64(text) => {
+                                    // For text (and already-encoded base64), return as JSON string
                                     serde_json::Value::String(text.clone())
                                 }
                                 ExecutionOutput::Json(value) => {
@@ -429,6 +582,9 @@ impl Contract {
                             // Log for debugging (with type info, truncated to avoid log limit)
                             let log_preview = match &output {
                                 ExecutionOutput::Bytes(bytes) => format!("Bytes({} bytes)", bytes.len()),
+                                ExecutionOutput::Cbor(bytes) => format!("Cbor({} bytes)", bytes.len()),
+                                ExecutionOutput::MessagePack(bytes) => format!("MessagePack({} bytes)", bytes.len()),
+                                ExecutionOutput::Base64(text) => format!("Base64({} chars)", text.len()),
                                 ExecutionOutput::Text(text) => {
                                     let preview: String = text.chars().take(100).collect();
                                     if text.len() > 100 {
@@ -486,11 +642,7 @@ impl Contract {
                         // Developer gets nothing on failure
 
                         // Refund NEAR (minus base fee)
-                        let refund = payment.0.saturating_sub(self.base_fee);
-                        if refund > 0 {
-                            near_sdk::Promise::new(request.payer_account_id.clone())
-                                .transfer(NearToken::from_yoctonear(refund));
-                        }
+                        let refund = self.refund_payment(&request, payment.0.saturating_sub(request.pricing_snapshot.base_fee), "execution_failed");
 
                         // Refund stablecoin to user's balance
                         if request.attached_usd > 0 {
@@ -503,28 +655,42 @@ impl Contract {
                             );
                         }
 
-                        self.total_fees_collected += self.base_fee;
+                        self.total_fees_collected += request.pricing_snapshot.base_fee;
 
                         // Log payment charged in easy-to-parse format for worker (only base fee charged on failure)
-                        log!("[[yNEAR charged: \"{}\"]]", self.base_fee);
+                        log!("[[yNEAR charged: \"{}\"]]", request.pricing_snapshot.base_fee);
 
                         // Get error message for event
                         let error_msg = exec_response.error.unwrap_or("Unknown error".to_string());
 
-                        // Emit failure event with error details
-                        events::emit::execution_completed(
-                            &self.event_standard,
-                            &self.event_version,
-                            &sender_id,
-                            &code_source,
-                            &exec_response.resources_used,
-                            false,
-                            Some(&error_msg),
-                            U128(self.base_fee),  // payment_charged (only base fee)
-                            U128(refund),         // payment_refunded
-                            exec_response.compilation_note.as_deref(),
+                        self.record_finished_request(
+                            &request,
+                            exec_response.resources_used.compile_time_ms,
+                            RequestOutcome::Failed { error: error_msg.clone(), payment_refunded: refund },
                         );
 
+                        // Emit failure event with error details (only the base fee is charged)
+                        events::ContractEvent::ExecutionCompleted {
+                            sender_id: &sender_id,
+                            code_source: &code_source,
+                            resources_used: &exec_response.resources_used,
+                            success: false,
+                            error_message: Some(&error_msg),
+                            payment_charged: U128(request.pricing_snapshot.base_fee),
+                            payment_refunded: U128(refund),
+                            compilation_note: exec_response.compilation_note.as_deref(),
+                        }
+                        .emit(&self.event_standard, &self.event_version);
+                        events::ContractEvent::ExecutionFailed {
+                            request_id,
+                            sender_id: &sender_id,
+                            error: &error_msg,
+                            error_structured: exec_response.error_structured.as_ref(),
+                            payment_charged: U128(request.pricing_snapshot.base_fee),
+                            payment_refunded: U128(refund),
+                        }
+                        .emit(&self.event_standard, &self.event_version);
+
                         // Log the failure (don't panic - state changes must persist!)
                         log!(
                             "Execution failed: {}. Resources: {{ instructions: {}, time_ms: {} }}. Refunded {} yoctoNEAR",
@@ -543,11 +709,7 @@ impl Contract {
                     // Developer gets nothing on failure
 
                     // Refund NEAR (minus base fee)
-                    let refund = payment.0.saturating_sub(self.base_fee);
-                    if refund > 0 {
-                        near_sdk::Promise::new(request.payer_account_id.clone())
-                            .transfer(NearToken::from_yoctonear(refund));
-                    }
+                    let refund = self.refund_payment(&request, payment.0.saturating_sub(request.pricing_snapshot.base_fee), "promise_failed");
 
                     // Refund stablecoin to user's balance
                     if request.attached_usd > 0 {
@@ -560,10 +722,26 @@ impl Contract {
                         );
                     }
 
-                    self.total_fees_collected += self.base_fee;
+                    self.total_fees_collected += request.pricing_snapshot.base_fee;
 
                     // Log payment charged in easy-to-parse format for worker (only base fee charged on promise failure)
-                    log!("[[yNEAR charged: \"{}\"]]", self.base_fee);
+                    log!("[[yNEAR charged: \"{}\"]]", request.pricing_snapshot.base_fee);
+
+                    let error_msg = format!("Execution promise failed: {:?}", promise_error);
+                    self.record_finished_request(
+                        &request,
+                        None,
+                        RequestOutcome::Failed { error: error_msg.clone(), payment_refunded: refund },
+                    );
+                    events::ContractEvent::ExecutionFailed {
+                        request_id,
+                        sender_id: &sender_id,
+                        error: &error_msg,
+                        error_structured: None,
+                        payment_charged: U128(request.pricing_snapshot.base_fee),
+                        payment_refunded: U128(refund),
+                    }
+                    .emit(&self.event_standard, &self.event_version);
 
                     // Log the promise failure (don't panic - state changes must persist!)
                     log!(
@@ -599,20 +777,291 @@ impl Contract {
             "Only the sender can cancel this execution"
         );
 
-        // Check if the timeout period has passed
-        let is_stale = env::block_timestamp() > request.timestamp + EXECUTION_TIMEOUT;
-        assert!(is_stale, "Execution is not yet stale, please wait");
+        self.cancel_stale_request(request, "stale, cancelled by sender");
+    }
 
-        // Remove the request and refund the payer
-        if let Some(stale_request) = self.pending_requests.remove(&request_id) {
-            near_sdk::Promise::new(stale_request.payer_account_id.clone())
-                .transfer(NearToken::from_yoctonear(stale_request.payment));
+    /// Cancel a request no worker resolved in time and reclaim its deposit
+    ///
+    /// Callable by the sender or the payer once the request's stale timeout
+    /// (`stale_timeout_seconds`, default EXECUTION_TIMEOUT) has passed and no
+    /// worker has resolved it. The full payment goes to the refund recipient
+    /// (`refund_account_id`, otherwise the payer) and `execution_cancelled` is emitted.
+    pub fn cancel_execution(&mut self, request_id: u64) {
+        let request = self
+            .pending_requests
+            .get(&request_id)
+            .expect("Execution request not found");
 
+        let caller = env::predecessor_account_id();
+        let reason = if caller == request.sender_id {
+            "stale, cancelled by sender"
+        } else if caller == request.payer_account_id {
+            "stale, cancelled by payer"
+        } else {
+            env::panic_str("Only the sender or payer can cancel this execution")
+        };
+
+        self.cancel_stale_request(request, reason);
+    }
+
+    /// Callback for NEAR refund transfers issued on resolution
+    /// A failed transfer returns the funds to this contract; record it for reconciliation
+    #[private]
+    pub fn on_refund_transfer(
+        &mut self,
+        request_id: u64,
+        recipient: AccountId,
+        amount: U128,
+        #[callback_result] transfer_result: Result<(), PromiseError>,
+    ) {
+        if transfer_result.is_err() {
             log!(
-                "Cancelled stale execution {} and refunded payer {}",
+                "Refund of {} yoctoNEAR to {} for request {} failed, funds kept by contract",
+                amount.0,
+                recipient,
+                request_id
+            );
+            events::ContractEvent::RefundFailed {
                 request_id,
-                stale_request.payer_account_id
+                recipient: &recipient,
+                amount,
+            }
+            .emit(&self.event_standard, &self.event_version);
+        }
+    }
+}
+
+// ============================================================================
+// Cancellation
+// ============================================================================
+
+impl Contract {
+    /// Remove a stale, unresolved request and refund its whole payment
+    fn cancel_stale_request(&mut self, request: ExecutionRequest, reason: &str) {
+        // Check if the timeout period (per-request override or EXECUTION_TIMEOUT) has passed
+        let is_stale = env::block_timestamp() > request.timestamp + request.stale_timeout_ns();
+        assert!(is_stale, "Execution is not yet stale, please wait");
+
+        // A resumed yield still runs on_execution_response, which charges and refunds
+        assert!(
+            request.resolved_at.is_none(),
+            "Execution already resolved, its result is being processed"
+        );
+
+        let request_id = request.request_id;
+        if let Some(stale_request) = self.remove_pending_request(request_id) {
+            let recipient = stale_request.refund_recipient().clone();
+            near_sdk::Promise::new(recipient.clone()).transfer(NearToken::from_yoctonear(stale_request.payment));
+            self.record_finished_request(
+                &stale_request,
+                None,
+                RequestOutcome::Cancelled { reason: reason.to_string() },
             );
+
+            events::ContractEvent::ExecutionCancelled {
+                request_id,
+                sender_id: &stale_request.sender_id,
+                cancelled_by: &env::predecessor_account_id(),
+                refund_recipient: &recipient,
+                payment_refunded: U128(stale_request.payment),
+                reason,
+            }
+            .emit(&self.event_standard, &self.event_version);
+
+            log!("Cancelled stale execution {} and refunded payer {}", request_id, recipient);
+        }
+    }
+}
+
+// ============================================================================
+// Refunds
+// ============================================================================
+
+impl Contract {
+    /// Refund `amount` yoctoNEAR of a resolved request to its refund recipient.
+    /// Amounts below MIN_REFUND_AMOUNT are skipped (the transfer costs more than it returns).
+    /// The transfer is a detached receipt with a logging callback, so a failed refund
+    /// never reverts the resolution. Returns the amount actually refunded.
+    pub(crate) fn refund_payment(&self, request: &ExecutionRequest, amount: Balance, reason: &str) -> Balance {
+        if amount == 0 {
+            return 0;
+        }
+        if amount < MIN_REFUND_AMOUNT {
+            log!(
+                "Skipping refund of {} yoctoNEAR for request {} (below minimum {})",
+                amount,
+                request.request_id,
+                MIN_REFUND_AMOUNT
+            );
+            return 0;
+        }
+
+        let recipient = request.refund_recipient().clone();
+        near_sdk::Promise::new(recipient.clone())
+            .transfer(NearToken::from_yoctonear(amount))
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(REFUND_CALLBACK_GAS)
+                    .on_refund_transfer(request.request_id, recipient.clone(), U128(amount)),
+            );
+
+        events::ContractEvent::RefundIssued {
+            request_id: request.request_id,
+            recipient: &recipient,
+            amount: U128(amount),
+            reason,
+        }
+        .emit(&self.event_standard, &self.event_version);
+        amount
+    }
+}
+
+// ============================================================================
+// Request Lifecycle
+// ============================================================================
+
+impl Contract {
+    /// Keep the lifecycle of a request that left `pending_requests` for `get_request_timeline`.
+    /// Only the last REQUEST_TIMELINE_RETENTION request ids keep a record; finishing
+    /// request N drops the record of request N - REQUEST_TIMELINE_RETENTION.
+    pub(crate) fn record_finished_request(
+        &mut self,
+        request: &ExecutionRequest,
+        compile_time_ms: Option<u64>,
+        mut outcome: RequestOutcome,
+    ) {
+        if request.request_id + REQUEST_TIMELINE_RETENTION < self.next_request_id {
+            return;
+        }
+        if let Some(expired) = request.request_id.checked_sub(REQUEST_TIMELINE_RETENTION) {
+            self.finished_requests.remove(&expired);
+        }
+
+        if let RequestOutcome::Failed { error, .. } = &mut outcome {
+            if error.chars().count() > MAX_TIMELINE_ERROR_CHARS {
+                *error = error.chars().take(MAX_TIMELINE_ERROR_CHARS).collect();
+            }
+        }
+
+        self.finished_requests.insert(
+            &request.request_id,
+            &RequestLifecycle {
+                sender_id: request.sender_id.clone(),
+                payment: request.payment,
+                submitted_at: request.timestamp,
+                output_submitted_at: request.output_submitted_at,
+                resolved_at: request.resolved_at,
+                compile_time_ms,
+                finished_at: env::block_timestamp(),
+                outcome,
+            },
+        );
+    }
+}
+
+// ============================================================================
+// Idempotency Keys
+// ============================================================================
+
+impl Contract {
+    /// Request an idempotency key still answers for: pending, or finished less than
+    /// IDEMPOTENCY_WINDOW ago. A key whose request is older is free for a new request.
+    pub(crate) fn live_idempotent_request(&self, entry: &(AccountId, String)) -> Option<u64> {
+        let request_id = self.idempotency_keys.get(entry)?;
+        if self.pending_requests.contains_key(&request_id) {
+            return Some(request_id);
+        }
+        let lifecycle = self.finished_requests.get(&request_id)?;
+        (env::block_timestamp() < lifecycle.finished_at + IDEMPOTENCY_WINDOW).then_some(request_id)
+    }
+
+    /// Answer a duplicate submission: refund the whole deposit and return the existing id
+    fn return_existing_request(&self, request_id: u64, entry: &(AccountId, String)) {
+        let deposit = env::attached_deposit();
+        if deposit.as_yoctonear() > 0 {
+            near_sdk::Promise::new(entry.0.clone()).transfer(deposit);
+        }
+        log!(
+            "Duplicate submission with idempotency key {:?} from {}: returning request {}, refunded {} yoctoNEAR",
+            entry.1,
+            entry.0,
+            request_id,
+            deposit.as_yoctonear()
+        );
+        env::value_return(request_id.to_string().as_bytes());
+    }
+
+    /// Remember the idempotency key of a new request, and forget the key of the request
+    /// REQUEST_TIMELINE_RETENTION ids back: its lifecycle record is gone, so it could no
+    /// longer be checked against IDEMPOTENCY_WINDOW anyway.
+    pub(crate) fn record_idempotency_key(&mut self, request_id: u64, entry: Option<(AccountId, String)>) {
+        if let Some(expired) = request_id.checked_sub(REQUEST_TIMELINE_RETENTION) {
+            if let Some(expired_entry) = self.idempotency_key_requests.remove(&expired) {
+                // The key may have been reused by a newer request since
+                if self.idempotency_keys.get(&expired_entry) == Some(expired) {
+                    self.idempotency_keys.remove(&expired_entry);
+                }
+            }
+        }
+
+        if let Some(entry) = entry {
+            self.idempotency_keys.insert(&entry, &request_id);
+            self.idempotency_key_requests.insert(&request_id, &entry);
+        }
+    }
+}
+
+// ============================================================================
+// Pending Request Bookkeeping
+// ============================================================================
+
+impl Contract {
+    /// Store a new pending request and bump the counters.
+    /// Emits `pending_capacity_reached` when this request fills the last slot.
+    pub(crate) fn insert_pending_request(&mut self, request_id: u64, request: &ExecutionRequest) {
+        self.pending_requests.insert(&request_id, request);
+        self.count_pending_request(request);
+
+        if self.pending_requests_count >= self.max_pending_requests {
+            events::ContractEvent::PendingCapacityReached {
+                pending_requests: self.pending_requests_count,
+                max_pending_requests: self.max_pending_requests,
+            }
+            .emit(&self.event_standard, &self.event_version);
+        }
+    }
+
+    /// Remove a pending request and release its slot
+    pub(crate) fn remove_pending_request(&mut self, request_id: u64) -> Option<ExecutionRequest> {
+        let request = self.pending_requests.remove(&request_id);
+        if let Some(request) = &request {
+            if request_id >= self.pending_counted_from {
+                self.uncount_pending_request(request);
+            }
+        }
+        request
+    }
+
+    /// Count a request that is already in `pending_requests` (migration seeding)
+    pub(crate) fn count_pending_request(&mut self, request: &ExecutionRequest) {
+        self.pending_requests_count += 1;
+        let depth = self.pending_by_priority.get(&request.priority).unwrap_or(0);
+        self.pending_by_priority.insert(&request.priority, &(depth + 1));
+        let sender_pending = self.pending_by_account.get(&request.sender_id).unwrap_or(0);
+        self.pending_by_account.insert(&request.sender_id, &(sender_pending + 1));
+    }
+
+    fn uncount_pending_request(&mut self, request: &ExecutionRequest) {
+        self.pending_requests_count = self.pending_requests_count.saturating_sub(1);
+        let depth = self.pending_by_priority.get(&request.priority).unwrap_or(0);
+        self.pending_by_priority.insert(&request.priority, &depth.saturating_sub(1));
+        match self.pending_by_account.get(&request.sender_id).unwrap_or(0) {
+            0 | 1 => {
+                self.pending_by_account.remove(&request.sender_id);
+            }
+            sender_pending => {
+                self.pending_by_account.insert(&request.sender_id, &(sender_pending - 1));
+            }
         }
     }
 }
@@ -630,12 +1079,14 @@ impl Contract {
         source: &ExecutionSource,
     ) -> (CodeSource, Option<String>) {
         match source {
-            ExecutionSource::GitHub { repo, commit, build_target } => {
+            ExecutionSource::GitHub { repo, commit, build_target, package, features } => {
                 (
                     CodeSource::GitHub {
                         repo: repo.clone(),
                         commit: commit.clone(),
                         build_target: build_target.clone(),
+                        package: package.clone(),
+                        features: features.clone(),
                     },
                     None,
                 )
@@ -681,6 +1132,20 @@ impl Contract {
         }
     }
 
+    /// Resource caps for a request: the override set for `project_id`, else the one
+    /// for the GitHub repo of `code_source`, else the global hard caps
+    pub(crate) fn resource_caps(&self, project_id: Option<&str>, code_source: &CodeSource) -> ProjectLimits {
+        let repo = match code_source {
+            CodeSource::GitHub { repo, .. } => Some(repo.as_str()),
+            CodeSource::WasmUrl { .. } => None,
+        };
+        project_id
+            .into_iter()
+            .chain(repo)
+            .find_map(|key| self.project_limits.get(&key.to_string()))
+            .unwrap_or_default()
+    }
+
     /// Internal helper to submit execution output (used by both public methods)
     pub(crate) fn submit_execution_output_internal(&mut self, request_id: u64, output: ExecutionOutput) {
         // Get the pending request
@@ -699,6 +1164,7 @@ impl Contract {
         let stored_output: crate::StoredOutput = output.into();
         request.pending_output = Some(stored_output);
         request.output_submitted = true;
+        request.output_submitted_at = Some(env::block_timestamp());
 
         // Save updated request
         self.pending_requests.insert(&request_id, &request);
@@ -710,10 +1176,67 @@ impl Contract {
         );
     }
 
+    /// Internal helper for `resolve_batch` (no operator check)
+    ///
+    /// `resume` performs the `promise_yield_resume` for a valid entry.
+    pub(crate) fn resolve_batch_internal(
+        &mut self,
+        results: Vec<(u64, ExecutionResponse)>,
+        mut resume: impl FnMut(&CryptoHash, &[u8]) -> bool,
+    ) -> Vec<ResolveBatchEntry> {
+        assert!(!results.is_empty(), "Empty batch");
+        assert!(
+            results.len() <= MAX_RESOLVE_BATCH_SIZE,
+            "Batch too large: {} results (max {})",
+            results.len(),
+            MAX_RESOLVE_BATCH_SIZE
+        );
+
+        let mut seen = std::collections::HashSet::new();
+        let mut statuses = Vec::with_capacity(results.len());
+
+        for (request_id, mut response) in results {
+            let status = if !seen.insert(request_id) {
+                ResolveStatus::Duplicate
+            } else {
+                match self.pending_requests.get(&request_id) {
+                    None => ResolveStatus::NotFound,
+                    Some(mut request) => {
+                        let result_signature = response.result_signature.take();
+                        let payload = serde_json::to_vec(&response).unwrap();
+                        if payload.len() > MAX_YIELD_PAYLOAD_BYTES {
+                            ResolveStatus::PayloadTooLarge
+                        } else if resume(&request.data_id, &payload) {
+                            request.resolved_at = Some(env::block_timestamp());
+                            self.pending_requests.insert(&request_id, &request);
+                            self.publish_result_signature(request_id, result_signature);
+                            log!(
+                                "Resolved request_id: {} in batch, success: {}, estimated cost: {}",
+                                request_id,
+                                response.success,
+                                request.pricing_snapshot.calculate_cost(&response.resources_used, request.priority)
+                            );
+                            ResolveStatus::Resumed
+                        } else {
+                            ResolveStatus::ResumeFailed
+                        }
+                    }
+                }
+            };
+
+            if status != ResolveStatus::Resumed {
+                log!("Skipped request_id: {} in batch: {:?}", request_id, status);
+            }
+            statuses.push(ResolveBatchEntry { request_id, status });
+        }
+
+        statuses
+    }
+
     /// Internal helper to resolve execution (no operator check)
-    fn resolve_execution_internal(&mut self, request_id: u64, response: ExecutionResponse) {
+    fn resolve_execution_internal(&mut self, request_id: u64, mut response: ExecutionResponse) {
         // Get the pending request
-        let request = self
+        let mut request = self
             .pending_requests
             .get(&request_id)
             .expect("Execution request not found");
@@ -721,7 +1244,7 @@ impl Contract {
         let data_id = request.data_id;
 
         // Calculate estimated cost for logging
-        let estimated_cost = self.calculate_cost(&response.resources_used);
+        let estimated_cost = request.pricing_snapshot.calculate_cost(&response.resources_used, request.priority);
 
         log!(
             "Resolving execution for request_id: {}, data_id: {:?}, success: {}, output_submitted: {}, resources_used: {{ instructions: {}, time_ms: {}, compile_time_ms: {:?} }}",
@@ -737,11 +1260,44 @@ impl Contract {
         // Log cost in easy-to-parse format for worker
         log!("[[yNEAR charged: \"{}\"]]", estimated_cost);
 
+        // The signature goes out as an event; keeping it out of the resume payload
+        // leaves the 1024 byte budget for the response itself
+        let result_signature = response.result_signature.take();
+
         // For large outputs, we only pass metadata through resume (output stays in storage)
         // The callback will retrieve it from pending_output field
         // This avoids the 1024 byte limit of promise_yield_resume
         if !env::promise_yield_resume(&data_id, &serde_json::to_vec(&response).unwrap()) {
             env::panic_str("Unable to resume execution promise");
         }
+
+        request.resolved_at = Some(env::block_timestamp());
+        self.pending_requests.insert(&request_id, &request);
+
+        self.publish_result_signature(request_id, result_signature);
+    }
+
+    /// Emit the worker's result signature so verifiers can pick it up from the receipt logs
+    fn publish_result_signature(&self, request_id: u64, result_signature: Option<ResultSignature>) {
+        if let Some(result_signature) = result_signature {
+            events::ContractEvent::ExecutionResultSigned {
+                request_id,
+                result_signature: &result_signature,
+            }
+            .emit(&self.event_standard, &self.event_version);
+        }
     }
 }
+
+/// `type/subtype` media type, optionally followed by `;` parameters, within MAX_INPUT_CONTENT_TYPE_LEN
+pub(crate) fn is_valid_content_type(content_type: &str) -> bool {
+    let is_token = |s: &str| {
+        !s.is_empty() && s.bytes().all(|b| b.is_ascii_alphanumeric() || b"!#$&-^_.+".contains(&b))
+    };
+    let media_type = content_type.split(';').next().unwrap_or_default().trim();
+    content_type.len() <= MAX_INPUT_CONTENT_TYPE_LEN
+        && content_type.bytes().all(|b| (b' '..=b'~').contains(&b))
+        && media_type
+            .split_once('/')
+            .is_some_and(|(kind, subtype)| is_token(kind) && is_token(subtype))
+}
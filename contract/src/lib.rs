@@ -30,16 +30,73 @@ pub const DATA_ID_REGISTER: u64 = 37;
 // Timeout for stale execution cancellation (10 minutes)
 pub const EXECUTION_TIMEOUT: u64 = 600 * 1_000_000_000;
 
+// Lower bound for a per-request stale_timeout_seconds override (1 minute)
+pub const MIN_STALE_TIMEOUT_SECONDS: u64 = 60;
+
 // Maximum resource limits (hard caps)
 pub const MAX_INSTRUCTIONS: u64 = 500_000_000_000; // 500 billion instructions
 pub const MAX_EXECUTION_SECONDS: u64 = 180; // 180 seconds
 pub const MAX_COMPILATION_SECONDS: u64 = 300; // 5 minutes max compilation time
+pub const MAX_OUTPUT_BYTES: u32 = 1024 * 1024; // 1 MB max stdout per execution
+
+// Default output cap when max_output_bytes is not set
+pub const DEFAULT_MAX_OUTPUT_BYTES: u32 = 128 * 1024; // 128 KB
+
+// Default cap on request input_data (owner-configurable up to MAX_INPUT_BYTES_LIMIT)
+// Larger inputs should be passed as a storage key or URL the WASM fetches itself
+pub const DEFAULT_MAX_INPUT_BYTES: u32 = 16 * 1024; // 16 KB
+pub const MAX_INPUT_BYTES_LIMIT: u32 = 1024 * 1024; // 1 MB
 
 // Large payload handling: threshold for including input_data in event log
 // Payloads >= this size are stored in state only, worker fetches via get_request()
 // NEAR has 16KB limit per log message, so we use 10KB to leave room for other fields
 pub const INPUT_DATA_EVENT_THRESHOLD: usize = 10_000; // 10KB
 
+// Default global cap on unresolved execution requests (backpressure)
+pub const DEFAULT_MAX_PENDING_REQUESTS: u64 = 10_000;
+
+// Default cap on unresolved execution requests of one sender (fair share of the global cap)
+pub const DEFAULT_MAX_PENDING_PER_ACCOUNT: u32 = 100;
+
+// promise_yield_resume payload limit (larger outputs go through submit_execution_output)
+pub const MAX_YIELD_PAYLOAD_BYTES: usize = 1024;
+
+// Maximum number of results accepted by resolve_batch in one transaction
+pub const MAX_RESOLVE_BATCH_SIZE: usize = 50;
+
+// NEAR refunds below this are kept instead of transferred (0.00001 NEAR = storage cost of 1 byte)
+pub const MIN_REFUND_AMOUNT: Balance = 10_000_000_000_000_000_000;
+
+// Gas for the callback that records a failed refund transfer
+pub const REFUND_CALLBACK_GAS: Gas = Gas::from_tgas(5);
+
+// Lifecycle records kept for get_request_timeline after a request finishes
+// (the record of request N is dropped when request N + this finishes)
+pub const REQUEST_TIMELINE_RETENTION: u64 = 5_000;
+
+// Highest RequestParams::priority; each level adds priority_fee to the request cost
+pub const MAX_PRIORITY: u8 = 10;
+
+// Default NEAR price of one priority level. Zero until the coordinator orders
+// polls by tier: until then priority is recorded and counted, but buys nothing.
+pub const DEFAULT_PRIORITY_FEE: Balance = 0;
+
+// A finished request still answers resubmissions with its idempotency key for this long (24 hours)
+pub const IDEMPOTENCY_WINDOW: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+// Maximum length of RequestParams::idempotency_key in bytes
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 64;
+
+// Maximum length of RequestParams::input_content_type in bytes
+pub const MAX_INPUT_CONTENT_TYPE_LEN: usize = 128;
+
+// Failure messages stored in lifecycle records are truncated to this many chars
+pub const MAX_TIMELINE_ERROR_CHARS: usize = 200;
+
+// Secret versions retained per profile by set_secrets (older ones are pruned)
+pub const DEFAULT_MAX_SECRET_VERSIONS: u32 = 3;
+pub const MAX_SECRET_VERSIONS_LIMIT: u32 = 20;
+
 #[derive(BorshSerialize, BorshStorageKey)]
 #[borsh(crate = "near_sdk::borsh")]
 enum StorageKey {
@@ -63,6 +120,22 @@ enum StorageKey {
     WalletOwnerList { account_id: AccountId },
     // Per-secret vault binding (side-table for sovereign-vault opt-in)
     SecretVaultBindings,
+    // Secret versioning (set_secrets / rollback_secret)
+    SecretVersions,
+    SecretVersionHistory,
+    // Lifecycle records of finished requests (get_request_timeline)
+    FinishedRequests,
+    // Idempotency keys of execution requests
+    IdempotencyKeys,
+    IdempotencyKeyRequests,
+    // Per-project resource cap overrides (set_project_limits)
+    ProjectLimits,
+    // NEAR rates requests stored before pricing snapshots are billed at
+    LegacyPricing,
+    // Pending request count per priority tier
+    PendingByPriority,
+    // Pending request count per sender
+    PendingByAccount,
 }
 
 /// Execution source - GitHub repo, pre-compiled WASM URL, or project reference
@@ -74,6 +147,12 @@ pub enum ExecutionSource {
         repo: String,
         commit: String,
         build_target: Option<String>, // e.g., "wasm32-wasip1"
+        /// Workspace member to build (`cargo build -p`)
+        #[serde(default)]
+        package: Option<String>,
+        /// Cargo features to enable (`cargo build --features`)
+        #[serde(default)]
+        features: Vec<String>,
     },
     /// Pre-compiled WASM file accessible via URL
     /// Worker downloads from URL, verifies SHA256 hash, then executes without compilation
@@ -93,12 +172,16 @@ pub enum ExecutionSource {
 /// Resolved code source for worker (GitHub or WasmUrl only, no Project)
 /// This is what gets sent to worker after resolving Project references
 #[derive(Clone, Debug)]
-#[near(serializers = [borsh, json])]
+#[near(serializers = [json])]
 pub enum CodeSource {
     GitHub {
         repo: String,
         commit: String,
         build_target: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        package: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        features: Vec<String>,
     },
     WasmUrl {
         url: String,
@@ -107,6 +190,56 @@ pub enum CodeSource {
     },
 }
 
+// Borsh layout is written by hand so project versions stored before `package`/`features`
+// existed still load: a GitHub source without them keeps the original variant 0 layout,
+// one with them is stored as variant 2.
+const CODE_SOURCE_GITHUB: u8 = 0;
+const CODE_SOURCE_WASM_URL: u8 = 1;
+const CODE_SOURCE_GITHUB_CARGO: u8 = 2;
+
+impl BorshSerialize for CodeSource {
+    fn serialize<W: near_sdk::borsh::io::Write>(&self, writer: &mut W) -> near_sdk::borsh::io::Result<()> {
+        match self {
+            CodeSource::GitHub { repo, commit, build_target, package, features } => {
+                if package.is_none() && features.is_empty() {
+                    CODE_SOURCE_GITHUB.serialize(writer)?;
+                    (repo, commit, build_target).serialize(writer)
+                } else {
+                    CODE_SOURCE_GITHUB_CARGO.serialize(writer)?;
+                    (repo, commit, build_target, package, features).serialize(writer)
+                }
+            }
+            CodeSource::WasmUrl { url, hash, build_target } => {
+                CODE_SOURCE_WASM_URL.serialize(writer)?;
+                (url, hash, build_target).serialize(writer)
+            }
+        }
+    }
+}
+
+impl BorshDeserialize for CodeSource {
+    fn deserialize_reader<R: near_sdk::borsh::io::Read>(reader: &mut R) -> near_sdk::borsh::io::Result<Self> {
+        match u8::deserialize_reader(reader)? {
+            CODE_SOURCE_GITHUB => {
+                let (repo, commit, build_target) = BorshDeserialize::deserialize_reader(reader)?;
+                Ok(CodeSource::GitHub { repo, commit, build_target, package: None, features: Vec::new() })
+            }
+            CODE_SOURCE_GITHUB_CARGO => {
+                let (repo, commit, build_target, package, features) = BorshDeserialize::deserialize_reader(reader)?;
+                Ok(CodeSource::GitHub { repo, commit, build_target, package, features })
+            }
+            CODE_SOURCE_WASM_URL => {
+                let (url, hash, build_target) = BorshDeserialize::deserialize_reader(reader)?;
+                Ok(CodeSource::WasmUrl { url, hash, build_target })
+            }
+            tag => Err(near_sdk::borsh::io::Error::new(
+                near_sdk::borsh::io::ErrorKind::InvalidData,
+                format!("Unknown CodeSource variant {}", tag),
+            )),
+        }
+    }
+}
+
 /// Optional request parameters for additional options
 #[derive(Clone, Debug, Default)]
 #[near(serializers = [borsh, json])]
@@ -135,6 +268,35 @@ pub struct RequestParams {
     /// Deducted from user's stablecoin balance in contract
     #[serde(default)]
     pub attached_usd: Option<U128>,
+
+    /// Account to receive NEAR refunds (cancellation, overpayment, failure)
+    /// instead of `payer_account_id`. Defaults to `payer_account_id` when absent.
+    #[serde(default)]
+    pub refund_account_id: Option<AccountId>,
+
+    /// Seconds after which the sender may cancel this request with `cancel_stale_execution`
+    /// Must be between MIN_STALE_TIMEOUT_SECONDS and EXECUTION_TIMEOUT; defaults to EXECUTION_TIMEOUT
+    #[serde(default)]
+    pub stale_timeout_seconds: Option<u64>,
+
+    /// Client-chosen key (1 to MAX_IDEMPOTENCY_KEY_LEN bytes) that makes resubmission safe:
+    /// while an earlier request from the same sender with this key is pending, or finished
+    /// less than IDEMPOTENCY_WINDOW ago, `request_execution` returns its request_id and
+    /// refunds the deposit instead of creating and charging a new request
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
+
+    /// Queue tier, 0 (default) to MAX_PRIORITY, passed to the coordinator with the request.
+    /// Each level costs priority_fee. Never affects resource limits.
+    #[serde(default)]
+    pub priority: Option<u8>,
+
+    /// Media type of `input_data` (e.g. "application/json", "text/plain"), `type/subtype`
+    /// with optional parameters, at most MAX_INPUT_CONTENT_TYPE_LEN bytes. Not interpreted
+    /// by OutLayer: the ark reads it as OUTLAYER_INPUT_CONTENT_TYPE to decide how to parse
+    /// its input.
+    #[serde(default)]
+    pub input_content_type: Option<String>,
 }
 
 /// Response format for execution output
@@ -147,6 +309,13 @@ pub enum ResponseFormat {
     Text,
     /// Parse stdout as JSON
     Json,
+    /// Stdout validated as a single CBOR item by the worker, returned as raw bytes
+    Cbor,
+    /// Stdout validated as a single MessagePack object by the worker, returned as raw bytes
+    MessagePack,
+    /// Raw stdout base64-encoded by the worker, returned as a string tagged `Base64`
+    /// (for binary output consumed through JSON-only clients)
+    Base64,
 }
 
 impl Default for ResponseFormat {
@@ -162,6 +331,9 @@ pub struct ResourceLimits {
     pub max_instructions: Option<u64>,
     pub max_memory_mb: Option<u32>,
     pub max_execution_seconds: Option<u64>,
+    /// Maximum stdout size; the execution fails if the WASM writes more
+    /// (None = DEFAULT_MAX_OUTPUT_BYTES)
+    pub max_output_bytes: Option<u32>,
 }
 
 impl Default for ResourceLimits {
@@ -170,13 +342,15 @@ impl Default for ResourceLimits {
             max_instructions: Some(1_000_000_000), // 1B instructions
             max_memory_mb: Some(128),              // 128 MB
             max_execution_seconds: Some(60),       // 60 seconds
+            max_output_bytes: Some(DEFAULT_MAX_OUTPUT_BYTES), // 128 KB
         }
     }
 }
 
 /// Execution request stored in contract
-#[derive(Clone, Debug)]
-#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, BorshSerialize)]
+#[borsh(crate = "near_sdk::borsh")]
+#[near(serializers = [json])]
 pub struct ExecutionRequest {
     pub request_id: u64,
     pub data_id: CryptoHash,
@@ -189,21 +363,196 @@ pub struct ExecutionRequest {
     pub secrets_ref: Option<SecretsReference>, // Reference to repo-based secrets
     pub response_format: ResponseFormat,
     pub input_data: Option<String>, // Optional input data for execution
+    pub input_content_type: Option<String>, // Media type of input_data declared by the caller
     pub payer_account_id: AccountId, // Account to receive refunds (explicit or defaults to sender)
     pub attached_usd: u128, // Payment to project developer (stablecoin minimal units)
 
     // Large output handling (2-call flow)
     pub pending_output: Option<StoredOutput>, // Temporary storage for large output data
     pub output_submitted: bool, // Flag indicating output data has been submitted
+
+    pub refund_account_id: Option<AccountId>, // Overrides payer_account_id as NEAR refund recipient
+    pub stale_timeout_seconds: Option<u64>, // Overrides EXECUTION_TIMEOUT for cancel_stale_execution
+
+    // Lifecycle markers (get_request_timeline); submission time is `timestamp`
+    pub output_submitted_at: Option<u64>, // When the worker stored a large output
+    pub resolved_at: Option<u64>, // When the worker resolved the request (yield resumed)
+
+    pub priority: u8, // Queue tier (RequestParams::priority), charged priority_fee per level
+
+    pub pricing_snapshot: PricingSnapshot, // NEAR rates at submission; the request is billed at these
 }
 
-/// Execution output - can be bytes, text, or parsed JSON
+// Borsh decoding is written by hand so requests stored before the layout changed
+// (`migration::ExecutionRequestV7`) still load after an upgrade instead of failing
+// to deserialize when their worker reports back.
+impl BorshDeserialize for ExecutionRequest {
+    fn deserialize_reader<R: near_sdk::borsh::io::Read>(reader: &mut R) -> near_sdk::borsh::io::Result<Self> {
+        // Only ever stored as a whole `pending_requests` value, so the rest of the reader is this request
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Self::deserialize_current(&mut bytes.as_slice()).or_else(|_| {
+            migration::ExecutionRequestV7::try_from_slice(&bytes).map(ExecutionRequest::from)
+        })
+    }
+}
+
+impl ExecutionRequest {
+    /// Decode the current layout; fails unless it consumes every byte
+    fn deserialize_current(buf: &mut &[u8]) -> near_sdk::borsh::io::Result<Self> {
+        // Fields are read in declaration order (struct expressions evaluate in order)
+        let request = Self {
+            request_id: BorshDeserialize::deserialize_reader(buf)?,
+            data_id: BorshDeserialize::deserialize_reader(buf)?,
+            sender_id: BorshDeserialize::deserialize_reader(buf)?,
+            execution_source: BorshDeserialize::deserialize_reader(buf)?,
+            resolved_source: BorshDeserialize::deserialize_reader(buf)?,
+            resource_limits: BorshDeserialize::deserialize_reader(buf)?,
+            payment: BorshDeserialize::deserialize_reader(buf)?,
+            timestamp: BorshDeserialize::deserialize_reader(buf)?,
+            secrets_ref: BorshDeserialize::deserialize_reader(buf)?,
+            response_format: BorshDeserialize::deserialize_reader(buf)?,
+            input_data: BorshDeserialize::deserialize_reader(buf)?,
+            input_content_type: BorshDeserialize::deserialize_reader(buf)?,
+            payer_account_id: BorshDeserialize::deserialize_reader(buf)?,
+            attached_usd: BorshDeserialize::deserialize_reader(buf)?,
+            pending_output: BorshDeserialize::deserialize_reader(buf)?,
+            output_submitted: BorshDeserialize::deserialize_reader(buf)?,
+            refund_account_id: BorshDeserialize::deserialize_reader(buf)?,
+            stale_timeout_seconds: BorshDeserialize::deserialize_reader(buf)?,
+            output_submitted_at: BorshDeserialize::deserialize_reader(buf)?,
+            resolved_at: BorshDeserialize::deserialize_reader(buf)?,
+            priority: BorshDeserialize::deserialize_reader(buf)?,
+            pricing_snapshot: BorshDeserialize::deserialize_reader(buf)?,
+        };
+        if !buf.is_empty() {
+            return Err(near_sdk::borsh::io::Error::new(
+                near_sdk::borsh::io::ErrorKind::InvalidData,
+                "Unexpected trailing bytes after ExecutionRequest",
+            ));
+        }
+        Ok(request)
+    }
+
+    /// Account that receives NEAR refunds: `refund_account_id` if set, otherwise `payer_account_id`
+    pub fn refund_recipient(&self) -> &AccountId {
+        self.refund_account_id.as_ref().unwrap_or(&self.payer_account_id)
+    }
+
+    /// Nanoseconds after `timestamp` when the request becomes cancellable as stale
+    pub fn stale_timeout_ns(&self) -> u64 {
+        self.stale_timeout_seconds
+            .map(|seconds| seconds * 1_000_000_000)
+            .unwrap_or(EXECUTION_TIMEOUT)
+    }
+}
+
+/// NEAR rates in effect when a request was submitted
+///
+/// Stored on each `ExecutionRequest` so a `set_pricing` / `set_priority_fee` call
+/// between submission and resolution doesn't change what the request is billed.
+#[derive(Clone, Debug, PartialEq)]
+#[near(serializers = [borsh, json])]
+pub struct PricingSnapshot {
+    pub base_fee: Balance,
+    pub per_million_instructions_fee: Balance,
+    pub per_ms_fee: Balance,
+    pub per_compile_ms_fee: Balance,
+    pub priority_fee: Balance,
+}
+
+impl PricingSnapshot {
+    /// Actual cost of an execution that used `metrics`
+    pub fn calculate_cost(&self, metrics: &ResourceMetrics, priority: u8) -> Balance {
+        let instruction_cost =
+            (metrics.instructions / 1_000_000) as u128 * self.per_million_instructions_fee;
+        let time_cost = metrics.time_ms as u128 * self.per_ms_fee;
+
+        // Add compilation cost if compilation occurred (uses separate, higher rate)
+        let compile_cost = metrics.compile_time_ms
+            .map(|ms| ms as u128 * self.per_compile_ms_fee)
+            .unwrap_or(0);
+
+        self.base_fee + instruction_cost + time_cost + compile_cost + self.priority_cost(priority)
+    }
+
+    /// Worst-case cost of an execution within `limits`
+    pub fn estimate_cost(&self, limits: &ResourceLimits, priority: u8) -> Balance {
+        // Use requested limits or defaults
+        let max_instructions = limits.max_instructions.unwrap_or(1_000_000_000);
+        let max_execution_seconds = limits.max_execution_seconds.unwrap_or(60);
+        let max_time_ms = max_execution_seconds * 1000;
+
+        // Calculate worst-case cost
+        let instruction_cost = (max_instructions / 1_000_000) as u128 * self.per_million_instructions_fee;
+        let time_cost = max_time_ms as u128 * self.per_ms_fee;
+
+        self.base_fee + instruction_cost + time_cost + self.priority_cost(priority)
+    }
+
+    /// Scheduling surcharge for a request in queue tier `priority`
+    pub fn priority_cost(&self, priority: u8) -> Balance {
+        self.priority_fee * priority as u128
+    }
+}
+
+/// Lifecycle record of a finished request, kept after it leaves `pending_requests`
+#[derive(Clone, Debug)]
+#[near(serializers = [borsh])]
+pub struct RequestLifecycle {
+    pub sender_id: AccountId,
+    pub payment: Balance,
+    pub submitted_at: u64,
+    pub output_submitted_at: Option<u64>,
+    pub resolved_at: Option<u64>,
+    pub compile_time_ms: Option<u64>, // Reported by the worker on resolution
+    pub finished_at: u64,
+    pub outcome: RequestOutcome,
+}
+
+/// How a request left `pending_requests`
+#[derive(Clone, Debug)]
+#[near(serializers = [borsh])]
+pub enum RequestOutcome {
+    Completed { payment_charged: Balance, payment_refunded: Balance },
+    Failed { error: String, payment_refunded: Balance },
+    Cancelled { reason: String },
+}
+
+/// Step of a request's lifecycle
+#[derive(Clone, Debug, PartialEq)]
+#[near(serializers = [json])]
+pub enum TimelineEventKind {
+    Submitted,
+    OutputSubmitted,
+    Compiled,
+    Resolved,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// One entry of `get_request_timeline`
+#[derive(Clone, Debug)]
+#[near(serializers = [json])]
+pub struct TimelineEvent {
+    pub event: TimelineEventKind,
+    pub timestamp: u64,
+    pub details: Option<String>,
+}
+
+/// Execution output - can be bytes, text, parsed JSON, CBOR/MessagePack-encoded bytes,
+/// or base64 of raw bytes
 #[derive(Clone, Debug)]
 #[near(serializers = [json])]
 pub enum ExecutionOutput {
     Bytes(Vec<u8>),
     Text(String),
     Json(serde_json::Value),
+    Cbor(Vec<u8>),
+    MessagePack(Vec<u8>),
+    /// Standard (padded) base64 of the raw output; the variant tells clients to decode it
+    Base64(String),
 }
 
 /// Internal storage format for ExecutionOutput (Borsh-compatible)
@@ -214,6 +563,9 @@ pub enum StoredOutput {
     Bytes(Vec<u8>),
     Text(Vec<u8>),      // UTF-8 bytes
     Json(Vec<u8>),      // JSON string as UTF-8 bytes
+    Cbor(Vec<u8>),      // Encoded CBOR item
+    MessagePack(Vec<u8>), // Encoded MessagePack object
+    Base64(Vec<u8>),    // Base64 string as ASCII bytes
 }
 
 impl From<ExecutionOutput> for StoredOutput {
@@ -225,6 +577,9 @@ impl From<ExecutionOutput> for StoredOutput {
                 let json_str = serde_json::to_string(&value).unwrap_or_default();
                 StoredOutput::Json(json_str.into_bytes())
             }
+            ExecutionOutput::Cbor(bytes) => StoredOutput::Cbor(bytes),
+            ExecutionOutput::MessagePack(bytes) => StoredOutput::MessagePack(bytes),
+            ExecutionOutput::Base64(text) => StoredOutput::Base64(text.into_bytes()),
         }
     }
 }
@@ -242,6 +597,11 @@ impl From<StoredOutput> for ExecutionOutput {
                     serde_json::from_str(&json_str).unwrap_or(serde_json::Value::Null)
                 )
             }
+            StoredOutput::Cbor(bytes) => ExecutionOutput::Cbor(bytes),
+            StoredOutput::MessagePack(bytes) => ExecutionOutput::MessagePack(bytes),
+            StoredOutput::Base64(bytes) => ExecutionOutput::Base64(
+                String::from_utf8(bytes).unwrap_or_default()
+            ),
         }
     }
 }
@@ -253,12 +613,66 @@ pub struct ExecutionResponse {
     pub success: bool,
     pub output: Option<ExecutionOutput>,
     pub error: Option<String>,
+    /// Machine-readable error `{"code", "message", "details"}` reported by the WASM
+    /// via `env::fail`, alongside the `error` string. Emitted in `execution_failed`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_structured: Option<serde_json::Value>,
     pub resources_used: ResourceMetrics,
     pub compilation_note: Option<String>, // e.g., "Cached WASM from 2025-01-10 14:30 UTC"
     /// Refund amount to return to user from attached_usd (stablecoin minimal units)
     /// Set by WASM via refund_usd() host function
     #[serde(default)]
     pub refund_usd: Option<u64>,
+    /// Worker signature over (request_id, input_hash, output_hash, worker_pubkey)
+    /// Published in an `execution_result_signed` event; never passed through yield resume
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_signature: Option<ResultSignature>,
+    /// Format the WASM declared for its output via `env::set_output_format`, if any.
+    /// A `Json` request whose WASM declared another format fails with an error saying so.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub declared_output_format: Option<ResponseFormat>,
+}
+
+/// Worker signature over an execution result, for external verifiers
+///
+/// The signed message is `"outlayer:execution-result:v1" || request_id (u64 LE) ||
+/// input_hash (32 bytes) || output_hash (32 bytes) || len (u32 LE) || worker_public_key`.
+/// The contract does not verify it; see `worker/src/result_signing.rs` for the format.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[near(serializers = [json])]
+pub struct ResultSignature {
+    /// Worker key that signed (e.g. "ed25519:...")
+    pub worker_public_key: String,
+    /// Hex SHA-256 of the request input data
+    pub input_hash: String,
+    /// Hex SHA-256 of the JSON output returned to the caller
+    pub output_hash: String,
+    /// Signature in NEAR string form (e.g. "ed25519:...")
+    pub signature: String,
+}
+
+/// Outcome of one entry in `resolve_batch`
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[near(serializers = [json])]
+pub enum ResolveStatus {
+    /// Yield resumed; charging, refund and events follow in `on_execution_response`
+    Resumed,
+    /// No pending request with this id (already resolved, cancelled or never existed)
+    NotFound,
+    /// Same request_id appeared earlier in the batch
+    Duplicate,
+    /// Response exceeds the yield payload limit; submit output separately first
+    PayloadTooLarge,
+    /// The yielded promise could not be resumed (e.g. already timed out)
+    ResumeFailed,
+}
+
+/// Per-request result of `resolve_batch`
+#[derive(Clone, Debug)]
+#[near(serializers = [json])]
+pub struct ResolveBatchEntry {
+    pub request_id: u64,
+    pub status: ResolveStatus,
 }
 
 /// Resource usage metrics
@@ -268,6 +682,10 @@ pub struct ResourceMetrics {
     pub instructions: u64,        // Instructions used during WASM execution
     pub time_ms: u64,              // Execution time in milliseconds
     pub compile_time_ms: Option<u64>, // Compilation time in milliseconds (if compiled)
+    #[serde(default)]
+    pub output_bytes: u64,         // Size of the returned output in bytes
+    #[serde(default)]
+    pub peak_memory_bytes: u64,    // Linear memory high-water mark, for tuning max_memory_mb
 }
 
 /// Reference to secrets stored in contract (new approach)
@@ -276,6 +694,8 @@ pub struct ResourceMetrics {
 pub struct SecretsReference {
     pub profile: String,      // Profile name (e.g., "default", "premium")
     pub account_id: AccountId, // Account that owns the secrets
+    #[serde(default)]
+    pub version: Option<u32>, // Pin a retained version (set_secrets), None = current
 }
 
 /// Secret profile stored in contract (internal storage)
@@ -340,6 +760,53 @@ pub struct SecretKey {
     pub owner: AccountId,         // Account that created these secrets
 }
 
+/// Secret key for JSON view (see `resolve_secret_key`)
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[near(serializers = [json])]
+pub struct SecretKeyView {
+    pub accessor: SecretAccessor,
+    pub profile: String,
+    pub owner: AccountId,
+}
+
+/// Key of one retained secret version
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[near(serializers = [borsh])]
+pub struct SecretVersionKey {
+    pub secret: SecretKey,
+    pub version: u32,
+}
+
+/// Retained secret version written by `set_secrets` (internal storage)
+#[derive(Clone, Debug)]
+#[near(serializers = [borsh])]
+pub struct SecretVersion {
+    pub encrypted_secrets: String,      // base64-encoded encrypted secrets
+    pub access: types::AccessCondition, // Access control rules at that version
+    pub created_at: u64,                // Timestamp when the version was pushed
+    pub storage_deposit: Balance,       // Storage staking for this version entry
+}
+
+/// Version bookkeeping for a secret profile (internal storage)
+#[derive(Clone, Debug)]
+#[near(serializers = [borsh])]
+pub struct SecretVersionHistory {
+    pub retained: Vec<u32>,       // Retained version numbers, oldest first
+    pub latest: u32,              // Last version number handed out
+    pub active: Option<u32>,      // Version the profile currently holds (None after store_secrets)
+    pub storage_deposit: Balance, // Storage staking for this history entry
+}
+
+/// Retained secret version for JSON view
+#[derive(Clone, Debug)]
+#[near(serializers = [json])]
+pub struct SecretVersionView {
+    pub version: u32,
+    pub created_at: u64,
+    pub storage_deposit: U128,
+    pub active: bool, // Profile currently holds this version
+}
+
 // ============================================================================
 // Project System - Persistent Storage for WASM Applications
 // ============================================================================
@@ -400,6 +867,67 @@ pub struct VersionView {
     pub is_active: bool,
 }
 
+/// Size and resource bounds of `request_execution` (see `get_limits`)
+#[derive(Clone, Debug, PartialEq)]
+#[near(serializers = [json])]
+pub struct ExecutionLimits {
+    /// Largest accepted `input_data`, in bytes
+    pub max_input_bytes: u32,
+    /// Hard cap on `resource_limits.max_output_bytes`
+    pub max_output_bytes: u32,
+    /// `max_output_bytes` used when the request doesn't set one
+    pub default_max_output_bytes: u32,
+    /// Hard cap on `resource_limits.max_instructions`
+    pub max_instructions: u64,
+    /// Hard cap on `resource_limits.max_execution_seconds`
+    pub max_execution_seconds: u64,
+    /// Longest compilation a worker runs before giving up
+    pub max_compilation_seconds: u64,
+}
+
+/// Resource caps of one project: an owner-set override or the global hard caps
+/// (see `get_project_limits`)
+#[derive(Clone, Debug, PartialEq)]
+#[near(serializers = [borsh, json])]
+pub struct ProjectLimits {
+    /// Cap on `resource_limits.max_instructions`
+    pub max_instructions: u64,
+    /// Cap on `resource_limits.max_execution_seconds`
+    pub max_execution_seconds: u64,
+}
+
+impl Default for ProjectLimits {
+    fn default() -> Self {
+        Self {
+            max_instructions: MAX_INSTRUCTIONS,
+            max_execution_seconds: MAX_EXECUTION_SECONDS,
+        }
+    }
+}
+
+/// Outcome of `simulate_execution`
+#[derive(Clone, Debug, PartialEq)]
+#[near(serializers = [json])]
+pub struct SimulationResult {
+    /// Whether `request_execution` would accept the request
+    pub accepted: bool,
+    /// Deposit the request would need at the simulated priority
+    pub estimated_cost: U128,
+    /// Why the request would be rejected (None when accepted)
+    pub reason: Option<String>,
+}
+
+/// Caller identity as seen by the contract (returned by `whoami`)
+#[derive(Clone, Debug)]
+#[near(serializers = [json])]
+pub struct CallerInfo {
+    pub predecessor: AccountId,
+    pub signer: AccountId,
+    /// Signer public key (e.g. "ed25519:...")
+    pub signer_pk: String,
+    pub current: AccountId,
+}
+
 /// Pricing view for JSON responses (includes both NEAR and USD pricing)
 #[derive(Clone, Debug)]
 #[near(serializers = [json])]
@@ -409,6 +937,7 @@ pub struct PricingView {
     pub per_million_instructions_fee: U128,
     pub per_ms_fee: U128,
     pub per_compile_ms_fee: U128,
+    pub priority_fee: U128,
     // USD pricing (for HTTPS API, in minimal token units)
     pub base_fee_usd: U128,
     pub per_million_instructions_fee_usd: U128,
@@ -490,6 +1019,52 @@ pub struct Contract {
     // entries deserialise unchanged after a contract upgrade that
     // adds this map.
     secret_vault_bindings: LookupMap<SecretKey, AccountId>,
+
+    // Number of entries in pending_requests (LookupMap has no len)
+    pending_requests_count: u64,
+
+    // Global cap on pending_requests; new submissions are rejected at capacity
+    max_pending_requests: u64,
+
+    // Retained secret versions: (SecretKey, version) -> SecretVersion
+    secret_versions: LookupMap<SecretVersionKey, SecretVersion>,
+
+    // Secret version bookkeeping: SecretKey -> retained versions / active version
+    secret_version_history: LookupMap<SecretKey, SecretVersionHistory>,
+
+    // Number of versions set_secrets retains per profile
+    max_secret_versions: u32,
+
+    // Lifecycle records of the last REQUEST_TIMELINE_RETENTION finished requests
+    finished_requests: LookupMap<u64, RequestLifecycle>,
+
+    // Idempotency keys: (sender_id, idempotency_key) -> request_id
+    idempotency_keys: LookupMap<(AccountId, String), u64>,
+
+    // Reverse index for pruning: request_id -> (sender_id, idempotency_key)
+    idempotency_key_requests: LookupMap<u64, (AccountId, String)>,
+
+    // NEAR cost of one priority level (RequestParams::priority)
+    priority_fee: Balance,
+
+    // Pending requests per priority: priority -> count (absent = 0)
+    pending_by_priority: LookupMap<u8, u64>,
+
+    // Largest input_data request_execution accepts, in bytes
+    max_input_bytes: u32,
+
+    // Resource cap overrides: project_id or GitHub repo -> caps
+    project_limits: LookupMap<String, ProjectLimits>,
+
+    // Pending requests per sender_id (absent = 0)
+    pending_by_account: LookupMap<AccountId, u32>,
+
+    // Cap on pending requests of one sender
+    max_pending_per_account: u32,
+
+    // Requests with a lower id are not in the pending counters (stored before
+    // migrate() seeded them); removing one leaves the counters untouched
+    pending_counted_from: u64,
 }
 
 #[near_bindgen]
@@ -502,7 +1077,7 @@ impl Contract {
             operator_id: operator_id.unwrap_or(owner_id),
             paused: false,
             event_standard: event_standard.unwrap_or("near-outlayer".to_string()),
-            event_version: event_version.unwrap_or("1.0.0".to_string()),
+            event_version: event_version.unwrap_or(events::EVENT_VERSION.to_string()),
             // NEAR pricing
             base_fee: 1_000_000_000_000_000_000_000, // 0.001 NEAR
             per_million_instructions_fee: 100_000_000_000_000, // 0.0000001 NEAR per million instructions
@@ -535,6 +1110,29 @@ impl Contract {
             wallet_owner_index: LookupMap::new(StorageKey::WalletOwnerIndex),
             // Per-vault master phase 2
             secret_vault_bindings: LookupMap::new(StorageKey::SecretVaultBindings),
+            // Backpressure
+            pending_requests_count: 0,
+            max_pending_requests: DEFAULT_MAX_PENDING_REQUESTS,
+            // Secret versioning
+            secret_versions: LookupMap::new(StorageKey::SecretVersions),
+            secret_version_history: LookupMap::new(StorageKey::SecretVersionHistory),
+            max_secret_versions: DEFAULT_MAX_SECRET_VERSIONS,
+            // Request timelines
+            finished_requests: LookupMap::new(StorageKey::FinishedRequests),
+            // Idempotent submissions
+            idempotency_keys: LookupMap::new(StorageKey::IdempotencyKeys),
+            idempotency_key_requests: LookupMap::new(StorageKey::IdempotencyKeyRequests),
+            // Priority tiers
+            priority_fee: DEFAULT_PRIORITY_FEE,
+            pending_by_priority: LookupMap::new(StorageKey::PendingByPriority),
+            // Input size cap
+            max_input_bytes: DEFAULT_MAX_INPUT_BYTES,
+            // Per-project resource caps
+            project_limits: LookupMap::new(StorageKey::ProjectLimits),
+            // Per-account backpressure
+            pending_by_account: LookupMap::new(StorageKey::PendingByAccount),
+            max_pending_per_account: DEFAULT_MAX_PENDING_PER_ACCOUNT,
+            pending_counted_from: 0,
         }
     }
 
@@ -635,31 +1233,20 @@ impl Contract {
         );
     }
 
-    fn calculate_cost(&self, metrics: &ResourceMetrics) -> Balance {
-        let instruction_cost =
-            (metrics.instructions / 1_000_000) as u128 * self.per_million_instructions_fee;
-        let time_cost = metrics.time_ms as u128 * self.per_ms_fee;
-
-        // Add compilation cost if compilation occurred (uses separate, higher rate)
-        let compile_cost = metrics.compile_time_ms
-            .map(|ms| ms as u128 * self.per_compile_ms_fee)
-            .unwrap_or(0);
-
-        self.base_fee + instruction_cost + time_cost + compile_cost
+    /// Current NEAR rates, as stored on requests submitted now
+    fn current_pricing(&self) -> PricingSnapshot {
+        PricingSnapshot {
+            base_fee: self.base_fee,
+            per_million_instructions_fee: self.per_million_instructions_fee,
+            per_ms_fee: self.per_ms_fee,
+            per_compile_ms_fee: self.per_compile_ms_fee,
+            priority_fee: self.priority_fee,
+        }
     }
 
-    /// Estimate cost based on resource limits
-    fn estimate_cost(&self, limits: &ResourceLimits) -> Balance {
-        // Use requested limits or defaults
-        let max_instructions = limits.max_instructions.unwrap_or(1_000_000_000);
-        let max_execution_seconds = limits.max_execution_seconds.unwrap_or(60);
-        let max_time_ms = max_execution_seconds * 1000;
-
-        // Calculate worst-case cost
-        let instruction_cost = (max_instructions / 1_000_000) as u128 * self.per_million_instructions_fee;
-        let time_cost = max_time_ms as u128 * self.per_ms_fee;
-
-        self.base_fee + instruction_cost + time_cost
+    /// Estimate cost based on resource limits, at current rates
+    fn estimate_cost(&self, limits: &ResourceLimits, priority: u8) -> Balance {
+        self.current_pricing().estimate_cost(limits, priority)
     }
 }
 
@@ -5,18 +5,40 @@
 //!
 //! * v4 → v5: rename `per_ms_fee_usd` → `per_sec_fee_usd`. (Run.)
 //! * v5 → v6: add `wallet_policies`, `wallet_owner_index`. (Run.)
-//! * **v6 → v7 (current): add `secret_vault_bindings` (Phase 2 of
-//!   per-vault master plan).**
+//! * v6 → v7: add `secret_vault_bindings` (Phase 2 of per-vault
+//!   master plan).
+//! * **v7 → v8 (current): add `pending_requests_count`,
+//!   `max_pending_requests` (global backpressure), `secret_versions`,
+//!   `secret_version_history`, `max_secret_versions` (secret versioning),
+//!   `finished_requests` (request timelines), `idempotency_keys`,
+//!   `idempotency_key_requests` (idempotent submission), `priority_fee`,
+//!   `pending_by_priority` (priority tiers), `max_input_bytes` (input size
+//!   cap), `project_limits` (per-project resource caps), and
+//!   `pending_by_account`, `max_pending_per_account`, `pending_counted_from`
+//!   (per-account backpressure).**
 //!
-//! Versions ≤ v6 are now historical. The `migrate()` entry point in
-//! this file targets v6 → v7 specifically. Production deployments must
-//! be on v6 before calling this migration; an earlier-version
-//! deployment must first run a v4/v5 → v6 migration from a prior code
-//! revision.
+//! `migrate()` reads v7 state, or v6 state if the v6 → v7 migration has
+//! not been run yet, and applies v6 → v7 on the way. Both layouts are
+//! tried with `try_from_slice`, which requires every byte to be consumed,
+//! so one layout never parses as another.
+//!
+//! Requests still pending at deploy time keep their v7 `ExecutionRequest`
+//! layout ([`ExecutionRequestV7`]). `ExecutionRequest`'s Borsh decoding
+//! falls back to it, so they resolve normally; the v7 rates `migrate()`
+//! stores under `StorageKey::LegacyPricing` bill them, as the v7 code
+//! would have.
+//!
+//! The pending counters are seeded from the requests actually pending: an
+//! unresumed yield times out after about 200 blocks and removes its
+//! request, so `migrate()` only looks at the last
+//! [`MIGRATION_PENDING_SCAN`] request ids. `pending_counted_from` marks
+//! where that scan started; a straggler below it leaves the counters alone
+//! when it is removed, so they never drift low.
 
 use crate::*;
-use near_sdk::borsh::BorshDeserialize;
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
 use near_sdk::collections::{LookupMap, UnorderedMap, UnorderedSet};
+use near_sdk::IntoStorageKey;
 
 /// Pre-Phase-2 contract state (v6). Mirrors the `Contract` struct as it
 /// existed immediately before the per-vault master Phase 2 changes
@@ -66,27 +88,61 @@ pub struct ContractV6 {
     wallet_owner_index: LookupMap<AccountId, UnorderedSet<String>>,
 }
 
-#[near_bindgen]
-impl Contract {
-    /// Migrate from v6 (pre-Phase-2) to v7 (per-vault master Phase 2).
-    ///
-    /// Adds `secret_vault_bindings` as an empty `LookupMap`. All
-    /// existing `SecretProfile` entries deserialise unchanged — old
-    /// secrets without a binding are interpreted by the worker as
-    /// "encrypted with the default OutLayer master". Customers
-    /// migrating to per-vault masters re-encrypt and rebind via
-    /// `update_user_secrets` at their own pace.
-    #[private]
-    #[init(ignore_state)]
-    pub fn migrate() -> Self {
-        let v6: ContractV6 = env::state_read().expect("failed to read v6 state");
+/// Per-vault master contract state (v7), the last layout deployed.
+/// `ContractV6` plus `secret_vault_bindings`.
+#[derive(BorshDeserialize)]
+#[borsh(crate = "near_sdk::borsh")]
+#[allow(dead_code)] // fields needed for borsh deserialisation only
+pub struct ContractV7 {
+    owner_id: AccountId,
+    operator_id: AccountId,
+    paused: bool,
+    event_standard: String,
+    event_version: String,
 
-        log!(
-            "Migrating contract v6 -> v7 (add secret_vault_bindings): owner={}, total_executions={}",
-            v6.owner_id,
-            v6.total_executions
-        );
+    // NEAR pricing
+    base_fee: Balance,
+    per_million_instructions_fee: Balance,
+    per_ms_fee: Balance,
+    per_compile_ms_fee: Balance,
+
+    // USD pricing
+    base_fee_usd: u128,
+    per_million_instructions_fee_usd: u128,
+    per_sec_fee_usd: u128,
+    per_compile_ms_fee_usd: u128,
+
+    payment_token_contract: Option<AccountId>,
+
+    next_request_id: u64,
+    pending_requests: LookupMap<u64, ExecutionRequest>,
+
+    total_executions: u64,
+    total_fees_collected: Balance,
+
+    secrets_storage: LookupMap<SecretKey, SecretProfile>,
+    user_secrets_index: LookupMap<AccountId, UnorderedSet<SecretKey>>,
+
+    projects: LookupMap<String, Project>,
+    project_versions: LookupMap<String, UnorderedMap<String, VersionInfo>>,
+    user_projects_index: LookupMap<AccountId, UnorderedSet<String>>,
+    next_project_id: u64,
+
+    developer_earnings: LookupMap<AccountId, u128>,
+    user_stablecoin_balances: LookupMap<AccountId, u128>,
+
+    wallet_policies: LookupMap<String, wallet::WalletPolicyEntry>,
+    wallet_owner_index: LookupMap<AccountId, UnorderedSet<String>>,
+
+    secret_vault_bindings: LookupMap<SecretKey, AccountId>,
+}
 
+impl From<ContractV6> for ContractV7 {
+    /// v6 → v7: `secret_vault_bindings` starts empty. Existing
+    /// `SecretProfile` entries deserialise unchanged — old secrets
+    /// without a binding are interpreted by the worker as "encrypted
+    /// with the default OutLayer master".
+    fn from(v6: ContractV6) -> Self {
         Self {
             owner_id: v6.owner_id,
             operator_id: v6.operator_id,
@@ -116,15 +172,228 @@ impl Contract {
             user_stablecoin_balances: v6.user_stablecoin_balances,
             wallet_policies: v6.wallet_policies,
             wallet_owner_index: v6.wallet_owner_index,
-            // ----- v7 -----
             secret_vault_bindings: LookupMap::new(StorageKey::SecretVaultBindings),
         }
     }
+}
+
+/// `ExecutionSource` as stored by v7 (GitHub without `package`/`features`)
+#[derive(BorshDeserialize, BorshSerialize)]
+#[borsh(crate = "near_sdk::borsh")]
+pub enum ExecutionSourceV7 {
+    GitHub { repo: String, commit: String, build_target: Option<String> },
+    WasmUrl { url: String, hash: String, build_target: Option<String> },
+    Project { project_id: String, version_key: Option<String> },
+}
+
+impl From<ExecutionSourceV7> for ExecutionSource {
+    fn from(old: ExecutionSourceV7) -> Self {
+        match old {
+            ExecutionSourceV7::GitHub { repo, commit, build_target } => {
+                ExecutionSource::GitHub { repo, commit, build_target, package: None, features: Vec::new() }
+            }
+            ExecutionSourceV7::WasmUrl { url, hash, build_target } => ExecutionSource::WasmUrl { url, hash, build_target },
+            ExecutionSourceV7::Project { project_id, version_key } => ExecutionSource::Project { project_id, version_key },
+        }
+    }
+}
+
+/// `ResourceLimits` as stored by v7 (no `max_output_bytes`)
+#[derive(BorshDeserialize, BorshSerialize)]
+#[borsh(crate = "near_sdk::borsh")]
+pub struct ResourceLimitsV7 {
+    pub max_instructions: Option<u64>,
+    pub max_memory_mb: Option<u32>,
+    pub max_execution_seconds: Option<u64>,
+}
+
+/// `SecretsReference` as stored by v7 (no pinned `version`)
+#[derive(BorshDeserialize, BorshSerialize)]
+#[borsh(crate = "near_sdk::borsh")]
+pub struct SecretsReferenceV7 {
+    pub profile: String,
+    pub account_id: AccountId,
+}
+
+/// `ExecutionRequest` as stored by v7. `CodeSource`, `ResponseFormat` and
+/// `StoredOutput` still read their v7 encoding, so they are used as is.
+#[derive(BorshDeserialize, BorshSerialize)]
+#[borsh(crate = "near_sdk::borsh")]
+pub struct ExecutionRequestV7 {
+    pub request_id: u64,
+    pub data_id: CryptoHash,
+    pub sender_id: AccountId,
+    pub execution_source: ExecutionSourceV7,
+    pub resolved_source: CodeSource,
+    pub resource_limits: ResourceLimitsV7,
+    pub payment: Balance,
+    pub timestamp: u64,
+    pub secrets_ref: Option<SecretsReferenceV7>,
+    pub response_format: ResponseFormat,
+    pub input_data: Option<String>,
+    pub payer_account_id: AccountId,
+    pub attached_usd: u128,
+    pub pending_output: Option<StoredOutput>,
+    pub output_submitted: bool,
+}
+
+impl From<ExecutionRequestV7> for ExecutionRequest {
+    fn from(old: ExecutionRequestV7) -> Self {
+        Self {
+            request_id: old.request_id,
+            data_id: old.data_id,
+            sender_id: old.sender_id,
+            execution_source: old.execution_source.into(),
+            resolved_source: old.resolved_source,
+            resource_limits: ResourceLimits {
+                max_instructions: old.resource_limits.max_instructions,
+                max_memory_mb: old.resource_limits.max_memory_mb,
+                max_execution_seconds: old.resource_limits.max_execution_seconds,
+                max_output_bytes: None,
+            },
+            payment: old.payment,
+            timestamp: old.timestamp,
+            secrets_ref: old.secrets_ref.map(|s| SecretsReference {
+                profile: s.profile,
+                account_id: s.account_id,
+                version: None,
+            }),
+            response_format: old.response_format,
+            input_data: old.input_data,
+            input_content_type: None,
+            payer_account_id: old.payer_account_id,
+            attached_usd: old.attached_usd,
+            pending_output: old.pending_output,
+            output_submitted: old.output_submitted,
+            refund_account_id: None,
+            stale_timeout_seconds: None,
+            output_submitted_at: None,
+            resolved_at: None,
+            priority: 0,
+            pricing_snapshot: legacy_pricing(),
+        }
+    }
+}
+
+/// Keep the v7 rates for requests submitted before the upgrade
+pub(crate) fn store_legacy_pricing(pricing: &PricingSnapshot) {
+    let bytes = near_sdk::borsh::to_vec(pricing).expect("failed to serialize legacy pricing");
+    env::storage_write(&StorageKey::LegacyPricing.into_storage_key(), &bytes);
+}
+
+/// Rates stored by `migrate()` for v7 requests
+fn legacy_pricing() -> PricingSnapshot {
+    env::storage_read(&StorageKey::LegacyPricing.into_storage_key())
+        .and_then(|bytes| PricingSnapshot::try_from_slice(&bytes).ok())
+        .expect("v7 request found but migrate() stored no legacy pricing")
+}
+
+/// Request ids below `next_request_id` that `migrate()` checks for pending requests
+pub const MIGRATION_PENDING_SCAN: u64 = 500;
+
+impl Contract {
+    /// Count the requests pending in `pending_counted_from..next_request_id`
+    pub(crate) fn seed_pending_counters(&mut self) {
+        for request_id in self.pending_counted_from..self.next_request_id {
+            if let Some(request) = self.pending_requests.get(&request_id) {
+                self.count_pending_request(&request);
+            }
+        }
+        log!("Seeded pending counters: {} pending requests", self.pending_requests_count);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Migrate from v7 (or v6) to v8.
+    ///
+    /// Every field added since v7 starts at its `new()` default: empty
+    /// maps, default caps and fees, and no project overrides until the
+    /// owner sets one. The pending counters start from the requests still
+    /// pending among the last `MIGRATION_PENDING_SCAN` ids.
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let bytes = env::storage_read(b"STATE").expect("failed to read contract state");
+        let v7 = ContractV7::try_from_slice(&bytes)
+            .or_else(|_| ContractV6::try_from_slice(&bytes).map(ContractV7::from))
+            .expect("failed to read v7 or v6 state");
+
+        log!(
+            "Migrating contract v7 -> v8: owner={}, total_executions={}",
+            v7.owner_id,
+            v7.total_executions
+        );
+
+        // v7 billed at the rates current at resolution; pending v7 requests keep these
+        store_legacy_pricing(&PricingSnapshot {
+            base_fee: v7.base_fee,
+            per_million_instructions_fee: v7.per_million_instructions_fee,
+            per_ms_fee: v7.per_ms_fee,
+            per_compile_ms_fee: v7.per_compile_ms_fee,
+            priority_fee: 0,
+        });
+
+        let mut contract = Self {
+            owner_id: v7.owner_id,
+            operator_id: v7.operator_id,
+            paused: v7.paused,
+            event_standard: v7.event_standard,
+            // 1.0.0 was the default; moving to 1.1.0 marks execution_completed as deprecated
+            event_version: if v7.event_version == "1.0.0" {
+                events::EVENT_VERSION.to_string()
+            } else {
+                v7.event_version
+            },
+            base_fee: v7.base_fee,
+            per_million_instructions_fee: v7.per_million_instructions_fee,
+            per_ms_fee: v7.per_ms_fee,
+            per_compile_ms_fee: v7.per_compile_ms_fee,
+            base_fee_usd: v7.base_fee_usd,
+            per_million_instructions_fee_usd: v7.per_million_instructions_fee_usd,
+            per_sec_fee_usd: v7.per_sec_fee_usd,
+            per_compile_ms_fee_usd: v7.per_compile_ms_fee_usd,
+            payment_token_contract: v7.payment_token_contract,
+            next_request_id: v7.next_request_id,
+            pending_requests: v7.pending_requests,
+            total_executions: v7.total_executions,
+            total_fees_collected: v7.total_fees_collected,
+            secrets_storage: v7.secrets_storage,
+            user_secrets_index: v7.user_secrets_index,
+            projects: v7.projects,
+            project_versions: v7.project_versions,
+            user_projects_index: v7.user_projects_index,
+            next_project_id: v7.next_project_id,
+            developer_earnings: v7.developer_earnings,
+            user_stablecoin_balances: v7.user_stablecoin_balances,
+            wallet_policies: v7.wallet_policies,
+            wallet_owner_index: v7.wallet_owner_index,
+            secret_vault_bindings: v7.secret_vault_bindings,
+            // ----- v8 -----
+            pending_requests_count: 0,
+            max_pending_requests: DEFAULT_MAX_PENDING_REQUESTS,
+            secret_versions: LookupMap::new(StorageKey::SecretVersions),
+            secret_version_history: LookupMap::new(StorageKey::SecretVersionHistory),
+            max_secret_versions: DEFAULT_MAX_SECRET_VERSIONS,
+            finished_requests: LookupMap::new(StorageKey::FinishedRequests),
+            idempotency_keys: LookupMap::new(StorageKey::IdempotencyKeys),
+            idempotency_key_requests: LookupMap::new(StorageKey::IdempotencyKeyRequests),
+            priority_fee: DEFAULT_PRIORITY_FEE,
+            pending_by_priority: LookupMap::new(StorageKey::PendingByPriority),
+            max_input_bytes: DEFAULT_MAX_INPUT_BYTES,
+            project_limits: LookupMap::new(StorageKey::ProjectLimits),
+            pending_by_account: LookupMap::new(StorageKey::PendingByAccount),
+            max_pending_per_account: DEFAULT_MAX_PENDING_PER_ACCOUNT,
+            pending_counted_from: v7.next_request_id.saturating_sub(MIGRATION_PENDING_SCAN),
+        };
+        contract.seed_pending_counters();
+        contract
+    }
 
     /// Returns the contract's storage-schema version. Bumped each time
     /// `migrate()` advances the layout. Off-chain tooling reads this to
     /// decide whether a deploy needs a migration call.
     pub fn get_storage_version(&self) -> String {
-        "7".to_string()
+        "8".to_string()
     }
 }
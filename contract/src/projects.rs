@@ -459,8 +459,12 @@ impl Contract {
 
     fn calculate_version_storage_size(&self, source: &CodeSource) -> u64 {
         let source_size = match source {
-            CodeSource::GitHub { repo, commit, build_target } => {
-                repo.len() + commit.len() + build_target.as_ref().map(|t| t.len()).unwrap_or(0)
+            CodeSource::GitHub { repo, commit, build_target, package, features } => {
+                repo.len()
+                    + commit.len()
+                    + build_target.as_ref().map(|t| t.len()).unwrap_or(0)
+                    + package.as_ref().map(|p| p.len()).unwrap_or(0)
+                    + features.iter().map(|f| f.len()).sum::<usize>()
             }
             CodeSource::WasmUrl { url, hash, build_target } => {
                 url.len() + hash.len() + build_target.as_ref().map(|t| t.len()).unwrap_or(0)
@@ -6,6 +6,9 @@ use near_sdk::json_types::U128;
 /// Storage cost per byte in NEAR
 pub const STORAGE_PRICE_PER_BYTE: Balance = 10_000_000_000_000_000_000; // 0.00001 NEAR per byte
 
+/// LookupMap entry overhead (key hash + pointer)
+const BASE_STORAGE_OVERHEAD: u64 = 40;
+
 #[near_bindgen]
 impl Contract {
     /// Store secrets with access control.
@@ -46,6 +49,206 @@ impl Contract {
         access: types::AccessCondition,
         vault_id: Option<AccountId>,
     ) {
+        let key = self.internal_store_secrets(accessor, profile, encrypted_secrets_base64, access, vault_id, 0, 0);
+
+        // The profile no longer matches any retained version
+        if let Some(mut history) = self.secret_version_history.get(&key) {
+            if history.active.take().is_some() {
+                self.secret_version_history.insert(&key, &history);
+            }
+        }
+
+        self.emit_secrets_updated(&key, events::SecretsChange::Stored, None);
+    }
+
+    /// Store secrets as a new version, keeping earlier versions for
+    /// [`Contract::rollback_secret`].
+    ///
+    /// Arguments, validation and vault binding semantics are the same as
+    /// [`Contract::store_secrets`]. The last `max_secret_versions`
+    /// versions (default 3) are retained; older ones are pruned and
+    /// their storage deposit is credited towards this call.
+    ///
+    /// The attached deposit must cover the profile plus the new version
+    /// entry (and the version history entry on first use); the excess
+    /// is refunded.
+    #[payable]
+    pub fn set_secrets(
+        &mut self,
+        accessor: SecretAccessor,
+        profile: String,
+        encrypted_secrets_base64: String,
+        access: types::AccessCondition,
+        vault_id: Option<AccountId>,
+    ) {
+        let key = SecretKey {
+            accessor: accessor.clone(),
+            profile: profile.clone(),
+            owner: env::predecessor_account_id(),
+        };
+
+        let existing_history = self.secret_version_history.get(&key);
+        let history_charge = if existing_history.is_none() {
+            self.secret_history_storage_size(&key) as u128 * STORAGE_PRICE_PER_BYTE
+        } else {
+            0
+        };
+        let mut history = existing_history.unwrap_or(SecretVersionHistory {
+            retained: Vec::new(),
+            latest: 0,
+            active: None,
+            storage_deposit: history_charge,
+        });
+
+        // Make room for the new version, crediting pruned deposits
+        let mut released: Balance = 0;
+        while history.retained.len() >= self.max_secret_versions as usize {
+            let pruned = history.retained.remove(0);
+            let version_key = SecretVersionKey { secret: key.clone(), version: pruned };
+            if let Some(old) = self.secret_versions.remove(&version_key) {
+                released += old.storage_deposit;
+            }
+        }
+
+        let version_deposit = self.secret_version_storage_size(&key, &encrypted_secrets_base64, &access) as u128
+            * STORAGE_PRICE_PER_BYTE;
+
+        self.internal_store_secrets(
+            accessor,
+            profile,
+            encrypted_secrets_base64.clone(),
+            access.clone(),
+            vault_id,
+            version_deposit + history_charge,
+            released,
+        );
+
+        let version = history.latest + 1;
+        self.secret_versions.insert(
+            &SecretVersionKey { secret: key.clone(), version },
+            &SecretVersion {
+                encrypted_secrets: encrypted_secrets_base64,
+                access,
+                created_at: env::block_timestamp(),
+                storage_deposit: version_deposit,
+            },
+        );
+        history.latest = version;
+        history.retained.push(version);
+        history.active = Some(version);
+        self.secret_version_history.insert(&key, &history);
+        self.emit_secrets_updated(&key, events::SecretsChange::Stored, Some(version));
+
+        log!(
+            "Secret version stored: accessor={:?}, profile={}, version={}, retained={:?}",
+            key.accessor,
+            key.profile,
+            version,
+            history.retained
+        );
+    }
+
+    /// Restore a retained version as the profile's current secrets.
+    ///
+    /// Only the owner can roll back: the profile is looked up under the
+    /// caller's account. No new version is created; the next
+    /// `set_secrets` continues the numbering. Attach a deposit if the
+    /// restored version needs more storage than the current profile;
+    /// the excess is refunded.
+    ///
+    /// # Arguments
+    /// * `accessor` - What code can access these secrets
+    /// * `profile` - Profile name
+    /// * `version` - Retained version (see `list_secret_versions`)
+    #[payable]
+    pub fn rollback_secret(&mut self, accessor: SecretAccessor, profile: String, version: u32) {
+        let caller = env::predecessor_account_id();
+        let key = SecretKey {
+            accessor: accessor.clone(),
+            profile: profile.clone(),
+            owner: caller.clone(),
+        };
+
+        let mut history = self.secret_version_history.get(&key)
+            .expect("No secret versions found");
+        let restored = self
+            .secret_versions
+            .get(&SecretVersionKey { secret: key.clone(), version })
+            .unwrap_or_else(|| env::panic_str(&format!("Secret version {} not found", version)));
+        let mut profile_data = self.secrets_storage.get(&key)
+            .expect("Secrets not found");
+
+        let vault_bound = self.secret_vault_bindings.get(&key).is_some();
+        let required_deposit = self.calculate_secret_storage_size(
+            &key,
+            &restored.encrypted_secrets,
+            &restored.access,
+            vault_bound,
+        ) as u128
+            * STORAGE_PRICE_PER_BYTE;
+        let attached_deposit = env::attached_deposit().as_yoctonear();
+        let total_available = attached_deposit + profile_data.storage_deposit;
+
+        assert!(
+            total_available >= required_deposit,
+            "Insufficient deposit for rollback. Required: {} yoctoNEAR, available (attached {} + old {}): {} yoctoNEAR",
+            required_deposit,
+            attached_deposit,
+            profile_data.storage_deposit,
+            total_available
+        );
+
+        let refund = total_available - required_deposit;
+        if refund > 0 {
+            near_sdk::Promise::new(caller.clone()).transfer(NearToken::from_yoctonear(refund));
+        }
+
+        profile_data.encrypted_secrets = restored.encrypted_secrets;
+        profile_data.access = restored.access;
+        profile_data.updated_at = env::block_timestamp();
+        profile_data.storage_deposit = required_deposit;
+        self.secrets_storage.insert(&key, &profile_data);
+
+        history.active = Some(version);
+        self.secret_version_history.insert(&key, &history);
+        self.emit_secrets_updated(&key, events::SecretsChange::RolledBack, Some(version));
+
+        log!(
+            "Secrets rolled back: accessor={:?}, profile={}, owner={}, version={}",
+            accessor,
+            profile,
+            caller,
+            version
+        );
+    }
+
+    fn emit_secrets_updated(&self, key: &SecretKey, change: events::SecretsChange, version: Option<u32>) {
+        events::ContractEvent::SecretsUpdated {
+            owner: &key.owner,
+            accessor: &key.accessor,
+            profile: &key.profile,
+            change,
+            version,
+        }
+        .emit(&self.event_standard, &self.event_version);
+    }
+
+    /// Validate and write the secret profile, settling its storage deposit.
+    ///
+    /// `version_charge` is extra storage (retained versions) the attached
+    /// deposit must also cover; `version_credit` is deposit released by
+    /// pruned versions that counts towards it. Returns the stored key.
+    #[allow(clippy::too_many_arguments)]
+    fn internal_store_secrets(
+        &mut self,
+        accessor: SecretAccessor,
+        profile: String,
+        encrypted_secrets_base64: String,
+        access: types::AccessCondition,
+        vault_id: Option<AccountId>,
+        version_charge: Balance,
+        version_credit: Balance,
+    ) -> SecretKey {
         let caller = env::predecessor_account_id();
 
         // Validate accessor
@@ -121,7 +324,8 @@ impl Contract {
             vault_bound_after_call,
         );
         let required_deposit = storage_usage as u128 * STORAGE_PRICE_PER_BYTE;
-        let attached_deposit = env::attached_deposit().as_yoctonear();
+        let total_required = required_deposit + version_charge;
+        let attached_deposit = env::attached_deposit().as_yoctonear() + version_credit;
 
         // Check if updating existing secrets
         let is_new = self.secrets_storage.get(&key).is_none();
@@ -131,16 +335,16 @@ impl Contract {
             let total_available = attached_deposit + existing.storage_deposit;
 
             assert!(
-                total_available >= required_deposit,
+                total_available >= total_required,
                 "Insufficient deposit for update. Required: {} yoctoNEAR, available (attached {} + old {}): {} yoctoNEAR",
-                required_deposit,
+                total_required,
                 attached_deposit,
                 existing.storage_deposit,
                 total_available
             );
 
             // Refund excess
-            let refund = total_available - required_deposit;
+            let refund = total_available - total_required;
             if refund > 0 {
                 near_sdk::Promise::new(caller.clone()).transfer(NearToken::from_yoctonear(refund));
                 log!(
@@ -148,22 +352,22 @@ impl Contract {
                     accessor, profile,
                     existing.storage_deposit,
                     attached_deposit,
-                    required_deposit,
+                    total_required,
                     refund
                 );
             }
         } else {
             // Check attached deposit
             assert!(
-                attached_deposit >= required_deposit,
+                attached_deposit >= total_required,
                 "Insufficient storage deposit. Required: {} yoctoNEAR, attached: {} yoctoNEAR",
-                required_deposit,
+                total_required,
                 attached_deposit
             );
 
             // Refund excess if any
-            if attached_deposit > required_deposit {
-                let refund = attached_deposit - required_deposit;
+            if attached_deposit > total_required {
+                let refund = attached_deposit - total_required;
                 near_sdk::Promise::new(caller.clone()).transfer(NearToken::from_yoctonear(refund));
             }
         }
@@ -232,6 +436,8 @@ impl Contract {
                 nonce
             );
         }
+
+        key
     }
 
     /// Delete secrets and refund storage deposit
@@ -262,6 +468,19 @@ impl Contract {
         );
     }
 
+    /// Delete repo-bound secrets and refund storage deposit
+    ///
+    /// Shorthand for [`Contract::delete_secrets`] with a
+    /// [`SecretAccessor::Repo`] accessor.
+    ///
+    /// # Arguments
+    /// * `repo` - Normalized repo path: "github.com/owner/repo"
+    /// * `branch` - Branch the secrets were stored for, or `None` for all branches
+    /// * `profile` - Profile name
+    pub fn delete_repo_secrets(&mut self, repo: String, branch: Option<String>, profile: String) {
+        self.delete_secrets(SecretAccessor::Repo { repo, branch }, profile);
+    }
+
     /// Internal method to delete secrets by key
     /// pub(crate) to allow access from payment.rs for delete_payment_key
     pub(crate) fn delete_secrets_internal(&mut self, key: SecretKey, caller: &AccountId) {
@@ -271,6 +490,17 @@ impl Contract {
         // Remove from storage
         self.secrets_storage.remove(&key);
 
+        // Drop retained versions, refunding their deposits with the profile's
+        let mut refund = profile_data.storage_deposit;
+        if let Some(history) = self.secret_version_history.remove(&key) {
+            refund += history.storage_deposit;
+            for version in history.retained {
+                if let Some(old) = self.secret_versions.remove(&SecretVersionKey { secret: key.clone(), version }) {
+                    refund += old.storage_deposit;
+                }
+            }
+        }
+
         // Drop the vault binding alongside the secret. Idempotent
         // — `remove()` on a missing key is a no-op.
         self.secret_vault_bindings.remove(&key);
@@ -287,11 +517,19 @@ impl Contract {
         }
 
         // Refund storage deposit
-        if profile_data.storage_deposit > 0 {
+        if refund > 0 {
             near_sdk::Promise::new(caller.clone())
-                .transfer(NearToken::from_yoctonear(profile_data.storage_deposit));
-            log!("Refunded {} yoctoNEAR", profile_data.storage_deposit);
+                .transfer(NearToken::from_yoctonear(refund));
+            log!("Refunded {} yoctoNEAR", refund);
+        }
+
+        events::ContractEvent::SecretsDeleted {
+            owner: &key.owner,
+            accessor: &key.accessor,
+            profile: &key.profile,
+            storage_refunded: U128(refund),
         }
+        .emit(&self.event_standard, &self.event_version);
     }
 
     /// Drop the vault binding for an existing secret without touching
@@ -372,41 +610,15 @@ impl Contract {
         // - User index entry: UnorderedSet overhead (for new entries)
         // - Base overhead: LookupMap entry overhead
 
-        const BASE_STORAGE_OVERHEAD: u64 = 40; // LookupMap entry overhead (key hash + pointer)
         const INDEX_ENTRY_OVERHEAD: u64 = 64; // UnorderedSet entry overhead
 
-        // Accessor size (Borsh serialization adds enum discriminant + data)
-        let accessor_size = match &key.accessor {
-            SecretAccessor::Repo { repo, branch } => {
-                1 + // enum discriminant
-                4 + repo.len() + // String with u32 length prefix
-                1 + branch.as_ref().map(|b| 4 + b.len()).unwrap_or(0) // Option<String>
-            }
-            SecretAccessor::WasmHash { hash } => {
-                1 + // enum discriminant
-                4 + hash.len() // String with u32 length prefix
-            }
-            SecretAccessor::Project { project_id } => {
-                1 + // enum discriminant
-                4 + project_id.len() // String with u32 length prefix
-            }
-            SecretAccessor::System(_secret_type) => {
-                1 + // enum discriminant for System
-                1   // enum discriminant for SystemSecretType (PaymentKey = 0)
-            }
-        };
-
-        // Key size
-        let key_size = (accessor_size
-            + 4 + key.profile.len() // String with u32 length prefix
-            + 4 + key.owner.as_str().len()) as u64; // AccountId (String with u32 length prefix)
+        let key_size = Self::secret_key_size(key);
 
         // Value size
         let encrypted_size = (4 + encrypted_secrets.len()) as u64; // String with u32 length prefix
 
         // AccessCondition size (serialize to estimate actual size)
-        let access_json = serde_json::to_string(access).unwrap_or_default();
-        let access_size = (access_json.len() + 10) as u64; // JSON + Borsh overhead
+        let access_size = Self::access_condition_size(access);
 
         let timestamps_and_deposit_size = 8 + 8 + 16; // created_at + updated_at + storage_deposit
 
@@ -432,6 +644,69 @@ impl Contract {
 
         BASE_STORAGE_OVERHEAD + key_size + value_size + index_overhead + binding_overhead
     }
+
+    /// Storage size of one `secret_versions` entry (in bytes)
+    fn secret_version_storage_size(
+        &self,
+        key: &SecretKey,
+        encrypted_secrets: &str,
+        access: &types::AccessCondition,
+    ) -> u64 {
+        let key_size = Self::secret_key_size(key) + 4; // + version: u32
+        let value_size = (4 + encrypted_secrets.len()) as u64 // String with u32 length prefix
+            + Self::access_condition_size(access)
+            + 8 + 16; // created_at + storage_deposit
+
+        BASE_STORAGE_OVERHEAD + key_size + value_size
+    }
+
+    /// Storage size of a `secret_version_history` entry (in bytes)
+    ///
+    /// Sized for `MAX_SECRET_VERSIONS_LIMIT` retained versions so the
+    /// deposit stays sufficient if the owner raises the limit later.
+    fn secret_history_storage_size(&self, key: &SecretKey) -> u64 {
+        let value_size = 4 + 4 * MAX_SECRET_VERSIONS_LIMIT as u64 // Vec<u32>
+            + 4 // latest
+            + 1 + 4 // active: Option<u32>
+            + 16; // storage_deposit
+
+        BASE_STORAGE_OVERHEAD + Self::secret_key_size(key) + value_size
+    }
+
+    /// Borsh size of a `SecretKey` (in bytes)
+    fn secret_key_size(key: &SecretKey) -> u64 {
+        // Accessor size (Borsh serialization adds enum discriminant + data)
+        let accessor_size = match &key.accessor {
+            SecretAccessor::Repo { repo, branch } => {
+                1 + // enum discriminant
+                4 + repo.len() + // String with u32 length prefix
+                1 + branch.as_ref().map(|b| 4 + b.len()).unwrap_or(0) // Option<String>
+            }
+            SecretAccessor::WasmHash { hash } => {
+                1 + // enum discriminant
+                4 + hash.len() // String with u32 length prefix
+            }
+            SecretAccessor::Project { project_id } => {
+                1 + // enum discriminant
+                4 + project_id.len() // String with u32 length prefix
+            }
+            SecretAccessor::System(_secret_type) => {
+                1 + // enum discriminant for System
+                1   // enum discriminant for SystemSecretType (PaymentKey = 0)
+            }
+        };
+
+        (accessor_size
+            + 4 + key.profile.len() // String with u32 length prefix
+            + 4 + key.owner.as_str().len()) as u64 // AccountId (String with u32 length prefix)
+    }
+
+    /// Approximate Borsh size of an `AccessCondition` (in bytes)
+    fn access_condition_size(access: &types::AccessCondition) -> u64 {
+        // Serialize to estimate actual size
+        let access_json = serde_json::to_string(access).unwrap_or_default();
+        (access_json.len() + 10) as u64 // JSON + Borsh overhead
+    }
 }
 
 // View methods
@@ -484,7 +759,8 @@ impl Contract {
     /// Get secrets (for keystore worker to read)
     ///
     /// For Repo accessor: if querying with a specific branch returns None,
-    /// automatically tries with branch=null to find wildcard secrets.
+    /// automatically tries with branch=null to find wildcard secrets
+    /// (see `resolve_secret_key` for the precedence).
     ///
     /// # Arguments
     /// * `accessor` - What code can access these secrets (Repo or WasmHash)
@@ -496,44 +772,72 @@ impl Contract {
         profile: String,
         owner: AccountId,
     ) -> Option<SecretProfileView> {
+        let (key, profile_data) = self.resolve_secret(accessor, profile, owner)?;
+        Some(SecretProfileView {
+            encrypted_secrets: profile_data.encrypted_secrets,
+            access: profile_data.access,
+            created_at: profile_data.created_at,
+            updated_at: profile_data.updated_at,
+            storage_deposit: U128(profile_data.storage_deposit),
+            accessor: key.accessor,
+        })
+    }
+
+    /// View — which stored secret a lookup for `(accessor, profile, owner)` uses
+    ///
+    /// Precedence, shared by `get_secrets` and `get_secret_with_vault` (and so
+    /// by the keystore when it decrypts secrets for an execution):
+    /// 1. The exact key. For a Repo accessor with a branch, a profile stored
+    ///    for that branch shadows the wildcard one.
+    /// 2. For a Repo accessor with a branch only: the wildcard
+    ///    `(repo, branch: null)` profile.
+    ///
+    /// WasmHash, Project and System accessors match exactly. Returns `None`
+    /// when neither key holds secrets.
+    pub fn resolve_secret_key(
+        &self,
+        accessor: SecretAccessor,
+        profile: String,
+        owner: AccountId,
+    ) -> Option<SecretKeyView> {
+        let (key, _) = self.resolve_secret(accessor, profile, owner)?;
+        Some(SecretKeyView {
+            accessor: key.accessor,
+            profile: key.profile,
+            owner: key.owner,
+        })
+    }
+
+    /// Stored key and profile per the `resolve_secret_key` precedence
+    fn resolve_secret(
+        &self,
+        accessor: SecretAccessor,
+        profile: String,
+        owner: AccountId,
+    ) -> Option<(SecretKey, SecretProfile)> {
         let key = SecretKey {
-            accessor: accessor.clone(),
-            profile: profile.clone(),
-            owner: owner.clone(),
+            accessor,
+            profile,
+            owner,
         };
 
         // Try with exact accessor first
         if let Some(profile_data) = self.secrets_storage.get(&key) {
-            return Some(SecretProfileView {
-                encrypted_secrets: profile_data.encrypted_secrets,
-                access: profile_data.access,
-                created_at: profile_data.created_at,
-                updated_at: profile_data.updated_at,
-                storage_deposit: U128(profile_data.storage_deposit),
-                accessor: key.accessor,
-            });
+            return Some((key, profile_data));
         }
 
         // For Repo with branch, try wildcard (branch=null)
-        if let SecretAccessor::Repo { repo, branch: Some(_) } = accessor {
+        if let SecretAccessor::Repo { repo, branch: Some(_) } = key.accessor {
             let wildcard_key = SecretKey {
                 accessor: SecretAccessor::Repo {
                     repo,
                     branch: None,
                 },
-                profile,
-                owner,
+                profile: key.profile,
+                owner: key.owner,
             };
-            if let Some(profile_data) = self.secrets_storage.get(&wildcard_key) {
-                return Some(SecretProfileView {
-                    encrypted_secrets: profile_data.encrypted_secrets,
-                    access: profile_data.access,
-                    created_at: profile_data.created_at,
-                    updated_at: profile_data.updated_at,
-                    storage_deposit: U128(profile_data.storage_deposit),
-                    accessor: wildcard_key.accessor,
-                });
-            }
+            let profile_data = self.secrets_storage.get(&wildcard_key)?;
+            return Some((wildcard_key, profile_data));
         }
 
         None
@@ -647,16 +951,31 @@ impl Contract {
         self.secrets_storage.get(&key).is_some()
     }
 
-    /// List all secrets for a user
+    /// List secrets for a user, without the encrypted payload
     ///
-    /// Returns array of secret metadata with accessor, profile info
-    pub fn list_user_secrets(&self, account_id: AccountId) -> Vec<UserSecretInfo> {
+    /// Returns array of secret metadata with accessor, profile info.
+    /// Pages follow the user's secret index (see `user_secrets_count`); without
+    /// `limit`, everything from `from_index` on is returned.
+    ///
+    /// # Arguments
+    /// * `account_id` - Account that owns the secrets
+    /// * `from_index` - Starting index (default: 0)
+    /// * `limit` - Maximum number of secrets to return (default: all)
+    pub fn list_user_secrets(
+        &self,
+        account_id: AccountId,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<UserSecretInfo> {
         let user_secrets = self.user_secrets_index.get(&account_id);
 
         match user_secrets {
             Some(secrets_set) => {
-                secrets_set
-                    .iter()
+                let keys = secrets_set.as_vector();
+                let from = from_index.unwrap_or(0).min(keys.len());
+                let to = limit.map_or(keys.len(), |limit| from.saturating_add(limit).min(keys.len()));
+                (from..to)
+                    .filter_map(|index| keys.get(index))
                     .filter_map(|key| {
                         self.secrets_storage.get(&key).map(|profile| UserSecretInfo {
                             accessor: key.accessor.clone(),
@@ -672,6 +991,99 @@ impl Contract {
             None => vec![],
         }
     }
+
+    /// Number of secrets stored by a user (the length `list_user_secrets` pages over)
+    pub fn user_secrets_count(&self, account_id: AccountId) -> u64 {
+        self.user_secrets_index
+            .get(&account_id)
+            .map_or(0, |secrets_set| secrets_set.len())
+    }
+
+    /// List retained versions of a secret profile, oldest first
+    ///
+    /// Empty for profiles never written with `set_secrets`.
+    ///
+    /// # Arguments
+    /// * `accessor` - What code can access these secrets
+    /// * `profile` - Profile name
+    /// * `owner` - Account that owns the secrets
+    pub fn list_secret_versions(
+        &self,
+        accessor: SecretAccessor,
+        profile: String,
+        owner: AccountId,
+    ) -> Vec<SecretVersionView> {
+        let key = SecretKey {
+            accessor,
+            profile,
+            owner,
+        };
+        let Some(history) = self.secret_version_history.get(&key) else {
+            return vec![];
+        };
+
+        history
+            .retained
+            .iter()
+            .filter_map(|&version| {
+                self.secret_versions
+                    .get(&SecretVersionKey { secret: key.clone(), version })
+                    .map(|entry| SecretVersionView {
+                        version,
+                        created_at: entry.created_at,
+                        storage_deposit: U128(entry.storage_deposit),
+                        active: history.active == Some(version),
+                    })
+            })
+            .collect()
+    }
+
+    /// Get a retained secret version (for keystore worker to read pinned
+    /// `SecretsReference.version`)
+    ///
+    /// Same wildcard fallback as `get_secrets`: for a Repo accessor with a
+    /// branch, the branch=null profile is used if the exact one has no
+    /// version history.
+    pub fn get_secret_version(
+        &self,
+        accessor: SecretAccessor,
+        profile: String,
+        owner: AccountId,
+        version: u32,
+    ) -> Option<SecretProfileView> {
+        let mut key = SecretKey {
+            accessor,
+            profile,
+            owner,
+        };
+
+        if self.secret_version_history.get(&key).is_none() {
+            match &key.accessor {
+                SecretAccessor::Repo { repo, branch: Some(_) } => {
+                    key.accessor = SecretAccessor::Repo {
+                        repo: repo.clone(),
+                        branch: None,
+                    };
+                }
+                _ => return None,
+            }
+        }
+
+        let entry = self.secret_versions.get(&SecretVersionKey { secret: key.clone(), version })?;
+        Some(SecretProfileView {
+            encrypted_secrets: entry.encrypted_secrets,
+            access: entry.access,
+            created_at: entry.created_at,
+            updated_at: entry.created_at,
+            storage_deposit: U128(entry.storage_deposit),
+            accessor: key.accessor,
+        })
+    }
+
+    /// Number of versions `set_secrets` retains per profile
+    pub fn get_max_secret_versions(&self) -> u32 {
+        self.max_secret_versions
+    }
 }
 
 /// Combined response for [`Contract::get_secret_with_vault`]. Returning
@@ -1378,8 +1790,10 @@ mod tests {
         );
 
         // List user secrets
-        let secrets = contract.list_user_secrets(user.clone());
+        let secrets = contract.list_user_secrets(user.clone(), None, None);
         assert_eq!(secrets.len(), 2);
+        assert_eq!(contract.user_secrets_count(user.clone()), 2);
+        assert_eq!(contract.user_secrets_count(accounts(3)), 0);
 
         // Verify we have both types
         let has_repo = secrets.iter().any(|s| matches!(&s.accessor, SecretAccessor::Repo { .. }));
@@ -1387,4 +1801,133 @@ mod tests {
         assert!(has_repo);
         assert!(has_wasm);
     }
+
+    #[test]
+    fn test_list_user_secrets_pagination() {
+        let owner = accounts(0);
+        let user = accounts(2);
+        testing_env!(get_context(owner.clone(), NearToken::from_near(0)).build());
+        let mut contract = Contract::new(owner, None, None, None);
+
+        testing_env!(get_context(user.clone(), NearToken::from_near(1)).build());
+        for profile in ["alpha", "beta", "gamma"] {
+            contract.store_secrets(
+                SecretAccessor::Repo {
+                    repo: "github.com/alice/project".to_string(),
+                    branch: None,
+                },
+                profile.to_string(),
+                "base64encodeddata".to_string(),
+                types::AccessCondition::AllowAll,
+                None,
+            );
+        }
+
+        let profiles = |from: Option<u64>, limit: Option<u64>| -> Vec<String> {
+            contract
+                .list_user_secrets(user.clone(), from, limit)
+                .into_iter()
+                .map(|info| info.profile)
+                .collect()
+        };
+        assert_eq!(profiles(None, None), vec!["alpha", "beta", "gamma"]);
+        assert_eq!(profiles(Some(0), Some(2)), vec!["alpha", "beta"]);
+        assert_eq!(profiles(Some(2), Some(2)), vec!["gamma"]);
+        assert_eq!(profiles(Some(1), None), vec!["beta", "gamma"]);
+        assert!(profiles(Some(5), Some(2)).is_empty());
+        assert_eq!(profiles(Some(1), Some(u64::MAX)), vec!["beta", "gamma"]);
+    }
+
+    #[test]
+    fn test_branch_secret_shadows_wildcard() {
+        let owner = accounts(0);
+        let user = accounts(2);
+        testing_env!(get_context(owner.clone(), NearToken::from_near(0)).build());
+        let mut contract = Contract::new(owner, None, None, None);
+
+        let repo = |branch: Option<&str>| SecretAccessor::Repo {
+            repo: "github.com/alice/project".to_string(),
+            branch: branch.map(str::to_string),
+        };
+        testing_env!(get_context(user.clone(), NearToken::from_near(1)).build());
+        for (branch, data) in [(None, "wildcard"), (Some("main"), "main-only")] {
+            contract.store_secrets(
+                repo(branch),
+                "default".to_string(),
+                data.to_string(),
+                types::AccessCondition::AllowAll,
+                None,
+            );
+        }
+
+        // The branch-specific profile wins over the wildcard
+        let resolved = contract
+            .resolve_secret_key(repo(Some("main")), "default".to_string(), user.clone())
+            .unwrap();
+        assert_eq!(resolved.accessor, repo(Some("main")));
+        let secrets = contract
+            .get_secrets(repo(Some("main")), "default".to_string(), user.clone())
+            .unwrap();
+        assert_eq!(secrets.encrypted_secrets, "main-only");
+        assert_eq!(secrets.accessor, repo(Some("main")));
+
+        // Querying the wildcard itself never picks up a branch profile
+        let secrets = contract
+            .get_secrets(repo(None), "default".to_string(), user.clone())
+            .unwrap();
+        assert_eq!(secrets.encrypted_secrets, "wildcard");
+    }
+
+    #[test]
+    fn test_missing_branch_falls_back_to_wildcard() {
+        let owner = accounts(0);
+        let user = accounts(2);
+        testing_env!(get_context(owner.clone(), NearToken::from_near(0)).build());
+        let mut contract = Contract::new(owner, None, None, None);
+
+        let repo = |branch: Option<&str>| SecretAccessor::Repo {
+            repo: "github.com/alice/project".to_string(),
+            branch: branch.map(str::to_string),
+        };
+        testing_env!(get_context(user.clone(), NearToken::from_near(1)).build());
+        contract.store_secrets(
+            repo(None),
+            "default".to_string(),
+            "wildcard".to_string(),
+            types::AccessCondition::AllowAll,
+            None,
+        );
+
+        let resolved = contract
+            .resolve_secret_key(repo(Some("feature-x")), "default".to_string(), user.clone())
+            .unwrap();
+        assert_eq!(
+            resolved,
+            SecretKeyView {
+                accessor: repo(None),
+                profile: "default".to_string(),
+                owner: user.clone(),
+            }
+        );
+        let secrets = contract
+            .get_secrets(repo(Some("feature-x")), "default".to_string(), user.clone())
+            .unwrap();
+        assert_eq!(secrets.encrypted_secrets, "wildcard");
+        assert_eq!(secrets.accessor, repo(None));
+
+        // No fallback across profiles, owners or other accessor kinds
+        assert!(contract
+            .resolve_secret_key(repo(Some("feature-x")), "prod".to_string(), user.clone())
+            .is_none());
+        assert!(contract
+            .resolve_secret_key(repo(Some("feature-x")), "default".to_string(), accounts(3))
+            .is_none());
+        assert!(contract
+            .resolve_secret_key(
+                SecretAccessor::WasmHash { hash: "a".repeat(64) },
+                "default".to_string(),
+                user,
+            )
+            .is_none());
+    }
 }
@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod tests {
     use crate::tests::{get_context, setup_contract};
+    use crate::execution::is_valid_content_type;
     use crate::EXECUTION_TIMEOUT;
     use crate::*;
     use near_sdk::test_utils::accounts;
@@ -16,6 +17,8 @@ mod tests {
             repo: "https://github.com/test/repo".to_string(),
             commit: "abc123".to_string(),
             build_target: Some("wasm32-wasi".to_string()),
+            package: None,
+            features: Vec::new(),
         };
         ExecutionRequest {
             request_id,
@@ -25,6 +28,8 @@ mod tests {
                 repo: "https://github.com/test/repo".to_string(),
                 commit: "abc123".to_string(),
                 build_target: Some("wasm32-wasi".to_string()),
+                package: None,
+                features: Vec::new(),
             },
             resolved_source: code_source,
             resource_limits: ResourceLimits::default(),
@@ -32,11 +37,18 @@ mod tests {
             timestamp,
             secrets_ref: None,
             input_data: None,
+            input_content_type: None,
             response_format: ResponseFormat::default(),
             payer_account_id: sender,
             pending_output: None,
             output_submitted: false,
             attached_usd: 0,
+            refund_account_id: None,
+            stale_timeout_seconds: None,
+            output_submitted_at: None,
+            resolved_at: None,
+            priority: 0,
+            pricing_snapshot: Contract::new(accounts(0), None, None, None).current_pricing(),
         }
     }
 
@@ -45,6 +57,8 @@ mod tests {
             repo: "https://github.com/test/repo".to_string(),
             commit: "main".to_string(),
             build_target: Some("wasm32-wasi".to_string()),
+            package: None,
+            features: Vec::new(),
         }
     }
 
@@ -144,6 +158,23 @@ mod tests {
         assert_eq!(request.response_format, ResponseFormat::Bytes);
     }
 
+    #[test]
+    fn test_response_format_binary_encodings() {
+        let mut contract = setup_contract();
+        let source = test_execution_source();
+
+        let context = get_context(accounts(1), NearToken::from_near(1));
+        testing_env!(context.build());
+
+        contract.request_execution(source.clone(), None, None, None, Some(ResponseFormat::Cbor), None, None);
+        contract.request_execution(source.clone(), None, None, None, Some(ResponseFormat::MessagePack), None, None);
+        contract.request_execution(source.clone(), None, None, None, Some(ResponseFormat::Base64), None, None);
+
+        assert_eq!(contract.get_request(0).unwrap().response_format, ResponseFormat::Cbor);
+        assert_eq!(contract.get_request(1).unwrap().response_format, ResponseFormat::MessagePack);
+        assert_eq!(contract.get_request(2).unwrap().response_format, ResponseFormat::Base64);
+    }
+
     #[test]
     #[should_panic(expected = "Execution is not yet stale, please wait")]
     fn test_cancel_stale_execution_not_stale() {
@@ -178,6 +209,349 @@ mod tests {
         contract.cancel_stale_execution(999);
     }
 
+    /// Receiver of the single refund transfer created by the last call
+    fn refund_receiver() -> AccountId {
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        assert_eq!(receipts.len(), 1, "Expected exactly one refund receipt");
+        receipts[0].receiver_id.clone()
+    }
+
+    #[test]
+    fn test_cancel_stale_execution_refunds_payer_by_default() {
+        let mut contract = setup_contract();
+        let sender = accounts(3);
+        let initial_timestamp = env::block_timestamp();
+
+        let execution_request = test_execution_request(0, sender.clone(), initial_timestamp);
+        assert_eq!(execution_request.refund_recipient(), &sender);
+        contract.pending_requests.insert(&0, &execution_request);
+
+        let mut context = get_context(sender.clone(), NearToken::from_near(0));
+        context.block_timestamp(initial_timestamp + EXECUTION_TIMEOUT + 1);
+        testing_env!(context.build());
+
+        contract.cancel_stale_execution(0);
+
+        assert_eq!(refund_receiver(), sender);
+    }
+
+    #[test]
+    fn test_cancel_stale_execution_refunds_refund_account() {
+        let mut contract = setup_contract();
+        let sender = accounts(3);
+        let treasury = accounts(5);
+        let initial_timestamp = env::block_timestamp();
+
+        let mut execution_request = test_execution_request(0, sender.clone(), initial_timestamp);
+        execution_request.refund_account_id = Some(treasury.clone());
+        contract.pending_requests.insert(&0, &execution_request);
+
+        let mut context = get_context(sender, NearToken::from_near(0));
+        context.block_timestamp(initial_timestamp + EXECUTION_TIMEOUT + 1);
+        testing_env!(context.build());
+
+        contract.cancel_stale_execution(0);
+
+        assert_eq!(refund_receiver(), treasury);
+    }
+
+    #[test]
+    fn test_emergency_cancel_refunds_refund_account() {
+        let mut contract = setup_contract();
+        let sender = accounts(3);
+        let treasury = accounts(5);
+
+        let mut execution_request = test_execution_request(0, sender, env::block_timestamp());
+        execution_request.refund_account_id = Some(treasury.clone());
+        contract.pending_requests.insert(&0, &execution_request);
+
+        let context = get_context(accounts(0), NearToken::from_near(0));
+        testing_env!(context.build());
+
+        contract.emergency_cancel_execution(0);
+
+        assert_eq!(refund_receiver(), treasury);
+    }
+
+    #[test]
+    fn test_request_execution_stores_refund_account() {
+        let mut contract = setup_contract();
+        let sender = accounts(3);
+        let treasury = accounts(5);
+
+        let context = get_context(sender.clone(), NearToken::from_near(1));
+        testing_env!(context.build());
+
+        contract.request_execution(
+            test_execution_source(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(RequestParams {
+                refund_account_id: Some(treasury.clone()),
+                ..Default::default()
+            }),
+        );
+
+        let request = contract.get_request(0).expect("Request should exist");
+        assert_eq!(request.payer_account_id, sender);
+        assert_eq!(request.refund_account_id, Some(treasury.clone()));
+        assert_eq!(request.refund_recipient(), &treasury);
+    }
+
+    fn request_with_stale_timeout(contract: &mut Contract, sender: AccountId, stale_timeout_seconds: u64) {
+        let context = get_context(sender, NearToken::from_near(1));
+        testing_env!(context.build());
+        contract.request_execution(
+            test_execution_source(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(RequestParams {
+                stale_timeout_seconds: Some(stale_timeout_seconds),
+                ..Default::default()
+            }),
+        );
+    }
+
+    #[test]
+    fn test_cancel_stale_execution_short_override() {
+        let mut contract = setup_contract();
+        let sender = accounts(3);
+        request_with_stale_timeout(&mut contract, sender.clone(), MIN_STALE_TIMEOUT_SECONDS);
+        let request = contract.get_request(0).expect("Request should exist");
+        assert_eq!(request.stale_timeout_seconds, Some(MIN_STALE_TIMEOUT_SECONDS));
+
+        // Well before the global EXECUTION_TIMEOUT, but past the override
+        let mut context = get_context(sender, NearToken::from_near(0));
+        context.block_timestamp(request.timestamp + MIN_STALE_TIMEOUT_SECONDS * 1_000_000_000 + 1);
+        testing_env!(context.build());
+        contract.cancel_stale_execution(0);
+
+        assert!(contract.get_request(0).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Execution is not yet stale, please wait")]
+    fn test_cancel_stale_execution_short_override_not_yet_stale() {
+        let mut contract = setup_contract();
+        let sender = accounts(3);
+        request_with_stale_timeout(&mut contract, sender.clone(), 120);
+        let request = contract.get_request(0).expect("Request should exist");
+
+        let mut context = get_context(sender, NearToken::from_near(0));
+        context.block_timestamp(request.timestamp + 120 * 1_000_000_000);
+        testing_env!(context.build());
+        contract.cancel_stale_execution(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "stale_timeout_seconds 59 must be between 60 and 600 seconds")]
+    fn test_request_execution_stale_timeout_below_minimum() {
+        let mut contract = setup_contract();
+        request_with_stale_timeout(&mut contract, accounts(3), MIN_STALE_TIMEOUT_SECONDS - 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "stale_timeout_seconds 601 must be between 60 and 600 seconds")]
+    fn test_request_execution_stale_timeout_above_global() {
+        let mut contract = setup_contract();
+        request_with_stale_timeout(&mut contract, accounts(3), EXECUTION_TIMEOUT / 1_000_000_000 + 1);
+    }
+
+    /// Pending request 0 from accounts(3), paid for by accounts(5)
+    fn insert_request_paid_by_other(contract: &mut Contract, timestamp: u64) -> (AccountId, AccountId) {
+        let (sender, payer) = (accounts(3), accounts(5));
+        let mut execution_request = test_execution_request(0, sender.clone(), timestamp);
+        execution_request.payer_account_id = payer.clone();
+        contract.insert_pending_request(0, &execution_request);
+        (sender, payer)
+    }
+
+    #[test]
+    fn test_cancel_execution_by_payer_after_timeout() {
+        let mut contract = setup_contract();
+        let initial_timestamp = env::block_timestamp();
+        let (sender, payer) = insert_request_paid_by_other(&mut contract, initial_timestamp);
+
+        let mut context = get_context(payer.clone(), NearToken::from_near(0));
+        context.block_timestamp(initial_timestamp + EXECUTION_TIMEOUT + 1);
+        testing_env!(context.build());
+        contract.cancel_execution(0);
+
+        assert!(contract.get_request(0).is_none());
+        assert_eq!(contract.get_pending_capacity().0, 0);
+        assert_eq!(refund_receiver(), payer);
+
+        let events = events_named("execution_cancelled");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["request_id"], 0);
+        assert_eq!(events[0]["sender_id"], sender.as_str());
+        assert_eq!(events[0]["cancelled_by"], payer.as_str());
+        assert_eq!(events[0]["refund_recipient"], payer.as_str());
+        assert_eq!(events[0]["payment_refunded"], "100000000000000000000000");
+        assert_eq!(
+            timeline_kinds(&contract, 0),
+            vec![TimelineEventKind::Submitted, TimelineEventKind::Cancelled]
+        );
+    }
+
+    #[test]
+    fn test_cancel_execution_by_sender_after_timeout() {
+        let mut contract = setup_contract();
+        let initial_timestamp = env::block_timestamp();
+        let (sender, payer) = insert_request_paid_by_other(&mut contract, initial_timestamp);
+
+        let mut context = get_context(sender, NearToken::from_near(0));
+        context.block_timestamp(initial_timestamp + EXECUTION_TIMEOUT + 1);
+        testing_env!(context.build());
+        contract.cancel_execution(0);
+
+        assert!(contract.get_request(0).is_none());
+        assert_eq!(refund_receiver(), payer);
+    }
+
+    #[test]
+    #[should_panic(expected = "Execution is not yet stale, please wait")]
+    fn test_cancel_execution_before_timeout() {
+        let mut contract = setup_contract();
+        let initial_timestamp = env::block_timestamp();
+        let (_, payer) = insert_request_paid_by_other(&mut contract, initial_timestamp);
+
+        let mut context = get_context(payer, NearToken::from_near(0));
+        context.block_timestamp(initial_timestamp + EXECUTION_TIMEOUT);
+        testing_env!(context.build());
+        contract.cancel_execution(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the sender or payer can cancel this execution")]
+    fn test_cancel_execution_unauthorized() {
+        let mut contract = setup_contract();
+        let initial_timestamp = env::block_timestamp();
+        insert_request_paid_by_other(&mut contract, initial_timestamp);
+
+        let mut context = get_context(accounts(4), NearToken::from_near(0));
+        context.block_timestamp(initial_timestamp + EXECUTION_TIMEOUT + 1);
+        testing_env!(context.build());
+        contract.cancel_execution(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Execution already resolved, its result is being processed")]
+    fn test_cancel_execution_after_resolution() {
+        let mut contract = setup_contract();
+        let initial_timestamp = env::block_timestamp();
+        let (_, payer) = insert_request_paid_by_other(&mut contract, initial_timestamp);
+        let mut request = contract.get_request(0).unwrap();
+        request.resolved_at = Some(initial_timestamp + 1);
+        contract.pending_requests.insert(&0, &request);
+
+        let mut context = get_context(payer, NearToken::from_near(0));
+        context.block_timestamp(initial_timestamp + EXECUTION_TIMEOUT + 1);
+        testing_env!(context.build());
+        contract.cancel_execution(0);
+    }
+
+    #[test]
+    fn test_request_execution_resumes_after_resolution_frees_slot() {
+        let mut contract = setup_contract();
+        contract.set_max_pending_requests(2);
+        let sender = accounts(3);
+
+        let context = get_context(sender.clone(), NearToken::from_near(1));
+        testing_env!(context.build());
+        contract.request_execution(test_execution_source(), None, None, None, None, None, None);
+        contract.request_execution(test_execution_source(), None, None, None, None, None, None);
+        assert_eq!(contract.get_pending_capacity(), (2, 2));
+        assert!(
+            near_sdk::test_utils::get_logs()
+                .iter()
+                .any(|l| l.contains("\"event\":\"pending_capacity_reached\"")),
+            "Expected pending_capacity_reached event"
+        );
+
+        // Resolving a request frees a slot
+        testing_env!(get_context(accounts(0), NearToken::from_near(0)).build());
+        let request = contract.get_request(0).expect("Request should exist");
+        contract.on_execution_response(
+            0,
+            sender.clone(),
+            request.resolved_source.clone(),
+            request.resource_limits.clone(),
+            U128(request.payment),
+            Err(near_sdk::PromiseError::Failed),
+        );
+        assert_eq!(contract.get_pending_capacity(), (1, 2));
+
+        testing_env!(get_context(sender, NearToken::from_near(1)).build());
+        contract.request_execution(test_execution_source(), None, None, None, None, None, None);
+        assert_eq!(contract.get_pending_capacity(), (2, 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "service at capacity, try later")]
+    fn test_request_execution_at_capacity_panics() {
+        let mut contract = setup_contract();
+        contract.set_max_pending_requests(1);
+
+        let context = get_context(accounts(3), NearToken::from_near(1));
+        testing_env!(context.build());
+        contract.request_execution(test_execution_source(), None, None, None, None, None, None);
+        contract.request_execution(test_execution_source(), None, None, None, None, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "sender has 2 pending requests, the maximum per account; try later")]
+    fn test_request_execution_per_account_limit_panics() {
+        let mut contract = setup_contract();
+        contract.set_max_pending_per_account(2);
+
+        testing_env!(get_context(accounts(3), NearToken::from_near(1)).build());
+        contract.request_execution(test_execution_source(), None, None, None, None, None, None);
+        contract.request_execution(test_execution_source(), None, None, None, None, None, None);
+
+        // Another sender still gets in
+        testing_env!(get_context(accounts(4), NearToken::from_near(1)).build());
+        contract.request_execution(test_execution_source(), None, None, None, None, None, None);
+        assert_eq!(contract.get_account_pending_capacity(accounts(3)), (2, 2));
+        assert_eq!(contract.get_pending_capacity().0, 3);
+
+        testing_env!(get_context(accounts(3), NearToken::from_near(1)).build());
+        contract.request_execution(test_execution_source(), None, None, None, None, None, None);
+    }
+
+    #[test]
+    fn test_pending_counters_seeded_from_pending_requests() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(3), NearToken::from_near(1)).build());
+        for _ in 0..3 {
+            contract.request_execution(test_execution_source(), None, None, None, None, None, None);
+        }
+        contract.remove_pending_request(1);
+
+        // As after migrate(): requests are stored but uncounted, and the scan starts at id 1
+        contract.pending_requests_count = 0;
+        contract.pending_by_account.remove(&accounts(3));
+        contract.pending_by_priority.remove(&0);
+        contract.pending_counted_from = 1;
+        contract.seed_pending_counters();
+        assert_eq!(contract.get_pending_capacity().0, 1);
+        assert_eq!(contract.get_account_pending_capacity(accounts(3)).0, 1);
+        assert_eq!(contract.get_queue_depth_by_priority()[0], 1);
+
+        // Request 0 was below the scan: removing it leaves the counters alone
+        contract.remove_pending_request(0);
+        assert_eq!(contract.get_pending_capacity().0, 1);
+        contract.remove_pending_request(2);
+        assert_eq!(contract.get_pending_capacity().0, 0);
+        assert_eq!(contract.get_account_pending_capacity(accounts(3)).0, 0);
+    }
+
     #[test]
     fn test_calculate_cost() {
         let contract = setup_contract();
@@ -186,9 +560,11 @@ mod tests {
             instructions: 10_000_000, // 10M instructions
             time_ms: 5000,            // 5000 ms (5 seconds)
             compile_time_ms: None,    // No compilation
+            output_bytes: 0,
+            peak_memory_bytes: 0,
         };
 
-        let cost = contract.calculate_cost(&metrics);
+        let cost = contract.current_pricing().calculate_cost(&metrics, 0);
 
         // Expected:
         // base_fee: 10_000_000_000_000_000_000_000
@@ -207,13 +583,33 @@ mod tests {
             instructions: 10_000_000, // 10M instructions
             time_ms: 5000,            // 5000 ms (5 seconds) execution
             compile_time_ms: Some(3000), // 3000 ms (3 seconds) compilation
+            output_bytes: 0,
+            peak_memory_bytes: 0,
         };
 
-        let cost = contract.calculate_cost(&metrics);
+        let cost = contract.current_pricing().calculate_cost(&metrics, 0);
 
         assert_eq!(cost, 1_800_001_000_000_000_000_000);
     }
 
+    #[test]
+    fn test_calculate_cost_with_priority() {
+        let mut contract = setup_contract();
+        contract.set_priority_fee(U128(1_000_000_000_000_000_000_000));
+
+        let metrics = ResourceMetrics {
+            instructions: 10_000_000,
+            time_ms: 5000,
+            compile_time_ms: None,
+            output_bytes: 0,
+            peak_memory_bytes: 0,
+        };
+
+        // Each priority level adds priority_fee (0.001 NEAR here)
+        let cost = contract.current_pricing().calculate_cost(&metrics, 2);
+        assert_eq!(cost, contract.current_pricing().calculate_cost(&metrics, 0) + 2_000_000_000_000_000_000_000);
+    }
+
     #[test]
     fn test_calculate_cost_zero_resources() {
         let contract = setup_contract();
@@ -222,9 +618,11 @@ mod tests {
             instructions: 0,
             time_ms: 0,
             compile_time_ms: None,
+            output_bytes: 0,
+            peak_memory_bytes: 0,
         };
 
-        let cost = contract.calculate_cost(&metrics);
+        let cost = contract.current_pricing().calculate_cost(&metrics, 0);
 
         // Should only charge base_fee
         assert_eq!(cost, 1_000_000_000_000_000_000_000); // 0.001 NEAR
@@ -238,9 +636,11 @@ mod tests {
             instructions: 0,
             time_ms: 0,
             compile_time_ms: Some(10000), // 10 seconds compilation
+            output_bytes: 0,
+            peak_memory_bytes: 0,
         };
 
-        let cost = contract.calculate_cost(&metrics);
+        let cost = contract.current_pricing().calculate_cost(&metrics, 0);
 
         assert_eq!(cost, 2000_000_000_000_000_000_000); // 0.0002 NEAR
     }
@@ -276,6 +676,160 @@ mod tests {
         contract.request_execution(source, None, None, None, None, None, None);
     }
 
+    #[test]
+    fn test_request_execution_defaults_max_output_bytes() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(2), NearToken::from_near(1)).build());
+
+        let limits = ResourceLimits { max_output_bytes: None, ..Default::default() };
+        contract.request_execution(test_execution_source(), Some(limits), None, None, None, None, None);
+
+        let request = contract.get_request(0).expect("Request should exist");
+        assert_eq!(request.resource_limits.max_output_bytes, Some(DEFAULT_MAX_OUTPUT_BYTES));
+    }
+
+    #[test]
+    #[should_panic(expected = "Requested max_output_bytes 1048577 exceeds hard limit of 1048576 bytes")]
+    fn test_request_execution_max_output_bytes_over_cap() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(2), NearToken::from_near(1)).build());
+
+        let limits = ResourceLimits { max_output_bytes: Some(MAX_OUTPUT_BYTES + 1), ..Default::default() };
+        contract.request_execution(test_execution_source(), Some(limits), None, None, None, None, None);
+    }
+
+    #[test]
+    fn test_request_execution_accepts_input_at_limit() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(2), NearToken::from_near(1)).build());
+
+        let input = "x".repeat(DEFAULT_MAX_INPUT_BYTES as usize);
+        contract.request_execution(test_execution_source(), Some(ResourceLimits::default()), Some(input), None, None, None, None);
+        assert!(contract.get_request(0).is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "input_data is 16385 bytes, exceeds max_input_bytes of 16384")]
+    fn test_request_execution_input_over_limit() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(2), NearToken::from_near(1)).build());
+
+        let input = "x".repeat(DEFAULT_MAX_INPUT_BYTES as usize + 1);
+        contract.request_execution(test_execution_source(), Some(ResourceLimits::default()), Some(input), None, None, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "input_data is 101 bytes, exceeds max_input_bytes of 100")]
+    fn test_request_execution_input_over_configured_limit() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(0), NearToken::from_near(0)).build());
+        contract.set_max_input_bytes(100);
+        assert_eq!(contract.get_limits().max_input_bytes, 100);
+
+        testing_env!(get_context(accounts(2), NearToken::from_near(1)).build());
+        let input = "x".repeat(101);
+        contract.request_execution(test_execution_source(), Some(ResourceLimits::default()), Some(input), None, None, None, None);
+    }
+
+    fn input_params(content_type: &str) -> Option<RequestParams> {
+        Some(RequestParams {
+            input_content_type: Some(content_type.to_string()),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_request_execution_stores_input_content_type() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(2), NearToken::from_near(1)).build());
+
+        let params = input_params("application/json; charset=utf-8");
+        contract.request_execution(test_execution_source(), Some(ResourceLimits::default()), Some("{}".to_string()), None, None, None, params);
+        let request = contract.get_request(0).unwrap();
+        assert_eq!(request.input_content_type.as_deref(), Some("application/json; charset=utf-8"));
+
+        contract.request_execution(test_execution_source(), Some(ResourceLimits::default()), None, None, None, None, None);
+        assert_eq!(contract.get_request(1).unwrap().input_content_type, None);
+    }
+
+    #[test]
+    fn test_content_type_validation() {
+        for valid in ["text/plain", "application/octet-stream", "application/vnd.api+json", "text/csv;header=present"] {
+            assert!(is_valid_content_type(valid), "rejected {}", valid);
+        }
+        let too_long = format!("application/{}", "x".repeat(MAX_INPUT_CONTENT_TYPE_LEN));
+        for invalid in ["", "json", "/json", "text/", "text/plain\n", "text /plain", too_long.as_str()] {
+            assert!(!is_valid_content_type(invalid), "accepted {}", invalid);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "input_content_type must be a type/subtype media type")]
+    fn test_request_execution_rejects_invalid_content_type() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(2), NearToken::from_near(1)).build());
+        contract.request_execution(test_execution_source(), Some(ResourceLimits::default()), None, None, None, None, input_params("json"));
+    }
+
+    fn high_compute_limits() -> ResourceLimits {
+        ResourceLimits {
+            max_instructions: Some(MAX_INSTRUCTIONS * 2),
+            max_execution_seconds: Some(300),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_project_limits_override_raises_cap() {
+        let mut contract = setup_contract();
+        let raised = ProjectLimits { max_instructions: MAX_INSTRUCTIONS * 2, max_execution_seconds: 300 };
+        testing_env!(get_context(accounts(0), NearToken::from_near(0)).build());
+        contract.set_project_limits("https://github.com/test/repo".to_string(), Some(raised.clone()));
+
+        assert_eq!(contract.get_project_limits("https://github.com/test/repo".to_string()), raised);
+        assert_eq!(contract.get_project_limits("https://github.com/other/repo".to_string()), ProjectLimits::default());
+
+        testing_env!(get_context(accounts(2), NearToken::from_near(1)).build());
+        contract.request_execution(test_execution_source(), Some(high_compute_limits()), None, None, None, None, None);
+        let request = contract.get_request(0).expect("Request should exist");
+        assert_eq!(request.resource_limits.max_instructions, Some(MAX_INSTRUCTIONS * 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "Requested max_instructions 1000000000000 exceeds hard limit of 500000000000")]
+    fn test_project_limits_other_projects_stay_clamped() {
+        let mut contract = setup_contract();
+        let raised = ProjectLimits { max_instructions: MAX_INSTRUCTIONS * 2, max_execution_seconds: 300 };
+        testing_env!(get_context(accounts(0), NearToken::from_near(0)).build());
+        contract.set_project_limits("https://github.com/trusted/repo".to_string(), Some(raised));
+
+        testing_env!(get_context(accounts(2), NearToken::from_near(1)).build());
+        contract.request_execution(test_execution_source(), Some(high_compute_limits()), None, None, None, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requested max_execution_seconds 300 exceeds hard limit of 180 seconds")]
+    fn test_project_limits_cleared_override_restores_global_caps() {
+        let mut contract = setup_contract();
+        let raised = ProjectLimits { max_instructions: MAX_INSTRUCTIONS, max_execution_seconds: 300 };
+        testing_env!(get_context(accounts(0), NearToken::from_near(0)).build());
+        contract.set_project_limits("https://github.com/test/repo".to_string(), Some(raised));
+        contract.set_project_limits("https://github.com/test/repo".to_string(), None);
+
+        testing_env!(get_context(accounts(2), NearToken::from_near(1)).build());
+        let limits = ResourceLimits { max_execution_seconds: Some(300), ..Default::default() };
+        contract.request_execution(test_execution_source(), Some(limits), None, None, None, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "max_execution_seconds cannot exceed the 600 second execution timeout")]
+    fn test_project_limits_bounded_by_execution_timeout() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(0), NearToken::from_near(0)).build());
+        let limits = ProjectLimits { max_instructions: MAX_INSTRUCTIONS, max_execution_seconds: 601 };
+        contract.set_project_limits("alice.near/app".to_string(), Some(limits));
+    }
+
     #[test]
     fn test_submit_execution_output() {
         let mut contract = setup_contract();
@@ -335,6 +889,8 @@ mod tests {
             repo: "https://github.com/test/repo".to_string(),
             commit: "abc123".to_string(),
             build_target: Some("wasm32-wasi".to_string()),
+            package: None,
+            features: Vec::new(),
         };
         let execution_request = ExecutionRequest {
             request_id: 0,
@@ -344,6 +900,8 @@ mod tests {
                 repo: "https://github.com/test/repo".to_string(),
                 commit: "abc123".to_string(),
                 build_target: Some("wasm32-wasi".to_string()),
+                package: None,
+                features: Vec::new(),
             },
             resolved_source: code_source,
             resource_limits: ResourceLimits::default(),
@@ -351,11 +909,18 @@ mod tests {
             timestamp: env::block_timestamp(),
             secrets_ref: None,
             input_data: None,
+            input_content_type: None,
             response_format: ResponseFormat::Text,
             payer_account_id: sender.clone(),
             pending_output: Some(StoredOutput::Text("old".as_bytes().to_vec())),
             output_submitted: true,
             attached_usd: 0,
+            refund_account_id: None,
+            stale_timeout_seconds: None,
+            output_submitted_at: None,
+            resolved_at: None,
+            priority: 0,
+            pricing_snapshot: contract.current_pricing(),
         };
         contract.pending_requests.insert(&0, &execution_request);
 
@@ -378,6 +943,8 @@ mod tests {
             repo: "https://github.com/test/repo".to_string(),
             commit: "abc123".to_string(),
             build_target: Some("wasm32-wasi".to_string()),
+            package: None,
+            features: Vec::new(),
         };
         let execution_request = ExecutionRequest {
             request_id: 0,
@@ -387,6 +954,8 @@ mod tests {
                 repo: "https://github.com/test/repo".to_string(),
                 commit: "abc123".to_string(),
                 build_target: Some("wasm32-wasi".to_string()),
+                package: None,
+                features: Vec::new(),
             },
             resolved_source: code_source,
             resource_limits: ResourceLimits::default(),
@@ -394,11 +963,18 @@ mod tests {
             timestamp: env::block_timestamp(),
             secrets_ref: None,
             input_data: None,
+            input_content_type: None,
             response_format: ResponseFormat::Text,
             payer_account_id: sender.clone(),
             pending_output: Some(StoredOutput::Text(large_text.as_bytes().to_vec())),
             output_submitted: true,
             attached_usd: 0,
+            refund_account_id: None,
+            stale_timeout_seconds: None,
+            output_submitted_at: None,
+            resolved_at: None,
+            priority: 0,
+            pricing_snapshot: contract.current_pricing(),
         };
         contract.pending_requests.insert(&0, &execution_request);
 
@@ -410,13 +986,18 @@ mod tests {
             success: true,
             output: None, // Output will be taken from pending_output
             error: None,
+            error_structured: None,
             resources_used: ResourceMetrics {
                 instructions: 1_000_000,
                 time_ms: 100,
                 compile_time_ms: None,
+                output_bytes: 0,
+                peak_memory_bytes: 0,
             },
             compilation_note: None,
             refund_usd: None,
+            result_signature: None,
+            declared_output_format: None,
         };
 
         // This would normally call promise_yield_resume, which we can't test directly
@@ -465,6 +1046,81 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_stored_output_conversion_cbor_and_msgpack() {
+        // {"a": 1} in each encoding
+        let cbor = vec![0xa1, 0x61, b'a', 0x01];
+        let stored: StoredOutput = ExecutionOutput::Cbor(cbor.clone()).into();
+        match ExecutionOutput::from(stored) {
+            ExecutionOutput::Cbor(b) => assert_eq!(b, cbor),
+            _ => panic!("Wrong type"),
+        }
+
+        let msgpack = vec![0x81, 0xa1, b'a', 0x01];
+        let stored: StoredOutput = ExecutionOutput::MessagePack(msgpack.clone()).into();
+        match ExecutionOutput::from(stored) {
+            ExecutionOutput::MessagePack(b) => assert_eq!(b, msgpack),
+            _ => panic!("Wrong type"),
+        }
+    }
+
+    #[test]
+    fn test_v7_pending_request_still_loads() {
+        use crate::migration::{ExecutionRequestV7, ExecutionSourceV7, ResourceLimitsV7, SecretsReferenceV7};
+        use near_sdk::borsh::BorshDeserialize;
+
+        let mut contract = setup_contract();
+        let v7_rates = PricingSnapshot { base_fee: 7, ..contract.current_pricing() };
+        crate::migration::store_legacy_pricing(&v7_rates);
+
+        let v7 = ExecutionRequestV7 {
+            request_id: 3,
+            data_id: [9; 32],
+            sender_id: accounts(2),
+            execution_source: ExecutionSourceV7::Project { project_id: "alice.near/app".to_string(), version_key: None },
+            resolved_source: CodeSource::WasmUrl { url: "https://x/a.wasm".to_string(), hash: "ab".to_string(), build_target: None },
+            resource_limits: ResourceLimitsV7 { max_instructions: Some(5), max_memory_mb: Some(64), max_execution_seconds: Some(10) },
+            payment: 1_000,
+            timestamp: 42,
+            secrets_ref: Some(SecretsReferenceV7 { profile: "default".to_string(), account_id: accounts(2) }),
+            response_format: ResponseFormat::Json,
+            input_data: Some("{}".to_string()),
+            payer_account_id: accounts(2),
+            attached_usd: 0,
+            pending_output: None,
+            output_submitted: false,
+        };
+        let bytes = near_sdk::borsh::to_vec(&v7).unwrap();
+
+        let request = ExecutionRequest::try_from_slice(&bytes).expect("v7 layout must decode");
+        assert_eq!(request.request_id, 3);
+        assert_eq!(request.resource_limits.max_memory_mb, Some(64));
+        assert_eq!(request.resource_limits.max_output_bytes, None);
+        assert_eq!(request.secrets_ref.as_ref().unwrap().version, None);
+        assert_eq!(request.priority, 0);
+        assert_eq!(request.pricing_snapshot, v7_rates);
+
+        // Stored back in the current layout, which decodes without the fallback
+        contract.pending_requests.insert(&3, &request);
+        assert_eq!(contract.get_request(3).unwrap().pricing_snapshot, v7_rates);
+        let current = near_sdk::borsh::to_vec(&request).unwrap();
+        assert!(ExecutionRequestV7::try_from_slice(&current).is_err());
+    }
+
+    #[test]
+    fn test_stored_output_conversion_base64() {
+        // [0x00, 0xff, 0x10] base64-encoded by the worker
+        let stored: StoredOutput = ExecutionOutput::Base64("AP8Q".to_string()).into();
+        match ExecutionOutput::from(stored) {
+            ExecutionOutput::Base64(text) => assert_eq!(text, "AP8Q"),
+            _ => panic!("Wrong type"),
+        }
+
+        // Serialized with its tag, so JSON clients know to decode
+        let json = serde_json::to_value(ExecutionOutput::Base64("AP8Q".to_string())).unwrap();
+        assert_eq!(json, serde_json::json!({"Base64": "AP8Q"}));
+    }
+
     #[test]
     fn test_submit_execution_output_and_resolve_stores_output() {
         let mut contract = setup_contract();
@@ -521,8 +1177,685 @@ mod tests {
                 instructions: 1000,
                 time_ms: 10,
                 compile_time_ms: None,
+                output_bytes: 0,
+                peak_memory_bytes: 0,
             },
             None, // compilation_note
+            None, // result_signature
+            None, // declared_output_format
+        );
+    }
+
+    fn test_response(success: bool, output: Option<ExecutionOutput>) -> ExecutionResponse {
+        ExecutionResponse {
+            success,
+            output,
+            error: if success { None } else { Some("panicked".to_string()) },
+            error_structured: None,
+            resources_used: ResourceMetrics {
+                instructions: 1_000_000,
+                time_ms: 100,
+                compile_time_ms: None,
+                output_bytes: 0,
+                peak_memory_bytes: 0,
+            },
+            compilation_note: None,
+            refund_usd: None,
+            result_signature: None,
+            declared_output_format: None,
+        }
+    }
+
+    #[test]
+    fn test_resolve_batch_mixed_valid_and_invalid() {
+        let mut contract = setup_contract();
+        let sender = accounts(3);
+        let now = env::block_timestamp();
+        for (id, data_id) in [(0u64, [1u8; 32]), (1, [2; 32]), (2, [3; 32]), (3, [4; 32])] {
+            contract
+                .pending_requests
+                .insert(&id, &test_execution_request_with_data_id(id, sender.clone(), now, data_id));
+        }
+
+        testing_env!(get_context(accounts(1), NearToken::from_near(0)).build());
+
+        let batch = vec![
+            (0, test_response(true, Some(ExecutionOutput::Text("ok".to_string())))),
+            (42, test_response(true, None)),                                          // unknown id
+            (1, test_response(false, None)),
+            (0, test_response(true, None)),                                           // duplicate
+            (2, test_response(true, Some(ExecutionOutput::Text("X".repeat(2000))))), // too large to resume
+            (3, test_response(true, None)),                                           // yield already gone
+        ];
+
+        let mut resumed = Vec::new();
+        let statuses = contract.resolve_batch_internal(batch, |data_id, payload| {
+            let response: ExecutionResponse = serde_json::from_slice(payload).unwrap();
+            resumed.push((*data_id, response.success));
+            *data_id != [4; 32]
+        });
+
+        let statuses: Vec<(u64, ResolveStatus)> = statuses.into_iter().map(|e| (e.request_id, e.status)).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                (0, ResolveStatus::Resumed),
+                (42, ResolveStatus::NotFound),
+                (1, ResolveStatus::Resumed),
+                (0, ResolveStatus::Duplicate),
+                (2, ResolveStatus::PayloadTooLarge),
+                (3, ResolveStatus::ResumeFailed),
+            ]
+        );
+        // Each valid request is resumed once with its own response
+        assert_eq!(resumed, vec![([1; 32], true), ([2; 32], false), ([4; 32], true)]);
+
+        // Requests stay pending until their callback charges/refunds them
+        assert!(contract.get_request(0).is_some());
+        assert!(contract.get_request(2).is_some());
+    }
+
+    #[test]
+    fn test_resolve_batch_callbacks_settle_per_request() {
+        let mut contract = setup_contract();
+        let sender = accounts(3);
+        let now = env::block_timestamp();
+        contract.pending_requests.insert(&0, &test_execution_request_with_data_id(0, sender.clone(), now, [1; 32]));
+        contract.pending_requests.insert(&1, &test_execution_request_with_data_id(1, sender.clone(), now, [2; 32]));
+
+        testing_env!(get_context(accounts(1), NearToken::from_near(0)).build());
+        let mut payloads = Vec::new();
+        contract.resolve_batch_internal(
+            vec![(0, test_response(true, None)), (1, test_response(false, None))],
+            |_, payload| {
+                payloads.push(serde_json::from_slice::<ExecutionResponse>(payload).unwrap());
+                true
+            },
+        );
+
+        // Deliver each resumed payload to its own callback, as the runtime would
+        testing_env!(get_context(accounts(0), NearToken::from_near(0)).build());
+        let fees_before = contract.total_fees_collected;
+        for (request_id, response) in payloads.into_iter().enumerate() {
+            let request = contract.get_request(request_id as u64).unwrap();
+            contract.on_execution_response(
+                request_id as u64,
+                sender.clone(),
+                request.resolved_source.clone(),
+                request.resource_limits.clone(),
+                U128(request.payment),
+                Ok(response),
+            );
+        }
+
+        assert!(contract.get_request(0).is_none());
+        assert!(contract.get_request(1).is_none());
+        assert!(contract.total_fees_collected > fees_before);
+        let completed = near_sdk::test_utils::get_logs()
+            .iter()
+            .filter(|l| l.contains("\"event\":\"execution_completed\""))
+            .count();
+        assert_eq!(completed, 2);
+    }
+
+    fn resolve_with_metrics(contract: &mut Contract, request_id: u64, success: bool, instructions: u64) {
+        let request = contract.get_request(request_id).unwrap();
+        let mut response = test_response(success, None);
+        response.resources_used.instructions = instructions;
+        testing_env!(get_context(accounts(0), NearToken::from_near(0)).build());
+        contract.on_execution_response(
+            request_id,
+            request.sender_id.clone(),
+            request.resolved_source.clone(),
+            request.resource_limits.clone(),
+            U128(request.payment),
+            Ok(response),
+        );
+    }
+
+    fn events_named(name: &str) -> Vec<serde_json::Value> {
+        near_sdk::test_utils::get_logs()
+            .iter()
+            .filter_map(|l| l.strip_prefix("EVENT_JSON:"))
+            .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap())
+            .filter(|e| e["event"] == name)
+            .map(|e| e["data"][0].clone())
+            .collect()
+    }
+
+    fn refund_events() -> Vec<serde_json::Value> {
+        events_named("refund_issued")
+    }
+
+    #[test]
+    fn test_overpayment_refunded_on_resolution() {
+        let mut contract = setup_contract();
+        let sender = accounts(3);
+        let treasury = accounts(5);
+
+        // Worst-case deposit for a 1B-instruction limit, but only 10M instructions used
+        let mut request = test_execution_request(0, sender.clone(), env::block_timestamp());
+        request.payment = contract.estimate_cost(&request.resource_limits, 0);
+        request.refund_account_id = Some(treasury.clone());
+        contract.pending_requests.insert(&0, &request);
+
+        let used = ResourceMetrics {
+            instructions: 10_000_000,
+            time_ms: 100,
+            compile_time_ms: None,
+            output_bytes: 0,
+            peak_memory_bytes: 0,
+        };
+        let expected_refund = request.payment - request.pricing_snapshot.calculate_cost(&used, 0);
+        assert!(expected_refund >= MIN_REFUND_AMOUNT);
+
+        resolve_with_metrics(&mut contract, 0, true, used.instructions);
+
+        let refunds = refund_events();
+        assert_eq!(refunds.len(), 1);
+        assert_eq!(refunds[0]["request_id"], 0);
+        assert_eq!(refunds[0]["recipient"], treasury.to_string());
+        assert_eq!(refunds[0]["amount"], expected_refund.to_string());
+        assert_eq!(refunds[0]["reason"], "overpayment");
+        assert!(contract.get_request(0).is_none());
+    }
+
+    #[test]
+    fn test_refund_below_minimum_skipped() {
+        let mut contract = setup_contract();
+        let used = ResourceMetrics {
+            instructions: 10_000_000,
+            time_ms: 100,
+            compile_time_ms: None,
+            output_bytes: 0,
+            peak_memory_bytes: 0,
+        };
+
+        // Deposit exceeds the actual cost by less than MIN_REFUND_AMOUNT
+        let mut request = test_execution_request(0, accounts(3), env::block_timestamp());
+        request.payment = request.pricing_snapshot.calculate_cost(&used, 0) + MIN_REFUND_AMOUNT - 1;
+        contract.pending_requests.insert(&0, &request);
+
+        resolve_with_metrics(&mut contract, 0, true, used.instructions);
+
+        assert!(refund_events().is_empty());
+        let completed = near_sdk::test_utils::get_logs()
+            .into_iter()
+            .find(|l| l.contains("\"event\":\"execution_completed\""))
+            .expect("completion event");
+        assert!(completed.contains("\"payment_refunded\":\"0\""));
+        assert!(contract.get_request(0).is_none());
+    }
+
+    #[test]
+    fn test_failed_execution_refund_event() {
+        let mut contract = setup_contract();
+        let request = test_execution_request(0, accounts(3), env::block_timestamp());
+        contract.pending_requests.insert(&0, &request);
+
+        resolve_with_metrics(&mut contract, 0, false, 1_000_000);
+
+        let refunds = refund_events();
+        assert_eq!(refunds.len(), 1);
+        assert_eq!(refunds[0]["amount"], (request.payment - contract.base_fee).to_string());
+        assert_eq!(refunds[0]["reason"], "execution_failed");
+
+        let failed = events_named("execution_failed");
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0]["request_id"], 0);
+        assert_eq!(failed[0]["payment_charged"], contract.base_fee.to_string());
+        assert!(failed[0]["error_structured"].is_null());
+        assert!(events_named("execution_resolved").is_empty());
+    }
+
+    #[test]
+    fn test_declared_output_format_is_optional() {
+        // Older workers leave it out
+        let response: ExecutionResponse = serde_json::from_value(serde_json::json!({
+            "success": true,
+            "output": { "Text": "report" },
+            "error": null,
+            "resources_used": { "instructions": 1000, "time_ms": 10, "compile_time_ms": null, "output_bytes": 6 },
+            "compilation_note": null,
+        }))
+        .unwrap();
+        assert!(response.declared_output_format.is_none());
+        assert!(serde_json::to_value(&response).unwrap().get("declared_output_format").is_none());
+
+        let response: ExecutionResponse = serde_json::from_value(serde_json::json!({
+            "success": true,
+            "output": { "Text": "report" },
+            "error": null,
+            "resources_used": { "instructions": 1000, "time_ms": 10, "compile_time_ms": null, "output_bytes": 6 },
+            "compilation_note": null,
+            "declared_output_format": "Text",
+        }))
+        .unwrap();
+        assert_eq!(response.declared_output_format, Some(ResponseFormat::Text));
+    }
+
+    #[test]
+    fn test_structured_error_in_failed_event() {
+        let mut contract = setup_contract();
+        let request = test_execution_request(0, accounts(3), env::block_timestamp());
+        contract.pending_requests.insert(&0, &request);
+
+        // Worker payload: older workers leave error_structured out entirely
+        let mut response: ExecutionResponse = serde_json::from_value(serde_json::json!({
+            "success": false,
+            "output": null,
+            "error": "INSUFFICIENT_BALANCE: need 5 USDC",
+            "resources_used": { "instructions": 1000, "time_ms": 10, "compile_time_ms": null, "output_bytes": 0 },
+            "compilation_note": null,
+        }))
+        .unwrap();
+        assert!(response.error_structured.is_none());
+
+        let document = serde_json::json!({
+            "code": "INSUFFICIENT_BALANCE",
+            "message": "need 5 USDC",
+            "details": { "required": "5000000" },
+        });
+        response.error_structured = Some(document.clone());
+        let payload = serde_json::to_value(&response).unwrap();
+        assert_eq!(payload["error_structured"], document);
+
+        testing_env!(get_context(accounts(0), NearToken::from_near(0)).build());
+        contract.on_execution_response(
+            0,
+            request.sender_id.clone(),
+            request.resolved_source.clone(),
+            request.resource_limits.clone(),
+            U128(request.payment),
+            Ok(response),
+        );
+
+        let failed = events_named("execution_failed");
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0]["error"], "INSUFFICIENT_BALANCE: need 5 USDC");
+        assert_eq!(failed[0]["error_structured"]["code"], "INSUFFICIENT_BALANCE");
+        assert_eq!(failed[0]["error_structured"]["details"]["required"], "5000000");
+    }
+
+    #[test]
+    fn test_execution_resolved_event_billing_fields() {
+        let mut contract = setup_contract();
+        let mut request = test_execution_request(4, accounts(3), env::block_timestamp());
+        request.payment = contract.estimate_cost(&request.resource_limits, 0);
+        contract.pending_requests.insert(&4, &request);
+
+        resolve_with_metrics(&mut contract, 4, true, 25_000_000);
+
+        let resolved = events_named("execution_resolved");
+        assert_eq!(resolved.len(), 1);
+        let used = ResourceMetrics {
+            instructions: 25_000_000,
+            time_ms: 100,
+            compile_time_ms: None,
+            output_bytes: 0,
+            peak_memory_bytes: 0,
+        };
+        assert_eq!(resolved[0]["request_id"], 4);
+        assert_eq!(resolved[0]["sender_id"], accounts(3).to_string());
+        assert_eq!(resolved[0]["actual_cost"], request.pricing_snapshot.calculate_cost(&used, 0).to_string());
+        assert_eq!(resolved[0]["instructions"], 25_000_000);
+        assert_eq!(resolved[0]["time_ms"], 100);
+        assert!(events_named("execution_failed").is_empty());
+    }
+
+    #[test]
+    fn test_execution_completed_dropped_from_event_version_2() {
+        let mut contract = setup_contract();
+        assert_eq!(contract.event_version, events::EVENT_VERSION);
+        let request = test_execution_request(0, accounts(3), env::block_timestamp());
+        contract.pending_requests.insert(&0, &request);
+        resolve_with_metrics(&mut contract, 0, true, 1_000_000);
+        assert_eq!(events_named("execution_completed").len(), 1);
+
+        contract.event_version = "2.0.0".to_string();
+        let request = test_execution_request(1, accounts(3), env::block_timestamp());
+        contract.pending_requests.insert(&1, &request);
+        resolve_with_metrics(&mut contract, 1, true, 1_000_000);
+        assert!(events_named("execution_completed").is_empty());
+        let resolved = events_named("execution_resolved");
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0]["request_id"], 1);
+
+        assert!(events::emits_execution_completed("1.0.0"));
+        assert!(!events::emits_execution_completed("2.1.0"));
+    }
+
+    #[test]
+    fn test_fee_change_after_submission_bills_original_rates() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(2), NearToken::from_near(1)).build());
+        contract.request_execution(test_execution_source(), Some(ResourceLimits::default()), None, None, None, None, None);
+        let submitted = contract.get_request(0).unwrap().pricing_snapshot;
+
+        // Owner raises every NEAR rate before the worker resolves
+        testing_env!(get_context(accounts(0), NearToken::from_near(0)).build());
+        contract.set_pricing(
+            Some(U128(submitted.base_fee * 2)),
+            Some(U128(submitted.per_million_instructions_fee * 10)),
+            Some(U128(submitted.per_ms_fee * 10)),
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        assert_ne!(contract.current_pricing(), submitted);
+
+        resolve_with_metrics(&mut contract, 0, true, 25_000_000);
+
+        let used = ResourceMetrics {
+            instructions: 25_000_000,
+            time_ms: 100,
+            compile_time_ms: None,
+            output_bytes: 0,
+            peak_memory_bytes: 0,
+        };
+        let resolved = events_named("execution_resolved");
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0]["actual_cost"], submitted.calculate_cost(&used, 0).to_string());
+        assert_ne!(
+            resolved[0]["actual_cost"],
+            contract.current_pricing().calculate_cost(&used, 0).to_string()
+        );
+    }
+
+    #[test]
+    fn test_failed_refund_transfer_does_not_panic() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(0), NearToken::from_near(0)).build());
+
+        contract.on_refund_transfer(7, accounts(5), U128(MIN_REFUND_AMOUNT), Err(near_sdk::PromiseError::Failed));
+
+        let logs = near_sdk::test_utils::get_logs();
+        assert!(logs.iter().any(|l| l.contains("\"event\":\"refund_failed\"") && l.contains("\"request_id\":7")));
+    }
+
+    #[test]
+    fn test_result_signature_published_not_resumed() {
+        let mut contract = setup_contract();
+        let now = env::block_timestamp();
+        contract.pending_requests.insert(&0, &test_execution_request_with_data_id(0, accounts(3), now, [1; 32]));
+
+        testing_env!(get_context(accounts(1), NearToken::from_near(0)).build());
+        let mut response = test_response(true, Some(ExecutionOutput::Text("ok".to_string())));
+        // ml-dsa-65 signatures are several KB in string form, far above the yield budget
+        response.result_signature = Some(ResultSignature {
+            worker_public_key: "ml-dsa-65:worker".to_string(),
+            input_hash: "aa".repeat(32),
+            output_hash: "bb".repeat(32),
+            signature: format!("ml-dsa-65:{}", "S".repeat(4500)),
+        });
+
+        let mut payloads = Vec::new();
+        let statuses = contract.resolve_batch_internal(vec![(0, response)], |_, payload| {
+            payloads.push(payload.to_vec());
+            true
+        });
+
+        assert_eq!(statuses[0].status, ResolveStatus::Resumed);
+        let resumed: ExecutionResponse = serde_json::from_slice(&payloads[0]).unwrap();
+        assert!(resumed.result_signature.is_none());
+
+        let logs = near_sdk::test_utils::get_logs();
+        let signed = logs
+            .iter()
+            .find(|l| l.contains("\"event\":\"execution_result_signed\""))
+            .expect("signature event");
+        assert!(signed.contains("\"request_id\":0"));
+        assert!(signed.contains(&"bb".repeat(32)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only operator can call this")]
+    fn test_resolve_batch_unauthorized() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(5), NearToken::from_near(0)).build());
+        contract.resolve_batch(vec![(0, test_response(true, None))]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Batch too large")]
+    fn test_resolve_batch_too_large() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(1), NearToken::from_near(0)).build());
+        let batch = (0..=MAX_RESOLVE_BATCH_SIZE as u64).map(|id| (id, test_response(true, None))).collect();
+        contract.resolve_batch(batch);
+    }
+
+    fn timeline_kinds(contract: &Contract, request_id: u64) -> Vec<TimelineEventKind> {
+        contract.get_request_timeline(request_id).into_iter().map(|e| e.event).collect()
+    }
+
+    /// Resume `request_id` through resolve_batch at `resolved_at` and deliver the callback at `finished_at`
+    fn resolve_at(contract: &mut Contract, request_id: u64, response: ExecutionResponse, resolved_at: u64, finished_at: u64) {
+        testing_env!(get_context(accounts(1), NearToken::from_near(0)).block_timestamp(resolved_at).build());
+        let mut payloads = Vec::new();
+        contract.resolve_batch_internal(vec![(request_id, response)], |_, payload| {
+            payloads.push(serde_json::from_slice::<ExecutionResponse>(payload).unwrap());
+            true
+        });
+
+        let request = contract.get_request(request_id).unwrap();
+        testing_env!(get_context(accounts(0), NearToken::from_near(0)).block_timestamp(finished_at).build());
+        contract.on_execution_response(
+            request_id,
+            request.sender_id.clone(),
+            request.resolved_source.clone(),
+            request.resource_limits.clone(),
+            U128(request.payment),
+            Ok(payloads.remove(0)),
+        );
+    }
+
+    #[test]
+    fn test_request_timeline_completed() {
+        let mut contract = setup_contract();
+        contract.next_request_id = 1;
+        contract.pending_requests.insert(&0, &test_execution_request(0, accounts(3), 1_000));
+
+        testing_env!(get_context(accounts(1), NearToken::from_near(0)).block_timestamp(2_000).build());
+        contract.submit_execution_output(0, ExecutionOutput::Text("A".repeat(2000)));
+
+        let mut response = test_response(true, None);
+        response.resources_used.compile_time_ms = Some(250);
+        resolve_at(&mut contract, 0, response, 3_000, 4_000);
+
+        assert!(contract.get_request(0).is_none());
+        let timeline = contract.get_request_timeline(0);
+        let kinds: Vec<TimelineEventKind> = timeline.iter().map(|e| e.event.clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TimelineEventKind::Submitted,
+                TimelineEventKind::OutputSubmitted,
+                TimelineEventKind::Compiled,
+                TimelineEventKind::Resolved,
+                TimelineEventKind::Completed,
+            ]
         );
+        let timestamps: Vec<u64> = timeline.iter().map(|e| e.timestamp).collect();
+        assert_eq!(timestamps, vec![1_000, 2_000, 3_000, 3_000, 4_000]);
+        assert_eq!(timeline[2].details.as_deref(), Some("compile_time_ms 250"));
+        assert!(timeline[4].details.as_ref().unwrap().starts_with("charged "));
+    }
+
+    #[test]
+    fn test_request_timeline_failed_and_cancelled() {
+        let mut contract = setup_contract();
+        contract.next_request_id = 2;
+        let sender = accounts(3);
+        contract.pending_requests.insert(&0, &test_execution_request(0, sender.clone(), 1_000));
+        contract.pending_requests.insert(&1, &test_execution_request(1, sender.clone(), 1_000));
+
+        resolve_at(&mut contract, 0, test_response(false, None), 2_000, 3_000);
+        let failed = contract.get_request_timeline(0);
+        assert_eq!(
+            timeline_kinds(&contract, 0),
+            vec![TimelineEventKind::Submitted, TimelineEventKind::Resolved, TimelineEventKind::Failed]
+        );
+        assert!(failed[2].details.as_ref().unwrap().starts_with("panicked"));
+
+        let stale_at = 1_000 + EXECUTION_TIMEOUT + 1;
+        testing_env!(get_context(sender, NearToken::from_near(0)).block_timestamp(stale_at).build());
+        contract.cancel_stale_execution(1);
+        let cancelled = contract.get_request_timeline(1);
+        assert_eq!(
+            timeline_kinds(&contract, 1),
+            vec![TimelineEventKind::Submitted, TimelineEventKind::Cancelled]
+        );
+        assert_eq!(cancelled[1].timestamp, stale_at);
+    }
+
+    #[test]
+    fn test_request_timeline_pending_unknown_and_retention() {
+        let mut contract = setup_contract();
+        contract.pending_requests.insert(&0, &test_execution_request(0, accounts(3), 1_000));
+        assert_eq!(timeline_kinds(&contract, 0), vec![TimelineEventKind::Submitted]);
+        assert!(contract.get_request_timeline(7).is_empty());
+
+        // Finishing request N drops the record of request N - REQUEST_TIMELINE_RETENTION
+        let newer = REQUEST_TIMELINE_RETENTION;
+        contract.next_request_id = newer;
+        resolve_at(&mut contract, 0, test_response(true, None), 2_000, 3_000);
+        assert_eq!(timeline_kinds(&contract, 0).len(), 3);
+        contract.next_request_id = newer + 1;
+        contract.pending_requests.insert(&newer, &test_execution_request(newer, accounts(3), 1_000));
+        resolve_at(&mut contract, newer, test_response(true, None), 4_000, 5_000);
+        assert!(contract.get_request_timeline(0).is_empty());
+        assert_eq!(timeline_kinds(&contract, newer).len(), 3);
+    }
+
+    fn request_with_idempotency_key(contract: &mut Contract, sender: AccountId, key: &str, timestamp: u64) {
+        testing_env!(get_context(sender, NearToken::from_near(1)).block_timestamp(timestamp).build());
+        contract.request_execution(
+            test_execution_source(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(RequestParams {
+                idempotency_key: Some(key.to_string()),
+                ..Default::default()
+            }),
+        );
+    }
+
+    #[test]
+    fn test_idempotent_resubmission_creates_one_request() {
+        let mut contract = setup_contract();
+        let sender = accounts(3);
+        request_with_idempotency_key(&mut contract, sender.clone(), "order-42", 1_000);
+        request_with_idempotency_key(&mut contract, sender.clone(), "order-42", 2_000);
+
+        // One request, charged once; the resubmitted deposit goes straight back
+        assert_eq!(contract.next_request_id, 1);
+        assert_eq!(contract.get_pending_capacity().0, 1);
+        assert_eq!(contract.get_request(0).unwrap().payment, NearToken::from_near(1).as_yoctonear());
+        assert_eq!(refund_receiver(), sender);
+        assert!(near_sdk::test_utils::get_logs().iter().any(|log| log.contains("returning request 0")));
+        assert_eq!(
+            contract.get_request_id_by_idempotency_key(sender, "order-42".to_string()),
+            Some(0)
+        );
+
+        // Keys are scoped to the sender
+        request_with_idempotency_key(&mut contract, accounts(4), "order-42", 3_000);
+        assert_eq!(contract.next_request_id, 2);
+    }
+
+    #[test]
+    fn test_idempotency_key_expires_after_window() {
+        let mut contract = setup_contract();
+        let sender = accounts(3);
+        request_with_idempotency_key(&mut contract, sender.clone(), "order-42", 1_000);
+
+        // A finished request still answers for its key during IDEMPOTENCY_WINDOW
+        let cancelled_at = 1_000 + EXECUTION_TIMEOUT + 1;
+        testing_env!(get_context(sender.clone(), NearToken::from_near(0)).block_timestamp(cancelled_at).build());
+        contract.cancel_stale_execution(0);
+        request_with_idempotency_key(&mut contract, sender.clone(), "order-42", cancelled_at + IDEMPOTENCY_WINDOW - 1);
+        assert_eq!(contract.next_request_id, 1);
+
+        request_with_idempotency_key(&mut contract, sender.clone(), "order-42", cancelled_at + IDEMPOTENCY_WINDOW);
+        assert_eq!(contract.next_request_id, 2);
+        assert_eq!(
+            contract.get_request_id_by_idempotency_key(sender, "order-42".to_string()),
+            Some(1)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "idempotency_key must be 1 to 64 bytes")]
+    fn test_idempotency_key_too_long() {
+        let mut contract = setup_contract();
+        request_with_idempotency_key(&mut contract, accounts(3), &"k".repeat(MAX_IDEMPOTENCY_KEY_LEN + 1), 1_000);
+    }
+
+    #[test]
+    fn test_code_source_borsh_reads_pre_cargo_layout() {
+        #[derive(BorshSerialize)]
+        #[borsh(crate = "near_sdk::borsh")]
+        #[allow(dead_code)]
+        enum LegacyCodeSource {
+            GitHub { repo: String, commit: String, build_target: Option<String> },
+            WasmUrl { url: String, hash: String, build_target: Option<String> },
+        }
+
+        let legacy = near_sdk::borsh::to_vec(&LegacyCodeSource::GitHub {
+            repo: "https://github.com/test/repo".to_string(),
+            commit: "abc123".to_string(),
+            build_target: Some("wasm32-wasip1".to_string()),
+        })
+        .unwrap();
+        match CodeSource::try_from_slice(&legacy).unwrap() {
+            CodeSource::GitHub { commit, package, features, .. } => {
+                assert_eq!(commit, "abc123");
+                assert_eq!(package, None);
+                assert!(features.is_empty());
+            }
+            other => panic!("expected GitHub source, got {:?}", other),
+        }
+
+        // Sources without package/features are still written in the old layout
+        let plain = CodeSource::GitHub {
+            repo: "https://github.com/test/repo".to_string(),
+            commit: "abc123".to_string(),
+            build_target: Some("wasm32-wasip1".to_string()),
+            package: None,
+            features: Vec::new(),
+        };
+        assert_eq!(near_sdk::borsh::to_vec(&plain).unwrap(), legacy);
+    }
+
+    #[test]
+    fn test_code_source_cargo_selection_round_trips() {
+        let source = CodeSource::GitHub {
+            repo: "https://github.com/test/workspace".to_string(),
+            commit: "abc123".to_string(),
+            build_target: Some("wasm32-wasip2".to_string()),
+            package: Some("price-ark".to_string()),
+            features: vec!["coingecko".to_string(), "binance".to_string()],
+        };
+        let bytes = near_sdk::borsh::to_vec(&source).unwrap();
+        match CodeSource::try_from_slice(&bytes).unwrap() {
+            CodeSource::GitHub { package, features, .. } => {
+                assert_eq!(package.as_deref(), Some("price-ark"));
+                assert_eq!(features, vec!["coingecko", "binance"]);
+            }
+            other => panic!("expected GitHub source, got {:?}", other),
+        }
+
+        // JSON callers that don't know the new fields still parse
+        let parsed: ExecutionSource = serde_json::from_str(
+            r#"{"GitHub": {"repo": "https://github.com/test/repo", "commit": "main", "build_target": null}}"#,
+        )
+        .unwrap();
+        assert!(matches!(parsed, ExecutionSource::GitHub { package: None, ref features, .. } if features.is_empty()));
     }
 }
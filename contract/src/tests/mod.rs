@@ -64,6 +64,279 @@ mod basic_tests {
         assert_eq!(per_compile_ms.0, 100_000_000_000_000_000); // 0.0001 NEAR per second (compilation)
     }
 
+    #[test]
+    fn test_get_pricing_full_matches_fee_fields() {
+        let contract = setup_contract();
+        let pricing = contract.get_pricing_full();
+
+        assert_eq!(pricing.base_fee.0, contract.base_fee);
+        assert_eq!(pricing.per_million_instructions_fee.0, contract.per_million_instructions_fee);
+        assert_eq!(pricing.per_ms_fee.0, contract.per_ms_fee);
+        assert_eq!(pricing.per_compile_ms_fee.0, contract.per_compile_ms_fee);
+        assert_eq!(pricing.base_fee_usd.0, contract.base_fee_usd);
+        assert_eq!(pricing.per_million_instructions_fee_usd.0, contract.per_million_instructions_fee_usd);
+        assert_eq!(pricing.per_sec_fee_usd.0, contract.per_sec_fee_usd);
+        assert_eq!(pricing.per_compile_ms_fee_usd.0, contract.per_compile_ms_fee_usd);
+    }
+
+    fn github_source() -> ExecutionSource {
+        ExecutionSource::GitHub {
+            repo: "https://github.com/test/repo".to_string(),
+            commit: "main".to_string(),
+            build_target: Some("wasm32-wasi".to_string()),
+            package: None,
+            features: Vec::new(),
+        }
+    }
+
+    fn estimate_limits() -> ResourceLimits {
+        ResourceLimits {
+            max_instructions: Some(10_000_000_000),
+            max_memory_mb: Some(128),
+            max_execution_seconds: Some(5),
+            max_output_bytes: None,
+        }
+    }
+
+    #[test]
+    fn test_estimate_execution_cost_breakdown() {
+        let contract = setup_contract();
+        let pricing = contract.get_pricing_full();
+
+        let estimate = contract.estimate_execution_cost(Some(estimate_limits()), None);
+        let expected =
+            pricing.base_fee.0 + 10_000 * pricing.per_million_instructions_fee.0 + 5_000 * pricing.per_ms_fee.0;
+        assert_eq!(estimate.0, expected);
+
+        // No limits: priced at the default limits
+        assert_eq!(
+            contract.estimate_execution_cost(None, None),
+            contract.estimate_execution_cost(Some(ResourceLimits::default()), None)
+        );
+    }
+
+    #[test]
+    fn test_estimate_execution_cost_is_sufficient_deposit() {
+        let mut contract = setup_contract();
+        let estimate = contract.estimate_execution_cost(Some(estimate_limits()), None);
+
+        let context = get_context(accounts(3), NearToken::from_yoctonear(estimate.0));
+        testing_env!(context.build());
+        contract.request_execution(
+            github_source(),
+            Some(estimate_limits()),
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+
+        assert_eq!(contract.get_request(0).expect("Request should exist").payment, estimate.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient payment")]
+    fn test_deposit_below_estimate_rejected() {
+        let mut contract = setup_contract();
+        let estimate = contract.estimate_execution_cost(Some(estimate_limits()), None);
+
+        let context = get_context(accounts(3), NearToken::from_yoctonear(estimate.0 - 1));
+        testing_env!(context.build());
+        contract.request_execution(
+            github_source(),
+            Some(estimate_limits()),
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+    }
+
+    fn simulated_source() -> CodeSource {
+        CodeSource::GitHub {
+            repo: "https://github.com/test/repo.git".to_string(),
+            commit: "main".to_string(),
+            build_target: Some("wasm32-wasi".to_string()),
+            package: None,
+            features: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_simulate_execution_checks_payment_and_limits() {
+        let mut contract = setup_contract();
+        let estimate = contract.estimate_execution_cost(Some(estimate_limits()), None);
+
+        let sim = contract.simulate_execution(simulated_source(), estimate_limits(), estimate, None, None, None, None, None);
+        assert_eq!(sim, SimulationResult { accepted: true, estimated_cost: estimate, reason: None });
+
+        let sim = contract.simulate_execution(simulated_source(), estimate_limits(), U128(estimate.0 - 1), None, None, None, None, None);
+        assert!(!sim.accepted);
+        assert!(sim.reason.unwrap().starts_with("Insufficient payment"));
+
+        let too_long = ResourceLimits { max_execution_seconds: Some(MAX_EXECUTION_SECONDS + 1), ..estimate_limits() };
+        let sim = contract.simulate_execution(simulated_source(), too_long, U128(10u128.pow(27)), None, None, None, None, None);
+        assert!(sim.reason.unwrap().contains("max_execution_seconds"));
+
+        let bad_hash = CodeSource::WasmUrl { url: "https://example.com/a.wasm".to_string(), hash: "abc".to_string(), build_target: None };
+        let sim = contract.simulate_execution(bad_hash, estimate_limits(), estimate, None, None, None, None, None);
+        assert!(sim.reason.unwrap().contains("64 hex characters"));
+
+        contract.set_paused(true);
+        let sim = contract.simulate_execution(simulated_source(), estimate_limits(), estimate, None, None, None, None, None);
+        assert_eq!(sim.reason.as_deref(), Some("Contract is paused"));
+        // Nothing was submitted
+        assert_eq!(contract.next_request_id, 0);
+    }
+
+    #[test]
+    fn test_simulate_execution_checks_secrets_exist() {
+        let mut contract = setup_contract();
+        let estimate = contract.estimate_execution_cost(Some(estimate_limits()), None);
+        let secrets_ref = |profile: &str| Some(SecretsReference {
+            profile: profile.to_string(),
+            account_id: accounts(3),
+            version: None,
+        });
+
+        let sim = contract.simulate_execution(simulated_source(), estimate_limits(), estimate, secrets_ref("default"), None, None, None, None);
+        assert!(sim.reason.unwrap().starts_with("Secrets not found"));
+
+        // Stored for all branches under the normalized repo path
+        testing_env!(get_context(accounts(3), NearToken::from_near(1)).build());
+        contract.store_secrets(
+            SecretAccessor::Repo { repo: "github.com/test/repo".to_string(), branch: None },
+            "default".to_string(),
+            "ZW5jcnlwdGVk".to_string(),
+            types::AccessCondition::AllowAll,
+            None,
+        );
+
+        let sim = contract.simulate_execution(simulated_source(), estimate_limits(), estimate, secrets_ref("default"), None, None, None, None);
+        assert!(sim.accepted, "{:?}", sim.reason);
+        let sim = contract.simulate_execution(simulated_source(), estimate_limits(), estimate, secrets_ref("premium"), None, None, None, None);
+        assert!(!sim.accepted);
+    }
+
+    #[test]
+    fn test_simulate_execution_matches_request_checks() {
+        let mut contract = setup_contract();
+        let estimate = contract.estimate_execution_cost(Some(estimate_limits()), None);
+        let simulate = |contract: &Contract, limits: ResourceLimits, payment: U128, project_id: Option<&str>, input: Option<String>, priority: Option<u8>, sender: Option<AccountId>| {
+            contract.simulate_execution(simulated_source(), limits, payment, None, project_id.map(str::to_string), input, priority, sender)
+        };
+
+        // Input size is checked like request_execution does
+        let input = "x".repeat(contract.max_input_bytes as usize + 1);
+        let sim = simulate(&contract, estimate_limits(), estimate, None, Some(input), None, None);
+        assert!(sim.reason.unwrap().contains("exceeds max_input_bytes"));
+
+        // Priority is validated and priced in
+        let sim = simulate(&contract, estimate_limits(), estimate, None, None, Some(MAX_PRIORITY + 1), None);
+        assert!(sim.reason.unwrap().starts_with("priority"));
+        testing_env!(get_context(accounts(0), NearToken::from_near(0)).build());
+        contract.set_priority_fee(U128(1_000));
+        let priced = contract.estimate_execution_cost(Some(estimate_limits()), Some(2));
+        let sim = simulate(&contract, estimate_limits(), estimate, None, None, Some(2), None);
+        assert_eq!(sim.estimated_cost, priced);
+        assert!(sim.reason.unwrap().starts_with("Insufficient payment"));
+
+        // A project's cap override applies when the project is named
+        let raised = ProjectLimits { max_instructions: MAX_INSTRUCTIONS * 2, max_execution_seconds: MAX_EXECUTION_SECONDS };
+        contract.set_project_limits("alice.near/app".to_string(), Some(raised));
+        let big = ResourceLimits { max_instructions: Some(MAX_INSTRUCTIONS * 2), ..estimate_limits() };
+        let payment = U128(10u128.pow(27));
+        let sim = simulate(&contract, big.clone(), payment, None, None, None, None);
+        assert!(sim.reason.unwrap().contains("max_instructions"));
+        let sim = simulate(&contract, big, payment, Some("alice.near/app"), None, None, None);
+        assert!(sim.accepted, "{:?}", sim.reason);
+
+        // The per-account limit needs to know the sender
+        contract.set_max_pending_per_account(1);
+        contract.pending_by_account.insert(&accounts(3), &1);
+        let sim = simulate(&contract, estimate_limits(), estimate, None, None, None, Some(accounts(3)));
+        assert!(sim.reason.unwrap().starts_with("sender has 1 pending requests"));
+        let sim = simulate(&contract, estimate_limits(), estimate, None, None, None, Some(accounts(4)));
+        assert!(sim.accepted, "{:?}", sim.reason);
+    }
+
+    fn request_with_priority(contract: &mut Contract, priority: u8, deposit: u128) {
+        testing_env!(get_context(accounts(3), NearToken::from_yoctonear(deposit)).build());
+        contract.request_execution(
+            github_source(),
+            Some(estimate_limits()),
+            None,
+            None,
+            None,
+            None,
+            Some(RequestParams {
+                priority: Some(priority),
+                ..Default::default()
+            }),
+        );
+    }
+
+    #[test]
+    fn test_priority_is_charged_and_counted_per_tier() {
+        let mut contract = setup_contract();
+        contract.set_priority_fee(U128(7));
+        assert_eq!(contract.get_pricing_full().priority_fee.0, 7);
+
+        let base = contract.estimate_execution_cost(Some(estimate_limits()), None).0;
+        let estimate = contract.estimate_execution_cost(Some(estimate_limits()), Some(3)).0;
+        assert_eq!(estimate, base + 3 * 7);
+
+        request_with_priority(&mut contract, 3, estimate);
+        request_with_priority(&mut contract, 0, base);
+        assert_eq!(contract.get_request(0).unwrap().priority, 3);
+
+        let depth = contract.get_queue_depth_by_priority();
+        assert_eq!(depth.len(), MAX_PRIORITY as usize + 1);
+        assert_eq!((depth[0], depth[3]), (1, 1));
+
+        contract.remove_pending_request(0);
+        assert_eq!(contract.get_queue_depth_by_priority()[3], 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient payment")]
+    fn test_priority_needs_priority_fee_deposit() {
+        let mut contract = setup_contract();
+        contract.set_priority_fee(U128(1_000_000_000_000_000_000_000));
+        let base = contract.estimate_execution_cost(Some(estimate_limits()), None).0;
+        request_with_priority(&mut contract, 1, base);
+    }
+
+    #[test]
+    #[should_panic(expected = "priority 11 exceeds maximum of 10")]
+    fn test_priority_above_maximum_rejected() {
+        let mut contract = setup_contract();
+        request_with_priority(&mut contract, MAX_PRIORITY + 1, 10u128.pow(24));
+    }
+
+    #[test]
+    fn test_whoami() {
+        let contract = setup_contract();
+
+        let signer_pk: near_sdk::PublicKey = "ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp"
+            .parse()
+            .unwrap();
+        let mut context = get_context(accounts(2), NearToken::from_near(0));
+        context
+            .current_account_id(accounts(4))
+            .signer_account_id(accounts(3))
+            .signer_account_pk(signer_pk);
+        testing_env!(context.build());
+
+        let info = contract.whoami();
+        assert_eq!(info.predecessor, accounts(2));
+        assert_eq!(info.signer, accounts(3));
+        assert_eq!(info.signer_pk, "ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp");
+        assert_eq!(info.current, accounts(4));
+    }
+
     #[test]
     fn test_is_paused() {
         let contract = setup_contract();
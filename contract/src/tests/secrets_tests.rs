@@ -379,18 +379,33 @@ fn test_delete_refunds_exact_amount() {
         None,
     );
 
-    // Delete and check refund
-    testing_env!(context.attached_deposit(NearToken::from_yoctonear(1)).build());
-    contract.delete_secrets(
+    let stored = contract.get_secrets(
         SecretAccessor::Repo {
             repo: "github.com/test/repo".to_string(),
             branch: None,
         },
         "test".to_string(),
+        accounts(1),
+    ).unwrap();
+    assert_eq!(stored.storage_deposit.0, cost.0);
+    assert_eq!(contract.user_secrets_count(accounts(1)), 1);
+
+    // Delete and check refund
+    testing_env!(context.attached_deposit(NearToken::from_yoctonear(1)).build());
+    contract.delete_repo_secrets("github.com/test/repo".to_string(), None, "test".to_string());
+
+    // The whole deposit goes back to the owner and the index entry is gone
+    let logs = near_sdk::test_utils::get_logs();
+    assert!(
+        logs.contains(&format!("Refunded {} yoctoNEAR", cost.0)),
+        "expected a refund of the original deposit, logs: {:?}",
+        logs
     );
+    assert!(logs.iter().any(|l| l.contains("\"event\":\"secrets_deleted\"")
+        && l.contains(&format!("\"storage_refunded\":\"{}\"", cost.0))));
+    assert_eq!(contract.user_secrets_count(accounts(1)), 0);
+    assert!(contract.list_user_secrets(accounts(1), None, None).is_empty());
 
-    // Note: In real scenario, we'd check Promise transfers
-    // Here we verify the secret is gone
     let deleted = contract.get_secrets(
         SecretAccessor::Repo {
             repo: "github.com/test/repo".to_string(),
@@ -450,3 +465,170 @@ fn test_access_condition_size_affects_cost() {
     println!("Simple access cost: {}, Complex access cost: {}", cost_simple.0, cost_complex.0);
     assert!(cost_complex.0 > cost_simple.0, "Complex access condition should cost more");
 }
+
+fn versioned_accessor() -> SecretAccessor {
+    SecretAccessor::Repo {
+        repo: "github.com/test/repo".to_string(),
+        branch: None,
+    }
+}
+
+fn set_version(contract: &mut Contract, context: &mut VMContextBuilder, data: &str) {
+    testing_env!(context.attached_deposit(NearToken::from_near(1)).build());
+    contract.set_secrets(
+        versioned_accessor(),
+        "default".to_string(),
+        data.to_string(),
+        types::AccessCondition::AllowAll,
+        None,
+    );
+}
+
+#[test]
+fn test_set_secrets_retains_last_versions() {
+    let mut context = get_context(accounts(1));
+    testing_env!(context.build());
+    let mut contract = Contract::new(accounts(0), Some(accounts(0)), None, None);
+
+    for data in ["v1", "v2", "v3", "v4"] {
+        set_version(&mut contract, &mut context, data);
+    }
+
+    let versions = contract.list_secret_versions(versioned_accessor(), "default".to_string(), accounts(1));
+    let numbers: Vec<u32> = versions.iter().map(|v| v.version).collect();
+    assert_eq!(numbers, vec![2, 3, 4], "Default limit keeps the last 3 versions");
+    assert!(versions.iter().all(|v| v.storage_deposit.0 > 0), "Retained versions are charged storage");
+    assert!(versions[2].active && !versions[0].active && !versions[1].active);
+
+    let current = contract.get_secrets(versioned_accessor(), "default".to_string(), accounts(1)).unwrap();
+    assert_eq!(current.encrypted_secrets, "v4");
+    assert!(contract
+        .get_secret_version(versioned_accessor(), "default".to_string(), accounts(1), 1)
+        .is_none());
+}
+
+#[test]
+fn test_rollback_secret_restores_version() {
+    let mut context = get_context(accounts(1));
+    testing_env!(context.build());
+    let mut contract = Contract::new(accounts(0), Some(accounts(0)), None, None);
+
+    set_version(&mut contract, &mut context, "good");
+    set_version(&mut contract, &mut context, "bad");
+
+    testing_env!(context.attached_deposit(NearToken::from_yoctonear(0)).build());
+    contract.rollback_secret(versioned_accessor(), "default".to_string(), 1);
+
+    let current = contract.get_secrets(versioned_accessor(), "default".to_string(), accounts(1)).unwrap();
+    assert_eq!(current.encrypted_secrets, "good");
+    let versions = contract.list_secret_versions(versioned_accessor(), "default".to_string(), accounts(1));
+    assert!(versions[0].active && !versions[1].active);
+
+    // Pinned version stays readable; the next version continues the numbering
+    let pinned = contract
+        .get_secret_version(versioned_accessor(), "default".to_string(), accounts(1), 2)
+        .unwrap();
+    assert_eq!(pinned.encrypted_secrets, "bad");
+    set_version(&mut contract, &mut context, "fixed");
+    let versions = contract.list_secret_versions(versioned_accessor(), "default".to_string(), accounts(1));
+    assert_eq!(versions.last().unwrap().version, 3);
+}
+
+#[test]
+#[should_panic(expected = "Secret version 1 not found")]
+fn test_rollback_to_pruned_version_fails() {
+    let mut context = get_context(accounts(1));
+    testing_env!(context.build());
+    let mut contract = Contract::new(accounts(0), Some(accounts(0)), None, None);
+
+    testing_env!(context.predecessor_account_id(accounts(0)).build());
+    contract.set_max_secret_versions(1);
+
+    testing_env!(context.predecessor_account_id(accounts(1)).build());
+    set_version(&mut contract, &mut context, "v1");
+    set_version(&mut contract, &mut context, "v2");
+    contract.rollback_secret(versioned_accessor(), "default".to_string(), 1);
+}
+
+#[test]
+#[should_panic(expected = "No secret versions found")]
+fn test_rollback_secret_only_owner() {
+    let mut context = get_context(accounts(1));
+    testing_env!(context.build());
+    let mut contract = Contract::new(accounts(0), Some(accounts(0)), None, None);
+
+    set_version(&mut contract, &mut context, "v1");
+    set_version(&mut contract, &mut context, "v2");
+
+    // Another account resolves to its own (non-existent) profile
+    testing_env!(context.predecessor_account_id(accounts(2)).build());
+    contract.rollback_secret(versioned_accessor(), "default".to_string(), 1);
+}
+
+#[test]
+fn test_delete_secrets_refunds_versions() {
+    let mut context = get_context(accounts(1));
+    testing_env!(context.build());
+    let mut contract = Contract::new(accounts(0), Some(accounts(0)), None, None);
+
+    set_version(&mut contract, &mut context, "v1");
+    set_version(&mut contract, &mut context, "v2");
+
+    testing_env!(context.attached_deposit(NearToken::from_yoctonear(0)).build());
+    contract.delete_secrets(versioned_accessor(), "default".to_string());
+
+    assert!(contract
+        .list_secret_versions(versioned_accessor(), "default".to_string(), accounts(1))
+        .is_empty());
+    assert!(contract
+        .get_secret_version(versioned_accessor(), "default".to_string(), accounts(1), 2)
+        .is_none());
+}
+
+#[test]
+fn test_secrets_updated_events() {
+    let mut context = get_context(accounts(1));
+    testing_env!(context.build());
+    let mut contract = Contract::new(accounts(0), Some(accounts(0)), None, None);
+
+    let secrets_events = || -> Vec<serde_json::Value> {
+        near_sdk::test_utils::get_logs()
+            .iter()
+            .filter_map(|l| l.strip_prefix("EVENT_JSON:"))
+            .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap())
+            .filter(|e| e["event"] == "secrets_updated")
+            .map(|e| e["data"][0].clone())
+            .collect()
+    };
+
+    set_version(&mut contract, &mut context, "v1");
+    let events = secrets_events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["owner"], accounts(1).to_string());
+    assert_eq!(events[0]["profile"], "default");
+    assert_eq!(events[0]["change"], "stored");
+    assert_eq!(events[0]["version"], 1);
+
+    set_version(&mut contract, &mut context, "v2");
+    testing_env!(context.attached_deposit(NearToken::from_yoctonear(0)).build());
+    contract.rollback_secret(versioned_accessor(), "default".to_string(), 1);
+    let events = secrets_events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["change"], "rolled_back");
+    assert_eq!(events[0]["version"], 1);
+
+    testing_env!(context.build());
+    contract.delete_secrets(versioned_accessor(), "default".to_string());
+    assert!(secrets_events().is_empty());
+    let deleted: Vec<serde_json::Value> = near_sdk::test_utils::get_logs()
+        .iter()
+        .filter_map(|l| l.strip_prefix("EVENT_JSON:"))
+        .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap())
+        .filter(|e| e["event"] == "secrets_deleted")
+        .map(|e| e["data"][0].clone())
+        .collect();
+    assert_eq!(deleted.len(), 1);
+    assert_eq!(deleted[0]["owner"], accounts(1).to_string());
+    assert_eq!(deleted[0]["profile"], "default");
+    assert!(deleted[0]["storage_refunded"].as_str().unwrap().parse::<u128>().unwrap() > 0);
+}
@@ -7,6 +7,91 @@ impl Contract {
         self.pending_requests.get(&request_id)
     }
 
+    /// Reconstruct a request's lifecycle for support/debugging
+    ///
+    /// Ordered events: Submitted, OutputSubmitted (large outputs), Compiled
+    /// (compile time reported with the resolution), Resolved, then one of
+    /// Completed / Failed / Cancelled. Available while the request is pending
+    /// and for the last REQUEST_TIMELINE_RETENTION finished requests; empty
+    /// otherwise. Workers claim jobs off-chain (coordinator), so claiming is
+    /// not part of the timeline.
+    pub fn get_request_timeline(&self, request_id: u64) -> Vec<TimelineEvent> {
+        if let Some(request) = self.pending_requests.get(&request_id) {
+            return timeline_events(
+                &request.sender_id,
+                request.payment,
+                request.timestamp,
+                request.output_submitted_at,
+                request.resolved_at,
+                None,
+            );
+        }
+
+        let Some(lifecycle) = self.finished_requests.get(&request_id) else {
+            return vec![];
+        };
+
+        let mut events = timeline_events(
+            &lifecycle.sender_id,
+            lifecycle.payment,
+            lifecycle.submitted_at,
+            lifecycle.output_submitted_at,
+            lifecycle.resolved_at,
+            lifecycle.compile_time_ms,
+        );
+        let (event, details) = match lifecycle.outcome {
+            RequestOutcome::Completed { payment_charged, payment_refunded } => (
+                TimelineEventKind::Completed,
+                format!("charged {} yoctoNEAR, refunded {} yoctoNEAR", payment_charged, payment_refunded),
+            ),
+            RequestOutcome::Failed { error, payment_refunded } => (
+                TimelineEventKind::Failed,
+                format!("{} (refunded {} yoctoNEAR)", error, payment_refunded),
+            ),
+            RequestOutcome::Cancelled { reason } => (TimelineEventKind::Cancelled, reason),
+        };
+        events.push(TimelineEvent {
+            event,
+            timestamp: lifecycle.finished_at,
+            details: Some(details),
+        });
+        events
+    }
+
+    /// Request created by `account_id` with `idempotency_key`, while the key still
+    /// deduplicates (request pending or finished less than IDEMPOTENCY_WINDOW ago).
+    /// Lets a client whose submission outcome is unknown find the request without resubmitting.
+    pub fn get_request_id_by_idempotency_key(&self, account_id: AccountId, idempotency_key: String) -> Option<u64> {
+        self.live_idempotent_request(&(account_id, idempotency_key))
+    }
+
+    /// Pending requests per queue tier: entry i is the number of pending requests with
+    /// priority i (0..=MAX_PRIORITY)
+    pub fn get_queue_depth_by_priority(&self) -> Vec<u64> {
+        (0..=MAX_PRIORITY).map(|priority| self.pending_by_priority.get(&priority).unwrap_or(0)).collect()
+    }
+
+    /// Get pending request load: (pending_requests_count, max_pending_requests)
+    pub fn get_pending_capacity(&self) -> (u64, u64) {
+        (self.pending_requests_count, self.max_pending_requests)
+    }
+
+    /// Get one sender's pending request load: (pending requests, max_pending_per_account)
+    pub fn get_account_pending_capacity(&self, account_id: AccountId) -> (u32, u32) {
+        (self.pending_by_account.get(&account_id).unwrap_or(0), self.max_pending_per_account)
+    }
+
+    /// Echo the caller identity the contract sees (predecessor vs signer).
+    /// Diagnostic for cross-contract auth issues.
+    pub fn whoami(&self) -> CallerInfo {
+        CallerInfo {
+            predecessor: env::predecessor_account_id(),
+            signer: env::signer_account_id(),
+            signer_pk: String::from(&env::signer_account_pk()),
+            current: env::current_account_id(),
+        }
+    }
+
     /// Get contract statistics
     pub fn get_stats(&self) -> (u64, U128) {
         (self.total_executions, U128(self.total_fees_collected))
@@ -32,6 +117,7 @@ impl Contract {
             per_million_instructions_fee: U128(self.per_million_instructions_fee),
             per_ms_fee: U128(self.per_ms_fee),
             per_compile_ms_fee: U128(self.per_compile_ms_fee),
+            priority_fee: U128(self.priority_fee),
             // USD pricing
             base_fee_usd: U128(self.base_fee_usd),
             per_million_instructions_fee_usd: U128(self.per_million_instructions_fee_usd),
@@ -45,10 +131,56 @@ impl Contract {
         self.payment_token_contract.clone()
     }
 
-    /// Estimate cost for given resource limits
-    pub fn estimate_execution_cost(&self, resource_limits: Option<ResourceLimits>) -> U128 {
+    /// Estimate cost for given resource limits and priority (default 0)
+    pub fn estimate_execution_cost(&self, resource_limits: Option<ResourceLimits>, priority: Option<u8>) -> U128 {
         let limits = resource_limits.unwrap_or_default();
-        U128(self.estimate_cost(&limits))
+        U128(self.estimate_cost(&limits, priority.unwrap_or(0)))
+    }
+
+    /// Dry run of `request_execution` in execute mode
+    ///
+    /// Runs the same pre-flight checks (pause, capacity, input size, priority, hard
+    /// limits or the project's overrides, payment against the estimate at `priority`,
+    /// and with `secrets_ref` that the secrets exist for the code source) and reports
+    /// the first one that fails, without changing state. Free to call from a wallet
+    /// before sending the real request.
+    ///
+    /// `project_id` applies that project's resource caps, as a `Project` source would.
+    /// Views have no caller, so the per-account pending limit is only checked when
+    /// `sender_id` names the account that will send the request.
+    ///
+    /// Secrets bound to one branch are only found when `commit` names that branch; the
+    /// branch of a commit hash is resolved off-chain by the worker.
+    #[allow(clippy::too_many_arguments)]
+    pub fn simulate_execution(
+        &self,
+        code_source: CodeSource,
+        resource_limits: ResourceLimits,
+        payment: U128,
+        secrets_ref: Option<SecretsReference>,
+        project_id: Option<String>,
+        input_data: Option<String>,
+        priority: Option<u8>,
+        sender_id: Option<AccountId>,
+    ) -> SimulationResult {
+        let priority = priority.unwrap_or(0);
+        let estimated_cost = self.estimate_cost(&resource_limits, priority);
+        let reason = self.simulation_rejection(
+            &code_source,
+            &resource_limits,
+            payment.0,
+            estimated_cost,
+            secrets_ref,
+            project_id.as_deref(),
+            input_data.as_ref().map_or(0, |d| d.len()),
+            priority,
+            sender_id.as_ref(),
+        );
+        SimulationResult {
+            accepted: reason.is_none(),
+            estimated_cost: U128(estimated_cost),
+            reason,
+        }
     }
 
     /// Get maximum resource limits (hard caps)
@@ -57,6 +189,24 @@ impl Contract {
         (MAX_INSTRUCTIONS, MAX_EXECUTION_SECONDS, MAX_COMPILATION_SECONDS)
     }
 
+    /// Resource caps of a project (`project_id` or GitHub repo): its override if the
+    /// owner set one, otherwise the global hard caps
+    pub fn get_project_limits(&self, project: String) -> ProjectLimits {
+        self.project_limits.get(&project).unwrap_or_default()
+    }
+
+    /// Size and resource bounds a request must stay within
+    pub fn get_limits(&self) -> ExecutionLimits {
+        ExecutionLimits {
+            max_input_bytes: self.max_input_bytes,
+            max_output_bytes: MAX_OUTPUT_BYTES,
+            default_max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            max_instructions: MAX_INSTRUCTIONS,
+            max_execution_seconds: MAX_EXECUTION_SECONDS,
+            max_compilation_seconds: MAX_COMPILATION_SECONDS,
+        }
+    }
+
     /// Check if contract is paused
     pub fn is_paused(&self) -> bool {
         self.paused
@@ -173,3 +323,191 @@ impl Contract {
         }
     }
 }
+
+impl Contract {
+    /// First check of `simulate_execution` that fails, in `request_execution` order
+    #[allow(clippy::too_many_arguments)]
+    fn simulation_rejection(
+        &self,
+        code_source: &CodeSource,
+        limits: &ResourceLimits,
+        payment: Balance,
+        estimated_cost: Balance,
+        secrets_ref: Option<SecretsReference>,
+        project_id: Option<&str>,
+        input_len: usize,
+        priority: u8,
+        sender_id: Option<&AccountId>,
+    ) -> Option<String> {
+        if self.paused {
+            return Some("Contract is paused".to_string());
+        }
+        if self.pending_requests_count >= self.max_pending_requests {
+            return Some("service at capacity, try later".to_string());
+        }
+        let sender_pending = sender_id.and_then(|sender| self.pending_by_account.get(sender)).unwrap_or(0);
+        if sender_pending >= self.max_pending_per_account {
+            return Some(format!(
+                "sender has {} pending requests, the maximum per account; try later",
+                sender_pending
+            ));
+        }
+        if let Some(error) = code_source_error(code_source) {
+            return Some(error);
+        }
+
+        if input_len > self.max_input_bytes as usize {
+            return Some(format!(
+                "input_data is {} bytes, exceeds max_input_bytes of {}; pass large inputs as a storage key or URL instead",
+                input_len, self.max_input_bytes
+            ));
+        }
+        if priority > MAX_PRIORITY {
+            return Some(format!("priority {} exceeds maximum of {}", priority, MAX_PRIORITY));
+        }
+
+        let caps = self.resource_caps(project_id, code_source);
+        let max_instructions = limits.max_instructions.unwrap_or_default();
+        if max_instructions > caps.max_instructions {
+            return Some(format!(
+                "Requested max_instructions {} exceeds hard limit of {}",
+                max_instructions, caps.max_instructions
+            ));
+        }
+        let max_execution_seconds = limits.max_execution_seconds.unwrap_or_default();
+        if max_execution_seconds > caps.max_execution_seconds {
+            return Some(format!(
+                "Requested max_execution_seconds {} exceeds hard limit of {} seconds",
+                max_execution_seconds, caps.max_execution_seconds
+            ));
+        }
+        let max_output_bytes = limits.max_output_bytes.unwrap_or(DEFAULT_MAX_OUTPUT_BYTES);
+        if max_output_bytes > MAX_OUTPUT_BYTES {
+            return Some(format!(
+                "Requested max_output_bytes {} exceeds hard limit of {} bytes",
+                max_output_bytes, MAX_OUTPUT_BYTES
+            ));
+        }
+
+        if payment < estimated_cost {
+            return Some(format!(
+                "Insufficient payment: required {} yoctoNEAR for compute, got {} yoctoNEAR",
+                estimated_cost, payment
+            ));
+        }
+
+        let secrets_ref = secrets_ref?;
+        if secrets_ref.version == Some(0) {
+            return Some("secrets_ref.version must be at least 1".to_string());
+        }
+        let accessor = secrets_accessor(code_source);
+        let found = match secrets_ref.version {
+            Some(version) => self
+                .get_secret_version(accessor, secrets_ref.profile.clone(), secrets_ref.account_id.clone(), version)
+                .is_some(),
+            None => self
+                .get_secrets(accessor, secrets_ref.profile.clone(), secrets_ref.account_id.clone())
+                .is_some(),
+        };
+        if !found {
+            return Some(format!(
+                "Secrets not found: profile '{}' of {}{} for this code source",
+                secrets_ref.profile,
+                secrets_ref.account_id,
+                secrets_ref.version.map(|v| format!(" version {}", v)).unwrap_or_default()
+            ));
+        }
+        None
+    }
+}
+
+/// Why a code source can't be built or fetched, if it is malformed
+fn code_source_error(code_source: &CodeSource) -> Option<String> {
+    match code_source {
+        CodeSource::GitHub { repo, commit, .. } => {
+            if repo.trim().is_empty() {
+                Some("Repository cannot be empty".to_string())
+            } else if commit.trim().is_empty() {
+                Some("Commit cannot be empty".to_string())
+            } else {
+                None
+            }
+        }
+        CodeSource::WasmUrl { url, hash, .. } => {
+            if url.trim().is_empty() {
+                Some("WASM URL cannot be empty".to_string())
+            } else if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                Some("WASM hash must be 64 hex characters (SHA256)".to_string())
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// Accessor the keystore looks secrets up under for a code source
+///
+/// GitHub repos are normalized like the keystore does (`github.com/owner/repo`), and
+/// `commit` is tried as a branch before the lookup falls back to the all-branches profile.
+fn secrets_accessor(code_source: &CodeSource) -> SecretAccessor {
+    match code_source {
+        CodeSource::GitHub { repo, commit, .. } => {
+            let repo = repo.trim();
+            let repo = repo
+                .strip_prefix("https://")
+                .or_else(|| repo.strip_prefix("http://"))
+                .or_else(|| repo.strip_prefix("ssh://"))
+                .unwrap_or(repo);
+            let repo = match repo.strip_prefix("git@") {
+                Some(rest) => rest.replace(':', "/"),
+                None => repo.to_string(),
+            };
+            SecretAccessor::Repo {
+                repo: repo.strip_suffix(".git").unwrap_or(&repo).to_string(),
+                branch: Some(commit.clone()),
+            }
+        }
+        CodeSource::WasmUrl { hash, .. } => SecretAccessor::WasmHash { hash: hash.clone() },
+    }
+}
+
+/// Timeline events up to the resolution, from stored lifecycle markers
+fn timeline_events(
+    sender_id: &AccountId,
+    payment: Balance,
+    submitted_at: u64,
+    output_submitted_at: Option<u64>,
+    resolved_at: Option<u64>,
+    compile_time_ms: Option<u64>,
+) -> Vec<TimelineEvent> {
+    let mut events = vec![TimelineEvent {
+        event: TimelineEventKind::Submitted,
+        timestamp: submitted_at,
+        details: Some(format!("sender {}, payment {} yoctoNEAR", sender_id, payment)),
+    }];
+
+    if let Some(timestamp) = output_submitted_at {
+        events.push(TimelineEvent {
+            event: TimelineEventKind::OutputSubmitted,
+            timestamp,
+            details: None,
+        });
+    }
+
+    if let Some(timestamp) = resolved_at {
+        if let Some(compile_time_ms) = compile_time_ms {
+            events.push(TimelineEvent {
+                event: TimelineEventKind::Compiled,
+                timestamp,
+                details: Some(format!("compile_time_ms {}", compile_time_ms)),
+            });
+        }
+        events.push(TimelineEvent {
+            event: TimelineEventKind::Resolved,
+            timestamp,
+            details: None,
+        });
+    }
+
+    events
+}
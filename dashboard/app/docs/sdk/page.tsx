@@ -38,7 +38,7 @@ export default function SdkPage() {
 
         <SyntaxHighlighter language="toml" style={vscDarkPlus} className="rounded-lg mb-4">
           {`[dependencies]
-outlayer = "0.1"
+outlayer = "0.2"
 serde = { version = "1.0", features = ["derive"] }
 serde_json = "1.0"`}
         </SyntaxHighlighter>
@@ -343,7 +343,7 @@ name = "my-outlayer-app"
 path = "src/main.rs"
 
 [dependencies]
-outlayer = "0.1"
+outlayer = "0.2"
 serde = { version = "1.0", features = ["derive"] }
 serde_json = "1.0"
 
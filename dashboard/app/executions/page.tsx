@@ -260,6 +260,8 @@ export default function JobsPage() {
         return { color: 'bg-orange-100 text-orange-800', text: 'Compilation Failed' };
       case 'execution_failed':
         return { color: 'bg-red-100 text-red-800', text: 'Execution Failed' };
+      case 'timeout':
+        return { color: 'bg-red-100 text-red-800', text: 'Timed Out' };
       case 'insufficient_payment':
         return { color: 'bg-purple-100 text-purple-800', text: 'Insufficient Payment' };
       case 'custom':
@@ -1,5 +1,5 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LookupMap, UnorderedSet};
+use near_sdk::collections::{LookupMap, UnorderedMap, UnorderedSet};
 use near_sdk::json_types::Base58CryptoHash;
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{env, ext_contract, near_bindgen, require, AccountId, CurveType, Promise, PromiseOrValue, PublicKey, BorshStorageKey, NearToken, Allowance, Gas};
@@ -80,6 +80,16 @@ enum StorageKey {
     /// proposal. See `vault_version_approval_args` doc on
     /// `KeystoreDao` for the trust model.
     VaultVersionApprovalArgs,
+    // ----- v4 (proposal expiry) -----
+    /// Pending keystore proposals by public key. See `pending_proposals`.
+    PendingProposals,
+    // ----- v5 (proposal vote ledger, weighted voting, execution timelock) -----
+    /// Voters per proposal, in voting order. See `proposal_voters`.
+    ProposalVoters,
+    /// Voting weight per DAO member. See `member_weights`.
+    MemberWeights,
+    /// Earliest execution time of approved proposals. See `proposal_executable_at`.
+    ProposalExecutableAt,
 }
 
 /// Full TEE measurements for verifying the entire dstack environment.
@@ -114,7 +124,9 @@ pub struct KeystoreProposal {
     #[schemars(with = "String")]
     pub submitter: AccountId,
     pub created_at: u64,
+    /// Weight of all approve votes cast (including members removed since)
     pub votes_for: u32,
+    /// Weight of all reject votes cast (including members removed since)
     pub votes_against: u32,
     pub status: ProposalStatus,
 }
@@ -123,11 +135,22 @@ pub struct KeystoreProposal {
 #[serde(crate = "near_sdk::serde")]
 pub enum ProposalStatus {
     Pending,
+    /// Reached the threshold; waiting out `execution_delay_ns` before
+    /// `execute_proposal` adds the access key
     Approved,
     Rejected,
     Executed,
 }
 
+/// One page of `get_proposals`
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProposalPage {
+    pub proposals: Vec<KeystoreProposal>,
+    /// Number of proposals ever created (ids are `1..=total`)
+    pub total: u64,
+}
+
 /// Information about an approved keystore
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(crate = "near_sdk::serde")]
@@ -162,11 +185,22 @@ pub struct VaultVersionInfo {
     pub approved_at: u64,
 }
 
+/// Default voting window for keystore proposals: 7 days in nanoseconds
+pub const DEFAULT_VOTING_PERIOD_NS: u64 = 7 * 24 * 60 * 60 * 1_000_000_000;
+
+/// Default delay between approval and execution of a keystore proposal:
+/// none, the deciding vote adds the access key
+pub const DEFAULT_EXECUTION_DELAY_NS: u64 = 0;
+
 /// Max number of cached quote collaterals (one per platform/FMSPC, e.g. Phala + self-hosted).
 /// `submit_keystore_registration` selects the slot whose FMSPC matches the worker's quote and
 /// verifies ONLY that slot; keep small (gas).
 const MAX_COLLATERALS: usize = 2;
 
+/// Largest voting weight one DAO member can hold. Keeps weight sums far
+/// from `u32` overflow.
+pub const MAX_MEMBER_WEIGHT: u32 = 1_000;
+
 /// Keystore DAO Contract
 ///
 /// This contract manages keystore registration through DAO governance.
@@ -186,7 +220,8 @@ pub struct KeystoreDao {
     /// DAO members who can vote
     pub dao_members: UnorderedSet<AccountId>,
 
-    /// Minimum votes required for approval (>50% of members)
+    /// Minimum vote weight required for approval (>50% of the total
+    /// member weight; see `member_weights`)
     pub approval_threshold: u32,
 
     /// Owner who can manage DAO members and collateral
@@ -275,6 +310,49 @@ pub struct KeystoreDao {
     /// `vault_version_votes` when quorum is reached.
     /// Only `Approve` has metadata; `Revoke` is just `hash`.
     pub vault_version_approval_args: LookupMap<Base58CryptoHash, ApprovalArgs>,
+
+    // ----- v4: proposal expiry -----
+
+    /// How long a keystore proposal stays open for voting (nanoseconds
+    /// after `created_at`). A proposal still `Pending` past this window
+    /// is expired: the next `vote` on it, or anyone calling
+    /// `expire_proposal`, marks it `Rejected`.
+    pub voting_period_ns: u64,
+
+    /// Public key -> id of its `Pending` proposal, or of its `Approved`
+    /// one waiting for `execute_proposal`. At most one live proposal per
+    /// key: a resubmission is refused while the previous one is still
+    /// open or awaiting execution, and allowed once it has expired (the
+    /// old proposal is rejected in the same call). Entries are removed
+    /// when a proposal is executed or rejected, so this also bounds the
+    /// iteration in `get_active_proposals`.
+    pub pending_proposals: UnorderedMap<PublicKey, u64>,
+
+    // ----- v5: proposal vote ledger -----
+
+    /// Accounts that voted on each proposal, in voting order. `votes`
+    /// is keyed by `(proposal_id, voter)` and can't be enumerated, so
+    /// this is what lets `get_proposal_votes` list who voted which way
+    /// (including members removed since).
+    pub proposal_voters: LookupMap<u64, Vec<AccountId>>,
+
+    /// Voting weight of each DAO member, set by `add_dao_member`. A
+    /// member without an entry (e.g. from `new` or from before weights
+    /// existed) weighs 1. Keystore proposals and the vault-version
+    /// multisig both sum voter weights against `approval_threshold`.
+    pub member_weights: LookupMap<AccountId, u32>,
+
+    /// Time between a keystore proposal reaching the threshold and its
+    /// access key being added (nanoseconds). 0 executes in the deciding
+    /// `vote`; otherwise the proposal stays `Approved` until someone
+    /// calls `execute_proposal` after the delay, and the owner can
+    /// `veto_proposal` it in the meantime.
+    pub execution_delay_ns: u64,
+
+    /// Approved proposal id -> earliest `execute_proposal` time
+    /// (approval time + the `execution_delay_ns` in force then). Removed
+    /// on execution or veto.
+    pub proposal_executable_at: LookupMap<u64, u64>,
 }
 
 /// First-proposer's metadata for an in-flight approve proposal. See
@@ -360,8 +438,13 @@ impl KeystoreDao {
         init_account_id: AccountId,
         dao_members: Vec<AccountId>,
         mpc_contract_id: AccountId,
+        voting_period_ns: Option<u64>,
+        execution_delay_ns: Option<u64>,
     ) -> Self {
         assert!(!dao_members.is_empty(), "DAO must have at least one member");
+        let voting_period_ns = voting_period_ns.unwrap_or(DEFAULT_VOTING_PERIOD_NS);
+        assert!(voting_period_ns > 0, "voting_period_ns must be positive");
+        let execution_delay_ns = execution_delay_ns.unwrap_or(DEFAULT_EXECUTION_DELAY_NS);
 
         let mut members_set = UnorderedSet::new(StorageKey::DaoMembers);
         for member in dao_members.iter() {
@@ -392,6 +475,12 @@ impl KeystoreDao {
             vault_version_approval_args: LookupMap::new(
                 StorageKey::VaultVersionApprovalArgs,
             ),
+            voting_period_ns,
+            pending_proposals: UnorderedMap::new(StorageKey::PendingProposals),
+            proposal_voters: LookupMap::new(StorageKey::ProposalVoters),
+            member_weights: LookupMap::new(StorageKey::MemberWeights),
+            execution_delay_ns,
+            proposal_executable_at: LookupMap::new(StorageKey::ProposalExecutableAt),
         }
     }
 
@@ -423,6 +512,22 @@ impl KeystoreDao {
             "Keystore already approved"
         );
 
+        // One live proposal per key; an expired one is rejected to make room
+        if let Some(existing_id) = self.pending_proposals.get(&public_key) {
+            let existing = self.proposals.get(&existing_id).expect("Proposal not found");
+            assert!(
+                existing.status == ProposalStatus::Pending,
+                "Keystore proposal {} is approved and awaiting execution",
+                existing_id
+            );
+            assert!(
+                self.is_expired(&existing),
+                "Keystore already has pending proposal {}",
+                existing_id
+            );
+            self.internal_expire_proposal(existing_id, existing);
+        }
+
         // Use ONLY cached collateral(s) (security: prevent custom collateral bypass).
         let collaterals = self.collaterals.clone();
         assert!(
@@ -520,6 +625,7 @@ impl KeystoreDao {
 
         let proposal_id = self.next_proposal_id;
         self.proposals.insert(&proposal_id, &proposal);
+        self.pending_proposals.insert(&public_key, &proposal_id);
         self.next_proposal_id += 1;
 
         env::log_str(&format!(
@@ -531,6 +637,11 @@ impl KeystoreDao {
     }
 
     /// DAO member votes on proposal
+    ///
+    /// A vote on a proposal whose voting period has ended is not counted;
+    /// the proposal is marked `Rejected` instead. The vote that reaches
+    /// the threshold executes the proposal, unless `execution_delay_ns`
+    /// is set: then it is left `Approved` for `execute_proposal`.
     pub fn vote(&mut self, proposal_id: u64, approve: bool) {
         let voter = env::predecessor_account_id();
 
@@ -550,6 +661,11 @@ impl KeystoreDao {
             "Proposal is not pending"
         );
 
+        if self.is_expired(&proposal) {
+            self.internal_expire_proposal(proposal_id, proposal);
+            return;
+        }
+
         // Check if already voted
         let vote_key = (proposal_id, voter.clone());
         assert!(
@@ -559,16 +675,20 @@ impl KeystoreDao {
 
         // Record vote
         self.votes.insert(&vote_key, &approve);
+        let weight = self.member_weight(&voter);
+        let mut voters = self.proposal_voters.get(&proposal_id).unwrap_or_default();
+        voters.push(voter);
+        self.proposal_voters.insert(&proposal_id, &voters);
 
         if approve {
-            proposal.votes_for += 1;
+            proposal.votes_for += weight;
         } else {
-            proposal.votes_against += 1;
+            proposal.votes_against += weight;
         }
 
-        // Recompute LIVE counts using only current DAO members for the
-        // threshold decision. The stored `votes_for` / `votes_against`
-        // are kept as "all votes ever cast" for the view API, but
+        // Recompute LIVE weights using only current DAO members (at their
+        // current weight) for the threshold decision. The stored
+        // `votes_for` / `votes_against` are kept as "all votes ever cast" for the view API, but
         // would otherwise allow a removed member's vote to push the
         // proposal past threshold or rejection without any current
         // member's consent — see `approve_vault_version` for the
@@ -576,27 +696,40 @@ impl KeystoreDao {
         let (live_for, live_against) = self.dao_members.iter().fold(
             (0u32, 0u32),
             |(f, a), member| match self.votes.get(&(proposal_id, member.clone())) {
-                Some(true) => (f + 1, a),
-                Some(false) => (f, a + 1),
+                Some(true) => (f + self.member_weight(&member), a),
+                Some(false) => (f, a + self.member_weight(&member)),
                 None => (f, a),
             },
         );
 
+        // Rejected once the remaining weight can no longer reach the threshold
         if live_for >= self.approval_threshold {
             proposal.status = ProposalStatus::Approved;
 
             env::log_str(&format!(
-                "Proposal {} approved with {} votes",
+                "Proposal {} approved with vote weight {}",
                 proposal_id, live_for
             ));
 
-            self.internal_execute_proposal(proposal_id, proposal);
-        } else if live_against > (self.dao_members.len() as u32 - self.approval_threshold) {
+            if self.execution_delay_ns == 0 {
+                self.internal_execute_proposal(proposal_id, proposal);
+            } else {
+                let executable_at = env::block_timestamp().saturating_add(self.execution_delay_ns);
+                self.proposals.insert(&proposal_id, &proposal);
+                self.proposal_executable_at.insert(&proposal_id, &executable_at);
+
+                env::log_str(&format!(
+                    "Proposal {} executable at {}",
+                    proposal_id, executable_at
+                ));
+            }
+        } else if live_against > self.total_weight().saturating_sub(self.approval_threshold) {
             proposal.status = ProposalStatus::Rejected;
             self.proposals.insert(&proposal_id, &proposal);
+            self.pending_proposals.remove(&proposal.public_key);
 
             env::log_str(&format!(
-                "Proposal {} rejected with {} votes against",
+                "Proposal {} rejected with vote weight {} against",
                 proposal_id, live_against
             ));
         }
@@ -606,6 +739,69 @@ impl KeystoreDao {
         }
     }
 
+    /// Reject a `Pending` proposal whose voting period has ended.
+    /// Callable by anyone; frees the key for resubmission.
+    pub fn expire_proposal(&mut self, proposal_id: u64) {
+        let proposal = self.proposals.get(&proposal_id)
+            .expect("Proposal not found");
+        assert_eq!(
+            proposal.status, ProposalStatus::Pending,
+            "Proposal is not pending"
+        );
+        assert!(self.is_expired(&proposal), "Voting period has not ended");
+
+        self.internal_expire_proposal(proposal_id, proposal);
+    }
+
+    /// Add the access key of an `Approved` proposal once its execution
+    /// delay has passed. Callable by anyone.
+    pub fn execute_proposal(&mut self, proposal_id: u64) {
+        let proposal = self.proposals.get(&proposal_id)
+            .expect("Proposal not found");
+        assert_eq!(
+            proposal.status, ProposalStatus::Approved,
+            "Proposal is not approved"
+        );
+        let executable_at = self.proposal_executable_at.get(&proposal_id).unwrap_or(0);
+        assert!(
+            env::block_timestamp() >= executable_at,
+            "Execution delay has not passed (executable at {})",
+            executable_at
+        );
+
+        self.proposal_executable_at.remove(&proposal_id);
+        self.internal_execute_proposal(proposal_id, proposal);
+    }
+
+    /// Owner: Reject an `Approved` proposal before it is executed, e.g.
+    /// when a malicious keystore got through the vote. Frees the key for
+    /// resubmission.
+    pub fn veto_proposal(&mut self, proposal_id: u64) {
+        self.assert_owner();
+        let mut proposal = self.proposals.get(&proposal_id)
+            .expect("Proposal not found");
+        assert_eq!(
+            proposal.status, ProposalStatus::Approved,
+            "Proposal is not approved"
+        );
+
+        proposal.status = ProposalStatus::Rejected;
+        self.proposals.insert(&proposal_id, &proposal);
+        self.pending_proposals.remove(&proposal.public_key);
+        self.proposal_executable_at.remove(&proposal_id);
+
+        env::log_str(&format!("Proposal {} vetoed by owner", proposal_id));
+    }
+
+    /// Owner: Set the delay between approval and execution of keystore
+    /// proposals (nanoseconds, 0 = execute on the deciding vote).
+    /// Proposals already approved keep the delay they were approved with.
+    pub fn set_execution_delay(&mut self, execution_delay_ns: u64) {
+        self.assert_owner();
+        self.execution_delay_ns = execution_delay_ns;
+        env::log_str(&format!("Execution delay set to {} ns", execution_delay_ns));
+    }
+
     /// Owner: Add approved TEE measurements (MRTD + RTMR0-3).
     ///
     /// All 5 measurements must match for a keystore to register.
@@ -652,16 +848,25 @@ impl KeystoreDao {
         env::log_str(&format!("Approved measurements removed. Remaining: {}", self.approved_measurements.len()));
     }
 
-    /// Owner: Add DAO member
-    pub fn add_dao_member(&mut self, member: AccountId) {
+    /// Owner: Add DAO member with a voting weight (default 1, at most
+    /// `MAX_MEMBER_WEIGHT`). Calling it for an existing member updates
+    /// their weight.
+    pub fn add_dao_member(&mut self, member: AccountId, weight: Option<u32>) {
         self.assert_owner();
+        let weight = weight.unwrap_or(1);
+        assert!(
+            (1..=MAX_MEMBER_WEIGHT).contains(&weight),
+            "weight must be between 1 and {}",
+            MAX_MEMBER_WEIGHT
+        );
 
         self.dao_members.insert(&member);
+        self.member_weights.insert(&member, &weight);
 
         // Recalculate threshold
-        self.approval_threshold = (self.dao_members.len() as u32 / 2) + 1;
+        self.approval_threshold = self.total_weight() / 2 + 1;
 
-        env::log_str(&format!("Added DAO member: {}", member));
+        env::log_str(&format!("Added DAO member: {} (weight {})", member, weight));
     }
 
     /// Owner: Remove DAO member
@@ -670,9 +875,10 @@ impl KeystoreDao {
         assert!(self.dao_members.len() > 1, "Cannot remove last DAO member");
 
         self.dao_members.remove(&member);
+        self.member_weights.remove(&member);
 
         // Recalculate threshold
-        self.approval_threshold = (self.dao_members.len() as u32 / 2) + 1;
+        self.approval_threshold = self.total_weight() / 2 + 1;
 
         env::log_str(&format!("Removed DAO member: {}", member));
     }
@@ -708,14 +914,60 @@ impl KeystoreDao {
         self.proposals.get(&proposal_id)
     }
 
-    /// Get all proposals
-    pub fn get_proposals(&self, from_index: u64, limit: u64) -> Vec<KeystoreProposal> {
-        let mut result = Vec::new();
+    /// Get proposals with ids `from_index..from_index + limit`, plus the
+    /// total count to paginate against
+    pub fn get_proposals(&self, from_index: u64, limit: u64) -> ProposalPage {
+        let mut proposals = Vec::new();
         for i in from_index..from_index.saturating_add(limit).min(self.next_proposal_id) {
             if let Some(proposal) = self.proposals.get(&i) {
-                result.push(proposal);
+                proposals.push(proposal);
             }
         }
+        ProposalPage {
+            proposals,
+            total: self.next_proposal_id - 1,
+        }
+    }
+
+    /// Get up to `limit` proposals with the given stored `status`, scanning
+    /// ids upward from `from_index`. Pass the last returned id + 1 as the
+    /// next `from_index`.
+    ///
+    /// `Pending` includes proposals whose voting period has ended but that
+    /// nobody has expired yet; `get_active_proposals` filters those out.
+    pub fn get_proposals_by_status(&self, status: ProposalStatus, from_index: u64, limit: u64) -> Vec<KeystoreProposal> {
+        (from_index.max(1)..self.next_proposal_id)
+            .filter_map(|id| self.proposals.get(&id))
+            .filter(|p| p.status == status)
+            .take(limit as usize)
+            .collect()
+    }
+
+    /// Votes cast on a proposal as `(voter, approve)`, in voting order.
+    /// Includes votes of members removed since (still counted in
+    /// `votes_for` / `votes_against`, but not towards the threshold).
+    pub fn get_proposal_votes(&self, proposal_id: u64) -> Vec<(AccountId, bool)> {
+        self.proposal_voters
+            .get(&proposal_id)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|voter| {
+                let approve = self.votes.get(&(proposal_id, voter.clone()))?;
+                Some((voter, approve))
+            })
+            .collect()
+    }
+
+    /// Get proposals still open for voting (`Pending` and within the voting
+    /// period), ordered by id
+    pub fn get_active_proposals(&self) -> Vec<KeystoreProposal> {
+        let mut result: Vec<KeystoreProposal> = self
+            .pending_proposals
+            .values()
+            .filter_map(|id| self.proposals.get(&id))
+            .filter(|p| p.status == ProposalStatus::Pending && !self.is_expired(p))
+            .collect();
+        result.sort_by_key(|p| p.id);
         result
     }
 
@@ -724,6 +976,21 @@ impl KeystoreDao {
         self.dao_members.to_vec()
     }
 
+    /// Earliest time `execute_proposal` accepts an `Approved` proposal
+    /// (None if the proposal is not waiting for execution)
+    pub fn get_proposal_executable_at(&self, proposal_id: u64) -> Option<u64> {
+        self.proposal_executable_at.get(&proposal_id)
+    }
+
+    /// Voting weight of a DAO member (0 if not a member)
+    pub fn get_member_weight(&self, account_id: AccountId) -> u32 {
+        if self.dao_members.contains(&account_id) {
+            self.member_weight(&account_id)
+        } else {
+            0
+        }
+    }
+
     /// Get approved measurements list
     pub fn get_approved_measurements(&self) -> Vec<ApprovedMeasurements> {
         self.approved_measurements.clone()
@@ -757,6 +1024,9 @@ impl KeystoreDao {
             "init_account_id": self.init_account_id,
             "mpc_contract_id": self.mpc_contract_id,
             "approval_threshold": self.approval_threshold,
+            "total_weight": self.total_weight(),
+            "voting_period_ns": self.voting_period_ns,
+            "execution_delay_ns": self.execution_delay_ns,
             "dao_members_count": self.dao_members.len(),
             "next_proposal_id": self.next_proposal_id,
             "approved_keystores_count": self.approved_keystores.len(),
@@ -862,8 +1132,8 @@ impl KeystoreDao {
     /// Quorum-gated approval of a new vault contract code hash.
     ///
     /// Each DAO member's call records a vote for the given hash;
-    /// when `approval_threshold` (>50%) distinct members have
-    /// voted, the hash is whitelisted and the pending entry is
+    /// when the voters' combined weight reaches `approval_threshold`
+    /// (>50% of total weight), the hash is whitelisted and the pending entry is
     /// cleared. The first proposer's `(label, audit_url)` is locked
     /// into `vault_version_approval_args`; subsequent voters MUST
     /// pass matching args or the call panics with a clear message
@@ -873,7 +1143,7 @@ impl KeystoreDao {
     /// approved hash overwrites the metadata only after the new
     /// approval reaches quorum.
     ///
-    /// Returns the number of votes recorded so far (so a caller can
+    /// Returns the vote weight recorded so far (so a caller can
     /// see whether their vote was decisive).
     pub fn approve_vault_version(
         &mut self,
@@ -929,7 +1199,7 @@ impl KeystoreDao {
         if !voters.contains(&voter) {
             voters.push(voter);
         }
-        let count = voters.len() as u32;
+        let count = self.weight_of(&voters);
 
         if count >= self.approval_threshold {
             // Quorum reached — execute and clear BOTH the vote
@@ -973,7 +1243,7 @@ impl KeystoreDao {
     /// `is_vault_code_approved` check returns false), and any new
     /// deployment of this hash will be rejected by vault-checker.
     ///
-    /// Gated by `approval_threshold` votes, same pattern as
+    /// Gated by `approval_threshold` vote weight, same pattern as
     /// `approve_vault_version`. Vote ledger is keyed on
     /// `Revoke { hash }` so this is independent of any pending
     /// Approve votes for the same hash.
@@ -988,7 +1258,7 @@ impl KeystoreDao {
         if !voters.contains(&voter) {
             voters.push(voter);
         }
-        let count = voters.len() as u32;
+        let count = self.weight_of(&voters);
 
         if count >= self.approval_threshold {
             self.vault_version_votes.remove(&action);
@@ -1005,13 +1275,16 @@ impl KeystoreDao {
         count
     }
 
-    /// View — how many DAO members have so far voted for the given
-    /// pending vault-version action? Returns 0 if none. Useful for
-    /// dashboards showing "<n>/<threshold> votes".
+    /// View — combined weight of the current DAO members who have so
+    /// far voted for the given pending vault-version action? Returns 0
+    /// if none. Useful for dashboards showing "<n>/<threshold> votes".
     pub fn get_vault_version_votes(&self, action: VaultVersionAction) -> u32 {
         self.vault_version_votes
             .get(&action)
-            .map(|v| v.len() as u32)
+            .map(|voters| {
+                let current: Vec<AccountId> = voters.into_iter().filter(|v| self.dao_members.contains(v)).collect();
+                self.weight_of(&current)
+            })
             .unwrap_or(0)
     }
 
@@ -1280,6 +1553,7 @@ impl KeystoreDao {
         // Mark as executed
         proposal.status = ProposalStatus::Executed;
         self.proposals.insert(&proposal_id, &proposal);
+        self.pending_proposals.remove(&proposal.public_key);
 
         // Add to approved keystores
         self.approved_keystores.insert(&proposal.public_key);
@@ -1293,6 +1567,38 @@ impl KeystoreDao {
         ));
     }
 
+    /// Voting period of a still-pending proposal has ended
+    fn is_expired(&self, proposal: &KeystoreProposal) -> bool {
+        env::block_timestamp() > proposal.created_at.saturating_add(self.voting_period_ns)
+    }
+
+    /// Mark an expired pending proposal `Rejected` and free its key
+    fn internal_expire_proposal(&mut self, proposal_id: u64, mut proposal: KeystoreProposal) {
+        proposal.status = ProposalStatus::Rejected;
+        self.proposals.insert(&proposal_id, &proposal);
+        self.pending_proposals.remove(&proposal.public_key);
+
+        env::log_str(&format!(
+            "Proposal {} expired without reaching threshold ({} for, {} against)",
+            proposal_id, proposal.votes_for, proposal.votes_against
+        ));
+    }
+
+    /// Voting weight of a member (1 unless set by `add_dao_member`)
+    fn member_weight(&self, member: &AccountId) -> u32 {
+        self.member_weights.get(member).unwrap_or(1)
+    }
+
+    /// Combined weight of all current DAO members
+    fn total_weight(&self) -> u32 {
+        self.dao_members.iter().map(|m| self.member_weight(&m)).sum()
+    }
+
+    /// Combined weight of `voters`
+    fn weight_of(&self, voters: &[AccountId]) -> u32 {
+        voters.iter().map(|v| self.member_weight(v)).sum()
+    }
+
     /// Helper: assert the caller is a DAO member. Used by the
     /// single-signer methods (cessation flips, vault version whitelist
     /// management, manual ban / unban).
@@ -1391,6 +1697,8 @@ mod tests {
             "init.near".parse().unwrap(),
             vec![member_a(), member_b()],
             "v1.signer.near".parse().unwrap(),
+            None,
+            None,
         )
     }
 
@@ -1540,6 +1848,8 @@ mod tests {
                 m("m5.near"),
             ],
             m("v1.signer.near"),
+            None,
+            None,
         )
     }
 
@@ -1942,4 +2252,338 @@ mod tests {
         assert!(dao.is_vault_verified(vault_a()));
         assert!(!dao.is_vault_verified(vault_b()));
     }
+
+    // ===== Proposal expiry =====
+
+    const DAY_NS: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+    fn keystore_key(byte: u8) -> PublicKey {
+        let mut bytes = vec![0u8];
+        bytes.extend_from_slice(&[byte; 32]);
+        PublicKey::try_from(bytes).unwrap()
+    }
+
+    /// Insert a pending proposal directly (the real path needs a TDX quote)
+    fn pending_proposal(dao: &mut KeystoreDao, public_key: PublicKey) -> u64 {
+        let id = dao.next_proposal_id;
+        let proposal = KeystoreProposal {
+            id,
+            public_key: public_key.clone(),
+            measurements: ApprovedMeasurements {
+                mrtd: "00".repeat(48),
+                rtmr0: "00".repeat(48),
+                rtmr1: "00".repeat(48),
+                rtmr2: "00".repeat(48),
+                rtmr3: "00".repeat(48),
+            },
+            submitter: "init.near".parse().unwrap(),
+            created_at: env::block_timestamp(),
+            votes_for: 0,
+            votes_against: 0,
+            status: ProposalStatus::Pending,
+        };
+        dao.proposals.insert(&id, &proposal);
+        dao.pending_proposals.insert(&public_key, &id);
+        dao.next_proposal_id += 1;
+        id
+    }
+
+    fn at(predecessor: AccountId, timestamp: u64) {
+        testing_env!(ctx(predecessor).block_timestamp(timestamp).build());
+    }
+
+    #[test]
+    fn proposal_expires_after_voting_period() {
+        let mut dao = fresh_dao();
+        assert_eq!(dao.voting_period_ns, DEFAULT_VOTING_PERIOD_NS);
+        dao.voting_period_ns = DAY_NS;
+
+        at(member_a(), 1_000);
+        let expiring = pending_proposal(&mut dao, keystore_key(1));
+        at(member_a(), 1_000 + DAY_NS / 2);
+        let fresh = pending_proposal(&mut dao, keystore_key(2));
+
+        // Within the window both are active and votes count
+        at(member_a(), 1_000 + DAY_NS);
+        dao.vote(expiring, true);
+        assert_eq!(dao.get_proposal(expiring).unwrap().votes_for, 1);
+        let active: Vec<u64> = dao.get_active_proposals().iter().map(|p| p.id).collect();
+        assert_eq!(active, vec![expiring, fresh]);
+
+        // One ns past the window: filtered from the view, late vote rejects it
+        at(member_b(), 1_000 + DAY_NS + 1);
+        let active: Vec<u64> = dao.get_active_proposals().iter().map(|p| p.id).collect();
+        assert_eq!(active, vec![fresh]);
+        dao.vote(expiring, true);
+        let proposal = dao.get_proposal(expiring).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Rejected);
+        assert_eq!(proposal.votes_for, 1, "late vote must not be counted");
+        assert!(!dao.approved_keystores.contains(&keystore_key(1)));
+        assert!(dao.pending_proposals.get(&keystore_key(1)).is_none());
+
+        // Anyone can expire the other one once its window has passed
+        at(outsider(), 1_000 + DAY_NS + DAY_NS / 2 + 1);
+        dao.expire_proposal(fresh);
+        assert_eq!(dao.get_proposal(fresh).unwrap().status, ProposalStatus::Rejected);
+        assert!(dao.get_active_proposals().is_empty());
+    }
+
+    #[test]
+    fn proposals_page_filter_and_list_votes() {
+        let mut dao = fresh_dao();
+        at(member_a(), 1_000);
+        let first = pending_proposal(&mut dao, keystore_key(1));
+        let second = pending_proposal(&mut dao, keystore_key(2));
+        let third = pending_proposal(&mut dao, keystore_key(3));
+
+        // member-a approves, member-b rejects: the second proposal is rejected
+        dao.vote(second, true);
+        at(member_b(), 1_000);
+        dao.vote(second, false);
+
+        let page = dao.get_proposals(1, 2);
+        assert_eq!(page.total, 3);
+        assert_eq!(page.proposals.iter().map(|p| p.id).collect::<Vec<_>>(), vec![first, second]);
+        assert!(dao.get_proposals(4, 10).proposals.is_empty());
+
+        let pending: Vec<u64> = dao.get_proposals_by_status(ProposalStatus::Pending, 0, 10).iter().map(|p| p.id).collect();
+        assert_eq!(pending, vec![first, third]);
+        let pending: Vec<u64> = dao.get_proposals_by_status(ProposalStatus::Pending, first + 1, 1).iter().map(|p| p.id).collect();
+        assert_eq!(pending, vec![third]);
+        assert_eq!(dao.get_proposals_by_status(ProposalStatus::Rejected, 0, 10)[0].id, second);
+
+        assert_eq!(dao.get_proposal_votes(second), vec![(member_a(), true), (member_b(), false)]);
+        assert!(dao.get_proposal_votes(first).is_empty());
+
+        // Votes of a removed member stay listed
+        testing_env!(ctx("owner.near".parse().unwrap()).build());
+        dao.remove_dao_member(member_b());
+        assert_eq!(dao.get_proposal_votes(second).len(), 2);
+    }
+
+    fn member_c() -> AccountId {
+        "member-c.near".parse().unwrap()
+    }
+
+    /// member-a and member-b weigh 1 each, member-c weighs 3: total 5,
+    /// threshold 3.
+    fn weighted_dao() -> KeystoreDao {
+        let mut dao = fresh_dao();
+        dao.add_dao_member(member_c(), Some(3));
+        dao
+    }
+
+    #[test]
+    fn heavy_member_crosses_threshold_alone() {
+        let mut dao = weighted_dao();
+        assert_eq!(dao.approval_threshold, 3);
+        assert_eq!(dao.get_member_weight(member_a()), 1);
+        assert_eq!(dao.get_member_weight(member_c()), 3);
+        assert_eq!(dao.get_member_weight(outsider()), 0);
+
+        at(member_c(), 1_000);
+        let id = pending_proposal(&mut dao, keystore_key(1));
+        dao.vote(id, true);
+        let proposal = dao.get_proposal(id).unwrap();
+        assert_eq!(proposal.votes_for, 3);
+        assert_eq!(proposal.status, ProposalStatus::Executed);
+        assert!(dao.approved_keystores.contains(&keystore_key(1)));
+    }
+
+    #[test]
+    fn light_members_fall_short_and_weighted_reject_wins() {
+        let mut dao = weighted_dao();
+        at(member_a(), 1_000);
+        let id = pending_proposal(&mut dao, keystore_key(1));
+        dao.vote(id, true);
+        at(member_b(), 1_000);
+        dao.vote(id, true);
+        // 2 of 5 weight: neither approved nor out of reach
+        let proposal = dao.get_proposal(id).unwrap();
+        assert_eq!(proposal.votes_for, 2);
+        assert_eq!(proposal.status, ProposalStatus::Pending);
+
+        // 3 against leaves at most 2 for: rejected
+        at(member_c(), 1_000);
+        dao.vote(id, false);
+        let proposal = dao.get_proposal(id).unwrap();
+        assert_eq!(proposal.votes_against, 3);
+        assert_eq!(proposal.status, ProposalStatus::Rejected);
+        assert!(!dao.approved_keystores.contains(&keystore_key(1)));
+    }
+
+    #[test]
+    fn removing_weighted_member_recomputes_threshold() {
+        let mut dao = weighted_dao();
+        dao.remove_dao_member(member_c());
+        assert_eq!(dao.approval_threshold, 2);
+        assert_eq!(dao.get_member_weight(member_c()), 0);
+
+        // Re-adding without a weight falls back to 1
+        dao.add_dao_member(member_c(), None);
+        assert_eq!(dao.get_member_weight(member_c()), 1);
+        assert_eq!(dao.approval_threshold, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "weight must be between 1 and 1000")]
+    fn add_dao_member_rejects_zero_weight() {
+        let mut dao = fresh_dao();
+        dao.add_dao_member(member_c(), Some(0));
+    }
+
+    /// fresh_dao with a one-day execution delay and proposal `id` approved
+    /// at timestamp 1_000
+    fn approved_with_delay() -> (KeystoreDao, u64) {
+        let mut dao = fresh_dao();
+        dao.set_execution_delay(DAY_NS);
+        at(member_a(), 1_000);
+        let id = pending_proposal(&mut dao, keystore_key(1));
+        dao.vote(id, true);
+        at(member_b(), 1_000);
+        dao.vote(id, true);
+        (dao, id)
+    }
+
+    #[test]
+    fn approved_proposal_executes_after_delay() {
+        let (mut dao, id) = approved_with_delay();
+        assert_eq!(dao.get_proposal(id).unwrap().status, ProposalStatus::Approved);
+        assert!(!dao.approved_keystores.contains(&keystore_key(1)));
+        assert_eq!(dao.get_proposal_executable_at(id), Some(1_000 + DAY_NS));
+        assert!(dao.get_active_proposals().is_empty());
+
+        at(outsider(), 1_000 + DAY_NS);
+        dao.execute_proposal(id);
+        assert_eq!(dao.get_proposal(id).unwrap().status, ProposalStatus::Executed);
+        assert!(dao.approved_keystores.contains(&keystore_key(1)));
+        assert!(dao.pending_proposals.get(&keystore_key(1)).is_none());
+        assert_eq!(dao.get_proposal_executable_at(id), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Execution delay has not passed")]
+    fn execute_proposal_rejects_premature_execution() {
+        let (mut dao, id) = approved_with_delay();
+        at(outsider(), 1_000 + DAY_NS - 1);
+        dao.execute_proposal(id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Proposal is not approved")]
+    fn execute_proposal_rejects_pending_proposal() {
+        let mut dao = fresh_dao();
+        at(member_a(), 1_000);
+        let id = pending_proposal(&mut dao, keystore_key(1));
+        dao.execute_proposal(id);
+    }
+
+    #[test]
+    fn owner_vetoes_approved_proposal() {
+        let (mut dao, id) = approved_with_delay();
+        testing_env!(ctx("owner.near".parse().unwrap()).block_timestamp(1_000 + DAY_NS / 2).build());
+        dao.veto_proposal(id);
+
+        assert_eq!(dao.get_proposal(id).unwrap().status, ProposalStatus::Rejected);
+        assert_eq!(dao.get_proposal_executable_at(id), None);
+        assert!(dao.pending_proposals.get(&keystore_key(1)).is_none());
+        assert!(!dao.approved_keystores.contains(&keystore_key(1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only owner can call this method")]
+    fn veto_proposal_is_owner_only() {
+        let (mut dao, id) = approved_with_delay();
+        at(member_a(), 1_000);
+        dao.veto_proposal(id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Voting period has not ended")]
+    fn expire_proposal_rejects_live_proposal() {
+        let mut dao = fresh_dao();
+        at(outsider(), 1_000);
+        let id = pending_proposal(&mut dao, keystore_key(1));
+        at(outsider(), 1_000 + DEFAULT_VOTING_PERIOD_NS);
+        dao.expire_proposal(id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Proposal is not pending")]
+    fn expire_proposal_rejects_executed_proposal() {
+        let mut dao = fresh_dao();
+        at(member_a(), 1_000);
+        let id = pending_proposal(&mut dao, keystore_key(1));
+        dao.vote(id, true);
+        at(member_b(), 1_000);
+        dao.vote(id, true);
+        assert_eq!(dao.get_proposal(id).unwrap().status, ProposalStatus::Executed);
+        assert!(dao.get_active_proposals().is_empty());
+
+        at(outsider(), 1_000 + DEFAULT_VOTING_PERIOD_NS + 1);
+        dao.expire_proposal(id);
+    }
+
+    // ===== Keystore proposal vote counting =====
+    //
+    // Proposals start with zero votes (`submit_keystore_registration`
+    // does not pre-seed `votes_for`), so approval needs
+    // `approval_threshold` genuine approve votes from current members.
+
+    #[test]
+    fn keystore_proposal_needs_threshold_approvals() {
+        let mut dao = fresh_dao();
+        assert_eq!(dao.approval_threshold, 2);
+        at(member_a(), 1_000);
+        let id = pending_proposal(&mut dao, keystore_key(1));
+        assert_eq!(dao.get_proposal(id).unwrap().votes_for, 0);
+
+        dao.vote(id, true);
+        let proposal = dao.get_proposal(id).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Pending);
+        assert_eq!(proposal.votes_for, 1);
+        assert!(!dao.approved_keystores.contains(&keystore_key(1)));
+
+        at(member_b(), 1_000);
+        dao.vote(id, true);
+        assert_eq!(dao.get_proposal(id).unwrap().status, ProposalStatus::Executed);
+        assert!(dao.approved_keystores.contains(&keystore_key(1)));
+    }
+
+    #[test]
+    fn keystore_proposal_rejected_before_threshold() {
+        let mut dao = fresh_dao();
+        at(member_a(), 1_000);
+        let id = pending_proposal(&mut dao, keystore_key(1));
+
+        // 2 members, threshold 2: one reject makes approval unreachable
+        dao.vote(id, false);
+        let proposal = dao.get_proposal(id).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Rejected);
+        assert_eq!((proposal.votes_for, proposal.votes_against), (0, 1));
+        assert!(!dao.approved_keystores.contains(&keystore_key(1)));
+    }
+
+    #[test]
+    fn keystore_proposal_rejected_by_blocking_minority() {
+        let mut dao = fresh_dao_5();
+        assert_eq!(dao.approval_threshold, 3);
+        let m = |s: &str| -> AccountId { s.parse().unwrap() };
+        at(m("m1.near"), 1_000);
+        let id = pending_proposal(&mut dao, keystore_key(1));
+
+        dao.vote(id, true);
+        at(m("m2.near"), 1_000);
+        dao.vote(id, false);
+        at(m("m3.near"), 1_000);
+        dao.vote(id, false);
+        assert_eq!(dao.get_proposal(id).unwrap().status, ProposalStatus::Pending);
+
+        // Third reject leaves at most 2 possible approvals < threshold 3
+        at(m("m4.near"), 1_000);
+        dao.vote(id, false);
+        assert_eq!(dao.get_proposal(id).unwrap().status, ProposalStatus::Rejected);
+        assert!(!dao.approved_keystores.contains(&keystore_key(1)));
+    }
 }
+
@@ -1,6 +1,6 @@
 use crate::*;
 use near_sdk::borsh::BorshDeserialize;
-use near_sdk::collections::{LookupMap, UnorderedSet};
+use near_sdk::collections::{LookupMap, UnorderedMap, UnorderedSet};
 
 // ============================================================
 // V0 → V1 (legacy: rtmr3 → full ApprovedMeasurements)
@@ -76,15 +76,14 @@ pub struct KeystoreDaoV1 {
 // V2 is the shape currently live on `dao.outlayer.{testnet,near}`: the
 // full vault-registry struct (cessation + vault-code whitelist +
 // verified/banned + vault-version multisig) carrying a SINGLE
-// `quote_collateral: Option<String>`. This `migrate()` replaces that
-// single Option with a multi-slot `collaterals: Vec<String>` (one slot
-// per platform/FMSPC) so a mixed Phala + self-hosted fleet can register.
+// `quote_collateral: Option<String>`. V3 replaces that single Option
+// with a multi-slot `collaterals: Vec<String>` (one slot per
+// platform/FMSPC) so a mixed Phala + self-hosted fleet can register.
 //
 // Field order MUST match the current on-chain serialized layout exactly
-// (same as the `KeystoreDao` struct in lib.rs, with `quote_collateral`
-// in place of `collaterals`). Every other field is carried through
-// verbatim — the vault registry is already on-chain and is NOT
-// re-seeded here.
+// (same as V3 below, with `quote_collateral` in place of `collaterals`).
+// Every other field is carried through verbatim — the vault registry is
+// already on-chain and is NOT re-seeded here.
 
 #[derive(BorshDeserialize)]
 #[borsh(crate = "near_sdk::borsh")]
@@ -112,23 +111,125 @@ pub struct KeystoreDaoV2 {
     pub vault_version_approval_args: LookupMap<Base58CryptoHash, ApprovalArgs>,
 }
 
+// ============================================================
+// V3 → current (proposal expiry, vote ledger, weighted voting,
+// execution timelock)
+// ============================================================
+//
+// V3 is the multi-collateral layout: V2 with `collaterals` in place of
+// `quote_collateral`. A deployment that already ran the V2 → V3
+// `migrate()` holds it; otherwise `migrate()` reaches it from V2 via
+// `From`. The current layout appends `voting_period_ns`,
+// `pending_proposals`, `proposal_voters`, `member_weights`,
+// `execution_delay_ns` and `proposal_executable_at`.
+
+#[derive(BorshDeserialize)]
+#[borsh(crate = "near_sdk::borsh")]
+pub struct KeystoreDaoV3 {
+    pub dao_members: UnorderedSet<AccountId>,
+    pub approval_threshold: u32,
+    pub owner_id: AccountId,
+    pub init_account_id: AccountId,
+    pub mpc_contract_id: AccountId,
+    pub proposals: LookupMap<u64, KeystoreProposal>,
+    pub next_proposal_id: u64,
+    pub votes: LookupMap<(u64, AccountId), bool>,
+    pub approved_keystores: UnorderedSet<PublicKey>,
+    pub approved_measurements: Vec<ApprovedMeasurements>,
+    pub collaterals: Vec<String>,
+    // ----- v2: vault registry -----
+    pub ceased_operations: bool,
+    pub approved_vault_code_hashes: UnorderedSet<Base58CryptoHash>,
+    pub vault_versions: LookupMap<Base58CryptoHash, VaultVersionInfo>,
+    pub verified_vaults: UnorderedSet<AccountId>,
+    pub banned_vaults: UnorderedSet<AccountId>,
+    // ----- v3: vault-version multisig -----
+    pub vault_version_votes: LookupMap<VaultVersionAction, Vec<AccountId>>,
+    pub vault_version_approval_args: LookupMap<Base58CryptoHash, ApprovalArgs>,
+}
+
+impl From<KeystoreDaoV2> for KeystoreDaoV3 {
+    fn from(old: KeystoreDaoV2) -> Self {
+        Self {
+            dao_members: old.dao_members,
+            approval_threshold: old.approval_threshold,
+            owner_id: old.owner_id,
+            init_account_id: old.init_account_id,
+            mpc_contract_id: old.mpc_contract_id,
+            proposals: old.proposals,
+            next_proposal_id: old.next_proposal_id,
+            votes: old.votes,
+            approved_keystores: old.approved_keystores,
+            approved_measurements: old.approved_measurements,
+            // Move the single cached collateral into slot 0; owner adds
+            // others (self-hosted FMSPC) via `update_collateral(c, 1)`.
+            collaterals: old.quote_collateral.map(|c| vec![c]).unwrap_or_default(),
+            ceased_operations: old.ceased_operations,
+            approved_vault_code_hashes: old.approved_vault_code_hashes,
+            vault_versions: old.vault_versions,
+            verified_vaults: old.verified_vaults,
+            banned_vaults: old.banned_vaults,
+            vault_version_votes: old.vault_version_votes,
+            vault_version_approval_args: old.vault_version_approval_args,
+        }
+    }
+}
+
 #[near_bindgen]
 impl KeystoreDao {
-    /// Migrate the live (vault-registry) state to the multi-collateral
-    /// layout: `quote_collateral: Option<String>` → `collaterals:
-    /// Vec<String>`. An existing `Some(c)` (e.g. the Phala 20a06f000000
-    /// collateral) is carried into slot 0; `None` becomes an empty vec.
-    /// The owner then adds the self-hosted FMSPC via
-    /// `update_collateral(collateral, 1)`. All vault-registry fields are
-    /// preserved verbatim.
+    /// Migrate the live state to the current layout in one step.
     ///
-    /// V0 → V1 and V1 → V2 are no longer reachable from this method;
-    /// `dao.outlayer.{testnet,near}` already migrated through those and
-    /// the old structs above remain only for archaeological reference.
+    /// Reads the live V2 (single `quote_collateral`) state and moves an
+    /// existing `Some(c)` collateral (e.g. the Phala 20a06f000000
+    /// collateral) into `collaterals[0]`; V3 state, from an already-run
+    /// V2 → V3 migration, is read as is. Then:
+    ///
+    /// * `voting_period_ns` defaults to `DEFAULT_VOTING_PERIOD_NS` when
+    ///   not given. Existing `Pending` proposals are indexed into
+    ///   `pending_proposals` so they expire, block duplicate submissions
+    ///   and show up in `get_active_proposals` like new ones.
+    /// * `proposal_voters` is rebuilt from `votes` for the current DAO
+    ///   members — the only voters that can be looked up, since `votes`
+    ///   can't be enumerated. Votes of members removed before this
+    ///   migration stay counted in `votes_for` / `votes_against` but are
+    ///   not listed by `get_proposal_votes`.
+    /// * `member_weights` starts empty, i.e. every member weighs 1, and
+    ///   there is no execution delay.
+    ///
+    /// Both scans are over all proposal ids (× members for the vote
+    /// ledger) — keystore registrations are rare, so they stay far below
+    /// the gas limit. V0 → V1 and V1 → V2 are no longer reachable from
+    /// this method; the old structs above remain only for archaeological
+    /// reference.
     #[private]
     #[init(ignore_state)]
-    pub fn migrate() -> Self {
-        let old: KeystoreDaoV2 = env::state_read().expect("failed to read V2 state");
+    pub fn migrate(voting_period_ns: Option<u64>) -> Self {
+        let bytes = env::storage_read(b"STATE").expect("failed to read contract state");
+        let old = KeystoreDaoV2::try_from_slice(&bytes)
+            .map(KeystoreDaoV3::from)
+            .or_else(|_| KeystoreDaoV3::try_from_slice(&bytes))
+            .expect("failed to read V2 or V3 state");
+        let voting_period_ns = voting_period_ns.unwrap_or(DEFAULT_VOTING_PERIOD_NS);
+        assert!(voting_period_ns > 0, "voting_period_ns must be positive");
+
+        let members = old.dao_members.to_vec();
+        let mut pending_proposals = UnorderedMap::new(StorageKey::PendingProposals);
+        let mut proposal_voters = LookupMap::new(StorageKey::ProposalVoters);
+        for id in 1..old.next_proposal_id {
+            if let Some(proposal) = old.proposals.get(&id) {
+                if proposal.status == ProposalStatus::Pending {
+                    pending_proposals.insert(&proposal.public_key, &id);
+                }
+            }
+            let voters: Vec<AccountId> = members
+                .iter()
+                .filter(|member| old.votes.contains_key(&(id, (*member).clone())))
+                .cloned()
+                .collect();
+            if !voters.is_empty() {
+                proposal_voters.insert(&id, &voters);
+            }
+        }
 
         Self {
             dao_members: old.dao_members,
@@ -141,9 +242,7 @@ impl KeystoreDao {
             votes: old.votes,
             approved_keystores: old.approved_keystores,
             approved_measurements: old.approved_measurements,
-            // Move the single cached collateral into slot 0; owner adds
-            // others (self-hosted FMSPC) via `update_collateral(c, 1)`.
-            collaterals: old.quote_collateral.map(|c| vec![c]).unwrap_or_default(),
+            collaterals: old.collaterals,
             // ----- v2: carried through verbatim -----
             ceased_operations: old.ceased_operations,
             approved_vault_code_hashes: old.approved_vault_code_hashes,
@@ -153,6 +252,18 @@ impl KeystoreDao {
             // ----- v3: carried through verbatim -----
             vault_version_votes: old.vault_version_votes,
             vault_version_approval_args: old.vault_version_approval_args,
+            // ----- v4: proposal expiry -----
+            voting_period_ns,
+            pending_proposals,
+            // ----- v5: proposal vote ledger, weighted voting -----
+            proposal_voters,
+            // Every existing member keeps the default weight of 1, so
+            // `approval_threshold` is unchanged.
+            member_weights: LookupMap::new(StorageKey::MemberWeights),
+            // No delay: proposals keep executing on the deciding vote
+            // until the owner calls `set_execution_delay`.
+            execution_delay_ns: DEFAULT_EXECUTION_DELAY_NS,
+            proposal_executable_at: LookupMap::new(StorageKey::ProposalExecutableAt),
         }
     }
 }
@@ -0,0 +1,384 @@
+//! Short-lived cache of operator access-key lookups
+//!
+//! Every lookup is a NEAR RPC `view_access_key` query. Workers that
+//! re-register in bursts (restarts, keystore redeploys) would otherwise turn
+//! into one RPC round-trip each and push us into RPC rate limits.
+//!
+//! The lookup tells a missing key (`Ok(false)`, RPC `UNKNOWN_ACCESS_KEY`)
+//! apart from a missing account ([`TeeAuthError::AccountNotFound`], RPC
+//! `UNKNOWN_ACCOUNT`), so a typo'd operator account isn't reported as an
+//! unregistered worker key. Any other RPC failure is `NearRpcError`.
+//!
+//! Results are cached per `(account_id, public_key)`. A key that exists stays
+//! cached for `positive_ttl`; a missing key only for `negative_ttl`, so a key
+//! added on-chain moments ago becomes visible on the next attempt. Removal of
+//! a key is picked up after at most `positive_ttl`. Errors are never cached.
+//! When full, the least recently used entry is evicted.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::tee_challenge::TeeAuthError;
+
+/// Default number of `(account, key)` pairs kept
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// How long a found key is trusted
+pub const DEFAULT_POSITIVE_TTL: Duration = Duration::from_secs(30);
+
+/// How long a missing key is remembered
+pub const DEFAULT_NEGATIVE_TTL: Duration = Duration::from_secs(2);
+
+/// Lookups per check; a key added moments ago may not be visible at final yet
+const CHECK_ATTEMPTS: u32 = 3;
+
+/// Pause between lookups of a check
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+struct CachedLookup {
+    exists: bool,
+    expires_at: Instant,
+    last_used: u64,
+}
+
+struct Inner {
+    entries: HashMap<(String, String), CachedLookup>,
+    /// Monotonic use counter for LRU ordering
+    tick: u64,
+}
+
+/// LRU + TTL cache of `view_access_key` results
+pub struct AccessKeyCache {
+    /// Reused for every RPC lookup
+    http: reqwest::Client,
+    capacity: usize,
+    positive_ttl: Duration,
+    negative_ttl: Duration,
+    inner: Mutex<Inner>,
+}
+
+impl Default for AccessKeyCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY, DEFAULT_POSITIVE_TTL, DEFAULT_NEGATIVE_TTL)
+    }
+}
+
+impl AccessKeyCache {
+    pub fn new(capacity: usize, positive_ttl: Duration, negative_ttl: Duration) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            capacity: capacity.max(1),
+            positive_ttl,
+            negative_ttl,
+            inner: Mutex::new(Inner {
+                entries: HashMap::new(),
+                tick: 0,
+            }),
+        }
+    }
+
+    fn get(&self, account_id: &str, public_key: &str, now: Instant) -> Option<bool> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.tick += 1;
+        let tick = inner.tick;
+        let key = (account_id.to_string(), public_key.to_string());
+        match inner.entries.get_mut(&key) {
+            Some(entry) if entry.expires_at > now => {
+                entry.last_used = tick;
+                Some(entry.exists)
+            }
+            Some(_) => {
+                inner.entries.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn insert(&self, account_id: &str, public_key: &str, exists: bool, now: Instant) {
+        let ttl = if exists { self.positive_ttl } else { self.negative_ttl };
+        let mut inner = self.inner.lock().unwrap();
+        inner.tick += 1;
+        let tick = inner.tick;
+        let key = (account_id.to_string(), public_key.to_string());
+
+        if !inner.entries.contains_key(&key) && inner.entries.len() >= self.capacity {
+            inner.entries.retain(|_, e| e.expires_at > now);
+            if inner.entries.len() >= self.capacity {
+                let lru = inner
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, e)| e.last_used)
+                    .map(|(k, _)| k.clone());
+                if let Some(lru) = lru {
+                    inner.entries.remove(&lru);
+                }
+            }
+        }
+
+        inner.entries.insert(
+            key,
+            CachedLookup {
+                exists,
+                expires_at: now + ttl,
+                last_used: tick,
+            },
+        );
+    }
+
+    /// Return the cached result, or run `fetch` and cache what it returns
+    ///
+    /// The lock is not held across `fetch`, so concurrent misses for the same
+    /// key may each query RPC once; the last result wins.
+    pub async fn get_or_fetch<F, Fut, E>(&self, account_id: &str, public_key: &str, fetch: F) -> Result<bool, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<bool, E>>,
+    {
+        if let Some(exists) = self.get(account_id, public_key, Instant::now()) {
+            return Ok(exists);
+        }
+        let exists = fetch().await?;
+        self.insert(account_id, public_key, exists, Instant::now());
+        Ok(exists)
+    }
+}
+
+/// [`check_access_key_with_retry`] behind `cache`
+pub async fn check_access_key_cached(
+    cache: &AccessKeyCache,
+    rpc_url: &str,
+    account_id: &str,
+    public_key: &str,
+) -> Result<bool, TeeAuthError> {
+    cache
+        .get_or_fetch(account_id, public_key, || {
+            check_access_key_with_retry(&cache.http, rpc_url, account_id, public_key)
+        })
+        .await
+}
+
+/// [`check_access_key_on_contract`], retried while the key is missing or RPC fails
+///
+/// A missing account is returned at once: waiting doesn't create it.
+pub async fn check_access_key_with_retry(
+    client: &reqwest::Client,
+    rpc_url: &str,
+    account_id: &str,
+    public_key: &str,
+) -> Result<bool, TeeAuthError> {
+    let mut attempt = 1;
+    loop {
+        let result = check_access_key_on_contract(client, rpc_url, account_id, public_key).await;
+        match result {
+            Ok(true) | Err(TeeAuthError::AccountNotFound(_)) => return result,
+            _ if attempt >= CHECK_ATTEMPTS => return result,
+            _ => {
+                attempt += 1;
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+        }
+    }
+}
+
+/// Whether `public_key` is an access key of `account_id`, at final
+pub async fn check_access_key_on_contract(
+    client: &reqwest::Client,
+    rpc_url: &str,
+    account_id: &str,
+    public_key: &str,
+) -> Result<bool, TeeAuthError> {
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": "dontcare",
+        "method": "query",
+        "params": {
+            "request_type": "view_access_key",
+            "finality": "final",
+            "account_id": account_id,
+            "public_key": public_key,
+        },
+    });
+    let response: serde_json::Value = client
+        .post(rpc_url)
+        .json(&request)
+        .send()
+        .await
+        .map_err(|e| TeeAuthError::NearRpcError(e.to_string()))?
+        .json()
+        .await
+        .map_err(|e| TeeAuthError::NearRpcError(format!("invalid RPC response: {e}")))?;
+    parse_view_access_key(&response, account_id)
+}
+
+/// Map a `view_access_key` JSON-RPC response to found / missing key / error
+fn parse_view_access_key(response: &serde_json::Value, account_id: &str) -> Result<bool, TeeAuthError> {
+    if let Some(error) = response.get("error") {
+        return match error.pointer("/cause/name").and_then(|name| name.as_str()) {
+            Some("UNKNOWN_ACCESS_KEY") => Ok(false),
+            Some("UNKNOWN_ACCOUNT") => Err(TeeAuthError::AccountNotFound(account_id.to_string())),
+            _ => Err(TeeAuthError::NearRpcError(error.to_string())),
+        };
+    }
+    match response.get("result") {
+        Some(result) if result.get("permission").is_some() => Ok(true),
+        Some(result) => Err(TeeAuthError::NearRpcError(format!("unexpected view_access_key result: {result}"))),
+        None => Err(TeeAuthError::NearRpcError("RPC response has neither result nor error".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Stand-in for the RPC client: answers `exists` and counts requests
+    struct MockRpc {
+        exists: bool,
+        requests: AtomicUsize,
+    }
+
+    impl MockRpc {
+        fn new(exists: bool) -> Self {
+            Self { exists, requests: AtomicUsize::new(0) }
+        }
+
+        async fn view_access_key(&self) -> anyhow::Result<bool> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            Ok(self.exists)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_second_call_within_ttl_skips_rpc() {
+        let cache = AccessKeyCache::default();
+        let rpc = MockRpc::new(true);
+
+        for _ in 0..2 {
+            let exists = cache
+                .get_or_fetch("worker.testnet", "ed25519:abc", || rpc.view_access_key())
+                .await
+                .unwrap();
+            assert!(exists);
+        }
+        assert_eq!(rpc.requests.load(Ordering::SeqCst), 1);
+
+        // Different key on the same account is a separate entry
+        cache
+            .get_or_fetch("worker.testnet", "ed25519:def", || rpc.view_access_key())
+            .await
+            .unwrap();
+        assert_eq!(rpc.requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_errors_not_cached() {
+        let cache = AccessKeyCache::default();
+        let result = cache
+            .get_or_fetch("worker.testnet", "ed25519:abc", || async { anyhow::bail!("rpc down") })
+            .await;
+        assert!(result.is_err());
+
+        let rpc = MockRpc::new(true);
+        cache
+            .get_or_fetch("worker.testnet", "ed25519:abc", || rpc.view_access_key())
+            .await
+            .unwrap();
+        assert_eq!(rpc.requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_negative_results_expire_sooner() {
+        let cache = AccessKeyCache::new(16, Duration::from_secs(30), Duration::from_secs(2));
+        let start = Instant::now();
+        cache.insert("worker.testnet", "ed25519:found", true, start);
+        cache.insert("worker.testnet", "ed25519:missing", false, start);
+
+        let later = start + Duration::from_secs(3);
+        assert_eq!(cache.get("worker.testnet", "ed25519:found", later), Some(true));
+        assert_eq!(cache.get("worker.testnet", "ed25519:missing", later), None);
+
+        let much_later = start + Duration::from_secs(31);
+        assert_eq!(cache.get("worker.testnet", "ed25519:found", much_later), None);
+    }
+
+    #[test]
+    fn test_parse_view_access_key_responses() {
+        let found = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": "dontcare",
+            "result": {
+                "nonce": 85,
+                "permission": "FullAccess",
+                "block_height": 19884918,
+                "block_hash": "GGJQ8yjmo7aEoj8ZpAhGehnq9BSWFx4xswHYzDwwAP2n"
+            }
+        });
+        assert_eq!(parse_view_access_key(&found, "operator.testnet"), Ok(true));
+
+        let missing_key = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": "dontcare",
+            "error": {
+                "name": "HANDLER_ERROR",
+                "cause": {
+                    "name": "UNKNOWN_ACCESS_KEY",
+                    "info": { "public_key": "ed25519:abc", "block_height": 1, "block_hash": "x" }
+                },
+                "code": -32000,
+                "message": "Server error"
+            }
+        });
+        assert_eq!(parse_view_access_key(&missing_key, "operator.testnet"), Ok(false));
+
+        let missing_account = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": "dontcare",
+            "error": {
+                "name": "HANDLER_ERROR",
+                "cause": {
+                    "name": "UNKNOWN_ACCOUNT",
+                    "info": { "requested_account_id": "operatr.testnet", "block_height": 1, "block_hash": "x" }
+                },
+                "code": -32000,
+                "message": "Server error"
+            }
+        });
+        assert_eq!(
+            parse_view_access_key(&missing_account, "operatr.testnet"),
+            Err(TeeAuthError::AccountNotFound("operatr.testnet".to_string()))
+        );
+
+        let other = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": "dontcare",
+            "error": {
+                "name": "HANDLER_ERROR",
+                "cause": { "name": "UNAVAILABLE_SHARD", "info": {} },
+                "code": -32000,
+                "message": "Server error"
+            }
+        });
+        assert!(matches!(
+            parse_view_access_key(&other, "operator.testnet"),
+            Err(TeeAuthError::NearRpcError(_))
+        ));
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let cache = AccessKeyCache::new(2, Duration::from_secs(30), Duration::from_secs(2));
+        let now = Instant::now();
+        cache.insert("a.testnet", "k", true, now);
+        cache.insert("b.testnet", "k", true, now);
+        // Touch a, so b is the LRU entry
+        assert_eq!(cache.get("a.testnet", "k", now), Some(true));
+        cache.insert("c.testnet", "k", true, now);
+
+        assert_eq!(cache.get("a.testnet", "k", now), Some(true));
+        assert_eq!(cache.get("b.testnet", "k", now), None);
+        assert_eq!(cache.get("c.testnet", "k", now), Some(true));
+    }
+}
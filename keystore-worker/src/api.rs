@@ -43,11 +43,6 @@ use tower_http::trace::TraceLayer;
 
 use crate::attestation::Attestation;
 
-/// In-memory TEE challenge entry (for challenge-response protocol)
-struct TeeChallenge {
-    created_at: std::time::Instant,
-}
-
 /// In-memory TEE session entry
 #[derive(Clone)]
 struct TeeSession {
@@ -80,6 +75,12 @@ pub struct MpcContext {
     pub mpc_ckd_config: crate::mpc_ckd::MpcCkdConfig,
     pub keystore_dao_signer: near_crypto::InMemorySigner,
     pub signer_nonce_lock: tokio::sync::Mutex<()>,
+    /// Ark app secrets by project_id (see `/ckd/app-key`). Each project costs one
+    /// MPC tx per keystore process; concurrent first requests share it through the
+    /// per-project `OnceCell`. A failed derivation leaves the cell empty for a retry.
+    pub ark_app_secrets: tokio::sync::Mutex<
+        std::collections::HashMap<String, std::sync::Arc<tokio::sync::OnceCell<[u8; 48]>>>,
+    >,
 }
 
 /// Application state shared across all handlers
@@ -111,8 +112,10 @@ pub struct AppState {
     /// registration completes; the registration task runs async and
     /// populates this AFTER cloning.
     pub mpc_context: std::sync::Arc<std::sync::OnceLock<MpcContext>>,
-    /// In-memory TEE challenge store: challenge_hex -> TeeChallenge
-    tee_challenges: std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, TeeChallenge>>>,
+    /// Issued TEE challenges, each accepted once within its TTL
+    tee_challenges: std::sync::Arc<crate::tee_challenge::ChallengeStore>,
+    /// Cached operator access-key lookups for TEE registration
+    access_keys: std::sync::Arc<crate::access_key_cache::AccessKeyCache>,
     /// In-memory TEE session store: session_id -> TeeSession
     tee_sessions: std::sync::Arc<std::sync::Mutex<std::collections::HashMap<uuid::Uuid, TeeSession>>>,
 }
@@ -140,7 +143,8 @@ impl AppState {
             near_client: near_client.map(std::sync::Arc::new),
             is_ready: std::sync::Arc::new(std::sync::atomic::AtomicBool::new(is_ready)),
             mpc_context: std::sync::Arc::new(std::sync::OnceLock::new()),
-            tee_challenges: std::sync::Arc::new(std::sync::Mutex::new(std::collections::HashMap::new())),
+            tee_challenges: std::sync::Arc::new(crate::tee_challenge::ChallengeStore::default()),
+            access_keys: std::sync::Arc::new(crate::access_key_cache::AccessKeyCache::default()),
             tee_sessions: std::sync::Arc::new(std::sync::Mutex::new(std::collections::HashMap::new())),
         }
     }
@@ -183,6 +187,7 @@ impl AppState {
             mpc_ckd_config: config,
             keystore_dao_signer,
             signer_nonce_lock: tokio::sync::Mutex::new(()),
+            ark_app_secrets: tokio::sync::Mutex::new(std::collections::HashMap::new()),
         };
         if let Err(rejected) = self.mpc_context.set(new_ctx) {
             let prev = self
@@ -400,6 +405,10 @@ pub struct DecryptRequest {
     /// Profile name (e.g., "default", "production")
     pub profile: String,
 
+    /// Pinned secret version (`set_secrets`); None reads the current profile
+    #[serde(default)]
+    pub version: Option<u32>,
+
     /// Owner account ID (who owns the secrets)
     pub owner: String,
 
@@ -651,6 +660,28 @@ pub struct VrfGenerateResponse {
     pub signature_hex: String,
 }
 
+/// Request for an ark's MPC-derived app key
+#[derive(Debug, Deserialize)]
+pub struct CkdAppKeyRequest {
+    /// Project the ark runs in; fixes the derivation path (set by the worker, not the ark)
+    pub project_id: String,
+    /// MPC domain to derive in
+    pub domain_id: u64,
+    /// Ark's BLS12-381 G1 public key (`bls12381g1:<base58>`) the result is encrypted to
+    pub app_public_key: String,
+    /// TEE attestation proving worker identity
+    pub attestation: Attestation,
+}
+
+/// MPC CKD output, decryptable only with the ark's app private key
+#[derive(Debug, Serialize)]
+pub struct CkdAppKeyResponse {
+    /// BLS12-381 G1 point (`bls12381g1:<base58>`)
+    pub big_y: String,
+    /// BLS12-381 G1 point (`bls12381g1:<base58>`)
+    pub big_c: String,
+}
+
 /// Response with VRF public key
 #[derive(Debug, Serialize)]
 pub struct VrfPublicKeyResponse {
@@ -1130,6 +1161,7 @@ pub fn create_router(state: AppState) -> Router {
         .route("/storage/encrypt", post(storage_encrypt_handler))
         .route("/storage/decrypt", post(storage_decrypt_handler))
         .route("/vrf/generate", post(vrf_generate_handler))
+        .route("/ckd/app-key", post(ckd_app_key_handler))
         .layer(middleware::from_fn_with_state(
             state.clone(),
             tee_session_middleware,
@@ -1328,6 +1360,7 @@ async fn pubkey_handler(
         "NEAR_MAX_INSTRUCTIONS",
         "NEAR_MAX_MEMORY_MB",
         "NEAR_MAX_EXECUTION_SECONDS",
+        "NEAR_MAX_OUTPUT_BYTES",
         "NEAR_REQUEST_ID",
         "NEAR_NETWORK_ID",
         "OUTLAYER_PROJECT_ID",
@@ -1532,13 +1565,28 @@ async fn decrypt_handler(
         .ok_or_else(|| ApiError::InternalError("NEAR client not configured".to_string()))?;
 
     let accessor_json = accessor_to_contract_json(&req.accessor);
-    let combined = near_client
-        .get_secret_with_vault(accessor_json, &req.profile, &req.owner)
+    let mut combined = near_client
+        .get_secret_with_vault(accessor_json.clone(), &req.profile, &req.owner)
         .await
         .map_err(|e| {
             tracing::error!(task_id = %task_id_str, error = %e, "Failed to read secrets from contract");
             ApiError::InternalError(format!("Failed to read secrets from contract: {}", e))
         })?;
+
+    // Pinned version: same vault binding, ciphertext + access rules of that version
+    if let (Some(version), Some(_)) = (req.version, &combined.profile) {
+        combined.profile = near_client
+            .get_secret_version(accessor_json, &req.profile, &req.owner, version)
+            .await
+            .map_err(|e| {
+                tracing::error!(task_id = %task_id_str, error = %e, version, "Failed to read secret version from contract");
+                ApiError::InternalError(format!("Failed to read secret version from contract: {}", e))
+            })?;
+        if combined.profile.is_none() {
+            tracing::warn!(task_id = %task_id_str, profile = %req.profile, owner = %req.owner, version, "Secret version not found in contract");
+            return Err(ApiError::BadRequest(format!("Secret version {} not found in contract", version)));
+        }
+    }
     let vault_id_str = combined.vault_id;
 
     let secret_profile = combined.profile.ok_or_else(|| {
@@ -1806,6 +1854,82 @@ async fn vrf_generate_handler(
     Ok(Json(VrfGenerateResponse { output_hex, signature_hex }))
 }
 
+/// POST /ckd/app-key — MPC-derived app key for an ark (worker-only)
+///
+/// The project's secret comes from keystore-dao `request_key` with the project's derivation
+/// path, so it needs the MPC context of a TEE-registered keystore. It is derived once per
+/// project and kept in TEE memory (`MpcContext::ark_app_secrets`); every request after the
+/// first is answered without an on-chain call, encrypted to that request's `app_public_key`.
+async fn ckd_app_key_handler(
+    State(state): State<AppState>,
+    Json(req): Json<CkdAppKeyRequest>,
+) -> Result<Json<CkdAppKeyResponse>, ApiError> {
+    if !state.is_ready() {
+        return Err(ApiError::Unauthorized(
+            "Keystore not ready. Waiting for DAO approval and master key from MPC.".to_string()
+        ));
+    }
+
+    if req.project_id.is_empty() {
+        return Err(ApiError::BadRequest("project_id must not be empty".to_string()));
+    }
+    if !req.app_public_key.starts_with("bls12381g1:") {
+        return Err(ApiError::BadRequest(
+            "app_public_key must be a BLS12-381 G1 key (bls12381g1:<base58>)".to_string()
+        ));
+    }
+
+    crate::attestation::verify_attestation(
+        &req.attestation,
+        &state.config.tee_mode,
+        &state.expected_measurements,
+    )
+    .map_err(|e| {
+        tracing::warn!(error = %e, "App key attestation verification failed");
+        ApiError::Unauthorized(format!("Attestation verification failed: {}", e))
+    })?;
+
+    let ctx = state.mpc_context.get().ok_or_else(|| {
+        ApiError::Forbidden(
+            "App keys are only available from a TEE keystore registered with keystore-dao".to_string()
+        )
+    })?;
+
+    // Secrets are derived and verified against the configured MPC domain only
+    if req.domain_id != ctx.mpc_ckd_config.mpc_domain_id {
+        return Err(ApiError::BadRequest(format!(
+            "domain_id must be the keystore's MPC domain {}",
+            ctx.mpc_ckd_config.mpc_domain_id
+        )));
+    }
+
+    let mpc_client = crate::mpc_ckd::MpcCkdClient::new(ctx.mpc_ckd_config.clone());
+    let cell = {
+        let mut secrets = ctx.ark_app_secrets.lock().await;
+        secrets.entry(req.project_id.clone()).or_default().clone()
+    };
+    let secret = cell
+        .get_or_try_init(|| {
+            mpc_client.request_ark_secret(&ctx.keystore_dao_signer, &req.project_id, &ctx.signer_nonce_lock)
+        })
+        .await
+        .map_err(|e| {
+            tracing::error!(project_id = %req.project_id, error = %e, "App key request failed");
+            ApiError::InternalError(format!("App key request failed: {:#}", e))
+        })?;
+
+    let response = mpc_client
+        .encrypt_app_key(secret, &req.app_public_key)
+        .map_err(|e| ApiError::BadRequest(format!("{:#}", e)))?;
+
+    tracing::info!(project_id = %req.project_id, domain = req.domain_id, "App key issued");
+
+    Ok(Json(CkdAppKeyResponse {
+        big_y: response.big_y,
+        big_c: response.big_c,
+    }))
+}
+
 /// GET /vrf/pubkey — Get VRF public key (public, no auth)
 async fn vrf_pubkey_handler(
     State(state): State<AppState>,
@@ -2525,6 +2649,7 @@ async fn add_generated_secret_handler(
         "NEAR_MAX_INSTRUCTIONS",
         "NEAR_MAX_MEMORY_MB",
         "NEAR_MAX_EXECUTION_SECONDS",
+        "NEAR_MAX_OUTPUT_BYTES",
         "NEAR_REQUEST_ID",
         "NEAR_NETWORK_ID",
         "OUTLAYER_PROJECT_ID",
@@ -3274,19 +3399,7 @@ fn verify_near_signature(
 async fn tee_challenge_handler(
     State(state): State<AppState>,
 ) -> Result<Json<serde_json::Value>, ApiError> {
-    let challenge = shared_tee_helpers::generate_challenge();
-
-    // Store challenge in memory with timestamp
-    {
-        let mut challenges = state.tee_challenges.lock().unwrap();
-
-        // Clean up expired challenges (>60 seconds old)
-        challenges.retain(|_, c| c.created_at.elapsed().as_secs() < 60);
-
-        challenges.insert(challenge.clone(), TeeChallenge {
-            created_at: std::time::Instant::now(),
-        });
-    }
+    let challenge = state.tee_challenges.issue();
 
     tracing::debug!("TEE challenge generated: {}...", &challenge[..16]);
 
@@ -3304,44 +3417,47 @@ struct RegisterTeeRequest {
 /// Register a TEE session after challenge-response verification
 ///
 /// 1. Verify challenge exists and is not expired
-/// 2. Verify ed25519 signature
-/// 3. Check public key exists on register-contract via NEAR RPC
+/// 2. Verify the signature (ed25519, ml-dsa-65 or secp256k1)
+/// 3. Check public key exists on the operator account via NEAR RPC (skipped if 2 failed)
 /// 4. Create session and return session_id
 async fn register_tee_handler(
     State(state): State<AppState>,
     Json(req): Json<RegisterTeeRequest>,
 ) -> Result<Json<serde_json::Value>, ApiError> {
-    // 1. Find and remove challenge (one-time use)
-    {
-        let mut challenges = state.tee_challenges.lock().unwrap();
-        let challenge = challenges.remove(&req.challenge).ok_or_else(|| {
-            ApiError::BadRequest("Invalid or expired challenge".to_string())
-        })?;
-
-        // Check expiration (60 seconds)
-        if challenge.created_at.elapsed().as_secs() > 60 {
-            return Err(ApiError::BadRequest("Challenge expired".to_string()));
-        }
-    }
+    use crate::tee_challenge::TeeAuthError;
 
-    // 2. Verify signature
-    shared_tee_helpers::verify_signature(&req.public_key, &req.challenge, &req.signature, state.config.tee_allowed_key_types)
-        .map_err(|e| ApiError::BadRequest(format!("Signature verification failed: {}", e)))?;
-
-    // 3. Check key on operator account via NEAR RPC (with retry for finality lag)
     let operator_account_id = state.config.operator_account_id.as_ref().ok_or_else(|| {
         ApiError::InternalError("OPERATOR_ACCOUNT_ID not configured on keystore".to_string())
     })?;
 
-    let key_exists = shared_tee_helpers::check_access_key_with_retry(
-        &state.config.near_rpc_url,
-        operator_account_id,
+    // 1-3. Consume challenge (one-time use, 60s TTL), verify signature, then check the key
+    // on the operator account (with retry for finality lag, cached briefly)
+    let outcome = crate::tee_challenge::verify_and_check(
+        &state.tee_challenges,
         &req.public_key,
+        &req.challenge,
+        &req.signature,
+        state.config.tee_allowed_key_types,
+        || {
+            crate::access_key_cache::check_access_key_cached(
+                &state.access_keys,
+                &state.config.near_rpc_url,
+                operator_account_id,
+                &req.public_key,
+            )
+        },
     )
     .await
-    .map_err(|e| ApiError::InternalError(format!("NEAR RPC check failed: {}", e)))?;
+    .map_err(|e| match e {
+        // A missing operator account is keystore misconfiguration, not a bad request
+        TeeAuthError::NearRpcError(_) | TeeAuthError::AccountNotFound(_) => ApiError::InternalError(e.to_string()),
+        _ => ApiError::BadRequest(e.to_string()),
+    })?;
 
-    if !key_exists {
+    if !outcome.signature_valid {
+        return Err(ApiError::BadRequest("Signature verification failed".to_string()));
+    }
+    if !outcome.key_registered {
         return Err(ApiError::Unauthorized(format!(
             "Public key {} not found on operator account {}",
             req.public_key, operator_account_id
@@ -6041,7 +6157,7 @@ mod tests {
             tee_mode: crate::config::TeeMode::None,
             operator_account_id: None,
             keystore_key_type: near_crypto::KeyType::ED25519,
-            tee_allowed_key_types: shared_tee_helpers::AllowedKeyTypes { ed25519: true, ml_dsa_65: true },
+            tee_allowed_key_types: crate::tee_challenge::TeeKeyTypes { ed25519: true, ml_dsa_65: true, secp256k1: false },
         };
         AppState::new(crate::crypto::Keystore::generate(), config, None)
     }
@@ -42,8 +42,8 @@ pub struct Config {
     pub keystore_key_type: near_crypto::KeyType,
 
     /// Which WORKER signature schemes this keystore accepts for TEE-session challenge-response.
-    /// Set via TEE_ALLOWED_KEY_TYPES, e.g. "ed25519,ml-dsa-65". Default: ed25519 only.
-    pub tee_allowed_key_types: shared_tee_helpers::AllowedKeyTypes,
+    /// Set via TEE_ALLOWED_KEY_TYPES, e.g. "ed25519,ml-dsa-65,secp256k1". Default: ed25519 only.
+    pub tee_allowed_key_types: crate::tee_challenge::TeeKeyTypes,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -117,7 +117,7 @@ impl Config {
         };
 
         // Which worker schemes this keystore admits for TEE-session auth (default ed25519).
-        let tee_allowed_key_types = shared_tee_helpers::AllowedKeyTypes::from_csv(
+        let tee_allowed_key_types = crate::tee_challenge::TeeKeyTypes::from_csv(
             &std::env::var("TEE_ALLOWED_KEY_TYPES").unwrap_or_else(|_| "ed25519".to_string()),
         );
 
@@ -32,6 +32,8 @@ mod types;
 mod utils;
 mod mpc_ckd;
 mod tee_registration;
+mod tee_challenge;
+mod access_key_cache;
 mod tdx_attestation;
 mod vault_verifier;
 
@@ -20,7 +20,8 @@
 //!
 //! 1. [`vault_load_locks`] (per-vault, used by [`add_customer`])
 //! 2. [`crate::api::MpcContext::signer_nonce_lock`] (process-wide,
-//!    held by `/sign-vault-verification` around tx broadcast)
+//!    held by `/sign-vault-verification` around tx broadcast and by
+//!    [`MpcCkdClient::request_ark_secret`] until its tx is final)
 //!
 //! No current code path takes both, but anyone adding a "verify and
 //! immediately load master" optimisation MUST take vault_load_locks
@@ -36,7 +37,9 @@ use near_jsonrpc_client::{methods, JsonRpcClient};
 use near_jsonrpc_primitives::types::query::QueryResponseKind;
 use near_primitives::transaction::{Action, FunctionCallAction, Transaction, TransactionV0};
 use near_primitives::types::{AccountId, Balance, BlockReference, Finality, FunctionArgs, Gas};
-use near_primitives::views::{QueryRequest, CallResult, FinalExecutionStatus};
+use near_primitives::views::{
+    CallResult, FinalExecutionOutcomeView, FinalExecutionStatus, QueryRequest, TxExecutionStatus,
+};
 use rand_core::OsRng;
 use serde::{Deserialize, Serialize};
 use sha2::Sha256;
@@ -52,6 +55,20 @@ const OUTPUT_SECRET_SIZE: usize = 32;
 // MPC app_id derivation prefix (must match MPC contract exactly)
 const APP_ID_DERIVATION_PREFIX: &str = "near-mpc v0.1.0 app_id derivation:";
 
+/// Tx status polls while waiting for a CKD answer (each waits up to the RPC timeout)
+const MPC_OUTCOME_POLLS: u32 = 10;
+
+/// Prefix of the derivation path of per-ark app keys (see [`ark_derivation_path`])
+const ARK_DERIVATION_PREFIX: &str = "outlayer-ark:";
+
+/// Derivation path of an ark's app key: `outlayer-ark:{project_id}`
+///
+/// Distinct from the default master (`""`) and per-vault paths (HMAC hex), so an ark
+/// can never be handed a key another flow derives.
+pub fn ark_derivation_path(project_id: &str) -> String {
+    format!("{}{}", ARK_DERIVATION_PREFIX, project_id)
+}
+
 /// Derive app_id the same way MPC contract does
 /// app_id = SHA3-256("{prefix}{predecessor_id},{derivation_path}")
 fn derive_app_id(predecessor_id: &str, derivation_path: &str) -> [u8; 32] {
@@ -440,6 +457,68 @@ impl MpcCkdClient {
         Ok(master_secret)
     }
 
+    /// Derive an ark's app secret: the MPC output for its [`ark_derivation_path`],
+    /// decrypted and pairing-verified inside the TEE.
+    ///
+    /// Signed as keystore-dao through its `request_key` proxy, like the default master.
+    /// The raw 48-byte G1 point is returned (not HKDF-stretched) so
+    /// [`Self::encrypt_app_key`] can hand the same secret to every execution of the
+    /// project without another on-chain call. `nonce_lock` guards the keystore-dao
+    /// signer's nonce; it is released once the tx is final, before MPC answers.
+    pub async fn request_ark_secret(
+        &self,
+        signer: &near_crypto::InMemorySigner,
+        project_id: &str,
+        nonce_lock: &tokio::sync::Mutex<()>,
+    ) -> Result<[u8; BLS12381G1_PUBLIC_KEY_SIZE]> {
+        let derivation_path = ark_derivation_path(project_id);
+        tracing::info!(
+            project_id,
+            domain = self.config.mpc_domain_id,
+            "Requesting ark app secret from MPC via keystore-dao proxy"
+        );
+
+        let (ephemeral_private_key, ephemeral_public_key) = self.generate_ephemeral_key();
+        let request_args = CkdRequestArgs {
+            request: CkdArgs {
+                derivation_path: derivation_path.clone(),
+                app_public_key: self.g1_to_near_format(ephemeral_public_key),
+                domain_id: self.config.mpc_domain_id,
+            },
+        };
+        let receiver_id = signer.account_id.clone();
+        let response = self
+            .call_mpc_contract_serialized(signer, &receiver_id, "request_key", request_args, nonce_lock)
+            .await?;
+
+        let app_id = derive_app_id(signer.account_id.as_str(), &derivation_path);
+        self.decrypt_secret_and_verify(response.big_y, response.big_c, ephemeral_private_key, &app_id)
+    }
+
+    /// Encrypt an app secret to an ark's `app_public_key` the way the MPC network does:
+    /// big_y = r·G, big_c = secret + r·app_public_key for a fresh random r. The ark
+    /// decrypts it like any CKD response (secret = big_c - big_y·app_private_key).
+    pub fn encrypt_app_key(
+        &self,
+        secret: &[u8; BLS12381G1_PUBLIC_KEY_SIZE],
+        app_public_key: &str,
+    ) -> Result<CkdResponse> {
+        let app_public_key = self
+            .near_format_to_g1(app_public_key)
+            .context("app_public_key must be a BLS12-381 G1 point (bls12381g1:<base58>)")?;
+        let secret = G1Projective::from_compressed(secret)
+            .into_option()
+            .context("App secret is not a G1 point")?;
+
+        let (r, big_y) = self.generate_ephemeral_key();
+        let big_c = secret + app_public_key * r;
+        Ok(CkdResponse {
+            scheme: None,
+            big_y: self.g1_to_near_format(big_y),
+            big_c: self.g1_to_near_format(big_c),
+        })
+    }
+
     /// Generate ephemeral BLS12-381 G1 keypair
     fn generate_ephemeral_key(&self) -> (Scalar, G1Projective) {
         let mut rng = OsRng;
@@ -465,6 +544,9 @@ impl MpcCkdClient {
         let bytes = bs58::decode(base58_part)
             .into_vec()
             .context("Invalid base58 encoding")?;
+        if bytes.len() < 48 {
+            anyhow::bail!("BLS12-381 G1 point too short: {} bytes", bytes.len());
+        }
 
         // Convert to compressed array
         let mut compressed = [0u8; 48];
@@ -524,8 +606,137 @@ impl MpcCkdClient {
             "Submitting CKD tx"
         );
 
+        let (signed_transaction, nonce) = self.sign_mpc_tx(signer, receiver_id, method_name, &request).await?;
+        let request = methods::broadcast_tx_commit::RpcBroadcastTxCommitRequest { signed_transaction };
+
+        let outcome = match self.rpc_client.call(request).await {
+            Ok(o) => o,
+            Err(e) => {
+                // Surface the exact RPC/broadcast error. Without this the
+                // whole failure is swallowed: the only thing that reaches
+                // the caller is the `MPC CKD failed for vault …` wrapper
+                // in `add_customer`, with no InvalidTxError variant, no
+                // status, nothing in the logs. Log the full chain here so
+                // a pre-inclusion reject (InvalidSignature / InvalidNonce /
+                // NotEnoughBalance / InvalidAccessKey / Expired) is visible.
+                tracing::error!(
+                    signer = %signer.account_id,
+                    receiver = %receiver_id,
+                    method = method_name,
+                    nonce,
+                    signer_pubkey = %signer.public_key,
+                    error = ?e,
+                    "CKD broadcast_tx_commit failed (pre-inclusion / RPC error)"
+                );
+                // The single most common operational failure here is a vault
+                // that can't cover the gas prepayment for the MPC call. Turn
+                // it into a TYPED, actionable error so the HTTP layer can tell
+                // the user to top up the vault instead of returning an opaque
+                // 400/503. (Exact have/need amounts stay in the log above.)
+                if is_not_enough_balance(&e) {
+                    return Err(anyhow::Error::new(InsufficientVaultBalance {
+                        vault: signer.account_id.clone(),
+                    }));
+                }
+                return Err(anyhow::Error::new(e).context("Failed to call MPC contract"));
+            }
+        };
+
+        self.ckd_response_from_outcome(&outcome, signer, receiver_id, method_name)
+    }
+
+    /// [`Self::call_mpc_contract`] for a signer shared by several tasks (the keystore-dao
+    /// signer). `nonce_lock` is held only until the tx is in a final block, where the
+    /// next caller's nonce query sees it; waiting for the MPC answer, which takes
+    /// several blocks, happens after the lock is released.
+    async fn call_mpc_contract_serialized(
+        &self,
+        signer: &near_crypto::InMemorySigner,
+        receiver_id: &AccountId,
+        method_name: &str,
+        request: CkdRequestArgs,
+        nonce_lock: &tokio::sync::Mutex<()>,
+    ) -> Result<CkdResponse> {
+        tracing::info!(
+            signer = %signer.account_id,
+            receiver = %receiver_id,
+            method = method_name,
+            "Submitting CKD tx (serialized signer)"
+        );
+
+        let tx_hash = {
+            let _guard = nonce_lock.lock().await;
+            let (signed_transaction, nonce) = self.sign_mpc_tx(signer, receiver_id, method_name, &request).await?;
+            let tx_hash = signed_transaction.get_hash();
+            self.rpc_client
+                .call(methods::send_tx::RpcSendTransactionRequest {
+                    signed_transaction,
+                    wait_until: TxExecutionStatus::IncludedFinal,
+                })
+                .await
+                .map_err(|e| {
+                    tracing::error!(
+                        signer = %signer.account_id,
+                        receiver = %receiver_id,
+                        method = method_name,
+                        nonce,
+                        error = ?e,
+                        "CKD send_tx failed (pre-inclusion / RPC error)"
+                    );
+                    anyhow::Error::new(e).context("Failed to call MPC contract")
+                })?;
+            tx_hash
+        };
+
+        // MPC answers through yield/resume a few blocks after inclusion. A tx status
+        // request waits up to the RPC node's timeout, so poll a bounded number of times.
+        let mut attempt = 0;
+        let outcome = loop {
+            attempt += 1;
+            let status = self
+                .rpc_client
+                .call(methods::tx::RpcTransactionStatusRequest {
+                    transaction_info: methods::tx::TransactionInfo::TransactionId {
+                        tx_hash,
+                        sender_account_id: signer.account_id.clone(),
+                    },
+                    wait_until: TxExecutionStatus::Final,
+                })
+                .await;
+            match status {
+                Ok(response) => {
+                    if let Some(outcome) = response.final_execution_outcome {
+                        break outcome.into_outcome();
+                    }
+                }
+                Err(e) if attempt >= MPC_OUTCOME_POLLS => {
+                    return Err(anyhow::Error::new(e))
+                        .with_context(|| format!("No final outcome for CKD tx {}", tx_hash));
+                }
+                Err(e) => {
+                    tracing::debug!(%tx_hash, attempt, "CKD tx not final yet ({})", e);
+                }
+            }
+            if attempt >= MPC_OUTCOME_POLLS {
+                anyhow::bail!("No final outcome for CKD tx {} after {} polls", tx_hash, attempt);
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+        };
+
+        self.ckd_response_from_outcome(&outcome, signer, receiver_id, method_name)
+    }
+
+    /// Nonce, recent block hash and signature for a CKD function call. Returns the
+    /// signed tx and its nonce (for logs).
+    async fn sign_mpc_tx(
+        &self,
+        signer: &near_crypto::InMemorySigner,
+        receiver_id: &AccountId,
+        method_name: &str,
+        request: &CkdRequestArgs,
+    ) -> Result<(near_primitives::transaction::SignedTransaction, u64)> {
         // Serialize request to JSON
-        let args = serde_json::to_vec(&request)?;
+        let args = serde_json::to_vec(request)?;
 
         // Get access key information for nonce.
         // Retry: a freshly-added access key may not be visible to the RPC node yet
@@ -596,48 +807,21 @@ impl MpcCkdClient {
 
         let transaction = Transaction::V0(transaction_v0);
 
-        // Sign and send transaction
         let signature = signer.sign(transaction.get_hash_and_size().0.as_ref());
-        let request = methods::broadcast_tx_commit::RpcBroadcastTxCommitRequest {
-            signed_transaction: near_primitives::transaction::SignedTransaction::new(
-                signature,
-                transaction,
-            ),
-        };
-
-        let outcome = match self.rpc_client.call(request).await {
-            Ok(o) => o,
-            Err(e) => {
-                // Surface the exact RPC/broadcast error. Without this the
-                // whole failure is swallowed: the only thing that reaches
-                // the caller is the `MPC CKD failed for vault …` wrapper
-                // in `add_customer`, with no InvalidTxError variant, no
-                // status, nothing in the logs. Log the full chain here so
-                // a pre-inclusion reject (InvalidSignature / InvalidNonce /
-                // NotEnoughBalance / InvalidAccessKey / Expired) is visible.
-                tracing::error!(
-                    signer = %signer.account_id,
-                    receiver = %receiver_id,
-                    method = method_name,
-                    nonce,
-                    signer_pubkey = %signer.public_key,
-                    error = ?e,
-                    "CKD broadcast_tx_commit failed (pre-inclusion / RPC error)"
-                );
-                // The single most common operational failure here is a vault
-                // that can't cover the gas prepayment for the MPC call. Turn
-                // it into a TYPED, actionable error so the HTTP layer can tell
-                // the user to top up the vault instead of returning an opaque
-                // 400/503. (Exact have/need amounts stay in the log above.)
-                if is_not_enough_balance(&e) {
-                    return Err(anyhow::Error::new(InsufficientVaultBalance {
-                        vault: signer.account_id.clone(),
-                    }));
-                }
-                return Err(anyhow::Error::new(e).context("Failed to call MPC contract"));
-            }
-        };
+        Ok((
+            near_primitives::transaction::SignedTransaction::new(signature, transaction),
+            nonce,
+        ))
+    }
 
+    /// CKD response from the tx outcome, or the error it failed with
+    fn ckd_response_from_outcome(
+        &self,
+        outcome: &FinalExecutionOutcomeView,
+        signer: &near_crypto::InMemorySigner,
+        receiver_id: &AccountId,
+        method_name: &str,
+    ) -> Result<CkdResponse> {
         // Check transaction status and extract result
         match &outcome.status {
             FinalExecutionStatus::SuccessValue(value) => {
@@ -1452,4 +1636,40 @@ mod tests {
                 .expect("cached fast-path must succeed without touching the network");
         }
     }
+
+    #[test]
+    fn encrypted_app_key_decrypts_with_app_private_key() {
+        // Same scheme as an MPC response: secret = big_c - big_y * sk
+        let client = MpcCkdClient::new(unreachable_config());
+        let secret = G1Projective::generator() * Scalar::random(&mut OsRng);
+        let (sk, pk) = client.generate_ephemeral_key();
+
+        let response = client
+            .encrypt_app_key(&secret.to_compressed(), &client.g1_to_near_format(pk))
+            .unwrap();
+        let big_y = client.near_format_to_g1(&response.big_y).unwrap();
+        let big_c = client.near_format_to_g1(&response.big_c).unwrap();
+        assert_eq!(big_c - big_y * sk, secret);
+
+        // A fresh r per call: the same secret never repeats a ciphertext
+        let again = client
+            .encrypt_app_key(&secret.to_compressed(), &client.g1_to_near_format(pk))
+            .unwrap();
+        assert_ne!(again.big_c, response.big_c);
+    }
+
+    #[test]
+    fn ark_derivation_path_is_per_project_and_disjoint() {
+        // Same project → same path → same MPC app_id, so an ark's key
+        // survives keystore restarts; other projects and the default
+        // master (empty path) land in different app_ids.
+        let path = ark_derivation_path("alice.near/my-ark");
+        assert_eq!(path, "outlayer-ark:alice.near/my-ark");
+        assert_ne!(path, ark_derivation_path("bob.near/my-ark"));
+        assert_eq!(
+            derive_app_id("dao.testnet", &path),
+            derive_app_id("dao.testnet", &ark_derivation_path("alice.near/my-ark"))
+        );
+        assert_ne!(derive_app_id("dao.testnet", &path), derive_app_id("dao.testnet", ""));
+    }
 }
\ No newline at end of file
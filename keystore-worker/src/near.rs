@@ -507,6 +507,33 @@ impl NearClient {
         Ok(SecretWithVault { profile, vault_id })
     }
 
+    /// Retained secret version (`set_secrets`) for a pinned
+    /// `SecretsReference.version`.
+    ///
+    /// Returns the SecretProfile-shaped JSON of that version, `None` if
+    /// the version is not retained (pruned or never written). Same Repo
+    /// wildcard fallback as `get_secrets`.
+    pub async fn get_secret_version(
+        &self,
+        accessor_json: serde_json::Value,
+        profile: &str,
+        owner: &str,
+        version: u32,
+    ) -> Result<Option<serde_json::Value>> {
+        let args = json!({
+            "accessor": accessor_json,
+            "profile": profile,
+            "owner": owner,
+            "version": version,
+        });
+
+        let response = self
+            .view_call_json(&self.contract_id.clone(), "get_secret_version", args)
+            .await?;
+
+        Ok(if response.is_null() { None } else { Some(response) })
+    }
+
     /// Fetch the contract hash of an account.
     ///
     /// Returns the base58-encoded sha256 of the WASM code the account
@@ -0,0 +1,486 @@
+//! One-time challenges for the TEE session handshake
+//! (`/tee-challenge` + `/register-tee`).
+//!
+//! `shared_tee_helpers::generate_challenge` only produces a random nonce; it
+//! does not remember what it handed out. Without tracking, a captured
+//! `(challenge, signature)` pair would verify forever. [`ChallengeStore`]
+//! records every issued challenge with its issue time and accepts each one
+//! exactly once within the TTL.
+//!
+//! Consumed challenges are remembered until their TTL runs out so a replay
+//! inside that window is reported as [`TeeAuthError::ChallengeAlreadyUsed`]
+//! rather than as an unknown challenge. After the TTL both cases collapse to
+//! [`TeeAuthError::UnknownChallenge`] — either way the replay is rejected.
+//!
+//! Worker keys are accepted as `ed25519:`, `ml-dsa-65:` (both verified by
+//! `shared_tee_helpers`) or `secp256k1:` (verified here), subject to
+//! `TEE_ALLOWED_KEY_TYPES`. secp256k1 signatures are over the raw 32-byte
+//! challenge, which is exactly the message size ECDSA signs.
+//!
+//! [`verify_and_check`] runs the whole handshake check: challenge, signature
+//! and then the on-chain key lookup, which is skipped when the signature is
+//! invalid. Its [`AuthOutcome`] says which of the two failed.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long an issued challenge stays valid
+pub const DEFAULT_CHALLENGE_TTL: Duration = Duration::from_secs(60);
+
+/// Why a challenge-response was rejected
+#[derive(Debug, PartialEq, Eq)]
+pub enum TeeAuthError {
+    /// Never issued by this store (or forgotten after its TTL)
+    UnknownChallenge,
+    /// Issued, but not answered within the TTL
+    ChallengeExpired,
+    /// Already answered once
+    ChallengeAlreadyUsed,
+    /// Challenge was valid but the signature over it did not verify
+    InvalidSignature(String),
+    /// Public key is not in a scheme this keystore accepts
+    UnsupportedKeyType(String),
+    /// The account the key was looked up on does not exist
+    AccountNotFound(String),
+    /// The on-chain access key lookup failed (not "key missing", which is `Ok(false)`)
+    NearRpcError(String),
+}
+
+impl std::fmt::Display for TeeAuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TeeAuthError::UnknownChallenge => write!(f, "Invalid or expired challenge"),
+            TeeAuthError::ChallengeExpired => write!(f, "Challenge expired"),
+            TeeAuthError::ChallengeAlreadyUsed => write!(f, "Challenge already used"),
+            TeeAuthError::InvalidSignature(e) => write!(f, "Signature verification failed: {e}"),
+            TeeAuthError::UnsupportedKeyType(t) => write!(f, "Unsupported worker key type: {t}"),
+            TeeAuthError::AccountNotFound(a) => write!(f, "Account {a} does not exist"),
+            TeeAuthError::NearRpcError(e) => write!(f, "NEAR RPC check failed: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for TeeAuthError {}
+
+/// Worker signature schemes accepted for TEE sessions (`TEE_ALLOWED_KEY_TYPES`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TeeKeyTypes {
+    pub ed25519: bool,
+    pub ml_dsa_65: bool,
+    pub secp256k1: bool,
+}
+
+impl TeeKeyTypes {
+    /// Parse a comma-separated list such as `"ed25519,secp256k1"`
+    ///
+    /// ed25519/ml-dsa-65 parsing is delegated to `shared_tee_helpers` so the
+    /// two stay in sync; `secp256k1` is recognized here.
+    pub fn from_csv(csv: &str) -> Self {
+        let shared = shared_tee_helpers::AllowedKeyTypes::from_csv(csv);
+        Self {
+            ed25519: shared.ed25519,
+            ml_dsa_65: shared.ml_dsa_65,
+            secp256k1: csv
+                .split(',')
+                .any(|t| t.trim().trim_matches('"').eq_ignore_ascii_case("secp256k1")),
+        }
+    }
+
+    fn shared(&self) -> shared_tee_helpers::AllowedKeyTypes {
+        shared_tee_helpers::AllowedKeyTypes {
+            ed25519: self.ed25519,
+            ml_dsa_65: self.ml_dsa_65,
+        }
+    }
+}
+
+struct IssuedChallenge {
+    issued_at: Instant,
+    consumed: bool,
+}
+
+/// In-memory registry of issued TEE challenges
+pub struct ChallengeStore {
+    ttl: Duration,
+    challenges: Mutex<HashMap<String, IssuedChallenge>>,
+}
+
+impl Default for ChallengeStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_CHALLENGE_TTL)
+    }
+}
+
+impl ChallengeStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            challenges: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Generate a fresh challenge and remember when it was issued
+    pub fn issue(&self) -> String {
+        self.issue_at(Instant::now())
+    }
+
+    /// Mark `challenge` as used
+    ///
+    /// Fails if the challenge was never issued, has expired, or was already
+    /// consumed. The check and the state change happen under one lock, so two
+    /// concurrent registrations can't both consume the same challenge.
+    pub fn consume(&self, challenge: &str) -> Result<(), TeeAuthError> {
+        self.consume_at(challenge, Instant::now())
+    }
+
+    fn issue_at(&self, now: Instant) -> String {
+        let challenge = shared_tee_helpers::generate_challenge();
+        let mut challenges = self.challenges.lock().unwrap();
+        // Prune on issue so the map is bounded by the issue rate over one TTL
+        challenges.retain(|_, c| now.saturating_duration_since(c.issued_at) <= self.ttl);
+        challenges.insert(
+            challenge.clone(),
+            IssuedChallenge {
+                issued_at: now,
+                consumed: false,
+            },
+        );
+        challenge
+    }
+
+    fn consume_at(&self, challenge: &str, now: Instant) -> Result<(), TeeAuthError> {
+        let mut challenges = self.challenges.lock().unwrap();
+        let entry = challenges
+            .get_mut(challenge)
+            .ok_or(TeeAuthError::UnknownChallenge)?;
+
+        if entry.consumed {
+            return Err(TeeAuthError::ChallengeAlreadyUsed);
+        }
+        if now.saturating_duration_since(entry.issued_at) > self.ttl {
+            challenges.remove(challenge);
+            return Err(TeeAuthError::ChallengeExpired);
+        }
+
+        entry.consumed = true;
+        Ok(())
+    }
+}
+
+/// Consume `challenge` from `store`, then verify `signature` over it
+///
+/// The challenge is burned before the signature is checked, so a wrong
+/// signature also uses it up and the caller has to request a new one.
+pub fn verify_challenge_response(
+    store: &ChallengeStore,
+    public_key: &str,
+    challenge: &str,
+    signature: &str,
+    allowed_key_types: TeeKeyTypes,
+) -> Result<(), TeeAuthError> {
+    store.consume(challenge)?;
+    verify_signature(public_key, challenge, signature, allowed_key_types)
+}
+
+/// Result of a challenge-response whose challenge was valid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuthOutcome {
+    /// The signature over the challenge verified for the public key
+    pub signature_valid: bool,
+    /// The public key is registered on-chain (always false if the signature is invalid)
+    pub key_registered: bool,
+}
+
+/// Consume `challenge`, verify `signature` and, only if it is valid, run `key_registered`
+///
+/// `key_registered` is the on-chain lookup (`Ok(false)` = key missing). Challenge
+/// errors, unsupported key types and lookup failures are returned as errors; an
+/// invalid signature or a missing key is an [`AuthOutcome`].
+pub async fn verify_and_check<F, Fut>(
+    store: &ChallengeStore,
+    public_key: &str,
+    challenge: &str,
+    signature: &str,
+    allowed_key_types: TeeKeyTypes,
+    key_registered: F,
+) -> Result<AuthOutcome, TeeAuthError>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<bool, TeeAuthError>>,
+{
+    match verify_challenge_response(store, public_key, challenge, signature, allowed_key_types) {
+        Ok(()) => {}
+        Err(TeeAuthError::InvalidSignature(reason)) => {
+            tracing::debug!("TEE signature rejected for {}: {}", public_key, reason);
+            return Ok(AuthOutcome {
+                signature_valid: false,
+                key_registered: false,
+            });
+        }
+        Err(e) => return Err(e),
+    }
+    Ok(AuthOutcome {
+        signature_valid: true,
+        key_registered: key_registered().await?,
+    })
+}
+
+/// Verify a worker's signature over the hex `challenge`, dispatching on the key prefix
+fn verify_signature(
+    public_key: &str,
+    challenge: &str,
+    signature: &str,
+    allowed_key_types: TeeKeyTypes,
+) -> Result<(), TeeAuthError> {
+    let key_type = public_key.split_once(':').map(|(t, _)| t).unwrap_or_default();
+    match key_type {
+        "ed25519" | "ml-dsa-65" => {
+            shared_tee_helpers::verify_signature(public_key, challenge, signature, allowed_key_types.shared())
+                .map(|_| ())
+                .map_err(|e| TeeAuthError::InvalidSignature(e.to_string()))
+        }
+        "secp256k1" => {
+            if !allowed_key_types.secp256k1 {
+                return Err(TeeAuthError::UnsupportedKeyType(
+                    "secp256k1 (not enabled in TEE_ALLOWED_KEY_TYPES)".to_string(),
+                ));
+            }
+            verify_secp256k1(public_key, challenge, signature)
+        }
+        other => Err(TeeAuthError::UnsupportedKeyType(other.to_string())),
+    }
+}
+
+fn verify_secp256k1(public_key: &str, challenge: &str, signature: &str) -> Result<(), TeeAuthError> {
+    let invalid = |msg: &str| TeeAuthError::InvalidSignature(msg.to_string());
+
+    let public_key: near_crypto::PublicKey = public_key.parse().map_err(|_| invalid("malformed secp256k1 public key"))?;
+    let signature: near_crypto::Signature = signature.parse().map_err(|_| invalid("malformed signature"))?;
+    if signature.key_type() != near_crypto::KeyType::SECP256K1 {
+        return Err(invalid("signature scheme does not match secp256k1 public key"));
+    }
+    let message = hex::decode(challenge).map_err(|_| invalid("challenge is not hex"))?;
+    if message.len() != 32 {
+        return Err(invalid("secp256k1 challenge must be 32 bytes"));
+    }
+
+    if !signature.verify(&message, &public_key) {
+        return Err(invalid("secp256k1 signature does not verify"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_key_types() -> TeeKeyTypes {
+        TeeKeyTypes { ed25519: true, ml_dsa_65: true, secp256k1: true }
+    }
+
+    fn sign_challenge(key: &near_crypto::SecretKey, challenge: &str) -> String {
+        key.sign(&hex::decode(challenge).unwrap()).to_string()
+    }
+
+    #[test]
+    fn test_challenge_consumed_once() {
+        let store = ChallengeStore::default();
+        let challenge = store.issue();
+
+        assert_eq!(store.consume(&challenge), Ok(()));
+        assert_eq!(store.consume(&challenge), Err(TeeAuthError::ChallengeAlreadyUsed));
+    }
+
+    #[test]
+    fn test_unknown_challenge_rejected() {
+        let store = ChallengeStore::default();
+        store.issue();
+
+        assert_eq!(store.consume("00ff"), Err(TeeAuthError::UnknownChallenge));
+    }
+
+    #[test]
+    fn test_challenge_expires_after_ttl() {
+        let store = ChallengeStore::new(Duration::from_secs(60));
+        let start = Instant::now();
+        let stale = store.issue_at(start);
+        let fresh = store.issue_at(start);
+
+        let later = start + Duration::from_secs(61);
+        assert_eq!(store.consume_at(&stale, later), Err(TeeAuthError::ChallengeExpired));
+        // Expired entries are dropped, so a retry is simply unknown
+        assert_eq!(store.consume_at(&stale, later), Err(TeeAuthError::UnknownChallenge));
+        // Exactly at the TTL is still accepted
+        assert_eq!(store.consume_at(&fresh, start + Duration::from_secs(60)), Ok(()));
+    }
+
+    #[test]
+    fn test_issue_prunes_old_challenges() {
+        let store = ChallengeStore::new(Duration::from_secs(60));
+        let start = Instant::now();
+        let used = store.issue_at(start);
+        store.consume_at(&used, start).unwrap();
+        store.issue_at(start);
+
+        store.issue_at(start + Duration::from_secs(120));
+        assert_eq!(store.challenges.lock().unwrap().len(), 1);
+        assert_eq!(
+            store.consume_at(&used, start + Duration::from_secs(120)),
+            Err(TeeAuthError::UnknownChallenge)
+        );
+    }
+
+    #[test]
+    fn test_bad_signature_burns_challenge() {
+        let store = ChallengeStore::default();
+        let challenge = store.issue();
+        let key = near_crypto::SecretKey::from_random(near_crypto::KeyType::ED25519);
+
+        let result = verify_challenge_response(
+            &store,
+            &key.public_key().to_string(),
+            &challenge,
+            &key.sign(b"not the challenge").to_string(),
+            all_key_types(),
+        );
+        assert!(matches!(result, Err(TeeAuthError::InvalidSignature(_))));
+
+        // A correct signature can't reuse the burned challenge
+        let signature = sign_challenge(&key, &challenge);
+        assert_eq!(
+            verify_challenge_response(&store, &key.public_key().to_string(), &challenge, &signature, all_key_types()),
+            Err(TeeAuthError::ChallengeAlreadyUsed)
+        );
+    }
+
+    /// Stand-in for the RPC lookup: answers `registered` and counts calls
+    struct MockRpc {
+        registered: bool,
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    impl MockRpc {
+        fn new(registered: bool) -> Self {
+            Self { registered, calls: std::sync::atomic::AtomicUsize::new(0) }
+        }
+
+        async fn view_access_key(&self) -> Result<bool, TeeAuthError> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(self.registered)
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(std::sync::atomic::Ordering::SeqCst)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_and_check_outcomes() {
+        let key = near_crypto::SecretKey::from_random(near_crypto::KeyType::ED25519);
+        let public_key = key.public_key().to_string();
+
+        for (signature_valid, registered) in [(true, true), (true, false), (false, true), (false, false)] {
+            let store = ChallengeStore::default();
+            let challenge = store.issue();
+            let signature = if signature_valid {
+                sign_challenge(&key, &challenge)
+            } else {
+                key.sign(b"not the challenge").to_string()
+            };
+            let rpc = MockRpc::new(registered);
+
+            let outcome = verify_and_check(&store, &public_key, &challenge, &signature, all_key_types(), || {
+                rpc.view_access_key()
+            })
+            .await
+            .unwrap();
+
+            assert_eq!(outcome.signature_valid, signature_valid);
+            assert_eq!(outcome.key_registered, signature_valid && registered);
+            // The RPC lookup only runs for a valid signature
+            assert_eq!(rpc.calls(), usize::from(signature_valid));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_and_check_surfaces_errors() {
+        let store = ChallengeStore::default();
+        let key = near_crypto::SecretKey::from_random(near_crypto::KeyType::ED25519);
+        let rpc = MockRpc::new(true);
+
+        let result =
+            verify_and_check(&store, &key.public_key().to_string(), "00ff", "ed25519:x", all_key_types(), || {
+                rpc.view_access_key()
+            })
+            .await;
+        assert_eq!(result, Err(TeeAuthError::UnknownChallenge));
+        assert_eq!(rpc.calls(), 0);
+
+        let challenge = store.issue();
+        let signature = sign_challenge(&key, &challenge);
+        let result = verify_and_check(&store, &key.public_key().to_string(), &challenge, &signature, all_key_types(), || async {
+            Err(TeeAuthError::NearRpcError("timeout".to_string()))
+        })
+        .await;
+        assert_eq!(result, Err(TeeAuthError::NearRpcError("timeout".to_string())));
+    }
+
+    #[test]
+    fn test_secp256k1_sign_verify_roundtrip() {
+        let store = ChallengeStore::default();
+        let key = near_crypto::SecretKey::from_random(near_crypto::KeyType::SECP256K1);
+        let public_key = key.public_key().to_string();
+        assert!(public_key.starts_with("secp256k1:"));
+
+        let challenge = store.issue();
+        let signature = sign_challenge(&key, &challenge);
+        assert_eq!(
+            verify_challenge_response(&store, &public_key, &challenge, &signature, all_key_types()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_secp256k1_wrong_key_or_challenge_rejected() {
+        let key = near_crypto::SecretKey::from_random(near_crypto::KeyType::SECP256K1);
+        let other = near_crypto::SecretKey::from_random(near_crypto::KeyType::SECP256K1);
+        let challenge = hex::encode([7u8; 32]);
+        let signature = sign_challenge(&key, &challenge);
+
+        let result = verify_signature(&other.public_key().to_string(), &challenge, &signature, all_key_types());
+        assert!(matches!(result, Err(TeeAuthError::InvalidSignature(_))));
+
+        let tampered = hex::encode([8u8; 32]);
+        let result = verify_signature(&key.public_key().to_string(), &tampered, &signature, all_key_types());
+        assert!(matches!(result, Err(TeeAuthError::InvalidSignature(_))));
+
+        // ed25519 signature presented for a secp256k1 key
+        let ed_key = near_crypto::SecretKey::from_random(near_crypto::KeyType::ED25519);
+        let result = verify_signature(
+            &key.public_key().to_string(),
+            &challenge,
+            &sign_challenge(&ed_key, &challenge),
+            all_key_types(),
+        );
+        assert!(matches!(result, Err(TeeAuthError::InvalidSignature(_))));
+    }
+
+    #[test]
+    fn test_key_type_policy() {
+        let key = near_crypto::SecretKey::from_random(near_crypto::KeyType::SECP256K1);
+        let challenge = hex::encode([1u8; 32]);
+        let signature = sign_challenge(&key, &challenge);
+
+        let ed25519_only = TeeKeyTypes::from_csv("ed25519");
+        assert!(!ed25519_only.secp256k1);
+        let result = verify_signature(&key.public_key().to_string(), &challenge, &signature, ed25519_only);
+        assert!(matches!(result, Err(TeeAuthError::UnsupportedKeyType(_))));
+
+        assert!(TeeKeyTypes::from_csv("ed25519, secp256k1").secp256k1);
+
+        let result = verify_signature("rsa:abc", &challenge, &signature, all_key_types());
+        assert_eq!(result, Err(TeeAuthError::UnsupportedKeyType("rsa".to_string())));
+    }
+}
@@ -1,6 +1,10 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::serde::{Deserialize, Serialize};
-use near_sdk::{env, near_bindgen, AccountId, Allowance, CurveType, NearToken, Promise, PublicKey, BorshStorageKey};
+use near_sdk::store::{key::Sha256, IterableSet};
+use near_sdk::{
+    env, near_bindgen, AccountId, Allowance, BorshStorageKey, CurveType, Gas, NearToken, Promise, PromiseOrValue,
+    PromiseResult, PublicKey,
+};
 use schemars::JsonSchema;
 
 // Collateral wrapper with Borsh support (same approach as MPC Node)
@@ -25,6 +29,7 @@ register_custom_getrandom!(randomness_unsupported);
 #[allow(dead_code)] // ApprovedRtmr3 used only in migration deserialization
 enum StorageKey {
     ApprovedRtmr3,
+    WorkerKeys,
 }
 
 /// Full TEE measurements for verifying the entire dstack environment.
@@ -48,6 +53,49 @@ pub struct ApprovedMeasurements {
     pub rtmr3: String,
 }
 
+/// An approved measurement set plus the metadata operators need to manage it.
+///
+/// `label` names the image build (e.g. "worker v1.4.2 phala"); `expires_at` (nanoseconds) lets
+/// an approval lapse on its own once the build is retired. Expired entries stay listed until
+/// removed but no longer admit registrations.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, JsonSchema, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ApprovedMeasurementEntry {
+    pub measurements: ApprovedMeasurements,
+    /// Human-readable name of the image build
+    pub label: String,
+    /// Block timestamp (ns) when the entry was added
+    pub added_at: u64,
+    /// Block timestamp (ns) after which the entry no longer admits registrations
+    pub expires_at: Option<u64>,
+}
+
+impl ApprovedMeasurementEntry {
+    fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|t| t <= now)
+    }
+}
+
+/// SGX enclave identity for workers running in an SGX enclave instead of a TDX VM.
+/// Both fields are 64 hex characters (32 bytes).
+///
+/// MRENCLAVE pins the exact enclave build; MRSIGNER pins the key that signed it. Both must
+/// match, so a different build from the same signer is rejected.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, JsonSchema, Clone, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ApprovedSgxMeasurements {
+    /// MRENCLAVE - Hash of the enclave code and initial data
+    pub mrenclave: String,
+    /// MRSIGNER - Hash of the enclave signing key
+    pub mrsigner: String,
+}
+
+/// Measurements extracted from a verified quote, by TEE type
+enum QuoteMeasurements {
+    Tdx(ApprovedMeasurements),
+    Sgx(ApprovedSgxMeasurements),
+}
+
 /// Register Contract - TEE Worker Key Registration
 ///
 /// This contract verifies TDX attestations and registers worker public keys
@@ -71,6 +119,9 @@ pub struct ApprovedMeasurements {
 /// register_worker_key tries each until the worker's quote verifies; keep small (gas).
 const MAX_COLLATERALS: usize = 2;
 
+/// Gas for `on_worker_keys_removed` (reads N promise results, updates the tracked key set)
+const GAS_REMOVE_KEYS_CALLBACK: Gas = Gas::from_tgas(20);
+
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct RegisterContract {
@@ -81,8 +132,8 @@ pub struct RegisterContract {
     pub init_worker_account: AccountId,
 
     /// Full TEE measurements approved for worker registration.
-    /// Each entry contains MRTD + RTMR0-3 (all must match for registration).
-    pub approved_measurements: Vec<ApprovedMeasurements>,
+    /// Each entry contains MRTD + RTMR0-3 (all must match for registration) with its label/expiry.
+    pub approved_measurements: Vec<ApprovedMeasurementEntry>,
 
     /// Cached quote collateral(s) — Intel reference data for TDX quote verification, one slot
     /// per platform/FMSPC (e.g. [0]=Phala 20a06f000000, [1]=self-hosted B0C06F000000).
@@ -91,7 +142,16 @@ pub struct RegisterContract {
     /// MAX_COLLATERALS; owner manages slots via `update_collateral(collateral, index)`.
     pub collaterals: Vec<String>,
 
-    pub outlayer_contract_id: AccountId
+    pub outlayer_contract_id: AccountId,
+
+    /// SGX enclave identities approved for worker registration.
+    /// Each entry contains MRENCLAVE + MRSIGNER (both must match for registration).
+    pub approved_sgx_measurements: Vec<ApprovedSgxMeasurements>,
+
+    /// Worker keys added by `register_worker_key`. The runtime gives contracts no way to list
+    /// their own access keys, so this is what `remove_all_worker_keys_except` revokes from.
+    /// Owner/full-access keys are never in this set. Keys hashed (ml-dsa-65 keys are ~2 KB).
+    pub worker_keys: IterableSet<PublicKey, Sha256>,
 }
 
 impl Default for RegisterContract {
@@ -125,29 +185,31 @@ impl RegisterContract {
             approved_measurements: Vec::new(),
             collaterals: Vec::new(),
             outlayer_contract_id,
+            approved_sgx_measurements: Vec::new(),
+            worker_keys: IterableSet::with_hasher(StorageKey::WorkerKeys),
         }
     }
 
     /// Register worker public key with TEE attestation
     ///
     /// This method:
-    /// 1. Verifies TDX quote signature (Intel cryptographic proof)
-    /// 2. Extracts RTMR3 from quote
-    /// 3. Checks RTMR3 is in approved list
+    /// 1. Verifies TDX or SGX quote signature (Intel cryptographic proof)
+    /// 2. Extracts MRTD + RTMR0-3 (TDX) or MRENCLAVE + MRSIGNER (SGX) from quote
+    /// 3. Checks them against the matching approved list
     /// 4. Extracts public key from quote report_data
     /// 5. Verifies public_key parameter matches embedded key
     /// 6. Adds access key to this contract's account (self)
     ///
     /// # Arguments
     /// * `public_key` - Worker's ed25519 public key (generated in TEE)
-    /// * `tdx_quote_hex` - Hex-encoded TDX quote from Phala dstack
+    /// * `tdx_quote_hex` - Hex-encoded TDX quote from Phala dstack (or an SGX quote)
     ///
     /// # Returns
     /// Promise to add access key to this account
     ///
     /// # Panics
     /// - If TDX quote verification fails
-    /// - If RTMR3 not in approved list
+    /// - If measurements not in approved list
     /// - If public key doesn't match quote
     /// - If collateral not cached (use update_collateral first)
     pub fn register_worker_key(
@@ -177,9 +239,9 @@ impl RegisterContract {
         let quote_bytes = hex::decode(&tdx_quote_hex).expect("Invalid quote hex encoding");
         let quote_fmspc = hex::encode(
             dcap_qvl::quote::Quote::parse(&quote_bytes)
-                .expect("Failed to parse TDX quote")
+                .expect("Failed to parse quote")
                 .fmspc()
-                .expect("No FMSPC in TDX quote"),
+                .expect("No FMSPC in quote"),
         );
         let collateral = collaterals
             .iter()
@@ -196,26 +258,48 @@ impl RegisterContract {
             });
         let (measurements, report_data_prefix) = self
             .verify_worker_registration(&tdx_quote_hex, collateral)
-            .unwrap_or_else(|e| env::panic_str(&format!("TDX quote verification failed: {e}")));
+            .unwrap_or_else(|e| env::panic_str(&format!("Quote verification failed: {e}")));
 
         // Log verification result for admin visibility
         env::log_str(&format!(
-            "📋 Verified TDX quote. report_data binding (hex): {}",
+            "📋 Verified quote. report_data binding (hex): {}",
             hex::encode(report_data_prefix)
         ));
-        env::log_str(&format!(
-            "📋 Measurements from TDX quote: mrtd={}, rtmr0={}, rtmr1={}, rtmr2={}, rtmr3={}",
-            measurements.mrtd, measurements.rtmr0, measurements.rtmr1,
-            measurements.rtmr2, measurements.rtmr3
-        ));
 
-        // 2. Check full measurements are approved
-        assert!(
-            self.approved_measurements.contains(&measurements),
-            "Worker measurements not approved. MRTD={}, RTMR0={}, RTMR1={}, RTMR2={}, RTMR3={}.",
-            measurements.mrtd, measurements.rtmr0, measurements.rtmr1,
-            measurements.rtmr2, measurements.rtmr3
-        );
+        // 2. Check full measurements are approved (against the list for this TEE type)
+        let approved_summary = match &measurements {
+            QuoteMeasurements::Tdx(m) => {
+                env::log_str(&format!(
+                    "📋 Measurements from TDX quote: mrtd={}, rtmr0={}, rtmr1={}, rtmr2={}, rtmr3={}",
+                    m.mrtd, m.rtmr0, m.rtmr1, m.rtmr2, m.rtmr3
+                ));
+                let entry = self.find_measurements(m).unwrap_or_else(|| {
+                    env::panic_str(&format!(
+                        "Worker measurements not approved. MRTD={}, RTMR0={}, RTMR1={}, RTMR2={}, RTMR3={}.",
+                        m.mrtd, m.rtmr0, m.rtmr1, m.rtmr2, m.rtmr3
+                    ))
+                });
+                assert!(
+                    !entry.is_expired(env::block_timestamp()),
+                    "Worker measurements approval \"{}\" expired at {}",
+                    entry.label,
+                    entry.expires_at.unwrap_or_default()
+                );
+                "all 5 TDX measurements approved"
+            }
+            QuoteMeasurements::Sgx(m) => {
+                env::log_str(&format!(
+                    "📋 Measurements from SGX quote: mrenclave={}, mrsigner={}",
+                    m.mrenclave, m.mrsigner
+                ));
+                assert!(
+                    self.approved_sgx_measurements.contains(m),
+                    "Worker SGX measurements not approved. MRENCLAVE={}, MRSIGNER={}.",
+                    m.mrenclave, m.mrsigner
+                );
+                "SGX MRENCLAVE + MRSIGNER approved"
+            }
+        };
 
         // 3. Verify the submitted public_key matches the binding embedded in the TDX quote.
         //    ed25519: the binding is the raw 32-byte public key.
@@ -236,13 +320,14 @@ impl RegisterContract {
         };
         assert_eq!(
             report_data_prefix, expected_binding,
-            "Public key mismatch: provided key doesn't match TDX quote report_data"
+            "Public key mismatch: provided key doesn't match quote report_data"
         );
 
         // NOTE: do not log the full public_key with {:?} — an ml-dsa-65 key debug-prints to
         // ~19 KB and blows the 16 KB per-log limit. Log the (short) curve + report_data binding.
         env::log_str(&format!(
-            "✅ TEE verification passed: all 5 measurements approved, curve={:?}, binding={}",
+            "✅ TEE verification passed: {}, curve={:?}, binding={}",
+            approved_summary,
             public_key.curve_type(),
             hex::encode(report_data_prefix)
         ));
@@ -258,6 +343,8 @@ impl RegisterContract {
             current_account.clone(), public_key.curve_type(), hex::encode(report_data_prefix), method_names, self.outlayer_contract_id
         ));
 
+        self.worker_keys.insert(public_key.clone());
+
         // Add key to this account (self) with permissions for offchainvm_contract_id
         Promise::new(current_account.clone()).add_access_key_allowance(
             public_key,
@@ -286,12 +373,12 @@ impl RegisterContract {
         (hex.len() >= 12).then(|| hex[..12].to_string())
     }
 
-    /// Verify TDX quote and extract RTMR3 + embedded public key
+    /// Verify a TDX or SGX quote and extract its measurements + embedded public key
     ///
     /// Uses dcap-qvl library to:
-    /// - Parse TDX quote structure
+    /// - Parse quote structure
     /// - Verify Intel's cryptographic signature
-    /// - Extract RTMR3 (TEE measurement)
+    /// - Extract MRTD + RTMR0-3 (TDX report) or MRENCLAVE + MRSIGNER (SGX report)
     /// - Extract public key from report_data (first 32 bytes)
     ///
     /// Returns Err (not panic) on any failure (e.g. mismatched-FMSPC collateral or bad signature).
@@ -299,7 +386,7 @@ impl RegisterContract {
         &self,
         quote_hex: &str,
         collateral_str: &str,
-    ) -> Result<(ApprovedMeasurements, [u8; 32]), String> {
+    ) -> Result<(QuoteMeasurements, [u8; 32]), String> {
         use dcap_qvl::verify;
         use hex::decode;
 
@@ -317,42 +404,48 @@ impl RegisterContract {
         // Uses dcap_qvl 0.3.11 with "contract" feature (NEAR-compatible, same as MPC Node)
         let now = env::block_timestamp() / 1_000_000_000; // Convert to seconds
         let result = verify::verify(&quote_bytes, collateral.inner(), now)
-            .map_err(|e| format!("TDX quote verification failed (signature/TCB/collateral mismatch): {e:?}"))?;
+            .map_err(|e| format!("Quote verification failed (signature/TCB/collateral mismatch): {e:?}"))?;
 
         // Reject anything but an up-to-date platform: dcap-qvl's verify() returns Ok
         // for OutOfDate / ConfigurationNeeded / SWHardeningNeeded — only Revoked errors.
         if result.status.as_str() != "UpToDate" {
             return Err(format!(
-                "TDX TCB status not acceptable: {} (platform needs a firmware/microcode update)",
+                "TCB status not acceptable: {} (platform needs a firmware/microcode update)",
                 result.status
             ));
         }
         if !result.advisory_ids.is_empty() {
             return Err(format!(
-                "TDX platform has outstanding security advisories: {}",
+                "Platform has outstanding security advisories: {}",
                 result.advisory_ids.join(", ")
             ));
         }
 
-        // Extract all measurements from TDX report (MRTD + RTMR0-3)
-        let td10 = result
-            .report
-            .as_td10()
-            .ok_or_else(|| "Quote is not TDX format (expected TD10)".to_string())?;
-
-        let measurements = ApprovedMeasurements {
-            mrtd: hex::encode(td10.mr_td.to_vec()),
-            rtmr0: hex::encode(td10.rt_mr0.to_vec()),
-            rtmr1: hex::encode(td10.rt_mr1.to_vec()),
-            rtmr2: hex::encode(td10.rt_mr2.to_vec()),
-            rtmr3: hex::encode(td10.rt_mr3.to_vec()),
+        // Extract measurements by report type: TDX (MRTD + RTMR0-3) or SGX (MRENCLAVE + MRSIGNER)
+        let (measurements, report_data) = if let Some(td10) = result.report.as_td10() {
+            let m = ApprovedMeasurements {
+                mrtd: hex::encode(td10.mr_td.to_vec()),
+                rtmr0: hex::encode(td10.rt_mr0.to_vec()),
+                rtmr1: hex::encode(td10.rt_mr1.to_vec()),
+                rtmr2: hex::encode(td10.rt_mr2.to_vec()),
+                rtmr3: hex::encode(td10.rt_mr3.to_vec()),
+            };
+            (QuoteMeasurements::Tdx(m), td10.report_data)
+        } else if let Some(sgx) = result.report.as_sgx() {
+            let m = ApprovedSgxMeasurements {
+                mrenclave: hex::encode(sgx.mr_enclave.to_vec()),
+                mrsigner: hex::encode(sgx.mr_signer.to_vec()),
+            };
+            (QuoteMeasurements::Sgx(m), sgx.report_data)
+        } else {
+            return Err("Unsupported quote report type (expected TDX TD10 or SGX enclave report)".to_string());
         };
 
         // Extract the 32-byte report_data binding. This is the raw ed25519 public key, or
         // SHA-256 of an ml-dsa-65 public key (which is 1952 bytes and does not fit in
         // report_data's 32 bytes). register_worker_key re-derives this from the submitted key.
         let mut report_data_prefix = [0u8; 32];
-        report_data_prefix.copy_from_slice(&td10.report_data[..32]);
+        report_data_prefix.copy_from_slice(&report_data[..32]);
 
         Ok((measurements, report_data_prefix))
     }
@@ -392,24 +485,53 @@ impl RegisterContract {
     /// Get measurements from Phala attestation:
     /// `phala cvms attestation <CVM_NAME> --json | jq '.tcb_info'`
     ///
+    /// `label` names the image build (defaults to empty). `expires_at` is a block timestamp in
+    /// nanoseconds after which the entry stops admitting registrations; it must be in the future.
+    /// Re-adding an already approved set updates its label and expiry.
+    ///
     /// If `clear_others` is true, removes all existing entries before adding.
-    pub fn add_approved_measurements(&mut self, measurements: ApprovedMeasurements, clear_others: Option<bool>) {
+    pub fn add_approved_measurements(
+        &mut self,
+        measurements: ApprovedMeasurements,
+        label: Option<String>,
+        expires_at: Option<u64>,
+        clear_others: Option<bool>,
+    ) {
         self.assert_owner();
         Self::validate_measurements(&measurements);
 
+        let now = env::block_timestamp();
+        if let Some(expires_at) = expires_at {
+            assert!(expires_at > now, "expires_at must be in the future");
+        }
+        let label = label.unwrap_or_default();
+
         if clear_others.unwrap_or(false) {
             let count = self.approved_measurements.len();
             self.approved_measurements.clear();
             env::log_str(&format!("Cleared {} existing measurement entries", count));
         }
 
-        if !self.approved_measurements.contains(&measurements) {
-            self.approved_measurements.push(measurements.clone());
+        match self
+            .approved_measurements
+            .iter_mut()
+            .find(|e| e.measurements == measurements)
+        {
+            Some(entry) => {
+                entry.label = label.clone();
+                entry.expires_at = expires_at;
+            }
+            None => self.approved_measurements.push(ApprovedMeasurementEntry {
+                measurements: measurements.clone(),
+                label: label.clone(),
+                added_at: now,
+                expires_at,
+            }),
         }
 
         env::log_str(&format!(
-            "Approved measurements added: mrtd={}, rtmr0={}, rtmr1={}, rtmr2={}, rtmr3={}",
-            measurements.mrtd, measurements.rtmr0, measurements.rtmr1,
+            "Approved measurements added: label=\"{}\", expires_at={:?}, mrtd={}, rtmr0={}, rtmr1={}, rtmr2={}, rtmr3={}",
+            label, expires_at, measurements.mrtd, measurements.rtmr0, measurements.rtmr1,
             measurements.rtmr2, measurements.rtmr3
         ));
         env::log_str(&format!("Total approved measurements: {}", self.approved_measurements.len()));
@@ -418,10 +540,42 @@ impl RegisterContract {
     /// Remove approved measurements
     pub fn remove_approved_measurements(&mut self, measurements: ApprovedMeasurements) {
         self.assert_owner();
-        self.approved_measurements.retain(|m| m != &measurements);
+        self.approved_measurements.retain(|e| e.measurements != measurements);
         env::log_str(&format!("Approved measurements removed. Remaining: {}", self.approved_measurements.len()));
     }
 
+    /// Add approved SGX enclave identity (MRENCLAVE + MRSIGNER).
+    ///
+    /// Both values must match for an SGX worker to register. TDX workers are checked
+    /// against `approved_measurements` instead; the two lists are independent.
+    pub fn add_approved_sgx_measurements(&mut self, measurements: ApprovedSgxMeasurements) {
+        self.assert_owner();
+        Self::validate_sgx_measurements(&measurements);
+
+        if !self.approved_sgx_measurements.contains(&measurements) {
+            self.approved_sgx_measurements.push(measurements.clone());
+        }
+
+        env::log_str(&format!(
+            "Approved SGX measurements added: mrenclave={}, mrsigner={}",
+            measurements.mrenclave, measurements.mrsigner
+        ));
+        env::log_str(&format!(
+            "Total approved SGX measurements: {}",
+            self.approved_sgx_measurements.len()
+        ));
+    }
+
+    /// Remove approved SGX measurements
+    pub fn remove_approved_sgx_measurements(&mut self, measurements: ApprovedSgxMeasurements) {
+        self.assert_owner();
+        self.approved_sgx_measurements.retain(|m| m != &measurements);
+        env::log_str(&format!(
+            "Approved SGX measurements removed. Remaining: {}",
+            self.approved_sgx_measurements.len()
+        ));
+    }
+
     /// Clear all approved measurements
     pub fn clear_all_approved_measurements(&mut self) {
         self.assert_owner();
@@ -438,26 +592,66 @@ impl RegisterContract {
     /// Each key is removed via an independent promise so that one
     /// missing key does not cause the entire batch to fail.
     ///
+    /// # Returns
+    /// `(public_key, removed)` per key, in input order. `removed` is false when the key
+    /// was already gone.
+    ///
     /// # Security
     /// - Removing a key invalidates any TEE sessions that rely on
     ///   `view_access_key` checks against this contract
     /// - Frees ~0.042 NEAR storage per key
-    pub fn remove_worker_keys(&mut self, public_keys: Vec<PublicKey>) {
+    pub fn remove_worker_keys(&mut self, public_keys: Vec<PublicKey>) -> PromiseOrValue<Vec<(PublicKey, bool)>> {
         self.assert_owner();
-        let account = env::current_account_id();
-        for key in &public_keys {
-            // Short fingerprint only: an ml-dsa-65 key debug-prints to ~19 KB (over the log limit).
-            env::log_str(&format!(
-                "Removing worker key: curve={:?}, sha256={}",
-                key.curve_type(),
-                hex::encode(env::sha256_array(key.as_bytes()))
-            ));
-            // Each delete_key is an independent promise — if one key
-            // doesn't exist, the others still get removed. Detached on purpose: we schedule
-            // them all and return nothing (near-sdk >= 5.29 marks Promise #[must_use]).
-            Promise::new(account.clone()).delete_key(key.clone()).detach();
-        }
-        env::log_str(&format!("Scheduled removal of {} worker key(s)", public_keys.len()));
+        self.revoke_keys(public_keys)
+    }
+
+    /// Revoke every tracked worker key not in `keep` (e.g. after a key rotation)
+    ///
+    /// Enumerates the keys recorded by `register_worker_key`, not the account's raw key list:
+    /// contracts cannot list their own access keys. Keys registered before tracking was added
+    /// are not covered; list them with `near account list-keys` and pass them to
+    /// `remove_worker_keys`. Owner/full-access keys are never touched.
+    ///
+    /// Returns the same per-key report as `remove_worker_keys`.
+    pub fn remove_all_worker_keys_except(&mut self, keep: Vec<PublicKey>) -> PromiseOrValue<Vec<(PublicKey, bool)>> {
+        self.assert_owner();
+        let stale: Vec<PublicKey> = self
+            .worker_keys
+            .iter()
+            .filter(|k| !keep.contains(k))
+            .cloned()
+            .collect();
+        env::log_str(&format!(
+            "Revoking {} of {} tracked worker key(s), keeping {}",
+            stale.len(),
+            self.worker_keys.len(),
+            keep.len()
+        ));
+        self.revoke_keys(stale)
+    }
+
+    /// Callback for key revocation: report which deletions succeeded
+    #[private]
+    pub fn on_worker_keys_removed(&mut self, public_keys: Vec<PublicKey>) -> Vec<(PublicKey, bool)> {
+        let results: Vec<(PublicKey, bool)> = public_keys
+            .into_iter()
+            .enumerate()
+            .map(|(i, key)| {
+                let removed = matches!(env::promise_result(i as u64), PromiseResult::Successful(_));
+                env::log_str(&format!(
+                    "{} worker key: curve={:?}, sha256={}",
+                    if removed { "Removed" } else { "Key not found, skipped" },
+                    key.curve_type(),
+                    hex::encode(env::sha256_array(key.as_bytes()))
+                ));
+                // A failed delete_key means the key no longer exists, so stop tracking it either way
+                self.worker_keys.remove(&key);
+                (key, removed)
+            })
+            .collect();
+        let removed = results.iter().filter(|(_, removed)| *removed).count();
+        env::log_str(&format!("Removed {} of {} worker key(s)", removed, results.len()));
+        results
     }
 
     /// Transfer ownership
@@ -472,14 +666,25 @@ impl RegisterContract {
 
     // ========== View methods ==========
 
-    /// Get list of approved measurements
-    pub fn get_approved_measurements(&self) -> Vec<ApprovedMeasurements> {
+    /// Get list of approved measurement entries (including expired ones, with their label/expiry)
+    pub fn get_approved_measurements(&self) -> Vec<ApprovedMeasurementEntry> {
         self.approved_measurements.clone()
     }
 
-    /// Check if measurements are approved
+    /// Check if measurements are approved and the approval has not expired
     pub fn is_measurements_approved(&self, measurements: ApprovedMeasurements) -> bool {
-        self.approved_measurements.contains(&measurements)
+        self.find_measurements(&measurements)
+            .is_some_and(|e| !e.is_expired(env::block_timestamp()))
+    }
+
+    /// Get list of approved SGX measurements
+    pub fn get_approved_sgx_measurements(&self) -> Vec<ApprovedSgxMeasurements> {
+        self.approved_sgx_measurements.clone()
+    }
+
+    /// Check if SGX measurements are approved
+    pub fn is_sgx_measurements_approved(&self, measurements: ApprovedSgxMeasurements) -> bool {
+        self.approved_sgx_measurements.contains(&measurements)
     }
 
     /// Get cached collateral at slot 0 (backward-compatible; None if no slots set)
@@ -507,12 +712,49 @@ impl RegisterContract {
         );
     }
 
+    /// Delete `keys` from this account as independent joined promises, then report per key
+    fn revoke_keys(&self, keys: Vec<PublicKey>) -> PromiseOrValue<Vec<(PublicKey, bool)>> {
+        let account = env::current_account_id();
+        let deletes = keys.iter().map(|key| {
+            // Short fingerprint only: an ml-dsa-65 key debug-prints to ~19 KB (over the log limit).
+            env::log_str(&format!(
+                "Removing worker key: curve={:?}, sha256={}",
+                key.curve_type(),
+                hex::encode(env::sha256_array(key.as_bytes()))
+            ));
+            // Each delete_key is its own receipt — if one key doesn't exist, the others
+            // still get removed; the callback sees each outcome separately.
+            Promise::new(account.clone()).delete_key(key.clone())
+        });
+        match deletes.reduce(|joined, next| joined.and(next)) {
+            Some(joined) => PromiseOrValue::Promise(
+                joined.then(
+                    Self::ext(account.clone())
+                        .with_static_gas(GAS_REMOVE_KEYS_CALLBACK)
+                        .on_worker_keys_removed(keys),
+                ),
+            ),
+            None => PromiseOrValue::Value(Vec::new()),
+        }
+    }
+
+    fn find_measurements(&self, measurements: &ApprovedMeasurements) -> Option<&ApprovedMeasurementEntry> {
+        self.approved_measurements
+            .iter()
+            .find(|e| &e.measurements == measurements)
+    }
+
     fn validate_measurement_field(name: &str, value: &str) {
+        Self::validate_hex_field(name, value, 96);
+    }
+
+    fn validate_hex_field(name: &str, value: &str, expected_len: usize) {
         assert_eq!(
             value.len(),
-            96,
-            "Invalid {} format: expected 96 hex characters, got {}",
+            expected_len,
+            "Invalid {} format: expected {} hex characters, got {}",
             name,
+            expected_len,
             value.len()
         );
         assert!(
@@ -529,6 +771,11 @@ impl RegisterContract {
         Self::validate_measurement_field("rtmr2", &m.rtmr2);
         Self::validate_measurement_field("rtmr3", &m.rtmr3);
     }
+
+    fn validate_sgx_measurements(m: &ApprovedSgxMeasurements) {
+        Self::validate_hex_field("mrenclave", &m.mrenclave, 64);
+        Self::validate_hex_field("mrsigner", &m.mrsigner, 64);
+    }
 }
 
 
@@ -576,7 +823,7 @@ mod tests {
 
         let mut contract = RegisterContract::new(accounts(1), accounts(2), accounts(3));
         let m = dummy_measurements();
-        contract.add_approved_measurements(m.clone(), None);
+        contract.add_approved_measurements(m.clone(), None, None, None);
 
         assert!(contract.is_measurements_approved(m));
         assert_eq!(contract.get_approved_measurements().len(), 1);
@@ -589,8 +836,8 @@ mod tests {
 
         let mut contract = RegisterContract::new(accounts(1), accounts(2), accounts(3));
         let m = dummy_measurements();
-        contract.add_approved_measurements(m.clone(), None);
-        contract.add_approved_measurements(m.clone(), None);
+        contract.add_approved_measurements(m.clone(), None, None, None);
+        contract.add_approved_measurements(m.clone(), None, None, None);
 
         assert_eq!(contract.get_approved_measurements().len(), 1);
     }
@@ -602,10 +849,10 @@ mod tests {
 
         let mut contract = RegisterContract::new(accounts(1), accounts(2), accounts(3));
         let m1 = dummy_measurements();
-        contract.add_approved_measurements(m1.clone(), None);
+        contract.add_approved_measurements(m1.clone(), None, None, None);
 
         let m2 = ApprovedMeasurements { rtmr3: "f".repeat(96), ..m1 };
-        contract.add_approved_measurements(m2.clone(), Some(true));
+        contract.add_approved_measurements(m2.clone(), None, None, Some(true));
 
         assert_eq!(contract.get_approved_measurements().len(), 1);
         assert!(contract.is_measurements_approved(m2));
@@ -625,7 +872,7 @@ mod tests {
             rtmr2: "d".repeat(96),
             rtmr3: "e".repeat(96),
         };
-        contract.add_approved_measurements(m, None);
+        contract.add_approved_measurements(m, None, None, None);
     }
 
     #[test]
@@ -639,7 +886,192 @@ mod tests {
         // Change predecessor to non-owner
         testing_env!(context.predecessor_account_id(accounts(3)).build());
 
-        contract.add_approved_measurements(dummy_measurements(), None);
+        contract.add_approved_measurements(dummy_measurements(), None, None, None);
+    }
+
+    fn dummy_sgx_measurements() -> ApprovedSgxMeasurements {
+        ApprovedSgxMeasurements {
+            mrenclave: "a".repeat(64),
+            mrsigner: "b".repeat(64),
+        }
+    }
+
+    #[test]
+    fn test_add_and_remove_sgx_measurements() {
+        let context = get_context();
+        testing_env!(context.build());
+
+        let mut contract = RegisterContract::new(accounts(1), accounts(2), accounts(3));
+        let m = dummy_sgx_measurements();
+        contract.add_approved_sgx_measurements(m.clone());
+        contract.add_approved_sgx_measurements(m.clone());
+
+        assert!(contract.is_sgx_measurements_approved(m.clone()));
+        assert_eq!(contract.get_approved_sgx_measurements().len(), 1);
+        // SGX and TDX lists are independent
+        assert_eq!(contract.get_approved_measurements().len(), 0);
+
+        contract.remove_approved_sgx_measurements(m);
+        assert_eq!(contract.get_approved_sgx_measurements().len(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid mrsigner format")]
+    fn test_invalid_sgx_measurement_length() {
+        let context = get_context();
+        testing_env!(context.build());
+
+        let mut contract = RegisterContract::new(accounts(1), accounts(2), accounts(3));
+        let m = ApprovedSgxMeasurements {
+            mrenclave: "a".repeat(64),
+            // TDX-sized value is rejected
+            mrsigner: "b".repeat(96),
+        };
+        contract.add_approved_sgx_measurements(m);
+    }
+
+    #[test]
+    fn test_migrate_from_single_collateral_state() {
+        let context = get_context();
+        testing_env!(context.build());
+
+        let old = migration::RegisterContractV2 {
+            owner_id: accounts(1),
+            init_worker_account: accounts(2),
+            approved_measurements: vec![dummy_measurements()],
+            quote_collateral: Some("phala".to_string()),
+            outlayer_contract_id: accounts(3),
+        };
+        env::state_write(&old);
+
+        let contract = RegisterContract::migrate();
+        assert_eq!(contract.get_collaterals(), vec!["phala".to_string()]);
+        assert_eq!(contract.get_approved_measurements()[0].measurements, dummy_measurements());
+        assert!(contract.get_approved_sgx_measurements().is_empty());
+    }
+
+    #[test]
+    fn test_migrate_from_tdx_only_state() {
+        let context = get_context();
+        testing_env!(context.build());
+
+        let old = migration::RegisterContractV3 {
+            owner_id: accounts(1),
+            init_worker_account: accounts(2),
+            approved_measurements: vec![dummy_measurements()],
+            collaterals: vec!["collateral".to_string()],
+            outlayer_contract_id: accounts(3),
+        };
+        env::state_write(&old);
+
+        let contract = RegisterContract::migrate();
+        let entries = contract.get_approved_measurements();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].measurements, dummy_measurements());
+        assert_eq!(entries[0].label, "");
+        assert_eq!(entries[0].expires_at, None);
+        assert_eq!(contract.get_collaterals(), vec!["collateral".to_string()]);
+        assert!(contract.get_approved_sgx_measurements().is_empty());
+    }
+
+    #[test]
+    fn test_migrate_keeps_sgx_measurements() {
+        let context = get_context();
+        testing_env!(context.build());
+
+        let old = migration::RegisterContractV4 {
+            owner_id: accounts(1),
+            init_worker_account: accounts(2),
+            approved_measurements: vec![dummy_measurements()],
+            collaterals: vec![],
+            outlayer_contract_id: accounts(3),
+            approved_sgx_measurements: vec![dummy_sgx_measurements()],
+        };
+        env::state_write(&old);
+
+        let contract = RegisterContract::migrate();
+        assert_eq!(contract.get_approved_measurements()[0].label, "");
+        assert_eq!(contract.get_approved_sgx_measurements(), vec![dummy_sgx_measurements()]);
+    }
+
+    #[test]
+    fn test_measurement_entry_label_and_expiry() {
+        let mut context = get_context();
+        testing_env!(context.block_timestamp(1_000).build());
+
+        let mut contract = RegisterContract::new(accounts(1), accounts(2), accounts(3));
+        let m = dummy_measurements();
+        contract.add_approved_measurements(m.clone(), Some("worker v1.4 phala".to_string()), Some(5_000), None);
+
+        let entry = &contract.get_approved_measurements()[0];
+        assert_eq!(entry.label, "worker v1.4 phala");
+        assert_eq!(entry.added_at, 1_000);
+        assert_eq!(entry.expires_at, Some(5_000));
+        assert!(contract.is_measurements_approved(m.clone()));
+
+        // Past expiry the entry is still listed but no longer approved
+        testing_env!(context.block_timestamp(5_000).build());
+        assert!(!contract.is_measurements_approved(m.clone()));
+        assert_eq!(contract.get_approved_measurements().len(), 1);
+
+        // Re-adding without expiry renews the approval in place
+        contract.add_approved_measurements(m.clone(), Some("worker v1.4 phala".to_string()), None, None);
+        assert!(contract.is_measurements_approved(m));
+        assert_eq!(contract.get_approved_measurements().len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "expires_at must be in the future")]
+    fn test_measurement_expiry_in_past_rejected() {
+        let mut context = get_context();
+        testing_env!(context.block_timestamp(1_000).build());
+
+        let mut contract = RegisterContract::new(accounts(1), accounts(2), accounts(3));
+        contract.add_approved_measurements(dummy_measurements(), None, Some(1_000), None);
+    }
+
+    fn worker_key(seed: &str) -> PublicKey {
+        let key = match seed {
+            "a" => "ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp",
+            _ => "ed25519:DcA2MzgpJbrUATQLLceocVckhhAqrkingax4oJ9kZ847",
+        };
+        key.parse().unwrap()
+    }
+
+    #[test]
+    fn test_remove_all_except_with_nothing_stale() {
+        let context = get_context();
+        testing_env!(context.build());
+
+        let mut contract = RegisterContract::new(accounts(1), accounts(2), accounts(3));
+        contract.worker_keys.insert(worker_key("a"));
+
+        match contract.remove_all_worker_keys_except(vec![worker_key("a")]) {
+            PromiseOrValue::Value(results) => assert!(results.is_empty()),
+            PromiseOrValue::Promise(_) => panic!("no keys should be revoked"),
+        }
+        assert!(contract.worker_keys.contains(&worker_key("a")));
+    }
+
+    #[test]
+    fn test_worker_keys_removed_callback_reports_each_key() {
+        let context = get_context();
+        testing_env!(
+            context.build(),
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![]), PromiseResult::Failed],
+        );
+
+        let mut contract = RegisterContract::new(accounts(1), accounts(2), accounts(3));
+        contract.worker_keys.insert(worker_key("a"));
+        contract.worker_keys.insert(worker_key("b"));
+
+        let results = contract.on_worker_keys_removed(vec![worker_key("a"), worker_key("b")]);
+        assert_eq!(results, vec![(worker_key("a"), true), (worker_key("b"), false)]);
+        // Both are gone from the account now, so neither stays tracked
+        assert!(contract.worker_keys.is_empty());
     }
 
     #[test]
@@ -649,7 +1081,7 @@ mod tests {
 
         let mut contract = RegisterContract::new(accounts(1), accounts(2), accounts(3));
         let m = dummy_measurements();
-        contract.add_approved_measurements(m.clone(), None);
+        contract.add_approved_measurements(m.clone(), None, None, None);
         assert_eq!(contract.get_approved_measurements().len(), 1);
 
         contract.remove_approved_measurements(m);
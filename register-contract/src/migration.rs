@@ -1,14 +1,13 @@
 use crate::*;
 use near_sdk::borsh::BorshDeserialize;
 
-/// Previous on-chain layout: a single `quote_collateral: Option<String>`.
+/// Layout with a single `quote_collateral: Option<String>`.
 /// The live `worker.outlayer.testnet` state matches this (owner + init account +
 /// approved_measurements + one collateral). `migrate()` moves that single collateral into the
-/// new multi-slot `collaterals` vec at slot 0 (e.g. the existing Phala 20a06f000000 collateral),
+/// multi-slot `collaterals` vec at slot 0 (e.g. the existing Phala 20a06f000000 collateral),
 /// preserving approved_measurements. Field order MUST match the serialized layout.
-#[derive(BorshDeserialize)]
+#[derive(BorshDeserialize, BorshSerialize)]
 #[borsh(crate = "near_sdk::borsh")]
-#[allow(dead_code)] // fields needed for Borsh deserialization during migration
 pub struct RegisterContractV2 {
     pub owner_id: AccountId,
     pub init_worker_account: AccountId,
@@ -17,21 +16,126 @@ pub struct RegisterContractV2 {
     pub outlayer_contract_id: AccountId,
 }
 
+/// Multi-slot collaterals, TDX-only (no `approved_sgx_measurements`).
+/// Field order MUST match the serialized layout.
+#[derive(BorshDeserialize, BorshSerialize)]
+#[borsh(crate = "near_sdk::borsh")]
+pub struct RegisterContractV3 {
+    pub owner_id: AccountId,
+    pub init_worker_account: AccountId,
+    pub approved_measurements: Vec<ApprovedMeasurements>,
+    pub collaterals: Vec<String>,
+    pub outlayer_contract_id: AccountId,
+}
+
+impl From<RegisterContractV2> for RegisterContractV3 {
+    fn from(old: RegisterContractV2) -> Self {
+        Self {
+            owner_id: old.owner_id,
+            init_worker_account: old.init_worker_account,
+            approved_measurements: old.approved_measurements,
+            // Move the single cached collateral into slot 0; owner adds others (self-hosted
+            // FMSPC) via `update_collateral(collateral, 1)`.
+            collaterals: old.quote_collateral.map(|c| vec![c]).unwrap_or_default(),
+            outlayer_contract_id: old.outlayer_contract_id,
+        }
+    }
+}
+
+/// Bare `ApprovedMeasurements` (no label/expiry) plus the SGX allowlist.
+/// Field order MUST match the serialized layout.
+#[derive(BorshDeserialize, BorshSerialize)]
+#[borsh(crate = "near_sdk::borsh")]
+pub struct RegisterContractV4 {
+    pub owner_id: AccountId,
+    pub init_worker_account: AccountId,
+    pub approved_measurements: Vec<ApprovedMeasurements>,
+    pub collaterals: Vec<String>,
+    pub outlayer_contract_id: AccountId,
+    pub approved_sgx_measurements: Vec<ApprovedSgxMeasurements>,
+}
+
+impl From<RegisterContractV3> for RegisterContractV4 {
+    fn from(old: RegisterContractV3) -> Self {
+        Self {
+            owner_id: old.owner_id,
+            init_worker_account: old.init_worker_account,
+            approved_measurements: old.approved_measurements,
+            collaterals: old.collaterals,
+            outlayer_contract_id: old.outlayer_contract_id,
+            // Owner approves SGX enclaves via `add_approved_sgx_measurements`.
+            approved_sgx_measurements: Vec::new(),
+        }
+    }
+}
+
+/// Previous layout: labeled measurement entries, no tracked `worker_keys` set.
+/// Field order MUST match the serialized layout.
+#[derive(BorshDeserialize, BorshSerialize)]
+#[borsh(crate = "near_sdk::borsh")]
+pub struct RegisterContractV5 {
+    pub owner_id: AccountId,
+    pub init_worker_account: AccountId,
+    pub approved_measurements: Vec<ApprovedMeasurementEntry>,
+    pub collaterals: Vec<String>,
+    pub outlayer_contract_id: AccountId,
+    pub approved_sgx_measurements: Vec<ApprovedSgxMeasurements>,
+}
+
+impl From<RegisterContractV4> for RegisterContractV5 {
+    fn from(old: RegisterContractV4) -> Self {
+        Self {
+            owner_id: old.owner_id,
+            init_worker_account: old.init_worker_account,
+            // Existing bare entries become unlabeled, non-expiring entries.
+            approved_measurements: old
+                .approved_measurements
+                .into_iter()
+                .map(|measurements| ApprovedMeasurementEntry {
+                    measurements,
+                    label: String::new(),
+                    added_at: env::block_timestamp(),
+                    expires_at: None,
+                })
+                .collect(),
+            collaterals: old.collaterals,
+            outlayer_contract_id: old.outlayer_contract_id,
+            approved_sgx_measurements: old.approved_sgx_measurements,
+        }
+    }
+}
+
 #[near_bindgen]
 impl RegisterContract {
     #[private]
     #[init(ignore_state)]
     pub fn migrate() -> Self {
-        let old: RegisterContractV2 = env::state_read().expect("Failed to read old state");
+        // Accept every layout since single-collateral (V2, live on `worker.outlayer.testnet`).
+        // `try_from_slice` requires every byte to be consumed, so one layout never parses as
+        // another.
+        let bytes = env::storage_read(b"STATE").expect("Failed to read old state");
+        let old = RegisterContractV5::try_from_slice(&bytes)
+            .or_else(|_| RegisterContractV4::try_from_slice(&bytes).map(RegisterContractV5::from))
+            .or_else(|_| {
+                RegisterContractV3::try_from_slice(&bytes)
+                    .map(|v3| RegisterContractV5::from(RegisterContractV4::from(v3)))
+            })
+            .or_else(|_| {
+                RegisterContractV2::try_from_slice(&bytes).map(|v2| {
+                    RegisterContractV5::from(RegisterContractV4::from(RegisterContractV3::from(v2)))
+                })
+            })
+            .expect("Failed to read old state");
 
         Self {
             owner_id: old.owner_id,
             init_worker_account: old.init_worker_account,
             approved_measurements: old.approved_measurements,
-            // Move the single cached collateral into slot 0; owner adds others (self-hosted
-            // FMSPC) via `update_collateral(collateral, 1)`.
-            collaterals: old.quote_collateral.map(|c| vec![c]).unwrap_or_default(),
+            collaterals: old.collaterals,
             outlayer_contract_id: old.outlayer_contract_id,
+            approved_sgx_measurements: old.approved_sgx_measurements,
+            // Keys registered before tracking are revoked via `remove_worker_keys`.
+            worker_keys: IterableSet::with_hasher(StorageKey::WorkerKeys),
         }
     }
 }
@@ -0,0 +1,133 @@
+//! NEP-413 signed message verification (feature `crypto`)
+//!
+//! Wallets sign off-chain messages per [NEP-413]: the signature covers
+//! `SHA256(2^31 + 413 as u32 LE || borsh(message, nonce, recipient, callback_url))`.
+//! [`verify_nep413`] rebuilds that payload the same way the keystore does, so an ark can
+//! check a user's signed request (gasless actions, meta-transactions) without carrying
+//! its own ed25519 and serialization code.
+//!
+//! Only checks the signature. Whether `public_key` belongs to the claimed account
+//! (an access key on chain) and whether the nonce was used before is up to the ark.
+//!
+//! ```rust,ignore
+//! use outlayer::crypto;
+//!
+//! if !crypto::verify_nep413(&msg, &nonce_b64, "my-ark.near", &public_key, &signature_b64) {
+//!     return Err("invalid signature".into());
+//! }
+//! ```
+//!
+//! [NEP-413]: https://github.com/near/NEPs/blob/master/neps/nep-0413.md
+
+use base64::Engine;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use sha2::{Digest, Sha256};
+
+/// NEP-413 tag: 2^31 + 413
+pub const NEP413_TAG: u32 = 2_147_484_061;
+
+/// Verify a NEP-413 signature
+///
+/// # Arguments
+/// * `message` - The signed message text
+/// * `nonce` - Base64 of the 32-byte nonce the wallet signed with
+/// * `recipient` - Recipient the message was signed for
+/// * `public_key` - Signer key as `ed25519:<base58>`
+/// * `signature` - Base64 ed25519 signature, as returned by wallet `signMessage`
+///
+/// # Returns
+/// `true` if the signature is valid; `false` if it isn't or any argument is malformed
+pub fn verify_nep413(message: &str, nonce: &str, recipient: &str, public_key: &str, signature: &str) -> bool {
+    let base64 = base64::engine::general_purpose::STANDARD;
+    let Some(nonce) = base64.decode(nonce).ok().and_then(|n| <[u8; 32]>::try_from(n).ok()) else {
+        return false;
+    };
+    let Some(signature) = base64.decode(signature).ok().and_then(|s| <[u8; 64]>::try_from(s).ok()) else {
+        return false;
+    };
+    let Some(key) = parse_public_key(public_key) else {
+        return false;
+    };
+    let hash = nep413_hash(message, &nonce, recipient);
+    key.verify(&hash, &Signature::from_bytes(&signature)).is_ok()
+}
+
+/// Hash a wallet signs for a NEP-413 message (no callback URL)
+pub fn nep413_hash(message: &str, nonce: &[u8; 32], recipient: &str) -> [u8; 32] {
+    let mut payload = Vec::with_capacity(4 + 4 + message.len() + 32 + 4 + recipient.len() + 1);
+    payload.extend_from_slice(&NEP413_TAG.to_le_bytes());
+    // Borsh: strings are u32 LE length + bytes, Option::None is a single 0
+    payload.extend_from_slice(&(message.len() as u32).to_le_bytes());
+    payload.extend_from_slice(message.as_bytes());
+    payload.extend_from_slice(nonce);
+    payload.extend_from_slice(&(recipient.len() as u32).to_le_bytes());
+    payload.extend_from_slice(recipient.as_bytes());
+    payload.push(0);
+    Sha256::digest(&payload).into()
+}
+
+fn parse_public_key(public_key: &str) -> Option<VerifyingKey> {
+    let encoded = public_key.strip_prefix("ed25519:")?;
+    let bytes: [u8; 32] = bs58::decode(encoded).into_vec().ok()?.try_into().ok()?;
+    VerifyingKey::from_bytes(&bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    struct Signed {
+        public_key: String,
+        nonce: String,
+        signature: String,
+    }
+
+    fn sign(message: &str, recipient: &str) -> Signed {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let nonce = [42u8; 32];
+        let signature = key.sign(&nep413_hash(message, &nonce, recipient));
+        let base64 = base64::engine::general_purpose::STANDARD;
+        Signed {
+            public_key: format!("ed25519:{}", bs58::encode(key.verifying_key().as_bytes()).into_string()),
+            nonce: base64.encode(nonce),
+            signature: base64.encode(signature.to_bytes()),
+        }
+    }
+
+    #[test]
+    fn accepts_valid_signature() {
+        let s = sign("transfer 5 to bob.near", "ark.near");
+        assert!(verify_nep413("transfer 5 to bob.near", &s.nonce, "ark.near", &s.public_key, &s.signature));
+    }
+
+    #[test]
+    fn rejects_changed_fields() {
+        let s = sign("transfer 5 to bob.near", "ark.near");
+        assert!(!verify_nep413("transfer 6 to bob.near", &s.nonce, "ark.near", &s.public_key, &s.signature));
+        assert!(!verify_nep413("transfer 5 to bob.near", &s.nonce, "other.near", &s.public_key, &s.signature));
+
+        let other_nonce = base64::engine::general_purpose::STANDARD.encode([1u8; 32]);
+        assert!(!verify_nep413("transfer 5 to bob.near", &other_nonce, "ark.near", &s.public_key, &s.signature));
+    }
+
+    #[test]
+    fn rejects_malformed_arguments() {
+        let s = sign("hi", "ark.near");
+        let short_nonce = base64::engine::general_purpose::STANDARD.encode([42u8; 16]);
+        let secp_key = s.public_key.replacen("ed25519:", "secp256k1:", 1);
+        assert!(!verify_nep413("hi", &short_nonce, "ark.near", &s.public_key, &s.signature));
+        assert!(!verify_nep413("hi", &s.nonce, "ark.near", &secp_key, &s.signature));
+        assert!(!verify_nep413("hi", &s.nonce, "ark.near", &s.public_key, "not base64!"));
+    }
+
+    #[test]
+    fn hash_matches_borsh_layout() {
+        let hash = nep413_hash("a", &[0u8; 32], "b");
+        let mut expected = NEP413_TAG.to_le_bytes().to_vec();
+        expected.extend_from_slice(&[1, 0, 0, 0, b'a']);
+        expected.extend_from_slice(&[0u8; 32]);
+        expected.extend_from_slice(&[1, 0, 0, 0, b'b', 0]);
+        assert_eq!(hash, <[u8; 32]>::from(Sha256::digest(&expected)));
+    }
+}
@@ -32,6 +32,9 @@
 //! - `NEAR_SENDER_ID` - Account that signed the transaction (original user, e.g. alice.near)
 //! - `NEAR_PREDECESSOR_ID` - Contract that called OutLayer directly (e.g. token.near)
 //! - `NEAR_TRANSACTION_HASH` - Transaction hash (if applicable)
+//! - `OUTLAYER_INPUT_CONTENT_TYPE` - Media type the caller declared for the input (if any)
+//!
+//! [`context()`] parses all of them once into a typed [`ExecutionContext`].
 //!
 //! Example call chain: User (alice.near) → Token (token.near) → OutLayer → Worker → WASM
 //! - NEAR_SENDER_ID = alice.near (user who signed)
@@ -45,6 +48,282 @@
 //! ```
 
 use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+/// Error parsing an injected environment variable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvError {
+    /// Variable name (e.g. `NEAR_BLOCK_HEIGHT`)
+    pub var: &'static str,
+    /// Raw value that failed to parse
+    pub value: String,
+    /// Parse failure reason
+    pub reason: String,
+}
+
+impl std::fmt::Display for EnvError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Env error: invalid {}={:?}: {}", self.var, self.value, self.reason)
+    }
+}
+
+impl std::error::Error for EnvError {}
+
+/// NEAR execution metadata injected by the worker
+///
+/// Unset or empty variables are `None` (HTTPS calls have no blockchain context).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionContext {
+    /// Account that signed the transaction (`NEAR_SENDER_ID`)
+    pub signer_id: Option<String>,
+    /// OutLayer contract account (`NEAR_CONTRACT_ID`)
+    pub contract_id: Option<String>,
+    /// Block of the execution request (`NEAR_BLOCK_HEIGHT`)
+    pub block_height: Option<u64>,
+    /// Block timestamp in nanoseconds (`NEAR_BLOCK_TIMESTAMP`)
+    pub block_timestamp: Option<u64>,
+    /// Receipt that created the request (`NEAR_RECEIPT_ID`)
+    pub receipt_id: Option<String>,
+    /// Contract that called OutLayer (`NEAR_PREDECESSOR_ID`)
+    pub predecessor_id: Option<String>,
+    /// Attached NEAR payment in yoctoNEAR (`NEAR_PAYMENT_YOCTO`)
+    pub payment_yocto: Option<u128>,
+    /// Execution request ID (`NEAR_REQUEST_ID`), 0 for HTTPS calls
+    pub request_id: u64,
+}
+
+impl ExecutionContext {
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, EnvError> {
+        let string = |var: &'static str| lookup(var).filter(|v| !v.is_empty());
+
+        Ok(Self {
+            signer_id: string("NEAR_SENDER_ID"),
+            contract_id: string("NEAR_CONTRACT_ID"),
+            block_height: parse_var("NEAR_BLOCK_HEIGHT", string("NEAR_BLOCK_HEIGHT"))?,
+            block_timestamp: parse_var("NEAR_BLOCK_TIMESTAMP", string("NEAR_BLOCK_TIMESTAMP"))?,
+            receipt_id: string("NEAR_RECEIPT_ID"),
+            predecessor_id: string("NEAR_PREDECESSOR_ID"),
+            payment_yocto: parse_var("NEAR_PAYMENT_YOCTO", string("NEAR_PAYMENT_YOCTO"))?,
+            request_id: parse_var("NEAR_REQUEST_ID", string("NEAR_REQUEST_ID"))?.unwrap_or(0),
+        })
+    }
+}
+
+fn parse_var<T>(var: &'static str, value: Option<String>) -> Result<Option<T>, EnvError>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    value
+        .map(|v| {
+            v.trim().parse().map_err(|e: T::Err| EnvError {
+                var,
+                reason: e.to_string(),
+                value: v,
+            })
+        })
+        .transpose()
+}
+
+/// Get all NEAR execution metadata as a typed struct
+///
+/// Variables are parsed on the first call and cached for the rest of the execution.
+///
+/// # Returns
+/// * `Ok(ExecutionContext)` - Parsed context (`None` for unset fields)
+/// * `Err(EnvError)` - A numeric variable is not a valid number
+///
+/// # Example
+/// ```rust,ignore
+/// let ctx = env::context()?;
+/// if let (Some(signer), Some(height)) = (&ctx.signer_id, ctx.block_height) {
+///     println!("request {} from {} at block {}", ctx.request_id, signer, height);
+/// }
+/// ```
+pub fn context() -> Result<ExecutionContext, EnvError> {
+    static CONTEXT: OnceLock<Result<ExecutionContext, EnvError>> = OnceLock::new();
+    CONTEXT
+        .get_or_init(|| ExecutionContext::from_lookup(|var| std::env::var(var).ok()))
+        .clone()
+}
+
+/// Timestamp of the block that created the request, in nanoseconds
+///
+/// Use this instead of `std::time::SystemTime::now()` for anything that must be
+/// reproducible: freshness checks, deadlines, expiry. The wall clock differs between
+/// the original run and a verification re-run, the block timestamp doesn't, and it can
+/// be checked against the chain.
+///
+/// # Returns
+/// * `Some(nanoseconds)` - From `NEAR_BLOCK_TIMESTAMP`
+/// * `None` - HTTPS calls (no block) or the variable is malformed
+///
+/// # Example
+/// ```rust,ignore
+/// let now = env::block_timestamp().ok_or("no block context")?;
+/// if signed_at_ns + MAX_AGE_NS < now {
+///     return Err("request expired".into());
+/// }
+/// ```
+pub fn block_timestamp() -> Option<u64> {
+    context().ok().and_then(|ctx| ctx.block_timestamp)
+}
+
+/// [`block_timestamp`] in whole seconds since the Unix epoch
+///
+/// The deterministic replacement for
+/// `SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs()`.
+pub fn now_secs() -> Option<u64> {
+    block_timestamp().map(|ns| ns / 1_000_000_000)
+}
+
+/// Deterministic pseudo-random bytes for this execution
+///
+/// Bytes come from a BLAKE3 stream seeded by the receipt id, request id and
+/// HTTPS call id (`NEAR_RECEIPT_ID`, `NEAR_REQUEST_ID`, `OUTLAYER_CALL_ID`).
+/// Successive calls continue the same stream, so the same execution always
+/// yields the same sequence and re-running it for verification reproduces the
+/// output exactly.
+///
+/// **Not unpredictable.** The seed is public and the worker operator can
+/// compute every byte before running the ark. Use it for shuffling, sampling
+/// and nonces that only need to be unique per execution; use [`crate::vrf`]
+/// for anything a participant could profit from predicting (lotteries, leader
+/// election, key material).
+///
+/// # Example
+/// ```rust,ignore
+/// let nonce = env::random_bytes(16);
+/// let pick = env::random_u64() % candidates.len() as u64;
+/// ```
+pub fn random_bytes(n: usize) -> Vec<u8> {
+    static SEED: OnceLock<[u8; 32]> = OnceLock::new();
+    static POSITION: AtomicU64 = AtomicU64::new(0);
+
+    let seed = SEED.get_or_init(|| {
+        let var = |name: &str| std::env::var(name).unwrap_or_default();
+        random_seed(&var("NEAR_RECEIPT_ID"), &var("NEAR_REQUEST_ID"), &var("OUTLAYER_CALL_ID"))
+    });
+    let position = POSITION.fetch_add(n as u64, Ordering::Relaxed);
+    random_stream(seed, position, n)
+}
+
+/// Deterministic pseudo-random `u64` for this execution
+///
+/// Next 8 bytes of [`random_bytes`] (little-endian); the same caveats apply.
+pub fn random_u64() -> u64 {
+    let bytes: [u8; 8] = random_bytes(8).try_into().expect("random_bytes(8) returns 8 bytes");
+    u64::from_le_bytes(bytes)
+}
+
+/// Instructions this execution has consumed so far
+///
+/// Counted by the worker's wasmtime fuel meter, roughly one per WASM operator, and
+/// exact as of the call. Time spent in host calls (RPC, storage, HTTP) is not
+/// counted. The value never decreases within one execution, so two readings bound
+/// the cost of the code between them.
+///
+/// # Example
+/// ```rust,ignore
+/// let before = env::instructions_used();
+/// process_batch(&items);
+/// let per_batch = env::instructions_used() - before;
+/// ```
+pub fn instructions_used() -> u64 {
+    crate::near::meter::api::instructions_used()
+}
+
+/// Instructions left before the execution is stopped
+///
+/// `max_instructions` from the request's resource limits minus
+/// [`instructions_used`]. An execution that reaches zero is killed mid-way, so
+/// long-running arks can use this to save progress to storage first.
+///
+/// # Example
+/// ```rust,ignore
+/// for (i, item) in items.iter().enumerate() {
+///     if env::instructions_remaining() < RESERVE_FOR_CHECKPOINT {
+///         storage::set("cursor", &i.to_le_bytes())?;
+///         break;
+///     }
+///     process(item);
+/// }
+/// ```
+pub fn instructions_remaining() -> u64 {
+    crate::near::meter::api::instructions_remaining()
+}
+
+/// Project app key derived by the MPC network, encrypted to the caller's key
+///
+/// Both points are `bls12381g1:<base58>`. The secret is `big_c - sk * big_y`, where `sk`
+/// is the scalar behind the `app_public_key` passed to [`request_app_key`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppKey {
+    pub big_y: String,
+    pub big_c: String,
+}
+
+/// Request this project's MPC-derived app key
+///
+/// The keystore asks the MPC network (keystore-dao `request_key`) for the key at
+/// derivation path `outlayer-ark:{project_id}`. The project is fixed by the worker,
+/// so an ark can only ever obtain its own project's key.
+///
+/// **Deterministic.** The decrypted secret depends only on the project and
+/// `domain_id`: every version of the project, on every worker, gets the same secret.
+/// `app_public_key` only protects it in transit, so generate a fresh ephemeral
+/// BLS12-381 key pair per call and keep the scalar inside the ark.
+///
+/// **TEE only.** Only a keystore running in TEE and registered with keystore-dao can
+/// request keys, and only a TEE worker can reach it; elsewhere this returns an error.
+/// The keystore requests the project's secret from MPC once (that first call takes
+/// seconds) and re-encrypts it for later calls. Limited to one call per execution —
+/// derive further keys from the secret instead of calling again.
+///
+/// # Arguments
+/// * `domain_id` - MPC domain of the BLS12-381 key
+/// * `app_public_key` - Ephemeral key to encrypt to, `bls12381g1:<base58>`
+///
+/// # Example
+/// ```rust,ignore
+/// let key = env::request_app_key(2, &ephemeral_public_key)?;
+/// let secret = decrypt(&key.big_y, &key.big_c, &ephemeral_scalar);
+/// ```
+pub fn request_app_key(domain_id: u64, app_public_key: &str) -> Result<AppKey, String> {
+    let (big_y, big_c, error) = crate::raw::ckd::request_app_key(domain_id, app_public_key);
+    if !error.is_empty() {
+        return Err(error);
+    }
+    Ok(AppKey { big_y, big_c })
+}
+
+/// Plain (unkeyed) 32-byte BLAKE3 digest of `data`
+///
+/// [`random_seed`] applies it to the length-prefixed receipt id, request id and
+/// call id; anyone re-deriving the seed must use the same hash.
+fn hash32(data: &[u8]) -> [u8; 32] {
+    *blake3::hash(data).as_bytes()
+}
+
+/// Seed for [`random_bytes`]; fields are length-prefixed so they can't run together
+fn random_seed(receipt_id: &str, request_id: &str, call_id: &str) -> [u8; 32] {
+    let mut data = b"outlayer:env:random:v1".to_vec();
+    for field in [receipt_id, request_id, call_id] {
+        data.extend_from_slice(&(field.len() as u64).to_le_bytes());
+        data.extend_from_slice(field.as_bytes());
+    }
+    hash32(&data)
+}
+
+/// `n` bytes of the keyed BLAKE3 output stream for `seed`, starting at `position`
+fn random_stream(seed: &[u8; 32], position: u64, n: usize) -> Vec<u8> {
+    let mut reader = blake3::Hasher::new_keyed(seed).finalize_xof();
+    reader.set_position(position);
+    let mut out = vec![0u8; n];
+    reader.fill(&mut out);
+    out
+}
 
 /// Get the NEAR account ID that requested this execution
 ///
@@ -97,6 +376,7 @@ pub fn request_id() -> Option<String> {
 /// Get input data from the execution request
 ///
 /// Reads all data from stdin, which contains the `input_data` from `request_execution`.
+/// Stdin is read once; later calls (and the other `input*` accessors) return the same data.
 ///
 /// # Returns
 /// The input data as bytes. Empty if no input was provided.
@@ -124,9 +404,55 @@ pub fn request_id() -> Option<String> {
 /// }
 /// ```
 pub fn input() -> Vec<u8> {
-    let mut buffer = Vec::new();
-    io::stdin().read_to_end(&mut buffer).unwrap_or(0);
-    buffer
+    stdin_input().to_vec()
+}
+
+fn stdin_input() -> &'static [u8] {
+    static INPUT: OnceLock<Vec<u8>> = OnceLock::new();
+    INPUT.get_or_init(|| {
+        let mut buffer = Vec::new();
+        io::stdin().read_to_end(&mut buffer).unwrap_or(0);
+        buffer
+    })
+}
+
+/// Get input data as raw bytes
+///
+/// Unlike [`input_string`] and [`input_json`], the bytes are returned untouched, for
+/// arks that take binary input such as a signed payload.
+///
+/// # Returns
+/// * `Some(bytes)` - The input exactly as received
+/// * `None` - No input provided
+///
+/// # Example
+/// ```rust,ignore
+/// let payload = env::input_bytes().ok_or("missing signed payload")?;
+/// ```
+pub fn input_bytes() -> Option<Vec<u8>> {
+    let data = stdin_input();
+    (!data.is_empty()).then(|| data.to_vec())
+}
+
+/// Media type the caller declared for the input
+///
+/// Set from `input_content_type` in the request params (`OUTLAYER_INPUT_CONTENT_TYPE`).
+/// OutLayer doesn't check the input against it; it tells the ark how the caller meant
+/// the input to be parsed.
+///
+/// # Returns
+/// * `Some(media_type)` - e.g. `"application/json"` or `"text/plain; charset=utf-8"`
+/// * `None` - The caller didn't declare one
+///
+/// # Example
+/// ```rust,ignore
+/// match env::input_content_type().as_deref() {
+///     Some("application/octet-stream") => verify(&env::input_bytes().unwrap_or_default()),
+///     _ => handle(env::input_json::<Request>()?),
+/// }
+/// ```
+pub fn input_content_type() -> Option<String> {
+    std::env::var("OUTLAYER_INPUT_CONTENT_TYPE").ok().filter(|v| !v.is_empty())
 }
 
 /// Get input data as a string
@@ -192,12 +518,18 @@ pub fn input_json<T: serde::de::DeserializeOwned>() -> Result<Option<T>, serde_j
 /// # Arguments
 /// * `data` - The output data as bytes
 ///
+/// # Panics
+/// If the output is being streamed with [`output_append`]: the two can't be mixed.
+///
 /// # Example
 /// ```rust,ignore
 /// let result = b"Success!";
 /// env::output(result);
 /// ```
 pub fn output(data: &[u8]) {
+    if let Err(e) = advance_output(OutputMode::Whole) {
+        panic!("{}", e);
+    }
     let _ = io::stdout().write_all(data);
     let _ = io::stdout().flush();
 }
@@ -243,6 +575,315 @@ pub fn output_json<T: serde::Serialize>(value: &T) -> Result<(), serde_json::Err
     Ok(())
 }
 
+/// Error from streaming output with [`output_append`] / [`output_finish`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputError {
+    /// Output was already written with [`output`] (or `output_string`, `output_json`, ...)
+    AlreadyWritten,
+    /// [`output_finish`] was already called
+    Finished,
+    /// Writing the chunk to the worker failed
+    Io(String),
+}
+
+impl std::fmt::Display for OutputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputError::AlreadyWritten => {
+                write!(f, "Output error: env::output and env::output_append can't be mixed")
+            }
+            OutputError::Finished => write!(f, "Output error: output already finished"),
+            OutputError::Io(reason) => write!(f, "Output error: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for OutputError {}
+
+/// How this execution writes its output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum OutputMode {
+    Unset = 0,
+    /// [`output`]: may be called repeatedly
+    Whole = 1,
+    /// [`output_append`] chunks
+    Streaming = 2,
+    /// [`output_finish`] was called
+    Finished = 3,
+}
+
+impl OutputMode {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => OutputMode::Whole,
+            2 => OutputMode::Streaming,
+            3 => OutputMode::Finished,
+            _ => OutputMode::Unset,
+        }
+    }
+
+    /// Mode after a call that needs `next`, or why the call isn't allowed
+    fn transition(self, next: OutputMode) -> Result<OutputMode, OutputError> {
+        match (self, next) {
+            (OutputMode::Finished, _) => Err(OutputError::Finished),
+            (OutputMode::Whole, OutputMode::Streaming | OutputMode::Finished)
+            | (OutputMode::Streaming, OutputMode::Whole) => Err(OutputError::AlreadyWritten),
+            (_, next) => Ok(next),
+        }
+    }
+}
+
+static OUTPUT_MODE: AtomicU8 = AtomicU8::new(OutputMode::Unset as u8);
+
+/// Record a call needing `next` in [`OUTPUT_MODE`]
+fn advance_output(next: OutputMode) -> Result<(), OutputError> {
+    let current = OutputMode::from_u8(OUTPUT_MODE.load(Ordering::Relaxed));
+    let mode = current.transition(next)?;
+    OUTPUT_MODE.store(mode as u8, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Append a chunk to the execution output
+///
+/// Streams output to the worker piece by piece, so an ark producing a large
+/// result (a CSV report, a long JSON array) never holds all of it in WASM
+/// memory. Chunks go straight to the worker's stdout buffer, which assembles
+/// the final output; a result over 1024 bytes is submitted on-chain with the
+/// `submit_execution_output` flow as usual. The total is still capped by
+/// `max_output_bytes`.
+///
+/// Call [`output_finish`] after the last chunk. Don't mix with [`output`].
+///
+/// # Returns
+/// * `Ok(())` - Chunk handed to the worker
+/// * `Err(OutputError::AlreadyWritten)` - [`output`] was already used
+/// * `Err(OutputError::Finished)` - [`output_finish`] was already called
+///
+/// # Example
+/// ```rust,ignore
+/// env::output_append(b"account,balance\n")?;
+/// for account in accounts {
+///     env::output_append(format!("{},{}\n", account.id, account.balance).as_bytes())?;
+/// }
+/// env::output_finish()?;
+/// ```
+pub fn output_append(chunk: &[u8]) -> Result<(), OutputError> {
+    advance_output(OutputMode::Streaming)?;
+    let mut stdout = io::stdout();
+    stdout
+        .write_all(chunk)
+        .and_then(|()| stdout.flush())
+        .map_err(|e| OutputError::Io(e.to_string()))
+}
+
+/// Finish streamed output
+///
+/// Flushes the last chunk; afterwards [`output_append`] and [`output`] fail.
+/// Finishing without any chunk leaves the output empty.
+///
+/// # Returns
+/// * `Ok(())` - Output complete
+/// * `Err(OutputError::AlreadyWritten)` - Output was written with [`output`]
+/// * `Err(OutputError::Finished)` - Called twice
+pub fn output_finish() -> Result<(), OutputError> {
+    advance_output(OutputMode::Finished)?;
+    io::stdout().flush().map_err(|e| OutputError::Io(e.to_string()))
+}
+
+/// Standard output envelope written by [`output_result`]
+///
+/// Serialized as `{"success": bool, "data": T | null, "error": E | null}`;
+/// both `data` and `error` are always present so clients can decode every ark
+/// the same way. Decode it with [`decode_result`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct OutputEnvelope<T, E> {
+    pub success: bool,
+    pub data: Option<T>,
+    pub error: Option<E>,
+}
+
+impl<T, E> From<Result<T, E>> for OutputEnvelope<T, E> {
+    fn from(result: Result<T, E>) -> Self {
+        match result {
+            Ok(data) => Self {
+                success: true,
+                data: Some(data),
+                error: None,
+            },
+            Err(error) => Self {
+                success: false,
+                data: None,
+                error: Some(error),
+            },
+        }
+    }
+}
+
+/// Write a `Result` as the standard [`OutputEnvelope`] JSON
+///
+/// # Returns
+/// * `Ok(())` - Success
+/// * `Err(e)` - JSON serialization error
+///
+/// # Example
+/// ```rust,ignore
+/// fn run() -> Result<Quote, String> { ... }
+///
+/// fn main() {
+///     // {"success":true,"data":{...},"error":null}
+///     // {"success":false,"data":null,"error":"pair not found"}
+///     env::output_result(run()).unwrap();
+/// }
+/// ```
+pub fn output_result<T: serde::Serialize, E: serde::Serialize>(
+    result: Result<T, E>,
+) -> Result<(), serde_json::Error> {
+    output_json(&OutputEnvelope::from(result))
+}
+
+/// Decode output written by [`output_result`] back into a `Result`
+///
+/// For callers of an ark (contracts, HTTPS clients, tests) that share the
+/// Rust types. A missing or `null` `data` decodes as `T` from JSON `null`,
+/// so `Ok(())` round-trips.
+///
+/// # Returns
+/// * `Ok(Ok(data))` / `Ok(Err(error))` - Decoded envelope
+/// * `Err(e)` - Not a valid envelope for `T` / `E`
+///
+/// # Example
+/// ```rust,ignore
+/// let quote: Result<Quote, String> = env::decode_result(&output_bytes)?;
+/// ```
+pub fn decode_result<T, E>(bytes: &[u8]) -> Result<Result<T, E>, serde_json::Error>
+where
+    T: serde::de::DeserializeOwned,
+    E: serde::de::DeserializeOwned,
+{
+    use serde::de::Error;
+    use serde_json::Value;
+
+    #[derive(serde::Deserialize)]
+    struct RawEnvelope {
+        success: bool,
+        #[serde(default)]
+        data: Value,
+        #[serde(default)]
+        error: Value,
+    }
+
+    let envelope: RawEnvelope = serde_json::from_slice(bytes)?;
+    if envelope.success {
+        serde_json::from_value(envelope.data).map(Ok)
+    } else if envelope.error.is_null() {
+        Err(serde_json::Error::custom("failed output envelope has no error"))
+    } else {
+        serde_json::from_value(envelope.error).map(Err)
+    }
+}
+
+/// Stderr line prefix the worker parses into `error_structured`
+pub const STRUCTURED_ERROR_PREFIX: &str = "OUTLAYER_ERROR:";
+
+/// Fail the execution with a machine-readable error
+///
+/// Writes `{"code", "message", "details"}` to stderr on an
+/// `OUTLAYER_ERROR:` line and exits with status 1. The worker reports it as
+/// `error_structured` next to the usual string `error` (which reads
+/// `CODE: message`), so clients can branch on `code` instead of matching
+/// error text. On-chain, it appears in the `execution_failed` event; when the
+/// 1024 byte result payload is too small, `details` is dropped.
+///
+/// `code` must be 1-64 characters of `A-Z a-z 0-9 _ - .`; otherwise the
+/// worker ignores the document and only the string error is returned.
+///
+/// # Example
+/// ```rust,ignore
+/// if balance < required {
+///     env::fail(
+///         "INSUFFICIENT_BALANCE",
+///         "balance too low for this swap",
+///         serde_json::json!({ "required": required.to_string(), "available": balance.to_string() }),
+///     );
+/// }
+/// ```
+pub fn fail(code: &str, message: &str, details: serde_json::Value) -> ! {
+    let _ = io::stdout().flush();
+    let mut stderr = io::stderr();
+    let _ = stderr.write_all(structured_error_line(code, message, details).as_bytes());
+    let _ = stderr.flush();
+    std::process::exit(1)
+}
+
+/// Sentinel line written by [`fail`]; starts with a newline in case stderr is mid-line
+fn structured_error_line(code: &str, message: &str, details: serde_json::Value) -> String {
+    let document = serde_json::json!({
+        "code": code,
+        "message": message,
+        "details": details,
+    });
+    format!("\n{}{}\n", STRUCTURED_ERROR_PREFIX, document)
+}
+
+/// Stderr line prefix the worker parses into the declared output format
+pub const OUTPUT_FORMAT_PREFIX: &str = "OUTLAYER_OUTPUT_FORMAT:";
+
+/// Format of the output an ark produces, declared with [`set_output_format`]
+///
+/// Same variants as the contract's `ResponseFormat`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseFormat {
+    Bytes,
+    Text,
+    Json,
+    Cbor,
+    MessagePack,
+    Base64,
+}
+
+impl ResponseFormat {
+    /// Name as serialized by the contract (`"Json"`, `"Text"`, ...)
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ResponseFormat::Bytes => "Bytes",
+            ResponseFormat::Text => "Text",
+            ResponseFormat::Json => "Json",
+            ResponseFormat::Cbor => "Cbor",
+            ResponseFormat::MessagePack => "MessagePack",
+            ResponseFormat::Base64 => "Base64",
+        }
+    }
+}
+
+/// Declare the format of the output this execution writes
+///
+/// The caller picks `response_format`, but the ark knows what it actually
+/// produced. When the caller asked for `Json` and the ark declares anything
+/// else, the worker fails the execution with a clear error instead of trying
+/// to parse the output as JSON. `Text` and `Bytes` requests return the output
+/// as-is. The declared format is reported back in the execution response.
+///
+/// Written to stderr as an `OUTLAYER_OUTPUT_FORMAT:` line; the last one wins.
+/// The worker keeps only the tail of stderr, so after heavy logging, call it
+/// again right before writing the output.
+///
+/// # Example
+/// ```rust,ignore
+/// env::set_output_format(env::ResponseFormat::Text);
+/// env::output_string("plain text report");
+/// ```
+pub fn set_output_format(format: ResponseFormat) {
+    let mut stderr = io::stderr();
+    let _ = stderr.write_all(output_format_line(format).as_bytes());
+    let _ = stderr.flush();
+}
+
+/// Sentinel line written by [`set_output_format`]
+fn output_format_line(format: ResponseFormat) -> String {
+    format!("\n{}{}\n", OUTPUT_FORMAT_PREFIX, format.as_str())
+}
+
 /// Get an environment variable
 ///
 /// This includes both system variables and secrets stored via the contract.
@@ -310,3 +951,210 @@ pub fn has_var(key: &str) -> bool {
 pub fn predecessor_account_id() -> Option<String> {
     std::env::var("NEAR_PREDECESSOR_ID").ok()
 }
+
+/// Get the metadata embedded by the [`metadata!`](crate::metadata) macro
+///
+/// Lets an ark self-report which build produced an output.
+///
+/// Requires exactly one `outlayer::metadata! { ... }` invocation in the binary;
+/// otherwise linking fails with an undefined `__OUTLAYER_ARK_METADATA` symbol.
+///
+/// # Example
+/// ```rust,ignore
+/// outlayer::metadata! {
+///     project: "alice.near/my-ark",
+///     version: "1.2.0",
+/// }
+///
+/// let meta = env::metadata();
+/// println!("{} v{}", meta.project, meta.version);
+/// ```
+pub fn metadata() -> crate::metadata::ArkMetadata {
+    extern "Rust" {
+        static __OUTLAYER_ARK_METADATA: crate::metadata::EmbeddedMetadata;
+    }
+    // SAFETY: the symbol is defined by the `metadata!` macro as an immutable static
+    unsafe { (*std::ptr::addr_of!(__OUTLAYER_ARK_METADATA)).to_metadata() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn parse(vars: &[(&str, &str)]) -> Result<ExecutionContext, EnvError> {
+        let vars: HashMap<String, String> =
+            vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        ExecutionContext::from_lookup(|var| vars.get(var).cloned())
+    }
+
+    #[test]
+    fn parses_near_context() {
+        let ctx = parse(&[
+            ("NEAR_SENDER_ID", "alice.near"),
+            ("NEAR_CONTRACT_ID", "outlayer.near"),
+            ("NEAR_BLOCK_HEIGHT", "123456"),
+            ("NEAR_BLOCK_TIMESTAMP", "1700000000000000000"),
+            ("NEAR_RECEIPT_ID", "9MzuZrRPW1BGpFnZJUJg6SzCrixPpJDfjsNeUobRXsLe"),
+            ("NEAR_PREDECESSOR_ID", "token.near"),
+            ("NEAR_PAYMENT_YOCTO", "100000000000000000000000"),
+            ("NEAR_REQUEST_ID", "42"),
+        ])
+        .unwrap();
+
+        assert_eq!(ctx.signer_id.as_deref(), Some("alice.near"));
+        assert_eq!(ctx.contract_id.as_deref(), Some("outlayer.near"));
+        assert_eq!(ctx.block_height, Some(123456));
+        assert_eq!(ctx.block_timestamp, Some(1_700_000_000_000_000_000));
+        assert_eq!(ctx.predecessor_id.as_deref(), Some("token.near"));
+        assert_eq!(ctx.payment_yocto, Some(100_000_000_000_000_000_000_000));
+        assert_eq!(ctx.request_id, 42);
+    }
+
+    #[test]
+    fn empty_and_unset_vars_are_none() {
+        // HTTPS calls set blockchain vars to empty strings
+        let ctx = parse(&[
+            ("NEAR_SENDER_ID", "alice.near"),
+            ("NEAR_BLOCK_HEIGHT", ""),
+            ("NEAR_RECEIPT_ID", ""),
+            ("NEAR_REQUEST_ID", ""),
+        ])
+        .unwrap();
+
+        assert_eq!(ctx.signer_id.as_deref(), Some("alice.near"));
+        assert_eq!(ctx.contract_id, None);
+        assert_eq!(ctx.block_height, None);
+        assert_eq!(ctx.receipt_id, None);
+        assert_eq!(ctx.payment_yocto, None);
+        assert_eq!(ctx.request_id, 0);
+    }
+
+    #[test]
+    fn invalid_number_is_env_error() {
+        let err = parse(&[("NEAR_BLOCK_HEIGHT", "12a")]).unwrap_err();
+        assert_eq!(err.var, "NEAR_BLOCK_HEIGHT");
+        assert_eq!(err.value, "12a");
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn hash32_known_answers() {
+        // Published BLAKE3 test vectors
+        assert_eq!(hex(&hash32(b"")), "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262");
+        assert_eq!(hex(&hash32(b"abc")), "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85");
+
+        // Pins the seed layout: domain tag, then each field as u64 LE length + bytes
+        assert_eq!(
+            hex(&random_seed("9MzuZrRPW1BGpFnZJUJg6SzCrixPpJDfjsNeUobRXsLe", "42", "")),
+            "dee9a28ce550b3cc44b4cc8da45a7299df8c67828e1a2cf38775fb2f50584f47"
+        );
+    }
+
+    #[test]
+    fn random_stream_is_deterministic_per_execution() {
+        let seed = random_seed("9MzuZrRPW1BGpFnZJUJg6SzCrixPpJDfjsNeUobRXsLe", "42", "");
+        assert_eq!(seed, random_seed("9MzuZrRPW1BGpFnZJUJg6SzCrixPpJDfjsNeUobRXsLe", "42", ""));
+
+        // Different request, receipt or field split gives a different seed
+        assert_ne!(seed, random_seed("9MzuZrRPW1BGpFnZJUJg6SzCrixPpJDfjsNeUobRXsLe", "43", ""));
+        assert_ne!(seed, random_seed("other", "42", ""));
+        assert_ne!(random_seed("ab", "c", ""), random_seed("a", "bc", ""));
+
+        // Reading in chunks continues the stream exactly
+        let whole = random_stream(&seed, 0, 48);
+        let mut chunks = random_stream(&seed, 0, 16);
+        chunks.extend(random_stream(&seed, 16, 32));
+        assert_eq!(whole, chunks);
+        assert_ne!(whole[..16], whole[16..32]);
+    }
+
+    #[test]
+    fn random_bytes_advances_stream() {
+        let a = random_bytes(32);
+        let b = random_bytes(32);
+        assert_eq!(a.len(), 32);
+        assert_ne!(a, b);
+        assert!(random_bytes(0).is_empty());
+    }
+
+    #[test]
+    fn output_envelope_ok_and_err() {
+        let ok: Result<u64, String> = Ok(42);
+        assert_eq!(
+            serde_json::to_value(OutputEnvelope::from(ok)).unwrap(),
+            serde_json::json!({"success": true, "data": 42, "error": null})
+        );
+
+        let err: Result<u64, String> = Err("pair not found".to_string());
+        assert_eq!(
+            serde_json::to_value(OutputEnvelope::from(err)).unwrap(),
+            serde_json::json!({"success": false, "data": null, "error": "pair not found"})
+        );
+    }
+
+    #[test]
+    fn decode_result_round_trips() {
+        let encode = |result: Result<Vec<u32>, String>| serde_json::to_vec(&OutputEnvelope::from(result)).unwrap();
+
+        let ok: Result<Vec<u32>, String> = decode_result(&encode(Ok(vec![1, 2]))).unwrap();
+        assert_eq!(ok, Ok(vec![1, 2]));
+        let err: Result<Vec<u32>, String> = decode_result(&encode(Err("boom".to_string()))).unwrap();
+        assert_eq!(err, Err("boom".to_string()));
+
+        // Unit success serializes data as null and still decodes
+        let unit = serde_json::to_vec(&OutputEnvelope::<(), String>::from(Ok(()))).unwrap();
+        assert_eq!(decode_result::<(), String>(&unit).unwrap(), Ok(()));
+
+        // Failure without an error, or not an envelope at all
+        assert!(decode_result::<u64, String>(br#"{"success":false,"data":null,"error":null}"#).is_err());
+        assert!(decode_result::<u64, String>(br#"{"value":1}"#).is_err());
+    }
+
+    #[test]
+    fn structured_error_line_is_one_json_line() {
+        let line = structured_error_line("NOT_FOUND", "pair\nnot found", serde_json::json!({ "pair": "NEAR/USDC" }));
+
+        let payload = line.trim().strip_prefix(STRUCTURED_ERROR_PREFIX).unwrap();
+        assert!(!payload.contains('\n'));
+        let document: serde_json::Value = serde_json::from_str(payload).unwrap();
+        assert_eq!(document["code"], "NOT_FOUND");
+        assert_eq!(document["message"], "pair\nnot found");
+        assert_eq!(document["details"]["pair"], "NEAR/USDC");
+    }
+
+    #[test]
+    fn output_format_line_uses_contract_names() {
+        assert_eq!(output_format_line(ResponseFormat::Json), "\nOUTLAYER_OUTPUT_FORMAT:Json\n");
+        assert_eq!(
+            output_format_line(ResponseFormat::MessagePack).trim().strip_prefix(OUTPUT_FORMAT_PREFIX),
+            Some("MessagePack")
+        );
+    }
+
+    #[test]
+    fn output_modes_cannot_be_mixed() {
+        use OutputMode::*;
+
+        assert_eq!(Unset.transition(Whole), Ok(Whole));
+        assert_eq!(Whole.transition(Whole), Ok(Whole));
+        assert_eq!(Unset.transition(Streaming), Ok(Streaming));
+        assert_eq!(Streaming.transition(Streaming), Ok(Streaming));
+        assert_eq!(Streaming.transition(Finished), Ok(Finished));
+        assert_eq!(Unset.transition(Finished), Ok(Finished));
+
+        assert_eq!(Streaming.transition(Whole), Err(OutputError::AlreadyWritten));
+        assert_eq!(Whole.transition(Streaming), Err(OutputError::AlreadyWritten));
+        assert_eq!(Whole.transition(Finished), Err(OutputError::AlreadyWritten));
+        assert_eq!(Finished.transition(Streaming), Err(OutputError::Finished));
+        assert_eq!(Finished.transition(Whole), Err(OutputError::Finished));
+        assert_eq!(Finished.transition(Finished), Err(OutputError::Finished));
+
+        for mode in [Unset, Whole, Streaming, Finished] {
+            assert_eq!(OutputMode::from_u8(mode as u8), mode);
+        }
+    }
+}
@@ -0,0 +1,383 @@
+//! NEAR Intents solver-relay client (feature `intents`)
+//!
+//! Publishes signed intents to the solver relay and waits for them to settle on
+//! `intents.near`. Swap arks share this instead of each carrying its own polling
+//! loop, so a change in the relay protocol is fixed in one place.
+//!
+//! ```rust,ignore
+//! use outlayer::intents;
+//! use std::time::Duration;
+//!
+//! let outcome = intents::publish_and_settle(
+//!     &signed_data,
+//!     &[quote_hash],
+//!     Duration::from_millis(250),
+//!     Duration::from_secs(30),
+//! )?;
+//! eprintln!("settled in tx {:?}", outcome.tx_hash);
+//! ```
+
+use serde_json::{json, Value};
+use std::time::Duration;
+use wasi_http_client::Client;
+
+/// Solver relay JSON-RPC endpoint
+pub const SOLVER_RELAY_URL: &str = "https://solver-relay-v2.chaindefuser.com/rpc";
+
+/// Connect timeout of each relay request
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Settlement state of a published intent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntentStatus {
+    /// Accepted by the relay, not settled yet (`PENDING`, `TX_BROADCASTED`)
+    Pending,
+    /// Executed on `intents.near`
+    Settled { tx_hash: Option<String> },
+    /// Rejected or failed on-chain
+    Failed { reason: String },
+    /// Unknown to the relay or no longer valid
+    NotFound,
+}
+
+/// Result of a settled intent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettlementOutcome {
+    pub intent_hash: String,
+    /// Settlement transaction, when the relay reports it
+    pub tx_hash: Option<String>,
+    /// Status requests made until settlement
+    pub polls: u32,
+    /// Errors of status requests that failed and were retried, oldest first
+    pub poll_errors: Vec<String>,
+}
+
+/// Solver relay error
+#[derive(Debug, Clone)]
+pub enum IntentsError {
+    /// Request failed or the relay answered with a non-2xx status
+    Http(String),
+    /// Malformed response or JSON-RPC error
+    Rpc(String),
+    /// The relay refused to publish the intent
+    Rejected(String),
+    /// The intent failed or is no longer valid
+    Failed { intent_hash: String, reason: String },
+    /// Not settled before the deadline
+    Timeout {
+        intent_hash: String,
+        last_status: Option<IntentStatus>,
+        /// Error of the last status request, when it failed
+        last_error: Option<String>,
+    },
+}
+
+impl std::fmt::Display for IntentsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IntentsError::Http(e) => write!(f, "Solver relay request failed: {}", e),
+            IntentsError::Rpc(e) => write!(f, "Solver relay error: {}", e),
+            IntentsError::Rejected(reason) => write!(f, "Intent rejected by solver relay: {}", reason),
+            IntentsError::Failed { intent_hash, reason } => {
+                write!(f, "Intent {} failed: {}", intent_hash, reason)
+            }
+            IntentsError::Timeout { intent_hash, last_status, last_error } => {
+                write!(f, "Intent {} not settled in time (last status: {:?}", intent_hash, last_status)?;
+                match last_error {
+                    Some(e) => write!(f, ", last poll error: {})", e),
+                    None => write!(f, ")"),
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for IntentsError {}
+
+/// Result type for solver relay operations
+pub type Result<T> = std::result::Result<T, IntentsError>;
+
+/// Publish a signed intent
+///
+/// Returns the intent hash to pass to [`get_status`].
+pub fn publish(signed_data: &Value, quote_hashes: &[String]) -> Result<String> {
+    let body = rpc_call("publish_intent", json!({ "signed_data": signed_data, "quote_hashes": quote_hashes }))?;
+    parse_publish_response(&body)
+}
+
+/// Current settlement status of an intent
+pub fn get_status(intent_hash: &str) -> Result<IntentStatus> {
+    let body = rpc_call("get_status", json!({ "intent_hash": intent_hash }))?;
+    parse_status_response(&body)
+}
+
+/// Poll [`get_status`] every `poll_interval` until the intent settles or `timeout` passes
+///
+/// Request errors while polling are retried and collected in
+/// [`SettlementOutcome::poll_errors`] (or reported as `last_error` on timeout);
+/// a failed or unknown intent ends the wait with [`IntentsError::Failed`].
+pub fn wait_for_settlement(intent_hash: &str, poll_interval: Duration, timeout: Duration) -> Result<SettlementOutcome> {
+    poll_until_settled(intent_hash, poll_interval, timeout, get_status, std::thread::sleep)
+}
+
+/// [`publish`] an intent, then [`wait_for_settlement`]
+pub fn publish_and_settle(
+    signed_data: &Value,
+    quote_hashes: &[String],
+    poll_interval: Duration,
+    timeout: Duration,
+) -> Result<SettlementOutcome> {
+    let intent_hash = publish(signed_data, quote_hashes)?;
+    wait_for_settlement(&intent_hash, poll_interval, timeout)
+}
+
+fn poll_until_settled(
+    intent_hash: &str,
+    poll_interval: Duration,
+    timeout: Duration,
+    mut status: impl FnMut(&str) -> Result<IntentStatus>,
+    mut sleep: impl FnMut(Duration),
+) -> Result<SettlementOutcome> {
+    let mut waited = Duration::ZERO;
+    let mut polls = 0;
+    let mut last_status = None;
+    let mut poll_errors = Vec::new();
+    let mut last_failed = false;
+
+    loop {
+        polls += 1;
+        match status(intent_hash) {
+            Ok(IntentStatus::Settled { tx_hash }) => {
+                return Ok(SettlementOutcome {
+                    intent_hash: intent_hash.to_string(),
+                    tx_hash,
+                    polls,
+                    poll_errors,
+                });
+            }
+            Ok(IntentStatus::Failed { reason }) => {
+                return Err(IntentsError::Failed {
+                    intent_hash: intent_hash.to_string(),
+                    reason,
+                });
+            }
+            Ok(IntentStatus::NotFound) => {
+                return Err(IntentsError::Failed {
+                    intent_hash: intent_hash.to_string(),
+                    reason: "not found or no longer valid".to_string(),
+                });
+            }
+            Ok(IntentStatus::Pending) => {
+                last_status = Some(IntentStatus::Pending);
+                last_failed = false;
+            }
+            Err(e) => {
+                poll_errors.push(format!("poll {}: {}", polls, e));
+                last_failed = true;
+            }
+        }
+
+        if waited >= timeout {
+            return Err(IntentsError::Timeout {
+                intent_hash: intent_hash.to_string(),
+                last_status,
+                last_error: if last_failed { poll_errors.pop() } else { None },
+            });
+        }
+        let step = poll_interval.min(timeout - waited).max(Duration::from_millis(1));
+        sleep(step);
+        waited += step;
+    }
+}
+
+/// POST one JSON-RPC call to the relay and return the response body
+fn rpc_call(method: &str, params: Value) -> Result<Vec<u8>> {
+    let request = json!({
+        "id": "outlayer",
+        "jsonrpc": "2.0",
+        "method": method,
+        "params": [params],
+    });
+
+    let response = Client::new()
+        .post(SOLVER_RELAY_URL)
+        .header("Content-Type", "application/json")
+        .connect_timeout(CONNECT_TIMEOUT)
+        .body(request.to_string().as_bytes())
+        .send()
+        .map_err(|e| IntentsError::Http(e.to_string()))?;
+
+    let status = response.status();
+    let body = response.body().map_err(|e| IntentsError::Http(e.to_string()))?;
+    if status / 100 != 2 {
+        return Err(IntentsError::Http(format!(
+            "{} returned HTTP {}: {}",
+            method,
+            status,
+            String::from_utf8_lossy(&body)
+        )));
+    }
+    Ok(body)
+}
+
+/// `result` of a JSON-RPC response
+fn rpc_result(body: &[u8]) -> Result<Value> {
+    let mut response: Value = serde_json::from_slice(body).map_err(|e| IntentsError::Rpc(e.to_string()))?;
+    if let Some(error) = response.get("error") {
+        return Err(IntentsError::Rpc(error.to_string()));
+    }
+    match response.get_mut("result") {
+        Some(result) => Ok(result.take()),
+        None => Err(IntentsError::Rpc("response has no result".to_string())),
+    }
+}
+
+fn parse_publish_response(body: &[u8]) -> Result<String> {
+    let result = rpc_result(body)?;
+    match result["status"].as_str() {
+        Some("OK") => result["intent_hash"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| IntentsError::Rpc("publish_intent returned no intent_hash".to_string())),
+        _ => Err(IntentsError::Rejected(
+            result["reason"].as_str().map(str::to_string).unwrap_or_else(|| result.to_string()),
+        )),
+    }
+}
+
+fn parse_status_response(body: &[u8]) -> Result<IntentStatus> {
+    let result = rpc_result(body)?;
+    let status = result["status"]
+        .as_str()
+        .ok_or_else(|| IntentsError::Rpc(format!("get_status returned no status: {}", result)))?;
+
+    Ok(match status {
+        "PENDING" | "TX_BROADCASTED" => IntentStatus::Pending,
+        "SETTLED" => IntentStatus::Settled {
+            tx_hash: result["data"]["hash"].as_str().map(str::to_string),
+        },
+        "FAILED" => IntentStatus::Failed {
+            reason: result["reason"]
+                .as_str()
+                .or_else(|| result["data"]["reason"].as_str())
+                .unwrap_or("failed")
+                .to_string(),
+        },
+        s if s.starts_with("NOT_FOUND") => IntentStatus::NotFound,
+        other => return Err(IntentsError::Rpc(format!("unknown intent status {}", other))),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(result: Value) -> Vec<u8> {
+        json!({ "jsonrpc": "2.0", "id": "outlayer", "result": result }).to_string().into_bytes()
+    }
+
+    #[test]
+    fn parses_publish_response() {
+        let ok = status(json!({ "status": "OK", "intent_hash": "6Vq5..." }));
+        assert_eq!(parse_publish_response(&ok).unwrap(), "6Vq5...");
+
+        let rejected = status(json!({ "status": "FAILED", "reason": "quote expired" }));
+        match parse_publish_response(&rejected) {
+            Err(IntentsError::Rejected(reason)) => assert_eq!(reason, "quote expired"),
+            other => panic!("expected Rejected, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_typed_statuses() {
+        let parse = |result| parse_status_response(&status(result)).unwrap();
+        assert_eq!(parse(json!({ "status": "PENDING" })), IntentStatus::Pending);
+        assert_eq!(parse(json!({ "status": "TX_BROADCASTED", "data": { "hash": "abc" } })), IntentStatus::Pending);
+        assert_eq!(
+            parse(json!({ "status": "SETTLED", "data": { "hash": "abc" } })),
+            IntentStatus::Settled { tx_hash: Some("abc".to_string()) }
+        );
+        assert_eq!(parse(json!({ "status": "NOT_FOUND_OR_NOT_VALID" })), IntentStatus::NotFound);
+        assert_eq!(
+            parse(json!({ "status": "FAILED", "reason": "slippage" })),
+            IntentStatus::Failed { reason: "slippage".to_string() }
+        );
+
+        let rpc_error = json!({ "jsonrpc": "2.0", "id": "1", "error": { "code": -32000 } }).to_string();
+        assert!(matches!(parse_status_response(rpc_error.as_bytes()), Err(IntentsError::Rpc(_))));
+    }
+
+    #[test]
+    fn polls_until_settled() {
+        let mut statuses = vec![
+            Ok(IntentStatus::Pending),
+            Err(IntentsError::Http("connection reset".to_string())),
+            Ok(IntentStatus::Settled { tx_hash: Some("abc".to_string()) }),
+        ]
+        .into_iter();
+        let mut slept = Vec::new();
+
+        let outcome = poll_until_settled(
+            "hash",
+            Duration::from_millis(250),
+            Duration::from_secs(30),
+            |_| statuses.next().unwrap(),
+            |d| slept.push(d),
+        )
+        .unwrap();
+        assert_eq!(outcome.polls, 3);
+        assert_eq!(outcome.tx_hash.as_deref(), Some("abc"));
+        assert_eq!(outcome.poll_errors, vec!["poll 2: Solver relay request failed: connection reset".to_string()]);
+        assert_eq!(slept, vec![Duration::from_millis(250); 2]);
+    }
+
+    #[test]
+    fn stops_at_deadline() {
+        let mut waited = Duration::ZERO;
+        let err = poll_until_settled(
+            "hash",
+            Duration::from_millis(400),
+            Duration::from_secs(1),
+            |_| Ok(IntentStatus::Pending),
+            |d| waited += d,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            IntentsError::Timeout { last_status: Some(IntentStatus::Pending), last_error: None, .. }
+        ));
+        // The last sleep is shortened so the deadline is not overshot
+        assert_eq!(waited, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn timeout_reports_last_poll_error() {
+        let err = poll_until_settled(
+            "hash",
+            Duration::from_millis(500),
+            Duration::from_secs(1),
+            |_| Err(IntentsError::Http("HTTP 503".to_string())),
+            |_| {},
+        )
+        .unwrap_err();
+        match err {
+            IntentsError::Timeout { last_status: None, last_error: Some(e), .. } => {
+                assert_eq!(e, "poll 3: Solver relay request failed: HTTP 503")
+            }
+            other => panic!("expected Timeout with last_error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn not_found_ends_the_wait() {
+        let err = poll_until_settled(
+            "hash",
+            Duration::from_millis(250),
+            Duration::from_secs(30),
+            |_| Ok(IntentStatus::NotFound),
+            |_| panic!("should not sleep"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, IntentsError::Failed { .. }));
+    }
+}
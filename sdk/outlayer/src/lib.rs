@@ -5,8 +5,17 @@
 //! ## Features
 //!
 //! - **Storage**: Persistent encrypted storage across executions
-//! - **Environment**: Access to execution context (signer, input/output)
+//! - **Environment**: Access to execution context (signer, input/output), deterministic `env::random_bytes`
+//!   and instruction budget readback (`env::instructions_used`)
 //! - **VRF**: Verifiable random function (Ed25519 signatures, on-chain verification)
+//! - **App keys**: Per-project secret derived by the NEAR MPC network (`env::request_app_key`, TEE only)
+//! - **RPC**: Dry-run simulation of contract calls (`rpc::simulate_call`)
+//! - **Oracle**: Price reads from an oracle ark with a staleness guard (`oracle::get_price`)
+//! - **Intents** (feature `intents`): Publish to the NEAR Intents solver relay and wait for settlement
+//! - **Crypto** (feature `crypto`): Verify NEP-413 signed messages (`crypto::verify_nep413`)
+//! - **Public storage over HTTP** (feature `storage-http`): Read another project's public
+//!   storage through the coordinator (`storage::http::read_public`)
+//! - **Metadata**: Compile-time project/version info via `metadata!`, readable with `env::metadata()`
 //!
 //! ## Requirements
 //!
@@ -58,14 +67,25 @@ wit_bindgen::generate!({
     world: "outlayer-host",
     path: "wit",
     with: {
-        "near:storage/api@0.1.0": generate,
+        "near:storage/api@0.2.0": generate,
         "near:vrf/api@0.1.0": generate,
+        "near:ckd/api@0.1.0": generate,
+        "near:meter/api@0.1.0": generate,
     },
 });
 
 pub mod storage;
 pub mod env;
 pub mod vrf;
+pub mod metadata;
+pub mod rpc;
+pub mod oracle;
+#[cfg(feature = "intents")]
+pub mod intents;
+#[cfg(feature = "crypto")]
+pub mod crypto;
+
+pub use metadata::ArkMetadata;
 
 /// Low-level access to generated WIT bindings
 ///
@@ -74,4 +94,6 @@ pub mod raw {
     pub use super::near::rpc::api as rpc;
     pub use super::near::storage::api as storage;
     pub use super::near::vrf::api as vrf;
+    pub use super::near::ckd::api as ckd;
+    pub use super::near::meter::api as meter;
 }
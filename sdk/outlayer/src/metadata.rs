@@ -0,0 +1,395 @@
+//! Ark metadata embedded at compile time
+//!
+//! The [`metadata!`](crate::metadata) macro bakes the project name, version and
+//! optional description/tags/allowed hosts/required capabilities into the
+//! compiled component:
+//!
+//! - as an `outlayer-metadata` custom section, so tooling can read it from the
+//!   WASM binary without executing it
+//! - as a static symbol, so the ark can read it back at runtime via
+//!   [`env::metadata()`](crate::env::metadata)
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! outlayer::metadata! {
+//!     project: "alice.near/my-ark",
+//!     version: "1.2.0",
+//!     description: "Price oracle",
+//!     tags: ["oracle", "defi"],
+//!     allowed_hosts: ["api.coingecko.com", "*.binance.com"],
+//!     requires: [storage, http],
+//! }
+//!
+//! fn main() {
+//!     let meta = outlayer::env::metadata();
+//!     eprintln!("running {} v{}", meta.project, meta.version);
+//! }
+//! ```
+//!
+//! The macro must be invoked exactly once per binary. Calling
+//! `env::metadata()` without it fails at link time.
+//!
+//! `project` and `version` are checked at compile time: `project` must be
+//! `<account_id>/<app-slug>` with a valid NEAR account id and a slug of
+//! `[a-z0-9-]+`, and `version` must be semver (`1.2.0`, `2.0.0-rc.1`).
+//!
+//! ## Allowed hosts
+//!
+//! `allowed_hosts` limits the ark's outbound HTTP: workers refuse requests to any
+//! host not listed, and the request fails with an error the ark can handle.
+//! Entries are `host`, `host:port` or `*.domain` (subdomains of `domain`); an
+//! empty list means no outbound HTTP. Without `allowed_hosts` the ark may reach
+//! any public host, unless the worker operator denies undeclared arks.
+//!
+//! ## Required capabilities
+//!
+//! `requires` lists the host services the ark can't run without: `storage`, `rpc`,
+//! `http` and `secrets`. A worker that lacks one (no keystore, RPC proxy off, the group
+//! disabled by its operator) rejects the job before compiling it with
+//! "project requires capability X" instead of the ark failing halfway. The compile
+//! worker reports the list with the uploaded WASM, so the coordinator can route jobs to
+//! workers that provide it. Unknown names are a compile error.
+
+/// Name of the WASM custom section holding the embedded metadata
+pub const SECTION_NAME: &str = "outlayer-metadata";
+
+/// Metadata describing the running ark
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ArkMetadata {
+    /// Project identifier (e.g. "alice.near/my-ark")
+    pub project: String,
+    /// Ark version (e.g. "1.2.0")
+    pub version: String,
+    /// Optional human-readable description
+    pub description: Option<String>,
+    /// Free-form tags
+    pub tags: Vec<String>,
+    /// Hosts the ark may send HTTP requests to (`None` = not declared)
+    pub allowed_hosts: Option<Vec<String>>,
+    /// Host services the ark needs to run
+    pub requires: Vec<Capability>,
+}
+
+/// Host service an ark can declare in `requires`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Capability {
+    /// Persistent storage (`outlayer::storage`)
+    Storage,
+    /// NEAR RPC proxy (`outlayer::rpc`)
+    Rpc,
+    /// Outbound HTTP (wasi-http)
+    Http,
+    /// Secrets decrypted by the keystore into environment variables
+    Secrets,
+}
+
+impl Capability {
+    /// Name used in `requires` and in the custom section
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Storage => "storage",
+            Self::Rpc => "rpc",
+            Self::Http => "http",
+            Self::Secrets => "secrets",
+        }
+    }
+}
+
+/// Compile-time representation written by the `metadata!` macro
+#[doc(hidden)]
+pub struct EmbeddedMetadata {
+    pub project: &'static str,
+    pub version: &'static str,
+    pub description: Option<&'static str>,
+    pub tags: &'static [&'static str],
+    pub allowed_hosts: Option<&'static [&'static str]>,
+    pub requires: &'static [Capability],
+}
+
+impl EmbeddedMetadata {
+    pub(crate) fn to_metadata(&self) -> ArkMetadata {
+        ArkMetadata {
+            project: self.project.to_string(),
+            version: self.version.to_string(),
+            description: self.description.map(str::to_string),
+            tags: self.tags.iter().map(|t| t.to_string()).collect(),
+            allowed_hosts: self
+                .allowed_hosts
+                .map(|hosts| hosts.iter().map(|h| h.to_string()).collect()),
+            requires: self.requires.to_vec(),
+        }
+    }
+}
+
+/// Copy a string into a fixed-size byte array (used to build the custom section)
+#[doc(hidden)]
+pub const fn section_bytes<const N: usize>(s: &str) -> [u8; N] {
+    let bytes = s.as_bytes();
+    let mut out = [0u8; N];
+    let mut i = 0;
+    while i < N {
+        out[i] = bytes[i];
+        i += 1;
+    }
+    out
+}
+
+/// Whether `s` is a valid NEAR account id (2-64 chars of `[a-z0-9]`, separated by
+/// single `.`, `-` or `_`, not at either end)
+pub const fn is_valid_account_id(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    if bytes.len() < 2 || bytes.len() > 64 {
+        return false;
+    }
+    let mut prev_separator = true;
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b.is_ascii_lowercase() || b.is_ascii_digit() {
+            prev_separator = false;
+        } else if b == b'.' || b == b'-' || b == b'_' {
+            if prev_separator {
+                return false;
+            }
+            prev_separator = true;
+        } else {
+            return false;
+        }
+        i += 1;
+    }
+    !prev_separator
+}
+
+/// Whether `s` is a valid project identifier: `<account_id>/<app-slug>`, slug `[a-z0-9-]+`
+pub const fn is_valid_project(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    let mut slash = 0;
+    while slash < bytes.len() && bytes[slash] != b'/' {
+        slash += 1;
+    }
+    if slash == bytes.len() || slash + 1 == bytes.len() {
+        return false;
+    }
+    let (account, slug) = bytes.split_at(slash);
+    let mut i = 1;
+    while i < slug.len() {
+        let b = slug[i];
+        if !(b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-') {
+            return false;
+        }
+        i += 1;
+    }
+    match std::str::from_utf8(account) {
+        Ok(account) => is_valid_account_id(account),
+        Err(_) => false,
+    }
+}
+
+/// Whether `s` is a semantic version (`MAJOR.MINOR.PATCH[-pre][+build]`)
+pub const fn is_valid_semver(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    // MAJOR.MINOR.PATCH: numbers without leading zeros
+    let mut part = 0;
+    while part < 3 {
+        let start = i;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        if i == start || (bytes[start] == b'0' && i - start > 1) {
+            return false;
+        }
+        part += 1;
+        if part < 3 {
+            if i == bytes.len() || bytes[i] != b'.' {
+                return false;
+            }
+            i += 1;
+        }
+    }
+    // Pre-release, then build metadata: dot-separated non-empty [0-9A-Za-z-] identifiers
+    let mut section = 0;
+    while i < bytes.len() {
+        let marker = bytes[i];
+        let prerelease = marker == b'-' && section == 0;
+        if !prerelease && marker != b'+' {
+            return false;
+        }
+        section = if prerelease { 1 } else { 2 };
+        i += 1;
+        loop {
+            let start = i;
+            let mut numeric = true;
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'-') {
+                numeric = numeric && bytes[i].is_ascii_digit();
+                i += 1;
+            }
+            if i == start {
+                return false;
+            }
+            // Numeric pre-release identifiers have no leading zeros
+            if prerelease && numeric && bytes[start] == b'0' && i - start > 1 {
+                return false;
+            }
+            if i < bytes.len() && bytes[i] == b'.' {
+                i += 1;
+            } else {
+                break;
+            }
+        }
+        if section == 2 && i < bytes.len() {
+            return false;
+        }
+    }
+    true
+}
+
+/// Embed ark metadata into the compiled component
+///
+/// Fields are string literals, except `requires`, which lists bare capability names.
+/// `description`, `tags`, `allowed_hosts` and `requires` are optional.
+/// The custom section is encoded as `key=value` lines, with lists comma-separated.
+///
+/// An invalid `project` or `version`, or an unknown capability, is a compile error
+/// (see the [module docs](crate::metadata)).
+///
+/// # Example
+/// ```rust,ignore
+/// outlayer::metadata! {
+///     project: "alice.near/my-ark",
+///     version: "1.2.0",
+/// }
+/// ```
+#[macro_export]
+macro_rules! metadata {
+    (
+        project: $project:literal,
+        version: $version:literal
+        $(, description: $description:literal)?
+        $(, tags: [$($tag:literal),* $(,)?])?
+        $(, allowed_hosts: [$($host:literal),* $(,)?])?
+        $(, requires: [$($capability:ident),* $(,)?])?
+        $(,)?
+    ) => {
+        const _: () = {
+            assert!(
+                $crate::metadata::is_valid_project($project),
+                "metadata!: `project` must be \"<account_id>/<app-slug>\", e.g. \"alice.near/my-ark\" (valid NEAR account id; slug of lowercase letters, digits and '-')"
+            );
+            assert!(
+                $crate::metadata::is_valid_semver($version),
+                "metadata!: `version` must be a semantic version, e.g. \"1.2.0\" or \"2.0.0-rc.1\""
+            );
+        };
+
+        #[doc(hidden)]
+        #[no_mangle]
+        pub static __OUTLAYER_ARK_METADATA: $crate::metadata::EmbeddedMetadata =
+            $crate::metadata::EmbeddedMetadata {
+                project: $project,
+                version: $version,
+                description: $crate::__metadata_opt!($($description)?),
+                tags: &[$($($tag),*)?],
+                allowed_hosts: $crate::__metadata_list!($([$($host),*])?),
+                requires: &[$($($crate::__metadata_capability!($capability)),*)?],
+            };
+
+        const _: () = {
+            const __SECTION: &str = concat!(
+                "project=", $project, "\n",
+                "version=", $version, "\n",
+                $("description=", $description, "\n",)?
+                "tags=", $($($tag, ",",)*)? "\n",
+                $("allowed_hosts=", $($host, ",",)* "\n",)?
+                $("requires=", $(stringify!($capability), ",",)* "\n",)?
+            );
+
+            #[link_section = "outlayer-metadata"]
+            #[used]
+            static __OUTLAYER_ARK_METADATA_SECTION: [u8; __SECTION.len()] =
+                $crate::metadata::section_bytes::<{ __SECTION.len() }>(__SECTION);
+        };
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __metadata_opt {
+    () => {
+        None
+    };
+    ($value:literal) => {
+        Some($value)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __metadata_list {
+    () => {
+        None
+    };
+    ([$($item:literal),*]) => {
+        Some(&[$($item),*])
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __metadata_capability {
+    (storage) => {
+        $crate::metadata::Capability::Storage
+    };
+    (rpc) => {
+        $crate::metadata::Capability::Rpc
+    };
+    (http) => {
+        $crate::metadata::Capability::Http
+    };
+    (secrets) => {
+        $crate::metadata::Capability::Secrets
+    };
+    ($other:ident) => {
+        compile_error!(concat!(
+            "metadata!: unknown capability `",
+            stringify!($other),
+            "` in `requires` (expected storage, rpc, http or secrets)"
+        ))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_project() {
+        assert!(is_valid_project("alice.near/my-ark"));
+        assert!(is_valid_project("oracle_team.testnet/price-feed-2"));
+        assert!(is_valid_project("a1/x"));
+
+        assert!(!is_valid_project("alice.near my-ark"));
+        assert!(!is_valid_project("alice.near"));
+        assert!(!is_valid_project("alice.near/"));
+        assert!(!is_valid_project("/my-ark"));
+        assert!(!is_valid_project("alice.near/My-Ark"));
+        assert!(!is_valid_project("alice.near/my_ark"));
+        assert!(!is_valid_project("alice.near/my/ark"));
+        assert!(!is_valid_project("Alice.near/my-ark"));
+        assert!(!is_valid_project("alice..near/my-ark"));
+        assert!(!is_valid_project(".alice.near/my-ark"));
+        assert!(!is_valid_project("a/my-ark"));
+    }
+
+    #[test]
+    fn validates_semver() {
+        for version in ["0.1.0", "1.2.0", "10.20.30", "2.0.0-rc.1", "1.0.0-alpha-1+build.5", "1.0.0+20240101"] {
+            assert!(is_valid_semver(version), "{} should be valid", version);
+        }
+        for version in ["1.2", "1.2.0.", "v1.2.0", "01.2.0", "1.2.0-", "1.2.0-rc..1", "1.2.0-01", "1.2.0+b+c", "1.2.x", ""] {
+            assert!(!is_valid_semver(version), "{} should be invalid", version);
+        }
+    }
+}
@@ -0,0 +1,235 @@
+//! Price reads from an oracle ark's public worker storage
+//!
+//! Oracle arks publish aggregated prices as plaintext worker storage under
+//! `price:<key>` (see [`crate::storage::set_worker_with_options`]). [`get_price`]
+//! reads one from another project and refuses it when it is older than the caller
+//! allows, so a swap never runs at an outdated rate. Age is measured against the
+//! block timestamp, so reads only work in block executions, not HTTPS calls.
+//!
+//! ```rust,ignore
+//! use outlayer::oracle::{self, OracleError};
+//!
+//! match oracle::get_price("p0000000000000003", "wrap.near", 300) {
+//!     Ok(price) => {
+//!         let usd = price.value_of(amount, 24);
+//!     }
+//!     Err(OracleError::Stale { age, .. }) => return Err(format!("price is {}s old", age)),
+//!     Err(e) => return Err(e.to_string()),
+//! }
+//! ```
+
+use crate::env;
+use crate::storage::{self, StorageError};
+use serde::Deserialize;
+
+/// Storage key prefix oracle arks publish prices under
+pub const PRICE_KEY_PREFIX: &str = "price:";
+
+/// Price read from an oracle, with the metadata it was published with
+#[derive(Debug, Clone, PartialEq)]
+pub struct Price {
+    /// Aggregated USD price of one whole token
+    pub price: f64,
+    /// When the oracle aggregated the price (unix seconds)
+    pub timestamp: u64,
+    /// Seconds between `timestamp` and the block the read ran in
+    pub age_secs: u64,
+    /// Per-source prices the aggregate was computed from
+    pub sources: Vec<PriceSource>,
+    /// How the sources were combined (e.g. `"median"`)
+    pub aggregation_method: String,
+}
+
+impl Price {
+    /// USD value of `amount` minimal units of a token with `decimals` decimals
+    pub fn value_of(&self, amount: u128, decimals: u32) -> f64 {
+        amount as f64 / 10f64.powi(decimals as i32) * self.price
+    }
+}
+
+/// One source's contribution to an aggregated price
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PriceSource {
+    pub name: String,
+    pub price: f64,
+    /// When the source quoted the price (unix seconds), if it says
+    #[serde(default)]
+    pub timestamp: Option<u64>,
+}
+
+/// Oracle read error
+#[derive(Debug, Clone)]
+pub enum OracleError {
+    /// The oracle has not published this key
+    NotFound { key: String },
+    /// The price is older than the allowed maximum
+    Stale { age: u64, max_age: u64 },
+    /// The price is timestamped after the current block
+    FromFuture { timestamp: u64, now: u64 },
+    /// No block timestamp to measure age against (HTTPS calls)
+    NoBlockContext,
+    /// The stored value is not a valid price
+    Invalid(String),
+    /// Storage operation failed
+    Storage(StorageError),
+}
+
+impl std::fmt::Display for OracleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OracleError::NotFound { key } => write!(f, "Price not found in oracle storage: {}", key),
+            OracleError::Stale { age, max_age } => {
+                write!(f, "Oracle price is {} seconds old (max {})", age, max_age)
+            }
+            OracleError::FromFuture { timestamp, now } => {
+                write!(f, "Oracle price timestamp {} is after the current block time {}", timestamp, now)
+            }
+            OracleError::NoBlockContext => {
+                write!(f, "Oracle price age needs a block timestamp (not available in HTTPS calls)")
+            }
+            OracleError::Invalid(e) => write!(f, "Invalid oracle price: {}", e),
+            OracleError::Storage(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for OracleError {}
+
+/// Price as stored by oracle arks
+#[derive(Deserialize)]
+struct StoredPrice {
+    price: f64,
+    timestamp: u64,
+    #[serde(default)]
+    sources: Vec<PriceSource>,
+    #[serde(default)]
+    aggregation_method: String,
+}
+
+/// Read `price:<key>` from the oracle project and check its freshness
+///
+/// # Arguments
+/// * `project_uuid` - UUID of the oracle project (e.g. `"p0000000000000003"`)
+/// * `key` - Price key without the `price:` prefix (e.g. `"wrap.near"`)
+/// * `max_age_secs` - Oldest acceptable price, in seconds
+///
+/// # Returns
+/// * `Ok(Price)` - Price at most `max_age_secs` old
+/// * `Err(OracleError::Stale)` - Price older than `max_age_secs`
+/// * `Err(OracleError::FromFuture)` - Price timestamped after the current block
+/// * `Err(OracleError::NoBlockContext)` - Called outside a block execution (HTTPS call)
+/// * `Err(OracleError::NotFound)` - Key not published
+/// * `Err(OracleError::Invalid)` - Not a positive, finite price
+/// * `Err(OracleError::Storage)` - Storage operation failed
+pub fn get_price(project_uuid: &str, key: &str, max_age_secs: u64) -> Result<Price, OracleError> {
+    let now = env::now_secs().ok_or(OracleError::NoBlockContext)?;
+    let storage_key = format!("{}{}", PRICE_KEY_PREFIX, key);
+    let data = storage::get_worker_from_project(&storage_key, Some(project_uuid))
+        .map_err(OracleError::Storage)?
+        .ok_or(OracleError::NotFound { key: storage_key })?;
+    parse_price(&data, now, max_age_secs)
+}
+
+fn parse_price(data: &[u8], now: u64, max_age_secs: u64) -> Result<Price, OracleError> {
+    let stored: StoredPrice =
+        serde_json::from_slice(data).map_err(|e| OracleError::Invalid(e.to_string()))?;
+    if !stored.price.is_finite() || stored.price <= 0.0 {
+        return Err(OracleError::Invalid(format!("price {}", stored.price)));
+    }
+
+    // A future timestamp would otherwise read as age 0 and pass any freshness check
+    let age = now.checked_sub(stored.timestamp).ok_or(OracleError::FromFuture {
+        timestamp: stored.timestamp,
+        now,
+    })?;
+    if age > max_age_secs {
+        return Err(OracleError::Stale { age, max_age: max_age_secs });
+    }
+
+    Ok(Price {
+        price: stored.price,
+        timestamp: stored.timestamp,
+        age_secs: age,
+        sources: stored.sources,
+        aggregation_method: stored.aggregation_method,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STORED: &str = r#"{
+        "price": 3.5,
+        "timestamp": 1700000000,
+        "sources": [
+            {"name": "coingecko", "price": 3.49, "timestamp": 1699999990},
+            {"name": "binance", "price": 3.51, "timestamp": null}
+        ],
+        "aggregation_method": "median"
+    }"#;
+
+    #[test]
+    fn parses_fresh_price_with_metadata() {
+        let price = parse_price(STORED.as_bytes(), 1_700_000_120, 300).unwrap();
+        assert_eq!(price.price, 3.5);
+        assert_eq!(price.age_secs, 120);
+        assert_eq!(price.aggregation_method, "median");
+        assert_eq!(price.sources.len(), 2);
+        assert_eq!(price.sources[0].timestamp, Some(1_699_999_990));
+        assert_eq!(price.sources[1].timestamp, None);
+    }
+
+    #[test]
+    fn rejects_stale_price() {
+        match parse_price(STORED.as_bytes(), 1_700_000_301, 300) {
+            Err(OracleError::Stale { age, max_age }) => assert_eq!((age, max_age), (301, 300)),
+            other => panic!("expected Stale, got {:?}", other),
+        }
+        // Exactly max_age old is still accepted
+        assert!(parse_price(STORED.as_bytes(), 1_700_000_300, 300).is_ok());
+    }
+
+    #[test]
+    fn rejects_price_from_the_future() {
+        match parse_price(STORED.as_bytes(), 1_699_999_999, 300) {
+            Err(OracleError::FromFuture { timestamp, now }) => {
+                assert_eq!((timestamp, now), (1_700_000_000, 1_699_999_999))
+            }
+            other => panic!("expected FromFuture, got {:?}", other),
+        }
+        // Published in the current block's second is age 0
+        assert_eq!(parse_price(STORED.as_bytes(), 1_700_000_000, 300).unwrap().age_secs, 0);
+    }
+
+    #[test]
+    fn needs_block_context() {
+        // Test processes have no NEAR_BLOCK_TIMESTAMP, like HTTPS calls; fails before any storage read
+        assert!(matches!(get_price("p0000000000000003", "wrap.near", 300), Err(OracleError::NoBlockContext)));
+    }
+
+    #[test]
+    fn rejects_invalid_price() {
+        for data in [
+            r#"{"price": 0.0, "timestamp": 1700000000}"#,
+            r#"{"price": -1.0, "timestamp": 1700000000}"#,
+            r#"{"timestamp": 1700000000}"#,
+            "not json",
+        ] {
+            assert!(
+                matches!(parse_price(data.as_bytes(), 1_700_000_000, 300), Err(OracleError::Invalid(_))),
+                "accepted {}",
+                data
+            );
+        }
+    }
+
+    #[test]
+    fn values_amount_using_token_decimals() {
+        let price = parse_price(STORED.as_bytes(), 1_700_000_000, 300).unwrap();
+        // 2 wNEAR (24 decimals) at $3.50
+        assert!((price.value_of(2_000_000_000_000_000_000_000_000, 24) - 7.0).abs() < 1e-9);
+        // 1.5 USDC (6 decimals)
+        assert!((price.value_of(1_500_000, 6) - 5.25).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,339 @@
+//! High-level NEAR RPC helpers for OutLayer WASM components
+//!
+//! Thin wrappers over the `near:rpc/api` host functions (see [`crate::raw::rpc`])
+//! for things that need more than a single raw call.
+//!
+//! ## Simulating a call
+//!
+//! ```rust,ignore
+//! use outlayer::rpc;
+//!
+//! let sim = rpc::simulate_call("alice.near", "token.near", "ft_balance_of",
+//!     r#"{"account_id":"alice.near"}"#, 0)?;
+//! if sim.success {
+//!     let balance: String = sim.json()?;
+//! } else {
+//!     eprintln!("would fail: {}", sim.error.unwrap_or_default());
+//! }
+//! ```
+
+use crate::near::rpc::api as raw;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// RPC error (transport failure or malformed response)
+#[derive(Debug, Clone)]
+pub struct RpcError(pub String);
+
+impl std::fmt::Display for RpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RPC error: {}", self.0)
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+/// Result type for RPC operations
+pub type Result<T> = std::result::Result<T, RpcError>;
+
+/// Predicted outcome of a function call, produced without submitting a transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimulationResult {
+    /// True if the call is predicted to succeed
+    pub success: bool,
+    /// Raw return value of the method (empty on failure)
+    pub result: Vec<u8>,
+    /// Logs emitted by the method during the dry run
+    pub logs: Vec<String>,
+    /// Gas burnt, when the node reports it (view-shaped dry runs don't)
+    pub gas_burnt: Option<u64>,
+    /// Failure reason when `success` is false
+    pub error: Option<String>,
+    /// Block the dry run was executed against
+    pub block_height: Option<u64>,
+}
+
+impl SimulationResult {
+    /// Return value as UTF-8 text
+    pub fn result_str(&self) -> String {
+        String::from_utf8_lossy(&self.result).to_string()
+    }
+
+    /// Deserialize the JSON return value
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        serde_json::from_slice(&self.result)
+            .map_err(|e| RpcError(format!("invalid JSON result: {}", e)))
+    }
+}
+
+/// Predict the outcome of calling `method` on `receiver` without submitting it
+///
+/// Runs the method as a view-shaped dry run (`query` / `call_function`) at the
+/// final block and reports success/failure, the return value and logs.
+///
+/// Limitations of a view-shaped dry run:
+/// - `signer` is not the predecessor during the dry run; when `deposit_yocto` is
+///   non-zero the signer's balance is checked against it instead.
+/// - Methods that write state, schedule promises or read the attached deposit
+///   fail in view mode and are reported as `success: false`.
+/// - `gas_burnt` is `None` because `call_function` doesn't report gas usage.
+///
+/// # Arguments
+/// * `signer` - Account that would sign the transaction
+/// * `receiver` - Contract to call
+/// * `method` - Method name
+/// * `args_json` - Method arguments as JSON (empty string = `{}`)
+/// * `deposit_yocto` - Deposit that would be attached, in yoctoNEAR
+///
+/// # Returns
+/// * `Ok(SimulationResult)` - Prediction (check `success`)
+/// * `Err(RpcError)` - RPC unavailable or unexpected response
+pub fn simulate_call(
+    signer: &str,
+    receiver: &str,
+    method: &str,
+    args_json: &str,
+    deposit_yocto: u128,
+) -> Result<SimulationResult> {
+    let args = if args_json.is_empty() { "{}" } else { args_json };
+    serde_json::from_str::<Value>(args)
+        .map_err(|e| RpcError(format!("args_json is not valid JSON: {}", e)))?;
+
+    if deposit_yocto > 0 {
+        let (response, error) = raw::view_account(signer, "final");
+        if !error.is_empty() {
+            return Err(RpcError(error));
+        }
+        let balance = parse_account_balance(&response)?;
+        if balance < deposit_yocto {
+            return Ok(SimulationResult {
+                success: false,
+                result: Vec::new(),
+                logs: Vec::new(),
+                gas_burnt: None,
+                error: Some(format!(
+                    "insufficient balance: {} has {} yoctoNEAR, deposit requires {}",
+                    signer, balance, deposit_yocto
+                )),
+                block_height: None,
+            });
+        }
+    }
+
+    let params = serde_json::json!({
+        "request_type": "call_function",
+        "finality": "final",
+        "account_id": receiver,
+        "method_name": method,
+        "args_base64": base64_encode(args.as_bytes()),
+    });
+
+    let (response, error) = raw::raw("query", &params.to_string());
+    if !error.is_empty() {
+        return Err(RpcError(error));
+    }
+
+    parse_simulation(&response)
+}
+
+/// Parse a `query` / `call_function` JSON-RPC response into a [`SimulationResult`]
+///
+/// Contract-level failures (panics, missing method or account) become
+/// `success: false`; anything else that isn't a result is an [`RpcError`].
+pub fn parse_simulation(response_json: &str) -> Result<SimulationResult> {
+    let response: Value = serde_json::from_str(response_json)
+        .map_err(|e| RpcError(format!("invalid RPC response: {}", e)))?;
+
+    // Structured error: {"error": {"name": "HANDLER_ERROR", "cause": {...}}}
+    if let Some(error) = response.get("error") {
+        if error.get("name").and_then(|n| n.as_str()) != Some("HANDLER_ERROR") {
+            return Err(RpcError(error_message(error)));
+        }
+        let info = error.get("cause").and_then(|c| c.get("info"));
+        let reason = info
+            .and_then(|i| i.get("vm_error"))
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| error_message(error));
+        return Ok(SimulationResult {
+            success: false,
+            result: Vec::new(),
+            logs: Vec::new(),
+            gas_burnt: None,
+            error: Some(reason),
+            block_height: info.and_then(|i| i.get("block_height")).and_then(|h| h.as_u64()),
+        });
+    }
+
+    let result = response
+        .get("result")
+        .ok_or_else(|| RpcError("response has neither result nor error".to_string()))?;
+
+    let logs = result
+        .get("logs")
+        .and_then(|l| l.as_array())
+        .map(|l| l.iter().filter_map(|s| s.as_str().map(str::to_string)).collect())
+        .unwrap_or_default();
+    let gas_burnt = result.get("gas_burnt").and_then(|g| g.as_u64());
+    let block_height = result.get("block_height").and_then(|h| h.as_u64());
+
+    // Legacy error shape: {"result": {"error": "wasm execution failed ...", "logs": []}}
+    if let Some(error) = result.get("error").and_then(|e| e.as_str()) {
+        return Ok(SimulationResult {
+            success: false,
+            result: Vec::new(),
+            logs,
+            gas_burnt,
+            error: Some(error.to_string()),
+            block_height,
+        });
+    }
+
+    let bytes = result
+        .get("result")
+        .and_then(|r| r.as_array())
+        .ok_or_else(|| RpcError("call_function result is missing".to_string()))?
+        .iter()
+        .map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(|| RpcError("call_function result is not a byte array".to_string()))?;
+
+    Ok(SimulationResult {
+        success: true,
+        result: bytes,
+        logs,
+        gas_burnt,
+        error: None,
+        block_height,
+    })
+}
+
+/// Extract `amount` from a `view_account` JSON-RPC response
+fn parse_account_balance(response_json: &str) -> Result<u128> {
+    let response: Value = serde_json::from_str(response_json)
+        .map_err(|e| RpcError(format!("invalid RPC response: {}", e)))?;
+    if let Some(error) = response.get("error") {
+        return Err(RpcError(error_message(error)));
+    }
+    response
+        .get("result")
+        .and_then(|r| r.get("amount"))
+        .and_then(|a| a.as_str())
+        .and_then(|a| a.parse().ok())
+        .ok_or_else(|| RpcError("view_account response has no amount".to_string()))
+}
+
+fn error_message(error: &Value) -> String {
+    error
+        .get("cause")
+        .and_then(|c| c.get("name"))
+        .or_else(|| error.get("message"))
+        .and_then(|m| m.as_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| error.to_string())
+}
+
+fn base64_encode(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | (b[2] as u32);
+        out.push(ALPHABET[(n >> 18) as usize & 63] as char);
+        out.push(ALPHABET[(n >> 12) as usize & 63] as char);
+        out.push(if chunk.len() > 1 { ALPHABET[(n >> 6) as usize & 63] as char } else { '=' });
+        out.push(if chunk.len() > 2 { ALPHABET[n as usize & 63] as char } else { '=' });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_successful_simulation() {
+        // "\"42\"" as bytes
+        let response = r#"{
+            "jsonrpc": "2.0",
+            "id": "1",
+            "result": {
+                "result": [34, 52, 50, 34],
+                "logs": ["balance checked"],
+                "block_height": 123456,
+                "block_hash": "9MzuZrRPW1BGpFnZJUJg6SzCrixPpJDfjsNeUobRXsLe"
+            }
+        }"#;
+
+        let sim = parse_simulation(response).unwrap();
+        assert!(sim.success);
+        assert_eq!(sim.result_str(), "\"42\"");
+        assert_eq!(sim.json::<String>().unwrap(), "42");
+        assert_eq!(sim.logs, vec!["balance checked".to_string()]);
+        assert_eq!(sim.block_height, Some(123456));
+        assert_eq!(sim.gas_burnt, None);
+        assert_eq!(sim.error, None);
+    }
+
+    #[test]
+    fn parses_contract_panic_as_failed_prediction() {
+        let response = r#"{
+            "jsonrpc": "2.0",
+            "id": "1",
+            "error": {
+                "name": "HANDLER_ERROR",
+                "cause": {
+                    "name": "CONTRACT_EXECUTION_ERROR",
+                    "info": {
+                        "vm_error": "wasm execution failed with error: FunctionCallError(ExecutionError(\"Smart contract panicked: not enough tokens\"))",
+                        "block_height": 123457
+                    }
+                },
+                "message": "Server error"
+            }
+        }"#;
+
+        let sim = parse_simulation(response).unwrap();
+        assert!(!sim.success);
+        assert!(sim.result.is_empty());
+        assert!(sim.error.unwrap().contains("not enough tokens"));
+        assert_eq!(sim.block_height, Some(123457));
+    }
+
+    #[test]
+    fn parses_legacy_error_shape() {
+        let response = r#"{
+            "result": {
+                "error": "wasm execution failed with error: MethodResolveError(MethodNotFound)",
+                "logs": ["entered"],
+                "block_height": 7
+            }
+        }"#;
+
+        let sim = parse_simulation(response).unwrap();
+        assert!(!sim.success);
+        assert_eq!(sim.logs, vec!["entered".to_string()]);
+        assert!(sim.error.unwrap().contains("MethodNotFound"));
+    }
+
+    #[test]
+    fn transport_errors_are_rpc_errors() {
+        let response = r#"{"error": {"name": "INTERNAL_ERROR", "cause": {"name": "TIMEOUT_ERROR"}}}"#;
+        assert_eq!(parse_simulation(response).unwrap_err().0, "TIMEOUT_ERROR");
+        assert!(parse_simulation("not json").is_err());
+    }
+
+    #[test]
+    fn parses_account_balance() {
+        let response = r#"{"result": {"amount": "1000000000000000000000000", "locked": "0"}}"#;
+        assert_eq!(parse_account_balance(response).unwrap(), 10u128.pow(24));
+    }
+
+    #[test]
+    fn encodes_base64() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"{}"), "e30=");
+        assert_eq!(base64_encode(b"{\"a\":1}"), "eyJhIjoxfQ==");
+        assert_eq!(base64_encode(b"abc"), "YWJj");
+    }
+}
@@ -26,6 +26,10 @@
 //!
 //! // List keys with prefix
 //! let keys = storage::list_keys("prefix:")?;
+//!
+//! // Batch read/write in one host call (up to MAX_BATCH_SIZE keys; set_many is atomic)
+//! let values = storage::get_many(&["a", "b"])?;
+//! storage::set_many(&[("a", b"1".as_slice()), ("b", b"2".as_slice())])?;
 //! ```
 //!
 //! ## Worker-Private Storage
@@ -43,6 +47,58 @@
 //! let state = storage::get_worker("internal-state")?;
 //! ```
 //!
+//! ## Append-Only Logs
+//!
+//! ```rust,ignore
+//! use outlayer::storage;
+//!
+//! // Atomic across concurrent executions; returns the new total length
+//! let len = storage::append("events", b"deposit:alice:100\n")?;
+//! let tail = storage::read_range("events", len.saturating_sub(1024), 1024)?;
+//! ```
+//!
+//! ## Expiring Entries
+//!
+//! Entries written with a TTL disappear on their own; no sweep job is needed.
+//!
+//! ```rust,ignore
+//! use outlayer::storage;
+//!
+//! storage::set_worker_with_ttl("session:abc", b"token", 3600)?;
+//! let left = storage::ttl_worker("session:abc")?; // Some(3600)
+//! ```
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! let usage = storage::usage()?; // key_count, total_bytes, worker_bytes, project_bytes
+//! let size = storage::key_bytes("order-book")?; // Some(bytes) or None
+//! ```
+//!
+//! ## Snapshots
+//!
+//! Checkpoint everything under a prefix and roll back on failure:
+//!
+//! ```rust,ignore
+//! let checkpoint = storage::snapshot("order:")?;
+//! if step().is_err() {
+//!     storage::restore(&checkpoint)?;
+//! }
+//! ```
+//!
+//! ## Transactions
+//!
+//! Update related keys together; either every write lands or none does
+//! (requires the `storage-txn` feature and a worker with transactions enabled):
+//!
+//! ```rust,ignore
+//! let mut txn = storage::begin()?;
+//! txn.set("book:bid:42", b"100@3")?;
+//! assert_eq!(txn.get("book:bid:42")?, Some(b"100@3".to_vec())); // sees its own writes
+//! txn.delete("book:ask:41")?;
+//! txn.commit()?; // dropping without commit rolls back
+//! ```
+//!
 //! ## Version Migration
 //!
 //! When upgrading your WASM, you can read data from a previous version:
@@ -55,18 +111,43 @@
 //! ```
 
 use crate::near::storage::api as raw;
+use std::sync::Arc;
+
+#[cfg(feature = "storage-http")]
+pub mod http;
 
 /// Storage error
 #[derive(Debug, Clone)]
-pub struct StorageError(pub String);
+pub enum StorageError {
+    /// Storage operation failed or was given invalid arguments
+    Host(String),
+    /// Value could not be converted to or from JSON (`get_json`/`set_json` helpers)
+    Serde(Arc<serde_json::Error>),
+}
 
 impl std::fmt::Display for StorageError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Storage error: {}", self.0)
+        match self {
+            StorageError::Host(e) => write!(f, "Storage error: {}", e),
+            StorageError::Serde(e) => write!(f, "Storage JSON error: {}", e),
+        }
     }
 }
 
-impl std::error::Error for StorageError {}
+impl std::error::Error for StorageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StorageError::Host(_) => None,
+            StorageError::Serde(e) => Some(e.as_ref()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for StorageError {
+    fn from(e: serde_json::Error) -> Self {
+        StorageError::Serde(Arc::new(e))
+    }
+}
 
 /// Result type for storage operations
 pub type Result<T> = std::result::Result<T, StorageError>;
@@ -91,7 +172,7 @@ pub fn set(key: &str, value: &[u8]) -> Result<()> {
     if error.is_empty() {
         Ok(())
     } else {
-        Err(StorageError(error))
+        Err(StorageError::Host(error))
     }
 }
 
@@ -115,7 +196,7 @@ pub fn set(key: &str, value: &[u8]) -> Result<()> {
 pub fn get(key: &str) -> Result<Option<Vec<u8>>> {
     let (data, error) = raw::get(key);
     if !error.is_empty() {
-        return Err(StorageError(error));
+        return Err(StorageError::Host(error));
     }
     if data.is_empty() {
         Ok(None)
@@ -187,10 +268,62 @@ pub fn delete(key: &str) -> bool {
 pub fn list_keys(prefix: &str) -> Result<Vec<String>> {
     let (keys_json, error) = raw::list_keys(prefix);
     if !error.is_empty() {
-        return Err(StorageError(error));
+        return Err(StorageError::Host(error));
     }
     serde_json::from_str(&keys_json)
-        .map_err(|e| StorageError(format!("Failed to parse keys list: {}", e)))
+        .map_err(|e| StorageError::Host(format!("Failed to parse keys list: {}", e)))
+}
+
+/// Maximum `limit` accepted by [`list_keys_paged`]
+pub const MAX_LIST_PAGE_SIZE: u32 = 1000;
+
+/// List keys one page at a time
+///
+/// Use this instead of [`list_keys`] when an ark may hold many keys: only one
+/// page is loaded into WASM memory per call. The cursor is opaque; pass back the
+/// one returned by the previous page. Keys added or removed between calls don't
+/// cause the remaining pages to repeat or skip other keys.
+///
+/// # Arguments
+/// * `prefix` - Prefix to filter keys (empty string for all keys)
+/// * `cursor` - `None` for the first page, then the cursor from the previous page
+/// * `limit` - Maximum keys per page (1 to [`MAX_LIST_PAGE_SIZE`])
+///
+/// # Returns
+/// * `Ok((keys, Some(cursor)))` - A page of keys, more may follow
+/// * `Ok((keys, None))` - Last page
+/// * `Err(StorageError)` - Storage operation failed or invalid limit
+///
+/// # Example
+/// ```rust,ignore
+/// let mut cursor = None;
+/// loop {
+///     let (keys, next) = storage::list_keys_paged("user:", cursor, 100)?;
+///     for key in keys {
+///         println!("Found user key: {}", key);
+///     }
+///     match next {
+///         Some(c) => cursor = Some(c),
+///         None => break,
+///     }
+/// }
+/// ```
+pub fn list_keys_paged(
+    prefix: &str,
+    cursor: Option<String>,
+    limit: u32,
+) -> Result<(Vec<String>, Option<String>)> {
+    if limit == 0 || limit > MAX_LIST_PAGE_SIZE {
+        return Err(StorageError::Host(format!(
+            "list_keys_paged: limit must be between 1 and {}",
+            MAX_LIST_PAGE_SIZE
+        )));
+    }
+    let (keys, next_cursor, error) = raw::list_keys_paged(prefix, cursor.as_deref(), limit);
+    if !error.is_empty() {
+        return Err(StorageError::Host(error));
+    }
+    Ok((keys, next_cursor))
 }
 
 /// Store worker-private data
@@ -232,7 +365,7 @@ pub fn set_worker_with_options(key: &str, value: &[u8], is_encrypted: Option<boo
     if error.is_empty() {
         Ok(())
     } else {
-        Err(StorageError(error))
+        Err(StorageError::Host(error))
     }
 }
 
@@ -280,7 +413,7 @@ pub fn get_worker(key: &str) -> Result<Option<Vec<u8>>> {
 pub fn get_worker_from_project(key: &str, project_uuid: Option<&str>) -> Result<Option<Vec<u8>>> {
     let (data, error) = raw::get_worker(key, project_uuid);
     if !error.is_empty() {
-        return Err(StorageError(error));
+        return Err(StorageError::Host(error));
     }
     if data.is_empty() {
         Ok(None)
@@ -289,6 +422,127 @@ pub fn get_worker_from_project(key: &str, project_uuid: Option<&str>) -> Result<
     }
 }
 
+/// Whether a worker key can be read by other projects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    /// Stored plaintext, readable with [`get_worker_from_project`]
+    Public,
+    /// Encrypted, only this project can read it
+    Private,
+}
+
+/// Get the visibility of a worker key of the current project
+///
+/// # Returns
+/// * `Ok(Some(Visibility))` - Key exists
+/// * `Ok(None)` - Key doesn't exist
+/// * `Err(StorageError)` - Storage operation failed
+///
+/// # Example
+/// ```rust,ignore
+/// // Secrets must never be readable by other projects
+/// assert_ne!(storage::visibility("api-secret")?, Some(Visibility::Public));
+/// ```
+pub fn visibility(key: &str) -> Result<Option<Visibility>> {
+    let (is_encrypted, error) = raw::get_worker_visibility(key);
+    if !error.is_empty() {
+        return Err(StorageError::Host(error));
+    }
+    Ok(is_encrypted.map(|encrypted| if encrypted { Visibility::Private } else { Visibility::Public }))
+}
+
+/// Make an existing worker key public or private without rewriting its value
+///
+/// The value and any TTL are kept. Setting the visibility the key already has is a no-op.
+///
+/// # Returns
+/// * `Ok(true)` - Key now has `visibility`
+/// * `Ok(false)` - Key doesn't exist, nothing was written
+/// * `Err(StorageError)` - Storage operation failed
+///
+/// # Example
+/// ```rust,ignore
+/// // Stop publishing a feed without losing its last value
+/// storage::set_visibility("price:ETH", Visibility::Private)?;
+/// ```
+pub fn set_visibility(key: &str, visibility: Visibility) -> Result<bool> {
+    let (existed, error) = raw::set_worker_visibility(key, visibility == Visibility::Private);
+    if !error.is_empty() {
+        return Err(StorageError::Host(error));
+    }
+    Ok(existed)
+}
+
+/// List public worker keys of another project
+///
+/// Returns every key the project stored with `set_worker_with_options(.., Some(false))`
+/// that starts with `prefix`, e.g. all `price:*` keys of an oracle. Encrypted keys are
+/// never listed. Pages of [`MAX_LIST_PAGE_SIZE`] keys are fetched until the listing is
+/// exhausted; use [`list_public_keys_from_project_paged`] to bound memory instead.
+///
+/// # Arguments
+/// * `project_uuid` - Project to list (e.g. "p0000000000000042")
+/// * `prefix` - Prefix to filter keys (empty string for all public keys)
+///
+/// # Returns
+/// * `Ok(Vec<String>)` - Matching public keys, in no particular order
+/// * `Err(StorageError)` - Storage operation failed, or the coordinator doesn't support public listing yet
+///
+/// # Example
+/// ```rust,ignore
+/// for key in storage::list_public_keys_from_project("p0000000000000042", "price:")? {
+///     if let Some(price) = storage::get_worker_from_project(&key, Some("p0000000000000042"))? {
+///         println!("{}: {:?}", key, price);
+///     }
+/// }
+/// ```
+pub fn list_public_keys_from_project(project_uuid: &str, prefix: &str) -> Result<Vec<String>> {
+    let mut all = Vec::new();
+    let mut cursor = None;
+    loop {
+        let (keys, next) = list_public_keys_from_project_paged(project_uuid, prefix, cursor, MAX_LIST_PAGE_SIZE)?;
+        all.extend(keys);
+        match next {
+            Some(c) => cursor = Some(c),
+            None => return Ok(all),
+        }
+    }
+}
+
+/// List public worker keys of another project one page at a time
+///
+/// Same cursor semantics as [`list_keys_paged`]. A page may hold fewer than `limit`
+/// keys (entries outside `prefix` still advance the cursor); only `None` ends the listing.
+///
+/// # Arguments
+/// * `project_uuid` - Project to list
+/// * `prefix` - Prefix to filter keys (empty string for all public keys)
+/// * `cursor` - `None` for the first page, then the cursor from the previous page
+/// * `limit` - Maximum keys per page (1 to [`MAX_LIST_PAGE_SIZE`])
+///
+/// # Returns
+/// * `Ok((keys, Some(cursor)))` - A page of keys, more may follow
+/// * `Ok((keys, None))` - Last page
+/// * `Err(StorageError)` - Storage operation failed, invalid limit, or public listing unsupported by the coordinator
+pub fn list_public_keys_from_project_paged(
+    project_uuid: &str,
+    prefix: &str,
+    cursor: Option<String>,
+    limit: u32,
+) -> Result<(Vec<String>, Option<String>)> {
+    if limit == 0 || limit > MAX_LIST_PAGE_SIZE {
+        return Err(StorageError::Host(format!(
+            "list_public_keys_from_project_paged: limit must be between 1 and {}",
+            MAX_LIST_PAGE_SIZE
+        )));
+    }
+    let (keys, next_cursor, error) = raw::list_public_keys(project_uuid, prefix, cursor.as_deref(), limit);
+    if !error.is_empty() {
+        return Err(StorageError::Host(error));
+    }
+    Ok((keys, next_cursor))
+}
+
 /// Get data from a specific WASM version (for migration)
 ///
 /// Use this when upgrading your WASM to read data written by a previous version.
@@ -316,7 +570,7 @@ pub fn get_worker_from_project(key: &str, project_uuid: Option<&str>) -> Result<
 pub fn get_by_version(key: &str, wasm_hash: &str) -> Result<Option<Vec<u8>>> {
     let (data, error) = raw::get_by_version(key, wasm_hash);
     if !error.is_empty() {
-        return Err(StorageError(error));
+        return Err(StorageError::Host(error));
     }
     if data.is_empty() {
         Ok(None)
@@ -343,7 +597,7 @@ pub fn clear_all() -> Result<()> {
     if error.is_empty() {
         Ok(())
     } else {
-        Err(StorageError(error))
+        Err(StorageError::Host(error))
     }
 }
 
@@ -368,7 +622,7 @@ pub fn clear_version(wasm_hash: &str) -> Result<()> {
     if error.is_empty() {
         Ok(())
     } else {
-        Err(StorageError(error))
+        Err(StorageError::Host(error))
     }
 }
 
@@ -406,7 +660,7 @@ pub fn get_string(key: &str) -> Result<Option<String>> {
         Some(data) => {
             String::from_utf8(data)
                 .map(Some)
-                .map_err(|e| StorageError(format!("Invalid UTF-8: {}", e)))
+                .map_err(|e| StorageError::Host(format!("Invalid UTF-8: {}", e)))
         }
         None => Ok(None),
     }
@@ -428,9 +682,7 @@ pub fn get_string(key: &str) -> Result<Option<String>> {
 /// storage::set_json("config", &config)?;
 /// ```
 pub fn set_json<T: serde::Serialize>(key: &str, value: &T) -> Result<()> {
-    let json = serde_json::to_vec(value)
-        .map_err(|e| StorageError(format!("JSON serialization failed: {}", e)))?;
-    set(key, &json)
+    set(key, &serde_json::to_vec(value)?)
 }
 
 /// Get a JSON-deserializable value
@@ -455,12 +707,26 @@ pub fn set_json<T: serde::Serialize>(key: &str, value: &T) -> Result<()> {
 /// }
 /// ```
 pub fn get_json<T: serde::de::DeserializeOwned>(key: &str) -> Result<Option<T>> {
-    match get(key)? {
-        Some(data) => {
-            serde_json::from_slice(&data)
-                .map(Some)
-                .map_err(|e| StorageError(format!("JSON deserialization failed: {}", e)))
-        }
+    from_json(get(key)?)
+}
+
+/// Store a JSON-serializable value in worker-private storage
+///
+/// Worker-private counterpart of [`set_json`].
+pub fn set_worker_json<T: serde::Serialize>(key: &str, value: &T) -> Result<()> {
+    set_worker(key, &serde_json::to_vec(value)?)
+}
+
+/// Get a JSON-deserializable value from worker-private storage
+///
+/// Worker-private counterpart of [`get_json`].
+pub fn get_worker_json<T: serde::de::DeserializeOwned>(key: &str) -> Result<Option<T>> {
+    from_json(get_worker(key)?)
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(data: Option<Vec<u8>>) -> Result<Option<T>> {
+    match data {
+        Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
         None => Ok(None),
     }
 }
@@ -490,11 +756,66 @@ pub fn get_json<T: serde::de::DeserializeOwned>(key: &str) -> Result<Option<T>>
 pub fn set_if_absent(key: &str, value: &[u8]) -> Result<bool> {
     let (inserted, error) = raw::set_if_absent(key, value);
     if !error.is_empty() {
-        return Err(StorageError(error));
+        return Err(StorageError::Host(error));
     }
     Ok(inserted)
 }
 
+/// Insert attempts of [`get_or_set_if_absent`] before it gives up on a key that keeps
+/// being deleted concurrently
+const GET_OR_SET_ATTEMPTS: usize = 3;
+
+/// Get a key's value, or compute and insert it if the key doesn't exist
+///
+/// Concurrent executions that all miss may each run `compute`, but only one value is
+/// written (via [`set_if_absent`]) and every caller gets that one back, so they agree
+/// on the result.
+///
+/// # Arguments
+/// * `key` - The key to read or initialize
+/// * `compute` - Produces the value when the key is absent; must not be empty, since
+///   [`get`] reads an empty value as a missing key
+///
+/// # Returns
+/// * `Ok(value)` - The existing value, or the one that won the insert
+/// * `Err(StorageError)` - Storage operation failed, `compute` returned an empty value,
+///   or the key was deleted between insert and read on every attempt
+///
+/// # Example
+/// ```rust,ignore
+/// let day = "2026-10-16";
+/// let aggregate = storage::get_or_set_if_absent(&format!("daily:{}", day), || {
+///     compute_daily_aggregate(day)
+/// })?;
+/// ```
+pub fn get_or_set_if_absent(key: &str, compute: impl FnOnce() -> Vec<u8>) -> Result<Vec<u8>> {
+    if let Some(existing) = get(key)? {
+        return Ok(existing);
+    }
+    let value = compute();
+    if value.is_empty() {
+        return Err(StorageError::Host(format!(
+            "get_or_set_if_absent: computed value for '{}' is empty",
+            key
+        )));
+    }
+    for _ in 0..GET_OR_SET_ATTEMPTS {
+        if set_if_absent(key, &value)? {
+            return Ok(value);
+        }
+        // Another execution inserted first: return its value, unless it was deleted
+        // before we could read it, in which case try inserting ours again
+        if let Some(winner) = get(key)? {
+            return Ok(winner);
+        }
+    }
+    Err(StorageError::Host(format!(
+        "get_or_set_if_absent: '{}' was deleted under every one of {} attempts",
+        key, GET_OR_SET_ATTEMPTS
+    )))
+}
+
+
 /// Set a key only if current value equals expected (compare-and-swap)
 ///
 /// This is useful for atomic updates when multiple processes might be
@@ -529,7 +850,7 @@ pub fn set_if_absent(key: &str, value: &[u8]) -> Result<bool> {
 pub fn set_if_equals(key: &str, expected: &[u8], new_value: &[u8]) -> Result<(bool, Option<Vec<u8>>)> {
     let (success, current, error) = raw::set_if_equals(key, expected, new_value);
     if !error.is_empty() {
-        return Err(StorageError(error));
+        return Err(StorageError::Host(error));
     }
     if success {
         Ok((true, None))
@@ -540,6 +861,49 @@ pub fn set_if_equals(key: &str, expected: &[u8], new_value: &[u8]) -> Result<(bo
     }
 }
 
+/// Delete a key only if its current value equals expected (compare-and-delete)
+///
+/// The check and the delete happen atomically on the host, so a value
+/// written by another execution between your read and the delete is never
+/// removed by mistake.
+///
+/// # Arguments
+/// * `key` - The key to delete
+/// * `expected` - The value the key must currently hold
+///
+/// # Returns
+/// * `Ok(true)` - Key held `expected` and was deleted
+/// * `Ok(false)` - Key was absent or held a different value (nothing deleted)
+/// * `Err(StorageError)` - Storage operation failed, or the coordinator doesn't support conditional deletes yet
+///
+/// # Example
+/// ```rust,ignore
+/// // Release a lease only if we still own it
+/// let holder = env::signer_account_id().unwrap_or_default();
+/// if !storage::compare_and_delete("lease:job-42", holder.as_bytes())? {
+///     println!("Lease expired or taken over by someone else");
+/// }
+/// ```
+pub fn compare_and_delete(key: &str, expected: &[u8]) -> Result<bool> {
+    let (deleted, error) = raw::compare_and_delete(key, expected);
+    if !error.is_empty() {
+        return Err(StorageError::Host(error));
+    }
+    Ok(deleted)
+}
+
+/// Delete a worker storage key only if its current value equals expected
+///
+/// Worker-storage counterpart of [`compare_and_delete`], for keys written
+/// with [`set_worker`].
+pub fn compare_and_delete_worker(key: &str, expected: &[u8]) -> Result<bool> {
+    let (deleted, error) = raw::compare_and_delete_worker(key, expected);
+    if !error.is_empty() {
+        return Err(StorageError::Host(error));
+    }
+    Ok(deleted)
+}
+
 /// Atomically increment a numeric value
 ///
 /// If the key doesn't exist, creates it with delta as the initial value.
@@ -566,7 +930,7 @@ pub fn set_if_equals(key: &str, expected: &[u8], new_value: &[u8]) -> Result<(bo
 pub fn increment(key: &str, delta: i64) -> Result<i64> {
     let (new_value, error) = raw::increment(key, delta);
     if !error.is_empty() {
-        return Err(StorageError(error));
+        return Err(StorageError::Host(error));
     }
     Ok(new_value)
 }
@@ -595,7 +959,596 @@ pub fn increment(key: &str, delta: i64) -> Result<i64> {
 pub fn decrement(key: &str, delta: i64) -> Result<i64> {
     let (new_value, error) = raw::decrement(key, delta);
     if !error.is_empty() {
-        return Err(StorageError(error));
+        return Err(StorageError::Host(error));
     }
     Ok(new_value)
 }
+
+// ==================== Append-Only Values ====================
+
+/// Largest value [`append`] / [`append_worker`] may produce (1 MiB)
+///
+/// An append that would grow the value past this fails without writing;
+/// continue the log under a new key (e.g. `log:2`).
+pub const MAX_APPEND_VALUE_SIZE: usize = 1024 * 1024;
+
+/// Atomically append bytes to a value
+///
+/// Creates the key if it doesn't exist. Concurrent appends from other
+/// executions are never lost: the worker retries with compare-and-swap.
+/// The host still rewrites the whole value, so keep logs well under
+/// [`MAX_APPEND_VALUE_SIZE`] and read them back with [`read_range`].
+/// Appending empty data writes nothing and returns the current length.
+///
+/// # Arguments
+/// * `key` - The key to append to
+/// * `data` - Bytes to add at the end of the value
+///
+/// # Returns
+/// * `Ok(len)` - Total length of the value after the append
+/// * `Err(StorageError)` - Storage operation failed or the value would exceed [`MAX_APPEND_VALUE_SIZE`]
+///
+/// # Example
+/// ```rust,ignore
+/// // Event log: one JSON line per event
+/// let len = storage::append("events", format!("{}\n", event_json).as_bytes())?;
+/// ```
+pub fn append(key: &str, data: &[u8]) -> Result<u64> {
+    let (new_len, error) = raw::append(key, data);
+    if !error.is_empty() {
+        return Err(StorageError::Host(error));
+    }
+    Ok(new_len)
+}
+
+/// Atomically append bytes to a worker-private value
+///
+/// Same as [`append`] for encrypted worker storage; read back with [`get_worker`].
+pub fn append_worker(key: &str, data: &[u8]) -> Result<u64> {
+    let (new_len, error) = raw::append_worker(key, data);
+    if !error.is_empty() {
+        return Err(StorageError::Host(error));
+    }
+    Ok(new_len)
+}
+
+/// Read part of a value
+///
+/// # Arguments
+/// * `key` - The key to read
+/// * `offset` - First byte to return
+/// * `len` - Maximum number of bytes to return
+///
+/// # Returns
+/// * `Ok(bytes)` - Up to `len` bytes from `offset`; empty if the key doesn't exist or `offset` is past the end
+/// * `Err(StorageError)` - Storage operation failed
+///
+/// # Example
+/// ```rust,ignore
+/// // Tail the last 4 KiB of a log
+/// let len = storage::append("events", b"")?;
+/// let tail = storage::read_range("events", len.saturating_sub(4096), 4096)?;
+/// ```
+pub fn read_range(key: &str, offset: u64, len: u64) -> Result<Vec<u8>> {
+    let (bytes, error) = raw::read_range(key, offset, len);
+    if !error.is_empty() {
+        return Err(StorageError::Host(error));
+    }
+    Ok(bytes)
+}
+
+// ==================== Expiring Entries ====================
+
+/// Store a value that expires after `ttl_seconds`
+///
+/// Once expired, the entry reads as missing (`get` returns `None`), is not
+/// returned by `list_keys` / `list_keys_paged`, and is deleted on the next read.
+/// Writing the key again with `set` removes the expiry.
+///
+/// Fails unless the worker runs with `STORAGE_TTL_ENABLED=true`.
+///
+/// # Arguments
+/// * `key` - The key to store the value under
+/// * `value` - The value to store (as bytes)
+/// * `ttl_seconds` - Lifetime in seconds (must be greater than 0)
+///
+/// # Returns
+/// * `Ok(())` - Value stored successfully
+/// * `Err(StorageError)` - Storage operation failed, `ttl_seconds` is 0, or TTL is disabled on the worker
+///
+/// # Example
+/// ```rust,ignore
+/// // Cache a quote for 5 minutes
+/// storage::set_with_ttl("quote:ETH", &price.to_le_bytes(), 300)?;
+/// ```
+pub fn set_with_ttl(key: &str, value: &[u8], ttl_seconds: u64) -> Result<()> {
+    if ttl_seconds == 0 {
+        return Err(StorageError::Host("ttl_seconds must be greater than 0".to_string()));
+    }
+    let error = raw::set_with_ttl(key, value, ttl_seconds);
+    if error.is_empty() {
+        Ok(())
+    } else {
+        Err(StorageError::Host(error))
+    }
+}
+
+/// Store worker-private data that expires after `ttl_seconds`
+///
+/// Same expiry semantics as [`set_with_ttl`]; read back with [`get_worker`].
+///
+/// # Example
+/// ```rust,ignore
+/// // Session valid for one hour, no sweep needed
+/// storage::set_worker_with_ttl(&format!("session:{}", id), &token, 3600)?;
+/// ```
+pub fn set_worker_with_ttl(key: &str, value: &[u8], ttl_seconds: u64) -> Result<()> {
+    if ttl_seconds == 0 {
+        return Err(StorageError::Host("ttl_seconds must be greater than 0".to_string()));
+    }
+    let error = raw::set_worker_with_ttl(key, value, ttl_seconds);
+    if error.is_empty() {
+        Ok(())
+    } else {
+        Err(StorageError::Host(error))
+    }
+}
+
+/// Remaining lifetime of a key in seconds
+///
+/// # Returns
+/// * `Ok(Some(seconds))` - Key expires in `seconds`
+/// * `Ok(None)` - Key doesn't exist, has expired, or was stored without TTL
+/// * `Err(StorageError)` - Storage operation failed
+///
+/// # Example
+/// ```rust,ignore
+/// // Renew before it runs out
+/// if storage::ttl("quote:ETH")?.map_or(true, |left| left < 60) {
+///     storage::set_with_ttl("quote:ETH", &fetch_price()?, 300)?;
+/// }
+/// ```
+pub fn ttl(key: &str) -> Result<Option<u64>> {
+    let (ttl, error) = raw::ttl(key);
+    if !error.is_empty() {
+        return Err(StorageError::Host(error));
+    }
+    Ok(ttl)
+}
+
+/// Remaining lifetime of a worker-private key in seconds
+///
+/// Same as [`ttl`] for keys written with [`set_worker_with_ttl`].
+pub fn ttl_worker(key: &str) -> Result<Option<u64>> {
+    let (ttl, error) = raw::ttl_worker(key);
+    if !error.is_empty() {
+        return Err(StorageError::Host(error));
+    }
+    Ok(ttl)
+}
+
+// ==================== Usage ====================
+
+/// Storage consumed by the project
+///
+/// Sizes are the bytes the backend stores per entry: encrypted key plus
+/// encrypted value, a few dozen bytes more than the plaintext.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StorageUsage {
+    /// Entries of the calling account plus the project's worker storage
+    pub key_count: u64,
+    /// `project_bytes + worker_bytes`
+    pub total_bytes: u64,
+    /// Worker-private storage of the project ([`set_worker`] and friends)
+    pub worker_bytes: u64,
+    /// Storage of the calling account in the project
+    pub project_bytes: u64,
+}
+
+/// Storage consumed by the calling account and the project's worker storage
+///
+/// Answered from the backend's metadata, so it is cheap even with many keys.
+/// Snapshot data is included; expired entries count until they are reclaimed.
+/// Fails with a "not supported by this coordinator" error on coordinators that
+/// don't serve usage queries yet.
+///
+/// # Example
+/// ```rust,ignore
+/// let usage = storage::usage()?;
+/// if usage.total_bytes > QUOTA * 9 / 10 {
+///     evict_least_recently_used()?;
+/// }
+/// ```
+pub fn usage() -> Result<StorageUsage> {
+    let (usage, error) = raw::usage();
+    if !error.is_empty() {
+        return Err(StorageError::Host(error));
+    }
+    Ok(StorageUsage {
+        key_count: usage.key_count,
+        total_bytes: usage.total_bytes,
+        worker_bytes: usage.worker_bytes,
+        project_bytes: usage.project_bytes,
+    })
+}
+
+/// Total bytes stored by the project; same as `usage()?.total_bytes`
+pub fn total_bytes() -> Result<u64> {
+    usage().map(|usage| usage.total_bytes)
+}
+
+/// Stored size of a key in bytes
+///
+/// # Returns
+/// * `Ok(Some(bytes))` - Encrypted key plus encrypted value size
+/// * `Ok(None)` - Key doesn't exist or has expired
+/// * `Err(StorageError)` - Storage operation failed
+pub fn key_bytes(key: &str) -> Result<Option<u64>> {
+    let (bytes, error) = raw::key_bytes(key);
+    if !error.is_empty() {
+        return Err(StorageError::Host(error));
+    }
+    Ok(bytes)
+}
+
+// ==================== Snapshots ====================
+
+/// Maximum number of keys one [`snapshot`] can capture
+pub const MAX_SNAPSHOT_KEYS: usize = 1000;
+
+/// Maximum total size (keys plus values) one [`snapshot`] can capture
+pub const MAX_SNAPSHOT_BYTES: usize = 4 * 1024 * 1024;
+
+/// Live snapshots kept per account; taking another drops the oldest
+pub const MAX_LIVE_SNAPSHOTS: usize = 8;
+
+/// Snapshot lifetime in seconds
+pub const SNAPSHOT_TTL_SECONDS: u64 = 3600;
+
+/// Handle to a storage snapshot taken with [`snapshot`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SnapshotId(pub String);
+
+impl std::fmt::Display for SnapshotId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Capture every key under `prefix` for a later [`restore`]
+///
+/// The copy is made host-side and stored encrypted next to your data, under
+/// the reserved key prefix `__outlayer_snapshot:` (writes to it are refused). A
+/// snapshot expires after [`SNAPSHOT_TTL_SECONDS`]; at most
+/// [`MAX_LIVE_SNAPSHOTS`] are kept and taking another drops the oldest. The id
+/// can be stored (e.g. with [`set_string`]) to roll back in a later execution.
+///
+/// # Arguments
+/// * `prefix` - Prefix of the keys to capture (empty string for all keys)
+///
+/// # Returns
+/// * `Ok(SnapshotId)` - Snapshot taken
+/// * `Err(StorageError)` - More than [`MAX_SNAPSHOT_KEYS`] keys or [`MAX_SNAPSHOT_BYTES`], or storage failure
+///
+/// # Example
+/// ```rust,ignore
+/// let checkpoint = storage::snapshot("order:")?;
+/// if let Err(e) = apply_order_transition() {
+///     storage::restore(&checkpoint)?;
+///     return Err(e);
+/// }
+/// ```
+pub fn snapshot(prefix: &str) -> Result<SnapshotId> {
+    let (id, error) = raw::snapshot(prefix);
+    if !error.is_empty() {
+        return Err(StorageError::Host(error));
+    }
+    Ok(SnapshotId(id))
+}
+
+/// Revert keys under the snapshot's prefix to the captured state
+///
+/// Captured keys get their captured values back (without TTL), and keys
+/// created under the prefix after the snapshot are deleted. Keys outside the
+/// prefix are not touched. The snapshot stays usable until it expires.
+///
+/// Not atomic: the host writes keys one by one, so an error can leave some keys
+/// restored and others not. Restoring is idempotent; retry with the same
+/// snapshot to finish.
+///
+/// # Returns
+/// * `Ok(())` - State reverted
+/// * `Err(StorageError)` - Snapshot unknown or expired, or storage failure
+pub fn restore(snapshot: &SnapshotId) -> Result<()> {
+    let error = raw::restore(&snapshot.0);
+    if error.is_empty() {
+        Ok(())
+    } else {
+        Err(StorageError::Host(error))
+    }
+}
+
+// ==================== Batch Operations ====================
+
+/// Maximum number of keys accepted by [`get_many`] / [`set_many`] in one call
+///
+/// `MAX-BATCH-SIZE` in wit/deps/storage.wit. The worker rejects larger batches;
+/// the SDK checks before calling the host.
+pub const MAX_BATCH_SIZE: usize = 100;
+
+/// Host error for [`set_many`] on a worker without transactions (pinned in storage.wit)
+const TRANSACTIONS_DISABLED: &str = "storage transactions are not enabled on this worker";
+
+/// Batch operation error
+#[derive(Debug, Clone)]
+pub enum BatchError {
+    /// The batch has more than [`MAX_BATCH_SIZE`] entries
+    TooLarge { size: usize, max: usize },
+    /// The worker can't write the batch atomically (storage transactions are disabled)
+    Unsupported,
+    /// Storage operation failed
+    Storage(StorageError),
+}
+
+impl std::fmt::Display for BatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BatchError::TooLarge { size, max } => {
+                write!(f, "Storage batch too large: {} entries (max {})", size, max)
+            }
+            BatchError::Unsupported => write!(f, "Storage error: {}", TRANSACTIONS_DISABLED),
+            BatchError::Storage(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for BatchError {}
+
+impl From<BatchError> for StorageError {
+    fn from(e: BatchError) -> Self {
+        match e {
+            BatchError::Storage(e) => e,
+            other => StorageError::Host(other.to_string()),
+        }
+    }
+}
+
+fn check_batch_size(size: usize) -> std::result::Result<(), BatchError> {
+    if size > MAX_BATCH_SIZE {
+        return Err(BatchError::TooLarge { size, max: MAX_BATCH_SIZE });
+    }
+    Ok(())
+}
+
+/// Get several values in a single host call
+///
+/// # Arguments
+/// * `keys` - Keys to retrieve (at most [`MAX_BATCH_SIZE`])
+///
+/// # Returns
+/// * `Ok(values)` - One entry per key, in input order; `None` for missing keys
+/// * `Err(BatchError::TooLarge)` - More than [`MAX_BATCH_SIZE`] keys
+/// * `Err(BatchError::Storage)` - Storage operation failed
+///
+/// # Example
+/// ```rust,ignore
+/// let values = storage::get_many(&["price:BTC", "price:ETH"])?;
+/// if let Some(btc) = &values[0] {
+///     println!("BTC: {:?}", btc);
+/// }
+/// ```
+pub fn get_many(keys: &[&str]) -> std::result::Result<Vec<Option<Vec<u8>>>, BatchError> {
+    check_batch_size(keys.len())?;
+    if keys.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let keys: Vec<String> = keys.iter().map(|k| k.to_string()).collect();
+    let (values, error) = raw::get_many(&keys);
+    if !error.is_empty() {
+        return Err(BatchError::Storage(StorageError::Host(error)));
+    }
+    if values.len() != keys.len() {
+        return Err(BatchError::Storage(StorageError::Host(format!(
+            "get_many returned {} values for {} keys",
+            values.len(),
+            keys.len()
+        ))));
+    }
+    Ok(values)
+}
+
+/// Store several values atomically in a single host call
+///
+/// Either every pair is stored or none is; a key listed twice keeps its last
+/// value. The worker commits the batch like a transaction, so it must run with
+/// `STORAGE_TRANSACTIONS_ENABLED=true`.
+///
+/// # Arguments
+/// * `pairs` - `(key, value)` pairs to store (at most [`MAX_BATCH_SIZE`])
+///
+/// # Returns
+/// * `Ok(())` - All values stored
+/// * `Err(BatchError::TooLarge)` - More than [`MAX_BATCH_SIZE`] pairs
+/// * `Err(BatchError::Unsupported)` - Storage transactions are disabled on the worker (nothing stored)
+/// * `Err(BatchError::Storage)` - Storage operation failed (nothing stored)
+///
+/// # Example
+/// ```rust,ignore
+/// storage::set_many(&[("price:BTC", b"97000".as_slice()), ("price:ETH", b"3400".as_slice())])?;
+/// ```
+pub fn set_many(pairs: &[(&str, &[u8])]) -> std::result::Result<(), BatchError> {
+    check_batch_size(pairs.len())?;
+    if pairs.is_empty() {
+        return Ok(());
+    }
+
+    let entries: Vec<(String, Vec<u8>)> = pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_vec()))
+        .collect();
+    let error = raw::set_many(&entries);
+    if error.is_empty() {
+        Ok(())
+    } else if error == TRANSACTIONS_DISABLED {
+        Err(BatchError::Unsupported)
+    } else {
+        Err(BatchError::Storage(StorageError::Host(error)))
+    }
+}
+
+// ==================== Transactions ====================
+// Behind the `storage-txn` feature: commits need the coordinator's transaction
+// endpoint, and workers only allow them with STORAGE_TRANSACTIONS_ENABLED=true.
+
+/// Maximum number of keys one transaction may write or delete
+#[cfg(feature = "storage-txn")]
+pub const MAX_TXN_KEYS: usize = MAX_BATCH_SIZE;
+
+/// Transactions one execution may keep open at the same time
+#[cfg(feature = "storage-txn")]
+pub const MAX_OPEN_TXNS: usize = 16;
+
+/// Storage transaction opened with [`begin`]
+///
+/// Writes and deletes are buffered by the worker and applied all-or-nothing by
+/// [`Txn::commit`]. Reads through the transaction see its own buffered writes
+/// first. Dropping a transaction without committing rolls it back.
+///
+/// Isolation: nothing is locked while the transaction is open. Concurrent
+/// executions don't see its writes until commit, keys it read may be changed by
+/// them before commit, and the last commit to a key wins. Use
+/// [`set_if_equals`] when a write must depend on a value not changing.
+#[cfg(feature = "storage-txn")]
+#[derive(Debug)]
+pub struct Txn {
+    handle: u32,
+    open: bool,
+}
+
+/// Open a storage transaction
+///
+/// # Returns
+/// * `Ok(Txn)` - Transaction opened
+/// * `Err(StorageError)` - More than [`MAX_OPEN_TXNS`] transactions open
+///
+/// # Example
+/// ```rust,ignore
+/// let mut txn = storage::begin()?;
+/// txn.set("order:42", b"filled")?;
+/// txn.delete("book:bid:42")?;
+/// txn.commit()?;
+/// ```
+#[cfg(feature = "storage-txn")]
+pub fn begin() -> Result<Txn> {
+    let (handle, error) = raw::txn_begin();
+    if !error.is_empty() {
+        return Err(StorageError::Host(error));
+    }
+    Ok(Txn { handle, open: true })
+}
+
+#[cfg(feature = "storage-txn")]
+impl Txn {
+    /// Buffer a write; nothing is stored until [`Txn::commit`]
+    ///
+    /// Fails if the transaction would touch more than [`MAX_TXN_KEYS`] keys.
+    pub fn set(&mut self, key: &str, value: &[u8]) -> Result<()> {
+        let error = raw::txn_set(self.handle, key, value);
+        if error.is_empty() {
+            Ok(())
+        } else {
+            Err(StorageError::Host(error))
+        }
+    }
+
+    /// Buffer a delete; nothing is deleted until [`Txn::commit`]
+    pub fn delete(&mut self, key: &str) -> Result<()> {
+        let error = raw::txn_delete(self.handle, key);
+        if error.is_empty() {
+            Ok(())
+        } else {
+            Err(StorageError::Host(error))
+        }
+    }
+
+    /// Read a key as this transaction sees it
+    ///
+    /// Returns the buffered value if the key was written in this transaction,
+    /// `None` if it was deleted, and the stored value otherwise.
+    pub fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let (value, error) = raw::txn_get(self.handle, key);
+        if !error.is_empty() {
+            return Err(StorageError::Host(error));
+        }
+        Ok(value)
+    }
+
+    /// Apply all buffered writes atomically
+    ///
+    /// # Returns
+    /// * `Ok(())` - Every write and delete was applied
+    /// * `Err(StorageError)` - Nothing was applied
+    pub fn commit(mut self) -> Result<()> {
+        self.open = false;
+        let error = raw::txn_commit(self.handle);
+        if error.is_empty() {
+            Ok(())
+        } else {
+            Err(StorageError::Host(error))
+        }
+    }
+
+    /// Discard all buffered writes
+    pub fn rollback(mut self) {
+        self.open = false;
+        raw::txn_rollback(self.handle);
+    }
+}
+
+#[cfg(feature = "storage-txn")]
+impl Drop for Txn {
+    fn drop(&mut self) {
+        if self.open {
+            raw::txn_rollback(self.handle);
+        }
+    }
+}
+
+/// Run `f` in a transaction, committing if it returns `Ok` and rolling back otherwise
+///
+/// # Example
+/// ```rust,ignore
+/// storage::transaction(|txn| {
+///     let bid = txn.get("book:bid")?.unwrap_or_default();
+///     txn.set("book:bid", &next_bid(&bid))?;
+///     txn.set("book:last_trade", &trade)?;
+///     Ok::<_, storage::StorageError>(())
+/// })?;
+/// ```
+#[cfg(feature = "storage-txn")]
+pub fn transaction<T, E: From<StorageError>>(
+    f: impl FnOnce(&mut Txn) -> std::result::Result<T, E>,
+) -> std::result::Result<T, E> {
+    let mut txn = begin()?;
+    let value = f(&mut txn)?;
+    txn.commit()?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_size_matches_wit() {
+        let wit = include_str!("../wit/deps/storage.wit");
+        assert!(wit.contains(&format!("MAX-BATCH-SIZE = {}:", MAX_BATCH_SIZE)));
+    }
+
+    #[test]
+    fn transactions_disabled_message_matches_wit() {
+        let wit = include_str!("../wit/deps/storage.wit");
+        assert!(wit.contains(&format!("\"{}\"", TRANSACTIONS_DISABLED)));
+    }
+}
@@ -0,0 +1,152 @@
+//! Public storage reads over HTTP (feature `storage-http`)
+//!
+//! Plaintext worker data (`storage::set_worker_with_options(key, value, Some(false))`)
+//! can be read by anyone through the
+//! coordinator's `/public/storage/get` endpoint, including arks and services that only
+//! know the owning project's UUID. [`read_public`] wraps that endpoint so arks don't each
+//! carry the URL format, the `exists` check and the base64 decoding.
+//!
+//! ```rust,ignore
+//! use outlayer::storage::http;
+//!
+//! match http::read_public(&coordinator_url, &project_uuid, "price:ETH")? {
+//!     Some(bytes) => { /* ... */ }
+//!     None => { /* key not set */ }
+//! }
+//! ```
+
+use base64::Engine;
+use std::time::Duration;
+use wasi_http_client::Client;
+
+/// Connect timeout of a public storage request
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Public storage read error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HttpError {
+    /// Request failed or the coordinator answered with a non-2xx status other than 403
+    Request(String),
+    /// The key exists but its value is encrypted (not stored with `is_encrypted = Some(false)`)
+    Encrypted,
+    /// Response isn't the expected JSON, or the value isn't valid base64
+    Response(String),
+}
+
+impl std::fmt::Display for HttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HttpError::Request(e) => write!(f, "Public storage request failed: {}", e),
+            HttpError::Encrypted => write!(f, "Storage key exists but is encrypted (not public)"),
+            HttpError::Response(e) => write!(f, "Invalid public storage response: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for HttpError {}
+
+/// Read a public storage value of another (or this) project
+///
+/// # Arguments
+/// * `coordinator_url` - Coordinator base URL
+/// * `project_uuid` - UUID of the project that owns the value (`OUTLAYER_PROJECT_UUID` for this one)
+/// * `key` - Storage key
+///
+/// # Returns
+/// * `Ok(Some(bytes))` - Value
+/// * `Ok(None)` - Key not set
+/// * `Err(HttpError::Encrypted)` - Key is set but not public
+pub fn read_public(coordinator_url: &str, project_uuid: &str, key: &str) -> Result<Option<Vec<u8>>, HttpError> {
+    let response = Client::new()
+        .get(&public_get_url(coordinator_url, project_uuid, key))
+        .connect_timeout(CONNECT_TIMEOUT)
+        .send()
+        .map_err(|e| HttpError::Request(e.to_string()))?;
+
+    let status = response.status();
+    let body = response.body().map_err(|e| HttpError::Request(e.to_string()))?;
+    parse_public_response(status, &body)
+}
+
+/// `/public/storage/get` URL with the query parameters percent-encoded
+fn public_get_url(coordinator_url: &str, project_uuid: &str, key: &str) -> String {
+    format!(
+        "{}/public/storage/get?project_uuid={}&key={}",
+        coordinator_url.trim_end_matches('/'),
+        encode_query_value(project_uuid),
+        encode_query_value(key)
+    )
+}
+
+/// Value of a `/public/storage/get` response: `{"exists": bool, "value": "<base64>"}`
+fn parse_public_response(status: u16, body: &[u8]) -> Result<Option<Vec<u8>>, HttpError> {
+    if status == 403 {
+        return Err(HttpError::Encrypted);
+    }
+    if status / 100 != 2 {
+        return Err(HttpError::Request(format!(
+            "HTTP {}: {}",
+            status,
+            String::from_utf8_lossy(body)
+        )));
+    }
+
+    let json: serde_json::Value =
+        serde_json::from_slice(body).map_err(|e| HttpError::Response(e.to_string()))?;
+    if !json["exists"].as_bool().unwrap_or(false) {
+        return Ok(None);
+    }
+    let value = json["value"]
+        .as_str()
+        .ok_or_else(|| HttpError::Response("existing key has no value".to_string()))?;
+    base64::engine::general_purpose::STANDARD
+        .decode(value)
+        .map(Some)
+        .map_err(|e| HttpError::Response(format!("value is not base64: {}", e)))
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters
+fn encode_query_value(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn url_encodes_key() {
+        assert_eq!(
+            public_get_url("https://api.example.com/", "0b2e-11", "user:alice.near/score 1"),
+            "https://api.example.com/public/storage/get?project_uuid=0b2e-11&key=user%3Aalice.near%2Fscore%201"
+        );
+    }
+
+    #[test]
+    fn parses_value_and_missing_key() {
+        assert_eq!(
+            parse_public_response(200, br#"{"exists":true,"value":"aGVsbG8="}"#),
+            Ok(Some(b"hello".to_vec()))
+        );
+        assert_eq!(parse_public_response(200, br#"{"exists":false,"value":null}"#), Ok(None));
+    }
+
+    #[test]
+    fn maps_errors() {
+        assert_eq!(parse_public_response(403, b"FORBIDDEN"), Err(HttpError::Encrypted));
+        assert!(matches!(parse_public_response(500, b"oops"), Err(HttpError::Request(e)) if e.contains("500")));
+        assert!(matches!(parse_public_response(200, b"not json"), Err(HttpError::Response(_))));
+        assert!(matches!(
+            parse_public_response(200, br#"{"exists":true,"value":"***"}"#),
+            Err(HttpError::Response(_))
+        ));
+    }
+}
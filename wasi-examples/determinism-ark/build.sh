@@ -0,0 +1,9 @@
+#!/bin/bash
+set -e
+
+echo "Building determinism-ark for wasm32-wasip1..."
+
+cargo build --target wasm32-wasip1 --release
+
+echo "Build complete!"
+echo "WASM file: target/wasm32-wasip1/release/determinism-ark.wasm"
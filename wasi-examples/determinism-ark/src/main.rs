@@ -0,0 +1,30 @@
+//! Determinism Ark - prints where the guest allocator put a few heap values
+//!
+//! Heap addresses are the classic accidental source of non-reproducible output
+//! (pointer formatting, address-keyed maps). OutLayer runs every ark in fresh,
+//! zero-filled linear memory with fixed growth, so the same WASM + input must
+//! print the same addresses on every run and every worker.
+//!
+//! Input (stdin) is mixed into the allocation sequence: a different input may move
+//! the addresses, the same input never does.
+
+use std::io::Read;
+
+fn main() {
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input).expect("failed to read stdin");
+
+    let boxed = Box::new(42u64);
+    let numbers: Vec<u64> = (0..1024).collect();
+    let text = format!("{}{}", input, "x".repeat(4096));
+    // Large enough to make the allocator grow linear memory
+    let big = vec![0u8; 256 * 1024];
+
+    print!(
+        "box={:p} vec={:p} string={:p} big={:p}",
+        boxed,
+        numbers.as_ptr(),
+        text.as_ptr(),
+        big.as_ptr()
+    );
+}
@@ -1,7 +1,7 @@
 //! Payment Keys with Intents - WASI module for swapping tokens to USDC for payment key top-up
 //!
 //! This module:
-//! 1. Reads token price from oracle-ark storage
+//! 1. Reads token price from oracle-ark storage (fails if older than 5 minutes)
 //! 2. Validates minimum value ($0.01 USDC)
 //! 3. Swaps token to USDC via 1Click API
 //! 4. Sends USDC to outlayer.near via ft_transfer_call with payment key nonce in msg
@@ -13,6 +13,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::env;
 use std::io::{self, Read, Write};
+use outlayer::oracle;
 use std::time::Duration;
 use wasi_http_client::Client;
 
@@ -27,6 +28,7 @@ const USDC_CONTRACT: &str = "17208628f84f5d6ad33f0da3bbbeb27ffcb398eac501a31bd6a
 const USDC_DEFUSE_ASSET: &str = "nep141:17208628f84f5d6ad33f0da3bbbeb27ffcb398eac501a31bd6ad2011e36133a1";
 const MIN_USDC_AMOUNT: u128 = 10_000; // $0.01 with 6 decimals
 const ORACLE_PROJECT_UUID: &str = "p0000000000000003";
+const MAX_PRICE_AGE_SECS: u64 = 300; // refuse to swap at a price older than 5 minutes
 
 // Token whitelist embedded at compile time
 const TOKENS_JSON: &str = include_str!("../tokens.json");
@@ -67,30 +69,6 @@ struct TokenConfig {
     defuse_asset_id: String,
 }
 
-// ============================================================================
-// Oracle Price Types (from oracle-ark)
-// ============================================================================
-
-#[derive(Deserialize, Debug)]
-struct StoredPrice {
-    price: f64,
-    timestamp: u64,
-    #[allow(dead_code)]
-    sources: Vec<SourceInfo>,
-    #[allow(dead_code)]
-    aggregation_method: String,
-}
-
-#[derive(Deserialize, Debug)]
-struct SourceInfo {
-    #[allow(dead_code)]
-    name: String,
-    #[allow(dead_code)]
-    price: f64,
-    #[allow(dead_code)]
-    timestamp: Option<u64>,
-}
-
 // ============================================================================
 // 1Click API Types (matching coordinator's backend/mod.rs)
 // ============================================================================
@@ -224,14 +202,20 @@ fn execute_topup(input: &Input, logs: &mut Vec<String>) -> Result<Output, Box<dy
         input.token_id, token_config.oracle_key, token_config.decimals
     ));
 
-    // Step 2: Get token price from oracle storage
-    let token_price = get_token_price(&token_config.oracle_key)?;
-    logs.push(format!("2. Oracle price: ${:.4}", token_price));
+    // Step 2: Get token price from oracle storage (fails if stale)
+    let price = oracle::get_price(ORACLE_PROJECT_UUID, &token_config.oracle_key, MAX_PRICE_AGE_SECS)?;
+    logs.push(format!(
+        "2. Oracle price: ${:.4} ({}s old, {} of {} sources)",
+        price.price,
+        price.age_secs,
+        price.aggregation_method,
+        price.sources.len()
+    ));
 
     // Step 3: Calculate expected USDC and validate minimum
     let amount: u128 = input.amount.parse()?;
     let token_in_decimals = amount as f64 / 10f64.powi(token_config.decimals as i32);
-    let expected_usdc = token_in_decimals * token_price;
+    let expected_usdc = price.value_of(amount, token_config.decimals);
     let expected_usdc_minimal = (expected_usdc * 1_000_000.0) as u128; // 6 decimals for USDC
 
     if expected_usdc_minimal < MIN_USDC_AMOUNT {
@@ -397,36 +381,6 @@ fn execute_topup(input: &Input, logs: &mut Vec<String>) -> Result<Output, Box<dy
     })
 }
 
-// ============================================================================
-// Oracle Functions
-// ============================================================================
-
-fn get_token_price(oracle_key: &str) -> Result<f64, Box<dyn std::error::Error>> {
-    let storage_key = format!("price:{}", oracle_key);
-
-    match outlayer::storage::get_worker_from_project(&storage_key, Some(ORACLE_PROJECT_UUID)) {
-        Ok(Some(data)) => {
-            let stored: StoredPrice = serde_json::from_slice(&data)?;
-
-            // Check freshness (max 5 minutes old)
-            let now = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)?
-                .as_secs();
-
-            if now.saturating_sub(stored.timestamp) > 300 {
-                eprintln!(
-                    "Warning: Price is {} seconds old",
-                    now.saturating_sub(stored.timestamp)
-                );
-            }
-
-            Ok(stored.price)
-        }
-        Ok(None) => Err(format!("Price not found in oracle storage: {}", storage_key).into()),
-        Err(e) => Err(format!("Failed to read oracle storage: {}", e).into()),
-    }
-}
-
 // ============================================================================
 // 1Click API Functions
 // ============================================================================
@@ -152,16 +152,9 @@ pub fn call(
 ) -> Result<String, Box<dyn std::error::Error>> {
     eprintln!("📤 Call: {}.{}", contract_id, method_name);
 
-    send_function_call_transaction(
-        rpc_url,
-        signer_account_id,
-        signer_private_key,
-        contract_id,
-        method_name,
-        args.as_bytes(),
-        gas,
-        deposit,
-    )
+    TxBuilder::new(contract_id)
+        .function_call(method_name, args, gas, deposit)
+        .sign_and_send(rpc_url, signer_account_id, signer_private_key)
 }
 
 // ============================================================================
@@ -212,35 +205,163 @@ pub fn ft_transfer_call(
         "msg": msg
     });
 
-    send_function_call_transaction(
-        rpc_url,
-        signer_account_id,
-        signer_private_key,
-        token_contract,
-        "ft_transfer_call",
-        serde_json::to_vec(&args)?.as_slice(),
-        300_000_000_000_000, // 300 TGas
-        1,                   // 1 yoctoNEAR
-    )
+    TxBuilder::new(token_contract)
+        .function_call(
+            "ft_transfer_call",
+            &args.to_string(),
+            300_000_000_000_000, // 300 TGas
+            1,                   // 1 yoctoNEAR
+        )
+        .sign_and_send(rpc_url, signer_account_id, signer_private_key)
 }
 
-/// Generic function to send any function call transaction
-fn send_function_call_transaction(
-    rpc_url: &str,
-    signer_account_id: &str,
-    signer_private_key: &str,
-    receiver_id: &str,
-    method_name: &str,
-    args: &[u8],
-    gas: u64,
-    deposit: u128,
-) -> Result<String, Box<dyn std::error::Error>> {
-    // Parse private key (remove "ed25519:" prefix if present)
-    let key_str = if signer_private_key.starts_with("ed25519:") {
-        &signer_private_key[8..]
-    } else {
-        signer_private_key
-    };
+// ============================================================================
+// Transaction Builder
+// ============================================================================
+
+/// How long `send_tx` waits before returning (NEAR RPC `wait_until`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitUntil {
+    /// Return right after the transaction is validated
+    None,
+    /// Included in a block
+    Included,
+    /// All receipts executed in non-final blocks (same as `broadcast_tx_commit`)
+    ExecutedOptimistic,
+    /// Included in a final block
+    IncludedFinal,
+    /// All receipts executed in final blocks
+    Executed,
+    /// All receipts executed and their blocks final
+    Final,
+}
+
+impl WaitUntil {
+    fn as_str(self) -> &'static str {
+        match self {
+            WaitUntil::None => "NONE",
+            WaitUntil::Included => "INCLUDED",
+            WaitUntil::ExecutedOptimistic => "EXECUTED_OPTIMISTIC",
+            WaitUntil::IncludedFinal => "INCLUDED_FINAL",
+            WaitUntil::Executed => "EXECUTED",
+            WaitUntil::Final => "FINAL",
+        }
+    }
+
+    /// Whether the RPC response carries the execution outcome to check for failures
+    fn returns_outcome(self) -> bool {
+        matches!(self, WaitUntil::ExecutedOptimistic | WaitUntil::Executed | WaitUntil::Final)
+    }
+}
+
+/// Accumulates actions against one receiver and sends them as a single transaction
+///
+/// All actions share one nonce and one signature, and NEAR applies them in order
+/// in one receipt: if any action fails, none of them takes effect.
+///
+/// ```rust,ignore
+/// let tx_hash = near_tx::TxBuilder::new(INTENTS_CONTRACT)
+///     .function_call("storage_deposit", "{}", 30_000_000_000_000, 1_250_000_000_000_000_000_000)
+///     .function_call("mt_transfer", &args, 100_000_000_000_000, 1)
+///     .wait_until(near_tx::WaitUntil::Final)
+///     .sign_and_send(&rpc_url, signer_id, &private_key)?;
+/// ```
+pub struct TxBuilder {
+    receiver_id: String,
+    actions: Vec<Action>,
+    wait_until: WaitUntil,
+}
+
+impl TxBuilder {
+    /// New transaction to `receiver_id`, waiting for `ExecutedOptimistic` by default
+    pub fn new(receiver_id: &str) -> Self {
+        Self {
+            receiver_id: receiver_id.to_string(),
+            actions: Vec::new(),
+            wait_until: WaitUntil::ExecutedOptimistic,
+        }
+    }
+
+    /// Add a function call on the receiver
+    pub fn function_call(mut self, method_name: &str, args: &str, gas: u64, deposit: u128) -> Self {
+        self.actions.push(Action::FunctionCall(FunctionCallAction {
+            method_name: method_name.to_string(),
+            args: args.as_bytes().to_vec(),
+            gas,
+            deposit,
+        }));
+        self
+    }
+
+    /// Add a NEAR transfer (in yoctoNEAR) to the receiver
+    pub fn transfer(mut self, amount: u128) -> Self {
+        self.actions.push(Action::Transfer(amount));
+        self
+    }
+
+    /// Finality to wait for before returning
+    pub fn wait_until(mut self, wait_until: WaitUntil) -> Self {
+        self.wait_until = wait_until;
+        self
+    }
+
+    /// Fetch the nonce once, sign all actions into one transaction and send it
+    ///
+    /// Returns the transaction hash. Execution failures are reported only when
+    /// `wait_until` waits for execution (`ExecutedOptimistic`, `Executed`, `Final`).
+    pub fn sign_and_send(
+        self,
+        rpc_url: &str,
+        signer_account_id: &str,
+        signer_private_key: &str,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        if self.actions.is_empty() {
+            return Err("Transaction has no actions".into());
+        }
+
+        let signing_key = parse_signing_key(signer_private_key)?;
+        let verifying_key = signing_key.verifying_key();
+
+        // Get nonce and block hash from RPC
+        let (nonce, block_hash) = get_access_key_info(rpc_url, signer_account_id, &verifying_key)?;
+
+        eprintln!(
+            "📝 Nonce: {}, Block hash: {}, actions: {}",
+            nonce,
+            hex::encode(&block_hash),
+            self.actions.len()
+        );
+
+        let transaction = Transaction {
+            signer_id: signer_account_id.to_string(),
+            public_key: PublicKey::ED25519(verifying_key.to_bytes()),
+            nonce: nonce + 1,
+            receiver_id: self.receiver_id,
+            block_hash,
+            actions: self.actions,
+        };
+
+        // Serialize and hash transaction
+        let tx_bytes = borsh::to_vec(&transaction)?;
+        let mut hasher = Sha256::new();
+        hasher.update(&tx_bytes);
+        let tx_hash = hasher.finalize();
+
+        // Sign transaction
+        let signature = signing_key.sign(&tx_hash);
+
+        let signed_tx = SignedTransaction {
+            transaction,
+            signature: Signature::ED25519(signature.to_bytes()),
+        };
+
+        send_transaction(rpc_url, &signed_tx, &bs58::encode(tx_hash).into_string(), self.wait_until)
+    }
+}
+
+/// Parse a NEAR ed25519 private key (`ed25519:` prefix optional)
+fn parse_signing_key(signer_private_key: &str) -> Result<SigningKey, Box<dyn std::error::Error>> {
+    let key_str = signer_private_key.strip_prefix("ed25519:").unwrap_or(signer_private_key);
 
     let key_bytes = bs58::decode(key_str)
         .into_vec()
@@ -254,45 +375,7 @@ fn send_function_call_transaction(
 
     let mut seed = [0u8; 32];
     seed.copy_from_slice(&key_bytes[..32]);
-    let signing_key = SigningKey::from_bytes(&seed);
-    let verifying_key = signing_key.verifying_key();
-
-    // Get nonce and block hash from RPC
-    let (nonce, block_hash) = get_access_key_info(rpc_url, signer_account_id, &verifying_key)?;
-
-    eprintln!("📝 Nonce: {}, Block hash: {}", nonce, hex::encode(&block_hash));
-
-    // Build transaction
-    let transaction = Transaction {
-        signer_id: signer_account_id.to_string(),
-        public_key: PublicKey::ED25519(verifying_key.to_bytes()),
-        nonce: nonce + 1,
-        receiver_id: receiver_id.to_string(),
-        block_hash,
-        actions: vec![Action::FunctionCall(FunctionCallAction {
-            method_name: method_name.to_string(),
-            args: args.to_vec(),
-            gas,
-            deposit,
-        })],
-    };
-
-    // Serialize and hash transaction
-    let tx_bytes = borsh::to_vec(&transaction)?;
-    let mut hasher = Sha256::new();
-    hasher.update(&tx_bytes);
-    let tx_hash = hasher.finalize();
-
-    // Sign transaction
-    let signature = signing_key.sign(&tx_hash);
-
-    let signed_tx = SignedTransaction {
-        transaction,
-        signature: Signature::ED25519(signature.to_bytes()),
-    };
-
-    // Send transaction via RPC
-    send_transaction(rpc_url, &signed_tx)
+    Ok(SigningKey::from_bytes(&seed))
 }
 
 // ============================================================================
@@ -504,6 +587,8 @@ fn get_access_key_info(
 fn send_transaction(
     rpc_url: &str,
     signed_tx: &SignedTransaction,
+    tx_hash: &str,
+    wait_until: WaitUntil,
 ) -> Result<String, Box<dyn std::error::Error>> {
     // Serialize transaction with borsh
     let tx_bytes = borsh::to_vec(signed_tx)?;
@@ -512,8 +597,11 @@ fn send_transaction(
     let request = JsonRpcRequest {
         jsonrpc: "2.0".to_string(),
         id: "dontcare".to_string(),
-        method: "broadcast_tx_commit".to_string(),
-        params: vec![tx_base64],
+        method: "send_tx".to_string(),
+        params: serde_json::json!({
+            "signed_tx_base64": tx_base64,
+            "wait_until": wait_until.as_str(),
+        }),
     };
 
     eprintln!("📡 Sending transaction to NEAR RPC...");
@@ -546,15 +634,12 @@ fn send_transaction(
 
     let result = json_response.result.ok_or("No result in RPC response")?;
 
-    // Extract transaction hash first (for logging)
-    let tx_hash = result
-        .get("transaction")
-        .and_then(|tx| tx.get("hash"))
-        .and_then(|h| h.as_str())
-        .ok_or("No transaction hash in response")?
-        .to_string();
+    eprintln!("📋 Transaction broadcast: {} (wait_until={})", tx_hash, wait_until.as_str());
 
-    eprintln!("📋 Transaction broadcast: {}", tx_hash);
+    // Below execution finality the response has no outcome to check
+    if !wait_until.returns_outcome() {
+        return Ok(tx_hash.to_string());
+    }
 
     // Parse the full execution outcome to check for failures
     let outcome: FinalExecutionOutcomeView = serde_json::from_value(result.clone())
@@ -607,7 +692,7 @@ fn send_transaction(
 
     eprintln!("✅ Transaction successful: {}", tx_hash);
 
-    Ok(tx_hash)
+    Ok(tx_hash.to_string())
 }
 
 /// Format TxExecutionError for user-friendly error messages
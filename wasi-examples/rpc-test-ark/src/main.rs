@@ -56,6 +56,9 @@ fn main() {
         test_view_account("outlayer.testnet", ""),  // default = final
         test_view_account_at_block("outlayer.testnet", "optimistic"),
         test_view("wrap.testnet", "ft_metadata", "{}", ""),
+        test_batch_view(),
+        test_ft_balance_of("wrap.testnet", "outlayer.testnet", ""),
+        test_ft_metadata("wrap.testnet", ""),
         test_view_access_key("outlayer.testnet", "ed25519:2nVT8TeatXPpcj6BuZCEJ8UmoEx7kKLJdC4fVnKc4MU9", ""),
         test_view_access_key_list("outlayer.testnet", ""),
         test_view_code("outlayer.testnet", ""),
@@ -295,6 +298,106 @@ fn test_view(contract_id: &str, method_name: &str, args_json: &str, finality: &s
     }
 }
 
+fn test_batch_view() -> TestResult {
+    eprintln!("Testing batch_view: 3 calls");
+
+    let call = |contract_id: &str, method_name: &str, args_json: &str| near::rpc::api::ViewCall {
+        contract_id: contract_id.to_string(),
+        method_name: method_name.to_string(),
+        args_json: args_json.to_string(),
+        finality: String::new(),
+    };
+    let results = near::rpc::api::batch_view(&[
+        call("wrap.testnet", "ft_metadata", "{}"),
+        call("wrap.testnet", "ft_total_supply", "{}"),
+        call("wrap.testnet", "ft_balance_of", r#"{"account_id":"outlayer.testnet"}"#),
+    ]);
+
+    let name = "batch_view(wrap.testnet x3)".to_string();
+    if results.len() != 3 {
+        return TestResult {
+            name,
+            success: false,
+            result: None,
+            error: Some(format!("expected 3 results, got {}", results.len())),
+        };
+    }
+    if let Some(error) = results.iter().find_map(|r| r.error.clone()) {
+        return TestResult {
+            name,
+            success: false,
+            result: None,
+            error: Some(error),
+        };
+    }
+
+    let parsed: Vec<serde_json::Value> = results
+        .iter()
+        .map(|r| serde_json::from_str(&r.result).unwrap_or_else(|_| serde_json::json!(r.result)))
+        .collect();
+
+    TestResult {
+        name,
+        success: true,
+        result: Some(serde_json::json!(parsed)),
+        error: None,
+    }
+}
+
+fn test_ft_balance_of(token_id: &str, account_id: &str, finality: &str) -> TestResult {
+    eprintln!("Testing ft_balance_of: {} on {}", account_id, token_id);
+
+    let (balance, error) = near::rpc::api::ft_balance_of(token_id, account_id, finality);
+    let name = format!("ft_balance_of({}, {})", token_id, account_id);
+
+    if !error.is_empty() {
+        return TestResult {
+            name,
+            success: false,
+            result: None,
+            error: Some(error),
+        };
+    }
+
+    TestResult {
+        name,
+        success: balance.parse::<u128>().is_ok(),
+        result: Some(serde_json::json!(balance)),
+        error: None,
+    }
+}
+
+fn test_ft_metadata(token_id: &str, finality: &str) -> TestResult {
+    eprintln!("Testing ft_metadata: {}", token_id);
+
+    let (metadata, error) = near::rpc::api::ft_metadata(token_id, finality);
+    let name = format!("ft_metadata({})", token_id);
+
+    if !error.is_empty() {
+        return TestResult {
+            name,
+            success: false,
+            result: None,
+            error: Some(error),
+        };
+    }
+
+    match serde_json::from_str::<serde_json::Value>(&metadata) {
+        Ok(parsed) => TestResult {
+            name,
+            success: parsed["decimals"].is_u64(),
+            result: Some(parsed),
+            error: None,
+        },
+        Err(e) => TestResult {
+            name,
+            success: false,
+            result: None,
+            error: Some(format!("invalid metadata JSON: {}", e)),
+        },
+    }
+}
+
 fn test_view_access_key(account_id: &str, public_key: &str, finality: &str) -> TestResult {
     eprintln!("Testing view_access_key: {} key={}", account_id, public_key);
 
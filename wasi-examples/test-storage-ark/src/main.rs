@@ -9,10 +9,16 @@
 //! - OUTLAYER_PROJECT_NAME = "name"
 
 use outlayer::{storage, env};
+use outlayer::storage::Visibility;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
-use wasi_http_client::Client;
-use base64::{Engine, engine::general_purpose::STANDARD};
+
+outlayer::metadata! {
+    project: "zavodil.testnet/test-storage",
+    version: "0.1.0",
+    description: "Test WASM for OutLayer persistent storage host functions",
+    tags: ["test", "storage"],
+    requires: [storage],
+}
 
 #[derive(Debug, Deserialize)]
 struct Input {
@@ -434,39 +440,10 @@ fn cmd_verify_public_http(key: &str, coordinator_url: &str) -> Output {
 
 /// HTTP request to coordinator's public storage endpoint
 fn verify_public_storage_http(key: &str, coordinator_url: &str, project_uuid: &str) -> Result<Option<String>, String> {
-    let url = format!(
-        "{}/public/storage/get?project_uuid={}&key={}",
-        coordinator_url.trim_end_matches('/'),
-        project_uuid,
-        key
-    );
-
-    let response = Client::new()
-        .get(&url)
-        .connect_timeout(Duration::from_secs(10))
-        .send()
-        .map_err(|e| format!("HTTP request failed: {}", e))?;
-
-    let status = response.status();
-    if status == 403 {
-        return Err("Storage key exists but is encrypted (not public)".to_string());
-    }
-    if status < 200 || status >= 300 {
-        return Err(format!("HTTP {}", status));
-    }
-
-    let body = response.body().map_err(|e| format!("Failed to read response: {}", e))?;
-    let json: serde_json::Value = serde_json::from_slice(&body)
-        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
-
-    let exists = json.get("exists").and_then(|v| v.as_bool()).unwrap_or(false);
-    if !exists {
+    let Some(value_bytes) = storage::http::read_public(coordinator_url, project_uuid, key).map_err(|e| e.to_string())?
+    else {
         return Ok(None);
-    }
-
-    let value_b64 = json.get("value").and_then(|v| v.as_str()).unwrap_or("");
-    let value_bytes = STANDARD.decode(value_b64)
-        .map_err(|e| format!("Failed to decode base64: {}", e))?;
+    };
     let value = String::from_utf8(value_bytes)
         .map_err(|e| format!("Invalid UTF-8: {}", e))?;
 
@@ -724,6 +701,41 @@ fn cmd_test_all() -> Output {
         results.push(test);
     }
 
+    // Test 28: Embedded metadata matches the metadata! invocation
+    let test = test_metadata();
+    if test.success { passed += 1; } else { failed += 1; }
+    results.push(test);
+
+    // Test 29: Batch write, then batch read (order preserved, None for missing)
+    let test = test_set_get_many();
+    if test.success { passed += 1; } else { failed += 1; }
+    results.push(test);
+
+    // Test 30: Batch larger than MAX_BATCH_SIZE is rejected with TooLarge
+    let test = test_batch_too_large();
+    if test.success { passed += 1; } else { failed += 1; }
+    results.push(test);
+
+    // Test 31: TTL write reports remaining lifetime; zero TTL is rejected
+    let test = test_set_with_ttl();
+    if test.success { passed += 1; } else { failed += 1; }
+    results.push(test);
+
+    // Test 32: Expired entry reads as missing and is not listed
+    let test = test_ttl_expiry();
+    if test.success { passed += 1; } else { failed += 1; }
+    results.push(test);
+
+    // Test 33: Snapshot, mutate, restore reverts the prefix exactly
+    let test = test_snapshot_restore();
+    if test.success { passed += 1; } else { failed += 1; }
+    results.push(test);
+
+    // Test 34: Appends accumulate, read_range tails, oversized append is rejected
+    let test = test_append_read_range();
+    if test.success { passed += 1; } else { failed += 1; }
+    results.push(test);
+
     // Final: Clear all and verify
     let test = test_clear_all_and_verify();
     if test.success { passed += 1; } else { failed += 1; }
@@ -752,6 +764,207 @@ fn cmd_test_all() -> Output {
 
 // ==================== Test Functions ====================
 
+fn test_set_get_many() -> TestResult {
+    let name = "set_many + get_many".to_string();
+    let pairs: [(&str, &[u8]); 3] = [
+        ("batch:BTC", b"97000"),
+        ("batch:ETH", b"3400"),
+        ("batch:NEAR", b"5"),
+    ];
+    if let Err(e) = storage::set_many(&pairs) {
+        return TestResult { name, success: false, error: Some(e.to_string()) };
+    }
+
+    match storage::get_many(&["batch:NEAR", "batch:missing", "batch:BTC"]) {
+        Ok(values) => {
+            let expected = vec![Some(b"5".to_vec()), None, Some(b"97000".to_vec())];
+            if values == expected {
+                TestResult { name, success: true, error: None }
+            } else {
+                TestResult {
+                    name,
+                    success: false,
+                    error: Some(format!("Expected {:?}, got {:?}", expected, values)),
+                }
+            }
+        }
+        Err(e) => TestResult { name, success: false, error: Some(e.to_string()) },
+    }
+}
+
+fn test_batch_too_large() -> TestResult {
+    let name = "get_many over MAX_BATCH_SIZE".to_string();
+    let keys: Vec<String> = (0..=storage::MAX_BATCH_SIZE).map(|i| format!("batch:{}", i)).collect();
+    let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+
+    match storage::get_many(&keys) {
+        Err(storage::BatchError::TooLarge { size, max }) if size == keys.len() && max == storage::MAX_BATCH_SIZE => {
+            TestResult { name, success: true, error: None }
+        }
+        other => TestResult {
+            name,
+            success: false,
+            error: Some(format!("Expected TooLarge error, got {:?}", other)),
+        },
+    }
+}
+
+fn test_set_with_ttl() -> TestResult {
+    let name = "set_with_ttl + ttl".to_string();
+    if storage::set_with_ttl("ttl:zero", b"v", 0).is_ok() {
+        return TestResult { name, success: false, error: Some("ttl_seconds=0 was accepted".to_string()) };
+    }
+    if let Err(e) = storage::set_with_ttl("ttl:long", b"v", 600) {
+        return TestResult { name, success: false, error: Some(e.to_string()) };
+    }
+    if let Err(e) = storage::set("ttl:none", b"v") {
+        return TestResult { name, success: false, error: Some(e.to_string()) };
+    }
+
+    match (storage::ttl("ttl:long"), storage::ttl("ttl:none"), storage::ttl("ttl:missing")) {
+        (Ok(Some(left)), Ok(None), Ok(None)) if left > 0 && left <= 600 => {
+            TestResult { name, success: true, error: None }
+        }
+        other => TestResult {
+            name,
+            success: false,
+            error: Some(format!("Expected (Some(1..=600), None, None), got {:?}", other)),
+        },
+    }
+}
+
+fn test_ttl_expiry() -> TestResult {
+    let name = "expired entry is gone".to_string();
+    if let Err(e) = storage::set_worker_with_ttl("ttl:short", b"v", 1) {
+        return TestResult { name, success: false, error: Some(e.to_string()) };
+    }
+    if let Err(e) = storage::set_with_ttl("ttl:short", b"v", 1) {
+        return TestResult { name, success: false, error: Some(e.to_string()) };
+    }
+    std::thread::sleep(std::time::Duration::from_secs(2));
+
+    let listed = match storage::list_keys("ttl:") {
+        Ok(keys) => keys.contains(&"ttl:short".to_string()),
+        Err(e) => return TestResult { name, success: false, error: Some(e.to_string()) },
+    };
+    match (storage::get("ttl:short"), storage::get_worker("ttl:short"), storage::ttl("ttl:short")) {
+        (Ok(None), Ok(None), Ok(None)) if !listed => TestResult { name, success: true, error: None },
+        other => TestResult {
+            name,
+            success: false,
+            error: Some(format!("Expected expired entry to be gone (listed: {}), got {:?}", listed, other)),
+        },
+    }
+}
+
+fn test_snapshot_restore() -> TestResult {
+    let name = "snapshot + restore".to_string();
+    let setup = storage::set("snap:a", b"1")
+        .and_then(|_| storage::set("snap:b", b"2"))
+        .and_then(|_| storage::set("nosnap:x", b"outside"));
+    if let Err(e) = setup {
+        return TestResult { name, success: false, error: Some(e.to_string()) };
+    }
+
+    let checkpoint = match storage::snapshot("snap:") {
+        Ok(id) => id,
+        Err(e) => return TestResult { name, success: false, error: Some(e.to_string()) },
+    };
+
+    let mutate = storage::set("snap:a", b"changed")
+        .and_then(|_| storage::set("snap:c", b"added"))
+        .and_then(|_| storage::set("nosnap:x", b"changed outside"));
+    storage::delete("snap:b");
+    if let Err(e) = mutate.and_then(|_| storage::restore(&checkpoint)) {
+        return TestResult { name, success: false, error: Some(e.to_string()) };
+    }
+
+    let mut keys = match storage::list_keys("snap:") {
+        Ok(keys) => keys,
+        Err(e) => return TestResult { name, success: false, error: Some(e.to_string()) },
+    };
+    keys.sort();
+    let state = (
+        keys,
+        storage::get("snap:a"),
+        storage::get("snap:b"),
+        storage::get("nosnap:x"),
+    );
+    match &state {
+        (keys, Ok(Some(a)), Ok(Some(b)), Ok(Some(x)))
+            if keys == &["snap:a", "snap:b"] && a == b"1" && b == b"2" && x == b"changed outside" =>
+        {
+            TestResult { name, success: true, error: None }
+        }
+        _ => TestResult {
+            name,
+            success: false,
+            error: Some(format!("State not reverted: {:?}", state)),
+        },
+    }
+}
+
+fn test_append_read_range() -> TestResult {
+    let name = "append + read_range".to_string();
+    let appends = storage::append("log:events", b"a,")
+        .and_then(|_| storage::append("log:events", b"b,"))
+        .and_then(|_| storage::append("log:events", b"c"));
+    let len = match appends {
+        Ok(len) => len,
+        Err(e) => return TestResult { name, success: false, error: Some(e.to_string()) },
+    };
+
+    let oversized = vec![0u8; storage::MAX_APPEND_VALUE_SIZE];
+    if storage::append("log:events", &oversized).is_ok() {
+        return TestResult { name, success: false, error: Some("append past MAX_APPEND_VALUE_SIZE was accepted".to_string()) };
+    }
+
+    let state = (
+        len,
+        storage::get("log:events"),
+        storage::read_range("log:events", 2, 2),
+        storage::read_range("log:events", 4, 100),
+        storage::read_range("log:missing", 0, 10),
+    );
+    match &state {
+        (5, Ok(Some(all)), Ok(middle), Ok(tail), Ok(missing))
+            if all == b"a,b,c" && middle == b"b," && tail == b"c" && missing.is_empty() =>
+        {
+            TestResult { name, success: true, error: None }
+        }
+        _ => TestResult {
+            name,
+            success: false,
+            error: Some(format!("Unexpected log state: {:?}", state)),
+        },
+    }
+}
+
+fn test_metadata() -> TestResult {
+    let meta = env::metadata();
+    let expected = outlayer::ArkMetadata {
+        project: "test-storage-ark".to_string(),
+        version: "0.1.0".to_string(),
+        description: Some("Test WASM for OutLayer persistent storage host functions".to_string()),
+        tags: vec!["test".to_string(), "storage".to_string()],
+        allowed_hosts: None,
+        requires: vec![outlayer::metadata::Capability::Storage],
+    };
+    if meta == expected {
+        TestResult {
+            name: "metadata()".to_string(),
+            success: true,
+            error: None,
+        }
+    } else {
+        TestResult {
+            name: "metadata()".to_string(),
+            success: false,
+            error: Some(format!("Expected {:?}, got {:?}", expected, meta)),
+        }
+    }
+}
+
 fn test_set(key: &str, value: &str) -> TestResult {
     match storage::set(key, value.as_bytes()) {
         Ok(()) => TestResult {
@@ -1095,6 +1308,11 @@ fn cmd_test_public_storage(coordinator_url: &str) -> Output {
         results.push(test);
     }
 
+    // Test 11: Visibility readback and flip
+    let test = test_visibility();
+    if test.success { passed += 1; } else { failed += 1; }
+    results.push(test);
+
     // HTTP verification tests (if coordinator_url provided)
     if !coordinator_url.is_empty() && !project_uuid.is_empty() {
         // Test HTTP: Verify public data via coordinator API
@@ -1139,6 +1357,45 @@ fn cmd_test_public_storage(coordinator_url: &str) -> Output {
     }
 }
 
+fn test_visibility() -> TestResult {
+    let name = "visibility + set_visibility".to_string();
+    let readback = (
+        storage::visibility("public-key-1"),
+        storage::visibility("private-key"),
+        storage::visibility("non-existent-public-key"),
+    );
+    if !matches!(
+        readback,
+        (Ok(Some(Visibility::Public)), Ok(Some(Visibility::Private)), Ok(None))
+    ) {
+        return TestResult {
+            name,
+            success: false,
+            error: Some(format!("Expected (Public, Private, None), got {:?}", readback)),
+        };
+    }
+
+    if let Err(e) = storage::set_worker("vis:flip", b"kept") {
+        return TestResult { name, success: false, error: Some(e.to_string()) };
+    }
+    let flipped = storage::set_visibility("vis:flip", Visibility::Public)
+        .and_then(|_| storage::visibility("vis:flip"));
+    let value = storage::get_worker("vis:flip");
+    let missing = storage::set_visibility("vis:missing", Visibility::Public);
+    let _ = storage::set_visibility("vis:flip", Visibility::Private);
+
+    match (flipped, value, missing) {
+        (Ok(Some(Visibility::Public)), Ok(Some(v)), Ok(false)) if v == b"kept" => {
+            TestResult { name, success: true, error: None }
+        }
+        other => TestResult {
+            name,
+            success: false,
+            error: Some(format!("Expected (Some(Public), Some(\"kept\"), false), got {:?}", other)),
+        },
+    }
+}
+
 fn test_set_public(key: &str, value: &str) -> TestResult {
     match storage::set_worker_with_options(key, value.as_bytes(), Some(false)) {
         Ok(()) => TestResult {
@@ -30,6 +30,9 @@ wasmtime::component::bindgen!({
     world: "rpc-host",
 });
 
+/// Maximum view calls of one `batch-view` in flight at once
+const MAX_BATCH_VIEW_CONCURRENCY: usize = 8;
+
 /// RPC Proxy client with rate limiting (using blocking HTTP)
 pub struct RpcProxy {
     /// HTTP client for RPC requests (blocking)
@@ -146,6 +149,113 @@ impl RpcProxy {
         Ok(body)
     }
 
+    /// Call a view function and return its decoded result
+    ///
+    /// The result bytes are returned as UTF-8 text; a response without a result array
+    /// (e.g. an RPC error object) is returned as its JSON.
+    pub fn view_function(
+        &self,
+        contract_id: &str,
+        method_name: &str,
+        args_json: &str,
+        finality_or_block: &str,
+    ) -> Result<String> {
+        let result = self.call_function_query(contract_id, method_name, args_json, finality_or_block)?;
+        if let Some(arr) = result.get("result").and_then(|r| r.get("result")).and_then(|r| r.as_array()) {
+            let bytes: Vec<u8> = arr
+                .iter()
+                .filter_map(|v| v.as_u64().map(|n| n as u8))
+                .collect();
+            return Ok(String::from_utf8_lossy(&bytes).to_string());
+        }
+        Ok(serde_json::to_string(&result).unwrap_or_default())
+    }
+
+    /// Call a view function and parse its result as JSON
+    ///
+    /// Unlike [`Self::view_function`], RPC errors and contract panics are returned as
+    /// errors instead of as the response JSON.
+    pub fn view_function_json(
+        &self,
+        contract_id: &str,
+        method_name: &str,
+        args: &Value,
+        finality_or_block: &str,
+    ) -> Result<Value> {
+        let response = self.call_function_query(contract_id, method_name, &args.to_string(), finality_or_block)?;
+        let bytes = view_result_bytes(&response).with_context(|| format!("{}.{} failed", contract_id, method_name))?;
+        serde_json::from_slice(&bytes).with_context(|| format!("{}.{} returned invalid JSON", contract_id, method_name))
+    }
+
+    /// NEP-141 balance of `account_id` on `token_id`, as a decimal string in minimal units
+    pub fn ft_balance_of(&self, token_id: &str, account_id: &str, finality_or_block: &str) -> Result<String> {
+        let args = json!({ "account_id": account_id });
+        match self.view_function_json(token_id, "ft_balance_of", &args, finality_or_block)? {
+            Value::String(balance) if balance.parse::<u128>().is_ok() => Ok(balance),
+            other => anyhow::bail!("{}.ft_balance_of returned {}, expected a U128 string", token_id, other),
+        }
+    }
+
+    /// NEP-148 metadata of `token_id`
+    pub fn ft_metadata(&self, token_id: &str, finality_or_block: &str) -> Result<Value> {
+        match self.view_function_json(token_id, "ft_metadata", &json!({}), finality_or_block)? {
+            metadata @ Value::Object(_) => Ok(metadata),
+            other => anyhow::bail!("{}.ft_metadata returned {}, expected an object", token_id, other),
+        }
+    }
+
+    /// `query` of a `call_function` request
+    fn call_function_query(
+        &self,
+        contract_id: &str,
+        method_name: &str,
+        args_json: &str,
+        finality_or_block: &str,
+    ) -> Result<Value> {
+        let args_base64 = base64::engine::general_purpose::STANDARD.encode(args_json.as_bytes());
+        let (finality, block_id) = parse_finality_or_block(finality_or_block);
+
+        let mut params = json!({
+            "request_type": "call_function",
+            "account_id": contract_id,
+            "method_name": method_name,
+            "args_base64": args_base64,
+        });
+        if let Some(fin) = finality {
+            params["finality"] = json!(fin);
+        } else if let Some(bid) = block_id {
+            params["block_id"] = bid;
+        }
+
+        self.call_method("query", params)
+    }
+
+    /// Run several view calls concurrently, returning results in input order
+    ///
+    /// At most `MAX_BATCH_VIEW_CONCURRENCY` requests are in flight at once. Every call
+    /// counts against the rate limit, so calls past the limit fail individually.
+    pub fn view_functions(&self, calls: &[near::rpc::api::ViewCall]) -> Vec<Result<String>> {
+        let mut results = Vec::with_capacity(calls.len());
+        for chunk in calls.chunks(MAX_BATCH_VIEW_CONCURRENCY) {
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = chunk
+                    .iter()
+                    .map(|call| {
+                        scope.spawn(move || {
+                            self.view_function(&call.contract_id, &call.method_name, &call.args_json, &call.finality)
+                        })
+                    })
+                    .collect();
+                results.extend(handles.into_iter().map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|_| Err(anyhow::anyhow!("view call panicked")))
+                }));
+            });
+        }
+        results
+    }
+
     pub fn view_account(&self, account_id: &str) -> Result<Value> {
         let params = json!({
             "request_type": "view_account",
@@ -381,43 +491,55 @@ fn parse_finality_or_block(s: &str) -> (Option<&str>, Option<Value>) {
     }
 }
 
+/// Return bytes of a `call_function` query response
+///
+/// Handles both shapes of a failed call: a JSON-RPC `error` (current nodes) and a
+/// `result.error` string (older nodes).
+fn view_result_bytes(response: &Value) -> Result<Vec<u8>> {
+    if let Some(error) = response.get("error") {
+        let cause = error["cause"]["info"]["error_message"]
+            .as_str()
+            .or_else(|| error["data"].as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| error.to_string());
+        anyhow::bail!("{}", cause);
+    }
+    let result = &response["result"];
+    if let Some(error) = result.get("error").and_then(Value::as_str) {
+        anyhow::bail!("{}", error);
+    }
+    let bytes = result["result"]
+        .as_array()
+        .context("response has no result bytes")?
+        .iter()
+        .map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()))
+        .collect::<Option<Vec<u8>>>()
+        .context("result bytes out of range")?;
+    Ok(bytes)
+}
+
 /// Implement the generated Host trait for RpcHostState
 impl near::rpc::api::Host for RpcHostState {
     // ==================== Query Methods ====================
 
     fn view(&mut self, contract_id: String, method_name: String, args_json: String, finality_or_block: String) -> (String, String) {
-        let args_base64 = base64::engine::general_purpose::STANDARD.encode(args_json.as_bytes());
-        let (finality, block_id) = parse_finality_or_block(&finality_or_block);
-
-        let mut params = json!({
-            "request_type": "call_function",
-            "account_id": contract_id,
-            "method_name": method_name,
-            "args_base64": args_base64,
-        });
-        if let Some(fin) = finality {
-            params["finality"] = json!(fin);
-        } else if let Some(bid) = block_id {
-            params["block_id"] = bid;
-        }
-
-        match self.proxy.call_method("query", params) {
-            Ok(result) => {
-                if let Some(result_array) = result.get("result").and_then(|r| r.get("result")) {
-                    if let Some(arr) = result_array.as_array() {
-                        let bytes: Vec<u8> = arr
-                            .iter()
-                            .filter_map(|v| v.as_u64().map(|n| n as u8))
-                            .collect();
-                        return (String::from_utf8_lossy(&bytes).to_string(), String::new());
-                    }
-                }
-                (serde_json::to_string(&result).unwrap_or_default(), String::new())
-            }
+        match self.proxy.view_function(&contract_id, &method_name, &args_json, &finality_or_block) {
+            Ok(result) => (result, String::new()),
             Err(e) => (String::new(), e.to_string()),
         }
     }
 
+    fn batch_view(&mut self, calls: Vec<near::rpc::api::ViewCall>) -> Vec<near::rpc::api::ViewResult> {
+        self.proxy
+            .view_functions(&calls)
+            .into_iter()
+            .map(|result| match result {
+                Ok(result) => near::rpc::api::ViewResult { result, error: None },
+                Err(e) => near::rpc::api::ViewResult { result: String::new(), error: Some(e.to_string()) },
+            })
+            .collect()
+    }
+
     fn view_account(&mut self, account_id: String, finality_or_block: String) -> (String, String) {
         let (finality, block_id) = parse_finality_or_block(&finality_or_block);
 
@@ -515,6 +637,22 @@ impl near::rpc::api::Host for RpcHostState {
         }
     }
 
+    // ==================== Token Methods (NEP-141) ====================
+
+    fn ft_balance_of(&mut self, token_id: String, account_id: String, finality_or_block: String) -> (String, String) {
+        match self.proxy.ft_balance_of(&token_id, &account_id, &finality_or_block) {
+            Ok(balance) => (balance, String::new()),
+            Err(e) => (String::new(), format!("{:#}", e)),
+        }
+    }
+
+    fn ft_metadata(&mut self, token_id: String, finality_or_block: String) -> (String, String) {
+        match self.proxy.ft_metadata(&token_id, &finality_or_block) {
+            Ok(metadata) => (metadata.to_string(), String::new()),
+            Err(e) => (String::new(), format!("{:#}", e)),
+        }
+    }
+
     // ==================== Block Methods ====================
 
     fn block(&mut self, finality_or_block: String) -> (String, String) {
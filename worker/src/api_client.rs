@@ -1,8 +1,49 @@
 use anyhow::{Context, Result};
+use bytes::Bytes;
+use futures_util::Stream;
 use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
+use crate::wasm_compression::{self, StreamDecoder, WASM_SHA256_HEADER, ZSTD_ENCODING};
+
+/// Live worker state reported in each heartbeat
+///
+/// The coordinator uses this for routing: skip workers without the needed
+/// capability, busy workers, and workers whose keystore is unreachable
+/// (secrets jobs) or whose operator key is running out of allowance.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct HeartbeatStatus {
+    /// Enabled capabilities ("compilation", "execution")
+    pub capabilities: Vec<String>,
+    /// Job currently being processed
+    pub current_job_id: Option<i64>,
+    /// Claimed jobs not finished yet
+    pub in_flight_count: u64,
+    /// Keystore answered its last health probe (false if no keystore is configured or not probed yet)
+    pub keystore_healthy: bool,
+    /// Remaining gas allowance of the operator access key in yoctoNEAR at the last probe (None = full access or unknown)
+    pub allowance_remaining: Option<String>,
+    /// RPC result cache counters (None = cache disabled)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rpc_cache: Option<crate::outlayer_rpc::RpcCacheStats>,
+}
+
+/// Heartbeat payload sent to `POST /workers/heartbeat`
+#[derive(Serialize)]
+struct HeartbeatRequest {
+    worker_id: String,
+    worker_name: String,
+    status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    event_monitor_block_height: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_poll_at: Option<u64>,
+    #[serde(flatten)]
+    live: HeartbeatStatus,
+}
+
 /// Job status for error classification
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -13,18 +54,26 @@ pub enum JobStatus {
     Failed,
     CompilationFailed,
     ExecutionFailed,
+    /// Execution exceeded `max_execution_seconds` (sent only with REPORT_TIMEOUT_STATUS)
+    Timeout,
     AccessDenied,
     InsufficientPayment,
     Custom,
 }
 
 /// Response format for execution output
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum ResponseFormat {
     Bytes,
     #[default]
     Text,
     Json,
+    /// Stdout must be a single well-formed CBOR item, submitted as raw bytes
+    Cbor,
+    /// Stdout must be a single well-formed MessagePack object, submitted as raw bytes
+    MessagePack,
+    /// Stdout submitted base64-encoded as a string
+    Base64,
 }
 
 /// Execution context metadata passed to WASM via environment variables
@@ -122,6 +171,12 @@ pub struct ExecutionRequest {
     /// Wallet ID for wallet-enabled executions (e.g. "ed25519:abc..." from X-Wallet-Id header)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub wallet_id: Option<String>,
+    /// Queue tier the request was created with (0 = default)
+    #[serde(default)]
+    pub priority: u8,
+    /// Media type of input_data declared by the caller (RequestParams.input_content_type)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_content_type: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -131,6 +186,12 @@ pub enum CodeSource {
         repo: String,
         commit: String,
         build_target: String,
+        /// Workspace member to build (`cargo build -p`)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        package: Option<String>,
+        /// Cargo features to enable (`cargo build --features`)
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        features: Vec<String>,
     },
     /// Pre-compiled WASM file accessible via URL
     /// Worker downloads from URL, verifies SHA256 hash, then executes without compilation
@@ -146,6 +207,9 @@ pub enum CodeSource {
 pub struct SecretsReference {
     pub profile: String,
     pub account_id: String,
+    /// Pinned secret version (None = current)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
 }
 
 impl SecretsReference {
@@ -264,11 +328,31 @@ impl CodeSource {
     }
 }
 
+/// Output cap applied when the request doesn't carry `max_output_bytes` (matches the contract)
+pub const DEFAULT_MAX_OUTPUT_BYTES: u32 = 128 * 1024;
+
+pub fn default_max_output_bytes() -> u32 {
+    DEFAULT_MAX_OUTPUT_BYTES
+}
+
+/// Stderr kept per execution when the request doesn't carry `max_log_bytes`
+pub const DEFAULT_MAX_LOG_BYTES: u32 = 64 * 1024;
+
+pub fn default_max_log_bytes() -> u32 {
+    DEFAULT_MAX_LOG_BYTES
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResourceLimits {
     pub max_instructions: u64,
     pub max_memory_mb: u32,
     pub max_execution_seconds: u64,
+    /// Maximum stdout size; larger outputs fail the execution
+    #[serde(default = "default_max_output_bytes")]
+    pub max_output_bytes: u32,
+    /// Maximum stderr kept as logs; older output is dropped, the execution never fails
+    #[serde(default = "default_max_log_bytes")]
+    pub max_log_bytes: u32,
 }
 
 /// Parameters for creating a new task in coordinator
@@ -293,14 +377,55 @@ pub struct CreateTaskParams {
     pub project_uuid: Option<String>,
     /// Project ID for project-based secrets (e.g., "alice.near/my-app")
     pub project_id: Option<String>,
+    /// Queue tier paid for on-chain (RequestParams.priority)
+    pub priority: u8,
+    /// Media type of input_data declared by the caller
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_content_type: Option<String>,
 }
 
-/// Execution output - can be bytes, text, or parsed JSON
+/// Execution output - can be bytes, text, parsed JSON, validated CBOR/MessagePack bytes,
+/// or base64 of raw bytes
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ExecutionOutput {
     Bytes(Vec<u8>),
     Text(String),
     Json(serde_json::Value),
+    Cbor(Vec<u8>),
+    MessagePack(Vec<u8>),
+    /// Standard (padded) base64 of stdout
+    Base64(String),
+}
+
+impl ExecutionOutput {
+    /// Plain JSON value the contract returns to the caller (without the enum wrapper)
+    ///
+    /// Mirrors `on_execution_response` in the contract: bytes and binary
+    /// encodings become base64 strings, text and base64 output a JSON string, JSON is
+    /// returned as-is.
+    pub fn to_contract_json(&self) -> serde_json::Value {
+        match self {
+            ExecutionOutput::Bytes(bytes)
+            | ExecutionOutput::Cbor(bytes)
+            | ExecutionOutput::MessagePack(bytes) => {
+                use base64::{engine::general_purpose::STANDARD, Engine};
+                serde_json::Value::String(STANDARD.encode(bytes))
+            }
+            ExecutionOutput::Text(text) | ExecutionOutput::Base64(text) => serde_json::Value::String(text.clone()),
+            ExecutionOutput::Json(json) => json.clone(),
+        }
+    }
+
+    /// Size of the output as submitted to the contract, reported as `output_bytes`
+    pub fn encoded_len(&self) -> usize {
+        match self {
+            ExecutionOutput::Bytes(bytes)
+            | ExecutionOutput::Cbor(bytes)
+            | ExecutionOutput::MessagePack(bytes) => bytes.len(),
+            ExecutionOutput::Text(text) | ExecutionOutput::Base64(text) => text.len(),
+            ExecutionOutput::Json(json) => serde_json::to_string(json).map(|s| s.len()).unwrap_or(0),
+        }
+    }
 }
 
 /// Project UUID info from coordinator
@@ -319,13 +444,48 @@ pub struct ExecutionResult {
     pub success: bool,
     pub output: Option<ExecutionOutput>,
     pub error: Option<String>,
+    /// Error document `{"code", "message", "details"}` from `env::fail`, alongside `error`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_structured: Option<serde_json::Value>,
     pub execution_time_ms: u64,
     pub instructions: u64,
+    /// High-water mark of guest linear memory, for tuning `max_memory_mb` (0 if nothing ran)
+    #[serde(default)]
+    pub peak_memory_bytes: u64,
     pub compile_time_ms: Option<u64>, // Compilation time if WASM was compiled in this execution
     pub compilation_note: Option<String>, // e.g., "Cached WASM from 2025-01-10 14:30 UTC"
     /// Refund amount to return to user from attached_usd (stablecoin, minimal token units)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub refund_usd: Option<u64>,
+    /// WASM stderr lines (last `max_log_bytes`), for the caller to debug its output.
+    /// Returned to HTTPS callers only; never submitted on-chain.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub logs: Vec<String>,
+    /// Execution was stopped at the `max_execution_seconds` deadline
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub timed_out: bool,
+    /// Output format the ark declared with `env::set_output_format`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub declared_output_format: Option<ResponseFormat>,
+}
+
+impl ExecutionResult {
+    /// Job status reported to the coordinator for a failed execution
+    ///
+    /// `Timeout` only with `report_timeout` (REPORT_TIMEOUT_STATUS): coordinators that
+    /// don't know the status reject the completion.
+    pub fn failure_status(&self, report_timeout: bool) -> JobStatus {
+        if self.timed_out && report_timeout {
+            JobStatus::Timeout
+        } else {
+            JobStatus::ExecutionFailed
+        }
+    }
+
+    /// Output size reported to the contract as `resources_used.output_bytes`
+    pub fn output_bytes(&self) -> u64 {
+        self.output.as_ref().map_or(0, |output| output.encoded_len() as u64)
+    }
 }
 
 /// Job type - compile or execute
@@ -389,6 +549,8 @@ pub struct ApiClient {
     auth_token: String,
     /// TEE session ID (set after successful TEE registration)
     tee_session_id: std::sync::Arc<std::sync::Mutex<Option<String>>>,
+    /// Coordinator accepts zstd-compressed WASM uploads (learned from `/wasm/exists`)
+    accepts_zstd: std::sync::Arc<AtomicBool>,
 }
 
 impl ApiClient {
@@ -407,6 +569,7 @@ impl ApiClient {
             base_url: base_url.trim_end_matches('/').to_string(),
             auth_token,
             tee_session_id: std::sync::Arc::new(std::sync::Mutex::new(None)),
+            accepts_zstd: std::sync::Arc::new(AtomicBool::new(false)),
         })
     }
 
@@ -889,9 +1052,11 @@ impl ApiClient {
         success: bool,
         output: Option<serde_json::Value>,
         error: Option<String>,
+        error_structured: Option<serde_json::Value>,
         instructions: u64,
         time_ms: u64,
         job_id: Option<i64>,
+        logs: Vec<String>,
     ) -> Result<()> {
         let url = format!("{}/https-calls/complete", self.base_url);
 
@@ -903,10 +1068,14 @@ impl ApiClient {
             output: Option<serde_json::Value>,
             #[serde(skip_serializing_if = "Option::is_none")]
             error: Option<String>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            error_structured: Option<serde_json::Value>,
             instructions: u64,
             time_ms: u64,
             #[serde(skip_serializing_if = "Option::is_none")]
             job_id: Option<i64>,
+            #[serde(skip_serializing_if = "Vec::is_empty")]
+            logs: Vec<String>,
         }
 
         let request = CompleteHttpsCallRequest {
@@ -914,9 +1083,11 @@ impl ApiClient {
             success,
             output,
             error: error.clone(),
+            error_structured,
             instructions,
             time_ms,
             job_id,
+            logs,
         };
 
         tracing::info!(
@@ -1000,6 +1171,9 @@ impl ApiClient {
 
     /// Download WASM binary from cache
     ///
+    /// Accepts a zstd-compressed response (see `wasm_compression`); the returned bytes are
+    /// always the uncompressed WASM.
+    ///
     /// # Arguments
     /// * `checksum` - SHA256 checksum of the WASM file
     ///
@@ -1010,29 +1184,106 @@ impl ApiClient {
         let url = format!("{}/wasm/{}", self.base_url, checksum);
 
         let response = self.add_auth_headers(self.client.get(&url))
+            .header(reqwest::header::ACCEPT_ENCODING, ZSTD_ENCODING)
             .send()
             .await
             .context("Failed to download WASM")?;
 
-        if response.status() == StatusCode::NOT_FOUND {
-            anyhow::bail!("WASM file not found: {}", checksum)
-        }
-
-        if !response.status().is_success() {
-            anyhow::bail!("Download failed with status: {}", response.status())
-        }
+        Self::check_wasm_download_status(response.status(), checksum)?;
+        let encoding = Self::wasm_encoding(&response);
 
         let bytes = response
             .bytes()
             .await
-            .context("Failed to read WASM bytes")?
-            .to_vec();
+            .context("Failed to read WASM bytes")?;
+
+        match encoding {
+            Some(expected_sha256) => {
+                let wasm = wasm_compression::decompress(&bytes, expected_sha256.as_deref())?;
+                tracing::debug!("Downloaded zstd WASM {}: {} -> {} bytes", checksum, bytes.len(), wasm.len());
+                Ok(wasm)
+            }
+            None => Ok(bytes.to_vec()),
+        }
+    }
+
+    /// `Some(sha256 header)` if the WASM response is zstd-compressed
+    fn wasm_encoding(response: &reqwest::Response) -> Option<Option<String>> {
+        let headers = response.headers();
+        let encoding = headers.get(reqwest::header::CONTENT_ENCODING)?.to_str().ok()?;
+        if !encoding.trim().eq_ignore_ascii_case(ZSTD_ENCODING) {
+            return None;
+        }
+        Some(
+            headers
+                .get(WASM_SHA256_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string),
+        )
+    }
+
+    /// Download WASM binary from cache as a stream of chunks
+    ///
+    /// Same endpoint and errors as `download_wasm`, but chunks are yielded as they arrive
+    /// so large artifacts never have to be held in memory at once. The request is sent on
+    /// first poll; the stream ends after the first error. A zstd-compressed response is
+    /// decompressed chunk by chunk and its hash checked once the last chunk arrived.
+    ///
+    /// # Arguments
+    /// * `checksum` - SHA256 checksum of the WASM file
+    pub fn download_wasm_streaming(&self, checksum: &str) -> impl Stream<Item = Result<Bytes>> {
+        let url = format!("{}/wasm/{}", self.base_url, checksum);
+        let request = self
+            .add_auth_headers(self.client.get(&url))
+            .header(reqwest::header::ACCEPT_ENCODING, ZSTD_ENCODING);
+        let checksum = checksum.to_string();
+
+        futures_util::stream::try_unfold(
+            (Some(request), None::<(reqwest::Response, Option<StreamDecoder>)>),
+            move |(request, response)| {
+                let checksum = checksum.clone();
+                async move {
+                    let (mut response, mut decoder) = match (request, response) {
+                        (_, Some(response)) => response,
+                        (Some(request), None) => {
+                            let response = request.send().await.context("Failed to download WASM")?;
+                            Self::check_wasm_download_status(response.status(), &checksum)?;
+                            let decoder = Self::wasm_encoding(&response).map(StreamDecoder::new).transpose()?;
+                            (response, decoder)
+                        }
+                        (None, None) => return Ok(None),
+                    };
+                    let chunk = response.chunk().await.context("Failed to read WASM bytes")?;
+                    let next = match (chunk, decoder.as_mut()) {
+                        (Some(chunk), Some(decoder)) => Some(decoder.push(&chunk)?),
+                        (Some(chunk), None) => Some(chunk),
+                        (None, Some(decoder)) => return Ok(Some((decoder.finish()?, (None, None)))),
+                        (None, None) => None,
+                    };
+                    Ok::<_, anyhow::Error>(next.map(|chunk| (chunk, (None, Some((response, decoder))))))
+                }
+            },
+        )
+    }
+
+    fn check_wasm_download_status(status: StatusCode, checksum: &str) -> Result<()> {
+        if status == StatusCode::NOT_FOUND {
+            anyhow::bail!("WASM file not found: {}", checksum)
+        }
 
-        Ok(bytes)
+        if !status.is_success() {
+            anyhow::bail!("Download failed with status: {}", status)
+        }
+
+        Ok(())
     }
 
     /// Upload compiled WASM binary to cache
     ///
+    /// Compressed with zstd when the coordinator advertised support in `/wasm/exists`.
+    /// Capabilities the WASM declares in its `requires` metadata are sent as `requires`
+    /// (comma-separated, omitted if none), so jobs can be routed to workers providing them.
+    ///
     /// # Arguments
     /// * `checksum` - SHA256 checksum of the WASM file
     /// * `repo` - GitHub repository URL
@@ -1048,23 +1299,53 @@ impl ApiClient {
         bytes: Vec<u8>,
     ) -> Result<()> {
         let url = format!("{}/wasm/upload", self.base_url);
+        let size = bytes.len();
+
+        let compressed = if self.accepts_zstd.load(Ordering::Relaxed) {
+            Some(wasm_compression::compress(&bytes)?)
+        } else {
+            None
+        };
+        let wasm_sha256 = wasm_compression::sha256_hex(&bytes);
+        let requires = crate::executor::requirements::declared(&bytes);
 
         // Create multipart form with correct field names (matching coordinator's handler)
-        let file_part = reqwest::multipart::Part::bytes(bytes.clone())
-            .file_name(format!("{}.wasm", checksum))
-            .mime_str("application/wasm")
-            .context("Failed to create file part")?;
+        let file_part = match &compressed {
+            Some(compressed) => reqwest::multipart::Part::bytes(compressed.clone())
+                .file_name(format!("{}.wasm.zst", checksum))
+                .mime_str("application/zstd"),
+            None => reqwest::multipart::Part::bytes(bytes)
+                .file_name(format!("{}.wasm", checksum))
+                .mime_str("application/wasm"),
+        }
+        .context("Failed to create file part")?;
 
-        let form = reqwest::multipart::Form::new()
+        let mut form = reqwest::multipart::Form::new()
             .text("checksum", checksum.clone())
             .text("repo_url", repo.clone())         // coordinator expects "repo_url"
             .text("commit_hash", commit.clone())    // coordinator expects "commit_hash"
             .text("build_target", build_target.clone()) // coordinator expects "build_target"
             .part("wasm_file", file_part);          // coordinator expects "wasm_file"
+        if compressed.is_some() {
+            form = form
+                .text("encoding", ZSTD_ENCODING)
+                .text("wasm_sha256", wasm_sha256);
+        }
+        if !requires.is_empty() {
+            form = form.text("requires", requires.join(","));
+        }
 
         tracing::info!(
-            "Uploading WASM: checksum={} size={} bytes repo={} commit={} target={}",
-            checksum, bytes.len(), repo, commit, build_target
+            "Uploading WASM: checksum={} size={} bytes{} repo={} commit={} target={}",
+            checksum,
+            size,
+            compressed
+                .as_ref()
+                .map(|c| format!(" (zstd {} bytes)", c.len()))
+                .unwrap_or_default(),
+            repo,
+            commit,
+            build_target
         );
 
         let response = self.add_auth_headers(self.client.post(&url))
@@ -1107,12 +1388,16 @@ impl ApiClient {
         struct ExistsResponse {
             exists: bool,
             created_at: Option<String>,
+            /// Coordinator takes `encoding=zstd` uploads
+            #[serde(default)]
+            accepts_zstd: bool,
         }
 
         let result = response
             .json::<ExistsResponse>()
             .await
             .context("Failed to parse exists response")?;
+        self.accepts_zstd.store(result.accepts_zstd, Ordering::Relaxed);
 
         Ok((result.exists, result.created_at))
     }
@@ -1239,6 +1524,7 @@ impl ApiClient {
             project_uuid: Option<String>,
             #[serde(skip_serializing_if = "Option::is_none")]
             project_id: Option<String>,
+            priority: u8,
         }
 
         #[derive(Deserialize)]
@@ -1264,6 +1550,7 @@ impl ApiClient {
             store_on_fastfs: params.store_on_fastfs,
             project_uuid: params.project_uuid,
             project_id: params.project_id,
+            priority: params.priority,
         };
 
         let response = self.add_auth_headers(self.client.post(&url))
@@ -1835,37 +2122,25 @@ impl ApiClient {
     /// * `worker_id` - Unique worker identifier
     /// * `worker_name` - Human-readable worker name
     /// * `status` - Current status (online, busy, offline)
-    /// * `current_task_id` - ID of currently executing task (if any)
+    /// * `live` - Capabilities, current job, load and health used by the coordinator for routing
     pub async fn send_heartbeat(
         &self,
         worker_id: String,
         worker_name: String,
         status: &str,
-        current_task_id: Option<i64>,
         event_monitor_block_height: Option<u64>,
         last_poll_at: Option<u64>,
+        live: HeartbeatStatus,
     ) -> Result<()> {
         let url = format!("{}/workers/heartbeat", self.base_url);
 
-        #[derive(Serialize)]
-        struct HeartbeatRequest {
-            worker_id: String,
-            worker_name: String,
-            status: String,
-            current_task_id: Option<i64>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            event_monitor_block_height: Option<u64>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            last_poll_at: Option<u64>,
-        }
-
         let request = HeartbeatRequest {
             worker_id,
             worker_name,
             status: status.to_string(),
-            current_task_id,
             event_monitor_block_height,
             last_poll_at,
+            live,
         };
 
         let response = self.add_auth_headers(self.client.post(&url))
@@ -2244,4 +2519,32 @@ mod tests {
         .unwrap();
         assert_eq!(client.base_url, "http://localhost:8080");
     }
+
+    #[test]
+    fn test_heartbeat_payload_includes_live_fields() {
+        let worker_status = crate::worker_status::WorkerStatus::new();
+        let mut jobs = worker_status.claim(3);
+        jobs.start(7);
+        worker_status.record_probe(true, Some(5));
+
+        let request = HeartbeatRequest {
+            worker_id: "worker-1".to_string(),
+            worker_name: "worker-1".to_string(),
+            status: "online".to_string(),
+            event_monitor_block_height: Some(100),
+            last_poll_at: None,
+            live: worker_status.heartbeat_status(vec!["execution".to_string()]),
+        };
+        let payload = serde_json::to_value(&request).unwrap();
+
+        assert_eq!(payload["worker_id"], "worker-1");
+        assert_eq!(payload["event_monitor_block_height"], 100);
+        assert!(payload.get("last_poll_at").is_none());
+        assert!(payload.get("current_task_id").is_none());
+        assert_eq!(payload["capabilities"], serde_json::json!(["execution"]));
+        assert_eq!(payload["current_job_id"], 7);
+        assert_eq!(payload["in_flight_count"], 3);
+        assert_eq!(payload["keystore_healthy"], true);
+        assert_eq!(payload["allowance_remaining"], "5");
+    }
 }
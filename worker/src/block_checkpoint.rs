@@ -0,0 +1,183 @@
+//! Event monitor checkpoint
+//!
+//! After every scanned block the event monitor records it as `last_processed_block` in a
+//! small JSON file, so a restart resumes where the previous run stopped instead of replaying
+//! from START_BLOCK_HEIGHT or waiting for the coordinator cursor (only sent with the 30s
+//! heartbeat).
+//!
+//! On resume the last `reorg_depth` blocks are scanned again, in case the checkpoint was
+//! written for a block that was later reorganized. Execution requests seen in that window
+//! are remembered (and persisted with the checkpoint), so a request that shows up again in
+//! a rescanned or re-included block is not submitted twice.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::path::PathBuf;
+use tracing::warn;
+
+/// Default number of blocks re-scanned on resume
+pub const DEFAULT_REORG_DEPTH: u64 = 3;
+
+/// First block to scan after `last_processed`, rewound by `reorg_depth` but never before
+/// `start_block`. `None` when nothing was processed yet.
+pub fn resume_block(start_block: u64, last_processed: Option<u64>, reorg_depth: u64) -> Option<u64> {
+    last_processed.map(|last| start_block.max((last + 1).saturating_sub(reorg_depth)))
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CheckpointFile {
+    last_processed_block: u64,
+    /// `(block_height, request_id)` of execution requests in the reorg window
+    #[serde(default)]
+    recent_requests: Vec<(u64, u64)>,
+}
+
+/// Persisted scan position plus the request ids seen in the last `reorg_depth` blocks
+pub struct BlockCheckpoint {
+    path: PathBuf,
+    reorg_depth: u64,
+    last_processed_block: Option<u64>,
+    recent: VecDeque<(u64, u64)>,
+    recent_ids: HashSet<u64>,
+}
+
+impl BlockCheckpoint {
+    /// Load the checkpoint at `path`; a missing or unreadable file starts empty
+    pub fn load(path: PathBuf, reorg_depth: u64) -> Self {
+        let file = match std::fs::read(&path) {
+            Ok(bytes) => match serde_json::from_slice::<CheckpointFile>(&bytes) {
+                Ok(file) => Some(file),
+                Err(e) => {
+                    warn!("Ignoring unreadable event monitor checkpoint {}: {}", path.display(), e);
+                    None
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                warn!("Failed to read event monitor checkpoint {}: {}", path.display(), e);
+                None
+            }
+        };
+
+        let mut checkpoint = Self {
+            path,
+            reorg_depth,
+            last_processed_block: None,
+            recent: VecDeque::new(),
+            recent_ids: HashSet::new(),
+        };
+        if let Some(file) = file {
+            checkpoint.last_processed_block = Some(file.last_processed_block);
+            for (block, request_id) in file.recent_requests {
+                checkpoint.record_request(block, request_id);
+            }
+        }
+        checkpoint
+    }
+
+    pub fn last_processed_block(&self) -> Option<u64> {
+        self.last_processed_block
+    }
+
+    /// Whether `request_id` was already handled within the reorg window
+    pub fn is_seen(&self, request_id: u64) -> bool {
+        self.recent_ids.contains(&request_id)
+    }
+
+    /// Remember a handled execution request included in `block`
+    pub fn record_request(&mut self, block: u64, request_id: u64) {
+        if self.recent_ids.insert(request_id) {
+            self.recent.push_back((block, request_id));
+        }
+    }
+
+    /// Mark `block` as fully processed and write the checkpoint
+    ///
+    /// Request ids older than `reorg_depth` blocks are forgotten.
+    pub fn mark_processed(&mut self, block: u64) -> Result<()> {
+        self.last_processed_block = Some(block);
+        while let Some(&(seen_at, request_id)) = self.recent.front() {
+            if seen_at + self.reorg_depth >= block {
+                break;
+            }
+            self.recent.pop_front();
+            self.recent_ids.remove(&request_id);
+        }
+        self.save()
+    }
+
+    fn save(&self) -> Result<()> {
+        let file = CheckpointFile {
+            last_processed_block: self.last_processed_block.unwrap_or(0),
+            recent_requests: self.recent.iter().copied().collect(),
+        };
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create checkpoint dir {}", dir.display()))?;
+        }
+        // Write then rename, so a crash mid-write never leaves a truncated checkpoint
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec(&file)?)
+            .with_context(|| format!("Failed to write checkpoint {}", tmp.display()))?;
+        std::fs::rename(&tmp, &self.path)
+            .with_context(|| format!("Failed to replace checkpoint {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resume_rewinds_by_reorg_depth() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("checkpoint.json");
+
+        let empty = BlockCheckpoint::load(path.clone(), 3);
+        assert_eq!(resume_block(100, empty.last_processed_block(), 3), None);
+
+        let mut checkpoint = BlockCheckpoint::load(path.clone(), 3);
+        checkpoint.mark_processed(200).unwrap();
+
+        let reloaded = BlockCheckpoint::load(path, 3);
+        assert_eq!(reloaded.last_processed_block(), Some(200));
+        // Blocks 198, 199 and 200 are scanned again
+        assert_eq!(resume_block(100, reloaded.last_processed_block(), 3), Some(198));
+        // A configured start past the checkpoint wins
+        assert_eq!(resume_block(500, reloaded.last_processed_block(), 3), Some(500));
+        assert_eq!(resume_block(0, Some(1), 3), Some(0));
+    }
+
+    #[test]
+    fn test_seen_requests_survive_restart_within_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("checkpoint.json");
+
+        let mut checkpoint = BlockCheckpoint::load(path.clone(), 3);
+        checkpoint.record_request(198, 7);
+        checkpoint.mark_processed(198).unwrap();
+        checkpoint.record_request(200, 8);
+        checkpoint.mark_processed(200).unwrap();
+
+        let mut reloaded = BlockCheckpoint::load(path, 3);
+        assert!(reloaded.is_seen(7));
+        assert!(reloaded.is_seen(8));
+
+        // Request 7 falls out of the window once block 202 is processed
+        reloaded.mark_processed(202).unwrap();
+        assert!(!reloaded.is_seen(7));
+        assert!(reloaded.is_seen(8));
+    }
+
+    #[test]
+    fn test_corrupt_file_starts_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("checkpoint.json");
+        std::fs::write(&path, b"{not json").unwrap();
+
+        let checkpoint = BlockCheckpoint::load(path, 3);
+        assert_eq!(checkpoint.last_processed_block(), None);
+    }
+}
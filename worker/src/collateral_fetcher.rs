@@ -75,3 +75,203 @@ pub async fn fetch_collateral_from_phala(tdx_quote_hex: &str) -> Result<String>
 
     Ok(collateral_json)
 }
+
+/// Re-fetch from Phala once the cached collateral is this old, even if Intel hasn't
+/// scheduled an update yet
+pub const COLLATERAL_MAX_AGE_SECONDS: u64 = 24 * 60 * 60;
+
+/// Collateral fetched by the refresh task
+#[derive(Debug, Clone)]
+pub struct CachedCollateral {
+    /// Collateral JSON, as passed to `update_collateral`
+    pub json: String,
+    /// Platform the collateral is for (hex, lowercase)
+    pub fmspc: String,
+    /// Unix seconds when it was fetched
+    pub fetched_at: u64,
+    /// Unix seconds of the earliest `nextUpdate` in its TCB info / QE identity
+    pub next_update: u64,
+}
+
+impl CachedCollateral {
+    pub fn parse(json: String, fetched_at: u64) -> Result<Self> {
+        let info = CollateralInfo::parse(&json)?;
+        Ok(Self {
+            json,
+            fmspc: info.fmspc,
+            fetched_at,
+            next_update: info.next_update,
+        })
+    }
+
+    /// Too old to keep serving: past its `nextUpdate` or older than [`COLLATERAL_MAX_AGE_SECONDS`]
+    pub fn is_stale(&self, now: u64) -> bool {
+        now >= self.next_update || now.saturating_sub(self.fetched_at) >= COLLATERAL_MAX_AGE_SECONDS
+    }
+}
+
+/// Platform and expiry read from a collateral JSON
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollateralInfo {
+    pub fmspc: String,
+    pub next_update: u64,
+}
+
+impl CollateralInfo {
+    /// Parse the `tcb_info` and `qe_identity` documents embedded (as JSON strings) in a collateral
+    pub fn parse(collateral_json: &str) -> Result<Self> {
+        let collateral: serde_json::Value =
+            serde_json::from_str(collateral_json).context("Collateral is not valid JSON")?;
+        let embedded = |field: &str| -> Result<serde_json::Value> {
+            let text = collateral[field]
+                .as_str()
+                .with_context(|| format!("Collateral has no {}", field))?;
+            serde_json::from_str(text).with_context(|| format!("Collateral {} is not valid JSON", field))
+        };
+        let tcb_info = embedded("tcb_info")?;
+        let qe_identity = embedded("qe_identity")?;
+
+        let fmspc = tcb_info["fmspc"]
+            .as_str()
+            .context("Collateral tcb_info has no fmspc")?
+            .to_lowercase();
+        let next_update = |doc: &serde_json::Value, name: &str| -> Result<u64> {
+            let date = doc["nextUpdate"]
+                .as_str()
+                .with_context(|| format!("Collateral {} has no nextUpdate", name))?;
+            parse_utc_timestamp(date).with_context(|| format!("Invalid {} nextUpdate '{}'", name, date))
+        };
+        Ok(Self {
+            fmspc,
+            next_update: next_update(&tcb_info, "tcb_info")?.min(next_update(&qe_identity, "qe_identity")?),
+        })
+    }
+}
+
+/// What the operator needs to do about the collateral cached on the register contract
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CollateralCheck {
+    /// The on-chain collateral for this platform is valid beyond the warning window
+    UpToDate { expires_in: u64 },
+    /// No on-chain collateral for this platform: registrations from it fail
+    Missing,
+    /// The on-chain collateral expires within the warning window (or already has)
+    Expiring { expires_in: i64 },
+}
+
+/// Compare the on-chain collateral's `nextUpdate` against `now` and the warning window
+pub fn check_on_chain_collateral(on_chain_next_update: Option<u64>, now: u64, warn_before: u64) -> CollateralCheck {
+    match on_chain_next_update {
+        None => CollateralCheck::Missing,
+        Some(next_update) if next_update > now.saturating_add(warn_before) => CollateralCheck::UpToDate {
+            expires_in: next_update - now,
+        },
+        Some(next_update) => CollateralCheck::Expiring {
+            expires_in: next_update as i64 - now as i64,
+        },
+    }
+}
+
+/// Delay before retrying a failed refresh: doubles from one minute up to `max`
+pub fn refresh_backoff(consecutive_failures: u32, max: std::time::Duration) -> std::time::Duration {
+    let delay = std::time::Duration::from_secs(60).saturating_mul(1 << consecutive_failures.saturating_sub(1).min(16));
+    delay.min(max)
+}
+
+/// Unix seconds of an Intel collateral date (`2025-06-12T09:22:50Z`, fractional seconds allowed)
+fn parse_utc_timestamp(date: &str) -> Option<u64> {
+    let date = date.strip_suffix('Z')?;
+    let (day, time) = date.split_once('T')?;
+    let mut day_parts = day.splitn(3, '-').map(|p| p.parse::<i64>().ok());
+    let (year, month, dom) = (day_parts.next()??, day_parts.next()??, day_parts.next()??);
+    let time = time.split('.').next()?;
+    let mut time_parts = time.splitn(3, ':').map(|p| p.parse::<i64>().ok());
+    let (hour, minute, second) = (time_parts.next()??, time_parts.next()??, time_parts.next()??);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&dom) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    // Days since 1970-01-01 (civil-from-days inverse, proleptic Gregorian)
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + dom - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    u64::try_from(days * 86_400 + hour * 3_600 + minute * 60 + second).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn collateral(fmspc: &str, tcb_next: &str, qe_next: &str) -> String {
+        serde_json::json!({
+            "tcb_info": serde_json::json!({ "fmspc": fmspc, "nextUpdate": tcb_next }).to_string(),
+            "qe_identity": serde_json::json!({ "nextUpdate": qe_next }).to_string(),
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_parse_utc_timestamp() {
+        assert_eq!(parse_utc_timestamp("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_utc_timestamp("2025-06-12T09:22:50Z"), Some(1_749_720_170));
+        assert_eq!(parse_utc_timestamp("2024-02-29T23:59:59.123Z"), Some(1_709_251_199));
+        assert_eq!(parse_utc_timestamp("2025-06-12 09:22:50"), None);
+        assert_eq!(parse_utc_timestamp("2025-13-01T00:00:00Z"), None);
+    }
+
+    #[test]
+    fn test_collateral_info_uses_earliest_next_update() {
+        let json = collateral("20A06F000000", "2025-07-12T00:00:00Z", "2025-07-01T00:00:00Z");
+        let info = CollateralInfo::parse(&json).unwrap();
+        assert_eq!(info.fmspc, "20a06f000000");
+        assert_eq!(info.next_update, parse_utc_timestamp("2025-07-01T00:00:00Z").unwrap());
+
+        assert!(CollateralInfo::parse("{\"tcb_info\": \"{}\"}").is_err());
+    }
+
+    #[test]
+    fn test_cache_goes_stale() {
+        let json = collateral("20a06f000000", "2025-07-12T00:00:00Z", "2025-07-12T00:00:00Z");
+        let next_update = parse_utc_timestamp("2025-07-12T00:00:00Z").unwrap();
+        let fetched_at = next_update - 10 * 24 * 60 * 60;
+        let cached = CachedCollateral::parse(json, fetched_at).unwrap();
+
+        assert!(!cached.is_stale(fetched_at + 60));
+        assert!(cached.is_stale(fetched_at + COLLATERAL_MAX_AGE_SECONDS));
+        assert!(cached.is_stale(next_update));
+    }
+
+    #[test]
+    fn test_check_on_chain_collateral() {
+        let day = 24 * 60 * 60;
+        assert_eq!(check_on_chain_collateral(None, 1_000, day), CollateralCheck::Missing);
+        assert_eq!(
+            check_on_chain_collateral(Some(1_000 + 2 * day), 1_000, day),
+            CollateralCheck::UpToDate { expires_in: 2 * day }
+        );
+        assert_eq!(
+            check_on_chain_collateral(Some(1_000 + day), 1_000, day),
+            CollateralCheck::Expiring { expires_in: day as i64 }
+        );
+        assert_eq!(
+            check_on_chain_collateral(Some(400), 1_000, day),
+            CollateralCheck::Expiring { expires_in: -600 }
+        );
+    }
+
+    #[test]
+    fn test_refresh_backoff_doubles_up_to_max() {
+        let max = Duration::from_secs(3_600);
+        assert_eq!(refresh_backoff(1, max), Duration::from_secs(60));
+        assert_eq!(refresh_backoff(2, max), Duration::from_secs(120));
+        assert_eq!(refresh_backoff(4, max), Duration::from_secs(480));
+        assert_eq!(refresh_backoff(10, max), max);
+        assert_eq!(refresh_backoff(u32::MAX, max), max);
+    }
+}
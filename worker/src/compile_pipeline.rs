@@ -0,0 +1,116 @@
+//! Compile slots and compile → execute handoff
+//!
+//! Compile jobs run on their own tokio tasks so a long `cargo` build doesn't stop the
+//! main loop from serving Execute jobs whose WASM is already built. A semaphore bounds
+//! how many compiles run at once; while every slot is taken the main loop polls without
+//! the `compilation` capability, and a compile job that still slips through waits for
+//! a slot before it is spawned.
+//!
+//! The WASM a compile job produces is handed to the Execute job of the same request
+//! through `compiled`, keyed by the request's data_id.
+
+use dashmap::DashMap;
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Freshly compiled WASM waiting for the Execute job of the same request
+pub struct CachedWasm {
+    pub checksum: String,
+    pub bytes: Vec<u8>,
+    pub compile_time_ms: u64,
+    pub created_at: Option<String>,
+    pub published_url: Option<String>,
+}
+
+pub struct CompilePipeline {
+    slots: Arc<Semaphore>,
+    compiled: DashMap<String, CachedWasm>,
+}
+
+impl CompilePipeline {
+    /// `max_concurrent` compiles at once (at least 1)
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            slots: Arc::new(Semaphore::new(max_concurrent.max(1))),
+            compiled: DashMap::new(),
+        }
+    }
+
+    /// Compile slots not currently taken
+    pub fn available_slots(&self) -> usize {
+        self.slots.available_permits()
+    }
+
+    /// Whether a compile could start right now
+    pub fn has_free_slot(&self) -> bool {
+        self.available_slots() > 0
+    }
+
+    /// Wait for a compile slot; the slot is released when the permit is dropped
+    pub async fn acquire(&self) -> OwnedSemaphorePermit {
+        self.slots
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("compile semaphore is never closed")
+    }
+
+    /// Store compiled WASM for the Execute job of request `data_id`
+    pub fn insert(&self, data_id: &str, wasm: CachedWasm) {
+        self.compiled.insert(data_id.to_string(), wasm);
+    }
+
+    /// Take compiled WASM for request `data_id`, if its compile job ran on this worker
+    pub fn take(&self, data_id: &str) -> Option<CachedWasm> {
+        self.compiled.remove(data_id).map(|(_, wasm)| wasm)
+    }
+
+    /// Drop whatever a request left behind (compile_only, failed or timed-out execute)
+    pub fn discard(&self, data_id: &str) {
+        self.compiled.remove(data_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wasm(checksum: &str) -> CachedWasm {
+        CachedWasm {
+            checksum: checksum.to_string(),
+            bytes: b"\0asm".to_vec(),
+            compile_time_ms: 1200,
+            created_at: None,
+            published_url: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slots_bound_concurrency() {
+        let pipeline = CompilePipeline::new(2);
+        let first = pipeline.acquire().await;
+        let _second = pipeline.acquire().await;
+        assert!(!pipeline.has_free_slot());
+
+        // A third compile waits until a slot is released
+        let waiting = tokio::time::timeout(std::time::Duration::from_millis(20), pipeline.acquire()).await;
+        assert!(waiting.is_err());
+
+        drop(first);
+        assert!(pipeline.has_free_slot());
+        let _third = pipeline.acquire().await;
+    }
+
+    #[test]
+    fn test_handoff_is_per_request() {
+        let pipeline = CompilePipeline::new(1);
+        pipeline.insert("data-1", wasm("aaa"));
+        pipeline.insert("data-2", wasm("bbb"));
+
+        assert_eq!(pipeline.take("data-1").unwrap().checksum, "aaa");
+        assert!(pipeline.take("data-1").is_none());
+
+        pipeline.discard("data-2");
+        assert!(pipeline.take("data-2").is_none());
+    }
+}
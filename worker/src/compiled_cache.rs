@@ -158,6 +158,11 @@ impl CompiledCache {
         self.evict_if_needed();
     }
 
+    /// Whether a compiled component for `wasm_checksum` is indexed
+    pub fn contains(&self, wasm_checksum: &str) -> bool {
+        self.entries.contains_key(wasm_checksum)
+    }
+
     /// Get compiled component from cache
     ///
     /// Returns None if:
@@ -33,10 +33,12 @@
 use anyhow::{Context, Result};
 use bollard::Docker;
 use sha2::{Digest, Sha256};
+use std::sync::{Arc, Mutex};
 use tracing::{info, warn};
 
 use crate::api_client::{ApiClient, CodeSource};
 use crate::config::Config;
+use crate::wasm_cache::WasmCache;
 
 /// Maximum WASM file size for URL downloads (10 MB)
 const MAX_WASM_SIZE_BYTES: u64 = 10 * 1024 * 1024;
@@ -51,15 +53,73 @@ mod wasm32_wasip2;
 
 pub use docker::CompilationError;
 
+/// Docker-mode counterpart of `native::validate_pinned_toolchain`, run after checkout when
+/// COMPILE_VERIFY_REPRODUCIBLE is set
+const PINNED_TOOLCHAIN_CHECK: &str = r#"
+if [ ! -f Cargo.lock ]; then
+    echo "❌ ERROR: reproducible builds require a committed Cargo.lock"
+    exit 1
+fi
+TOOLCHAIN_FILE=$(ls rust-toolchain.toml rust-toolchain 2>/dev/null | head -1)
+if [ -z "$TOOLCHAIN_FILE" ] || ! grep -Eq '^(channel *= *")?([0-9]+\.[0-9]+\.[0-9]+|(stable|beta|nightly)-[0-9]{4}-[0-9]{2}-[0-9]{2})"? *$' "$TOOLCHAIN_FILE"; then
+    echo "❌ ERROR: reproducible builds require an exact toolchain in rust-toolchain.toml (e.g. channel = \"1.82.0\")"
+    exit 1
+fi
+"#;
+
 /// Lock TTL for compilation in seconds (5 minutes)
 /// This prevents stale locks from blocking compilation forever
 const COMPILATION_LOCK_TTL_SECONDS: u64 = 300;
 
+/// Workspace member and cargo features to build for a GitHub source
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CargoSelection {
+    /// `cargo build -p`; `None` builds the repository root
+    pub package: Option<String>,
+    /// `cargo build --features`, sorted and deduplicated so the same set always gives the
+    /// same checksum
+    pub features: Vec<String>,
+}
+
+impl CargoSelection {
+    pub fn new(package: Option<String>, features: &[String]) -> Self {
+        let mut features = features.to_vec();
+        features.sort();
+        features.dedup();
+        Self { package, features }
+    }
+
+    /// Reject names that could inject options or shell syntax into the cargo command
+    fn validate(&self) -> Result<()> {
+        if let Some(package) = &self.package {
+            native::validate_cargo_name("package", package)?;
+        }
+        for feature in &self.features {
+            native::validate_cargo_name("feature", feature)?;
+        }
+        Ok(())
+    }
+
+    /// Extra `cargo build` arguments (empty when nothing is selected)
+    fn cargo_args(&self) -> String {
+        let mut args = String::new();
+        if let Some(package) = &self.package {
+            args.push_str(&format!(" -p {}", package));
+        }
+        if !self.features.is_empty() {
+            args.push_str(&format!(" --features {}", self.features.join(",")));
+        }
+        args
+    }
+}
+
 /// Compiler for building GitHub repositories into WASM binaries
 pub struct Compiler {
     api_client: ApiClient,
     config: Config,
     docker: Option<Docker>,
+    /// Local WASM cache with compile index (reused across restarts)
+    wasm_cache: Option<Arc<Mutex<WasmCache>>>,
 }
 
 impl Compiler {
@@ -77,9 +137,43 @@ impl Compiler {
             api_client,
             config,
             docker,
+            wasm_cache: None,
         })
     }
 
+    /// Look up and record compilation results in the local WASM cache
+    pub fn with_wasm_cache(mut self, cache: Arc<Mutex<WasmCache>>) -> Self {
+        self.wasm_cache = Some(cache);
+        self
+    }
+
+    /// WASM previously compiled or downloaded for `checksum` by this worker
+    fn cached_compile(&self, checksum: &str) -> Option<(Vec<u8>, Option<String>)> {
+        self.wasm_cache.as_ref()?.lock().ok()?.get_compiled(checksum)
+    }
+
+    /// Remember the WASM for `checksum` (and the tree it was built from) so it survives restarts
+    fn record_compile(
+        &self,
+        checksum: &str,
+        content_checksum: Option<&str>,
+        wasm_bytes: &[u8],
+        created_at: Option<String>,
+    ) {
+        if let Some(cache) = &self.wasm_cache {
+            if let Ok(mut c) = cache.lock() {
+                match c.put_compiled(checksum, wasm_bytes, created_at) {
+                    Ok(()) => {
+                        if let Some(content_checksum) = content_checksum {
+                            c.link_content(checksum, content_checksum);
+                        }
+                    }
+                    Err(e) => warn!("Failed to cache compiled WASM {}: {}", checksum, e),
+                }
+            }
+        }
+    }
+
     /// Compile a GitHub repository to WASM
     ///
     /// This function:
@@ -117,31 +211,54 @@ impl Compiler {
         timeout_seconds: Option<u64>,
         force_rebuild: bool,
     ) -> Result<(String, Vec<u8>, Option<String>)> {
-        let (repo, commit, build_target) = match code_source {
-            CodeSource::GitHub { repo, commit, build_target } => (repo, commit, build_target),
+        let (repo, commit, build_target, selection) = match code_source {
+            CodeSource::GitHub { repo, commit, build_target, package, features } => {
+                (repo, commit, build_target, CargoSelection::new(package.clone(), features))
+            }
             CodeSource::WasmUrl { hash, .. } => {
                 anyhow::bail!("Cannot compile a WasmUrl source (hash: {}). WasmUrl provides pre-compiled WASM.", hash);
             }
         };
+        selection.validate()?;
 
         // Generate checksum for this specific compilation
-        let checksum = self.compute_checksum(repo, commit, build_target);
+        let checksum = Self::compute_checksum(repo, commit, build_target, &selection);
 
         // Check if WASM already exists (skip if force_rebuild)
         if !force_rebuild {
+            if let Some((wasm_bytes, created_at)) = self.cached_compile(&checksum) {
+                info!("📦 WASM found in local compile cache: {} ({} bytes)", checksum, wasm_bytes.len());
+                return Ok((checksum, wasm_bytes, created_at));
+            }
+
             let (exists, created_at) = self.api_client.wasm_exists(&checksum).await?;
             if exists {
                 info!("WASM already exists in cache: {} (created: {:?})", checksum, created_at);
                 // Download and return it
                 let wasm_bytes = self.api_client.download_wasm(&checksum).await?;
+                self.record_compile(&checksum, None, &wasm_bytes, created_at.clone());
                 return Ok((checksum, wasm_bytes, created_at));
             }
         } else {
             info!("🔄 force_rebuild=true, skipping cache check");
         }
 
+        // Another commit with the same source tree may have been built already
+        let content_checksum = self.content_checksum(repo, commit, build_target, &selection).await;
+        if let Some(content_checksum) = content_checksum.as_deref().filter(|_| !force_rebuild) {
+            if let Some((wasm_bytes, created_at)) = self.cached_compile(content_checksum) {
+                info!(
+                    "📦 WASM for identical source tree found in local compile cache: {} -> {} ({} bytes)",
+                    checksum, content_checksum, wasm_bytes.len()
+                );
+                self.record_compile(&checksum, Some(content_checksum), &wasm_bytes, created_at.clone());
+                return Ok((checksum, wasm_bytes, created_at));
+            }
+        }
+
         // Try to acquire distributed lock to prevent duplicate compilations
-        let lock_key = format!("compile:{}:{}", repo, commit);
+        // (keyed by checksum: other targets, packages or features of the commit build in parallel)
+        let lock_key = format!("compile:{}", checksum);
         let acquired = self
             .api_client
             .acquire_lock(
@@ -168,6 +285,7 @@ impl Compiler {
                 if exists {
                     info!("WASM compilation completed by another worker (created: {:?})", created_at);
                     let wasm_bytes = self.api_client.download_wasm(&checksum).await?;
+                    self.record_compile(&checksum, content_checksum.as_deref(), &wasm_bytes, created_at.clone());
                     return Ok((checksum, wasm_bytes, created_at));
                 }
 
@@ -183,13 +301,13 @@ impl Compiler {
             info!("⏱️  Compiling with timeout: {}s", timeout);
             tokio::time::timeout(
                 std::time::Duration::from_secs(timeout),
-                self.compile_from_github(repo, commit, build_target)
+                self.compile_from_github(repo, commit, build_target, &selection)
             )
             .await
             .map_err(|_| anyhow::anyhow!("Compilation timeout exceeded: {}s", timeout))
             .and_then(|r| r)
         } else {
-            self.compile_from_github(repo, commit, build_target).await
+            self.compile_from_github(repo, commit, build_target, &selection).await
         };
 
         // Always release lock - regardless of compilation result
@@ -202,6 +320,7 @@ impl Compiler {
         let wasm_bytes = compile_result?;
 
         info!("✅ WASM compilation complete: {} ({} bytes)", checksum, wasm_bytes.len());
+        self.record_compile(&checksum, content_checksum.as_deref(), &wasm_bytes, None);
         Ok((checksum, wasm_bytes, None)) // Fresh compilation, no created_at yet
     }
 
@@ -214,7 +333,7 @@ impl Compiler {
         // Upload to coordinator
         info!("Uploading compiled WASM to coordinator");
         let (repo, commit, build_target) = match code_source {
-            CodeSource::GitHub { repo, commit, build_target } => (repo.as_str(), commit.as_str(), build_target.as_str()),
+            CodeSource::GitHub { repo, commit, build_target, .. } => (repo.as_str(), commit.as_str(), build_target.as_str()),
             CodeSource::WasmUrl { .. } => {
                 anyhow::bail!("Cannot upload a WasmUrl source - it's already compiled");
             }
@@ -229,11 +348,50 @@ impl Compiler {
 
     /// Compile WASM from GitHub repository
     ///
+    /// With COMPILE_VERIFY_REPRODUCIBLE the source is built twice and the job fails
+    /// unless both builds are byte-identical.
+    ///
     /// Compilation method depends on config.compilation_mode:
     /// - "docker": Use Docker containers (requires Docker socket)
     /// - "native": Use native Rust toolchain with bubblewrap (for TEE/Phala)
-    async fn compile_from_github(&self, repo: &str, commit: &str, build_target: &str) -> Result<Vec<u8>> {
-        info!("Compiling {} @ {} for target {}", repo, commit, build_target);
+    async fn compile_from_github(
+        &self,
+        repo: &str,
+        commit: &str,
+        build_target: &str,
+        selection: &CargoSelection,
+    ) -> Result<Vec<u8>> {
+        info!("Compiling {} @ {} for target {} {:?}", repo, commit, build_target, selection);
+
+        if !self.config.compile_verify_reproducible {
+            return self.build_from_github(repo, commit, build_target, selection).await;
+        }
+
+        // Two builds, each from a fresh clone in its own work dir/container
+        info!("🔁 Reproducibility check: building {} @ {} twice", repo, commit);
+        let first = self.build_from_github(repo, commit, build_target, selection).await?;
+        let second = self.build_from_github(repo, commit, build_target, selection).await?;
+        let (first_hash, second_hash) = (hex::encode(Sha256::digest(&first)), hex::encode(Sha256::digest(&second)));
+        if first_hash != second_hash {
+            anyhow::bail!(
+                "Build is not reproducible: two clean builds of {} @ {} produced different WASM \
+                 (sha256 {} vs {}). Check for build-time randomness (timestamps, absolute paths, \
+                 unpinned dependencies)",
+                repo, commit, first_hash, second_hash
+            );
+        }
+        info!("✅ Reproducible build: sha256 {}", first_hash);
+        Ok(first)
+    }
+
+    /// One clean build with the configured backend
+    async fn build_from_github(
+        &self,
+        repo: &str,
+        commit: &str,
+        build_target: &str,
+        selection: &CargoSelection,
+    ) -> Result<Vec<u8>> {
 
         // Validate untrusted repo/commit at the single choke point so BOTH the
         // native and docker backends are covered (the docker scripts interpolate
@@ -247,6 +405,12 @@ impl Compiler {
         info!("Using build target: {}", normalized_target);
         info!("Compilation mode: {}", self.config.compilation_mode);
 
+        let reproducible = self.config.compile_verify_reproducible;
+        let mut cargo_args = selection.cargo_args();
+        if reproducible {
+            cargo_args.push_str(" --locked");
+        }
+
         // Select compilation method based on config
         match self.config.compilation_mode.as_str() {
             "native" => {
@@ -257,13 +421,15 @@ impl Compiler {
                     repo,
                     commit,
                     &normalized_target,
+                    &cargo_args,
+                    reproducible,
                     Some(self.config.compile_timeout_seconds),
                 ).await
             }
             "docker" => {
                 // Docker-based compilation (traditional method)
                 info!("🐳 Using Docker-based compilation");
-                self.compile_from_github_docker(repo, commit, &normalized_target).await
+                self.compile_from_github_docker(repo, commit, &normalized_target, &cargo_args, reproducible).await
             }
             _ => {
                 anyhow::bail!(
@@ -275,7 +441,14 @@ impl Compiler {
     }
 
     /// Compile WASM from GitHub repository using Docker (traditional method)
-    async fn compile_from_github_docker(&self, repo: &str, commit: &str, build_target: &str) -> Result<Vec<u8>> {
+    async fn compile_from_github_docker(
+        &self,
+        repo: &str,
+        commit: &str,
+        build_target: &str,
+        cargo_args: &str,
+        reproducible: bool,
+    ) -> Result<Vec<u8>> {
         // Get Docker client (guaranteed to exist in docker mode)
         let docker = self.docker.as_ref()
             .context("Docker client not initialized. Set COMPILATION_MODE=docker")?;
@@ -298,7 +471,7 @@ impl Compiler {
         .await?;
 
         // Execute compilation using target-specific compiler
-        let result = self.compile_in_container(&container_id, build_target).await;
+        let result = self.compile_in_container(&container_id, build_target, cargo_args, reproducible).await;
 
         // Always cleanup container
         if let Err(e) = docker::cleanup_container(docker, &container_id).await {
@@ -310,20 +483,28 @@ impl Compiler {
     }
 
     /// Compile WASM in container using target-specific compiler
-    async fn compile_in_container(&self, container_id: &str, build_target: &str) -> Result<Vec<u8>> {
+    async fn compile_in_container(
+        &self,
+        container_id: &str,
+        build_target: &str,
+        cargo_args: &str,
+        reproducible: bool,
+    ) -> Result<Vec<u8>> {
         info!("Executing compilation in container {} for target {}", container_id, build_target);
 
         // Get Docker client (guaranteed to exist in docker mode)
         let docker = self.docker.as_ref()
             .context("Docker client not initialized")?;
 
+        let preflight = if reproducible { PINNED_TOOLCHAIN_CHECK } else { "" };
+
         // Select compiler based on build target
         match build_target {
             "wasm32-wasip2" => {
-                wasm32_wasip2::compile(docker, container_id, build_target).await
+                wasm32_wasip2::compile(docker, container_id, build_target, cargo_args, preflight).await
             }
             "wasm32-wasip1" | "wasm32-wasi" => {
-                wasm32_wasip1::compile(docker, container_id, build_target).await
+                wasm32_wasip1::compile(docker, container_id, build_target, cargo_args, preflight).await
             }
             _ => {
                 anyhow::bail!(
@@ -351,17 +532,60 @@ impl Compiler {
     }
 
     /// Compute checksum for a specific compilation
-    fn compute_checksum(&self, repo: &str, commit: &str, build_target: &str) -> String {
+    ///
+    /// Package and features are only hashed when set, so builds without them keep the
+    /// checksums already in the WASM cache.
+    fn compute_checksum(repo: &str, commit: &str, build_target: &str, selection: &CargoSelection) -> String {
         let mut hasher = Sha256::new();
         hasher.update(repo.as_bytes());
         hasher.update(b":");
         hasher.update(commit.as_bytes());
         hasher.update(b":");
         hasher.update(build_target.as_bytes());
+        if let Some(package) = &selection.package {
+            hasher.update(b":package=");
+            hasher.update(package.as_bytes());
+        }
+        if !selection.features.is_empty() {
+            hasher.update(b":features=");
+            hasher.update(selection.features.join(",").as_bytes());
+        }
         let result = hasher.finalize();
         hex::encode(result)
     }
 
+    /// Content checksum of the source tree of `commit`, if it can be reused
+    ///
+    /// Only computed with a local compile cache and for full commit hashes: a branch or
+    /// tag could move between hashing the tree and the build. Failures are logged and
+    /// just skip the lookup.
+    async fn content_checksum(
+        &self,
+        repo: &str,
+        commit: &str,
+        build_target: &str,
+        selection: &CargoSelection,
+    ) -> Option<String> {
+        self.wasm_cache.as_ref()?;
+        if !is_commit_hash(commit) {
+            return None;
+        }
+        match native::source_tree_hash(repo, commit).await {
+            Ok(tree) => Some(Self::compute_content_checksum(&tree, build_target, selection)),
+            Err(e) => {
+                warn!("Failed to hash source tree of {} @ {}: {:#}", repo, commit, e);
+                None
+            }
+        }
+    }
+
+    /// Checksum of a compilation keyed by source content instead of repo and commit
+    ///
+    /// `tree` is the git tree hash; the `tree` prefix can't collide with a repo URL.
+    fn compute_content_checksum(tree: &str, build_target: &str, selection: &CargoSelection) -> String {
+        Self::compute_checksum("tree", tree, build_target, selection)
+    }
+
     /// Download WASM from URL and verify hash
     ///
     /// Downloads pre-compiled WASM from URL (https://, ipfs://, ar://)
@@ -512,6 +736,11 @@ impl Compiler {
     }
 }
 
+/// Full 40-character hex commit hash (not a branch or tag)
+fn is_commit_hash(commit: &str) -> bool {
+    commit.len() == 40 && commit.chars().all(|c| c.is_ascii_hexdigit())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -542,4 +771,70 @@ mod tests {
         let checksum3 = hex::encode(hasher.finalize());
         assert_eq!(checksum1, checksum3);
     }
+
+    #[test]
+    fn test_checksum_includes_cargo_selection() {
+        let repo = "https://github.com/user/repo";
+        let plain = Compiler::compute_checksum(repo, "abc123", "wasm32-wasip1", &CargoSelection::default());
+
+        // Without a package or features the checksum is the one computed before they existed
+        let mut hasher = Sha256::new();
+        hasher.update(b"https://github.com/user/repo:abc123:wasm32-wasip1");
+        assert_eq!(plain, hex::encode(hasher.finalize()));
+
+        let package = CargoSelection::new(Some("price-ark".to_string()), &[]);
+        let features = CargoSelection::new(Some("price-ark".to_string()), &["binance".to_string()]);
+        let checksums = [
+            plain,
+            Compiler::compute_checksum(repo, "abc123", "wasm32-wasip1", &package),
+            Compiler::compute_checksum(repo, "abc123", "wasm32-wasip1", &features),
+        ];
+        assert_ne!(checksums[0], checksums[1]);
+        assert_ne!(checksums[1], checksums[2]);
+
+        // Feature order and duplicates don't matter
+        let a = CargoSelection::new(None, &["b".to_string(), "a".to_string()]);
+        let b = CargoSelection::new(None, &["a".to_string(), "b".to_string(), "a".to_string()]);
+        assert_eq!(
+            Compiler::compute_checksum(repo, "abc123", "wasm32-wasip2", &a),
+            Compiler::compute_checksum(repo, "abc123", "wasm32-wasip2", &b)
+        );
+    }
+
+    #[test]
+    fn test_content_checksum_ignores_repo_and_commit() {
+        let tree = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+        let selection = CargoSelection::default();
+        let content = Compiler::compute_content_checksum(tree, "wasm32-wasip2", &selection);
+        assert_ne!(
+            content,
+            Compiler::compute_checksum("https://github.com/user/repo", tree, "wasm32-wasip2", &selection)
+        );
+        assert_ne!(content, Compiler::compute_content_checksum(tree, "wasm32-wasip1", &selection));
+        assert_ne!(
+            content,
+            Compiler::compute_content_checksum(tree, "wasm32-wasip2", &CargoSelection::new(Some("ark".to_string()), &[]))
+        );
+
+        assert!(is_commit_hash("0123456789abcdef0123456789ABCDEF01234567"));
+        assert!(!is_commit_hash("main"));
+        assert!(!is_commit_hash("v1.0.0"));
+        assert!(!is_commit_hash("0123456789abcdef"));
+    }
+
+    #[test]
+    fn test_cargo_selection_args_and_validation() {
+        assert_eq!(CargoSelection::default().cargo_args(), "");
+        let selection = CargoSelection::new(
+            Some("price-ark".to_string()),
+            &["serde/std".to_string(), "binance".to_string()],
+        );
+        assert!(selection.validate().is_ok());
+        assert_eq!(selection.cargo_args(), " -p price-ark --features binance,serde/std");
+
+        for bad in ["--config=x", "ark;rm -rf /", "a b", "$(id)", ""] {
+            assert!(CargoSelection::new(Some(bad.to_string()), &[]).validate().is_err(), "{}", bad);
+            assert!(CargoSelection::new(None, &[bad.to_string()]).validate().is_err(), "{}", bad);
+        }
+    }
 }
@@ -42,6 +42,8 @@ const MAX_PROCESSES: u32 = 1024;
 /// * `repo` - GitHub repository URL (e.g., "https://github.com/user/repo")
 /// * `commit` - Git commit hash or branch name
 /// * `build_target` - WASM target (wasm32-wasip1, wasm32-wasip2)
+/// * `cargo_args` - Extra `cargo build` arguments (`-p`/`--features`/`--locked`)
+/// * `reproducible` - Enforce a committed Cargo.lock and pinned toolchain
 /// * `timeout_seconds` - Optional timeout override
 ///
 /// # Returns
@@ -60,6 +62,8 @@ pub async fn compile(
     repo: &str,
     commit: &str,
     build_target: &str,
+    cargo_args: &str,
+    reproducible: bool,
     timeout_seconds: Option<u64>,
 ) -> Result<Vec<u8>> {
     let timeout = timeout_seconds.unwrap_or(MAX_CPU_TIME_SECONDS);
@@ -76,9 +80,12 @@ pub async fn compile(
 
     // 3. Validate no build.rs (security check)
     validate_no_build_scripts(&work_dir)?;
+    if reproducible {
+        validate_pinned_toolchain(&work_dir)?;
+    }
 
     // 4. Compile with env isolation + ulimit
-    let wasm_bytes = compile_with_isolation(&work_dir, build_target, timeout).await?;
+    let wasm_bytes = compile_with_isolation(&work_dir, build_target, cargo_args, timeout).await?;
 
     // 5. Cleanup
     cleanup_dir(&work_dir)?;
@@ -177,6 +184,21 @@ pub(crate) fn validate_git_ref(r: &str) -> Result<()> {
     Ok(())
 }
 
+/// A cargo package or feature name goes into the build command line, so it must not
+/// start with `-` (option injection) and must use cargo's own charset (`/` for
+/// `dependency/feature`).
+pub(crate) fn validate_cargo_name(kind: &str, name: &str) -> Result<()> {
+    if name.is_empty() || name.starts_with('-') {
+        anyhow::bail!("Invalid cargo {} (empty or starts with '-'): {}", kind, name);
+    }
+    if name.len() > 64
+        || !name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '/' | '+' | '.'))
+    {
+        anyhow::bail!("Invalid cargo {} (illegal characters): {}", kind, name);
+    }
+    Ok(())
+}
+
 /// Clone Git repository
 async fn clone_repo(repo: &str, commit: &str, work_dir: &Path) -> Result<()> {
     // Validate untrusted inputs before they reach git (see fns above).
@@ -259,6 +281,49 @@ async fn clone_repo(repo: &str, commit: &str, work_dir: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Git tree hash of `commit`: a content hash of every tracked file
+///
+/// Untracked and ignored files (`target/`, anything in `.gitignore`) are never part of
+/// a tree, so two commits with identical sources share it. Fetches the commit without
+/// blobs, so only the commit and tree objects are downloaded.
+pub(crate) async fn source_tree_hash(repo: &str, commit: &str) -> Result<String> {
+    validate_repo_url(repo)?;
+    validate_git_ref(commit)?;
+
+    let work_dir = create_temp_dir()?;
+    let result = async {
+        let output = run_git_with_timeout(&["init", "--bare"], &work_dir, GIT_CHECKOUT_TIMEOUT, "init").await?;
+        if !output.status.success() {
+            anyhow::bail!("Git init failed: {}", String::from_utf8_lossy(&output.stderr));
+        }
+
+        let output = run_git_with_timeout(
+            &["fetch", "--depth", "1", "--filter=blob:none", "--no-tags", repo, commit],
+            &work_dir,
+            GIT_SHALLOW_CLONE_TIMEOUT,
+            "fetch --filter=blob:none",
+        )
+        .await?;
+        if !output.status.success() {
+            anyhow::bail!("Git fetch failed for '{}': {}", commit, String::from_utf8_lossy(&output.stderr));
+        }
+
+        let output =
+            run_git_with_timeout(&["rev-parse", "FETCH_HEAD^{tree}"], &work_dir, GIT_CHECKOUT_TIMEOUT, "rev-parse")
+                .await?;
+        if !output.status.success() {
+            anyhow::bail!("Git rev-parse failed: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+    .await;
+
+    if let Err(e) = cleanup_dir(&work_dir) {
+        warn!("Failed to cleanup {}: {}", work_dir.display(), e);
+    }
+    result
+}
+
 /// Classify git clone errors into user-friendly messages
 fn classify_clone_error(stderr: &str, repo: &str) -> Result<()> {
     if stderr.contains("Repository not found") || stderr.contains("not found") {
@@ -342,10 +407,59 @@ fn validate_no_build_scripts(work_dir: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Requirements for a reproducible build (COMPILE_VERIFY_REPRODUCIBLE)
+///
+/// Two workers only produce the same bytes if they resolve the same dependency versions
+/// and use the same compiler, so the repository must commit `Cargo.lock` (the build runs
+/// with `--locked`) and pin an exact toolchain in `rust-toolchain.toml` or `rust-toolchain`:
+/// a full version (`1.82.0`) or a dated channel (`nightly-2024-11-01`). A bare `stable`
+/// or `1.82` follows new releases and is rejected.
+fn validate_pinned_toolchain(work_dir: &Path) -> Result<()> {
+    let rejected = |user_message: &str, stderr: &str| -> anyhow::Error {
+        CompilationError {
+            user_message: user_message.to_string(),
+            stderr: stderr.to_string(),
+            stdout: String::new(),
+            exit_code: None,
+        }
+        .into()
+    };
+
+    if !work_dir.join("Cargo.lock").exists() {
+        return Err(rejected(
+            "Reproducible build: commit Cargo.lock to the repository so every worker resolves the same dependency versions.",
+            "Cargo.lock not found",
+        ));
+    }
+
+    let toolchain = ["rust-toolchain.toml", "rust-toolchain"]
+        .iter()
+        .find_map(|name| std::fs::read_to_string(work_dir.join(name)).ok());
+    let pinned = regex::Regex::new(
+        r#"^(channel\s*=\s*")?(\d+\.\d+\.\d+|(stable|beta|nightly)-\d{4}-\d{2}-\d{2})"?\s*$"#,
+    )
+    .unwrap();
+    match toolchain {
+        Some(contents) if contents.lines().any(|line| pinned.is_match(line.trim())) => {
+            info!("✅ Toolchain pinned and Cargo.lock present");
+            Ok(())
+        }
+        Some(_) => Err(rejected(
+            "Reproducible build: rust-toolchain.toml must pin an exact toolchain, e.g. channel = \"1.82.0\" or \"nightly-2024-11-01\" (not \"stable\").",
+            "rust-toolchain does not pin an exact version",
+        )),
+        None => Err(rejected(
+            "Reproducible build: add rust-toolchain.toml pinning an exact toolchain, e.g. channel = \"1.82.0\".",
+            "rust-toolchain.toml not found",
+        )),
+    }
+}
+
 /// Compile with env isolation and ulimit (TEE-friendly, no pivot_root)
 async fn compile_with_isolation(
     work_dir: &Path,
     build_target: &str,
+    cargo_args: &str,
     timeout: u64,
 ) -> Result<Vec<u8>> {
     info!("🔒 Starting compilation with env isolation + ulimit");
@@ -360,11 +474,12 @@ async fn compile_with_isolation(
     // Export PATH explicitly so cargo can be found
     // Note: We don't use --locked because user repos may not have Cargo.lock or it may be outdated
     let cargo_cmd = format!(
-        "export PATH=/usr/local/cargo/bin:/usr/local/bin:/usr/bin:/bin && export RUSTUP_HOME=/usr/local/rustup && ulimit -v {} && ulimit -t {} && ulimit -u {} && cargo build --target {} --release",
+        "export PATH=/usr/local/cargo/bin:/usr/local/bin:/usr/bin:/bin && export RUSTUP_HOME=/usr/local/rustup && ulimit -v {} && ulimit -t {} && ulimit -u {} && cargo build --target {} --release{}",
         MAX_MEMORY_BYTES / 1024, // ulimit -v expects KB
         timeout,
         MAX_PROCESSES,
-        build_target
+        build_target,
+        cargo_args
     );
 
     info!("Cargo command: {}", cargo_cmd);
@@ -615,6 +730,37 @@ serde_json = "1.0"
         std::fs::remove_dir_all(&temp_dir).ok();
     }
 
+    #[test]
+    fn test_validate_pinned_toolchain() {
+        let temp_dir = std::env::temp_dir().join("test-pinned-toolchain");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(&temp_dir).unwrap();
+
+        // No Cargo.lock
+        assert!(validate_pinned_toolchain(&temp_dir).is_err());
+
+        std::fs::write(temp_dir.join("Cargo.lock"), "version = 3\n").unwrap();
+        // No toolchain file
+        assert!(validate_pinned_toolchain(&temp_dir).is_err());
+
+        let toolchain = temp_dir.join("rust-toolchain.toml");
+        for floating in ["stable", "1.82", "nightly"] {
+            std::fs::write(&toolchain, format!("[toolchain]\nchannel = \"{}\"\n", floating)).unwrap();
+            assert!(validate_pinned_toolchain(&temp_dir).is_err(), "{} should be rejected", floating);
+        }
+        for pinned in ["1.82.0", "nightly-2024-11-01"] {
+            std::fs::write(&toolchain, format!("[toolchain]\nchannel = \"{}\"\ntargets = [\"wasm32-wasip2\"]\n", pinned)).unwrap();
+            assert!(validate_pinned_toolchain(&temp_dir).is_ok(), "{} should be accepted", pinned);
+        }
+
+        // Legacy plain-text rust-toolchain file
+        std::fs::remove_file(&toolchain).unwrap();
+        std::fs::write(temp_dir.join("rust-toolchain"), "1.82.0\n").unwrap();
+        assert!(validate_pinned_toolchain(&temp_dir).is_ok());
+
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
     #[test]
     fn test_classify_compilation_error() {
         let (category, msg) = classify_compilation_error("error[E0425]: cannot find value", None);
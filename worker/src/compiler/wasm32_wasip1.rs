@@ -22,11 +22,13 @@ use super::docker;
 /// * `docker` - Docker client
 /// * `container_id` - Container ID where compilation happens
 /// * `build_target` - Build target (wasm32-wasip1 or wasm32-wasi)
+/// * `cargo_args` - Extra `cargo build` arguments (`-p`/`--features`/`--locked`, validated by the caller)
+/// * `preflight` - Shell checks run in the checked-out repo before building (may be empty)
 ///
 /// # Returns
 /// * `Ok(Vec<u8>)` - Compiled and optimized WASM bytes
 /// * `Err(_)` - Compilation failed
-pub async fn compile(docker: &Docker, container_id: &str, build_target: &str) -> Result<Vec<u8>> {
+pub async fn compile(docker: &Docker, container_id: &str, build_target: &str, cargo_args: &str, preflight: &str) -> Result<Vec<u8>> {
     info!("Compiling WASI Preview 1 module: target={}", build_target);
     let start_time = std::time::Instant::now();
 
@@ -67,9 +69,10 @@ echo "🔧 TARGET_TO_ADD: $TARGET_TO_ADD"
 git clone -- "$REPO" repo
 cd repo
 git checkout "$COMMIT"
+{preflight}
 
 # Build WASM with size optimizations
-cargo build --release --target $TARGET_TO_ADD
+cargo build --release --target $TARGET_TO_ADD{cargo_args}
 WASM_FILE=$(find target/$TARGET_TO_ADD/release -maxdepth 1 -name "*.wasm" -type f | head -1)
 
 # Find compiled WASM
@@ -22,11 +22,13 @@ use super::docker;
 /// * `docker` - Docker client
 /// * `container_id` - Container ID where compilation happens
 /// * `build_target` - Build target (wasm32-wasip2)
+/// * `cargo_args` - Extra `cargo build` arguments (`-p`/`--features`/`--locked`, validated by the caller)
+/// * `preflight` - Shell checks run in the checked-out repo before building (may be empty)
 ///
 /// # Returns
 /// * `Ok(Vec<u8>)` - Compiled and optimized WASM component bytes
 /// * `Err(_)` - Compilation failed
-pub async fn compile(docker: &Docker, container_id: &str, build_target: &str) -> Result<Vec<u8>> {
+pub async fn compile(docker: &Docker, container_id: &str, build_target: &str, cargo_args: &str, preflight: &str) -> Result<Vec<u8>> {
     info!("Compiling WASI Preview 2 component: target={}", build_target);
     let start_time = std::time::Instant::now();
 
@@ -58,9 +60,10 @@ echo "🔧 TARGET: wasm32-wasip2"
 git clone -- "$REPO" repo
 cd repo
 git checkout "$COMMIT"
+{preflight}
 
 # Build WASM component with cargo
-cargo build --release --target wasm32-wasip2
+cargo build --release --target wasm32-wasip2{cargo_args}
 WASM_FILE=$(find target/wasm32-wasip2/release -maxdepth 1 -name "*.wasm" -type f | head -1)
 
 # Find compiled WASM
@@ -25,6 +25,10 @@ pub struct Config {
     pub enable_event_monitor: bool,
     pub poll_timeout_seconds: u64,
     pub scan_interval_ms: u64,
+    /// Local file the event monitor checkpoints its last processed block to
+    pub event_monitor_checkpoint_path: String,
+    /// Blocks re-scanned when resuming from a checkpoint
+    pub event_monitor_reorg_depth: u64,
 
     // Event filter (filter events by standard, function name and min version)
     pub event_filter_standard_name: String,   // default: "near-outlayer"
@@ -41,6 +45,8 @@ pub struct Config {
     pub compile_timeout_seconds: u64,
     pub compile_memory_limit_mb: u64,
     pub compile_cpu_limit: f64,
+    /// Build every source twice in clean environments and fail unless the WASM is identical
+    pub compile_verify_reproducible: bool,
 
     // WASM execution limits (defaults)
     pub default_max_instructions: u64,
@@ -52,11 +58,31 @@ pub struct Config {
     pub max_execution_seconds_cap: u64,
     /// Extra time budget for RPC calls, WASM download, result upload etc. (seconds)
     pub iteration_overhead_seconds: u64,
+    /// Time a SIGTERM'd worker gets to finish its current job before exiting (seconds)
+    pub shutdown_grace_period_seconds: u64,
+    /// Port of the Prometheus `/metrics` endpoint (None = disabled)
+    pub metrics_port: Option<u16>,
+    /// Seconds between checks of the TDX collateral on the register contract (0 = disabled)
+    pub collateral_check_interval_seconds: u64,
+    /// Warn the operator this many hours before the on-chain collateral expires
+    pub collateral_expiry_warning_hours: u64,
+    /// Compile jobs allowed to run at once on background tasks
+    pub max_concurrent_compiles: usize,
+    /// Retries of execution result submission after the first attempt
+    pub near_submit_max_retries: u32,
+    /// First retry backoff (ms), doubled per retry
+    pub near_submit_retry_base_ms: u64,
+    /// Backoff cap (ms)
+    pub near_submit_retry_max_ms: u64,
 
     // Keystore worker (optional - for secret decryption)
     pub keystore_base_url: Option<String>,
     pub keystore_auth_token: Option<String>,
     pub tee_mode: String,
+    /// Expose storage transactions (txn-*) to WASM; needs coordinator /storage/transaction
+    pub storage_transactions_enabled: bool,
+    /// Expose set-with-ttl to WASM; needs a coordinator that stores `expires_at`
+    pub storage_ttl_enabled: bool,
 
     // Worker registration mode
     // If true - use TEE registration flow (INIT_ACCOUNT_*)
@@ -83,6 +109,10 @@ pub struct Config {
     // Set to false in production to reduce log noise
     pub print_wasm_stderr: bool,
 
+    // Report executions stopped at max_execution_seconds with the "timeout" job status
+    // instead of "execution_failed"; enable only once the coordinator accepts it
+    pub report_timeout_status: bool,
+
     // Worker capabilities (what this worker can do)
     pub capabilities: WorkerCapabilities,
 
@@ -92,15 +122,31 @@ pub struct Config {
     // FastFS sender account (optional - separate account for paying FastFS storage)
     pub fastfs_sender_signer: Option<InMemorySigner>,
 
+    // Publish AOT-compiled artifacts on compile jobs and load them on execution
+    // (requires FASTFS_RECEIVER + FASTFS_SENDER_ACCOUNT_ID, whose key signs the artifacts)
+    pub aot_precompile: bool,
+
     // RPC Proxy configuration (for WASM host functions)
     #[allow(dead_code)]
     pub rpc_proxy: RpcProxyConfig,
+    /// Maximum size of the cache for block-pinned RPC results in MB (0 = disabled)
+    pub rpc_cache_max_size_mb: u64,
+    /// How long a cached RPC result is kept
+    pub rpc_cache_ttl_seconds: u64,
 
     // WASM cache configuration
     /// Maximum size of local WASM cache in MB (0 = disabled)
     pub wasm_cache_max_size_mb: u64,
     /// Directory for cached WASM files
     pub wasm_cache_dir: String,
+    /// Downloads larger than this (MB) are spooled to a temp file under `wasm_cache_dir`
+    /// and memory-mapped instead of being held on the heap
+    pub wasm_spool_threshold_mb: u64,
+
+    // Host functions disabled by operator policy (e.g. ["http", "rpc.transfer"])
+    pub disabled_host_functions: Vec<String>,
+    /// Block outbound HTTP of arks that don't declare `allowed_hosts` in their metadata
+    pub http_egress_default_deny: bool,
 }
 
 /// RPC Proxy configuration for WASM host functions
@@ -176,6 +222,8 @@ impl Config {
     /// - DEFAULT_MAX_INSTRUCTIONS: Default instruction limit (default: 10_000_000_000)
     /// - DEFAULT_MAX_MEMORY_MB: Default memory limit (default: 128)
     /// - DEFAULT_MAX_EXECUTION_SECONDS: Default execution timeout (default: 60)
+    /// - DISABLED_HOST_FUNCTIONS: Host functions to disable, JSON array or comma-separated
+    ///   (e.g. `["http", "rpc.transfer", "rpc.send_tx"]`, default: none)
     pub fn from_env() -> Result<Self> {
         // Load .env file if present
         dotenv::dotenv().ok();
@@ -265,6 +313,20 @@ impl Config {
             .parse::<u64>()
             .context("SCAN_INTERVAL_MS must be a valid number")?;
 
+        // Event monitor checkpoint - outside /tmp for the same reason as WASM_CACHE_DIR
+        let event_monitor_checkpoint_path = env::var("EVENT_MONITOR_CHECKPOINT_PATH").unwrap_or_else(|_| {
+            if let Some(cache_dir) = dirs::cache_dir() {
+                cache_dir.join("outlayer").join("event_monitor_checkpoint.json").to_string_lossy().to_string()
+            } else {
+                "/var/cache/outlayer/event_monitor_checkpoint.json".to_string()
+            }
+        });
+
+        let event_monitor_reorg_depth = env::var("EVENT_MONITOR_REORG_DEPTH")
+            .unwrap_or_else(|_| crate::block_checkpoint::DEFAULT_REORG_DEPTH.to_string())
+            .parse::<u64>()
+            .context("EVENT_MONITOR_REORG_DEPTH must be a valid number")?;
+
         // Event filter (filter events by standard, function name and min version)
         let event_filter_standard_name = env::var("EVENT_FILTER_STANDARD_NAME")
             .unwrap_or_else(|_| "near-outlayer".to_string());
@@ -300,6 +362,11 @@ impl Config {
             .parse::<f64>()
             .context("COMPILE_CPU_LIMIT must be a valid number")?;
 
+        let compile_verify_reproducible = env::var("COMPILE_VERIFY_REPRODUCIBLE")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .unwrap_or(false);
+
         let default_max_instructions = env::var("DEFAULT_MAX_INSTRUCTIONS")
             .unwrap_or_else(|_| "10000000000".to_string())
             .parse::<u64>()
@@ -325,10 +392,65 @@ impl Config {
             .parse::<u64>()
             .context("ITERATION_OVERHEAD_SECONDS must be a valid number")?;
 
+        let shutdown_grace_period_seconds = env::var("SHUTDOWN_GRACE_PERIOD_SECONDS")
+            .unwrap_or_else(|_| "120".to_string())
+            .parse::<u64>()
+            .context("SHUTDOWN_GRACE_PERIOD_SECONDS must be a valid number")?;
+
+        let metrics_port = env::var("METRICS_PORT")
+            .ok()
+            .map(|port| port.parse::<u16>())
+            .transpose()
+            .context("METRICS_PORT must be a valid port number")?;
+
+        let collateral_check_interval_seconds = env::var("COLLATERAL_CHECK_INTERVAL_SECONDS")
+            .unwrap_or_else(|_| "3600".to_string())
+            .parse::<u64>()
+            .context("COLLATERAL_CHECK_INTERVAL_SECONDS must be a valid number")?;
+
+        let collateral_expiry_warning_hours = env::var("COLLATERAL_EXPIRY_WARNING_HOURS")
+            .unwrap_or_else(|_| "72".to_string())
+            .parse::<u64>()
+            .context("COLLATERAL_EXPIRY_WARNING_HOURS must be a valid number")?;
+
+        let max_concurrent_compiles = env::var("MAX_CONCURRENT_COMPILES")
+            .unwrap_or_else(|_| "1".to_string())
+            .parse::<usize>()
+            .context("MAX_CONCURRENT_COMPILES must be a valid number")?;
+        if max_concurrent_compiles == 0 {
+            anyhow::bail!("MAX_CONCURRENT_COMPILES must be at least 1");
+        }
+
+        // Retries for submitting execution results to NEAR (transient RPC errors only)
+        let near_submit_max_retries = env::var("NEAR_SUBMIT_MAX_RETRIES")
+            .unwrap_or_else(|_| "3".to_string())
+            .parse::<u32>()
+            .context("NEAR_SUBMIT_MAX_RETRIES must be a valid number")?;
+
+        let near_submit_retry_base_ms = env::var("NEAR_SUBMIT_RETRY_BASE_MS")
+            .unwrap_or_else(|_| "500".to_string())
+            .parse::<u64>()
+            .context("NEAR_SUBMIT_RETRY_BASE_MS must be a valid number")?;
+
+        let near_submit_retry_max_ms = env::var("NEAR_SUBMIT_RETRY_MAX_MS")
+            .unwrap_or_else(|_| "8000".to_string())
+            .parse::<u64>()
+            .context("NEAR_SUBMIT_RETRY_MAX_MS must be a valid number")?;
+
         // Keystore configuration (optional)
         let keystore_base_url = env::var("KEYSTORE_BASE_URL").ok();
         let keystore_auth_token = env::var("KEYSTORE_AUTH_TOKEN").ok();
 
+        let storage_transactions_enabled = env::var("STORAGE_TRANSACTIONS_ENABLED")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .context("STORAGE_TRANSACTIONS_ENABLED must be 'true' or 'false'")?;
+
+        let storage_ttl_enabled = env::var("STORAGE_TTL_ENABLED")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .context("STORAGE_TTL_ENABLED must be 'true' or 'false'")?;
+
         let tee_mode_raw = env::var("TEE_MODE")
             .unwrap_or_else(|_| "none".to_string());
         // Remove quotes if present (Phala Cloud may add them)
@@ -361,6 +483,11 @@ impl Config {
             .parse::<bool>()
             .unwrap_or(false);
 
+        let report_timeout_status = env::var("REPORT_TIMEOUT_STATUS")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .context("REPORT_TIMEOUT_STATUS must be 'true' or 'false'")?;
+
         // Worker capabilities - what this worker can do
         let compilation_enabled = env::var("COMPILATION_ENABLED")
             .unwrap_or_else(|_| "true".to_string())
@@ -406,6 +533,12 @@ impl Config {
             None
         };
 
+        // AOT precompilation via FastFS (optional)
+        let aot_precompile = env::var("AOT_PRECOMPILE")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .unwrap_or(false);
+
         // Init account (optional - for paying gas on worker registration)
         let (init_account_id, init_account_signer) = if let Ok(init_account_str) = env::var("INIT_ACCOUNT_ID") {
             let init_account_id = AccountId::from_str(&init_account_str)
@@ -453,6 +586,16 @@ impl Config {
             allow_transactions: rpc_proxy_allow_transactions,
         };
 
+        let rpc_cache_max_size_mb = env::var("NEAR_RPC_PROXY_CACHE_MAX_SIZE_MB")
+            .unwrap_or_else(|_| "0".to_string()) // Default: disabled
+            .parse::<u64>()
+            .context("NEAR_RPC_PROXY_CACHE_MAX_SIZE_MB must be a valid number")?;
+
+        let rpc_cache_ttl_seconds = env::var("NEAR_RPC_PROXY_CACHE_TTL_SECONDS")
+            .unwrap_or_else(|_| "3600".to_string())
+            .parse::<u64>()
+            .context("NEAR_RPC_PROXY_CACHE_TTL_SECONDS must be a valid number")?;
+
         // WASM cache configuration
         let wasm_cache_max_size_mb = env::var("WASM_CACHE_MAX_SIZE_MB")
             .unwrap_or_else(|_| "100".to_string()) // Default: 100MB
@@ -471,6 +614,23 @@ impl Config {
             }
         });
 
+        let wasm_spool_threshold_mb = env::var("WASM_SPOOL_THRESHOLD_MB")
+            .unwrap_or_else(|_| "16".to_string()) // Default: 16MB
+            .parse::<u64>()
+            .context("WASM_SPOOL_THRESHOLD_MB must be a valid number")?;
+
+        // Host functions disabled by operator policy
+        let disabled_host_functions = parse_disabled_host_functions(
+            &env::var("DISABLED_HOST_FUNCTIONS").unwrap_or_default(),
+        )?;
+
+        // Arks that declare `allowed_hosts` are always limited to it; this also cuts off
+        // the ones that don't. Off by default: existing arks declare nothing yet.
+        let http_egress_default_deny = env::var("HTTP_EGRESS_DEFAULT_DENY")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .context("HTTP_EGRESS_DEFAULT_DENY must be 'true' or 'false'")?;
+
         Ok(Self {
             api_base_url,
             api_auth_token,
@@ -485,6 +645,8 @@ impl Config {
             enable_event_monitor,
             poll_timeout_seconds,
             scan_interval_ms,
+            event_monitor_checkpoint_path,
+            event_monitor_reorg_depth,
             event_filter_standard_name,
             event_filter_function_name,
             event_filter_min_version,
@@ -493,25 +655,42 @@ impl Config {
             compile_timeout_seconds,
             compile_memory_limit_mb,
             compile_cpu_limit,
+            compile_verify_reproducible,
             default_max_instructions,
             default_max_memory_mb,
             default_max_execution_seconds,
             max_execution_seconds_cap,
             iteration_overhead_seconds,
+            shutdown_grace_period_seconds,
+            metrics_port,
+            collateral_check_interval_seconds,
+            collateral_expiry_warning_hours,
+            max_concurrent_compiles,
+            near_submit_max_retries,
+            near_submit_retry_base_ms,
+            near_submit_retry_max_ms,
             keystore_base_url,
             keystore_auth_token,
             tee_mode,
+            storage_transactions_enabled,
+            storage_ttl_enabled,
             use_tee_registration,
             init_account_id,
             init_account_signer,
             save_system_hidden_logs_to_debug,
             print_wasm_stderr,
+            report_timeout_status,
             capabilities,
             fastfs_receiver,
             fastfs_sender_signer,
+            aot_precompile,
             rpc_proxy,
+            rpc_cache_max_size_mb,
+            rpc_cache_ttl_seconds,
             wasm_cache_max_size_mb,
             wasm_cache_dir,
+            wasm_spool_threshold_mb,
+            disabled_host_functions,
         })
     }
 
@@ -579,6 +758,15 @@ impl Config {
         self.operator_signer.as_ref().expect("Operator signer not set - registration must have failed")
     }
 
+    /// Retry policy for execution result submission
+    pub fn near_submit_retry_policy(&self) -> crate::tx_retry::RetryPolicy {
+        crate::tx_retry::RetryPolicy {
+            max_retries: self.near_submit_max_retries,
+            base_delay: std::time::Duration::from_millis(self.near_submit_retry_base_ms),
+            max_delay: std::time::Duration::from_millis(self.near_submit_retry_max_ms),
+        }
+    }
+
     /// Validate configuration
     pub fn validate(&self) -> Result<()> {
         if self.api_base_url.is_empty() {
@@ -609,6 +797,13 @@ impl Config {
             anyhow::bail!("Compile CPU limit must be positive");
         }
 
+        if self.aot_precompile && (self.fastfs_receiver.is_none() || self.fastfs_sender_signer.is_none()) {
+            anyhow::bail!(
+                "AOT_PRECOMPILE=true requires FASTFS_RECEIVER and FASTFS_SENDER_ACCOUNT_ID \
+                 (the FastFS sender key signs precompiled artifacts)"
+            );
+        }
+
         // Security check: native compilation mode must be isolated
         // because malicious build scripts can steal secrets via environment variables
         if self.compilation_mode == "native" {
@@ -629,6 +824,14 @@ impl Config {
                      Remove INIT_ACCOUNT_PRIVATE_KEY from .env for native compiler workers."
                 );
             }
+            if self.aot_precompile {
+                anyhow::bail!(
+                    "Security error: Native compilation mode (COMPILATION_MODE=native) \
+                     must NOT have AOT_PRECOMPILE=true. \
+                     Malicious build scripts could read FASTFS_SENDER_PRIVATE_KEY \
+                     and sign forged native code for executors."
+                );
+            }
             if self.operator_signer.is_some() {
                 anyhow::bail!(
                     "Security error: Native compilation mode (COMPILATION_MODE=native) \
@@ -643,6 +846,23 @@ impl Config {
     }
 }
 
+/// Parse DISABLED_HOST_FUNCTIONS: JSON array (`["http","rpc.transfer"]`) or comma-separated list
+fn parse_disabled_host_functions(raw: &str) -> Result<Vec<String>> {
+    let raw = raw.trim();
+    let entries: Vec<String> = if raw.starts_with('[') {
+        serde_json::from_str(raw)
+            .context("DISABLED_HOST_FUNCTIONS must be a JSON array of strings")?
+    } else {
+        raw.split(',').map(|s| s.trim().to_string()).collect()
+    };
+    let entries: Vec<String> = entries.into_iter().filter(|s| !s.is_empty()).collect();
+
+    // Validate entries early so a typo fails at startup, not at execution time
+    crate::executor::HostFunctionPolicy::new(&entries)?;
+
+    Ok(entries)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -664,6 +884,30 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn test_aot_precompile_validation() {
+        let mut config = create_test_config();
+        config.aot_precompile = true;
+        assert!(config.validate().is_err());
+
+        let secret_key = SecretKey::from_seed(near_crypto::KeyType::ED25519, "fastfs");
+        config.fastfs_receiver = Some("fastfs.testnet".to_string());
+        config.fastfs_sender_signer = Some(InMemorySigner {
+            account_id: "sender.testnet".parse().unwrap(),
+            public_key: secret_key.public_key(),
+            secret_key,
+        });
+        assert!(config.validate().is_ok());
+
+        // Native compilers must not hold the artifact signing key
+        config.compilation_mode = "native".to_string();
+        config.capabilities.execution = false;
+        config.operator_signer = None;
+        assert!(config.validate().is_err());
+        config.aot_precompile = false;
+        assert!(config.validate().is_ok());
+    }
+
     fn create_test_config() -> Config {
         Config {
             api_base_url: "http://localhost:8080".to_string(),
@@ -686,6 +930,8 @@ mod tests {
             enable_event_monitor: false,
             poll_timeout_seconds: 60,
             scan_interval_ms: 0,
+            event_monitor_checkpoint_path: "/tmp/event_monitor_checkpoint_test.json".to_string(),
+            event_monitor_reorg_depth: 3,
             event_filter_standard_name: "near-outlayer".to_string(),
             event_filter_function_name: "execution_requested".to_string(),
             event_filter_min_version: None,
@@ -694,33 +940,66 @@ mod tests {
             compile_timeout_seconds: 300,
             compile_memory_limit_mb: 2048,
             compile_cpu_limit: 2.0,
+            compile_verify_reproducible: false,
             default_max_instructions: 10_000_000_000,
             default_max_memory_mb: 128,
             default_max_execution_seconds: 60,
             max_execution_seconds_cap: 180,
             iteration_overhead_seconds: 60,
+            shutdown_grace_period_seconds: 120,
+            metrics_port: None,
+            collateral_check_interval_seconds: 3600,
+            collateral_expiry_warning_hours: 72,
+            max_concurrent_compiles: 1,
+            near_submit_max_retries: 3,
+            near_submit_retry_base_ms: 500,
+            near_submit_retry_max_ms: 8000,
             keystore_base_url: None,
             keystore_auth_token: None,
             tee_mode: "none".to_string(),
+            storage_transactions_enabled: false,
+            storage_ttl_enabled: false,
             use_tee_registration: false, // Test mode: use legacy with OPERATOR_PRIVATE_KEY
             init_account_id: None,
             init_account_signer: None,
             save_system_hidden_logs_to_debug: true, // Default: enabled for debugging
             print_wasm_stderr: false,
+            report_timeout_status: false,
             capabilities: WorkerCapabilities {
                 compilation: true,
                 execution: true,
             },
             fastfs_receiver: None,
             fastfs_sender_signer: None,
+            aot_precompile: false,
             rpc_proxy: RpcProxyConfig {
                 enabled: true,
                 rpc_url: None,
                 max_calls_per_execution: 100,
                 allow_transactions: true,
             },
+            rpc_cache_max_size_mb: 0,
+            rpc_cache_ttl_seconds: 3600,
             wasm_cache_max_size_mb: 100,
             wasm_cache_dir: "/tmp/wasm_cache_test".to_string(),
+            wasm_spool_threshold_mb: 16,
+            disabled_host_functions: vec![],
+            http_egress_default_deny: false,
         }
     }
+
+    #[test]
+    fn test_parse_disabled_host_functions() {
+        assert!(parse_disabled_host_functions("").unwrap().is_empty());
+        assert_eq!(
+            parse_disabled_host_functions(r#"["http", "rpc.transfer", "rpc.send_tx"]"#).unwrap(),
+            vec!["http", "rpc.transfer", "rpc.send_tx"]
+        );
+        assert_eq!(
+            parse_disabled_host_functions("http, rpc.transfer").unwrap(),
+            vec!["http", "rpc.transfer"]
+        );
+        assert!(parse_disabled_host_functions("htp").is_err());
+        assert!(parse_disabled_host_functions("[\"http\"").is_err());
+    }
 }
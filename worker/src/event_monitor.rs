@@ -5,6 +5,7 @@ use near_primitives::views::QueryRequest;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
@@ -12,6 +13,7 @@ use tokio::time::sleep;
 use tracing::{error, info, warn};
 
 use crate::api_client::{ApiClient, CreateTaskParams, ResourceLimits as ApiResourceLimits};
+use crate::block_checkpoint::{self, BlockCheckpoint};
 
 /// Error indicating block is not yet indexed by neardata
 /// This should be handled differently from other errors - we should wait, not skip
@@ -143,6 +145,12 @@ pub struct RequestData {
     /// Project ID for project-based secrets (e.g., "alice.near/my-app")
     #[serde(default)]
     pub project_id: Option<String>,
+    /// Queue tier paid for on-chain (0 = default)
+    #[serde(default)]
+    pub priority: u8,
+    /// Media type of input_data declared by the caller
+    #[serde(default)]
+    pub input_content_type: Option<String>,
 }
 
 /// Code source - either GitHub repo or pre-compiled WASM URL
@@ -164,6 +172,10 @@ pub struct GitHubSource {
     pub repo: String,
     pub commit: String,
     pub build_target: Option<String>,
+    #[serde(default)]
+    pub package: Option<String>,
+    #[serde(default)]
+    pub features: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -223,6 +235,8 @@ impl CodeSource {
                 repo: github.repo.clone(),
                 commit: github.commit.clone(),
                 build_target: github.build_target.clone().unwrap_or_else(|| "wasm32-wasi".to_string()),
+                package: github.package.clone(),
+                features: github.features.clone(),
             },
             CodeSource::WasmUrl { wasm_url } => crate::api_client::CodeSource::WasmUrl {
                 url: wasm_url.url.clone(),
@@ -238,6 +252,10 @@ pub struct ResourceLimits {
     pub max_instructions: u64,
     pub max_memory_mb: u32,
     pub max_execution_seconds: u64,
+    #[serde(default = "crate::api_client::default_max_output_bytes")]
+    pub max_output_bytes: u32,
+    #[serde(default = "crate::api_client::default_max_log_bytes")]
+    pub max_log_bytes: u32,
 }
 
 /// Block data from neardata.xyz API
@@ -329,6 +347,8 @@ pub struct EventMonitor {
     event_filter_min_version: Option<(u64, u64, u64)>, // Parsed semver (major, minor, patch)
     /// Shared block height for heartbeat reporting to coordinator
     shared_block_height: Arc<AtomicU64>,
+    /// Last processed block and recently handled request ids, persisted per block
+    checkpoint: BlockCheckpoint,
 }
 
 impl EventMonitor {
@@ -368,6 +388,8 @@ impl EventMonitor {
         event_filter_function_name: String,
         event_filter_min_version: Option<String>,
         shared_block_height: Arc<AtomicU64>,
+        checkpoint_path: PathBuf,
+        reorg_depth: u64,
     ) -> Result<Self> {
         let http_client = reqwest::Client::builder()
             .timeout(Duration::from_secs(10))
@@ -378,45 +400,51 @@ impl EventMonitor {
         let rpc_client = JsonRpcClient::connect(&near_rpc_url);
 
         // Determine starting block:
-        // 1. Try saved cursor from coordinator (persisted across restarts)
-        // 2. Fall back to START_BLOCK_HEIGHT env var
+        // 1. Last processed block from the local checkpoint or the coordinator cursor
+        //    (whichever is further), rewound by reorg_depth, but not before START_BLOCK_HEIGHT
+        // 2. Otherwise START_BLOCK_HEIGHT env var
         // 3. If 0, fetch latest block from NEAR RPC
-        // Max blocks behind before we skip to latest (100 blocks ≈ 2 minutes)
+        // Max blocks behind before we skip to latest (1000 blocks ≈ 20 minutes)
         const MAX_CATCHUP_BLOCKS: u64 = 1000;
 
-        let current_block = match api_client.get_block_cursor().await {
-            Ok(Some(saved_block)) if saved_block > 0 => {
-                // Check if cursor is too far behind — skip to latest instead of slow catch-up
+        let checkpoint = BlockCheckpoint::load(checkpoint_path, reorg_depth);
+
+        // The coordinator cursor is the next block to scan (heartbeat block height)
+        let cursor_last_processed = match api_client.get_block_cursor().await {
+            Ok(Some(saved_block)) if saved_block > 0 => Some(saved_block - 1),
+            Ok(_) => None,
+            Err(e) => {
+                warn!("Failed to load block cursor (using local checkpoint only): {}", e);
+                None
+            }
+        };
+        let last_processed = checkpoint.last_processed_block().max(cursor_last_processed);
+
+        let current_block = match block_checkpoint::resume_block(start_block, last_processed, reorg_depth) {
+            Some(resume_block) => {
+                // Check if checkpoint is too far behind — skip to latest instead of slow catch-up
                 let latest = Self::fetch_latest_block(&http_client, &near_rpc_url).await
-                    .unwrap_or(saved_block);
-                if latest > saved_block + MAX_CATCHUP_BLOCKS {
+                    .unwrap_or(resume_block);
+                if latest > resume_block + MAX_CATCHUP_BLOCKS {
                     info!(
-                        "⏩ Saved cursor {} is {} blocks behind latest {}. Skipping to latest.",
-                        saved_block, latest - saved_block, latest
+                        "⏩ Checkpoint {} is {} blocks behind latest {}. Skipping to latest.",
+                        resume_block, latest - resume_block, latest
                     );
                     latest
                 } else {
                     info!(
-                        "📌 Resuming from saved block cursor: {} ({} blocks behind latest)",
-                        saved_block, latest.saturating_sub(saved_block)
+                        "📌 Resuming from block {} (last processed {:?}, re-scanning up to {} blocks; {} blocks behind latest)",
+                        resume_block, last_processed, reorg_depth, latest.saturating_sub(resume_block)
                     );
-                    saved_block
+                    resume_block
                 }
             }
-            Ok(_) => {
+            None => {
                 if start_block == 0 {
                     info!("START_BLOCK_HEIGHT=0, fetching latest block from NEAR RPC...");
                     Self::fetch_latest_block(&http_client, &near_rpc_url).await?
                 } else {
-                    info!("No saved block cursor, using START_BLOCK_HEIGHT={}", start_block);
-                    start_block
-                }
-            }
-            Err(e) => {
-                warn!("Failed to load block cursor (falling back to START_BLOCK_HEIGHT): {}", e);
-                if start_block == 0 {
-                    Self::fetch_latest_block(&http_client, &near_rpc_url).await?
-                } else {
+                    info!("No checkpoint or saved block cursor, using START_BLOCK_HEIGHT={}", start_block);
                     start_block
                 }
             }
@@ -452,6 +480,7 @@ impl EventMonitor {
             event_filter_function_name,
             event_filter_min_version: parsed_min_version,
             shared_block_height,
+            checkpoint,
         })
     }
 
@@ -571,6 +600,10 @@ impl EventMonitor {
                         }
                     }
 
+                    if let Err(e) = self.checkpoint.mark_processed(self.current_block) {
+                        warn!("⚠️  Failed to save event monitor checkpoint: {}", e);
+                    }
+
                     // Move to next block
                     self.current_block += 1;
                     self.shared_block_height.store(self.current_block, Ordering::Relaxed);
@@ -983,7 +1016,7 @@ impl EventMonitor {
     }
 
     /// Handle execution_requested event by creating task in coordinator
-    async fn handle_execution_requested(&self, event: ExecutionRequestedEvent) -> Result<()> {
+    async fn handle_execution_requested(&mut self, event: ExecutionRequestedEvent) -> Result<()> {
         // Log raw event data for debugging
         tracing::debug!("📋 Raw request_data JSON: {}", event.request_data);
 
@@ -991,6 +1024,15 @@ impl EventMonitor {
         let request_data: RequestData = serde_json::from_str(&event.request_data)
             .context("Failed to parse request_data JSON")?;
 
+        // Already handled in a block that was re-scanned on resume or reorged back in
+        if self.checkpoint.is_seen(request_data.request_id) {
+            info!(
+                "ℹ️  Skipping request_id={} in block {}: already handled",
+                request_data.request_id, event.block_height
+            );
+            return Ok(());
+        }
+
         info!(
             "Creating task for execution request: request_id={} source={} sender={} response_format={:?} project_uuid={:?} project_id={:?}",
             request_data.request_id,
@@ -1038,6 +1080,8 @@ impl EventMonitor {
                 max_instructions: request_data.resource_limits.max_instructions,
                 max_memory_mb: request_data.resource_limits.max_memory_mb,
                 max_execution_seconds: request_data.resource_limits.max_execution_seconds,
+                max_output_bytes: request_data.resource_limits.max_output_bytes,
+                max_log_bytes: request_data.resource_limits.max_log_bytes,
             },
             input_data,
             secrets_ref: request_data.secrets_ref.clone(),
@@ -1051,10 +1095,12 @@ impl EventMonitor {
             store_on_fastfs: request_data.store_on_fastfs,
             project_uuid: request_data.project_uuid.clone(),
             project_id: request_data.project_id.clone(),
+            priority: request_data.priority,
+            input_content_type: request_data.input_content_type.clone(),
         };
 
-        info!("📤 Sending task to coordinator: project_uuid={:?} project_id={:?}",
-            request_data.project_uuid, request_data.project_id);
+        info!("📤 Sending task to coordinator: project_uuid={:?} project_id={:?} priority={}",
+            request_data.project_uuid, request_data.project_id, request_data.priority);
 
         match self.api_client.create_task(params).await
         {
@@ -1074,6 +1120,7 @@ impl EventMonitor {
             }
         }
 
+        self.checkpoint.record_request(event.block_height, request_data.request_id);
         Ok(())
     }
 
@@ -0,0 +1,103 @@
+//! Output format declared by the ark with `outlayer::env::set_output_format`
+//!
+//! The caller picks `response_format`, but the ark knows what it wrote. The SDK writes an
+//! `OUTLAYER_OUTPUT_FORMAT:<Format>` line to stderr; the last valid one is the declaration
+//! and all of them are removed from the logs returned to the caller.
+//!
+//! The declaration only changes the outcome for a `Json` request: if the ark declares any
+//! other format, the execution fails with an error saying so instead of a JSON parse error
+//! on its output. `Text`, `Bytes` and `Base64` requests return the output as-is; `Cbor` and
+//! `MessagePack` outputs are validated either way.
+
+use crate::api_client::ResponseFormat;
+
+/// Stderr line prefix written by `env::set_output_format`
+pub const OUTPUT_FORMAT_PREFIX: &str = "OUTLAYER_OUTPUT_FORMAT:";
+
+/// Take the declared format out of the execution logs
+pub fn take(logs: &mut Vec<String>) -> Option<ResponseFormat> {
+    let mut declared = None;
+    let mut kept: Vec<String> = Vec::with_capacity(logs.len());
+    for line in logs.drain(..) {
+        let Some(name) = line.trim().strip_prefix(OUTPUT_FORMAT_PREFIX) else {
+            kept.push(line);
+            continue;
+        };
+        if let Some(format) = parse_format(name) {
+            declared = Some(format);
+        }
+        // `set_output_format` starts the sentinel on a fresh line, which may leave an empty one
+        if kept.last().is_some_and(|l| l.is_empty()) {
+            kept.pop();
+        }
+    }
+    *logs = kept;
+    declared
+}
+
+/// Error for output the ark declared in a format the caller can't get, if any
+pub fn mismatch(requested: &ResponseFormat, declared: Option<&ResponseFormat>) -> Option<String> {
+    match (requested, declared) {
+        (ResponseFormat::Json, Some(declared)) if *declared != ResponseFormat::Json => Some(format!(
+            "Caller requested Json output but the ark declared its output as {:?}",
+            declared
+        )),
+        _ => None,
+    }
+}
+
+fn parse_format(name: &str) -> Option<ResponseFormat> {
+    match name {
+        "Bytes" => Some(ResponseFormat::Bytes),
+        "Text" => Some(ResponseFormat::Text),
+        "Json" => Some(ResponseFormat::Json),
+        "Cbor" => Some(ResponseFormat::Cbor),
+        "MessagePack" => Some(ResponseFormat::MessagePack),
+        "Base64" => Some(ResponseFormat::Base64),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &[&str]) -> Vec<String> {
+        text.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn test_last_declaration_wins_and_is_removed_from_logs() {
+        let mut logs = lines(&[
+            "fetching prices",
+            "",
+            "OUTLAYER_OUTPUT_FORMAT:Json",
+            "got 3 prices",
+            "",
+            "OUTLAYER_OUTPUT_FORMAT:Text",
+        ]);
+        assert_eq!(take(&mut logs), Some(ResponseFormat::Text));
+        assert_eq!(logs, lines(&["fetching prices", "got 3 prices"]));
+    }
+
+    #[test]
+    fn test_unknown_format_is_ignored() {
+        let mut logs = lines(&["OUTLAYER_OUTPUT_FORMAT:Json", "OUTLAYER_OUTPUT_FORMAT:Yaml"]);
+        assert_eq!(take(&mut logs), Some(ResponseFormat::Json));
+        assert!(logs.is_empty());
+
+        let mut undeclared = lines(&["hello"]);
+        assert_eq!(take(&mut undeclared), None);
+        assert_eq!(undeclared, lines(&["hello"]));
+    }
+
+    #[test]
+    fn test_only_json_requests_reject_other_declarations() {
+        assert!(mismatch(&ResponseFormat::Json, Some(&ResponseFormat::Text)).is_some());
+        assert!(mismatch(&ResponseFormat::Json, Some(&ResponseFormat::Bytes)).is_some());
+        assert!(mismatch(&ResponseFormat::Json, Some(&ResponseFormat::Json)).is_none());
+        assert!(mismatch(&ResponseFormat::Json, None).is_none());
+        assert!(mismatch(&ResponseFormat::Text, Some(&ResponseFormat::Bytes)).is_none());
+        assert!(mismatch(&ResponseFormat::Bytes, Some(&ResponseFormat::Json)).is_none());
+    }
+}
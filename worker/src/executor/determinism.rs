@@ -0,0 +1,122 @@
+//! Deterministic execution settings shared by the P1 and P2 executors
+//!
+//! The same WASM + input must yield byte-identical output on every worker,
+//! so results can be re-executed and attested. WASM has no ASLR: heap
+//! addresses are offsets into linear memory, which wasmtime always hands out
+//! zero-filled. They depend only on the guest allocator's call sequence,
+//! provided memory growth itself behaves identically everywhere:
+//!
+//! - Growth is capped at `max_memory_mb` through a store limiter, so
+//!   `memory.grow` fails at the same point on every worker instead of
+//!   depending on how much RAM the host happens to have.
+//! - NaN bit patterns are canonicalized and relaxed SIMD uses its
+//!   deterministic lowering, so float results don't vary by CPU.
+//!
+//! ## No host-side allocator
+//!
+//! The executor does not supply a "deterministic allocator". The allocator
+//! (dlmalloc, wee_alloc, ...) is compiled into the guest and only manages
+//! its own linear memory; the host has no hook to replace it. Given
+//! zero-filled memory and identical growth it is already deterministic:
+//! `wasi-examples/determinism-ark` prints `Box`/`Vec` addresses and
+//! `worker/tests/test_wasm_execution.rs` runs it twice through the executor
+//! to check they match. What still varies is anything the guest reads from
+//! the host (clock, random, HTTP), which is not this module's concern.
+//!
+//! ## Behavior changes on upgrade
+//!
+//! - `max_memory_mb` used to be advisory: arks could grow past it. It is now
+//!   enforced, so an ark that relied on more memory sees `memory.grow` fail
+//!   (usually an allocation abort) until its caller raises `max_memory_mb`.
+//!   `peak_memory_bytes` in the execution result shows what a run needs.
+//! - The engine settings change `Engine::precompile_compatibility_hash`.
+//!   Published AOT artifacts (`precompiled`) and `CompiledCache` entries from
+//!   older workers no longer match: workers fall back to compiling from the
+//!   WASM bytes (a one-time JIT cost per WASM) until compile workers
+//!   republish artifacts for the new engine tag.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use wasmtime::{Config, ResourceLimiter, StoreLimits, StoreLimitsBuilder};
+
+use crate::api_client::ResourceLimits;
+
+/// Apply engine-level determinism settings
+pub(crate) fn configure_engine(config: &mut Config) {
+    config.cranelift_nan_canonicalization(true);
+    config.relaxed_simd_deterministic(true);
+}
+
+/// Store limiter capping linear memory growth at `max_memory_mb`
+///
+/// Growth that is allowed is recorded in `peak`, so the execution can report how much
+/// memory it actually needed.
+pub(crate) fn memory_limits(limits: &ResourceLimits, peak: &PeakMemory) -> MemoryLimiter {
+    MemoryLimiter {
+        limits: StoreLimitsBuilder::new()
+            .memory_size((limits.max_memory_mb as usize) * 1024 * 1024)
+            .build(),
+        peak: peak.clone(),
+    }
+}
+
+/// High-water mark of guest linear memory in bytes, reported as `peak_memory_bytes`
+///
+/// Shared with the store's limiter, so it is still readable when the execution fails
+/// (e.g. the guest aborted after `memory.grow` hit `max_memory_mb`).
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PeakMemory(Arc<AtomicU64>);
+
+impl PeakMemory {
+    pub(crate) fn bytes(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn record(&self, bytes: usize) {
+        self.0.fetch_max(bytes as u64, Ordering::Relaxed);
+    }
+}
+
+/// [`StoreLimits`] that also tracks [`PeakMemory`]
+///
+/// wasmtime calls `memory_growing` for a memory's initial size too, so a guest that
+/// never grows still reports what it was instantiated with.
+pub(crate) struct MemoryLimiter {
+    limits: StoreLimits,
+    peak: PeakMemory,
+}
+
+impl ResourceLimiter for MemoryLimiter {
+    fn memory_growing(&mut self, current: usize, desired: usize, maximum: Option<usize>) -> anyhow::Result<bool> {
+        let allowed = self.limits.memory_growing(current, desired, maximum)?;
+        if allowed {
+            self.peak.record(desired);
+        }
+        Ok(allowed)
+    }
+
+    fn memory_grow_failed(&mut self, error: anyhow::Error) -> anyhow::Result<()> {
+        self.limits.memory_grow_failed(error)
+    }
+
+    fn table_growing(&mut self, current: usize, desired: usize, maximum: Option<usize>) -> anyhow::Result<bool> {
+        self.limits.table_growing(current, desired, maximum)
+    }
+
+    fn table_grow_failed(&mut self, error: anyhow::Error) -> anyhow::Result<()> {
+        self.limits.table_grow_failed(error)
+    }
+
+    fn instances(&self) -> usize {
+        self.limits.instances()
+    }
+
+    fn tables(&self) -> usize {
+        self.limits.tables()
+    }
+
+    fn memories(&self) -> usize {
+        self.limits.memories()
+    }
+}
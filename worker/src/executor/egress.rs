@@ -0,0 +1,238 @@
+//! Outbound HTTP allow-list declared by the ark
+//!
+//! Arks list the hosts they talk to with `allowed_hosts` in `outlayer::metadata!`, which
+//! ends up as an `allowed_hosts=` line in the `outlayer-metadata` custom section of the
+//! component. When a list is declared, wasi-http requests to any other host fail with
+//! [`BLOCKED_MESSAGE`] before a connection is made; an empty list blocks all outbound HTTP.
+//!
+//! Entries are `host`, `host:port` or `*.domain` (any subdomain of `domain`, not `domain`
+//! itself). Without a port, any port matches.
+//!
+//! An ark that declares nothing keeps unrestricted egress, unless the operator sets
+//! `HTTP_EGRESS_DEFAULT_DENY=true`, which blocks its outbound HTTP entirely. The SSRF guard
+//! in `wasi_p2` applies either way.
+
+use anyhow::Result;
+use wasmparser::{Parser, Payload};
+
+/// Custom section written by the SDK `metadata!` macro
+pub const METADATA_SECTION: &str = "outlayer-metadata";
+
+/// Error message returned to the guest for a request outside its allow-list
+pub const BLOCKED_MESSAGE: &str = "destination not in the ark's allowed_hosts";
+
+/// One `allowed_hosts` entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HostRule {
+    /// Lowercase host, without the `*.` of a wildcard entry
+    host: String,
+    /// Entry was `*.host`
+    wildcard: bool,
+    port: Option<u16>,
+}
+
+impl HostRule {
+    fn parse(entry: &str) -> Result<Self> {
+        let entry = entry.trim().to_lowercase();
+        let (host, port) = match entry.rsplit_once(':') {
+            Some((host, port)) => {
+                let port = port
+                    .parse::<u16>()
+                    .map_err(|_| anyhow::anyhow!("Invalid port in allowed_hosts entry '{}'", entry))?;
+                (host, Some(port))
+            }
+            None => (entry.as_str(), None),
+        };
+        let (host, wildcard) = match host.strip_prefix("*.") {
+            Some(domain) => (domain, true),
+            None => (host, false),
+        };
+        let valid = !host.is_empty()
+            && host.split('.').all(|label| {
+                !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            });
+        if !valid {
+            anyhow::bail!(
+                "Invalid allowed_hosts entry '{}': expected host, host:port or *.domain",
+                entry
+            );
+        }
+        Ok(Self {
+            host: host.to_string(),
+            wildcard,
+            port,
+        })
+    }
+
+    fn matches(&self, host: &str, port: u16) -> bool {
+        if self.port.is_some_and(|p| p != port) {
+            return false;
+        }
+        if self.wildcard {
+            host.strip_suffix(self.host.as_str())
+                .is_some_and(|sub| sub.len() > 1 && sub.ends_with('.'))
+        } else {
+            host == self.host
+        }
+    }
+}
+
+/// Hosts an execution may send HTTP requests to
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EgressPolicy {
+    /// `None` = unrestricted
+    allowed: Option<Vec<HostRule>>,
+}
+
+impl EgressPolicy {
+    /// Policy of a component: its declared `allowed_hosts`, or `default_deny` when it
+    /// declares none
+    ///
+    /// Fails if the declared list has a malformed entry, so a typo doesn't leave the ark
+    /// without the network access it expects.
+    pub fn for_component(wasm_bytes: &[u8], default_deny: bool) -> Result<Self> {
+        match declared_hosts(wasm_bytes) {
+            Some(entries) => Self::allow_list(&entries),
+            None if default_deny => Ok(Self { allowed: Some(Vec::new()) }),
+            None => Ok(Self::default()),
+        }
+    }
+
+    fn allow_list(entries: &[String]) -> Result<Self> {
+        let rules = entries.iter().map(|e| HostRule::parse(e)).collect::<Result<Vec<_>>>()?;
+        Ok(Self { allowed: Some(rules) })
+    }
+
+    /// True if outbound HTTP is limited to an allow-list
+    pub fn is_restricted(&self) -> bool {
+        self.allowed.is_some()
+    }
+
+    /// True if a request to `host:port` may be sent
+    pub fn allows(&self, host: &str, port: u16) -> bool {
+        let Some(rules) = &self.allowed else {
+            return true;
+        };
+        let host = host.trim_end_matches('.').to_lowercase();
+        rules.iter().any(|rule| rule.matches(&host, port))
+    }
+}
+
+/// `allowed_hosts` entries from the component's metadata section, if it declares them
+fn declared_hosts(wasm_bytes: &[u8]) -> Option<Vec<String>> {
+    section_list(&metadata_section(wasm_bytes)?, "allowed_hosts")
+}
+
+/// The component's `outlayer-metadata` custom section, if it has one
+pub(super) fn metadata_section(wasm_bytes: &[u8]) -> Option<String> {
+    // parse_all walks nested core modules too, where the SDK's section ends up
+    let section = Parser::new(0).parse_all(wasm_bytes).find_map(|payload| match payload {
+        Ok(Payload::CustomSection(reader)) if reader.name() == METADATA_SECTION => {
+            Some(reader.data().to_vec())
+        }
+        _ => None,
+    })?;
+    Some(String::from_utf8_lossy(&section).into_owned())
+}
+
+/// `key=` line of a metadata section (`key=value` lines, comma-separated list)
+pub(super) fn section_list(section: &str, key: &str) -> Option<Vec<String>> {
+    let values = section
+        .lines()
+        .find_map(|line| line.strip_prefix(key).and_then(|rest| rest.strip_prefix('=')))?;
+    Some(
+        values
+            .split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empty core module with one custom section
+    fn module_with_section(name: &str, data: &str) -> Vec<u8> {
+        let mut payload = vec![name.len() as u8];
+        payload.extend_from_slice(name.as_bytes());
+        payload.extend_from_slice(data.as_bytes());
+
+        assert!(payload.len() < 128, "size must fit one LEB128 byte");
+
+        let mut module = b"\0asm\x01\0\0\0".to_vec();
+        module.push(0); // custom section id
+        module.push(payload.len() as u8);
+        module.extend(payload);
+        module
+    }
+
+    fn policy(entries: &[&str]) -> EgressPolicy {
+        EgressPolicy::allow_list(&entries.iter().map(|e| e.to_string()).collect::<Vec<_>>()).unwrap()
+    }
+
+    #[test]
+    fn test_reads_declared_hosts_from_metadata_section() {
+        let wasm = module_with_section(
+            METADATA_SECTION,
+            "project=alice.near/oracle\nversion=1.0.0\ntags=\nallowed_hosts=api.coingecko.com,*.binance.com,\n",
+        );
+        assert_eq!(
+            declared_hosts(&wasm),
+            Some(vec!["api.coingecko.com".to_string(), "*.binance.com".to_string()])
+        );
+
+        let empty_list = module_with_section(METADATA_SECTION, "project=a.near/x\nallowed_hosts=\n");
+        assert_eq!(declared_hosts(&empty_list), Some(vec![]));
+
+        let undeclared = module_with_section(METADATA_SECTION, "project=a.near/x\nversion=1.0.0\n");
+        assert_eq!(declared_hosts(&undeclared), None);
+        assert_eq!(declared_hosts(&module_with_section("name", "allowed_hosts=a.com")), None);
+    }
+
+    #[test]
+    fn test_default_deny_only_applies_without_declaration() {
+        let undeclared = module_with_section("other", "");
+        assert!(!EgressPolicy::for_component(&undeclared, false).unwrap().is_restricted());
+
+        let denied = EgressPolicy::for_component(&undeclared, true).unwrap();
+        assert!(denied.is_restricted());
+        assert!(!denied.allows("example.com", 443));
+
+        let declared = module_with_section(METADATA_SECTION, "allowed_hosts=example.com\n");
+        let policy = EgressPolicy::for_component(&declared, true).unwrap();
+        assert!(policy.allows("example.com", 443));
+        assert!(!policy.allows("example.org", 443));
+    }
+
+    #[test]
+    fn test_host_matching() {
+        let policy = policy(&["api.coingecko.com", "*.binance.com", "rpc.example.org:8443"]);
+
+        assert!(policy.allows("api.coingecko.com", 443));
+        assert!(policy.allows("API.CoinGecko.com.", 80));
+        assert!(!policy.allows("coingecko.com", 443));
+        assert!(!policy.allows("api.coingecko.com.evil.io", 443));
+
+        assert!(policy.allows("api.binance.com", 443));
+        assert!(policy.allows("a.b.binance.com", 443));
+        assert!(!policy.allows("binance.com", 443));
+        assert!(!policy.allows("notbinance.com", 443));
+
+        assert!(policy.allows("rpc.example.org", 8443));
+        assert!(!policy.allows("rpc.example.org", 443));
+
+        assert!(EgressPolicy::default().allows("anything.io", 1));
+    }
+
+    #[test]
+    fn test_rejects_malformed_entries() {
+        for entry in ["", "*.", "http://a.com", "a.com:port", "a.com:70000", "a..com", "*.*.a.com", "[::1]"] {
+            assert!(HostRule::parse(entry).is_err(), "accepted {:?}", entry);
+        }
+        let wasm = module_with_section(METADATA_SECTION, "allowed_hosts=ok.com,bad host\n");
+        assert!(EgressPolicy::for_component(&wasm, false).is_err());
+    }
+}
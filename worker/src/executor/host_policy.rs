@@ -0,0 +1,261 @@
+//! Operator policy for disabling host functions
+//!
+//! Operators can run a restricted OutLayer by listing host functions in
+//! `DISABLED_HOST_FUNCTIONS`. Entries are either a whole group (`"http"`, `"rpc"`,
+//! `"storage"`, `"vrf"`, `"ckd"`, `"payment"`, `"wallet"`) or a single function inside a
+//! group (`"rpc.transfer"`, `"rpc.send_tx"`). Underscores and hyphens are
+//! interchangeable, so `rpc.send_tx` matches the WIT function `send-tx`.
+//!
+//! Disabled functions stay importable (the component still instantiates), but
+//! calling one traps with "host function disabled by operator policy".
+//! Disabling `http` makes every outgoing wasi-http request fail with the same message.
+//!
+//! `HTTP_EGRESS_DEFAULT_DENY` blocks outgoing HTTP of arks that don't declare an
+//! `allowed_hosts` list (see `egress`).
+
+use anyhow::Result;
+use std::collections::HashSet;
+use wasmtime::component::types::ComponentItem;
+use wasmtime::component::{Component, Linker};
+use wasmtime::Engine;
+
+/// Error message returned to the guest when it calls a disabled host function
+pub const DISABLED_MESSAGE: &str = "host function disabled by operator policy";
+
+/// Host function groups that can appear in `DISABLED_HOST_FUNCTIONS`
+pub const KNOWN_GROUPS: &[&str] = &["http", "rpc", "storage", "vrf", "ckd", "payment", "wallet"];
+
+/// Set of host functions disabled by the operator
+#[derive(Debug, Clone, Default)]
+pub struct HostFunctionPolicy {
+    disabled: HashSet<String>,
+    /// Block outbound HTTP of arks without an `allowed_hosts` declaration
+    http_default_deny: bool,
+}
+
+impl HostFunctionPolicy {
+    /// Build a policy from `DISABLED_HOST_FUNCTIONS` entries
+    ///
+    /// Fails on entries whose group is not one of [`KNOWN_GROUPS`], so a typo
+    /// doesn't silently leave a function enabled.
+    pub fn new<I, S>(entries: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut disabled = HashSet::new();
+        for entry in entries {
+            let entry = normalize(entry.as_ref());
+            if entry.is_empty() {
+                continue;
+            }
+            let group = entry.split('.').next().unwrap_or_default();
+            if !KNOWN_GROUPS.contains(&group) {
+                anyhow::bail!(
+                    "Unknown host function '{}' in DISABLED_HOST_FUNCTIONS (groups: {})",
+                    entry,
+                    KNOWN_GROUPS.join(", ")
+                );
+            }
+            disabled.insert(entry);
+        }
+        Ok(Self {
+            disabled,
+            http_default_deny: false,
+        })
+    }
+
+    /// Block outbound HTTP of arks that don't declare `allowed_hosts`
+    pub fn with_http_default_deny(mut self, deny: bool) -> Self {
+        self.http_default_deny = deny;
+        self
+    }
+
+    /// True if arks without an `allowed_hosts` declaration get no outbound HTTP
+    pub fn is_http_default_deny(&self) -> bool {
+        self.http_default_deny
+    }
+
+    /// True if nothing is disabled
+    pub fn is_empty(&self) -> bool {
+        self.disabled.is_empty()
+    }
+
+    /// True if outbound wasi-http requests are disabled
+    pub fn is_http_disabled(&self) -> bool {
+        self.is_group_disabled("http")
+    }
+
+    /// True if the whole group (e.g. `"storage"`) is disabled, not just some of its functions
+    pub fn is_group_disabled(&self, group: &str) -> bool {
+        self.disabled.contains(group)
+    }
+
+    /// True if `function` of the WIT interface `interface` (e.g. `near:rpc/api@0.1.0`) is disabled
+    pub fn is_disabled(&self, interface: &str, function: &str) -> bool {
+        let Some(group) = interface_group(interface) else {
+            return false;
+        };
+        self.disabled.contains(group)
+            || self.disabled.contains(&format!("{}.{}", group, normalize(function)))
+    }
+
+    /// Shadow every disabled function the component imports with a trapping stub
+    ///
+    /// Must be called after the real host functions were added to `linker`.
+    pub fn apply_to_linker<T>(
+        &self,
+        linker: &mut Linker<T>,
+        component: &Component,
+        engine: &Engine,
+    ) -> Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        let mut blocked: Vec<(String, String)> = Vec::new();
+        for (interface, item) in component.component_type().imports(engine) {
+            if let ComponentItem::ComponentInstance(instance) = item {
+                for (function, export) in instance.exports(engine) {
+                    if matches!(export, ComponentItem::ComponentFunc(_))
+                        && self.is_disabled(interface, function)
+                    {
+                        blocked.push((interface.to_string(), function.to_string()));
+                    }
+                }
+            }
+        }
+
+        if blocked.is_empty() {
+            return Ok(());
+        }
+
+        linker.allow_shadowing(true);
+        for (interface, function) in blocked {
+            tracing::info!("Host function {}#{} disabled by operator policy", interface, function);
+            let name = format!("{}.{}", interface_group(&interface).unwrap_or_default(), function);
+            linker.instance(&interface)?.func_new(&function, move |_store, _params, _results| {
+                Err(anyhow::anyhow!("{}: {}", DISABLED_MESSAGE, name))
+            })?;
+        }
+        linker.allow_shadowing(false);
+
+        Ok(())
+    }
+}
+
+/// Map a WIT interface name to its policy group (`near:rpc/api@0.1.0` -> `rpc`)
+fn interface_group(interface: &str) -> Option<&str> {
+    let (namespace, rest) = interface.split_once(':')?;
+    if namespace != "near" && namespace != "outlayer" {
+        return None;
+    }
+    let package = rest.split('/').next()?;
+    KNOWN_GROUPS.iter().copied().find(|g| *g == package)
+}
+
+fn normalize(name: &str) -> String {
+    name.trim().to_lowercase().replace('_', "-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmtime::component::Instance;
+    use wasmtime::Store;
+
+    const RPC_COMPONENT: &str = r#"
+        (component
+          (import "near:rpc/api@0.1.0" (instance $rpc
+            (export "view" (func (result u32)))
+            (export "transfer" (func (result u32)))
+          ))
+          (core func $view (canon lower (func $rpc "view")))
+          (core func $transfer (canon lower (func $rpc "transfer")))
+          (core module $m
+            (import "rpc" "view" (func $view (result i32)))
+            (import "rpc" "transfer" (func $transfer (result i32)))
+            (func (export "call-view") (result i32) call $view)
+            (func (export "call-transfer") (result i32) call $transfer)
+          )
+          (core instance $i (instantiate $m
+            (with "rpc" (instance
+              (export "view" (func $view))
+              (export "transfer" (func $transfer))
+            ))
+          ))
+          (func (export "call-view") (result u32) (canon lift (core func $i "call-view")))
+          (func (export "call-transfer") (result u32) (canon lift (core func $i "call-transfer")))
+        )
+    "#;
+
+    fn instantiate(policy: &HostFunctionPolicy) -> (Store<()>, Instance) {
+        let mut config = wasmtime::Config::new();
+        config.wasm_component_model(true);
+        let engine = Engine::new(&config).unwrap();
+        let component = Component::new(&engine, RPC_COMPONENT).unwrap();
+
+        let mut linker: Linker<()> = Linker::new(&engine);
+        let mut rpc = linker.instance("near:rpc/api@0.1.0").unwrap();
+        rpc.func_wrap("view", |_store, (): ()| Ok((1u32,))).unwrap();
+        rpc.func_wrap("transfer", |_store, (): ()| Ok((2u32,))).unwrap();
+
+        policy.apply_to_linker(&mut linker, &component, &engine).unwrap();
+
+        let mut store = Store::new(&engine, ());
+        let instance = linker.instantiate(&mut store, &component).unwrap();
+        (store, instance)
+    }
+
+    fn call(store: &mut Store<()>, instance: &Instance, name: &str) -> Result<u32> {
+        let func = instance.get_typed_func::<(), (u32,)>(&mut *store, name)?;
+        let (value,) = func.call(&mut *store, ())?;
+        func.post_return(&mut *store)?;
+        Ok(value)
+    }
+
+    #[test]
+    fn parses_and_matches_entries() {
+        let policy = HostFunctionPolicy::new(["http", "rpc.send_tx", " RPC.Transfer "]).unwrap();
+        assert!(policy.is_http_disabled());
+        assert!(policy.is_disabled("near:rpc/api@0.1.0", "send-tx"));
+        assert!(policy.is_disabled("near:rpc/api@0.1.0", "transfer"));
+        assert!(!policy.is_disabled("near:rpc/api@0.1.0", "view"));
+        assert!(!policy.is_disabled("near:storage/api@0.1.0", "get"));
+
+        let policy = HostFunctionPolicy::new(["wallet"]).unwrap();
+        assert!(!policy.is_http_disabled());
+        assert!(policy.is_disabled("outlayer:wallet/api@0.1.0", "transfer"));
+        assert!(!policy.is_disabled("wasi:http/outgoing-handler@0.2.0", "handle"));
+    }
+
+    #[test]
+    fn rejects_unknown_groups() {
+        assert!(HostFunctionPolicy::new(["rcp.transfer"]).is_err());
+        assert!(HostFunctionPolicy::new(["filesystem"]).is_err());
+    }
+
+    #[test]
+    fn disabled_function_errors_enabled_function_works() {
+        let policy = HostFunctionPolicy::new(["rpc.transfer"]).unwrap();
+        let (mut store, instance) = instantiate(&policy);
+
+        assert_eq!(call(&mut store, &instance, "call-view").unwrap(), 1);
+
+        let err = call(&mut store, &instance, "call-transfer").unwrap_err();
+        assert!(
+            format!("{:?}", err).contains(DISABLED_MESSAGE),
+            "unexpected error: {:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn empty_policy_leaves_functions_untouched() {
+        let policy = HostFunctionPolicy::default();
+        let (mut store, instance) = instantiate(&policy);
+
+        assert_eq!(call(&mut store, &instance, "call-view").unwrap(), 1);
+        assert_eq!(call(&mut store, &instance, "call-transfer").unwrap(), 2);
+    }
+}
@@ -0,0 +1,162 @@
+//! Bounded capture of WASM stderr
+//!
+//! Stderr is returned to the caller as `ExecutionResult::logs`. The buffer keeps only the
+//! most recent `max_log_bytes`: older bytes are dropped as new ones arrive, so a guest that
+//! logs in a loop costs a fixed amount of memory and never fails a write (unlike
+//! `MemoryOutputPipe`, which traps once full).
+
+use bytes::Bytes;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use wasmtime_wasi::{HostOutputStream, StdoutStream, StreamResult, Subscribe};
+
+struct Ring {
+    bytes: VecDeque<u8>,
+    /// Bytes dropped from the front to stay within capacity
+    dropped: u64,
+    /// Whether the last dropped byte was a newline (the buffer starts on a whole line)
+    starts_at_line: bool,
+}
+
+impl Ring {
+    /// Account for dropping the first `count` bytes of `bytes` followed by `incoming`
+    ///
+    /// Buffered bytes are removed; the caller skips the dropped prefix of `incoming`.
+    fn drop_front(&mut self, count: usize, incoming: &[u8]) {
+        if count == 0 {
+            return;
+        }
+        let buffered = self.bytes.len();
+        let last = if count <= buffered {
+            self.bytes[count - 1]
+        } else {
+            incoming[count - buffered - 1]
+        };
+        self.starts_at_line = last == b'\n';
+        self.dropped += count as u64;
+        self.bytes.drain(..count.min(buffered));
+    }
+}
+
+/// Stderr stream that keeps the last `capacity` bytes written
+#[derive(Clone)]
+pub(crate) struct LogCapture {
+    capacity: usize,
+    ring: Arc<Mutex<Ring>>,
+}
+
+impl LogCapture {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ring: Arc::new(Mutex::new(Ring {
+                bytes: VecDeque::with_capacity(capacity.min(64 * 1024)),
+                dropped: 0,
+                starts_at_line: true,
+            })),
+        }
+    }
+
+    /// Everything still in the buffer
+    pub(crate) fn contents(&self) -> Vec<u8> {
+        let ring = self.ring.lock().unwrap();
+        ring.bytes.iter().copied().collect()
+    }
+
+    /// Buffer split into lines
+    ///
+    /// When earlier output was dropped, a marker saying how many bytes were lost comes
+    /// first, and a line whose beginning was dropped is left out.
+    pub(crate) fn lines(&self) -> Vec<String> {
+        let (bytes, dropped, starts_at_line) = {
+            let ring = self.ring.lock().unwrap();
+            (ring.bytes.iter().copied().collect::<Vec<u8>>(), ring.dropped, ring.starts_at_line)
+        };
+        let text = String::from_utf8_lossy(&bytes);
+        let mut lines = text.lines();
+        let mut result = Vec::new();
+        if dropped > 0 {
+            let partial = if starts_at_line {
+                0
+            } else {
+                lines.next().map_or(0, |line| line.len() as u64 + 1)
+            };
+            result.push(format!("[{} bytes of earlier log output truncated]", dropped + partial));
+        }
+        result.extend(lines.map(str::to_string));
+        result
+    }
+}
+
+impl HostOutputStream for LogCapture {
+    fn write(&mut self, bytes: Bytes) -> StreamResult<()> {
+        let mut ring = self.ring.lock().unwrap();
+        let buffered = ring.bytes.len();
+        let overflow = (buffered + bytes.len()).saturating_sub(self.capacity);
+        ring.drop_front(overflow, &bytes);
+        // Part of this write itself may not fit
+        ring.bytes.extend(&bytes[overflow.saturating_sub(buffered)..]);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> StreamResult<()> {
+        Ok(())
+    }
+
+    fn check_write(&mut self) -> StreamResult<usize> {
+        // Writes never fail; old output makes room for new
+        Ok(self.capacity.max(1))
+    }
+}
+
+#[async_trait::async_trait]
+impl Subscribe for LogCapture {
+    async fn ready(&mut self) {}
+}
+
+impl StdoutStream for LogCapture {
+    fn stream(&self) -> Box<dyn HostOutputStream> {
+        Box::new(self.clone())
+    }
+
+    fn isatty(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines_within_capacity() {
+        let mut capture = LogCapture::new(1024);
+        capture.write(Bytes::from_static(b"loading config\n")).unwrap();
+        capture.write(Bytes::from_static(b"price=42\n")).unwrap();
+
+        assert_eq!(capture.lines(), vec!["loading config", "price=42"]);
+    }
+
+    #[test]
+    fn test_keeps_most_recent_bytes() {
+        let mut capture = LogCapture::new(16);
+        for i in 0..10 {
+            capture.write(Bytes::from(format!("line {}\n", i))).unwrap();
+        }
+
+        assert_eq!(capture.contents().len(), 16);
+        let lines = capture.lines();
+        assert_eq!(lines.last().unwrap(), "line 9");
+        assert!(lines[0].starts_with('['), "expected truncation marker, got {:?}", lines);
+        assert!(lines.iter().all(|line| !line.starts_with("line 0")));
+    }
+
+    #[test]
+    fn test_single_write_larger_than_capacity() {
+        let mut capture = LogCapture::new(8);
+        capture.write(Bytes::from_static(b"0123456789abcdef")).unwrap();
+
+        assert_eq!(capture.contents(), b"89abcdef");
+        assert_eq!(capture.check_write().unwrap(), 8);
+    }
+}
@@ -40,10 +40,23 @@ use crate::api_client::{ExecutionOutput, ExecutionResult, ResourceLimits, Respon
 use crate::compiled_cache::CompiledCache;
 use crate::outlayer_rpc::RpcProxy;
 use crate::outlayer_storage::client::StorageConfig;
-
+use determinism::PeakMemory;
+
+mod determinism;
+mod egress;
+mod declared_format;
+pub mod host_policy;
+mod log_capture;
+pub mod output_format;
+pub mod precompiled;
+pub mod requirements;
+pub mod structured_error;
 mod wasi_p1;
 mod wasi_p2;
 
+pub use host_policy::HostFunctionPolicy;
+pub use precompiled::PrecompiledStore;
+
 /// VRF configuration for host functions
 #[derive(Clone)]
 pub struct VrfConfig {
@@ -55,6 +68,16 @@ pub struct VrfConfig {
     pub sender_id: String,
 }
 
+/// App key (MPC CKD) configuration for host functions
+#[derive(Clone)]
+pub struct CkdConfig {
+    pub keystore_url: String,
+    pub keystore_auth_token: String,
+    pub tee_session_id: Option<String>,
+    /// Project the execution runs in (keystore derives `outlayer-ark:{project_id}`)
+    pub project_id: String,
+}
+
 /// Wallet configuration for host functions
 #[derive(Clone)]
 pub struct WalletConfig {
@@ -73,7 +96,9 @@ pub struct WalletConfig {
 /// - RPC Proxy: Allows WASM to make NEAR RPC calls without exposing API keys
 /// - Storage: Persistent storage for projects and standalone WASM
 /// - Compiled Cache: Pre-compiled WASM components for ~10x faster startup
+/// - Precompiled Store: AOT artifacts published by compile workers (P1 and P2)
 /// - VRF: Verifiable random function via keystore
+/// - CKD: Project app keys derived by the MPC network via keystore
 #[derive(Clone)]
 pub struct ExecutionContext {
     /// RPC proxy for NEAR blockchain access (only used in WASI P2)
@@ -86,8 +111,14 @@ pub struct ExecutionContext {
     pub compiled_cache: Option<Arc<Mutex<CompiledCache>>>,
     /// VRF configuration (only used in WASI P2, requires keystore + request_id)
     pub vrf_config: Option<VrfConfig>,
+    /// App key configuration (only used in WASI P2, requires keystore + project)
+    pub ckd_config: Option<CkdConfig>,
     /// Wallet configuration (only used in WASI P2, requires wallet_id in execution request)
     pub wallet_config: Option<WalletConfig>,
+    /// Source of AOT-compiled artifacts (AOT_PRECOMPILE)
+    pub precompiled: Option<Arc<PrecompiledStore>>,
+    /// Keystore is configured, so arks requiring `secrets` can run
+    pub keystore_configured: bool,
 }
 
 impl ExecutionContext {
@@ -100,7 +131,10 @@ impl ExecutionContext {
             runtime_handle,
             compiled_cache: None,
             vrf_config: None,
+            ckd_config: None,
             wallet_config: None,
+            precompiled: None,
+            keystore_configured: false,
         }
     }
 
@@ -125,6 +159,18 @@ impl ExecutionContext {
         self
     }
 
+    /// Create context with a store of AOT-compiled artifacts
+    pub fn with_precompiled(mut self, store: PrecompiledStore) -> Self {
+        self.precompiled = Some(Arc::new(store));
+        self
+    }
+
+    /// Mark the keystore as configured
+    pub fn with_keystore(mut self) -> Self {
+        self.keystore_configured = true;
+        self
+    }
+
     /// Check if RPC proxy is available
     #[allow(dead_code)]
     pub fn has_outlayer_rpc(&self) -> bool {
@@ -146,6 +192,8 @@ pub struct Executor {
     print_wasm_stderr: bool,
     /// Execution context with optional RPC proxy and other services
     context: Option<ExecutionContext>,
+    /// Host functions disabled by the operator (DISABLED_HOST_FUNCTIONS)
+    host_policy: HostFunctionPolicy,
 }
 
 impl Executor {
@@ -155,6 +203,7 @@ impl Executor {
             _default_max_instructions: default_max_instructions,
             print_wasm_stderr,
             context: None,
+            host_policy: HostFunctionPolicy::default(),
         }
     }
 
@@ -165,6 +214,12 @@ impl Executor {
         self
     }
 
+    /// Create executor with an operator host function policy
+    pub fn with_host_policy(mut self, policy: HostFunctionPolicy) -> Self {
+        self.host_policy = policy;
+        self
+    }
+
     /// Execute WASM with input data
     ///
     /// Returns ExecutionResult with success/failure and optional output
@@ -176,9 +231,10 @@ impl Executor {
     /// * `limits` - Resource limits for execution
     /// * `env_vars` - Environment variables (from secrets)
     /// * `build_target` - Build target (wasm32-wasip1, wasm32-wasip2)
-    /// * `response_format` - Output format (Bytes, Text, Json)
+    /// * `response_format` - Output format (Bytes, Text, Json, Cbor, MessagePack, Base64)
     /// * `storage_config` - Optional per-execution storage config (overrides context)
     /// * `vrf_config` - Optional per-execution VRF config (overrides context)
+    /// * `ckd_config` - Optional per-execution app key config (overrides context)
     /// * `wallet_config` - Optional per-execution wallet config (overrides context)
     pub async fn execute(
         &self,
@@ -191,6 +247,7 @@ impl Executor {
         response_format: &ResponseFormat,
         storage_config: Option<StorageConfig>,
         vrf_config: Option<VrfConfig>,
+        ckd_config: Option<CkdConfig>,
         wallet_config: Option<WalletConfig>,
     ) -> Result<ExecutionResult> {
         info!(
@@ -199,17 +256,19 @@ impl Executor {
         );
 
         let start = Instant::now();
+        let peak_memory = PeakMemory::default();
 
         // Try to execute with different WASI versions
-        let result = self.execute_async(wasm_bytes, wasm_checksum, input_data, limits, env_vars, build_target, storage_config, vrf_config, wallet_config).await;
+        let result = self.execute_async(wasm_bytes, wasm_checksum, input_data, limits, env_vars, build_target, storage_config, vrf_config, ckd_config, wallet_config, &peak_memory).await;
 
         let execution_time_ms = start.elapsed().as_millis() as u64;
+        let peak_memory_bytes = peak_memory.bytes();
 
         match result {
-            Ok((output_bytes, instructions, refund_usd)) => {
+            Ok((output_bytes, instructions, refund_usd, mut logs)) => {
                 info!(
-                    "WASM execution succeeded in {} ms, consumed {} instructions",
-                    execution_time_ms, instructions
+                    "WASM execution succeeded in {} ms, consumed {} instructions, peak memory {} bytes",
+                    execution_time_ms, instructions, peak_memory_bytes
                 );
                 info!("📦 Raw output size: {} bytes", output_bytes.len());
                 if let Some(refund) = refund_usd {
@@ -220,6 +279,19 @@ impl Executor {
                     warn!("⚠️ WASM produced empty output (stdout was empty)");
                 }
 
+                // The ark may have said what its output actually is
+                let declared_output_format = declared_format::take(&mut logs);
+                if let Some(error) = declared_format::mismatch(response_format, declared_output_format.as_ref()) {
+                    return Ok(malformed_output(
+                        error,
+                        execution_time_ms,
+                        instructions,
+                        peak_memory_bytes,
+                        logs,
+                        declared_output_format,
+                    ));
+                }
+
                 // Convert output based on requested format
                 let output = match response_format {
                     ResponseFormat::Bytes => {
@@ -238,39 +310,76 @@ impl Executor {
                             }
                             Err(e) => {
                                 // If JSON parsing fails, return error
-                                return Ok(ExecutionResult {
-                                    success: false,
-                                    output: None,
-                                    error: Some(format!(
+                                return Ok(malformed_output(
+                                    format!(
                                         "Failed to parse output as JSON: {}. Output was: {}",
                                         e,
                                         String::from_utf8_lossy(&output_bytes)
-                                    )),
+                                    ),
                                     execution_time_ms,
                                     instructions,
-                                    compile_time_ms: None, // Compilation not tracked in executor
-                                    compilation_note: None,
-                                    refund_usd: None,
-                                });
+                                    peak_memory_bytes,
+                                    logs,
+                                    declared_output_format,
+                                ));
                             }
                         }
                     }
+                    ResponseFormat::Cbor => {
+                        // Binary output is passed through, but only if it decodes
+                        if let Err(e) = output_format::validate_cbor(&output_bytes) {
+                            return Ok(malformed_output(
+                                format!("Output is not well-formed CBOR ({} bytes): {}", output_bytes.len(), e),
+                                execution_time_ms,
+                                instructions,
+                                peak_memory_bytes,
+                                logs,
+                                declared_output_format,
+                            ));
+                        }
+                        Some(ExecutionOutput::Cbor(output_bytes))
+                    }
+                    ResponseFormat::MessagePack => {
+                        if let Err(e) = output_format::validate_msgpack(&output_bytes) {
+                            return Ok(malformed_output(
+                                format!("Output is not well-formed MessagePack ({} bytes): {}", output_bytes.len(), e),
+                                execution_time_ms,
+                                instructions,
+                                peak_memory_bytes,
+                                logs,
+                                declared_output_format,
+                            ));
+                        }
+                        Some(ExecutionOutput::MessagePack(output_bytes))
+                    }
+                    ResponseFormat::Base64 => {
+                        // Binary output that survives JSON-only clients, unlike Bytes (a number array)
+                        use base64::{engine::general_purpose::STANDARD, Engine};
+                        Some(ExecutionOutput::Base64(STANDARD.encode(&output_bytes)))
+                    }
                 };
 
                 Ok(ExecutionResult {
                     success: true,
                     output,
                     error: None,
+                    error_structured: None,
                     execution_time_ms,
                     instructions,
+                    peak_memory_bytes,
                     compile_time_ms: None, // Compilation not tracked in executor
                     compilation_note: None,
                     refund_usd,
+                    logs,
+                    timed_out: false,
+                    declared_output_format,
                 })
             }
             Err(e) => {
-                let error_str = e.to_string();
+                // A guest that called env::fail() left its error document in stderr
+                let (error_str, error_structured) = structured_error::extract(&e.to_string());
                 info!("WASM execution failed: {}", error_str);
+                let timed_out = e.downcast_ref::<ExecutionTimeout>().is_some();
 
                 let is_penalty = error_str.contains("(penalty)");
 
@@ -291,11 +400,16 @@ impl Executor {
                     success: false,
                     output: None,
                     error: Some(error_str),
+                    error_structured,
                     execution_time_ms,
                     instructions,
+                    peak_memory_bytes,
                     compile_time_ms: None, // Compilation not tracked in executor
                     compilation_note: None,
                     refund_usd: None, // No refund on failure
+                    logs: Vec::new(), // stderr is part of the error message
+                    timed_out,
+                    declared_output_format: None,
                 })
             }
         }
@@ -311,7 +425,8 @@ impl Executor {
     /// 2. WASI Preview 1 module (standard WASI)
     /// 3. Error if no format matches
     ///
-    /// Returns: (output_bytes, instructions, refund_usd)
+    /// Returns: (output_bytes, instructions, refund_usd, logs); the memory high-water
+    /// mark is recorded in `peak_memory`
     async fn execute_async(
         &self,
         wasm_bytes: &[u8],
@@ -322,10 +437,12 @@ impl Executor {
         build_target: Option<&str>,
         storage_config: Option<StorageConfig>,
         vrf_config: Option<VrfConfig>,
+        ckd_config: Option<CkdConfig>,
         wallet_config: Option<WalletConfig>,
-    ) -> Result<(Vec<u8>, u64, Option<u64>)> {
+        peak_memory: &PeakMemory,
+    ) -> Result<(Vec<u8>, u64, Option<u64>, Vec<String>)> {
         // Create effective execution context with per-execution overrides
-        let has_overrides = storage_config.is_some() || vrf_config.is_some() || wallet_config.is_some();
+        let has_overrides = storage_config.is_some() || vrf_config.is_some() || ckd_config.is_some() || wallet_config.is_some();
         let effective_ctx: Option<ExecutionContext> = if has_overrides {
             if let Some(ref base_ctx) = self.context {
                 Some(ExecutionContext {
@@ -334,7 +451,10 @@ impl Executor {
                     runtime_handle: base_ctx.runtime_handle.clone(),
                     compiled_cache: base_ctx.compiled_cache.clone(),
                     vrf_config: vrf_config.or_else(|| base_ctx.vrf_config.clone()),
+                    ckd_config: ckd_config.or_else(|| base_ctx.ckd_config.clone()),
                     wallet_config: wallet_config.or_else(|| base_ctx.wallet_config.clone()),
+                    precompiled: base_ctx.precompiled.clone(),
+                    keystore_configured: base_ctx.keystore_configured,
                 })
             } else {
                 // No base context, create minimal one with overrides
@@ -344,7 +464,10 @@ impl Executor {
                     runtime_handle: tokio::runtime::Handle::current(),
                     compiled_cache: None,
                     vrf_config,
+                    ckd_config,
                     wallet_config,
+                    precompiled: None,
+                    keystore_configured: false,
                 })
             }
         } else {
@@ -352,8 +475,17 @@ impl Executor {
             self.context.clone()
         };
 
+        // Fail before compiling if the ark requires a host service this execution lacks
+        let available = requirements::Available::for_execution(effective_ctx.as_ref(), &self.host_policy);
+        let available = match build_target {
+            Some("wasm32-wasip1" | "wasm32-wasi") => available.for_preview1(),
+            _ => available,
+        };
+        requirements::check(wasm_bytes, available)?;
+
         // Get compiled cache from context
         let compiled_cache = effective_ctx.as_ref().and_then(|ctx| ctx.compiled_cache.clone());
+        let precompiled_store = effective_ctx.as_ref().and_then(|ctx| ctx.precompiled.clone());
 
         // Optimize: if we know build_target, try appropriate executor first
         if let Some(target) = build_target {
@@ -361,24 +493,48 @@ impl Executor {
             match target {
                 "wasm32-wasip2" => {
                     tracing::debug!("🔹 Trying WASI P2 executor (target: wasm32-wasip2)");
+                    // AOT artifact is only needed when the local compiled cache misses
+                    let cached = match (wasm_checksum, compiled_cache.as_ref()) {
+                        (Some(checksum), Some(cache)) => cache.lock().map(|c| c.contains(checksum)).unwrap_or(false),
+                        _ => false,
+                    };
+                    let precompiled = if cached {
+                        None
+                    } else {
+                        fetch_precompiled(precompiled_store.as_deref(), wasi_p2::get_p2_engine(), wasm_checksum).await
+                    };
                     // When target is known, return error directly (don't fallback to other formats)
                     // Pass execution context (RPC proxy + storage) and compiled cache to P2 executor
                     return wasi_p2::execute(
                         wasm_bytes,
                         wasm_checksum,
                         compiled_cache.as_ref(),
+                        precompiled.as_deref(),
                         input_data,
                         limits,
                         env_vars,
                         self.print_wasm_stderr,
                         effective_ctx.as_ref(),
+                        &self.host_policy,
+                        peak_memory,
                     ).await;
                 }
                 "wasm32-wasip1" | "wasm32-wasi" => {
                     tracing::debug!("🔹 Trying WASI P1 executor (target: {})", target);
                     // When target is known, return error directly (don't fallback to other formats)
                     // P1 does not support RPC proxy, storage, or compiled cache (no component model)
-                    return wasi_p1::execute(wasm_bytes, input_data, limits, env_vars, self.print_wasm_stderr).await;
+                    let precompiled =
+                        fetch_precompiled(precompiled_store.as_deref(), wasi_p1::get_p1_engine(), wasm_checksum).await;
+                    return wasi_p1::execute(
+                        wasm_bytes,
+                        precompiled.as_deref(),
+                        input_data,
+                        limits,
+                        env_vars,
+                        self.print_wasm_stderr,
+                        peak_memory,
+                    )
+                    .await;
                 }
                 _ => {
                     tracing::debug!("⚠️ Unknown target '{}', fallback to auto-detection", target);
@@ -391,22 +547,26 @@ impl Executor {
 
         // Fallback: auto-detect format (for unknown targets or if specific executor failed)
         // Try WASI P2 component first (with RPC proxy, storage, and compiled cache support)
+        // (AOT artifacts are looked up by build target only)
         if let Ok(result) = wasi_p2::execute(
             wasm_bytes,
             wasm_checksum,
             compiled_cache.as_ref(),
+            None,
             input_data,
             limits,
             env_vars.clone(),
             self.print_wasm_stderr,
             effective_ctx.as_ref(),
+            &self.host_policy,
+            peak_memory,
         ).await
         {
             return Ok(result);
         }
 
         // Try WASI P1 module (no RPC proxy, storage, or compiled cache)
-        if let Ok(result) = wasi_p1::execute(wasm_bytes, input_data, limits, env_vars.clone(), self.print_wasm_stderr).await
+        if let Ok(result) = wasi_p1::execute(wasm_bytes, None, input_data, limits, env_vars.clone(), self.print_wasm_stderr, peak_memory).await
         {
             return Ok(result);
         }
@@ -425,6 +585,95 @@ impl Executor {
     }
 }
 
+/// Verified AOT native code for `wasm_checksum`, if a store is configured and has it
+async fn fetch_precompiled(
+    store: Option<&PrecompiledStore>,
+    engine: &wasmtime::Engine,
+    wasm_checksum: Option<&str>,
+) -> Option<Vec<u8>> {
+    match (store, wasm_checksum) {
+        (Some(store), Some(checksum)) => store.fetch(engine, checksum).await,
+        _ => None,
+    }
+}
+
+/// The guest ran past `max_execution_seconds` and was stopped at the epoch deadline
+///
+/// Returned by the P1/P2 executors so the failure can be reported as
+/// `JobStatus::Timeout` (with REPORT_TIMEOUT_STATUS) instead of a generic execution failure. The message keeps the
+/// "(penalty)" marker: a timed-out execution is charged its full instruction budget.
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "WASM execution timed out: exceeded the {limit_secs}s execution limit after {elapsed_ms}ms (penalty). \
+     Full execution cost is charged with no refund (consumed {instructions} instructions)"
+)]
+pub struct ExecutionTimeout {
+    pub limit_secs: u64,
+    pub elapsed_ms: u64,
+    pub instructions: u64,
+}
+
+/// Whether `err` is the epoch-deadline trap (as opposed to a panic or other trap)
+pub(crate) fn is_epoch_deadline(err: &anyhow::Error) -> bool {
+    matches!(err.downcast_ref::<wasmtime::Trap>(), Some(wasmtime::Trap::Interrupt))
+}
+
+/// Stdout pipe for one execution, one byte larger than `max_output_bytes`
+///
+/// WASI writes are chunked to the pipe's remaining capacity, so a guest that
+/// writes past the limit fills the extra byte and [`check_output_size`] catches it.
+pub(crate) fn stdout_pipe(limits: &ResourceLimits) -> wasmtime_wasi::pipe::MemoryOutputPipe {
+    wasmtime_wasi::pipe::MemoryOutputPipe::new(limits.max_output_bytes as usize + 1)
+}
+
+/// Fail the execution if the guest wrote more than `max_output_bytes` to stdout
+///
+/// Checked before any other failure reason: a guest whose stdout was cut off
+/// usually panics on the failed write, and the size limit is the real cause.
+pub(crate) fn check_output_size(
+    stdout: &wasmtime_wasi::pipe::MemoryOutputPipe,
+    limits: &ResourceLimits,
+    fuel_consumed: u64,
+) -> Result<()> {
+    if stdout.contents().len() > limits.max_output_bytes as usize {
+        anyhow::bail!(
+            "Output exceeds max_output_bytes ({} bytes); execution aborted (consumed {} instructions)",
+            limits.max_output_bytes,
+            fuel_consumed
+        );
+    }
+    Ok(())
+}
+
+/// Failed result for output that doesn't match the requested response format
+///
+/// The WASM ran to completion, so the instructions it consumed are still charged and its
+/// logs are kept for debugging the output.
+fn malformed_output(
+    error: String,
+    execution_time_ms: u64,
+    instructions: u64,
+    peak_memory_bytes: u64,
+    logs: Vec<String>,
+    declared_output_format: Option<ResponseFormat>,
+) -> ExecutionResult {
+    ExecutionResult {
+        success: false,
+        output: None,
+        error: Some(error),
+        error_structured: None,
+        execution_time_ms,
+        instructions,
+        peak_memory_bytes,
+        compile_time_ms: None, // Compilation not tracked in executor
+        compilation_note: None,
+        refund_usd: None,
+        logs,
+        timed_out: false,
+        declared_output_format,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,298 @@
+//! Well-formedness checks for binary response formats
+//!
+//! `Cbor` and `MessagePack` outputs are submitted on-chain as opaque bytes,
+//! so the worker checks that stdout holds exactly one complete, well-formed
+//! item before submitting it. A truncated write or stray log line on stdout
+//! then fails the execution instead of handing callers bytes they can't decode.
+//!
+//! Only the encoding is checked (structure, lengths, UTF-8 in text strings),
+//! not application-level schemas.
+
+/// Maximum nesting of arrays/maps/tags accepted in an output item
+const MAX_DEPTH: usize = 128;
+
+/// Check that `bytes` is exactly one well-formed CBOR data item (RFC 8949)
+pub fn validate_cbor(bytes: &[u8]) -> Result<(), String> {
+    let mut reader = Reader::new(bytes);
+    cbor_item(&mut reader, 0)?;
+    reader.finish()
+}
+
+/// Check that `bytes` is exactly one well-formed MessagePack object
+pub fn validate_msgpack(bytes: &[u8]) -> Result<(), String> {
+    let mut reader = Reader::new(bytes);
+    msgpack_object(&mut reader, 0)?;
+    reader.finish()
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    fn take(&mut self, len: u64) -> Result<&'a [u8], String> {
+        let remaining = (self.bytes.len() - self.pos) as u64;
+        if len > remaining {
+            return Err(format!(
+                "unexpected end of data at offset {} (need {} bytes, {} left)",
+                self.pos, len, remaining
+            ));
+        }
+        let start = self.pos;
+        self.pos += len as usize;
+        Ok(&self.bytes[start..self.pos])
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn uint(&mut self, len: u64) -> Result<u64, String> {
+        Ok(self.take(len)?.iter().fold(0u64, |n, b| (n << 8) | *b as u64))
+    }
+
+    fn text(&mut self, len: u64) -> Result<(), String> {
+        let start = self.pos;
+        std::str::from_utf8(self.take(len)?)
+            .map(|_| ())
+            .map_err(|_| format!("invalid UTF-8 in text string at offset {}", start))
+    }
+
+    fn finish(&self) -> Result<(), String> {
+        if self.pos < self.bytes.len() {
+            return Err(format!(
+                "{} trailing bytes after the first item (offset {})",
+                self.bytes.len() - self.pos,
+                self.pos
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn check_depth(depth: usize) -> Result<(), String> {
+    if depth >= MAX_DEPTH {
+        return Err(format!("nesting deeper than {} levels", MAX_DEPTH));
+    }
+    Ok(())
+}
+
+/// CBOR initial-byte argument; `None` means indefinite length
+fn cbor_argument(reader: &mut Reader, info: u8) -> Result<Option<u64>, String> {
+    match info {
+        0..=23 => Ok(Some(info as u64)),
+        24 => reader.uint(1).map(Some),
+        25 => reader.uint(2).map(Some),
+        26 => reader.uint(4).map(Some),
+        27 => reader.uint(8).map(Some),
+        31 => Ok(None),
+        _ => Err(format!("reserved additional info {} at offset {}", info, reader.pos - 1)),
+    }
+}
+
+fn cbor_item(reader: &mut Reader, depth: usize) -> Result<(), String> {
+    check_depth(depth)?;
+    let offset = reader.pos;
+    let initial = reader.byte()?;
+    let major = initial >> 5;
+    let info = initial & 0x1f;
+
+    if major == 7 {
+        return match info {
+            0..=23 | 25..=27 => reader.take(cbor_float_len(info)).map(|_| ()),
+            24 => match reader.byte()? {
+                v if v < 32 => Err(format!("invalid two-byte simple value {} at offset {}", v, offset)),
+                _ => Ok(()),
+            },
+            31 => Err(format!("unexpected break at offset {}", offset)),
+            _ => Err(format!("reserved additional info {} at offset {}", info, offset)),
+        };
+    }
+
+    let argument = cbor_argument(reader, info)?;
+    match (major, argument) {
+        (0 | 1, Some(_)) => Ok(()),
+        (2, Some(len)) => reader.take(len).map(|_| ()),
+        (3, Some(len)) => reader.text(len),
+        (2 | 3, None) => loop {
+            let chunk_offset = reader.pos;
+            let chunk = reader.byte()?;
+            if chunk == 0xff {
+                return Ok(());
+            }
+            if chunk >> 5 != major || chunk & 0x1f == 31 {
+                return Err(format!("invalid indefinite-length string chunk at offset {}", chunk_offset));
+            }
+            let len = cbor_argument(reader, chunk & 0x1f)?.unwrap_or_default();
+            if major == 2 {
+                reader.take(len)?;
+            } else {
+                reader.text(len)?;
+            }
+        },
+        (4 | 5, Some(len)) => {
+            let items = if major == 5 { len.saturating_mul(2) } else { len };
+            for _ in 0..items {
+                cbor_item(reader, depth + 1)?;
+            }
+            Ok(())
+        }
+        (4 | 5, None) => {
+            let mut items = 0u64;
+            while reader.bytes.get(reader.pos) != Some(&0xff) {
+                cbor_item(reader, depth + 1)?;
+                items += 1;
+            }
+            reader.pos += 1;
+            if major == 5 && items % 2 != 0 {
+                return Err(format!("indefinite-length map at offset {} has a key without a value", offset));
+            }
+            Ok(())
+        }
+        (6, Some(_)) => cbor_item(reader, depth + 1),
+        _ => Err(format!("indefinite length not allowed for major type {} at offset {}", major, offset)),
+    }
+}
+
+/// Payload size following a major type 7 initial byte
+fn cbor_float_len(info: u8) -> u64 {
+    match info {
+        25 => 2,
+        26 => 4,
+        27 => 8,
+        _ => 0,
+    }
+}
+
+fn msgpack_object(reader: &mut Reader, depth: usize) -> Result<(), String> {
+    check_depth(depth)?;
+    let offset = reader.pos;
+    let marker = reader.byte()?;
+
+    let (len, kind) = match marker {
+        0x00..=0x7f | 0xe0..=0xff | 0xc0 | 0xc2 | 0xc3 => return Ok(()),
+        0x80..=0x8f => ((marker & 0x0f) as u64, Kind::Map),
+        0x90..=0x9f => ((marker & 0x0f) as u64, Kind::Array),
+        0xa0..=0xbf => ((marker & 0x1f) as u64, Kind::Str),
+        0xc4 => (reader.uint(1)?, Kind::Bin),
+        0xc5 => (reader.uint(2)?, Kind::Bin),
+        0xc6 => (reader.uint(4)?, Kind::Bin),
+        0xc7 => (reader.uint(1)?, Kind::Ext),
+        0xc8 => (reader.uint(2)?, Kind::Ext),
+        0xc9 => (reader.uint(4)?, Kind::Ext),
+        0xca | 0xd2 => (4, Kind::Bin),
+        0xcb | 0xd3 => (8, Kind::Bin),
+        0xcc | 0xd0 => (1, Kind::Bin),
+        0xcd | 0xd1 => (2, Kind::Bin),
+        0xce => (4, Kind::Bin),
+        0xcf => (8, Kind::Bin),
+        0xd4 => (1, Kind::Ext),
+        0xd5 => (2, Kind::Ext),
+        0xd6 => (4, Kind::Ext),
+        0xd7 => (8, Kind::Ext),
+        0xd8 => (16, Kind::Ext),
+        0xd9 => (reader.uint(1)?, Kind::Str),
+        0xda => (reader.uint(2)?, Kind::Str),
+        0xdb => (reader.uint(4)?, Kind::Str),
+        0xdc => (reader.uint(2)?, Kind::Array),
+        0xdd => (reader.uint(4)?, Kind::Array),
+        0xde => (reader.uint(2)?, Kind::Map),
+        0xdf => (reader.uint(4)?, Kind::Map),
+        0xc1 => return Err(format!("reserved marker 0xc1 at offset {}", offset)),
+    };
+
+    match kind {
+        Kind::Bin => reader.take(len).map(|_| ()),
+        Kind::Str => reader.text(len),
+        // Extension type byte precedes the payload
+        Kind::Ext => reader.take(len + 1).map(|_| ()),
+        Kind::Array | Kind::Map => {
+            let items = if matches!(kind, Kind::Map) { len * 2 } else { len };
+            for _ in 0..items {
+                msgpack_object(reader, depth + 1)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+enum Kind {
+    Bin,
+    Str,
+    Ext,
+    Array,
+    Map,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cbor_well_formed() {
+        // {"a": [1, -2, h'ff'], "b": 1.5}
+        let map = [
+            0xa2, 0x61, b'a', 0x83, 0x01, 0x21, 0x41, 0xff, 0x61, b'b', 0xf9, 0x3e, 0x00,
+        ];
+        assert!(validate_cbor(&map).is_ok());
+        // Tag 1 (epoch time) over a uint32
+        assert!(validate_cbor(&[0xc1, 0x1a, 0x51, 0x4b, 0x67, 0xb0]).is_ok());
+        // Indefinite-length text ("ab" + "c") and array [_ true, null]
+        assert!(validate_cbor(&[0x7f, 0x62, b'a', b'b', 0x61, b'c', 0xff]).is_ok());
+        assert!(validate_cbor(&[0x9f, 0xf5, 0xf6, 0xff]).is_ok());
+    }
+
+    #[test]
+    fn test_cbor_malformed() {
+        assert!(validate_cbor(b"").is_err());
+        // Plain text on stdout
+        assert!(validate_cbor(b"hello world").is_err());
+        // Array of 3 with only 2 items
+        assert!(validate_cbor(&[0x83, 0x01, 0x02]).is_err());
+        // Two items
+        assert!(validate_cbor(&[0x01, 0x02]).is_err());
+        // Reserved additional info, invalid UTF-8, stray break, odd indefinite map
+        assert!(validate_cbor(&[0x1c]).is_err());
+        assert!(validate_cbor(&[0x62, 0xc3, 0x28]).is_err());
+        assert!(validate_cbor(&[0xff]).is_err());
+        assert!(validate_cbor(&[0xbf, 0x01, 0xff]).is_err());
+        // Byte string claiming 2^64-1 bytes
+        let mut huge = vec![0x5b];
+        huge.extend_from_slice(&[0xff; 8]);
+        assert!(validate_cbor(&huge).is_err());
+        // Nesting bomb
+        assert!(validate_cbor(&[0x81; 10_000]).is_err());
+    }
+
+    #[test]
+    fn test_msgpack_well_formed() {
+        // {"a": [1, -2, bin(ff)], "b": 1.5f64}
+        let mut map = vec![0x82, 0xa1, b'a', 0x93, 0x01, 0xfe, 0xc4, 0x01, 0xff, 0xa1, b'b', 0xcb];
+        map.extend_from_slice(&1.5f64.to_be_bytes());
+        assert!(validate_msgpack(&map).is_ok());
+        // str8, uint64, nil, fixext4
+        assert!(validate_msgpack(&[0xd9, 0x02, b'h', b'i']).is_ok());
+        assert!(validate_msgpack(&[0xcf, 0, 0, 0, 0, 0, 0, 0, 1]).is_ok());
+        assert!(validate_msgpack(&[0xc0]).is_ok());
+        assert!(validate_msgpack(&[0xd6, 0xff, 0, 0, 0, 1]).is_ok());
+    }
+
+    #[test]
+    fn test_msgpack_malformed() {
+        assert!(validate_msgpack(b"").is_err());
+        assert!(validate_msgpack(&[0xc1]).is_err());
+        // fixmap with one pair, value missing
+        assert!(validate_msgpack(&[0x81, 0xa1, b'a']).is_err());
+        // Truncated float64 and trailing garbage
+        assert!(validate_msgpack(&[0xcb, 0x3f, 0xf8]).is_err());
+        assert!(validate_msgpack(&[0xc3, b'\n']).is_err());
+        // Invalid UTF-8 in fixstr
+        assert!(validate_msgpack(&[0xa2, 0xc3, 0x28]).is_err());
+        assert!(validate_msgpack(&[0x91; 10_000]).is_err());
+    }
+}
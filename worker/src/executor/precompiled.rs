@@ -0,0 +1,299 @@
+//! Ahead-of-time compiled WASM artifacts (`.cwasm`)
+//!
+//! With `AOT_PRECOMPILE=true`, compile workers run wasmtime's AOT compiler on
+//! the freshly built WASM and publish the serialized native code to FastFS
+//! next to the `.wasm`. Executors fetch it by name and load it with
+//! `Module::deserialize` / `Component::deserialize`, skipping JIT compilation.
+//!
+//! ## Engine versions
+//!
+//! Native code is only valid for the exact wasmtime version and engine
+//! configuration that produced it. Each artifact carries an engine tag
+//! (SHA-256 of `Engine::precompile_compatibility_hash`) that is also part of
+//! its file name, so a worker with a different engine asks for a different
+//! file; on mismatch or a missing file the executor compiles from the WASM
+//! bytes as before.
+//!
+//! ## Trust
+//!
+//! Deserializing native code is unsafe: a forged artifact is arbitrary code
+//! inside the worker. Artifacts are signed with the FastFS sender key shared
+//! by the deployment, and executors only load artifacts whose signature
+//! verifies against that key. Native-mode compilers can't publish artifacts
+//! (build scripts there could read the key), see `Config::validate`.
+//!
+//! ## Format
+//!
+//! | Field          | Encoding                                               |
+//! |----------------|--------------------------------------------------------|
+//! | magic          | ASCII `OLCWASM1`                                       |
+//! | engine tag     | 32 bytes                                               |
+//! | wasm checksum  | u32 little-endian length + hex SHA-256 of the `.wasm`  |
+//! | signature      | u32 little-endian length + NEAR string form            |
+//! | native code    | rest of the file, as produced by wasmtime              |
+//!
+//! The signature covers `outlayer:precompiled-wasm:v1 || engine tag ||
+//! len(checksum) || checksum || SHA-256(native code)`.
+
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use near_crypto::{PublicKey, SecretKey, Signature};
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+use wasmtime::Engine;
+
+/// Leading bytes of every artifact
+const ARTIFACT_MAGIC: &[u8; 8] = b"OLCWASM1";
+
+/// Domain separator for artifact signatures
+const SIGNATURE_DOMAIN: &[u8] = b"outlayer:precompiled-wasm:v1";
+
+/// MIME type used when publishing artifacts
+pub const ARTIFACT_MIME_TYPE: &str = "application/octet-stream";
+
+/// Timeout for fetching an artifact; on expiry the executor compiles from bytes
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Executor engine for `build_target` and whether it compiles components
+///
+/// None for targets without a dedicated executor (auto-detected at execution).
+pub fn engine_for_target(build_target: &str) -> Option<(&'static Engine, bool)> {
+    match build_target {
+        "wasm32-wasip2" => Some((super::wasi_p2::get_p2_engine(), true)),
+        "wasm32-wasip1" | "wasm32-wasi" => Some((super::wasi_p1::get_p1_engine(), false)),
+        _ => None,
+    }
+}
+
+/// Feeds `Hash` output into SHA-256 so the tag is stable across processes
+struct DigestHasher(Sha256);
+
+impl Hasher for DigestHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let digest = self.0.clone().finalize();
+        u64::from_le_bytes(digest[..8].try_into().expect("SHA-256 digest is 32 bytes"))
+    }
+}
+
+/// Identifies the wasmtime version and configuration native code is valid for
+pub fn engine_tag(engine: &Engine) -> [u8; 32] {
+    let mut hasher = DigestHasher(Sha256::new());
+    engine.precompile_compatibility_hash().hash(&mut hasher);
+    hasher.0.finalize().into()
+}
+
+/// File name of the artifact for `wasm_checksum` built by `engine`
+pub fn artifact_name(wasm_checksum: &str, engine: &Engine) -> String {
+    format!("{}.{}.cwasm", wasm_checksum, &hex::encode(engine_tag(engine))[..16])
+}
+
+fn signing_message(engine_tag: &[u8; 32], wasm_checksum: &str, native: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(SIGNATURE_DOMAIN.len() + 32 + 4 + wasm_checksum.len() + 32);
+    message.extend_from_slice(SIGNATURE_DOMAIN);
+    message.extend_from_slice(engine_tag);
+    message.extend_from_slice(&(wasm_checksum.len() as u32).to_le_bytes());
+    message.extend_from_slice(wasm_checksum.as_bytes());
+    message.extend_from_slice(&Sha256::digest(native));
+    message
+}
+
+/// AOT-compile `wasm_bytes` with `engine` and wrap it in a signed artifact
+///
+/// `is_component` selects component (wasm32-wasip2) or core module compilation.
+pub fn precompile(
+    engine: &Engine,
+    wasm_bytes: &[u8],
+    is_component: bool,
+    wasm_checksum: &str,
+    signer: &SecretKey,
+) -> Result<Vec<u8>> {
+    let native = if is_component {
+        engine.precompile_component(wasm_bytes)
+    } else {
+        engine.precompile_module(wasm_bytes)
+    }
+    .context("AOT compilation failed")?;
+
+    let tag = engine_tag(engine);
+    let signature = signer.sign(&signing_message(&tag, wasm_checksum, &native)).to_string();
+
+    let mut artifact = Vec::with_capacity(
+        ARTIFACT_MAGIC.len() + 32 + 8 + wasm_checksum.len() + signature.len() + native.len(),
+    );
+    artifact.extend_from_slice(ARTIFACT_MAGIC);
+    artifact.extend_from_slice(&tag);
+    artifact.extend_from_slice(&(wasm_checksum.len() as u32).to_le_bytes());
+    artifact.extend_from_slice(wasm_checksum.as_bytes());
+    artifact.extend_from_slice(&(signature.len() as u32).to_le_bytes());
+    artifact.extend_from_slice(signature.as_bytes());
+    artifact.extend_from_slice(&native);
+    Ok(artifact)
+}
+
+/// Split a length-prefixed field off the front of `bytes`
+fn take_prefixed<'a>(bytes: &mut &'a [u8], field: &str) -> Result<&'a [u8]> {
+    if bytes.len() < 4 {
+        anyhow::bail!("Truncated artifact: missing {} length", field);
+    }
+    let len = u32::from_le_bytes(bytes[..4].try_into().expect("4 bytes")) as usize;
+    if bytes.len() - 4 < len {
+        anyhow::bail!("Truncated artifact: {} needs {} bytes", field, len);
+    }
+    let value = &bytes[4..4 + len];
+    *bytes = &bytes[4 + len..];
+    Ok(value)
+}
+
+/// Check an artifact and return its native code
+///
+/// # Returns
+/// * `Ok(Some(native))` - Signed by `trusted_key` for this WASM and engine
+/// * `Ok(None)` - Built by a different wasmtime version/config (recompile)
+/// * `Err(_)` - Malformed, for another WASM, or not signed by `trusted_key`
+pub fn open<'a>(
+    engine: &Engine,
+    artifact: &'a [u8],
+    wasm_checksum: &str,
+    trusted_key: &PublicKey,
+) -> Result<Option<&'a [u8]>> {
+    let header_len = ARTIFACT_MAGIC.len() + 32;
+    if artifact.len() < header_len || &artifact[..ARTIFACT_MAGIC.len()] != ARTIFACT_MAGIC {
+        anyhow::bail!("Not a precompiled WASM artifact");
+    }
+    let tag: [u8; 32] = artifact[ARTIFACT_MAGIC.len()..header_len].try_into().expect("32 bytes");
+    if tag != engine_tag(engine) {
+        return Ok(None);
+    }
+
+    let mut rest = &artifact[header_len..];
+    let checksum = take_prefixed(&mut rest, "wasm checksum")?;
+    if checksum != wasm_checksum.as_bytes() {
+        anyhow::bail!(
+            "Artifact is for WASM {}, expected {}",
+            String::from_utf8_lossy(checksum),
+            wasm_checksum
+        );
+    }
+    let signature: Signature = std::str::from_utf8(take_prefixed(&mut rest, "signature")?)
+        .context("Invalid signature encoding")?
+        .parse()
+        .context("Invalid signature encoding")?;
+    let native = rest;
+
+    if !signature.verify(&signing_message(&tag, wasm_checksum, native), trusted_key) {
+        anyhow::bail!("Artifact signature does not verify for {}", trusted_key);
+    }
+    Ok(Some(native))
+}
+
+/// Fetches published artifacts for executors
+pub struct PrecompiledStore {
+    http: reqwest::Client,
+    /// FastFS directory holding the artifacts (`https://{sender}.fastfs.io/{receiver}`)
+    base_url: String,
+    /// Key artifacts must be signed with (FastFS sender)
+    trusted_key: PublicKey,
+}
+
+impl PrecompiledStore {
+    pub fn new(base_url: String, trusted_key: PublicKey) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            .build()
+            .context("Failed to build HTTP client for precompiled artifacts")?;
+        Ok(Self {
+            http,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            trusted_key,
+        })
+    }
+
+    /// Native code for `wasm_checksum` built by `engine`, if a trusted artifact is published
+    ///
+    /// Any failure (missing file, other engine, bad signature) returns None so
+    /// the caller falls back to compiling the WASM bytes.
+    pub async fn fetch(&self, engine: &Engine, wasm_checksum: &str) -> Option<Vec<u8>> {
+        let url = format!("{}/{}", self.base_url, artifact_name(wasm_checksum, engine));
+        let artifact = match self.download(&url).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                debug!("No precompiled artifact at {}: {}", url, e);
+                return None;
+            }
+        };
+
+        match open(engine, &artifact, wasm_checksum, &self.trusted_key) {
+            Ok(Some(native)) => {
+                debug!("⚡ Fetched precompiled artifact {} ({} bytes)", url, native.len());
+                Some(native.to_vec())
+            }
+            Ok(None) => {
+                debug!("Precompiled artifact {} was built by another engine, recompiling", url);
+                None
+            }
+            Err(e) => {
+                warn!("⚠️ Rejected precompiled artifact {}: {}", url, e);
+                None
+            }
+        }
+    }
+
+    async fn download(&self, url: &str) -> Result<Vec<u8>> {
+        let response = self.http.get(url).send().await?.error_for_status()?;
+        Ok(response.bytes().await?.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_crypto::KeyType;
+    use wasmtime::Config;
+
+    const EMPTY_MODULE_WAT: &str = "(module)";
+
+    fn engine(consume_fuel: bool) -> Engine {
+        let mut config = Config::new();
+        config.consume_fuel(consume_fuel);
+        Engine::new(&config).unwrap()
+    }
+
+    #[test]
+    fn test_open_verifies_signature_and_checksum() {
+        let key = SecretKey::from_seed(KeyType::ED25519, "fastfs-sender");
+        let engine = engine(true);
+        let artifact = precompile(&engine, EMPTY_MODULE_WAT.as_bytes(), false, "abc123", &key).unwrap();
+
+        let native = open(&engine, &artifact, "abc123", &key.public_key()).unwrap().unwrap();
+        assert_eq!(native, &artifact[artifact.len() - native.len()..]);
+
+        // Other WASM, other signer, tampered native code, not an artifact
+        assert!(open(&engine, &artifact, "def456", &key.public_key()).is_err());
+        let other = SecretKey::from_seed(KeyType::ED25519, "attacker");
+        assert!(open(&engine, &artifact, "abc123", &other.public_key()).is_err());
+        let mut tampered = artifact.clone();
+        *tampered.last_mut().unwrap() ^= 0xff;
+        assert!(open(&engine, &tampered, "abc123", &key.public_key()).is_err());
+        assert!(open(&engine, b"\0asm\x01\0\0\0", "abc123", &key.public_key()).is_err());
+        assert!(open(&engine, &artifact[..50], "abc123", &key.public_key()).is_err());
+    }
+
+    #[test]
+    fn test_engine_mismatch_requests_recompile() {
+        let key = SecretKey::from_seed(KeyType::ED25519, "fastfs-sender");
+        let fuel = engine(true);
+        let no_fuel = engine(false);
+        assert_eq!(engine_tag(&fuel), engine_tag(&engine(true)));
+        assert_ne!(engine_tag(&fuel), engine_tag(&no_fuel));
+        assert_ne!(artifact_name("abc123", &fuel), artifact_name("abc123", &no_fuel));
+
+        let artifact = precompile(&fuel, EMPTY_MODULE_WAT.as_bytes(), false, "abc123", &key).unwrap();
+        assert!(open(&no_fuel, &artifact, "abc123", &key.public_key()).unwrap().is_none());
+    }
+}
@@ -0,0 +1,132 @@
+//! Host capabilities an ark declares it needs
+//!
+//! `requires: [storage, rpc, http, secrets]` in `outlayer::metadata!` ends up as a
+//! `requires=` line in the `outlayer-metadata` custom section. The executor checks each
+//! entry against what this worker can provide for the job before compiling the WASM, for
+//! both P1 modules and P2 components, and fails with "Project requires capability X"
+//! instead of the ark running until its first call into the missing service.
+//!
+//! Compile workers send the declared list with the uploaded WASM (`/wasm/upload`), so the
+//! coordinator knows it before a job for that checksum is handed to an executor.
+//!
+//! Names this worker doesn't know (declared with a newer SDK) are treated as missing.
+
+use anyhow::Result;
+
+use super::egress::{metadata_section, section_list};
+use super::{ExecutionContext, HostFunctionPolicy};
+
+/// Host services available to one execution
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Available {
+    /// Storage config for the job's project, and `storage` not disabled
+    pub storage: bool,
+    /// RPC proxy configured, and `rpc` not disabled
+    pub rpc: bool,
+    /// Outbound wasi-http not disabled
+    pub http: bool,
+    /// Keystore configured to decrypt secrets
+    pub secrets: bool,
+}
+
+impl Available {
+    pub fn for_execution(exec_ctx: Option<&ExecutionContext>, policy: &HostFunctionPolicy) -> Self {
+        Self {
+            storage: exec_ctx.is_some_and(|ctx| ctx.storage_config.is_some())
+                && !policy.is_group_disabled("storage"),
+            rpc: exec_ctx.is_some_and(|ctx| ctx.outlayer_rpc.is_some()) && !policy.is_group_disabled("rpc"),
+            http: !policy.is_http_disabled(),
+            secrets: exec_ctx.is_some_and(|ctx| ctx.keystore_configured),
+        }
+    }
+
+    /// What is left for a WASI Preview 1 module: no component imports (storage, RPC)
+    /// and no wasi-http, only secrets passed as environment variables
+    pub fn for_preview1(self) -> Self {
+        Self {
+            storage: false,
+            rpc: false,
+            http: false,
+            ..self
+        }
+    }
+
+    fn provides(&self, capability: &str) -> bool {
+        match capability {
+            "storage" => self.storage,
+            "rpc" => self.rpc,
+            "http" => self.http,
+            "secrets" => self.secrets,
+            _ => false,
+        }
+    }
+}
+
+/// Fails on the first capability the WASM requires that `available` lacks
+pub fn check(wasm_bytes: &[u8], available: Available) -> Result<()> {
+    match metadata_section(wasm_bytes) {
+        Some(section) => check_section(&section, available),
+        None => Ok(()),
+    }
+}
+
+/// Capabilities declared by the WASM's `requires` metadata (empty if none)
+pub fn declared(wasm_bytes: &[u8]) -> Vec<String> {
+    metadata_section(wasm_bytes)
+        .and_then(|section| section_list(&section, "requires"))
+        .unwrap_or_default()
+}
+
+fn check_section(section: &str, available: Available) -> Result<()> {
+    for capability in section_list(section, "requires").unwrap_or_default() {
+        if !available.provides(&capability) {
+            anyhow::bail!(
+                "Project requires capability '{}', which this worker does not provide",
+                capability
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checks_declared_capabilities() {
+        let section = "project=alice.near/oracle\nversion=1.0.0\ntags=\nrequires=storage,http,\n";
+        let all = Available {
+            storage: true,
+            rpc: true,
+            http: true,
+            secrets: true,
+        };
+        assert!(check_section(section, all).is_ok());
+        assert!(check_section(section, Available { rpc: false, secrets: false, ..all }).is_ok());
+
+        let err = check_section(section, Available { storage: false, ..all }).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Project requires capability 'storage', which this worker does not provide"
+        );
+
+        // Nothing declared: runs anywhere
+        assert!(check_section("project=a.near/x\nversion=1.0.0\n", Available::default()).is_ok());
+        assert!(check_section("requires=\n", Available::default()).is_ok());
+
+        assert!(check_section("requires=gpu,\n", all).is_err());
+    }
+
+    #[test]
+    fn test_available_follows_operator_policy() {
+        let policy = HostFunctionPolicy::new(["http", "rpc.transfer"]).unwrap();
+        let available = Available::for_execution(None, &policy);
+        assert!(!available.http);
+        assert!(!available.storage && !available.rpc && !available.secrets);
+
+        let available = Available::for_execution(None, &HostFunctionPolicy::default());
+        assert!(available.http);
+        assert!(!available.for_preview1().http);
+    }
+}
@@ -0,0 +1,160 @@
+//! Structured errors reported with `outlayer::env::fail`
+//!
+//! The SDK writes one line `OUTLAYER_ERROR:{"code":...,"message":...,"details":...}` to
+//! stderr and exits with status 1. Stderr ends up in the error text of the failed
+//! execution; the last such line is parsed back out here and returned as
+//! `ExecutionResult::error_structured`, and replaced by `code: message` in the text so the
+//! string error stays readable.
+//!
+//! A line that isn't valid JSON, or whose `code` isn't a short identifier, is left in the
+//! text as-is: a guest can't get a structured error past these checks by accident.
+
+use serde_json::{json, Value};
+
+/// Stderr line prefix written by `env::fail`
+pub const STRUCTURED_ERROR_PREFIX: &str = "OUTLAYER_ERROR:";
+
+/// Longest accepted `code`
+pub const MAX_CODE_LEN: usize = 64;
+
+/// Longest `message` kept by [`compact`]
+const COMPACT_MESSAGE_CHARS: usize = 128;
+
+/// Split the structured error out of a failed execution's error text
+///
+/// Returns the text with the sentinel line replaced by `code: message`, and the error
+/// document `{"code", "message", "details"}` if one was found.
+pub fn extract(error: &str) -> (String, Option<Value>) {
+    let mut lines: Vec<&str> = error.lines().collect();
+    let found = lines
+        .iter()
+        .enumerate()
+        .rev()
+        .find_map(|(index, line)| parse_line(line).map(|document| (index, document)));
+
+    let Some((mut index, document)) = found else {
+        return (error.to_string(), None);
+    };
+
+    // `env::fail` starts the sentinel on a fresh line, which may leave an empty one before it
+    if index > 0 && lines[index - 1].is_empty() {
+        lines.remove(index - 1);
+        index -= 1;
+    }
+    let summary = format!(
+        "{}: {}",
+        document["code"].as_str().unwrap_or_default(),
+        document["message"].as_str().unwrap_or_default()
+    );
+    lines[index] = &summary;
+    (lines.join("\n"), Some(document))
+}
+
+/// Smaller form of an error document for the 1024 byte on-chain payload
+///
+/// `details` is dropped (flagged with `"details_truncated": true` when it had content) and
+/// the message is cut to its first 128 characters.
+pub fn compact(document: &Value) -> Value {
+    let message: String = document["message"]
+        .as_str()
+        .unwrap_or_default()
+        .chars()
+        .take(COMPACT_MESSAGE_CHARS)
+        .collect();
+    let mut compacted = json!({
+        "code": document["code"],
+        "message": message,
+        "details": Value::Null,
+    });
+    if !document["details"].is_null() {
+        compacted["details_truncated"] = Value::Bool(true);
+    }
+    compacted
+}
+
+/// Error document from one stderr line, if it is a valid sentinel line
+fn parse_line(line: &str) -> Option<Value> {
+    let payload = line.trim().strip_prefix(STRUCTURED_ERROR_PREFIX)?;
+    let value: Value = serde_json::from_str(payload).ok()?;
+    let code = value.get("code")?.as_str()?;
+    let message = value.get("message")?.as_str()?;
+    if !is_valid_code(code) {
+        return None;
+    }
+    Some(json!({
+        "code": code,
+        "message": message,
+        "details": value.get("details").cloned().unwrap_or(Value::Null),
+    }))
+}
+
+/// `code` is 1..=64 characters of `[A-Za-z0-9_.-]`
+fn is_valid_code(code: &str) -> bool {
+    !code.is_empty()
+        && code.len() <= MAX_CODE_LEN
+        && code.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extracts_last_sentinel_line() {
+        let stderr = "fetching balance\n\
+            OUTLAYER_ERROR:{\"code\":\"RETRY\",\"message\":\"first\",\"details\":null}\n\n\
+            OUTLAYER_ERROR:{\"code\":\"INSUFFICIENT_BALANCE\",\"message\":\"need 5 USDC\",\"details\":{\"required\":\"5000000\"}}\n\
+            Trap: Exited with i32 exit status 1";
+
+        let (text, document) = extract(stderr);
+        let document = document.unwrap();
+        assert_eq!(document["code"], "INSUFFICIENT_BALANCE");
+        assert_eq!(document["details"]["required"], "5000000");
+        assert_eq!(
+            text,
+            "fetching balance\n\
+             OUTLAYER_ERROR:{\"code\":\"RETRY\",\"message\":\"first\",\"details\":null}\n\
+             INSUFFICIENT_BALANCE: need 5 USDC\n\
+             Trap: Exited with i32 exit status 1"
+        );
+    }
+
+    #[test]
+    fn test_invalid_sentinel_is_left_in_text() {
+        for stderr in [
+            "OUTLAYER_ERROR:not json",
+            "OUTLAYER_ERROR:{\"code\":\"bad code!\",\"message\":\"m\"}",
+            "OUTLAYER_ERROR:{\"code\":\"\",\"message\":\"m\"}",
+            "OUTLAYER_ERROR:{\"code\":\"NO_MESSAGE\"}",
+            "plain panic message",
+        ] {
+            let (text, document) = extract(stderr);
+            assert!(document.is_none(), "unexpected document for {:?}", stderr);
+            assert_eq!(text, stderr);
+        }
+    }
+
+    #[test]
+    fn test_missing_details_is_null() {
+        let (_, document) = extract("OUTLAYER_ERROR:{\"code\":\"NOT_FOUND\",\"message\":\"pair not found\"}");
+        assert_eq!(document.unwrap()["details"], Value::Null);
+    }
+
+    #[test]
+    fn test_compact_drops_details() {
+        let document = json!({
+            "code": "RATE_LIMITED",
+            "message": "x".repeat(500),
+            "details": { "retry_after_secs": 30 },
+        });
+
+        let compacted = compact(&document);
+        assert_eq!(compacted["code"], "RATE_LIMITED");
+        assert_eq!(compacted["message"].as_str().unwrap().len(), 128);
+        assert_eq!(compacted["details"], Value::Null);
+        assert_eq!(compacted["details_truncated"], true);
+
+        let plain = compact(&json!({ "code": "A", "message": "b", "details": null }));
+        assert!(plain.get("details_truncated").is_none());
+    }
+}
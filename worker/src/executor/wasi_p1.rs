@@ -15,11 +15,13 @@
 use anyhow::{Context, Result};
 use std::collections::HashMap;
 use std::sync::OnceLock;
-use tracing::debug;
+use tracing::{debug, warn};
 use wasmtime::*;
 use wasmtime_wasi::preview1::{self, WasiP1Ctx};
 use wasmtime_wasi::WasiCtxBuilder;
 
+use super::determinism::{MemoryLimiter, PeakMemory};
+use super::log_capture::LogCapture;
 use crate::api_client::ResourceLimits;
 
 /// Global WASM engine for WASI P1 modules (core modules, NOT components)
@@ -38,56 +40,96 @@ static WASM_ENGINE_P1: OnceLock<Engine> = OnceLock::new();
 /// Get or initialize the global P1 engine
 ///
 /// This engine has component_model=false and is NOT compatible with P2 components.
-fn get_p1_engine() -> &'static Engine {
+pub(crate) fn get_p1_engine() -> &'static Engine {
     WASM_ENGINE_P1.get_or_init(|| {
         let mut config = Config::new();
         // NO component_model - P1 uses core modules
         config.async_support(true);        // Async execution
         config.consume_fuel(true);         // Instruction metering
         config.epoch_interruption(true);   // Allow interrupting host calls
+        super::determinism::configure_engine(&mut config);
         tracing::info!("⚡ Initialized global WASM engine for P1 (core modules)");
         Engine::new(&config).expect("Failed to create P1 WASM engine")
     })
 }
 
+/// Store data for WASI P1 execution
+struct P1State {
+    wasi: WasiP1Ctx,
+    /// Caps linear memory growth at max_memory_mb and tracks its peak (see `determinism`)
+    limits: MemoryLimiter,
+}
+
 /// Execute WASI Preview 1 module
 ///
 /// # Arguments
 /// * `wasm_bytes` - WASM module binary
+/// * `precompiled` - Verified AOT native code for `wasm_bytes` (see `precompiled`)
 /// * `input_data` - JSON input via stdin
 /// * `limits` - Resource limits (memory, instructions, time)
 /// * `env_vars` - Environment variables (from encrypted secrets)
 /// * `print_stderr` - Print WASM stderr to worker logs
+/// * `peak_memory` - Receives the linear memory high-water mark, also on failure
 ///
 /// # Returns
-/// * `Ok((output, fuel_consumed, refund_usd))` - Execution succeeded
+/// * `Ok((output, fuel_consumed, refund_usd, logs))` - Execution succeeded
 ///   - `refund_usd` is always None for P1 (no payment host function support)
+///   - `logs` - stderr lines, last `max_log_bytes` only
 /// * `Err(_)` - Not a valid P1 module or execution failed
 pub async fn execute(
     wasm_bytes: &[u8],
+    precompiled: Option<&[u8]>,
     input_data: &[u8],
     limits: &ResourceLimits,
     env_vars: Option<HashMap<String, String>>,
     print_stderr: bool,
-) -> Result<(Vec<u8>, u64, Option<u64>)> {
+    peak_memory: &PeakMemory,
+) -> Result<(Vec<u8>, u64, Option<u64>, Vec<String>)> {
+    let module = load_module(wasm_bytes, precompiled)?;
+
+    debug!("Loaded as WASI Preview 1 module (wasmtime)");
+
+    execute_module(&module, input_data, limits, env_vars, print_stderr, peak_memory).await
+}
+
+/// Load a module with the global P1 engine, from AOT native code when available
+fn load_module(wasm_bytes: &[u8], precompiled: Option<&[u8]>) -> Result<Module> {
     // Use global P1 engine (avoids ~50-100ms overhead per execution)
     let engine = get_p1_engine();
 
-    // Try to load as module
-    let module = wasmtime::Module::from_binary(&engine, wasm_bytes)
-        .context("Not a valid WASI Preview 1 module")?;
+    if let Some(native) = precompiled {
+        // SAFETY: `native` passed `precompiled::open` (signed by the deployment key for this engine)
+        match unsafe { Module::deserialize(engine, native) } {
+            Ok(module) => {
+                debug!("⚡ Loaded AOT-compiled P1 module");
+                return Ok(module);
+            }
+            Err(e) => warn!("⚠️ Failed to load AOT-compiled module, compiling from bytes: {}", e),
+        }
+    }
 
-    debug!("Loaded as WASI Preview 1 module (wasmtime)");
+    Module::from_binary(engine, wasm_bytes).context("Not a valid WASI Preview 1 module")
+}
+
+/// Execute an already compiled WASI Preview 1 module (compiled with the global P1 engine)
+async fn execute_module(
+    module: &Module,
+    input_data: &[u8],
+    limits: &ResourceLimits,
+    env_vars: Option<HashMap<String, String>>,
+    print_stderr: bool,
+    peak_memory: &PeakMemory,
+) -> Result<(Vec<u8>, u64, Option<u64>, Vec<String>)> {
+    let engine = get_p1_engine();
 
     // Create linker for WASI P1
     let mut linker = wasmtime::Linker::new(&engine);
-    preview1::add_to_linker_async(&mut linker, |t: &mut WasiP1Ctx| t)?;
+    preview1::add_to_linker_async(&mut linker, |t: &mut P1State| &mut t.wasi)?;
 
-    // Prepare stdin/stdout pipes
+    // Prepare stdin/stdout/stderr pipes
     let stdin_pipe = wasmtime_wasi::pipe::MemoryInputPipe::new(input_data.to_vec());
-    let stdout_pipe =
-        wasmtime_wasi::pipe::MemoryOutputPipe::new((limits.max_memory_mb as usize) * 1024 * 1024);
-    let stderr_pipe = wasmtime_wasi::pipe::MemoryOutputPipe::new(1024 * 1024);
+    let stdout_pipe = super::stdout_pipe(limits);
+    let stderr_pipe = LogCapture::new(limits.max_log_bytes as usize);
 
     // Build WASI P1 context
     let mut wasi_builder = WasiCtxBuilder::new();
@@ -103,10 +145,14 @@ pub async fn execute(
         }
     }
 
-    let wasi_p1_ctx = wasi_builder.build_p1();
+    let state = P1State {
+        wasi: wasi_builder.build_p1(),
+        limits: super::determinism::memory_limits(limits, peak_memory),
+    };
 
-    // Create store with fuel limit
-    let mut store = Store::new(&engine, wasi_p1_ctx);
+    // Create store with fuel limit and deterministic memory cap
+    let mut store = Store::new(&engine, state);
+    store.limiter(|state| &mut state.limits);
     store.set_fuel(limits.max_instructions)?;
     let timeout_secs = limits.max_execution_seconds.max(5);
     store.set_epoch_deadline(timeout_secs);
@@ -125,7 +171,7 @@ pub async fn execute(
     // Instantiate module
     debug!("Instantiating WASI P1 module");
     let instance = linker
-        .instantiate_async(&mut store, &module)
+        .instantiate_async(&mut store, module)
         .await
         .context("Failed to instantiate WASI P1 module")?;
 
@@ -138,22 +184,27 @@ pub async fn execute(
              Make sure you're using [[bin]] format with fn main(), not [lib] with cdylib",
         )?;
 
+    let started = std::time::Instant::now();
     let call_result = start.call_async(&mut store, ()).await;
     epoch_handle.abort();
 
     if let Err(e) = &call_result {
-        // Check if this was an epoch interruption (timeout)
-        if e.to_string().contains("interrupt") {
-            let fuel_consumed = limits.max_instructions - store.get_fuel().unwrap_or(0);
-            anyhow::bail!(
-                "WASM execution timed out after {} seconds (penalty). \
-                Full execution cost is charged with no refund (consumed {} instructions)",
-                timeout_secs,
-                fuel_consumed
-            );
+        if super::is_epoch_deadline(e) {
+            return Err(super::ExecutionTimeout {
+                limit_secs: timeout_secs,
+                elapsed_ms: started.elapsed().as_millis() as u64,
+                instructions: limits.max_instructions - store.get_fuel().unwrap_or(0),
+            }
+            .into());
         }
     }
 
+    super::check_output_size(
+        &stdout_pipe,
+        limits,
+        limits.max_instructions - store.get_fuel().unwrap_or(0),
+    )?;
+
     if let Err(e) = call_result {
         let error_str = e.to_string();
         tracing::error!("❌ WASI P1 _start failed: {}", error_str);
@@ -207,5 +258,185 @@ pub async fn execute(
     let output = stdout_pipe.contents().to_vec();
 
     // P1 does not support payment host functions, so refund_usd is always None
-    Ok((output, fuel_consumed, None))
+    Ok((output, fuel_consumed, None, stderr_pipe.lines()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_client::{DEFAULT_MAX_LOG_BYTES, DEFAULT_MAX_OUTPUT_BYTES};
+    use crate::executor::precompiled;
+    use near_crypto::{KeyType, SecretKey};
+
+    /// Grows memory until `memory.grow` fails and prints the final page count
+    const GROW_UNTIL_FULL_WAT: &str = r#"
+        (module
+          (import "wasi_snapshot_preview1" "fd_write"
+            (func $fd_write (param i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+
+          (func (export "_start")
+            (local $n i32)
+            (local $pos i32)
+            (loop $grow
+              (br_if $grow (i32.ne (memory.grow (i32.const 16)) (i32.const -1))))
+            (local.set $n (memory.size))
+            (local.set $pos (i32.const 1024))
+            (loop $digits
+              (local.set $pos (i32.sub (local.get $pos) (i32.const 1)))
+              (i32.store8 (local.get $pos)
+                (i32.add (i32.const 48) (i32.rem_u (local.get $n) (i32.const 10))))
+              (local.set $n (i32.div_u (local.get $n) (i32.const 10)))
+              (br_if $digits (i32.ne (local.get $n) (i32.const 0))))
+            (i32.store (i32.const 0) (local.get $pos))
+            (i32.store (i32.const 4) (i32.sub (i32.const 1024) (local.get $pos)))
+            (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))))
+    "#;
+
+    /// Writes the 10 bytes "0123456789" to stdout
+    const TEN_BYTES_WAT: &str = r#"
+        (module
+          (import "wasi_snapshot_preview1" "fd_write"
+            (func $fd_write (param i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 64) "0123456789")
+
+          (func (export "_start")
+            (i32.store (i32.const 0) (i32.const 64))
+            (i32.store (i32.const 4) (i32.const 10))
+            (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))))
+    "#;
+
+    /// Writes "result" to stdout and two lines to stderr
+    const STDERR_LINES_WAT: &str = r#"
+        (module
+          (import "wasi_snapshot_preview1" "fd_write"
+            (func $fd_write (param i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 64) "result")
+          (data (i32.const 128) "fetching price\nprice=42\n")
+
+          (func (export "_start")
+            (i32.store (i32.const 0) (i32.const 64))
+            (i32.store (i32.const 4) (i32.const 6))
+            (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))
+            (i32.store (i32.const 0) (i32.const 128))
+            (i32.store (i32.const 4) (i32.const 24))
+            (drop (call $fd_write (i32.const 2) (i32.const 0) (i32.const 1) (i32.const 8)))))
+    "#;
+
+    /// Spins forever without calling into the host
+    const BUSY_LOOP_WAT: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (func (export "_start")
+            (loop $spin (br $spin))))
+    "#;
+
+    fn test_limits(max_memory_mb: u32) -> ResourceLimits {
+        ResourceLimits {
+            max_instructions: 100_000_000,
+            max_memory_mb,
+            max_execution_seconds: 5,
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            max_log_bytes: DEFAULT_MAX_LOG_BYTES,
+        }
+    }
+
+    async fn run(wat: &str, limits: &ResourceLimits) -> Vec<u8> {
+        let module = Module::new(get_p1_engine(), wat).unwrap();
+        let (output, _, _, _) = execute_module(&module, b"", limits, None, false, &PeakMemory::default()).await.unwrap();
+        output
+    }
+
+    #[tokio::test]
+    async fn memory_growth_stops_at_max_memory_mb() {
+        let limits = test_limits(4);
+        let first = run(GROW_UNTIL_FULL_WAT, &limits).await;
+        let second = run(GROW_UNTIL_FULL_WAT, &limits).await;
+
+        // 4 MB = 64 pages of 64 KiB; growth in steps of 16 pages from 1 page stops at 49
+        assert_eq!(first, b"49");
+        assert_eq!(first, second);
+    }
+
+    #[tokio::test]
+    async fn peak_memory_is_the_high_water_mark() {
+        let module = Module::new(get_p1_engine(), GROW_UNTIL_FULL_WAT).unwrap();
+        let peak = PeakMemory::default();
+        execute_module(&module, b"", &test_limits(4), None, false, &peak).await.unwrap();
+
+        // Last allowed growth reached 49 pages; the refused step to 65 isn't counted
+        assert_eq!(peak.bytes(), 49 * 64 * 1024);
+
+        let module = Module::new(get_p1_engine(), TEN_BYTES_WAT).unwrap();
+        let peak = PeakMemory::default();
+        execute_module(&module, b"", &test_limits(16), None, false, &peak).await.unwrap();
+        // Never grows: the initial single page
+        assert_eq!(peak.bytes(), 64 * 1024);
+    }
+
+    #[tokio::test]
+    async fn output_over_max_output_bytes_fails() {
+        let module = Module::new(get_p1_engine(), TEN_BYTES_WAT).unwrap();
+
+        let exact = ResourceLimits { max_output_bytes: 10, ..test_limits(16) };
+        let (output, _, _, _) = execute_module(&module, b"", &exact, None, false, &PeakMemory::default()).await.unwrap();
+        assert_eq!(output, b"0123456789");
+
+        let capped = ResourceLimits { max_output_bytes: 4, ..test_limits(16) };
+        let err = execute_module(&module, b"", &capped, None, false, &PeakMemory::default()).await.unwrap_err();
+        assert!(
+            err.to_string().contains("Output exceeds max_output_bytes (4 bytes)"),
+            "unexpected error: {}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn stderr_is_returned_as_logs() {
+        let module = Module::new(get_p1_engine(), STDERR_LINES_WAT).unwrap();
+
+        let (output, _, _, logs) = execute_module(&module, b"", &test_limits(16), None, false, &PeakMemory::default()).await.unwrap();
+        assert_eq!(output, b"result");
+        assert_eq!(logs, vec!["fetching price", "price=42"]);
+
+        // Over max_log_bytes only the tail is kept, and the run still succeeds
+        let capped = ResourceLimits { max_log_bytes: 9, ..test_limits(16) };
+        let (_, _, _, logs) = execute_module(&module, b"", &capped, None, false, &PeakMemory::default()).await.unwrap();
+        assert_eq!(logs.last().unwrap(), "price=42");
+        assert!(logs[0].contains("truncated"), "unexpected logs: {:?}", logs);
+    }
+
+    // Multi-threaded: the epoch ticker must run while the guest spins
+    #[tokio::test(flavor = "multi_thread")]
+    async fn timeout_is_reported_as_execution_timeout() {
+        let module = Module::new(get_p1_engine(), BUSY_LOOP_WAT).unwrap();
+        // Enough fuel that the deadline, not fuel, stops the guest
+        let limits = ResourceLimits { max_instructions: u64::MAX / 2, ..test_limits(16) };
+
+        let err = execute_module(&module, b"", &limits, None, false, &PeakMemory::default()).await.unwrap_err();
+        let timeout = err.downcast_ref::<super::super::ExecutionTimeout>().expect("timeout error");
+        assert_eq!(timeout.limit_secs, 5);
+        assert!(err.to_string().contains("exceeded the 5s execution limit"), "unexpected error: {}", err);
+    }
+
+    #[tokio::test]
+    async fn precompiled_module_matches_compiled_from_bytes() {
+        let key = SecretKey::from_seed(KeyType::ED25519, "fastfs-sender");
+        let artifact = precompiled::precompile(get_p1_engine(), BOX_ADDRESS_WAT.as_bytes(), false, "box", &key).unwrap();
+        let native = precompiled::open(get_p1_engine(), &artifact, "box", &key.public_key())
+            .unwrap()
+            .expect("artifact built by the same engine");
+
+        // Garbage WASM bytes: the module can only come from the native code
+        let aot_module = load_module(b"not wasm", Some(native)).unwrap();
+        let jit_module = Module::new(get_p1_engine(), BOX_ADDRESS_WAT).unwrap();
+
+        let limits = test_limits(16);
+        let aot = execute_module(&aot_module, b"", &limits, None, false, &PeakMemory::default()).await.unwrap();
+        let jit = execute_module(&jit_module, b"", &limits, None, false, &PeakMemory::default()).await.unwrap();
+        assert_eq!(aot.0, b"4096");
+        assert_eq!(aot, jit);
+    }
 }
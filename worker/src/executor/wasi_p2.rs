@@ -40,13 +40,14 @@ static WASM_ENGINE_P2: OnceLock<Engine> = OnceLock::new();
 /// Get or initialize the global P2 engine
 ///
 /// This engine has component_model=true and is NOT compatible with P1 modules.
-fn get_p2_engine() -> &'static Engine {
+pub(crate) fn get_p2_engine() -> &'static Engine {
     WASM_ENGINE_P2.get_or_init(|| {
         let mut config = Config::new();
         config.wasm_component_model(true); // P2 ONLY: component model
         config.async_support(true);        // Required for wasi-http
         config.consume_fuel(true);         // Instruction metering
         config.epoch_interruption(true);   // Allow interrupting host calls (wasi-http)
+        super::determinism::configure_engine(&mut config);
         tracing::info!("⚡ Initialized global WASM engine for P2 (component model)");
         Engine::new(&config).expect("Failed to create P2 WASM engine")
     })
@@ -64,10 +65,15 @@ use crate::compiled_cache::CompiledCache;
 use crate::outlayer_rpc::RpcHostState;
 use crate::outlayer_storage::{StorageClient, StorageHostState, add_storage_to_linker};
 use crate::outlayer_payment::{PaymentHostState, add_payment_to_linker};
+use crate::outlayer_meter::add_meter_to_linker;
 use crate::outlayer_vrf::{VrfHostState, add_vrf_to_linker};
+use crate::outlayer_ckd::{CkdHostState, add_ckd_to_linker};
 use crate::outlayer_wallet::{WalletHostState, add_wallet_to_linker};
 
-use super::ExecutionContext;
+use super::determinism::{MemoryLimiter, PeakMemory};
+use super::egress::EgressPolicy;
+use super::log_capture::LogCapture;
+use super::{ExecutionContext, HostFunctionPolicy};
 
 /// Max time for a single outbound HTTP request from WASI (seconds)
 const HTTP_REQUEST_TIMEOUT_SECS: u64 = 30;
@@ -76,7 +82,7 @@ const HTTP_TIMEOUT_ABORT_THRESHOLD: u32 = 2;
 
 /// Host state for WASI P2 execution
 ///
-/// Contains WASI context, HTTP context, and optionally RPC proxy, storage, payment, VRF, and wallet state.
+/// Contains WASI context, HTTP context, and optionally RPC proxy, storage, payment, VRF, CKD, and wallet state.
 struct HostState {
     wasi_ctx: WasiCtx,
     wasi_http_ctx: WasiHttpCtx,
@@ -89,12 +95,20 @@ struct HostState {
     payment_state: Option<PaymentHostState>,
     /// VRF state (only present if keystore configured + request_id available)
     vrf_state: Option<VrfHostState>,
+    /// CKD state (only present if keystore configured + running in a project)
+    ckd_state: Option<CkdHostState>,
     /// Wallet state (only present if wallet_id in execution request)
     wallet_state: Option<WalletHostState>,
     /// Counter for timed-out HTTP requests (shared with spawned tasks)
     http_timeout_count: Arc<std::sync::atomic::AtomicU32>,
     /// Engine handle to force epoch interrupt when aborting due to HTTP abuse (Engine::clone is Arc)
     engine_handle: &'static Engine,
+    /// Outbound HTTP disabled by operator policy (DISABLED_HOST_FUNCTIONS contains "http")
+    http_disabled: bool,
+    /// Hosts outbound HTTP may reach (the ark's `allowed_hosts`)
+    egress: EgressPolicy,
+    /// Caps linear memory growth at max_memory_mb and tracks its peak (see `determinism`)
+    limits: MemoryLimiter,
 }
 
 impl WasiView for HostState {
@@ -174,6 +188,31 @@ impl WasiHttpView for HostState {
         request: hyper::Request<HyperOutgoingBody>,
         config: OutgoingRequestConfig,
     ) -> wasmtime_wasi_http::HttpResult<HostFutureIncomingResponse> {
+        if self.http_disabled {
+            tracing::warn!("WASI HTTP blocked: {}", super::host_policy::DISABLED_MESSAGE);
+            return Ok(HostFutureIncomingResponse::ready(Ok(Err(
+                wasmtime_wasi_http::bindings::http::types::ErrorCode::InternalError(
+                    Some(format!("{}: http", super::host_policy::DISABLED_MESSAGE))
+                )
+            ))));
+        }
+
+        let url = request.uri().to_string();
+        let host = request.uri().host().map(|h| h.to_string());
+        let port = request
+            .uri()
+            .port_u16()
+            .unwrap_or(if request.uri().scheme_str() == Some("http") { 80 } else { 443 });
+
+        if let Some(h) = host.as_deref().filter(|h| !self.egress.allows(h, port)) {
+            tracing::warn!("WASI HTTP blocked (not in allowed_hosts): {:?}", url);
+            return Ok(HostFutureIncomingResponse::ready(Ok(Err(
+                wasmtime_wasi_http::bindings::http::types::ErrorCode::InternalError(
+                    Some(format!("{}: {}:{}", super::egress::BLOCKED_MESSAGE, h, port))
+                )
+            ))));
+        }
+
         let timeout_count = self.http_timeout_count.clone();
         let engine = self.engine_handle; // &'static Engine
 
@@ -188,12 +227,6 @@ impl WasiHttpView for HostState {
             ))));
         }
 
-        let url = request.uri().to_string();
-        let host = request.uri().host().map(|h| h.to_string());
-        let port = request
-            .uri()
-            .port_u16()
-            .unwrap_or(if request.uri().scheme_str() == Some("http") { 80 } else { 443 });
         let timeout_duration = std::time::Duration::from_secs(HTTP_REQUEST_TIMEOUT_SECS);
 
         let handle = wasmtime_wasi::runtime::spawn(async move {
@@ -285,38 +318,66 @@ impl HostState {
         self.vrf_state.as_mut().expect("VRF state not initialized")
     }
 
+    /// Get CKD host state (for host function callbacks)
+    fn ckd_state_mut(&mut self) -> &mut CkdHostState {
+        self.ckd_state.as_mut().expect("CKD state not initialized")
+    }
+
     /// Get wallet host state (for host function callbacks)
     fn wallet_state_mut(&mut self) -> &mut WalletHostState {
         self.wallet_state.as_mut().expect("Wallet state not initialized")
     }
 }
 
+/// Load a component with the global P2 engine, from AOT native code when available
+fn load_component(engine: &Engine, wasm_bytes: &[u8], precompiled: Option<&[u8]>) -> Result<Component> {
+    if let Some(native) = precompiled {
+        // SAFETY: `native` passed `precompiled::open` (signed by the deployment key for this engine)
+        match unsafe { Component::deserialize(engine, native) } {
+            Ok(component) => {
+                debug!("⚡ Loaded AOT-compiled P2 component");
+                return Ok(component);
+            }
+            Err(e) => tracing::warn!("⚠️ Failed to load AOT-compiled component, compiling from bytes: {}", e),
+        }
+    }
+
+    Component::from_binary(engine, wasm_bytes).context("Not a valid WASI Preview 2 component")
+}
+
 /// Execute WASI Preview 2 component
 ///
 /// # Arguments
 /// * `wasm_bytes` - WASM component binary
 /// * `wasm_checksum` - SHA256 checksum of WASM bytes (for compiled cache key)
 /// * `compiled_cache` - Optional compiled component cache for ~10x speedup
+/// * `precompiled` - Verified AOT native code for `wasm_bytes`, used on a cache miss
 /// * `input_data` - JSON input via stdin
 /// * `limits` - Resource limits (memory, instructions, time)
 /// * `env_vars` - Environment variables (from encrypted secrets, includes ATTACHED_USD)
 /// * `print_stderr` - Print WASM stderr to worker logs
 /// * `exec_ctx` - Execution context with optional RPC proxy
+/// * `host_policy` - Host functions disabled by the operator (and HTTP egress default)
+/// * `peak_memory` - Receives the linear memory high-water mark, also on failure
 ///
 /// # Returns
-/// * `Ok((output, fuel_consumed, refund_usd))` - Execution succeeded
+/// * `Ok((output, fuel_consumed, refund_usd, logs))` - Execution succeeded
 ///   - `refund_usd` is Some if WASM called refund_usd() host function
+///   - `logs` - stderr lines, last `max_log_bytes` only
 /// * `Err(_)` - Not a valid P2 component or execution failed
 pub async fn execute(
     wasm_bytes: &[u8],
     wasm_checksum: Option<&str>,
     compiled_cache: Option<&Arc<Mutex<CompiledCache>>>,
+    precompiled: Option<&[u8]>,
     input_data: &[u8],
     limits: &ResourceLimits,
     env_vars: Option<HashMap<String, String>>,
     print_stderr: bool,
     exec_ctx: Option<&ExecutionContext>,
-) -> Result<(Vec<u8>, u64, Option<u64>)> {
+    host_policy: &HostFunctionPolicy,
+    peak_memory: &PeakMemory,
+) -> Result<(Vec<u8>, u64, Option<u64>, Vec<String>)> {
     // Use global P2 engine (avoids ~50-100ms overhead per execution)
     let engine = get_p2_engine();
 
@@ -329,10 +390,9 @@ pub async fn execute(
             debug!("⚡ Using compiled cache for {}", checksum);
             cached_component
         } else {
-            // Cache miss - compile from bytes
+            // Cache miss - load AOT native code or compile from bytes
             debug!("🔨 Compiling component (cache miss): {}", checksum);
-            let component = Component::from_binary(&engine, wasm_bytes)
-                .context("Not a valid WASI Preview 2 component")?;
+            let component = load_component(engine, wasm_bytes, precompiled)?;
 
             // Store in cache for next time
             if let Ok(mut c) = cache.lock() {
@@ -345,12 +405,16 @@ pub async fn execute(
         }
     } else {
         // No cache available - compile directly
-        Component::from_binary(&engine, wasm_bytes)
-            .context("Not a valid WASI Preview 2 component")?
+        load_component(engine, wasm_bytes, precompiled)?
     };
 
     debug!("Loaded as WASI Preview 2 component");
 
+    let egress = EgressPolicy::for_component(wasm_bytes, host_policy.is_http_default_deny())?;
+    if egress.is_restricted() {
+        debug!("Outbound HTTP limited to the ark's allowed_hosts");
+    }
+
     // Check which OutLayer SDK interfaces the WASM imports
     let has_storage_import = component.component_type().imports(&engine)
         .any(|(name, _)| name.contains("near:storage/api"));
@@ -393,7 +457,8 @@ pub async fn execute(
                 100, // max_calls
                 true, // allow_transactions
                 None, // No default signer - WASM provides signing keys
-            )?;
+            )?
+            .with_cache(outlayer_rpc.cache());
 
             // Add RPC host functions to linker
             crate::outlayer_rpc::add_rpc_to_linker(&mut linker, |state: &mut HostState| {
@@ -465,6 +530,9 @@ pub async fn execute(
         None
     };
 
+    // Instruction metering reads the store's fuel, so it needs no host state
+    add_meter_to_linker(&mut linker, limits.max_instructions)?;
+
     // Check if component imports VRF interface
     let has_vrf_import = component.component_type().imports(&engine)
         .any(|(name, _)| name.contains("near:vrf/api"));
@@ -494,6 +562,34 @@ pub async fn execute(
         None
     };
 
+    // Check if component imports CKD interface
+    let has_ckd_import = component.component_type().imports(&engine)
+        .any(|(name, _)| name.contains("near:ckd/api"));
+
+    let ckd_state = if has_ckd_import {
+        if let Some(ref ckd_cfg) = exec_ctx.and_then(|ctx| ctx.ckd_config.as_ref()) {
+            debug!("Adding CKD host functions to linker, project_id={}", ckd_cfg.project_id);
+
+            add_ckd_to_linker(&mut linker, |state: &mut HostState| {
+                state.ckd_state_mut()
+            })?;
+
+            Some(CkdHostState::new(
+                &ckd_cfg.project_id,
+                &ckd_cfg.keystore_url,
+                &ckd_cfg.keystore_auth_token,
+                ckd_cfg.tee_session_id.clone(),
+            ))
+        } else {
+            anyhow::bail!(
+                "WASM imports near:ckd/api but app keys are not available.\n\
+                App keys require: keystore configured + execution in a project."
+            );
+        }
+    } else {
+        None
+    };
+
     // Check if component imports wallet interface
     let has_wallet_import = component.component_type().imports(&engine)
         .any(|(name, _)| name.contains("outlayer:wallet/api"));
@@ -521,11 +617,13 @@ pub async fn execute(
         None
     };
 
+    // Replace host functions disabled by operator policy with trapping stubs
+    host_policy.apply_to_linker(&mut linker, &component, engine)?;
+
     // Prepare stdin/stdout/stderr pipes
     let stdin_pipe = wasmtime_wasi::pipe::MemoryInputPipe::new(input_data.to_vec());
-    let stdout_pipe =
-        wasmtime_wasi::pipe::MemoryOutputPipe::new((limits.max_memory_mb as usize) * 1024 * 1024);
-    let stderr_pipe = wasmtime_wasi::pipe::MemoryOutputPipe::new(1024 * 1024);
+    let stdout_pipe = super::stdout_pipe(limits);
+    let stderr_pipe = LogCapture::new(limits.max_log_bytes as usize);
 
     // Build WASI context
     let mut wasi_builder = WasiCtxBuilder::new();
@@ -563,13 +661,18 @@ pub async fn execute(
         storage_state,
         payment_state,
         vrf_state,
+        ckd_state,
         wallet_state,
         http_timeout_count: Arc::new(std::sync::atomic::AtomicU32::new(0)),
         engine_handle: engine,
+        http_disabled: host_policy.is_http_disabled(),
+        egress,
+        limits: super::determinism::memory_limits(limits, peak_memory),
     };
 
     // Create store with fuel limit + epoch deadline
     let mut store = Store::new(&engine, host_state);
+    store.limiter(|state| &mut state.limits);
     store.set_fuel(limits.max_instructions)?;
     let timeout_secs = limits.max_execution_seconds.max(5);
     // Epoch interruption: engine ticks every second, deadline = timeout_secs ticks.
@@ -599,6 +702,7 @@ pub async fn execute(
         .context("Failed to instantiate component")?;
 
     debug!("Running wasi:cli/run");
+    let started = std::time::Instant::now();
     let execution_result = command
         .wasi_cli_run()
         .call_run(&mut store)
@@ -649,9 +753,10 @@ pub async fn execute(
 
     match execution_result {
         Ok(Ok(())) => {
+            super::check_output_size(&stdout_pipe, limits, fuel_consumed)?;
             debug!("Component execution completed successfully");
             let output = stdout_pipe.contents().to_vec();
-            Ok((output, fuel_consumed, refund_usd))
+            Ok((output, fuel_consumed, refund_usd, stderr_pipe.lines()))
         }
         Ok(Err(_)) | Err(_) => {
             // Check if this was an epoch interruption (timeout)
@@ -659,19 +764,17 @@ pub async fn execute(
                 Err(e) => Some(e),
                 _ => None,
             };
-            let is_epoch_timeout = err_ref
-                .map(|e| e.to_string().contains("interrupt"))
-                .unwrap_or(false);
-
-            if is_epoch_timeout {
-                anyhow::bail!(
-                    "WASM execution timed out after {} seconds (penalty). \
-                    Full execution cost is charged with no refund (consumed {} instructions)",
-                    timeout_secs,
-                    fuel_consumed
-                );
+            if err_ref.is_some_and(super::is_epoch_deadline) {
+                return Err(super::ExecutionTimeout {
+                    limit_secs: timeout_secs,
+                    elapsed_ms: started.elapsed().as_millis() as u64,
+                    instructions: fuel_consumed,
+                }
+                .into());
             }
 
+            super::check_output_size(&stdout_pipe, limits, fuel_consumed)?;
+
             // Component exited with error or trapped
             let trap_msg = match &execution_result {
                 Err(e) => Some(e.to_string()),
@@ -37,6 +37,11 @@ enum FastfsData {
     Simple(SimpleFastfs),
 }
 
+/// Public URL of the files `sender` published to `receiver`
+pub fn directory_url(sender: &AccountId, receiver: &str) -> String {
+    format!("https://{}.fastfs.io/{}", sender, receiver)
+}
+
 /// FastFS client for uploading WASM files
 pub struct FastFsClient {
     client: JsonRpcClient,
@@ -64,20 +69,33 @@ impl FastFsClient {
     /// # Returns
     /// * `Ok(url)` - FastFS URL where the file is accessible
     pub async fn upload_wasm(&self, wasm_bytes: &[u8], checksum: &str) -> Result<String> {
-        let relative_path = format!("{}.wasm", checksum);
+        self.upload_file(&format!("{}.wasm", checksum), WASM_MIME_TYPE, wasm_bytes).await
+    }
+
+    /// Upload an arbitrary file to FastFS
+    ///
+    /// # Arguments
+    /// * `relative_path` - File name under the receiver directory
+    /// * `mime_type` - MIME type served by FastFS
+    /// * `content` - File bytes
+    ///
+    /// # Returns
+    /// * `Ok(url)` - FastFS URL where the file is accessible
+    pub async fn upload_file(&self, relative_path: &str, mime_type: &str, content: &[u8]) -> Result<String> {
+        let relative_path = relative_path.to_string();
 
         info!(
-            "📦 Uploading WASM to FastFS: {} ({} bytes)",
+            "📦 Uploading to FastFS: {} ({} bytes)",
             relative_path,
-            wasm_bytes.len()
+            content.len()
         );
 
         // Create FastFS data structure
         let fastfs_data = FastfsData::Simple(SimpleFastfs {
             relative_path: relative_path.clone(),
             content: Some(FastfsFileContent {
-                mime_type: WASM_MIME_TYPE.to_string(),
-                content: wasm_bytes.to_vec(),
+                mime_type: mime_type.to_string(),
+                content: content.to_vec(),
             }),
         });
 
@@ -166,12 +184,7 @@ impl FastFsClient {
         // Check if transaction succeeded
         match &outcome.status {
             near_primitives::views::FinalExecutionStatus::SuccessValue(_) => {
-                let url = format!(
-                    "https://{}.fastfs.io/{}/{}",
-                    self.signer.account_id,
-                    receiver_id,
-                    relative_path
-                );
+                let url = format!("{}/{}", directory_url(&self.signer.account_id, &self.receiver), relative_path);
 
                 info!("✅ FastFS upload successful!");
                 info!("   Transaction: {}", outcome.transaction_outcome.id);
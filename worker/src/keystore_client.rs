@@ -1,10 +1,35 @@
 //! Client for communicating with keystore worker
 //!
 //! Handles TEE attestation generation and secret decryption requests.
+//!
+//! `/decrypt` is on the execution path of every job with secrets, so transient failures
+//! (timeouts, dropped connections, 5xx) are retried with backoff. 4xx answers are returned
+//! at once: they carry the "not found" / "access denied" outcome the job status is built
+//! from. After `CIRCUIT_FAILURE_THRESHOLD` calls in a row fail transiently the keystore is
+//! considered down and calls fail immediately for `CIRCUIT_OPEN_DURATION`, instead of each
+//! job spending its retries on it.
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::time::{Duration, Instant};
+
+use crate::tx_retry::RetryPolicy;
+
+/// Consecutive transiently failed calls that open the circuit
+const CIRCUIT_FAILURE_THRESHOLD: u32 = 5;
+
+/// How long an open circuit fails calls without contacting the keystore
+const CIRCUIT_OPEN_DURATION: Duration = Duration::from_secs(30);
+
+/// Backoff for transient keystore failures; short, since a job is waiting on it
+fn keystore_retry_policy() -> RetryPolicy {
+    RetryPolicy {
+        max_retries: 2,
+        base_delay: Duration::from_millis(250),
+        max_delay: Duration::from_secs(2),
+    }
+}
 
 /// TEE attestation for keystore verification
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,6 +87,51 @@ pub struct KeystoreClient {
     tee_session_id: std::sync::Arc<std::sync::Mutex<Option<String>>>,
     /// TEE signing info for auto-reconnect (public key bytes + signing key)
     tee_signing_info: Option<std::sync::Arc<near_crypto::SecretKey>>,
+    /// Shared by all clones, so every job sees the keystore as down at once
+    circuit: std::sync::Arc<std::sync::Mutex<CircuitBreaker>>,
+}
+
+/// Consecutive failure count and, once it reaches the threshold, when calls resume
+#[derive(Debug, Default)]
+struct CircuitBreaker {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    /// Fails while the circuit is open
+    fn check(&self, now: Instant) -> Result<()> {
+        if let Some(until) = self.open_until.filter(|until| now < *until) {
+            anyhow::bail!(
+                "Keystore unavailable after {} consecutive failures, retrying in {}s",
+                self.consecutive_failures,
+                until.saturating_duration_since(now).as_secs().max(1)
+            );
+        }
+        Ok(())
+    }
+
+    fn record_success(&mut self) {
+        *self = Self::default();
+    }
+
+    /// After the cooldown one call gets through; if it fails too the circuit reopens
+    fn record_failure(&mut self, now: Instant) {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        if self.consecutive_failures >= CIRCUIT_FAILURE_THRESHOLD {
+            self.open_until = Some(now + CIRCUIT_OPEN_DURATION);
+        }
+    }
+}
+
+/// 5xx and 429: the keystore (or the proxy in front of it) may answer next time
+fn is_transient_status(status: reqwest::StatusCode) -> bool {
+    status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Timeouts and connection failures (refused, reset, closed mid-request)
+fn is_transient_error(err: &reqwest::Error) -> bool {
+    err.is_timeout() || err.is_connect() || err.is_request()
 }
 
 impl KeystoreClient {
@@ -70,14 +140,18 @@ impl KeystoreClient {
         Self {
             base_url,
             auth_token,
+            // One pooled client for all jobs; keepalive keeps the connection warm between them
             http_client: reqwest::Client::builder()
-                .timeout(std::time::Duration::from_secs(30))
-                .connect_timeout(std::time::Duration::from_secs(10))
+                .timeout(Duration::from_secs(30))
+                .connect_timeout(Duration::from_secs(10))
+                .tcp_keepalive(Duration::from_secs(30))
+                .pool_idle_timeout(Duration::from_secs(60))
                 .build()
                 .expect("Failed to build keystore HTTP client"),
             tee_mode,
             tee_session_id: std::sync::Arc::new(std::sync::Mutex::new(None)),
             tee_signing_info: None,
+            circuit: std::sync::Arc::new(std::sync::Mutex::new(CircuitBreaker::default())),
         }
     }
 
@@ -272,6 +346,33 @@ impl KeystoreClient {
         }
     }
 
+    /// Check that the keystore is reachable and reports status "ok"
+    pub async fn health_check(&self) -> Result<()> {
+        let url = format!("{}/health", self.base_url);
+
+        let response = self
+            .http_client
+            .get(&url)
+            .timeout(std::time::Duration::from_secs(5))
+            .send()
+            .await
+            .context("Failed to reach keystore")?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Keystore health check failed: {}", response.status());
+        }
+
+        let data: serde_json::Value = response
+            .json()
+            .await
+            .context("Failed to parse keystore health response")?;
+
+        match data["status"].as_str() {
+            Some("ok") => Ok(()),
+            other => anyhow::bail!("Keystore unhealthy: status={:?}", other),
+        }
+    }
+
     /// Get keystore public key (for testing/verification)
     #[allow(dead_code)]
     pub async fn get_public_key(&self) -> Result<String> {
@@ -306,11 +407,14 @@ impl KeystoreClient {
     /// 4. Keystore decrypts using derived key for seed
     /// 5. Returns HashMap of environment variables
     ///
+    /// `version` pins a retained secret version (`set_secrets`); None reads the current one.
+    ///
     /// Note: This requires keystore to have NEAR RPC access configured
     pub async fn decrypt_secrets(
         &self,
         accessor: SecretAccessor,
         profile: &str,
+        version: Option<u32>,
         owner: &str,
         user_account_id: &str,
         task_id: Option<&str>,
@@ -341,6 +445,8 @@ impl KeystoreClient {
         struct DecryptRequest {
             accessor: SecretAccessor,
             profile: String,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            version: Option<u32>,
             owner: String,
             user_account_id: String,
             attestation: Attestation,
@@ -350,6 +456,7 @@ impl KeystoreClient {
         let request = DecryptRequest {
             accessor: accessor.clone(),
             profile: profile.to_string(),
+            version,
             owner: owner.to_string(),
             user_account_id: user_account_id.to_string(),
             attestation,
@@ -376,11 +483,9 @@ impl KeystoreClient {
             "🔑 Sending decrypt request to keystore"
         );
 
-        let response = self.add_auth_headers(self.http_client.post(&url))
-            .json(&request)
-            .send()
-            .await
-            .context("Failed to send decrypt request")?;
+        let response = self
+            .send_with_retry("decrypt", || self.add_auth_headers(self.http_client.post(&url)).json(&request))
+            .await?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -395,16 +500,17 @@ impl KeystoreClient {
                     let retry_request = DecryptRequest {
                         accessor: accessor.clone(),
                         profile: profile.to_string(),
+                        version,
                         owner: owner.to_string(),
                         user_account_id: user_account_id.to_string(),
                         attestation,
                         task_id: task_id.map(|s| s.to_string()),
                     };
-                    let retry_response = self.add_auth_headers(self.http_client.post(&url))
-                        .json(&retry_request)
-                        .send()
-                        .await
-                        .context("Failed to send retry decrypt request")?;
+                    let retry_response = self
+                        .send_with_retry("decrypt", || {
+                            self.add_auth_headers(self.http_client.post(&url)).json(&retry_request)
+                        })
+                        .await?;
 
                     if retry_response.status().is_success() {
                         let body = retry_response.bytes().await
@@ -484,6 +590,55 @@ impl KeystoreClient {
         Ok(env_vars)
     }
 
+    /// Send a request, retrying transient failures, behind the circuit breaker
+    ///
+    /// Returns the first non-transient response (success or 4xx) as is; a 5xx that is
+    /// still failing after the last retry is returned too, for the caller's error handling.
+    /// An open circuit fails with its own message, which the job reports as a plain failure.
+    async fn send_with_retry(
+        &self,
+        operation: &str,
+        build: impl Fn() -> reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response> {
+        self.circuit.lock().unwrap().check(Instant::now())?;
+
+        let policy = keystore_retry_policy();
+        let mut attempt = 0;
+        loop {
+            let result = build().send().await;
+            let transient = match &result {
+                Ok(response) => is_transient_status(response.status()),
+                Err(e) => is_transient_error(e),
+            };
+
+            if !transient || attempt >= policy.max_retries {
+                let mut circuit = self.circuit.lock().unwrap();
+                if transient {
+                    circuit.record_failure(Instant::now());
+                } else if result.is_ok() {
+                    circuit.record_success();
+                }
+                return result.with_context(|| format!("Failed to send {} request", operation));
+            }
+
+            let delay = policy.delay(attempt);
+            let reason = match &result {
+                Ok(response) => response.status().to_string(),
+                Err(e) => e.to_string(),
+            };
+            tracing::warn!(
+                "Keystore {} failed transiently (attempt {}/{}): {}. Retrying in {:?}",
+                operation,
+                attempt + 1,
+                policy.max_retries + 1,
+                reason,
+                delay
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
     /// Decrypt secrets from contract (convenience wrapper for Repo accessor)
     ///
     /// This is a convenience method that wraps decrypt_secrets with Repo accessor.
@@ -492,6 +647,7 @@ impl KeystoreClient {
         repo: &str,
         branch: Option<&str>,
         profile: &str,
+        version: Option<u32>,
         owner: &str,
         user_account_id: &str,
         task_id: Option<&str>,
@@ -500,7 +656,7 @@ impl KeystoreClient {
             repo: repo.to_string(),
             branch: branch.map(|s| s.to_string()),
         };
-        self.decrypt_secrets(accessor, profile, owner, user_account_id, task_id).await
+        self.decrypt_secrets(accessor, profile, version, owner, user_account_id, task_id).await
     }
 
     /// Decrypt secrets from contract by WASM hash (convenience wrapper for WasmHash accessor)
@@ -510,6 +666,7 @@ impl KeystoreClient {
         &self,
         wasm_hash: &str,
         profile: &str,
+        version: Option<u32>,
         owner: &str,
         user_account_id: &str,
         task_id: Option<&str>,
@@ -517,7 +674,7 @@ impl KeystoreClient {
         let accessor = SecretAccessor::WasmHash {
             hash: wasm_hash.to_string(),
         };
-        self.decrypt_secrets(accessor, profile, owner, user_account_id, task_id).await
+        self.decrypt_secrets(accessor, profile, version, owner, user_account_id, task_id).await
     }
 
     /// Decrypt secrets from contract by project ID (convenience wrapper for Project accessor)
@@ -528,6 +685,7 @@ impl KeystoreClient {
         &self,
         project_id: &str,
         profile: &str,
+        version: Option<u32>,
         owner: &str,
         user_account_id: &str,
         task_id: Option<&str>,
@@ -535,7 +693,7 @@ impl KeystoreClient {
         let accessor = SecretAccessor::Project {
             project_id: project_id.to_string(),
         };
-        self.decrypt_secrets(accessor, profile, owner, user_account_id, task_id).await
+        self.decrypt_secrets(accessor, profile, version, owner, user_account_id, task_id).await
     }
 
     /// Generate VRF output via keystore
@@ -775,6 +933,43 @@ mod base64 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_transient_statuses() {
+        for code in [500, 502, 503, 504, 429] {
+            assert!(is_transient_status(reqwest::StatusCode::from_u16(code).unwrap()), "{}", code);
+        }
+        // Not found / access denied / bad request decide the job status, never retried
+        for code in [400, 401, 403, 404] {
+            assert!(!is_transient_status(reqwest::StatusCode::from_u16(code).unwrap()), "{}", code);
+        }
+    }
+
+    #[test]
+    fn test_circuit_opens_after_threshold_and_recovers() {
+        let now = Instant::now();
+        let mut circuit = CircuitBreaker::default();
+        for _ in 0..CIRCUIT_FAILURE_THRESHOLD - 1 {
+            circuit.record_failure(now);
+        }
+        assert!(circuit.check(now).is_ok());
+
+        circuit.record_failure(now);
+        let err = circuit.check(now).unwrap_err().to_string();
+        assert!(err.contains("Keystore unavailable"));
+        // Must not look like a user-side outcome to the job status mapping
+        assert!(!err.contains("not found") && !err.contains("denied"));
+
+        // Half-open after the cooldown: one more failure reopens it at once
+        let later = now + CIRCUIT_OPEN_DURATION;
+        assert!(circuit.check(later).is_ok());
+        circuit.record_failure(later);
+        assert!(circuit.check(later).is_err());
+
+        circuit.record_success();
+        assert!(circuit.check(later).is_ok());
+        assert_eq!(circuit.consecutive_failures, 0);
+    }
+
     #[test]
     fn test_generate_attestation_outlayer_tee() {
         let client = KeystoreClient::new(
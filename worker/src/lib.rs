@@ -1,17 +1,26 @@
 // Export modules for testing
 pub mod api_client;
+pub mod block_checkpoint;
 pub mod executor;
 pub mod compiler;
 pub mod near_client;
+pub mod tx_retry;
+pub mod result_signing;
 pub mod config;
 pub mod keystore_client;
+pub mod metrics;
 pub mod collateral_fetcher;
 pub mod fastfs;
 pub mod outlayer_rpc;
 pub mod outlayer_storage;
 pub mod outlayer_payment;
+pub mod outlayer_meter;
 pub mod outlayer_vrf;
+pub mod outlayer_ckd;
 pub mod outlayer_wallet;
 pub mod wasm_cache;
+pub mod wasm_compression;
 pub mod compiled_cache;
 pub mod event_monitor;
+pub mod worker_status;
+pub mod shutdown;
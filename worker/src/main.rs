@@ -1,5 +1,7 @@
 mod api_client;
+mod block_checkpoint;
 mod collateral_fetcher;
+mod compile_pipeline;
 mod compiled_cache;
 mod compiler;
 mod config;
@@ -7,15 +9,25 @@ mod event_monitor;
 mod executor;
 mod fastfs;
 mod keystore_client;
+mod metrics;
 mod near_client;
 mod registration;
+mod result_signing;
+mod shutdown;
 mod outlayer_rpc;
 mod outlayer_storage;
 mod outlayer_payment;
+mod outlayer_meter;
 mod outlayer_vrf;
+mod outlayer_ckd;
 mod outlayer_wallet;
+mod preflight;
 mod tdx_attestation;
+mod tx_retry;
 mod wasm_cache;
+mod wasm_compression;
+mod wasm_spool;
+mod worker_status;
 
 use anyhow::{Context, Result};
 use std::sync::{Arc, Mutex};
@@ -24,15 +36,20 @@ use tracing::{debug, error, info, warn};
 use api_client::{ApiClient, CodeSource, ExecutionResult, JobInfo, JobStatus, JobType};
 use compiled_cache::CompiledCache;
 use wasm_cache::WasmCache;
-use collateral_fetcher::fetch_collateral_from_phala;
+use wasm_spool::WasmBytes;
+use collateral_fetcher::{fetch_collateral_from_phala, CachedCollateral, CollateralCheck, CollateralInfo};
+use compile_pipeline::{CachedWasm, CompilePipeline};
 use compiler::Compiler;
 use config::Config;
 use event_monitor::EventMonitor;
-use executor::{Executor, ExecutionContext};
+use executor::{Executor, ExecutionContext, HostFunctionPolicy, PrecompiledStore};
 use keystore_client::KeystoreClient;
+use metrics::metrics;
 use near_client::NearClient;
 use outlayer_storage::StorageConfig;
+use shutdown::Shutdown;
 use tdx_attestation::{TdxClient, get_phala_app_info};
+use worker_status::WorkerStatus;
 
 /// Generate a dummy TDX quote and fetch collateral from Phala Cloud API
 ///
@@ -74,6 +91,11 @@ async fn main() -> Result<()> {
     let mut config = Config::from_env().context("Failed to load configuration")?;
     config.validate().context("Invalid configuration")?;
 
+    // Pre-flight TEE diagnostics: report measurements and collateral, then exit
+    if preflight::requested() {
+        return preflight::run(&config).await;
+    }
+
     // Auto-generate worker_id if not explicitly set via WORKER_ID env var
     if !Config::is_worker_id_from_env() {
         info!("🔍 WORKER_ID not set, auto-generating from network + type + Phala app_id...");
@@ -166,8 +188,13 @@ async fn main() -> Result<()> {
     // Initialize compiler (only if compilation capability enabled)
     let compiler = if config.capabilities.can_compile() {
         info!("✅ Compilation capability enabled - initializing compiler");
-        Some(Compiler::new(api_client.clone(), config.clone())
-            .context("Failed to create compiler")?)
+        let compiler = Compiler::new(api_client.clone(), config.clone())
+            .context("Failed to create compiler")?;
+        // Arc: compile jobs run on their own tasks (see compile_pipeline)
+        Some(Arc::new(match wasm_cache {
+            Some(ref cache) => compiler.with_wasm_cache(cache.clone()),
+            None => compiler,
+        }))
     } else {
         info!("⚠️  Compilation capability disabled - will only handle Execute jobs");
         None
@@ -176,11 +203,18 @@ async fn main() -> Result<()> {
     // Initialize RPC proxy if enabled
     let rpc_proxy = if config.rpc_proxy.enabled {
         info!("🔧 Initializing NEAR RPC proxy...");
-        let proxy = outlayer_rpc::RpcProxy::new(
+        let mut proxy = outlayer_rpc::RpcProxy::new(
             config.rpc_proxy.clone(),
             &config.near_rpc_url,
         )?;
         info!("✅ RPC proxy initialized: {}", proxy.get_rpc_url_masked());
+        if config.rpc_cache_max_size_mb > 0 {
+            info!("🗄️  RPC result cache: {}MB, TTL {}s", config.rpc_cache_max_size_mb, config.rpc_cache_ttl_seconds);
+            proxy = proxy.with_cache(Arc::new(outlayer_rpc::RpcCache::new(
+                config.rpc_cache_max_size_mb,
+                std::time::Duration::from_secs(config.rpc_cache_ttl_seconds),
+            )));
+        }
         Some(proxy)
     } else {
         info!("⚠️  RPC proxy disabled - WASM modules cannot make NEAR RPC calls");
@@ -207,7 +241,7 @@ async fn main() -> Result<()> {
     };
 
     // Initialize TDX client for task attestations
-    let tdx_client = tdx_attestation::TdxClient::new(config.tee_mode.clone());
+    let tdx_client = Arc::new(tdx_attestation::TdxClient::new(config.tee_mode.clone()));
 
     // IMPORTANT: Worker registration MUST happen BEFORE creating NearClient
     // because NearClient requires operator_signer which is generated during registration
@@ -415,6 +449,13 @@ async fn main() -> Result<()> {
 
         // Only create cache if we have a valid key
         if secret_key_bytes != [0u8; 32] {
+            // Same key signs the compile index so restarts reuse previous builds
+            if let Some(ref cache) = wasm_cache {
+                if let Ok(mut c) = cache.lock() {
+                    c.enable_compile_index(&secret_key_bytes);
+                }
+            }
+
             let compiled_cache_dir = std::path::PathBuf::from(&config.wasm_cache_dir).join("compiled");
             match CompiledCache::new(compiled_cache_dir.clone(), config.wasm_cache_max_size_mb, &secret_key_bytes) {
                 Ok(cache) => {
@@ -436,11 +477,13 @@ async fn main() -> Result<()> {
     };
 
     // Initialize executor with RPC proxy and compiled cache
+    let mut rpc_cache = None;
     let executor = {
         let runtime_handle = tokio::runtime::Handle::current();
         let mut exec_context = ExecutionContext::new(runtime_handle);
 
         if let Some(proxy) = rpc_proxy {
+            rpc_cache = proxy.cache();
             exec_context = exec_context.with_outlayer_rpc(proxy);
         }
 
@@ -448,8 +491,33 @@ async fn main() -> Result<()> {
             exec_context = exec_context.with_compiled_cache(cache.clone());
         }
 
-        Executor::new(config.default_max_instructions, config.print_wasm_stderr)
-            .with_context(exec_context)
+        if keystore_client.is_some() {
+            exec_context = exec_context.with_keystore();
+        }
+
+        // Load AOT artifacts published by compile workers (signed by the FastFS sender key)
+        if config.aot_precompile {
+            if let (Some(receiver), Some(sender)) = (&config.fastfs_receiver, &config.fastfs_sender_signer) {
+                let base_url = fastfs::directory_url(&sender.account_id, receiver);
+                info!("⚡ AOT artifacts enabled: {}", base_url);
+                exec_context = exec_context.with_precompiled(PrecompiledStore::new(base_url, sender.public_key())?);
+            }
+        }
+
+        let host_policy = HostFunctionPolicy::new(&config.disabled_host_functions)?
+            .with_http_default_deny(config.http_egress_default_deny);
+        if !host_policy.is_empty() {
+            info!("🚫 Host functions disabled by operator policy: {:?}", config.disabled_host_functions);
+        }
+        if config.http_egress_default_deny {
+            info!("🚫 Outbound HTTP blocked for arks without allowed_hosts");
+        }
+
+        Arc::new(
+            Executor::new(config.default_max_instructions, config.print_wasm_stderr)
+                .with_context(exec_context)
+                .with_host_policy(host_policy),
+        )
     };
 
     // Create NearClient with operator signer from registration
@@ -458,7 +526,8 @@ async fn main() -> Result<()> {
         config.get_operator_signer().clone(),
         config.offchainvm_contract_id.clone(),
     )
-    .context("Failed to create NEAR client")?;
+    .context("Failed to create NEAR client")?
+    .with_retry_policy(config.near_submit_retry_policy());
     info!("NEAR client initialized");
 
     // Shared event monitor block height for heartbeat reporting
@@ -467,13 +536,84 @@ async fn main() -> Result<()> {
     // Shared timestamp of last successful poll (epoch seconds)
     let shared_last_poll_at = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
 
+    // Current job / in-flight count, updated by the worker loop and reported in heartbeats
+    let worker_status = Arc::new(WorkerStatus::new());
+
+    // Bounded compile slots + compile → execute WASM handoff
+    info!("🔨 Max concurrent compiles: {}", config.max_concurrent_compiles);
+    let compile_pipeline = Arc::new(CompilePipeline::new(config.max_concurrent_compiles));
+
+    // Prometheus metrics endpoint (off unless METRICS_PORT is set)
+    if let Some(port) = config.metrics_port {
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve(port).await {
+                error!("❌ Metrics endpoint stopped: {}", e);
+            }
+        });
+    }
+
+    // Watch the TDX collateral cached on the register contract
+    if config.use_tee_registration && config.collateral_check_interval_seconds > 0 {
+        let collateral_tdx_client = tdx_client.clone();
+        let collateral_near_client = near_client.clone();
+        let register_contract_id = config.operator_account_id.clone();
+        let interval = std::time::Duration::from_secs(config.collateral_check_interval_seconds);
+        let warn_before = std::time::Duration::from_secs(config.collateral_expiry_warning_hours * 3600);
+        tokio::spawn(async move {
+            run_collateral_refresh(
+                collateral_tdx_client,
+                collateral_near_client,
+                register_contract_id,
+                interval,
+                warn_before,
+            )
+            .await;
+        });
+        info!(
+            "🔏 Collateral check started (every {}s, warning {}h before expiry)",
+            config.collateral_check_interval_seconds, config.collateral_expiry_warning_hours
+        );
+    }
+
+    // Probe keystore health and operator key allowance for heartbeats, off the heartbeat path
+    let probe_worker_status = worker_status.clone();
+    let probe_keystore_client = keystore_client.clone();
+    let probe_near_client = near_client.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(30));
+        loop {
+            interval.tick().await;
+            let keystore_healthy = match probe_keystore_client {
+                Some(ref kc) => match kc.health_check().await {
+                    Ok(()) => true,
+                    Err(e) => {
+                        warn!("Keystore health check failed: {}", e);
+                        false
+                    }
+                },
+                None => false,
+            };
+            let allowance_remaining = match probe_near_client.access_key_allowance().await {
+                Ok(allowance) => allowance,
+                Err(e) => {
+                    debug!("Failed to query operator key allowance: {}", e);
+                    None
+                }
+            };
+            probe_worker_status.record_probe(keystore_healthy, allowance_remaining);
+        }
+    });
+
     // Start heartbeat task
     let heartbeat_api_client = api_client.clone();
+    let heartbeat_worker_status = worker_status.clone();
+    let heartbeat_capabilities = config.capabilities.to_array();
     let heartbeat_worker_id = config.worker_id.clone();
     // Worker name = worker_id (already descriptive: mainnet-executor-75ab6ac2)
     let heartbeat_worker_name = config.worker_id.clone();
     let heartbeat_block_height = shared_block_height.clone();
     let heartbeat_last_poll_at = shared_last_poll_at.clone();
+    let heartbeat_rpc_cache = rpc_cache.clone();
     let stale_threshold_secs = config.poll_timeout_seconds + config.max_execution_seconds_cap + config.iteration_overhead_seconds;
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(30));
@@ -493,14 +633,16 @@ async fn main() -> Result<()> {
             } else {
                 "online"
             };
+            let mut live = heartbeat_worker_status.heartbeat_status(heartbeat_capabilities.clone());
+            live.rpc_cache = heartbeat_rpc_cache.as_ref().map(|cache| cache.stats());
             if let Err(e) = heartbeat_api_client
                 .send_heartbeat(
                     heartbeat_worker_id.clone(),
                     heartbeat_worker_name.clone(),
                     status,
-                    None,
                     event_monitor_block_height,
                     last_poll_at,
+                    live,
                 )
                 .await
             {
@@ -522,6 +664,8 @@ async fn main() -> Result<()> {
         let event_filter_function_name = config.event_filter_function_name.clone();
         let event_filter_min_version = config.event_filter_min_version.clone();
         let monitor_block_height = shared_block_height.clone();
+        let checkpoint_path = std::path::PathBuf::from(&config.event_monitor_checkpoint_path);
+        let reorg_depth = config.event_monitor_reorg_depth;
 
         tokio::spawn(async move {
             info!("Starting event monitor...");
@@ -536,6 +680,8 @@ async fn main() -> Result<()> {
                 event_filter_function_name,
                 event_filter_min_version,
                 monitor_block_height,
+                checkpoint_path,
+                reorg_depth,
             )
             .await
             {
@@ -583,8 +729,11 @@ async fn main() -> Result<()> {
     );
     info!("⏱️ Iteration timeout: {}s (poll={}s + cap={}s + overhead={}s)",
         iteration_timeout.as_secs(), config.poll_timeout_seconds, config.max_execution_seconds_cap, config.iteration_overhead_seconds);
-    loop {
-        match tokio::time::timeout(
+    let shutdown = Shutdown::listen();
+    let grace_period = tokio::time::Duration::from_secs(config.shutdown_grace_period_seconds);
+    let mut shutdown_deadline = None;
+    while !shutdown.is_requested() {
+        let iteration = tokio::time::timeout(
             iteration_timeout,
             worker_iteration(
                 &api_client,
@@ -596,10 +745,32 @@ async fn main() -> Result<()> {
                 &config,
                 wasm_cache.as_ref(),
                 compiled_cache.as_ref(),
+                &worker_status,
+                &compile_pipeline,
+                &shutdown,
             ),
-        )
-        .await
-        {
+        );
+        tokio::pin!(iteration);
+        let outcome = tokio::select! {
+            outcome = &mut iteration => outcome,
+            // Stopped polling inside the iteration; a job in hand gets the grace period to finish
+            _ = shutdown.requested() => {
+                let deadline = tokio::time::Instant::now() + grace_period;
+                shutdown_deadline = Some(deadline);
+                match tokio::time::timeout_at(deadline, &mut iteration).await {
+                    Ok(outcome) => outcome,
+                    Err(_) => {
+                        warn!(
+                            "⏰ Shutdown grace period of {}s expired, abandoning job {:?}",
+                            grace_period.as_secs(),
+                            worker_status.current_job_id()
+                        );
+                        break;
+                    }
+                }
+            }
+        };
+        match outcome {
             Ok(result) => {
                 // Update last poll timestamp — iteration returned, poll is alive
                 shared_last_poll_at.store(
@@ -626,38 +797,80 @@ async fn main() -> Result<()> {
             }
         }
     }
+
+    let deadline = shutdown_deadline.unwrap_or_else(|| tokio::time::Instant::now() + grace_period);
+    drain_background_jobs(&worker_status, deadline).await;
+    info!("👋 Worker stopped");
+    Ok(())
+}
+
+/// Wait for compile jobs still running on background tasks, up to `deadline`
+async fn drain_background_jobs(worker_status: &WorkerStatus, deadline: tokio::time::Instant) {
+    while worker_status.in_flight_count() > 0 {
+        if tokio::time::Instant::now() >= deadline {
+            warn!(
+                "⏰ Shutdown grace period expired with {} job(s) in flight, abandoning them (current job {:?})",
+                worker_status.in_flight_count(),
+                worker_status.current_job_id()
+            );
+            return;
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+    }
 }
 
 /// Single iteration of the worker loop
 ///
-/// Returns Ok(true) if a task was processed, Ok(false) if no task available
+/// Returns Ok(true) if a task was processed (or handed to a compile task), Ok(false) if no
+/// task available
 async fn worker_iteration(
     api_client: &ApiClient,
-    compiler: Option<&Compiler>,
-    executor: &Executor,
+    compiler: Option<&Arc<Compiler>>,
+    executor: &Arc<Executor>,
     near_client: &NearClient,
     keystore_client: Option<&KeystoreClient>,
-    tdx_client: &tdx_attestation::TdxClient,
+    tdx_client: &Arc<tdx_attestation::TdxClient>,
     config: &Config,
     wasm_cache: Option<&Arc<Mutex<WasmCache>>>,
     compiled_cache: Option<&Arc<Mutex<CompiledCache>>>,
+    worker_status: &Arc<WorkerStatus>,
+    compile_pipeline: &Arc<CompilePipeline>,
+    shutdown: &Shutdown,
 ) -> Result<bool> {
-    // Poll for a task (with long-polling) - specify capabilities to poll correct queue
-    let capabilities = config.capabilities.to_array();
+    // Poll for a task (with long-polling) - specify capabilities to poll correct queue.
+    // While every compile slot is busy, drop "compilation" so the coordinator only hands
+    // us tasks whose WASM is already built.
+    let mut capabilities = config.capabilities.to_array();
+    if !compile_pipeline.has_free_slot() {
+        capabilities.retain(|c| c != "compilation");
+        if capabilities.is_empty() {
+            // Compile-only worker with all slots busy: nothing to poll for until one frees up
+            tokio::select! {
+                permit = compile_pipeline.acquire() => drop(permit),
+                _ = shutdown.requested() => {}
+            }
+            return Ok(false);
+        }
+    }
     debug!("🔄 Polling for task (timeout={}s)...", config.poll_timeout_seconds);
-    let task = api_client
-        .poll_task(config.poll_timeout_seconds, &capabilities)
-        .await
-        .context("Failed to poll for task")?;
+    let task = tokio::select! {
+        task = api_client.poll_task(config.poll_timeout_seconds, &capabilities) => {
+            task.context("Failed to poll for task")?
+        }
+        // Nothing claimed yet: stop waiting for work
+        _ = shutdown.requested() => return Ok(false),
+    };
     debug!("🔄 Poll returned: {}", if task.is_some() { "task received" } else { "no task" });
 
     let Some(execution_request) = task else {
         // No execution request available
+        metrics().record_poll_idle();
         return Ok(false);
     };
 
-    info!("📨 Received execution request: request_id={} project_uuid={:?} project_id={:?}",
-        execution_request.request_id, execution_request.project_uuid, execution_request.project_id);
+    info!("📨 Received execution request: request_id={} project_uuid={:?} project_id={:?} priority={}",
+        execution_request.request_id, execution_request.project_uuid, execution_request.project_id,
+        execution_request.priority);
 
     // Extract request details
     let request_id = execution_request.request_id;
@@ -683,6 +896,7 @@ async fn worker_iteration(
     let payment_key_nonce = execution_request.payment_key_nonce;
     let usd_payment = execution_request.usd_payment.clone();
     let wallet_id = execution_request.wallet_id.clone();
+    let input_content_type = execution_request.input_content_type.clone();
 
     // Invariant: HTTPS calls must have call_id to route responses back to the user.
     // Without it, complete_https_call cannot update https_calls table → user gets 524 timeout.
@@ -728,10 +942,10 @@ async fn worker_iteration(
 
         // Convert contract's CodeSource to worker's api_client::CodeSource
         let code_source = match version_view.source {
-            near_client::ContractCodeSource::GitHub { repo, commit, build_target } => {
+            near_client::ContractCodeSource::GitHub { repo, commit, build_target, package, features } => {
                 let build_target = build_target.unwrap_or_else(|| "wasm32-wasip1".to_string());
                 info!("✅ Resolved: repo={} commit={} target={}", repo, commit, build_target);
-                api_client::CodeSource::GitHub { repo, commit, build_target }
+                api_client::CodeSource::GitHub { repo, commit, build_target, package, features }
             }
             near_client::ContractCodeSource::WasmUrl { url, hash, build_target } => {
                 let build_target = build_target.unwrap_or_else(|| "wasm32-wasip1".to_string());
@@ -768,9 +982,11 @@ async fn worker_iteration(
                                 false,
                                 None,
                                 Some(format!("Failed to resolve project: {}", e)),
+                                None,
                                 0,
                                 0,
                                 None, // No job_id yet - early error
+                                Vec::new(),
                             ).await {
                                 error!("❌ Failed to report HTTPS call error: {}", report_err);
                             }
@@ -795,7 +1011,7 @@ async fn worker_iteration(
     let project_id = execution_request.project_id.clone();
 
     info!("🎯 Claiming jobs for request_id={} data_id={} with capabilities={:?} compile_only={} force_rebuild={} has_compile_result={} project_uuid={:?}",
-          request_id, data_id, config.capabilities.to_array(), compile_only, force_rebuild, has_compile_result, project_uuid);
+          request_id, data_id, capabilities, compile_only, force_rebuild, has_compile_result, project_uuid);
     let claim_response = match api_client
         .claim_job(
             request_id,
@@ -806,7 +1022,7 @@ async fn worker_iteration(
             user_account_id.clone(),
             near_payment_yocto.clone(),
             transaction_hash.clone(),
-            config.capabilities.to_array(),
+            capabilities.clone(),
             compile_only,
             force_rebuild,
             has_compile_result,
@@ -830,18 +1046,173 @@ async fn worker_iteration(
     info!("✅ Claimed {} job(s) for request_id={}", claim_response.jobs.len(), request_id);
 
     // Extract pricing from response
-    let pricing = &claim_response.pricing;
+    let pricing = claim_response.pricing.clone();
     info!(
         "💰 Pricing: per_compile_ms={} max_compile_sec={}",
         pricing.per_compile_ms_fee, pricing.max_compilation_seconds
     );
 
-    // Local WASM cache - if we compile, we keep it in memory for execute job
-    let mut compiled_wasm: Option<(String, Vec<u8>, u64, Option<String>, Option<String>)> = None; // (checksum, bytes, compile_time_ms, created_at, published_url)
+    let request = ClaimedRequest {
+        request_id,
+        data_id,
+        code_source,
+        resource_limits,
+        input_data,
+        secrets_ref,
+        response_format,
+        context,
+        user_account_id,
+        near_payment_yocto,
+        attached_usd,
+        transaction_hash,
+        store_on_fastfs,
+        compile_only,
+        force_rebuild,
+        compile_result,
+        is_https_call,
+        call_id,
+        payment_key_owner,
+        payment_key_nonce,
+        usd_payment,
+        wallet_id,
+        input_content_type,
+        pricing,
+    };
+    let jobs = claim_response.jobs;
+
+    let has_compile_job = compiler.is_some()
+        && jobs.iter().any(|job| job.allowed && matches!(job.job_type, JobType::Compile));
+
+    if !has_compile_job {
+        // Execute-only: WASM is already built, run inline
+        let result = process_claimed_jobs(
+            &request,
+            jobs,
+            api_client,
+            compiler.map(Arc::as_ref),
+            executor,
+            near_client,
+            keystore_client,
+            tdx_client,
+            config,
+            wasm_cache,
+            compiled_cache,
+            worker_status,
+            compile_pipeline,
+        )
+        .await;
+        compile_pipeline.discard(&request.data_id);
+        result?;
+        return Ok(true);
+    }
+
+    // Compile (+ the execute job that follows it) runs on its own task so the loop can keep
+    // serving execute-only tasks. Backpressure: wait for a slot if the poll raced a compile.
+    let permit = compile_pipeline.acquire().await;
+    info!("🔨 Spawning compile pipeline for request_id={} ({} compile slot(s) free)",
+        request.request_id, compile_pipeline.available_slots());
+
+    let api_client = api_client.clone();
+    let compiler = compiler.cloned();
+    let executor = executor.clone();
+    let near_client = near_client.clone();
+    let keystore_client = keystore_client.cloned();
+    let tdx_client = tdx_client.clone();
+    let config = config.clone();
+    let wasm_cache = wasm_cache.cloned();
+    let compiled_cache = compiled_cache.cloned();
+    let worker_status = worker_status.clone();
+    let compile_pipeline = compile_pipeline.clone();
+    // Same budget the main loop gives a whole iteration
+    let timeout = tokio::time::Duration::from_secs(
+        config.poll_timeout_seconds + config.max_execution_seconds_cap + config.iteration_overhead_seconds,
+    );
+
+    tokio::spawn(async move {
+        let _permit = permit; // slot is held until compile and execute are done
+        let result = tokio::time::timeout(
+            timeout,
+            process_claimed_jobs(
+                &request,
+                jobs,
+                &api_client,
+                compiler.as_deref(),
+                &executor,
+                &near_client,
+                keystore_client.as_ref(),
+                &tdx_client,
+                &config,
+                wasm_cache.as_ref(),
+                compiled_cache.as_ref(),
+                &worker_status,
+                &compile_pipeline,
+            ),
+        )
+        .await;
+        compile_pipeline.discard(&request.data_id);
 
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => error!("❌ Compile pipeline failed for request_id={}: {}", request.request_id, e),
+            Err(_) => error!("⏰ Compile pipeline for request_id={} timed out after {}s", request.request_id, timeout.as_secs()),
+        }
+    });
+
+    Ok(true)
+}
+
+/// Everything from one claimed execution request that its jobs need
+struct ClaimedRequest {
+    request_id: u64,
+    data_id: String,
+    code_source: CodeSource,
+    resource_limits: api_client::ResourceLimits,
+    input_data: String,
+    secrets_ref: Option<api_client::SecretsReference>,
+    response_format: api_client::ResponseFormat,
+    context: api_client::ExecutionContext,
+    user_account_id: Option<String>,
+    near_payment_yocto: Option<String>,
+    attached_usd: Option<String>,
+    transaction_hash: Option<String>,
+    store_on_fastfs: bool,
+    compile_only: bool,
+    force_rebuild: bool,
+    compile_result: Option<String>,
+    is_https_call: bool,
+    call_id: Option<String>,
+    payment_key_owner: Option<String>,
+    payment_key_nonce: Option<i32>,
+    usd_payment: Option<String>,
+    wallet_id: Option<String>,
+    input_content_type: Option<String>,
+    pricing: api_client::PricingConfig,
+}
+
+/// Run the claimed jobs of one request in order (compile, then execute)
+///
+/// A compile job's WASM goes into `compile_pipeline` keyed by data_id; the execute job of
+/// the same request takes it from there instead of downloading it again.
+async fn process_claimed_jobs(
+    request: &ClaimedRequest,
+    jobs: Vec<JobInfo>,
+    api_client: &ApiClient,
+    compiler: Option<&Compiler>,
+    executor: &Executor,
+    near_client: &NearClient,
+    keystore_client: Option<&KeystoreClient>,
+    tdx_client: &tdx_attestation::TdxClient,
+    config: &Config,
+    wasm_cache: Option<&Arc<Mutex<WasmCache>>>,
+    compiled_cache: Option<&Arc<Mutex<CompiledCache>>>,
+    worker_status: &WorkerStatus,
+    compile_pipeline: &CompilePipeline,
+) -> Result<()> {
     // Process each job in order
-    for job in claim_response.jobs {
+    let mut claimed = worker_status.claim(jobs.len());
+    for job in jobs {
         info!("🔧 Processing job_id={} type={:?}", job.job_id, job.job_type);
+        claimed.start(job.job_id);
 
         if !job.allowed {
             warn!("⚠️ Job {} not allowed (already completed or failed)", job.job_id);
@@ -856,28 +1227,36 @@ async fn worker_iteration(
                     continue;
                 };
 
-                match handle_compile_job(
+                let compiled = handle_compile_job(
                     api_client,
                     compiler_ref,
                     near_client,
                     keystore_client,
                     tdx_client,
                     &job,
-                    &code_source,
-                    &context,
-                    &user_account_id,
-                    pricing,
-                    near_payment_yocto.as_ref(),
-                    request_id,
+                    &request.code_source,
+                    &request.context,
+                    &request.user_account_id,
+                    &request.pricing,
+                    request.near_payment_yocto.as_ref(),
+                    request.request_id,
                     config,
-                    store_on_fastfs,
-                    force_rebuild,
-                    compile_only,
+                    request.store_on_fastfs,
+                    request.force_rebuild,
+                    request.compile_only,
                 )
-                .await {
-                    Ok((checksum, wasm_bytes, compile_time_ms, created_at, published_url)) => {
-                        // Store in local cache for execute job (including compile time, created_at, and published_url)
-                        compiled_wasm = Some((checksum, wasm_bytes, compile_time_ms, created_at, published_url));
+                .await;
+                metrics().record_job(&job.job_type, compiled.is_ok());
+                match compiled {
+                    Ok((checksum, bytes, compile_time_ms, created_at, published_url)) => {
+                        // Hand off to the execute job (including compile time, created_at, and published_url)
+                        compile_pipeline.insert(&request.data_id, CachedWasm {
+                            checksum,
+                            bytes,
+                            compile_time_ms,
+                            created_at,
+                            published_url,
+                        });
                     }
                     Err(e) => {
                         // Compilation failed - complete_job already called in handle_compile_job
@@ -888,40 +1267,44 @@ async fn worker_iteration(
                 }
             }
             JobType::Execute => {
-                handle_execute_job(
+                let compiled_wasm = compile_pipeline.take(&request.data_id);
+                let executed = handle_execute_job(
                     api_client,
                     executor,
                     near_client,
                     keystore_client,
                     tdx_client,
                     &job,
-                    &code_source,
-                    &resource_limits,
-                    &input_data,
-                    secrets_ref.as_ref(),
-                    &response_format,
-                    &context,
-                    user_account_id.as_ref(),
-                    near_payment_yocto.as_ref(),
-                    attached_usd.as_ref(),
-                    transaction_hash.as_ref(),
-                    request_id,
-                    &data_id,
-                    compiled_wasm.as_ref().map(|(cs, b, ct, ca, pu)| (cs, b, ct, ca.as_deref(), pu.as_deref())), // Pass local WASM cache with published_url
-                    compile_result.as_ref(), // Pass compile_result (published_url or result for compile_only)
-                    compile_only,
+                    &request.code_source,
+                    &request.resource_limits,
+                    &request.input_data,
+                    request.secrets_ref.as_ref(),
+                    &request.response_format,
+                    &request.context,
+                    request.user_account_id.as_ref(),
+                    request.near_payment_yocto.as_ref(),
+                    request.attached_usd.as_ref(),
+                    request.transaction_hash.as_ref(),
+                    request.request_id,
+                    &request.data_id,
+                    compiled_wasm.as_ref().map(|w| (&w.checksum, &w.bytes, &w.compile_time_ms, w.created_at.as_deref(), w.published_url.as_deref())), // Pass local WASM cache with published_url
+                    request.compile_result.as_ref(), // Pass compile_result (published_url or result for compile_only)
+                    request.compile_only,
                     config.use_tee_registration,
                     config,
-                    is_https_call,
-                    call_id.as_ref(),
-                    payment_key_owner.as_ref(),
-                    payment_key_nonce,
-                    usd_payment.as_ref(),
-                    wallet_id.as_ref(),
+                    request.is_https_call,
+                    request.call_id.as_ref(),
+                    request.payment_key_owner.as_ref(),
+                    request.payment_key_nonce,
+                    request.usd_payment.as_ref(),
+                    request.wallet_id.as_ref(),
+                    request.input_content_type.as_ref(),
                     wasm_cache,
                     compiled_cache,
                 )
-                .await?;
+                .await;
+                metrics().record_job(&job.job_type, executed.is_ok());
+                executed?;
             }
         }
     }
@@ -929,7 +1312,7 @@ async fn worker_iteration(
     // Note: WASM upload now happens inside handle_compile_job BEFORE complete_job
     // This ensures WASM exists on coordinator when execute task is created
 
-    Ok(true)
+    Ok(())
 }
 
 /// Merge user secrets with system environment variables
@@ -1061,6 +1444,7 @@ fn merge_env_vars(
     env_vars.insert("NEAR_MAX_INSTRUCTIONS".to_string(), resource_limits.max_instructions.to_string());
     env_vars.insert("NEAR_MAX_MEMORY_MB".to_string(), resource_limits.max_memory_mb.to_string());
     env_vars.insert("NEAR_MAX_EXECUTION_SECONDS".to_string(), resource_limits.max_execution_seconds.to_string());
+    env_vars.insert("NEAR_MAX_OUTPUT_BYTES".to_string(), resource_limits.max_output_bytes.to_string());
 
     // Add project context (same for both modes)
     if let Some(proj_id) = project_id {
@@ -1084,35 +1468,90 @@ fn merge_env_vars(
 ///
 /// For P1: uses WasmCache (raw bytes LRU cache)
 /// For P2: downloads directly (CompiledCache handles caching in executor)
+///
+/// Downloads are streamed; artifacts larger than `spool_threshold` are spooled to a temp
+/// file in `spool_dir` and memory-mapped instead of being buffered on the heap.
 async fn fetch_wasm_bytes(
     api_client: &ApiClient,
     wasm_checksum: &str,
     wasm_cache: Option<&Arc<Mutex<WasmCache>>>,
     is_p2: bool,
     created_at: &Option<String>,
-) -> Result<Vec<u8>> {
+    spool_threshold: usize,
+    spool_dir: &std::path::Path,
+) -> Result<WasmBytes> {
     // P1: try WasmCache first (raw bytes cache)
     if !is_p2 {
         if let Some(cache) = wasm_cache {
             if let Some(cached_bytes) = cache.lock().ok().and_then(|mut c| c.get(wasm_checksum)) {
                 info!("✅ WASM LRU cache hit (P1): {} ({}KB)", wasm_checksum, cached_bytes.len() / 1024);
-                return Ok(cached_bytes);
+                return Ok(WasmBytes::Memory(cached_bytes));
             }
         }
     }
 
     // Download from coordinator
     info!("📥 Downloading WASM: checksum={} (cached since: {:?}) is_p2={}", wasm_checksum, created_at, is_p2);
-    let bytes = api_client.download_wasm(wasm_checksum).await
+    let stream = api_client.download_wasm_streaming(wasm_checksum);
+    let bytes = wasm_spool::spool_wasm_stream(stream, spool_threshold, spool_dir).await
         .map_err(|e| {
             error!("❌ Failed to download WASM: {}", e);
             anyhow::anyhow!("Failed to download WASM: {}", e)
         })?;
 
-    info!("✅ Downloaded WASM: {} bytes", bytes.len());
+    match bytes {
+        WasmBytes::Memory(_) => info!("✅ Downloaded WASM: {} bytes", bytes.len()),
+        WasmBytes::Mapped(_) => info!("✅ Downloaded WASM: {} bytes (spooled to disk, mapped)", bytes.len()),
+    }
     Ok(bytes)
 }
 
+/// AOT-compile `wasm_bytes` for the executor engine of `build_target` and publish it to FastFS
+///
+/// Executors fall back to compiling the WASM when the artifact is missing,
+/// so failures are logged rather than failing the compile job.
+async fn publish_precompiled(config: &Config, checksum: &str, build_target: &str, wasm_bytes: &[u8]) {
+    let (Some(receiver), Some(signer)) = (&config.fastfs_receiver, &config.fastfs_sender_signer) else {
+        return;
+    };
+    let Some((engine, is_component)) = executor::precompiled::engine_for_target(build_target) else {
+        info!("⏭️ No AOT executor engine for build target {}, skipping precompilation", build_target);
+        return;
+    };
+
+    let start_time = std::time::Instant::now();
+    let secret_key = signer.secret_key.clone();
+    let wasm = wasm_bytes.to_vec();
+    let wasm_checksum = checksum.to_string();
+    let artifact = match tokio::task::spawn_blocking(move || {
+        executor::precompiled::precompile(engine, &wasm, is_component, &wasm_checksum, &secret_key)
+    })
+    .await
+    {
+        Ok(Ok(artifact)) => artifact,
+        Ok(Err(e)) => {
+            warn!("⚠️ AOT compilation failed for {}: {}", checksum, e);
+            return;
+        }
+        Err(e) => {
+            warn!("⚠️ AOT compilation task failed for {}: {}", checksum, e);
+            return;
+        }
+    };
+
+    let name = executor::precompiled::artifact_name(checksum, engine);
+    info!("⚡ AOT-compiled {} in {}ms ({} bytes)", name, start_time.elapsed().as_millis(), artifact.len());
+
+    let fastfs_client = fastfs::FastFsClient::new(&config.near_rpc_url, signer.clone(), receiver);
+    if let Err(e) = fastfs_client
+        .upload_file(&name, executor::precompiled::ARTIFACT_MIME_TYPE, &artifact)
+        .await
+    {
+        // FastFS transaction "fails" but the indexer picks up the file (same as .wasm uploads)
+        debug!("FastFS artifact upload status: {}", e);
+    }
+}
+
 /// Handle a compile job
 /// Returns (checksum, wasm_bytes, compile_time_ms) for local caching
 async fn handle_compile_job(
@@ -1153,6 +1592,10 @@ async fn handle_compile_job(
                 info!("✅ WASM downloaded: checksum={} size={} bytes time={}ms cached={:?}",
                     checksum, wasm_bytes.len(), download_time_ms, created_at.is_some());
 
+                if config.aot_precompile {
+                    publish_precompiled(config, &checksum, build_target, &wasm_bytes).await;
+                }
+
                 // Report completion to coordinator
                 // Note: For downloads, we report 0 compile_cost (no compilation happened)
                 if let Err(e) = api_client
@@ -1207,7 +1650,7 @@ async fn handle_compile_job(
 
     // Extract GitHub fields - compile jobs for GitHub source
     let (repo, commit, build_target) = match code_source {
-        CodeSource::GitHub { repo, commit, build_target } => {
+        CodeSource::GitHub { repo, commit, build_target, .. } => {
             // If commit is empty, use "main" as default
             let commit_str = if commit.is_empty() {
                 info!("⚠️ Commit is empty, using 'main' as default branch");
@@ -1283,6 +1726,7 @@ async fn handle_compile_job(
     // Compile the code with timeout (returns checksum and bytes, does NOT upload yet)
     let compile_result = compiler.compile_local_with_options(code_source, timeout_seconds, force_rebuild).await;
     let compile_time_ms = start_time.elapsed().as_millis() as u64;
+    metrics().observe_compile(start_time.elapsed());
 
     match compile_result {
         Ok((checksum, wasm_bytes, created_at)) => {
@@ -1325,6 +1769,11 @@ async fn handle_compile_job(
                 info!("✅ WASM uploaded successfully");
             }
 
+            // Publish AOT-compiled native code so executors skip JIT compilation
+            if config.aot_precompile {
+                publish_precompiled(config, &checksum, build_target, &wasm_bytes).await;
+            }
+
             // Upload to FastFS if requested (before complete_job to include result)
             let mut compile_result_for_executor: Option<String> = None;
             let mut published_url: Option<String> = None;
@@ -1546,6 +1995,7 @@ async fn handle_execute_job(
     payment_key_nonce: Option<i32>, // Payment Key nonce for HTTPS calls
     usd_payment: Option<&String>, // USD payment amount for HTTPS calls
     wallet_id: Option<&String>, // Wallet ID for wallet-enabled WASM executions
+    input_content_type: Option<&String>, // Media type of input_data declared by the caller
     wasm_cache: Option<&Arc<Mutex<WasmCache>>>, // Local WASM LRU cache (P1 only)
     compiled_cache: Option<&Arc<Mutex<CompiledCache>>>, // Compiled component cache (P2 only)
 ) -> Result<()> {
@@ -1578,11 +2028,16 @@ async fn handle_execute_job(
             success: true,
             output: Some(api_client::ExecutionOutput::Text(result_to_send.clone())),
             error: None,
+            error_structured: None,
             execution_time_ms: 0, // No execution
             instructions: 0, // No execution
+            peak_memory_bytes: 0,
             compile_time_ms: None, // Already counted in compile job
             compilation_note: Some(compilation_note),
             refund_usd: None,
+            logs: Vec::new(),
+            timed_out: false,
+            declared_output_format: None,
         };
 
         if is_https_call {
@@ -1595,9 +2050,11 @@ async fn handle_execute_job(
                 true,
                 output_json,
                 None,
+                None,
                 0, // No instructions
                 0, // No execution time
                 Some(job.job_id),
+                Vec::new(),
             ).await {
                 Ok(()) => {
                     info!("✅ Compile result submitted to coordinator successfully");
@@ -1646,7 +2103,7 @@ async fn handle_execute_job(
             }
         } else {
             // Blockchain calls: submit to NEAR contract
-            match near_client.submit_execution_result(request_id, &result).await {
+            match near_client.submit_execution_result(request_id, input_data, &result).await {
                 Ok((tx_hash, outcome)) => {
                     info!("✅ Compile result submitted to NEAR successfully: tx_hash={}", tx_hash);
 
@@ -1721,9 +2178,11 @@ async fn handle_execute_job(
                     false,
                     None,
                     Some(compile_error.clone()),
+                    None,
                     0,
                     0,
                     Some(job.job_id),
+                    Vec::new(),
                 ).await {
                     error!("❌ Failed to report HTTPS call compile error: {}", https_err);
                 }
@@ -1756,15 +2215,20 @@ async fn handle_execute_job(
                 success: false,
                 output: None,
                 error: Some(compile_error.clone()),
+                error_structured: None,
                 execution_time_ms: 0,
                 instructions: 0,
+                peak_memory_bytes: 0,
                 compile_time_ms: None,
                 compilation_note: Some("Compilation failed".to_string()),
                 refund_usd: None,
+                logs: Vec::new(),
+                timed_out: false,
+                declared_output_format: None,
             };
 
             let near_result = near_client
-                .submit_execution_result(request_id, &error_result)
+                .submit_execution_result(request_id, input_data, &error_result)
                 .await;
 
             match near_result {
@@ -1853,6 +2317,9 @@ async fn handle_execute_job(
         false
     };
 
+    let spool_threshold = (config.wasm_spool_threshold_mb as usize).saturating_mul(1024 * 1024);
+    let spool_dir = std::path::Path::new(&config.wasm_cache_dir).join("spool");
+
     // Get WASM bytes, compile time, creation timestamp, published URL
     // P2 + valid compiled cache: skip download (executor loads from cache)
     // P2 + invalid/no cache: download from coordinator
@@ -1861,11 +2328,11 @@ async fn handle_execute_job(
         // Local compile cache from same execution (freshly compiled)
         if cached_checksum == wasm_checksum {
             info!("✅ Using locally compiled WASM: {} bytes (freshly compiled!) compiled in {}ms", cached_bytes.len(), cached_compile_time);
-            (cached_bytes.clone(), Some(*cached_compile_time), cached_created_at.map(|s| s.to_string()), cached_published_url.map(|s| s.to_string()))
+            (WasmBytes::Memory(cached_bytes.clone()), Some(*cached_compile_time), cached_created_at.map(|s| s.to_string()), cached_published_url.map(|s| s.to_string()))
         } else {
             warn!("⚠️ Checksum mismatch - need to fetch WASM");
             let created_at = api_client.wasm_exists(wasm_checksum).await.ok().and_then(|(_, ca)| ca);
-            match fetch_wasm_bytes(api_client, wasm_checksum, wasm_cache, is_p2, &created_at).await {
+            match fetch_wasm_bytes(api_client, wasm_checksum, wasm_cache, is_p2, &created_at, spool_threshold, &spool_dir).await {
                 Ok(bytes) => (bytes, job.compile_time_ms, created_at, None),
                 Err(e) => {
                     let error_msg = format!("Failed to download WASM: {}", e);
@@ -1879,11 +2346,11 @@ async fn handle_execute_job(
         // P2 compiled cache is valid - skip download
         info!("⚡ CompiledCache valid for {} - skipping WASM download", wasm_checksum);
         let created_at = api_client.wasm_exists(wasm_checksum).await.ok().and_then(|(_, ca)| ca);
-        (Vec::new(), job.compile_time_ms, created_at, None)
+        (WasmBytes::Memory(Vec::new()), job.compile_time_ms, created_at, None)
     } else {
         // Need to fetch WASM bytes (P1, or P2 with no/invalid cache)
         let created_at = api_client.wasm_exists(wasm_checksum).await.ok().and_then(|(_, ca)| ca);
-        match fetch_wasm_bytes(api_client, wasm_checksum, wasm_cache, is_p2, &created_at).await {
+        match fetch_wasm_bytes(api_client, wasm_checksum, wasm_cache, is_p2, &created_at, spool_threshold, &spool_dir).await {
             Ok(bytes) => (bytes, job.compile_time_ms, created_at, None),
             Err(e) => {
                 let error_msg = format!("Failed to download WASM: {}", e);
@@ -1918,7 +2385,7 @@ async fn handle_execute_job(
         let secrets_result = if let Some(ref proj_id) = job.project_id {
             // Project-based execution: use project-scoped secrets
             info!("📦 Decrypting project-based secrets for project: {}", proj_id);
-            keystore.decrypt_secrets_by_project(proj_id, &secrets_ref.profile, &secrets_ref.account_id, caller, Some(data_id)).await
+            keystore.decrypt_secrets_by_project(proj_id, &secrets_ref.profile, secrets_ref.version, &secrets_ref.account_id, caller, Some(data_id)).await
         } else {
             // Non-project execution: use code_source type for secrets
             match code_source {
@@ -1942,13 +2409,13 @@ async fn handle_execute_job(
                     };
 
                     // Call keystore to decrypt secrets by repo
-                    keystore.decrypt_secrets_from_contract(repo, branch.as_deref(), &secrets_ref.profile, &secrets_ref.account_id, caller, Some(data_id)).await
+                    keystore.decrypt_secrets_from_contract(repo, branch.as_deref(), &secrets_ref.profile, secrets_ref.version, &secrets_ref.account_id, caller, Some(data_id)).await
                 }
                 CodeSource::WasmUrl { hash, .. } => {
                     info!("📦 Decrypting wasm_hash-based secrets for WasmUrl source: {}", hash);
 
                     // Call keystore to decrypt secrets by wasm_hash
-                    keystore.decrypt_secrets_by_wasm_hash(hash, &secrets_ref.profile, &secrets_ref.account_id, caller, Some(data_id)).await
+                    keystore.decrypt_secrets_by_wasm_hash(hash, &secrets_ref.profile, secrets_ref.version, &secrets_ref.account_id, caller, Some(data_id)).await
                 }
             }
         };
@@ -1984,15 +2451,20 @@ async fn handle_execute_job(
                     success: false,
                     output: None,
                     error: Some(error_msg.clone()),
+                    error_structured: None,
                     execution_time_ms: 0,
                     instructions: 0,
+                    peak_memory_bytes: 0,
                     compile_time_ms: None,
                     compilation_note: None,
                     refund_usd: None,
+                    logs: Vec::new(),
+                    timed_out: false,
+                    declared_output_format: None,
                 };
 
                 // Extract actual cost from contract logs (base_fee on failure)
-                let actual_cost = match near_client.submit_execution_result(request_id, &error_result).await {
+                let actual_cost = match near_client.submit_execution_result(request_id, input_data, &error_result).await {
                     Ok((tx_hash, outcome)) => {
                         info!("✅ Failure reported to NEAR contract (contract panicked as expected): tx_hash={}", tx_hash);
                         let cost = NearClient::extract_payment_from_logs(&outcome);
@@ -2058,6 +2530,11 @@ async fn handle_execute_job(
         env_vars.insert("WALLET_ID".to_string(), wid.to_string());
     }
 
+    // Declared input media type, read by the SDK's env::input_content_type()
+    if let Some(content_type) = input_content_type {
+        env_vars.insert("OUTLAYER_INPUT_CONTENT_TYPE".to_string(), content_type.clone());
+    }
+
     // Get build target from code source
     let build_target = match code_source {
         CodeSource::GitHub { build_target, .. } => Some(build_target.as_str()),
@@ -2089,6 +2566,8 @@ async fn handle_execute_job(
                 tee_mode: config.tee_mode.clone(),
                 keystore_tee_session_id: keystore_client
                     .and_then(|kc| kc.get_tee_session_id()),
+                transactions_enabled: config.storage_transactions_enabled,
+                ttl_enabled: config.storage_ttl_enabled,
             })
         }
         (None, _, _) | (_, None, _) => {
@@ -2122,6 +2601,19 @@ async fn handle_execute_job(
         _ => None,
     };
 
+    // Create app key config if keystore is configured AND running in a project
+    // (the keystore derives the key for the project, standalone WASM has none)
+    let ckd_config = match (&config.keystore_base_url, &config.keystore_auth_token, &job.project_id) {
+        (Some(keystore_url), Some(keystore_token), Some(project_id)) => Some(executor::CkdConfig {
+            keystore_url: keystore_url.clone(),
+            keystore_auth_token: keystore_token.clone(),
+            tee_session_id: keystore_client
+                .and_then(|kc| kc.get_tee_session_id()),
+            project_id: project_id.clone(),
+        }),
+        _ => None,
+    };
+
     // Create wallet config if wallet_id is present in execution request
     let wallet_config = wallet_id.map(|wid| {
         debug!("Wallet enabled for execution: wallet_id={}", wid);
@@ -2134,6 +2626,7 @@ async fn handle_execute_job(
 
     // Execute WASM
     info!("🚀 Executing WASM...");
+    let exec_started = std::time::Instant::now();
     let exec_result = executor
         .execute(
             &wasm_bytes,
@@ -2145,9 +2638,11 @@ async fn handle_execute_job(
             response_format,
             storage_config,
             vrf_config,
+            ckd_config,
             wallet_config,
         )
         .await;
+    metrics().observe_execute(exec_started.elapsed());
 
     // Cache raw WASM after execution - only for P1 (P2 uses CompiledCache for native code)
     // This is a security measure: WASI P2 has access to /tmp, so we cache only after WASI exits
@@ -2205,9 +2700,11 @@ async fn handle_execute_job(
                             false,
                             None,
                             Some(error_msg.clone()),
+                            execution_result.error_structured.clone(),
                             execution_result.instructions,
                             execution_result.execution_time_ms,
                             Some(job.job_id),
+                            execution_result.logs.clone(),
                         ).await {
                             error!("❌ Failed to report HTTPS call error: {}", https_err);
                         }
@@ -2224,7 +2721,7 @@ async fn handle_execute_job(
                                 None,
                                 None,
                                 if compile_cost > 0 { Some(compile_cost.to_string()) } else { None },
-                                Some(JobStatus::ExecutionFailed),
+                                Some(execution_result.failure_status(config.report_timeout_status)),
                                 None,
                             )
                             .await
@@ -2264,25 +2761,18 @@ async fn handle_execute_job(
                 info!("📤 HTTPS call: submitting result to coordinator (call_id={})", call_id_str);
 
                 // Convert ExecutionOutput to serde_json::Value
-                let output_json = execution_result.output.as_ref().map(|out| match out {
-                    api_client::ExecutionOutput::Bytes(bytes) => {
-                        use base64::{engine::general_purpose::STANDARD, Engine};
-                        serde_json::Value::String(STANDARD.encode(bytes))
-                    }
-                    api_client::ExecutionOutput::Text(text) => {
-                        serde_json::Value::String(text.clone())
-                    }
-                    api_client::ExecutionOutput::Json(json) => json.clone(),
-                });
+                let output_json = execution_result.output.as_ref().map(|out| out.to_contract_json());
 
                 match api_client.complete_https_call(
                     call_id_str,
                     true,
                     output_json.clone(),
                     None,
+                    None,
                     execution_result.instructions,
                     execution_result.execution_time_ms,
                     Some(job.job_id),
+                    execution_result.logs.clone(),
                 ).await {
                     Ok(()) => {
                         info!("✅ HTTPS call result submitted to coordinator successfully");
@@ -2422,7 +2912,7 @@ async fn handle_execute_job(
 
             // Submit result to NEAR contract (critical path - highest priority)
             info!("📤 Submitting result to NEAR contract...");
-            let near_result = near_client.submit_execution_result(request_id, &execution_result).await;
+            let near_result = near_client.submit_execution_result(request_id, input_data, &execution_result).await;
 
             // Report to coordinator (can wait, non-critical)
             match near_result {
@@ -2454,7 +2944,7 @@ async fn handle_execute_job(
                             None,
                             if actual_cost > 0 { Some(actual_cost.to_string()) } else { None },
                             if compile_cost > 0 { Some(compile_cost.to_string()) } else { None },
-                            None, // No error category for success
+                            (!execution_result.success).then(|| execution_result.failure_status(config.report_timeout_status)),
                             None, // No compile_result
                         )
                         .await
@@ -2466,45 +2956,13 @@ async fn handle_execute_job(
                     // Generate and store TDX attestation
                     {
                         // Calculate output hash to match what the contract returns to the user
-                        // The contract converts ExecutionOutput to serde_json::Value and returns it
-                        use sha2::{Digest, Sha256};
-                        let output_hash = if let Some(ref output) = execution_result.output {
-                            let mut hasher = Sha256::new();
-
-                            // Hash the JSON value that the contract returns (see contract/src/execution.rs:308-322)
-                            let json_value = match output {
-                                api_client::ExecutionOutput::Bytes(bytes) => {
-                                    // Contract returns base64-encoded string for bytes
-                                    use base64::{engine::general_purpose::STANDARD, Engine};
-                                    serde_json::Value::String(STANDARD.encode(bytes))
-                                },
-                                api_client::ExecutionOutput::Text(text) => {
-                                    // Contract returns text as JSON string
-                                    serde_json::Value::String(text.clone())
-                                },
-                                api_client::ExecutionOutput::Json(json) => {
-                                    // Contract returns JSON value directly
-                                    json.clone()
-                                },
-                            };
-
-                            // Serialize the JSON value to string (this is what gets returned from contract)
-                            let json_string = serde_json::to_string(&json_value)
-                                .unwrap_or_else(|_| "null".to_string());
-                            hasher.update(json_string.as_bytes());
-
-                            hex::encode(hasher.finalize())
-                        } else {
-                            // No output — hash known sentinel so verifiers can match
-                            hex::encode(Sha256::digest("[EXECUTION-FAILED]"))
-                        };
+                        // (same hash the result signature covers, see result_signing)
+                        let output_hash = hex::encode(result_signing::output_hash(execution_result.output.as_ref()));
 
                         // Generate and store TDX attestation only if TEE registration is enabled
                         if use_tee_registration {
                             // Calculate input hash
-                            let mut input_hasher = Sha256::new();
-                            input_hasher.update(input_data.as_bytes());
-                            let input_hash = hex::encode(input_hasher.finalize());
+                            let input_hash = hex::encode(result_signing::input_hash(input_data));
 
                             // Format secrets_ref for attestation (None if empty fields)
                             let secrets_ref_str = secrets_ref.and_then(|sr| sr.as_attestation_ref());
@@ -2612,9 +3070,11 @@ async fn handle_execute_job(
                         false,
                         None,
                         Some(error_msg.clone()),
+                        None,
                         0,
                         0,
                         Some(job.job_id),
+                        Vec::new(),
                     ).await {
                         error!("❌ Failed to report HTTPS call error: {}", https_err);
                     }
@@ -2647,15 +3107,20 @@ async fn handle_execute_job(
                 success: false,
                 output: None,
                 error: Some(error_msg.clone()),
+                error_structured: None,
                 execution_time_ms: 0,
                 instructions: 0,
+                peak_memory_bytes: 0,
                 compile_time_ms,
                 compilation_note: None,
                 refund_usd: None,
+                logs: Vec::new(),
+                timed_out: false,
+                declared_output_format: None,
             };
 
             // Submit error to NEAR contract (critical path) and extract actual cost
-            let actual_cost = match near_client.submit_execution_result(request_id, &result).await {
+            let actual_cost = match near_client.submit_execution_result(request_id, input_data, &result).await {
                 Ok((tx_hash, outcome)) => {
                     info!("✅ Failure reported to NEAR contract (contract panicked as expected): tx_hash={}", tx_hash);
                     let cost = NearClient::extract_payment_from_logs(&outcome);
@@ -2683,7 +3148,7 @@ async fn handle_execute_job(
                     None,
                     if actual_cost > 0 { Some(actual_cost.to_string()) } else { None },
                     None,
-                    Some(api_client::JobStatus::ExecutionFailed), // WASM execution error (panic, trap, timeout)
+                    Some(api_client::JobStatus::ExecutionFailed), // WASM execution error (panic, trap)
                     None, // No compile_result
                 )
                 .await
@@ -2770,6 +3235,125 @@ async fn send_startup_attestation_with_quote(
 ///
 /// Polls multiple task queues and processes contract system operations that require
 /// yield/resume mechanism. These are business logic operations, not WASM execution.
+/// Keep the TDX collateral on the register contract from expiring unnoticed
+///
+/// Intel collateral carries a `nextUpdate` date; once the register contract's copy for this
+/// platform is past it, TEE registrations fail. `update_collateral` is owner-only, so the
+/// worker can't refresh it itself: every `interval` it compares the on-chain copy with
+/// collateral from Phala (cached until stale) and logs the update command for the operator
+/// when it is missing or expires within `warn_before`. Failures back off and retry; they
+/// never stop the worker.
+async fn run_collateral_refresh(
+    tdx_client: Arc<TdxClient>,
+    near_client: NearClient,
+    register_contract_id: near_primitives::types::AccountId,
+    interval: std::time::Duration,
+    warn_before: std::time::Duration,
+) {
+    let mut cached: Option<CachedCollateral> = None;
+    let mut failures = 0u32;
+    loop {
+        let delay = match check_collateral(&tdx_client, &near_client, &register_contract_id, &mut cached, warn_before)
+            .await
+        {
+            Ok(()) => {
+                failures = 0;
+                interval
+            }
+            Err(e) => {
+                failures = failures.saturating_add(1);
+                let delay = collateral_fetcher::refresh_backoff(failures, interval);
+                warn!(
+                    "⚠️  Collateral check failed ({} in a row), retrying in {}s: {:#}",
+                    failures,
+                    delay.as_secs(),
+                    e
+                );
+                delay
+            }
+        };
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// One collateral check: refresh the cached Phala collateral if stale, compare the
+/// register contract's copy for this platform and tell the operator when it needs updating
+async fn check_collateral(
+    tdx_client: &TdxClient,
+    near_client: &NearClient,
+    register_contract_id: &near_primitives::types::AccountId,
+    cached: &mut Option<CachedCollateral>,
+    warn_before: std::time::Duration,
+) -> Result<()> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    let fresh: &CachedCollateral = match cached {
+        Some(collateral) if !collateral.is_stale(now) => collateral,
+        slot => {
+            let json = generate_dummy_quote_and_fetch_collateral(tdx_client).await?;
+            let fresh = CachedCollateral::parse(json, now).context("Phala returned unusable collateral")?;
+            info!("🔏 Collateral fetched for FMSPC {} (nextUpdate {})", fresh.fmspc, fresh.next_update);
+            slot.insert(fresh)
+        }
+    };
+
+    // Slots that don't parse belong to no platform we can match, skip them
+    let on_chain = near_client.fetch_collaterals(register_contract_id).await?;
+    let slot = on_chain.iter().enumerate().find_map(|(index, json)| {
+        CollateralInfo::parse(json)
+            .ok()
+            .filter(|info| info.fmspc == fresh.fmspc)
+            .map(|info| (index, info.next_update))
+    });
+    let on_chain_next_update = slot.map(|(_, next_update)| next_update);
+    metrics().set_collateral(fresh.fetched_at, on_chain_next_update);
+
+    let index = slot.map_or(on_chain.len(), |(index, _)| index);
+    match collateral_fetcher::check_on_chain_collateral(on_chain_next_update, now, warn_before.as_secs()) {
+        CollateralCheck::UpToDate { expires_in } => {
+            debug!("Collateral for FMSPC {} valid for another {}h", fresh.fmspc, expires_in / 3600);
+            return Ok(());
+        }
+        CollateralCheck::Missing => {
+            warn!(
+                "⚠️  No collateral for FMSPC {} on {}: TEE registrations from this platform will fail",
+                fresh.fmspc, register_contract_id
+            );
+        }
+        CollateralCheck::Expiring { expires_in } if expires_in <= 0 => {
+            warn!(
+                "⚠️  Collateral for FMSPC {} on {} expired {}h ago",
+                fresh.fmspc, register_contract_id, -expires_in / 3600
+            );
+        }
+        CollateralCheck::Expiring { expires_in } => {
+            warn!(
+                "⚠️  Collateral for FMSPC {} on {} expires in {}h",
+                fresh.fmspc, register_contract_id, expires_in / 3600
+            );
+        }
+    }
+
+    if fresh.next_update <= on_chain_next_update.unwrap_or(0) {
+        warn!("   Phala has no newer collateral yet, will check again");
+        return Ok(());
+    }
+    warn!("   Update it (owner only) with the collateral fetched from Phala:");
+    warn!("   COLLATERAL=$(cat <<'EOF'");
+    warn!("{}", fresh.json);
+    warn!("EOF");
+    warn!("   )");
+    warn!(
+        "   near call {} update_collateral \"$(jq -n --arg c \"$COLLATERAL\" '{{collateral: $c, index: {}}}')\" \\",
+        register_contract_id, index
+    );
+    warn!("     --accountId <owner> --gas 300000000000000");
+    Ok(())
+}
+
 async fn run_contract_system_callbacks_handler(
     api_client: ApiClient,
     keystore_client: Option<KeystoreClient>,
@@ -0,0 +1,380 @@
+//! Prometheus metrics
+//!
+//! Job, NEAR submission and RPC proxy counters for operators to alert on: compile
+//! failure rate, execution latency, RPC error rate, workers starved of jobs. They are
+//! kept process-wide (the RPC proxy is created per execution) and served in the
+//! Prometheus text format at `/metrics` when `METRICS_PORT` is set.
+//!
+//! | Metric | Type | Labels |
+//! |---|---|---|
+//! | `jobs_processed_total` | counter | `type` (compile, execute), `status` (success, failure) |
+//! | `compile_duration_seconds` | histogram | |
+//! | `execute_duration_seconds` | histogram | |
+//! | `near_submit_failures_total` | counter | |
+//! | `rpc_requests_total` | counter | `method`, `status` (ok, error, cached) |
+//! | `poll_idle_total` | counter | |
+//! | `tdx_collateral_age_seconds` | gauge | |
+//! | `tdx_collateral_expires_in_seconds` | gauge | |
+//!
+//! The collateral gauges are only reported once the refresh task has run: age of the
+//! collateral last fetched from Phala, and time left before the register contract's copy
+//! for this platform expires (negative once it has).
+//!
+//! An execute job that reports a failing WASM to the contract is still a `success`: the
+//! job did its work. `failure` means the handler itself gave up.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, info, warn};
+
+use crate::api_client::JobType;
+
+/// Histogram bucket bounds in seconds, covering cached hits up to the 600s execution cap
+const DURATION_BUCKETS: [f64; 11] = [0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0];
+
+/// RPC methods reported by name; anything else a guest sends through `near_rpc_raw` is
+/// counted as `other` so the label set stays bounded
+const KNOWN_RPC_METHODS: &[&str] = &[
+    "query",
+    "block",
+    "chunk",
+    "gas_price",
+    "status",
+    "network_info",
+    "validators",
+    "tx",
+    "send_tx",
+    "broadcast_tx_async",
+    "broadcast_tx_commit",
+    "EXPERIMENTAL_changes",
+    "EXPERIMENTAL_changes_in_block",
+    "EXPERIMENTAL_genesis_config",
+    "EXPERIMENTAL_protocol_config",
+    "EXPERIMENTAL_receipt",
+    "EXPERIMENTAL_tx_status",
+];
+
+/// Outcome of a proxied RPC call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RpcStatus {
+    /// Answered by RPC
+    Ok,
+    /// Transport failure, non-2xx status or JSON-RPC error
+    Error,
+    /// Answered from the worker's RPC cache
+    Cached,
+}
+
+impl RpcStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            RpcStatus::Ok => "ok",
+            RpcStatus::Error => "error",
+            RpcStatus::Cached => "cached",
+        }
+    }
+}
+
+struct Histogram {
+    /// Observations per bucket, the last one past the largest bound
+    buckets: [u64; DURATION_BUCKETS.len() + 1],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    const fn new() -> Self {
+        Self {
+            buckets: [0; DURATION_BUCKETS.len() + 1],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        let bucket = DURATION_BUCKETS
+            .iter()
+            .position(|&bound| seconds <= bound)
+            .unwrap_or(DURATION_BUCKETS.len());
+        self.buckets[bucket] += 1;
+        self.sum += seconds;
+        self.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        let mut cumulative = 0;
+        for (bound, count) in DURATION_BUCKETS.iter().zip(&self.buckets) {
+            cumulative += count;
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, cumulative);
+        }
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, self.count);
+        let _ = writeln!(out, "{}_sum {}", name, self.sum);
+        let _ = writeln!(out, "{}_count {}", name, self.count);
+    }
+}
+
+/// Worker metrics registry
+pub struct Metrics {
+    /// (type, status) -> count
+    jobs_processed: Mutex<BTreeMap<(&'static str, &'static str), u64>>,
+    compile_duration: Mutex<Histogram>,
+    execute_duration: Mutex<Histogram>,
+    near_submit_failures: AtomicU64,
+    /// (method, status) -> count
+    rpc_requests: Mutex<BTreeMap<(&'static str, RpcStatus), u64>>,
+    poll_idle: AtomicU64,
+    /// Unix seconds, 0 = not fetched yet
+    collateral_fetched_at: AtomicU64,
+    /// Unix seconds of the on-chain collateral's `nextUpdate`, 0 = unknown
+    collateral_next_update: AtomicU64,
+}
+
+static METRICS: Metrics = Metrics::new();
+
+/// Process-wide registry
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+impl Metrics {
+    const fn new() -> Self {
+        Self {
+            jobs_processed: Mutex::new(BTreeMap::new()),
+            compile_duration: Mutex::new(Histogram::new()),
+            execute_duration: Mutex::new(Histogram::new()),
+            near_submit_failures: AtomicU64::new(0),
+            rpc_requests: Mutex::new(BTreeMap::new()),
+            poll_idle: AtomicU64::new(0),
+            collateral_fetched_at: AtomicU64::new(0),
+            collateral_next_update: AtomicU64::new(0),
+        }
+    }
+
+    /// Count a finished job
+    pub fn record_job(&self, job_type: &JobType, success: bool) {
+        let job_type = match job_type {
+            JobType::Compile => "compile",
+            JobType::Execute => "execute",
+        };
+        let status = if success { "success" } else { "failure" };
+        *self.jobs_processed.lock().unwrap().entry((job_type, status)).or_default() += 1;
+    }
+
+    pub fn observe_compile(&self, duration: Duration) {
+        self.compile_duration.lock().unwrap().observe(duration);
+    }
+
+    pub fn observe_execute(&self, duration: Duration) {
+        self.execute_duration.lock().unwrap().observe(duration);
+    }
+
+    /// Count a result that could not be submitted to the contract
+    pub fn record_near_submit_failure(&self) {
+        self.near_submit_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a proxied RPC call
+    pub fn record_rpc_request(&self, method: &str, status: RpcStatus) {
+        let method = KNOWN_RPC_METHODS.iter().find(|&&m| m == method).copied().unwrap_or("other");
+        *self.rpc_requests.lock().unwrap().entry((method, status)).or_default() += 1;
+    }
+
+    /// Count a long-poll that returned without a job
+    pub fn record_poll_idle(&self) {
+        self.poll_idle.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record the collateral state seen by the refresh task
+    pub fn set_collateral(&self, fetched_at: u64, on_chain_next_update: Option<u64>) {
+        self.collateral_fetched_at.store(fetched_at, Ordering::Relaxed);
+        self.collateral_next_update
+            .store(on_chain_next_update.unwrap_or(0), Ordering::Relaxed);
+    }
+
+    /// Prometheus text exposition of all metrics
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        out.push_str("# HELP jobs_processed_total Jobs finished by the worker\n");
+        out.push_str("# TYPE jobs_processed_total counter\n");
+        for ((job_type, status), count) in self.jobs_processed.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "jobs_processed_total{{type=\"{}\",status=\"{}\"}} {}",
+                job_type, status, count
+            );
+        }
+
+        self.compile_duration.lock().unwrap().render(
+            &mut out,
+            "compile_duration_seconds",
+            "Time spent compiling WASM",
+        );
+        self.execute_duration.lock().unwrap().render(
+            &mut out,
+            "execute_duration_seconds",
+            "Time spent executing WASM",
+        );
+
+        out.push_str("# HELP near_submit_failures_total Execution results that failed to reach the contract\n");
+        out.push_str("# TYPE near_submit_failures_total counter\n");
+        let _ = writeln!(
+            out,
+            "near_submit_failures_total {}",
+            self.near_submit_failures.load(Ordering::Relaxed)
+        );
+
+        out.push_str("# HELP rpc_requests_total NEAR RPC calls made through the proxy\n");
+        out.push_str("# TYPE rpc_requests_total counter\n");
+        for ((method, status), count) in self.rpc_requests.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "rpc_requests_total{{method=\"{}\",status=\"{}\"}} {}",
+                method,
+                status.as_str(),
+                count
+            );
+        }
+
+        out.push_str("# HELP poll_idle_total Long-polls that returned without a job\n");
+        out.push_str("# TYPE poll_idle_total counter\n");
+        let _ = writeln!(out, "poll_idle_total {}", self.poll_idle.load(Ordering::Relaxed));
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+        let fetched_at = self.collateral_fetched_at.load(Ordering::Relaxed);
+        if fetched_at > 0 {
+            out.push_str("# HELP tdx_collateral_age_seconds Age of the TDX collateral last fetched from Phala\n");
+            out.push_str("# TYPE tdx_collateral_age_seconds gauge\n");
+            let _ = writeln!(out, "tdx_collateral_age_seconds {}", now - fetched_at as i64);
+        }
+        let next_update = self.collateral_next_update.load(Ordering::Relaxed);
+        if next_update > 0 {
+            out.push_str(
+                "# HELP tdx_collateral_expires_in_seconds Time until the register contract's TDX collateral expires\n",
+            );
+            out.push_str("# TYPE tdx_collateral_expires_in_seconds gauge\n");
+            let _ = writeln!(out, "tdx_collateral_expires_in_seconds {}", next_update as i64 - now);
+        }
+
+        out
+    }
+}
+
+/// Serve `GET /metrics` on `port` until the process exits
+pub async fn serve(port: u16) -> Result<()> {
+    let listener = TcpListener::bind(("0.0.0.0", port))
+        .await
+        .with_context(|| format!("Failed to bind metrics endpoint on port {}", port))?;
+    info!("📈 Metrics endpoint listening on :{}/metrics", port);
+
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                warn!("Metrics endpoint accept failed: {}", e);
+                continue;
+            }
+        };
+        tokio::spawn(async move {
+            if let Err(e) = respond(stream).await {
+                debug!("Metrics request failed: {}", e);
+            }
+        });
+    }
+}
+
+/// Answer one HTTP request and close the connection
+async fn respond(mut stream: TcpStream) -> Result<()> {
+    let mut buf = [0u8; 1024];
+    let n = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf)).await??;
+    let request = String::from_utf8_lossy(&buf[..n]);
+    let mut request_line = request.split_whitespace();
+
+    let (status, body) = match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", metrics().render()),
+        _ => ("404 Not Found", String::new()),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_renders_labelled_counters() {
+        let metrics = Metrics::new();
+        metrics.record_job(&JobType::Compile, false);
+        metrics.record_job(&JobType::Execute, true);
+        metrics.record_job(&JobType::Execute, true);
+        metrics.record_rpc_request("query", RpcStatus::Ok);
+        metrics.record_rpc_request("query", RpcStatus::Cached);
+        metrics.record_rpc_request("made_up_method", RpcStatus::Error);
+        metrics.record_near_submit_failure();
+        metrics.record_poll_idle();
+
+        let text = metrics.render();
+        assert!(text.contains("jobs_processed_total{type=\"compile\",status=\"failure\"} 1\n"));
+        assert!(text.contains("jobs_processed_total{type=\"execute\",status=\"success\"} 2\n"));
+        assert!(text.contains("rpc_requests_total{method=\"query\",status=\"ok\"} 1\n"));
+        assert!(text.contains("rpc_requests_total{method=\"query\",status=\"cached\"} 1\n"));
+        assert!(text.contains("rpc_requests_total{method=\"other\",status=\"error\"} 1\n"));
+        assert!(text.contains("near_submit_failures_total 1\n"));
+        assert!(text.contains("poll_idle_total 1\n"));
+    }
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let metrics = Metrics::new();
+        metrics.observe_execute(Duration::from_millis(50));
+        metrics.observe_execute(Duration::from_secs(3));
+        metrics.observe_execute(Duration::from_secs(900));
+
+        let text = metrics.render();
+        assert!(text.contains("execute_duration_seconds_bucket{le=\"0.1\"} 1\n"));
+        assert!(text.contains("execute_duration_seconds_bucket{le=\"2.5\"} 1\n"));
+        assert!(text.contains("execute_duration_seconds_bucket{le=\"5\"} 2\n"));
+        assert!(text.contains("execute_duration_seconds_bucket{le=\"600\"} 2\n"));
+        assert!(text.contains("execute_duration_seconds_bucket{le=\"+Inf\"} 3\n"));
+        assert!(text.contains("execute_duration_seconds_count 3\n"));
+        assert!(text.contains("compile_duration_seconds_count 0\n"));
+    }
+
+    #[test]
+    fn test_collateral_gauges_only_after_refresh() {
+        let metrics = Metrics::new();
+        assert!(!metrics.render().contains("tdx_collateral"));
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        metrics.set_collateral(now, None);
+        let text = metrics.render();
+        assert!(text.contains("tdx_collateral_age_seconds "));
+        assert!(!text.contains("tdx_collateral_expires_in_seconds"));
+
+        metrics.set_collateral(now, Some(now + 7 * 24 * 60 * 60));
+        let text = metrics.render();
+        let expires_in: i64 = text
+            .lines()
+            .find_map(|line| line.strip_prefix("tdx_collateral_expires_in_seconds "))
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(expires_in > 6 * 24 * 60 * 60);
+    }
+}
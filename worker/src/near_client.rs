@@ -4,10 +4,14 @@ use near_jsonrpc_client::{methods, JsonRpcClient};
 use near_primitives::transaction::{Action, FunctionCallAction, Transaction, TransactionV0};
 use near_primitives::types::{AccountId, Balance, BlockReference, Finality, Gas};
 use near_primitives::views::FinalExecutionOutcomeView;
+use near_primitives::transaction::SignedTransaction;
 use serde_json::{json, Value};
 use tracing::{debug, info, warn};
 
 use crate::api_client::{ExecutionOutput, ExecutionResult};
+use crate::metrics::metrics;
+use crate::result_signing::{self, ResultSignature};
+use crate::tx_retry::{self, RetryPolicy, TxSubmitter};
 
 /// NEAR blockchain client for worker operations
 #[derive(Clone)]
@@ -15,6 +19,33 @@ pub struct NearClient {
     client: JsonRpcClient,
     signer: InMemorySigner,
     contract_id: AccountId,
+    /// Retries for execution result submission
+    retry_policy: RetryPolicy,
+}
+
+/// Function call on the worker contract, signed and broadcast by `tx_retry`
+struct ContractCall<'a> {
+    near: &'a NearClient,
+    method_name: &'a str,
+    args: Vec<u8>,
+    gas: u64,
+    deposit: u128,
+}
+
+#[async_trait::async_trait]
+impl TxSubmitter for ContractCall<'_> {
+    type Tx = SignedTransaction;
+    type Outcome = FinalExecutionOutcomeView;
+
+    async fn sign(&self) -> Result<SignedTransaction> {
+        self.near
+            .sign_function_call(&self.near.contract_id, self.method_name, self.args.clone(), self.gas, self.deposit)
+            .await
+    }
+
+    async fn broadcast(&self, tx: SignedTransaction) -> Result<FinalExecutionOutcomeView> {
+        self.near.broadcast_commit(tx).await
+    }
 }
 
 impl NearClient {
@@ -31,9 +62,16 @@ impl NearClient {
             client,
             signer,
             contract_id,
+            retry_policy: RetryPolicy::default(),
         })
     }
 
+    /// Set the retry policy for execution result submission
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
     /// RPC call timeout to prevent hanging on unresponsive RPC nodes
     const RPC_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
 
@@ -132,12 +170,18 @@ impl NearClient {
                 match serde_json::from_str::<Value>(event_json) {
                     Ok(event) => {
                         if let Some(event_type) = event.get("event").and_then(|e| e.as_str()) {
-                            if event_type == "execution_completed" {
-                                info!("   ✓ Found execution_completed event!");
+                            // execution_completed is deprecated (event version 1.1.0, gone in 2.0.0)
+                            let charged_field = match event_type {
+                                "execution_resolved" => Some("actual_cost"),
+                                "execution_failed" | "execution_completed" => Some("payment_charged"),
+                                _ => None,
+                            };
+                            if let Some(charged_field) = charged_field {
+                                info!("   ✓ Found {} event!", event_type);
 
                                 if let Some(data) = event.get("data").and_then(|d| d.as_array()) {
                                     if let Some(first_data) = data.first() {
-                                        if let Some(payment_str) = first_data.get("payment_charged").and_then(|p| p.as_str()) {
+                                        if let Some(payment_str) = first_data.get(charged_field).and_then(|p| p.as_str()) {
                                             info!("   ✓ Found payment_charged: {}", payment_str);
 
                                             match payment_str.parse::<u128>() {
@@ -183,6 +227,7 @@ impl NearClient {
         &self,
         request_id: u64,
         result: &ExecutionResult,
+        result_signature: &ResultSignature,
     ) -> Result<(String, FinalExecutionOutcomeView)> {
         let output = result.output.as_ref().unwrap();
 
@@ -195,10 +240,14 @@ impl NearClient {
             "resources_used": {
                 "instructions": result.instructions,
                 "time_ms": result.execution_time_ms,
+                "peak_memory_bytes": result.peak_memory_bytes,
                 "compile_time_ms": result.compile_time_ms,
+                "output_bytes": result.output_bytes(),
             },
             "compilation_note": result.compilation_note,
             "refund_usd": result.refund_usd,
+            "declared_output_format": result.declared_output_format,
+            "result_signature": result_signature,
         });
 
         let args_json = serde_json::to_string(&args)
@@ -211,7 +260,7 @@ impl NearClient {
 
         // Call the combined method (400 TGas: 100 for submit + 300 for internal resolve)
         let outcome = self
-            .call_contract_method(
+            .call_contract_method_with_retry(
                 "submit_execution_output_and_resolve",
                 args_json.into_bytes(),
                 300_000_000_000_000, // 300 TGas total
@@ -289,8 +338,16 @@ impl NearClient {
     /// - If payload < 1024 bytes: calls `resolve_execution` directly (1-call)
     /// - If payload >= 1024 bytes: calls `submit_execution_output_and_resolve` (optimized 1-transaction flow)
     ///
+    /// The response carries a [`ResultSignature`] by the operator key over
+    /// `(request_id, input_hash, output_hash, worker_pubkey)` so external
+    /// parties can verify the result without TDX quotes (see `result_signing`).
+    ///
+    /// Timeouts, connection errors and nonce races are retried per the client's
+    /// `RetryPolicy`; other RPC errors are returned immediately.
+    ///
     /// # Arguments
     /// * `request_id` - Request ID from the contract
+    /// * `input_data` - Input data the WASM was executed with
     /// * `result` - Execution result from WASM executor
     ///
     /// # Returns
@@ -298,6 +355,20 @@ impl NearClient {
     pub async fn submit_execution_result(
         &self,
         request_id: u64,
+        input_data: &str,
+        result: &ExecutionResult,
+    ) -> Result<(String, FinalExecutionOutcomeView)> {
+        let submitted = self.send_execution_result(request_id, input_data, result).await;
+        if submitted.is_err() {
+            metrics().record_near_submit_failure();
+        }
+        submitted
+    }
+
+    async fn send_execution_result(
+        &self,
+        request_id: u64,
+        input_data: &str,
         result: &ExecutionResult,
     ) -> Result<(String, FinalExecutionOutcomeView)> {
         info!(
@@ -305,21 +376,35 @@ impl NearClient {
             request_id, result.success
         );
 
+        // Error truncation below never touches the output, so one signature covers every flow
+        let result_signature = result_signing::sign_result(
+            &self.signer.secret_key,
+            request_id,
+            input_data,
+            result.output.as_ref(),
+        );
+
         // Check payload size to decide between 1-call or 2-call flow
         // Build full ExecutionResponse to estimate payload size
         let full_response = json!({
             "success": result.success,
             "output": result.output,
             "error": result.error,
+            "error_structured": result.error_structured,
             "resources_used": {
                 "instructions": result.instructions,
                 "time_ms": result.execution_time_ms,
+                "peak_memory_bytes": result.peak_memory_bytes,
                 "compile_time_ms": result.compile_time_ms,
+                "output_bytes": result.output_bytes(),
             },
             "compilation_note": result.compilation_note,
             "refund_usd": result.refund_usd,
+            "declared_output_format": result.declared_output_format,
         });
 
+        // result_signature is left out: the contract emits it as an event
+        // instead of passing it through the 1024 byte yield payload
         let response_json = serde_json::to_string(&full_response)
             .context("Failed to serialize response")?;
 
@@ -337,7 +422,7 @@ impl NearClient {
                 // Success case: use optimized 2-call flow (submit_execution_output_and_resolve)
                 info!("⚠️  Payload exceeds limit ({} >= {}), using 2-call flow (submit_execution_output_and_resolve)",
                     payload_size, PAYLOAD_LIMIT);
-                return self.submit_result_two_call_flow(request_id, result).await;
+                return self.submit_result_two_call_flow(request_id, result, &result_signature).await;
             } else {
                 // Error case: truncate error message to fit in 1024 byte limit
                 // This prevents transaction failure due to large error messages
@@ -347,32 +432,35 @@ impl NearClient {
                 // Calculate how much space we have for error message
                 // Reserve space for JSON structure: {"success":false,"output":null,"error":"...","resources_used":{...}}
                 const MAX_ERROR_SIZE: usize = 512; // Conservative limit to ensure total payload < 1024
-
-                let truncated_result = if let Some(ref error_msg) = result.error {
-                    if error_msg.len() > MAX_ERROR_SIZE {
-                        let truncated = format!("{}... (truncated, original size: {} bytes)",
-                            &error_msg[..MAX_ERROR_SIZE], error_msg.len());
-                        info!("   Truncated error from {} to {} bytes", error_msg.len(), MAX_ERROR_SIZE);
-
-                        let mut new_result = result.clone();
-                        new_result.error = Some(truncated);
-                        new_result
-                    } else {
-                        result.clone()
+                // A compacted structured error (code + 128 char message) takes up to ~250 bytes
+                const MAX_ERROR_SIZE_WITH_STRUCTURED: usize = 256;
+
+                let mut truncated_result = result.clone();
+                let max_error_size = match &result.error_structured {
+                    Some(document) => {
+                        truncated_result.error_structured = Some(crate::executor::structured_error::compact(document));
+                        MAX_ERROR_SIZE_WITH_STRUCTURED
                     }
-                } else {
-                    result.clone()
+                    None => MAX_ERROR_SIZE,
                 };
+                if let Some(ref error_msg) = result.error {
+                    if error_msg.len() > max_error_size {
+                        let truncated = format!("{}... (truncated, original size: {} bytes)",
+                            &error_msg[..max_error_size], error_msg.len());
+                        info!("   Truncated error from {} to {} bytes", error_msg.len(), max_error_size);
+                        truncated_result.error = Some(truncated);
+                    }
+                }
 
                 // Continue with 1-call flow using truncated result
-                return self.submit_small_result(request_id, &truncated_result).await;
+                return self.submit_small_result(request_id, &truncated_result, &result_signature).await;
             }
         } else {
             info!("✅ Payload size OK, using 1-call flow (resolve_execution only)");
         }
 
         // Use standard 1-call flow
-        self.submit_small_result(request_id, result).await
+        self.submit_small_result(request_id, result, &result_signature).await
     }
 
     /// Submit small execution result using 1-call flow (resolve_execution only)
@@ -382,6 +470,7 @@ impl NearClient {
         &self,
         request_id: u64,
         result: &ExecutionResult,
+        result_signature: &ResultSignature,
     ) -> Result<(String, FinalExecutionOutcomeView)> {
         // 1-call flow: Prepare method arguments for resolve_execution with output
         let args = json!({
@@ -390,13 +479,18 @@ impl NearClient {
                 "success": result.success,
                 "output": result.output,
                 "error": result.error,
+                "error_structured": result.error_structured,
                 "resources_used": {
                     "instructions": result.instructions,
                     "time_ms": result.execution_time_ms,
+                    "peak_memory_bytes": result.peak_memory_bytes,
                     "compile_time_ms": result.compile_time_ms,
+                    "output_bytes": result.output_bytes(),
                 },
                 "compilation_note": result.compilation_note,
                 "refund_usd": result.refund_usd,
+                "declared_output_format": result.declared_output_format,
+                "result_signature": result_signature,
             }
         });
 
@@ -415,7 +509,7 @@ impl NearClient {
         info!("   Gas: 300 TGas");
 
         let outcome = self
-            .call_contract_method(
+            .call_contract_method_with_retry(
                 "resolve_execution",
                 args_json.into_bytes(),
                 300_000_000_000_000, // 300 TGas (increased for yield resume)
@@ -529,6 +623,27 @@ impl NearClient {
         self.call_contract_method_internal(&self.contract_id, method_name, args, gas, deposit).await
     }
 
+    /// Call a contract method on the default contract, retrying per `retry_policy`
+    ///
+    /// Transient RPC errors rebroadcast the same signed transaction, nonce errors sign
+    /// again with a fresh nonce, anything else fails immediately (see `tx_retry`).
+    async fn call_contract_method_with_retry(
+        &self,
+        method_name: &str,
+        args: Vec<u8>,
+        gas: u64,
+        deposit: u128,
+    ) -> Result<FinalExecutionOutcomeView> {
+        let call = ContractCall {
+            near: self,
+            method_name,
+            args,
+            gas,
+            deposit,
+        };
+        tx_retry::submit_with_retry(&call, &self.retry_policy, method_name).await
+    }
+
     /// Call a contract method (internal implementation)
     async fn call_contract_method_internal(
         &self,
@@ -538,6 +653,21 @@ impl NearClient {
         gas: u64,
         deposit: u128,
     ) -> Result<FinalExecutionOutcomeView> {
+        let signed_transaction = self
+            .sign_function_call(contract_id, method_name, args, gas, deposit)
+            .await?;
+        self.broadcast_commit(signed_transaction).await
+    }
+
+    /// Build and sign a function call with the next nonce of the signer's access key
+    async fn sign_function_call(
+        &self,
+        contract_id: &AccountId,
+        method_name: &str,
+        args: Vec<u8>,
+        gas: u64,
+        deposit: u128,
+    ) -> Result<SignedTransaction> {
         // Get account access key for nonce
         let access_key_query = methods::query::RpcQueryRequest {
             block_reference: BlockReference::Finality(Finality::Final),
@@ -590,10 +720,11 @@ impl NearClient {
 
         // Sign transaction
         let signature = self.signer.sign(transaction.get_hash_and_size().0.as_ref());
-        let signed_transaction = near_primitives::transaction::SignedTransaction::new(
-            signature,
-            transaction,
-        );
+        Ok(SignedTransaction::new(signature, transaction))
+    }
+
+    /// Broadcast a signed transaction and wait for its outcome
+    async fn broadcast_commit(&self, signed_transaction: SignedTransaction) -> Result<FinalExecutionOutcomeView> {
         let hash = signed_transaction.get_hash();
 
         // Broadcast transaction with commit (wait for finality)
@@ -788,6 +919,36 @@ impl NearClient {
         Ok(tx_hash)
     }
 
+    /// Remaining allowance of the operator access key in yoctoNEAR
+    ///
+    /// Returns `None` for full-access keys and function-call keys without an allowance limit.
+    pub async fn access_key_allowance(&self) -> Result<Option<u128>> {
+        let request = methods::query::RpcQueryRequest {
+            block_reference: BlockReference::Finality(Finality::Final),
+            request: near_primitives::views::QueryRequest::ViewAccessKey {
+                account_id: self.signer.account_id.clone(),
+                public_key: self.signer.public_key(),
+            },
+        };
+
+        let response = tokio::time::timeout(Self::RPC_TIMEOUT, self.client.call(request))
+            .await
+            .context("NEAR RPC access key query timed out")?
+            .context("Failed to query access key")?;
+
+        match response.kind {
+            near_jsonrpc_primitives::types::query::QueryResponseKind::AccessKey(access_key) => {
+                match access_key.permission {
+                    near_primitives::views::AccessKeyPermissionView::FunctionCall { allowance, .. } => {
+                        Ok(allowance.map(|a| a.as_yoctonear()))
+                    }
+                    near_primitives::views::AccessKeyPermissionView::FullAccess => Ok(None),
+                }
+            }
+            _ => anyhow::bail!("Unexpected query response"),
+        }
+    }
+
     /// Fetch project info from contract by project_id
     ///
     /// Returns project with active version info (repo, commit, build_target)
@@ -861,6 +1022,31 @@ impl NearClient {
             anyhow::bail!("Unexpected response kind from get_version");
         }
     }
+
+    /// Fetch the TDX collaterals cached on a register contract, in slot order
+    ///
+    /// The slot index is what `update_collateral` takes to replace one of them.
+    pub async fn fetch_collaterals(&self, register_contract_id: &AccountId) -> Result<Vec<String>> {
+        let request = methods::query::RpcQueryRequest {
+            block_reference: BlockReference::Finality(Finality::Final),
+            request: near_primitives::views::QueryRequest::CallFunction {
+                account_id: register_contract_id.clone(),
+                method_name: "get_collaterals".to_string(),
+                args: b"{}".to_vec().into(),
+            },
+        };
+
+        let response = tokio::time::timeout(Self::RPC_TIMEOUT, self.client.call(request))
+            .await
+            .context("NEAR RPC get_collaterals timed out")?
+            .context("Failed to call get_collaterals")?;
+
+        if let near_jsonrpc_primitives::types::query::QueryResponseKind::CallResult(result) = response.kind {
+            serde_json::from_slice(&result.result).context("Failed to parse collaterals")
+        } else {
+            anyhow::bail!("Unexpected response kind from get_collaterals");
+        }
+    }
 }
 
 /// Project info from contract
@@ -891,6 +1077,10 @@ pub enum ContractCodeSource {
         repo: String,
         commit: String,
         build_target: Option<String>,
+        #[serde(default)]
+        package: Option<String>,
+        #[serde(default)]
+        features: Vec<String>,
     },
     WasmUrl {
         url: String,
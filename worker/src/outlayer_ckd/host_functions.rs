@@ -0,0 +1,204 @@
+//! CKD host functions for WASM components
+//!
+//! Implements the `near:ckd/api` WIT interface.
+//! Proxies to keystore-worker `/ckd/app-key`, which submits keystore-dao `request_key`.
+
+use anyhow::Result;
+use tracing::debug;
+use wasmtime::component::Linker;
+
+use crate::keystore_client::Attestation;
+
+// Generate bindings from WIT
+wasmtime::component::bindgen!({
+    path: "wit",
+    world: "near:ckd/ckd-host",
+});
+
+/// App key result: (big_y, big_c, error)
+type AppKeyResult = (String, String, String);
+
+/// Host state for CKD functions
+pub struct CkdHostState {
+    /// Project the execution runs in (fixes the derivation path)
+    project_id: String,
+    /// Blocking HTTP client for keystore calls
+    http_client: reqwest::blocking::Client,
+    /// Keystore base URL
+    keystore_url: String,
+    /// Auth token for keystore
+    auth_token: String,
+    /// TEE session ID (provides actual auth via X-TEE-Session header)
+    tee_session_id: Option<String>,
+    /// Call counter for rate limiting
+    call_count: u32,
+    /// Max app key requests per execution (a project's first one is an on-chain MPC call)
+    max_calls: u32,
+}
+
+impl CkdHostState {
+    /// Create CKD host state
+    ///
+    /// `tee_session_id` provides the actual auth — attestation in the body is a stub
+    /// (same pattern as VRF).
+    pub fn new(project_id: &str, keystore_url: &str, auth_token: &str, tee_session_id: Option<String>) -> Self {
+        // The keystore waits for the MPC transaction to finish
+        let http_client = reqwest::blocking::Client::builder()
+            .timeout(std::time::Duration::from_secs(60))
+            .connect_timeout(std::time::Duration::from_secs(5))
+            .build()
+            .expect("failed to build CKD HTTP client");
+
+        Self {
+            project_id: project_id.to_string(),
+            http_client,
+            keystore_url: keystore_url.to_string(),
+            auth_token: auth_token.to_string(),
+            tee_session_id,
+            call_count: 0,
+            max_calls: 1,
+        }
+    }
+
+    /// Stub attestation — real auth is via X-TEE-Session header
+    fn stub_attestation() -> Attestation {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        Attestation {
+            tee_type: "none".to_string(),
+            quote: base64_encode(b"session-auth"),
+            worker_pubkey: None,
+            timestamp,
+        }
+    }
+}
+
+impl near::ckd::api::Host for CkdHostState {
+    fn request_app_key(&mut self, domain_id: u64, app_public_key: String) -> AppKeyResult {
+        debug!(
+            "ckd::request_app_key domain_id={}, project_id={}",
+            domain_id, self.project_id
+        );
+
+        let fail = |error: String| (String::new(), String::new(), error);
+
+        if !app_public_key.starts_with("bls12381g1:") {
+            return fail("app_public_key must be a BLS12-381 G1 key (bls12381g1:<base58>)".to_string());
+        }
+
+        // Only a TEE worker has a session with the keystore; it refuses anyone else
+        let Some(ref session_id) = self.tee_session_id else {
+            return fail("App keys require a worker running in TEE".to_string());
+        };
+
+        if self.call_count >= self.max_calls {
+            return fail(format!(
+                "App key rate limit exceeded: {} calls (max: {})",
+                self.call_count + 1,
+                self.max_calls
+            ));
+        }
+        self.call_count += 1;
+
+        let attestation = Self::stub_attestation();
+        let url = format!("{}/ckd/app-key", self.keystore_url);
+
+        let request = self
+            .http_client
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", self.auth_token))
+            .header("X-TEE-Session", session_id.as_str())
+            .json(&serde_json::json!({
+                "project_id": self.project_id,
+                "domain_id": domain_id,
+                "app_public_key": app_public_key,
+                "attestation": serde_json::json!({
+                    "tee_type": attestation.tee_type,
+                    "quote": attestation.quote,
+                    "worker_pubkey": attestation.worker_pubkey,
+                    "timestamp": attestation.timestamp,
+                }),
+            }));
+
+        match request.send() {
+            Ok(response) => {
+                if !response.status().is_success() {
+                    let status = response.status();
+                    let error = response.text().unwrap_or_default();
+                    return fail(format!("Keystore app key request failed ({}): {}", status, error));
+                }
+
+                match response.json::<serde_json::Value>() {
+                    Ok(json) => {
+                        let big_y = json["big_y"].as_str().unwrap_or("").to_string();
+                        let big_c = json["big_c"].as_str().unwrap_or("").to_string();
+                        if big_y.is_empty() || big_c.is_empty() {
+                            return fail("Keystore app key response is missing big_y/big_c".to_string());
+                        }
+                        debug!("ckd::request_app_key success");
+                        (big_y, big_c, String::new())
+                    }
+                    Err(e) => fail(format!("Failed to parse app key response: {}", e)),
+                }
+            }
+            Err(e) => fail(format!("App key request failed: {}", e)),
+        }
+    }
+}
+
+/// Add CKD host functions to a wasmtime component linker
+pub fn add_ckd_to_linker<T: Send + 'static>(
+    linker: &mut Linker<T>,
+    get_state: impl Fn(&mut T) -> &mut CkdHostState + Send + Sync + Copy + 'static,
+) -> Result<()> {
+    near::ckd::api::add_to_linker(linker, get_state)
+}
+
+fn base64_encode(data: &[u8]) -> String {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD.encode(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_state(tee_session_id: Option<&str>) -> CkdHostState {
+        CkdHostState {
+            project_id: "alice.near/my-ark".to_string(),
+            http_client: reqwest::blocking::Client::new(),
+            keystore_url: "http://localhost:0".to_string(),
+            auth_token: String::new(),
+            tee_session_id: tee_session_id.map(str::to_string),
+            call_count: 0,
+            max_calls: 1,
+        }
+    }
+
+    const APP_KEY: &str = "bls12381g1:6KtVVcAAGacrjNGePN8bp3KV6fYGrw1rFsyc7cVJCqR16Zc2ZFg3HX3hSZxSfv1oH6";
+
+    #[test]
+    fn test_rejects_non_bls_app_key() {
+        let mut state = make_state(Some("session"));
+        let (_, _, error) = near::ckd::api::Host::request_app_key(&mut state, 2, "ed25519:abc".to_string());
+        assert!(error.contains("BLS12-381"), "got: {}", error);
+        assert_eq!(state.call_count, 0);
+    }
+
+    #[test]
+    fn test_requires_tee_session() {
+        let mut state = make_state(None);
+        let (_, _, error) = near::ckd::api::Host::request_app_key(&mut state, 2, APP_KEY.to_string());
+        assert_eq!(error, "App keys require a worker running in TEE");
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let mut state = make_state(Some("session"));
+        state.max_calls = 0;
+        let (_, _, error) = near::ckd::api::Host::request_app_key(&mut state, 2, APP_KEY.to_string());
+        assert!(error.contains("rate limit"), "expected rate limit error, got: {}", error);
+    }
+}
@@ -0,0 +1,9 @@
+//! MPC CKD host functions for WASM components
+//!
+//! Lets an ark request its project's MPC-derived app key. The derivation path is bound
+//! to the project by the keystore — the ark only chooses the domain and the key the
+//! result is encrypted to.
+
+pub mod host_functions;
+
+pub use host_functions::{CkdHostState, add_ckd_to_linker};
@@ -0,0 +1,113 @@
+//! Instruction metering host functions for WASM components
+//!
+//! Implements the `near:meter/api` WIT interface on top of wasmtime fuel. The store
+//! starts with `max_instructions` fuel, so `remaining` is the fuel left and `used` is
+//! the difference.
+//!
+//! The functions need the store itself rather than a slice of `HostState`, so they are
+//! registered with `func_wrap` instead of through bindgen like the other interfaces.
+//! Compiled code keeps the fuel counter in the store up to date at every call out of
+//! WASM, so the values are exact as of the call. They hold no state and are always
+//! added to the linker.
+
+use anyhow::Result;
+use wasmtime::component::Linker;
+use wasmtime::StoreContextMut;
+
+/// WIT interface implemented here
+pub const METER_INTERFACE: &str = "near:meter/api@0.1.0";
+
+/// Add metering host functions to a wasmtime component linker
+///
+/// `max_instructions` must be the fuel the store is started with.
+pub fn add_meter_to_linker<T: Send + 'static>(linker: &mut Linker<T>, max_instructions: u64) -> Result<()> {
+    let mut instance = linker.instance(METER_INTERFACE)?;
+    instance.func_wrap("instructions-used", move |store: StoreContextMut<'_, T>, (): ()| {
+        Ok((instructions_used(max_instructions, store.get_fuel()?),))
+    })?;
+    instance.func_wrap("instructions-remaining", |store: StoreContextMut<'_, T>, (): ()| {
+        Ok((store.get_fuel()?,))
+    })?;
+    Ok(())
+}
+
+/// Instructions consumed out of `max_instructions` with `remaining` fuel left
+fn instructions_used(max_instructions: u64, remaining: u64) -> u64 {
+    max_instructions.saturating_sub(remaining)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmtime::component::Component;
+    use wasmtime::{Config, Engine, Store};
+
+    /// Exports `used` (spins 1000 iterations, then reads the meter) and `remaining`
+    const METERED_COMPONENT: &str = r#"
+        (component
+          (import "near:meter/api@0.1.0" (instance $meter
+            (export "instructions-used" (func (result u64)))
+            (export "instructions-remaining" (func (result u64)))
+          ))
+          (core func $used (canon lower (func $meter "instructions-used")))
+          (core func $remaining (canon lower (func $meter "instructions-remaining")))
+          (core module $m
+            (import "meter" "used" (func $used (result i64)))
+            (import "meter" "remaining" (func $remaining (result i64)))
+            (func (export "used") (result i64)
+              (local $i i32)
+              (loop $spin
+                (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                (br_if $spin (i32.lt_u (local.get $i) (i32.const 1000))))
+              (call $used))
+            (func (export "remaining") (result i64)
+              (call $remaining))
+          )
+          (core instance $meter_core
+            (export "used" (func $used))
+            (export "remaining" (func $remaining)))
+          (core instance $i (instantiate $m (with "meter" (instance $meter_core))))
+          (func (export "used") (result u64) (canon lift (core func $i "used")))
+          (func (export "remaining") (result u64) (canon lift (core func $i "remaining")))
+        )
+    "#;
+
+    #[test]
+    fn test_meter_reads_fuel_and_is_monotonic() {
+        const BUDGET: u64 = 1_000_000;
+
+        let mut config = Config::new();
+        config.wasm_component_model(true);
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).unwrap();
+        let component = Component::new(&engine, METERED_COMPONENT).unwrap();
+
+        let mut linker = Linker::<()>::new(&engine);
+        add_meter_to_linker(&mut linker, BUDGET).unwrap();
+        let mut store = Store::new(&engine, ());
+        store.set_fuel(BUDGET).unwrap();
+        let instance = linker.instantiate(&mut store, &component).unwrap();
+        let used = instance.get_typed_func::<(), (u64,)>(&mut store, "used").unwrap();
+        let remaining = instance.get_typed_func::<(), (u64,)>(&mut store, "remaining").unwrap();
+
+        let (first,) = used.call(&mut store, ()).unwrap();
+        used.post_return(&mut store).unwrap();
+        // The loop alone runs several thousand operators
+        assert!(first >= 1000, "used {} instructions", first);
+
+        let (second,) = used.call(&mut store, ()).unwrap();
+        used.post_return(&mut store).unwrap();
+        assert!(second >= first + 1000, "first {}, second {}", first, second);
+
+        let (left,) = remaining.call(&mut store, ()).unwrap();
+        remaining.post_return(&mut store).unwrap();
+        assert!(left <= BUDGET - second);
+        assert_eq!(instructions_used(BUDGET, left), BUDGET - left);
+    }
+
+    #[test]
+    fn test_used_saturates() {
+        assert_eq!(instructions_used(100, 40), 60);
+        assert_eq!(instructions_used(100, 150), 0);
+    }
+}
@@ -0,0 +1,7 @@
+//! Instruction metering host functions for WASM components
+//!
+//! Lets WASI components read their own instruction budget (`env::instructions_used`).
+
+pub mod host_functions;
+
+pub use host_functions::add_meter_to_linker;
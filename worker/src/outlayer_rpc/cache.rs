@@ -0,0 +1,276 @@
+//! Cache for immutable NEAR RPC results
+//!
+//! A query pinned to a block height or hash always gets the same answer, and arks ask the
+//! same ones over and over across executions (contract code, token metadata at a snapshot
+//! block, ...). Those responses are kept here and shared by every execution on the worker.
+//!
+//! Only block-pinned reads are cached. Queries at a finality (`final`, `optimistic`,
+//! `near-final`) follow the chain head, so they always go to RPC, as do transactions, tx
+//! status and error responses.
+//! Entries expire after the TTL (a backstop for a height asked before it was final), and
+//! the least recently used ones are evicted once the cache exceeds its size limit.
+//!
+//! Cached calls still count against the per-execution call limit, so an ark behaves the
+//! same whether the cache is warm or not.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// `query` request types whose result is fixed once the block is
+const CACHEABLE_QUERIES: &[&str] = &[
+    "view_account",
+    "view_code",
+    "view_state",
+    "view_access_key",
+    "view_access_key_list",
+    "call_function",
+];
+
+/// Block-level methods whose result is fixed for a given block or chunk
+const CACHEABLE_METHODS: &[&str] = &["block", "chunk", "EXPERIMENTAL_changes", "EXPERIMENTAL_changes_in_block"];
+
+/// Cache counters, reported in worker heartbeats
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RpcCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub size_bytes: u64,
+}
+
+struct CacheEntry {
+    response: Value,
+    /// Serialized size of the response
+    size: u64,
+    inserted: Instant,
+    /// Last access time for LRU eviction
+    last_used: Instant,
+}
+
+#[derive(Default)]
+struct Entries {
+    map: HashMap<String, CacheEntry>,
+    total_size: u64,
+}
+
+impl Entries {
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.map.remove(key) {
+            self.total_size -= entry.size;
+        }
+    }
+}
+
+/// TTL + LRU cache of RPC responses, keyed by method and normalized params
+pub struct RpcCache {
+    entries: Mutex<Entries>,
+    max_size_bytes: u64,
+    ttl: Duration,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl RpcCache {
+    pub fn new(max_size_mb: u64, ttl: Duration) -> Self {
+        Self::with_max_bytes(max_size_mb * 1024 * 1024, ttl)
+    }
+
+    fn with_max_bytes(max_size_bytes: u64, ttl: Duration) -> Self {
+        Self {
+            entries: Mutex::new(Entries::default()),
+            max_size_bytes,
+            ttl,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Cached response for `key`, counting the lookup as a hit or a miss
+    pub fn get(&self, key: &str) -> Option<Value> {
+        let mut entries = self.entries.lock().unwrap();
+        let expired = match entries.map.get_mut(key) {
+            Some(entry) if entry.inserted.elapsed() < self.ttl => {
+                entry.last_used = Instant::now();
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Some(entry.response.clone());
+            }
+            Some(_) => true,
+            None => false,
+        };
+        if expired {
+            entries.remove(key);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        None
+    }
+
+    /// Store a successful response; RPC errors and responses larger than the cache are skipped
+    pub fn put(&self, key: String, response: &Value) {
+        if response.get("error").is_some() {
+            return;
+        }
+        let size = serde_json::to_vec(response).map(|bytes| bytes.len() as u64).unwrap_or(0);
+        if size == 0 || size > self.max_size_bytes {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        entries.remove(&key);
+        while entries.total_size + size > self.max_size_bytes {
+            let oldest = entries
+                .map
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(oldest) => entries.remove(&oldest),
+                None => break,
+            }
+        }
+        let now = Instant::now();
+        entries.total_size += size;
+        entries.map.insert(
+            key,
+            CacheEntry {
+                response: response.clone(),
+                size,
+                inserted: now,
+                last_used: now,
+            },
+        );
+    }
+
+    pub fn stats(&self) -> RpcCacheStats {
+        let entries = self.entries.lock().unwrap();
+        RpcCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: entries.map.len(),
+            size_bytes: entries.total_size,
+        }
+    }
+}
+
+/// Cache key for a JSON-RPC call, or `None` if its result can change
+pub fn cache_key(method: &str, params: &Value) -> Option<String> {
+    let pinned = match method {
+        "query" => {
+            let request_type = params.get("request_type").and_then(Value::as_str)?;
+            CACHEABLE_QUERIES.contains(&request_type) && is_block_pinned(params)
+        }
+        "gas_price" => params
+            .as_array()
+            .and_then(|args| args.first())
+            .is_some_and(|block| !block.is_null()),
+        _ if CACHEABLE_METHODS.contains(&method) => {
+            is_block_pinned(params) || params.get("chunk_id").is_some()
+        }
+        _ => false,
+    };
+    pinned.then(|| format!("{}:{}", method, normalize(params)))
+}
+
+/// Params name an explicit block instead of a finality
+fn is_block_pinned(params: &Value) -> bool {
+    params.get("finality").is_none()
+        && params.get("block_id").is_some_and(|id| id.is_u64() || id.is_string())
+}
+
+/// JSON with object keys sorted, so equal params always give the same key
+fn normalize(value: &Value) -> String {
+    fn sorted(value: &Value) -> Value {
+        match value {
+            Value::Object(map) => {
+                let mut fields: Vec<_> = map.iter().collect();
+                fields.sort_by(|a, b| a.0.cmp(b.0));
+                Value::Object(fields.into_iter().map(|(k, v)| (k.clone(), sorted(v))).collect())
+            }
+            Value::Array(items) => Value::Array(items.iter().map(sorted).collect()),
+            other => other.clone(),
+        }
+    }
+    sorted(value).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn response(result: &str) -> Value {
+        json!({ "jsonrpc": "2.0", "id": "proxy", "result": { "value": result } })
+    }
+
+    #[test]
+    fn test_only_block_pinned_calls_are_cacheable() {
+        let pinned = json!({ "request_type": "view_code", "account_id": "wrap.testnet", "block_id": 180_000_000u64 });
+        let at_final = json!({ "request_type": "view_code", "account_id": "wrap.testnet", "finality": "final" });
+        let optimistic = json!({ "finality": "optimistic" });
+
+        assert!(cache_key("query", &pinned).is_some());
+        assert!(cache_key("query", &at_final).is_none());
+        assert!(cache_key("block", &optimistic).is_none());
+        assert!(cache_key("block", &json!({ "block_id": "9BfCJBnQqGvJBZs8K3cS4DkbhJk4UXcXg7mTm3rz7xnb" })).is_some());
+        assert!(cache_key("chunk", &json!({ "chunk_id": "EBM2qg5cGr47EjMPtH88uvmXHDHqmWPzKaQadbWhdw22" })).is_some());
+        assert!(cache_key("gas_price", &json!([null])).is_none());
+        assert!(cache_key("gas_price", &json!([180_000_000u64])).is_some());
+        assert!(cache_key("send_tx", &json!({ "signed_tx_base64": "AAAA", "block_id": 1 })).is_none());
+        assert!(cache_key("EXPERIMENTAL_tx_status", &json!({ "tx_hash": "abc", "sender_account_id": "a.near" })).is_none());
+    }
+
+    #[test]
+    fn test_key_ignores_param_order() {
+        let a = json!({ "request_type": "view_account", "account_id": "a.near", "block_id": 5 });
+        let b = json!({ "block_id": 5, "account_id": "a.near", "request_type": "view_account" });
+        assert_eq!(cache_key("query", &a), cache_key("query", &b));
+        assert_ne!(cache_key("query", &a), cache_key("block", &json!({ "block_id": 5 })));
+    }
+
+    #[test]
+    fn test_hits_misses_and_errors() {
+        let cache = RpcCache::new(1, Duration::from_secs(60));
+        assert!(cache.get("block:5").is_none());
+
+        cache.put("block:5".to_string(), &response("five"));
+        cache.put("block:6".to_string(), &json!({ "error": { "name": "HANDLER_ERROR" } }));
+
+        assert_eq!(cache.get("block:5"), Some(response("five")));
+        assert!(cache.get("block:6").is_none());
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 2, 1));
+    }
+
+    #[test]
+    fn test_entries_expire_after_ttl() {
+        let cache = RpcCache::new(1, Duration::from_millis(10));
+        cache.put("block:5".to_string(), &response("five"));
+        std::thread::sleep(Duration::from_millis(20));
+
+        assert!(cache.get("block:5").is_none());
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn test_least_recently_used_is_evicted() {
+        let size = serde_json::to_vec(&response("a")).unwrap().len() as u64;
+        let cache = RpcCache::with_max_bytes(size * 2, Duration::from_secs(60));
+
+        cache.put("a".to_string(), &response("a"));
+        std::thread::sleep(Duration::from_millis(2));
+        cache.put("b".to_string(), &response("b"));
+        std::thread::sleep(Duration::from_millis(2));
+        // Touch "a" so "b" is the least recently used
+        assert!(cache.get("a").is_some());
+        cache.put("c".to_string(), &response("c"));
+
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("c").is_some());
+        assert_eq!(cache.stats().size_bytes, size * 2);
+    }
+}
@@ -25,12 +25,18 @@ use std::sync::Arc;
 use std::time::Duration;
 use wasmtime::component::Linker;
 
+use super::cache::{self, RpcCache};
+use crate::metrics::{metrics, RpcStatus};
+
 // Generate bindings from WIT - sync mode for simpler implementation
 wasmtime::component::bindgen!({
     path: "wit",
     world: "rpc-host",
 });
 
+/// Maximum view calls of one `batch-view` in flight at once
+const MAX_BATCH_VIEW_CONCURRENCY: usize = 8;
+
 /// RPC Proxy client with rate limiting (using blocking HTTP)
 pub struct RpcProxy {
     /// HTTP client for RPC requests (blocking)
@@ -45,6 +51,8 @@ pub struct RpcProxy {
     call_count: Arc<AtomicU32>,
     /// Optional signer for transactions (account_id, private_key)
     signer: Option<(String, String)>,
+    /// Cache for block-pinned results, shared by all executions
+    cache: Option<Arc<RpcCache>>,
 }
 
 impl RpcProxy {
@@ -68,9 +76,16 @@ impl RpcProxy {
             allow_transactions,
             call_count: Arc::new(AtomicU32::new(0)),
             signer,
+            cache: None,
         })
     }
 
+    /// Serve block-pinned reads from `cache` (see [`super::cache`])
+    pub fn with_cache(mut self, cache: Option<Arc<RpcCache>>) -> Self {
+        self.cache = cache;
+        self
+    }
+
     /// Reset call counter
     #[allow(dead_code)]
     pub fn reset_call_count(&self) {
@@ -118,6 +133,15 @@ impl RpcProxy {
 
         self.check_rate_limit()?;
 
+        let cache_key = self.cache.as_ref().and_then(|_| cache::cache_key(method, &params));
+        if let (Some(cache), Some(key)) = (&self.cache, &cache_key) {
+            if let Some(response) = cache.get(key) {
+                debug!("[RPC] Cache hit: {}", key);
+                metrics().record_rpc_request(method, RpcStatus::Cached);
+                return Ok(response);
+            }
+        }
+
         let request = json!({
             "jsonrpc": "2.0",
             "id": "proxy",
@@ -127,11 +151,26 @@ impl RpcProxy {
 
         info!("[RPC] Sending {} request to {}", method, Self::safe_url_display(&self.rpc_url));
 
+        let body = self.send_request(&request);
+        let status = match &body {
+            Ok(body) if body.get("error").is_none() => RpcStatus::Ok,
+            _ => RpcStatus::Error,
+        };
+        metrics().record_rpc_request(method, status);
+        let body = body?;
+        if let (Some(cache), Some(key)) = (&self.cache, cache_key) {
+            cache.put(key, &body);
+        }
+        Ok(body)
+    }
+
+    /// POST a JSON-RPC request and parse the response
+    fn send_request(&self, request: &Value) -> Result<Value> {
         let response = self
             .client
             .post(&self.rpc_url)
             .header("Content-Type", "application/json")
-            .json(&request)
+            .json(request)
             .send()
             .context("Failed to send RPC request")?;
 
@@ -141,10 +180,110 @@ impl RpcProxy {
             anyhow::bail!("RPC returned status {}: {}", status, error_text);
         }
 
-        let body: Value = response
-            .json()
-            .context("Failed to parse RPC response")?;
-        Ok(body)
+        response.json().context("Failed to parse RPC response")
+    }
+
+    /// Call a view function and return its decoded result
+    ///
+    /// The result bytes are returned as UTF-8 text; a response without a result array
+    /// (e.g. an RPC error object) is returned as its JSON.
+    pub fn view_function(
+        &self,
+        contract_id: &str,
+        method_name: &str,
+        args_json: &str,
+        finality_or_block: &str,
+    ) -> Result<String> {
+        let result = self.call_function_query(contract_id, method_name, args_json, finality_or_block)?;
+        if let Some(arr) = result.get("result").and_then(|r| r.get("result")).and_then(|r| r.as_array()) {
+            let bytes: Vec<u8> = arr
+                .iter()
+                .filter_map(|v| v.as_u64().map(|n| n as u8))
+                .collect();
+            return Ok(String::from_utf8_lossy(&bytes).to_string());
+        }
+        Ok(serde_json::to_string(&result).unwrap_or_default())
+    }
+
+    /// Call a view function and parse its result as JSON
+    ///
+    /// Unlike [`Self::view_function`], RPC errors and contract panics are returned as
+    /// errors instead of as the response JSON.
+    pub fn view_function_json(
+        &self,
+        contract_id: &str,
+        method_name: &str,
+        args: &Value,
+        finality_or_block: &str,
+    ) -> Result<Value> {
+        let response = self.call_function_query(contract_id, method_name, &args.to_string(), finality_or_block)?;
+        let bytes = view_result_bytes(&response).with_context(|| format!("{}.{} failed", contract_id, method_name))?;
+        serde_json::from_slice(&bytes).with_context(|| format!("{}.{} returned invalid JSON", contract_id, method_name))
+    }
+
+    /// NEP-141 balance of `account_id` on `token_id`, as a decimal string in minimal units
+    pub fn ft_balance_of(&self, token_id: &str, account_id: &str, finality_or_block: &str) -> Result<String> {
+        let args = json!({ "account_id": account_id });
+        match self.view_function_json(token_id, "ft_balance_of", &args, finality_or_block)? {
+            Value::String(balance) if balance.parse::<u128>().is_ok() => Ok(balance),
+            other => anyhow::bail!("{}.ft_balance_of returned {}, expected a U128 string", token_id, other),
+        }
+    }
+
+    /// NEP-148 metadata of `token_id`
+    pub fn ft_metadata(&self, token_id: &str, finality_or_block: &str) -> Result<Value> {
+        match self.view_function_json(token_id, "ft_metadata", &json!({}), finality_or_block)? {
+            metadata @ Value::Object(_) => Ok(metadata),
+            other => anyhow::bail!("{}.ft_metadata returned {}, expected an object", token_id, other),
+        }
+    }
+
+    /// `query` of a `call_function` request
+    fn call_function_query(
+        &self,
+        contract_id: &str,
+        method_name: &str,
+        args_json: &str,
+        finality_or_block: &str,
+    ) -> Result<Value> {
+        let args_base64 = base64::engine::general_purpose::STANDARD.encode(args_json.as_bytes());
+        let block = BlockReference::parse(finality_or_block)?;
+
+        let mut params = json!({
+            "request_type": "call_function",
+            "account_id": contract_id,
+            "method_name": method_name,
+            "args_base64": args_base64,
+        });
+        block.apply(&mut params);
+
+        self.call_method("query", params)
+    }
+
+    /// Run several view calls concurrently, returning results in input order
+    ///
+    /// At most `MAX_BATCH_VIEW_CONCURRENCY` requests are in flight at once. Every call
+    /// counts against the rate limit, so calls past the limit fail individually.
+    pub fn view_functions(&self, calls: &[near::rpc::api::ViewCall]) -> Vec<Result<String>> {
+        let mut results = Vec::with_capacity(calls.len());
+        for chunk in calls.chunks(MAX_BATCH_VIEW_CONCURRENCY) {
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = chunk
+                    .iter()
+                    .map(|call| {
+                        scope.spawn(move || {
+                            self.view_function(&call.contract_id, &call.method_name, &call.args_json, &call.finality)
+                        })
+                    })
+                    .collect();
+                results.extend(handles.into_iter().map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|_| Err(anyhow::anyhow!("view call panicked")))
+                }));
+            });
+        }
+        results
     }
 
     #[allow(dead_code)]
@@ -242,16 +381,17 @@ impl RpcProxy {
             .as_ref()
             .ok_or_else(|| anyhow::anyhow!("No signer configured. Use --rpc-signer-account and --rpc-signer-key"))?;
 
-        self.sign_and_send_tx_as(signer_account, secret_key_str, receiver_id, actions)
+        self.sign_and_send_tx_as(signer_account, secret_key_str, receiver_id, actions, "FINAL")
     }
 
-    /// Sign and send a transaction with explicit signer
+    /// Sign and send a transaction with explicit signer, waiting for `wait_until`
     pub fn sign_and_send_tx_as(
         &self,
         signer_account: &str,
         secret_key_str: &str,
         receiver_id: &str,
         actions: Vec<Action>,
+        wait_until: &str,
     ) -> Result<String> {
         debug!("[RPC_PROXY] sign_and_send_tx_as called: signer={}, receiver={}", signer_account, receiver_id);
 
@@ -320,8 +460,7 @@ impl RpcProxy {
 
         info!("[RPC_PROXY] Sending transaction to RPC...");
 
-        // Send transaction and wait for finalization
-        let result = self.send_tx(&signed_tx_base64, Some("FINAL"))
+        let result = self.send_tx(&signed_tx_base64, Some(wait_until))
             .map_err(|e| {
                 eprintln!("[RPC_PROXY] Failed to send tx: {}", e);
                 e
@@ -376,18 +515,95 @@ impl RpcHostState {
     }
 }
 
-/// Helper to parse finality-or-block parameter
-fn parse_finality_or_block(s: &str) -> (Option<&str>, Option<Value>) {
-    if s.is_empty() || s == "final" {
-        (Some("final"), None)
-    } else if s == "optimistic" {
-        (Some("optimistic"), None)
-    } else if let Ok(height) = s.parse::<u64>() {
-        (None, Some(json!(height)))
-    } else {
-        // Assume it's a block hash
-        (None, Some(json!(s)))
+/// Finalities accepted wherever a `finality-or-block` argument is taken
+const FINALITIES: &[&str] = &["final", "optimistic", "near-final"];
+
+/// `wait-until` values of `send_tx`, `tx_status`, `call` and `transfer`
+const WAIT_UNTIL_VALUES: &[&str] = &["NONE", "INCLUDED", "EXECUTED_OPTIMISTIC", "INCLUDED_FINAL", "EXECUTED", "FINAL"];
+
+/// Block a query runs against
+#[derive(Debug, Clone, PartialEq)]
+enum BlockReference {
+    Finality(&'static str),
+    BlockId(Value),
+}
+
+impl BlockReference {
+    /// Parse a `finality-or-block` argument
+    ///
+    /// Accepts "final", "optimistic", "near-final", a block height or a base58 block
+    /// hash. The empty string means "final" (kept for guests written before validation).
+    fn parse(s: &str) -> Result<Self> {
+        if s.is_empty() {
+            return Ok(Self::Finality("final"));
+        }
+        if let Some(finality) = FINALITIES.iter().find(|f| **f == s) {
+            return Ok(Self::Finality(finality));
+        }
+        if let Ok(height) = s.parse::<u64>() {
+            return Ok(Self::BlockId(json!(height)));
+        }
+        if bs58::decode(s).into_vec().is_ok_and(|hash| hash.len() == 32) {
+            return Ok(Self::BlockId(json!(s)));
+        }
+        anyhow::bail!(
+            "Invalid finality or block '{}': expected \"final\", \"optimistic\", \"near-final\", a block height or a block hash",
+            s
+        )
+    }
+
+    /// Add `finality` or `block_id` to query params
+    fn apply(self, params: &mut Value) {
+        match self {
+            Self::Finality(finality) => params["finality"] = json!(finality),
+            Self::BlockId(block_id) => params["block_id"] = block_id,
+        }
+    }
+
+    /// Params of block-level methods: `{"finality": ..}` or `{"block_id": ..}`
+    fn params(self) -> Value {
+        let mut params = json!({});
+        self.apply(&mut params);
+        params
+    }
+}
+
+/// Validate a `wait-until` argument, `default` for the empty string
+fn parse_wait_until(s: &str, default: &'static str) -> Result<&'static str> {
+    if s.is_empty() {
+        return Ok(default);
+    }
+    match WAIT_UNTIL_VALUES.iter().find(|w| **w == s) {
+        Some(wait_until) => Ok(wait_until),
+        None => anyhow::bail!("Invalid wait_until '{}': expected one of {}", s, WAIT_UNTIL_VALUES.join(", ")),
+    }
+}
+
+/// Return bytes of a `call_function` query response
+///
+/// Handles both shapes of a failed call: a JSON-RPC `error` (current nodes) and a
+/// `result.error` string (older nodes).
+fn view_result_bytes(response: &Value) -> Result<Vec<u8>> {
+    if let Some(error) = response.get("error") {
+        let cause = error["cause"]["info"]["error_message"]
+            .as_str()
+            .or_else(|| error["data"].as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| error.to_string());
+        anyhow::bail!("{}", cause);
     }
+    let result = &response["result"];
+    if let Some(error) = result.get("error").and_then(Value::as_str) {
+        anyhow::bail!("{}", error);
+    }
+    let bytes = result["result"]
+        .as_array()
+        .context("response has no result bytes")?
+        .iter()
+        .map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()))
+        .collect::<Option<Vec<u8>>>()
+        .context("result bytes out of range")?;
+    Ok(bytes)
 }
 
 /// Implement the generated Host trait for RpcHostState
@@ -395,50 +611,35 @@ impl near::rpc::api::Host for RpcHostState {
     // ==================== Query Methods ====================
 
     fn view(&mut self, contract_id: String, method_name: String, args_json: String, finality_or_block: String) -> (String, String) {
-        let args_base64 = base64::engine::general_purpose::STANDARD.encode(args_json.as_bytes());
-        let (finality, block_id) = parse_finality_or_block(&finality_or_block);
-
-        let mut params = json!({
-            "request_type": "call_function",
-            "account_id": contract_id,
-            "method_name": method_name,
-            "args_base64": args_base64,
-        });
-        if let Some(fin) = finality {
-            params["finality"] = json!(fin);
-        } else if let Some(bid) = block_id {
-            params["block_id"] = bid;
-        }
-
-        match self.proxy.call_method("query", params) {
-            Ok(result) => {
-                if let Some(result_array) = result.get("result").and_then(|r| r.get("result")) {
-                    if let Some(arr) = result_array.as_array() {
-                        let bytes: Vec<u8> = arr
-                            .iter()
-                            .filter_map(|v| v.as_u64().map(|n| n as u8))
-                            .collect();
-                        return (String::from_utf8_lossy(&bytes).to_string(), String::new());
-                    }
-                }
-                (serde_json::to_string(&result).unwrap_or_default(), String::new())
-            }
+        match self.proxy.view_function(&contract_id, &method_name, &args_json, &finality_or_block) {
+            Ok(result) => (result, String::new()),
             Err(e) => (String::new(), e.to_string()),
         }
     }
 
+    fn batch_view(&mut self, calls: Vec<near::rpc::api::ViewCall>) -> Vec<near::rpc::api::ViewResult> {
+        debug!("[HOST] batch_view() invoked with {} calls", calls.len());
+        self.proxy
+            .view_functions(&calls)
+            .into_iter()
+            .map(|result| match result {
+                Ok(result) => near::rpc::api::ViewResult { result, error: None },
+                Err(e) => near::rpc::api::ViewResult { result: String::new(), error: Some(e.to_string()) },
+            })
+            .collect()
+    }
+
     fn view_account(&mut self, account_id: String, finality_or_block: String) -> (String, String) {
-        let (finality, block_id) = parse_finality_or_block(&finality_or_block);
+        let block = match BlockReference::parse(&finality_or_block) {
+            Ok(block) => block,
+            Err(e) => return (String::new(), e.to_string()),
+        };
 
         let mut params = json!({
             "request_type": "view_account",
             "account_id": account_id,
         });
-        if let Some(fin) = finality {
-            params["finality"] = json!(fin);
-        } else if let Some(bid) = block_id {
-            params["block_id"] = bid;
-        }
+        block.apply(&mut params);
 
         match self.proxy.call_method("query", params) {
             Ok(result) => (serde_json::to_string(&result).unwrap_or_default(), String::new()),
@@ -447,18 +648,17 @@ impl near::rpc::api::Host for RpcHostState {
     }
 
     fn view_access_key(&mut self, account_id: String, public_key: String, finality_or_block: String) -> (String, String) {
-        let (finality, block_id) = parse_finality_or_block(&finality_or_block);
+        let block = match BlockReference::parse(&finality_or_block) {
+            Ok(block) => block,
+            Err(e) => return (String::new(), e.to_string()),
+        };
 
         let mut params = json!({
             "request_type": "view_access_key",
             "account_id": account_id,
             "public_key": public_key,
         });
-        if let Some(fin) = finality {
-            params["finality"] = json!(fin);
-        } else if let Some(bid) = block_id {
-            params["block_id"] = bid;
-        }
+        block.apply(&mut params);
 
         match self.proxy.call_method("query", params) {
             Ok(result) => (serde_json::to_string(&result).unwrap_or_default(), String::new()),
@@ -467,17 +667,16 @@ impl near::rpc::api::Host for RpcHostState {
     }
 
     fn view_access_key_list(&mut self, account_id: String, finality_or_block: String) -> (String, String) {
-        let (finality, block_id) = parse_finality_or_block(&finality_or_block);
+        let block = match BlockReference::parse(&finality_or_block) {
+            Ok(block) => block,
+            Err(e) => return (String::new(), e.to_string()),
+        };
 
         let mut params = json!({
             "request_type": "view_access_key_list",
             "account_id": account_id,
         });
-        if let Some(fin) = finality {
-            params["finality"] = json!(fin);
-        } else if let Some(bid) = block_id {
-            params["block_id"] = bid;
-        }
+        block.apply(&mut params);
 
         match self.proxy.call_method("query", params) {
             Ok(result) => (serde_json::to_string(&result).unwrap_or_default(), String::new()),
@@ -486,17 +685,16 @@ impl near::rpc::api::Host for RpcHostState {
     }
 
     fn view_code(&mut self, account_id: String, finality_or_block: String) -> (String, String) {
-        let (finality, block_id) = parse_finality_or_block(&finality_or_block);
+        let block = match BlockReference::parse(&finality_or_block) {
+            Ok(block) => block,
+            Err(e) => return (String::new(), e.to_string()),
+        };
 
         let mut params = json!({
             "request_type": "view_code",
             "account_id": account_id,
         });
-        if let Some(fin) = finality {
-            params["finality"] = json!(fin);
-        } else if let Some(bid) = block_id {
-            params["block_id"] = bid;
-        }
+        block.apply(&mut params);
 
         match self.proxy.call_method("query", params) {
             Ok(result) => (serde_json::to_string(&result).unwrap_or_default(), String::new()),
@@ -505,18 +703,17 @@ impl near::rpc::api::Host for RpcHostState {
     }
 
     fn view_state(&mut self, account_id: String, prefix_base64: String, finality_or_block: String) -> (String, String) {
-        let (finality, block_id) = parse_finality_or_block(&finality_or_block);
+        let block = match BlockReference::parse(&finality_or_block) {
+            Ok(block) => block,
+            Err(e) => return (String::new(), e.to_string()),
+        };
 
         let mut params = json!({
             "request_type": "view_state",
             "account_id": account_id,
             "prefix_base64": prefix_base64,
         });
-        if let Some(fin) = finality {
-            params["finality"] = json!(fin);
-        } else if let Some(bid) = block_id {
-            params["block_id"] = bid;
-        }
+        block.apply(&mut params);
 
         match self.proxy.call_method("query", params) {
             Ok(result) => (serde_json::to_string(&result).unwrap_or_default(), String::new()),
@@ -524,17 +721,28 @@ impl near::rpc::api::Host for RpcHostState {
         }
     }
 
+    // ==================== Token Methods (NEP-141) ====================
+
+    fn ft_balance_of(&mut self, token_id: String, account_id: String, finality_or_block: String) -> (String, String) {
+        match self.proxy.ft_balance_of(&token_id, &account_id, &finality_or_block) {
+            Ok(balance) => (balance, String::new()),
+            Err(e) => (String::new(), format!("{:#}", e)),
+        }
+    }
+
+    fn ft_metadata(&mut self, token_id: String, finality_or_block: String) -> (String, String) {
+        match self.proxy.ft_metadata(&token_id, &finality_or_block) {
+            Ok(metadata) => (metadata.to_string(), String::new()),
+            Err(e) => (String::new(), format!("{:#}", e)),
+        }
+    }
+
     // ==================== Block Methods ====================
 
     fn block(&mut self, finality_or_block: String) -> (String, String) {
-        let (finality, block_id) = parse_finality_or_block(&finality_or_block);
-
-        let params = if let Some(fin) = finality {
-            json!({ "finality": fin })
-        } else if let Some(bid) = block_id {
-            json!({ "block_id": bid })
-        } else {
-            json!({ "finality": "final" })
+        let params = match BlockReference::parse(&finality_or_block) {
+            Ok(block) => block.params(),
+            Err(e) => return (String::new(), e.to_string()),
         };
 
         match self.proxy.call_method("block", params) {
@@ -550,8 +758,16 @@ impl near::rpc::api::Host for RpcHostState {
             if parts.len() != 2 {
                 return (String::new(), "Invalid format. Use 'block_id,shard_id' or 'chunk_id'".to_string());
             }
-            let block_id = parts[0].parse::<u64>().unwrap_or(0);
-            let shard_id = parts[1].parse::<u64>().unwrap_or(0);
+            let block_id = match BlockReference::parse(parts[0]) {
+                Ok(BlockReference::BlockId(id)) => id,
+                Ok(BlockReference::Finality(_)) | Err(_) => {
+                    return (String::new(), format!("Invalid block '{}': expected a block height or hash", parts[0]));
+                }
+            };
+            let shard_id = match parts[1].parse::<u64>() {
+                Ok(shard_id) => shard_id,
+                Err(_) => return (String::new(), format!("Invalid shard id '{}'", parts[1])),
+            };
             json!({
                 "block_id": block_id,
                 "shard_id": shard_id
@@ -567,14 +783,9 @@ impl near::rpc::api::Host for RpcHostState {
     }
 
     fn changes(&mut self, finality_or_block: String) -> (String, String) {
-        let (finality, block_id) = parse_finality_or_block(&finality_or_block);
-
-        let params = if let Some(fin) = finality {
-            json!({ "finality": fin })
-        } else if let Some(bid) = block_id {
-            json!({ "block_id": bid })
-        } else {
-            json!({ "finality": "final" })
+        let params = match BlockReference::parse(&finality_or_block) {
+            Ok(block) => block.params(),
+            Err(e) => return (String::new(), e.to_string()),
         };
 
         match self.proxy.call_method("EXPERIMENTAL_changes", params) {
@@ -586,28 +797,28 @@ impl near::rpc::api::Host for RpcHostState {
     // ==================== Transaction Methods ====================
 
     fn send_tx(&mut self, signed_tx_base64: String, wait_until: String) -> (String, String) {
-        let wait = if wait_until.is_empty() {
-            Some("EXECUTED_OPTIMISTIC")
-        } else {
-            Some(wait_until.as_str())
+        let wait = match parse_wait_until(&wait_until, "EXECUTED_OPTIMISTIC") {
+            Ok(wait) => wait,
+            Err(e) => return (String::new(), e.to_string()),
         };
 
-        match self.proxy.send_tx(&signed_tx_base64, wait) {
+        match self.proxy.send_tx(&signed_tx_base64, Some(wait)) {
             Ok(result) => (serde_json::to_string(&result).unwrap_or_default(), String::new()),
             Err(e) => (String::new(), e.to_string()),
         }
     }
 
     fn tx_status(&mut self, tx_hash: String, sender_account_id: String, wait_until: String) -> (String, String) {
-        let mut params = json!({
+        let wait = match parse_wait_until(&wait_until, "EXECUTED_OPTIMISTIC") {
+            Ok(wait) => wait,
+            Err(e) => return (String::new(), e.to_string()),
+        };
+        let params = json!({
             "tx_hash": tx_hash,
-            "sender_account_id": sender_account_id
+            "sender_account_id": sender_account_id,
+            "wait_until": wait,
         });
 
-        if !wait_until.is_empty() {
-            params["wait_until"] = json!(wait_until);
-        }
-
         match self.proxy.call_method("EXPERIMENTAL_tx_status", params) {
             Ok(result) => (serde_json::to_string(&result).unwrap_or_default(), String::new()),
             Err(e) => (String::new(), e.to_string()),
@@ -637,9 +848,12 @@ impl near::rpc::api::Host for RpcHostState {
         gas: String,
         wait_until: String,       // NEW: wait until finality
     ) -> (String, String) {
+        let wait = match parse_wait_until(&wait_until, "FINAL") {
+            Ok(wait) => wait,
+            Err(e) => return (String::new(), e.to_string()),
+        };
         debug!("[HOST] call() invoked: signer={}, receiver={}, method={}, deposit={}, gas={}, wait={}",
-            signer_id, receiver_id, method_name, deposit_yocto, gas,
-            if wait_until.is_empty() { "FINAL" } else { &wait_until });
+            signer_id, receiver_id, method_name, deposit_yocto, gas, wait);
 
         let deposit: u128 = match deposit_yocto.parse() {
             Ok(d) => d,
@@ -668,9 +882,7 @@ impl near::rpc::api::Host for RpcHostState {
 
         debug!("[HOST] Calling sign_and_send_tx_as...");
 
-        // Note: wait_until is ignored for now - sign_and_send_tx_as always waits for FINAL
-        // TODO: Add wait_until parameter to sign_and_send_tx_as
-        match self.proxy.sign_and_send_tx_as(&signer_id, &signer_key, &receiver_id, vec![action]) {
+        match self.proxy.sign_and_send_tx_as(&signer_id, &signer_key, &receiver_id, vec![action], wait) {
             Ok(tx_hash) => {
                 info!("[HOST] Transaction successful: {}", tx_hash);
                 (tx_hash, String::new())
@@ -693,9 +905,12 @@ impl near::rpc::api::Host for RpcHostState {
         amount_yocto: String,
         wait_until: String,       // NEW: wait until finality
     ) -> (String, String) {
+        let wait = match parse_wait_until(&wait_until, "FINAL") {
+            Ok(wait) => wait,
+            Err(e) => return (String::new(), e.to_string()),
+        };
         debug!("[HOST] transfer() invoked: signer={}, receiver={}, amount={}, wait={}",
-            signer_id, receiver_id, amount_yocto,
-            if wait_until.is_empty() { "FINAL" } else { &wait_until });
+            signer_id, receiver_id, amount_yocto, wait);
 
         let amount: u128 = match amount_yocto.parse() {
             Ok(a) => a,
@@ -704,9 +919,7 @@ impl near::rpc::api::Host for RpcHostState {
 
         let action = Action::Transfer(TransferAction { deposit: Balance::from_yoctonear(amount) });
 
-        // Note: wait_until is ignored for now - sign_and_send_tx_as always waits for FINAL
-        // TODO: Add wait_until parameter to sign_and_send_tx_as
-        match self.proxy.sign_and_send_tx_as(&signer_id, &signer_key, &receiver_id, vec![action]) {
+        match self.proxy.sign_and_send_tx_as(&signer_id, &signer_key, &receiver_id, vec![action], wait) {
             Ok(tx_hash) => (tx_hash, String::new()),
             Err(e) => (String::new(), e.to_string()),
         }
@@ -715,12 +928,11 @@ impl near::rpc::api::Host for RpcHostState {
     // ==================== Network Methods ====================
 
     fn gas_price(&mut self, block_id: String) -> (String, String) {
-        let params = if block_id.is_empty() {
-            json!([null])
-        } else if let Ok(height) = block_id.parse::<u64>() {
-            json!([height])
-        } else {
-            json!([block_id])
+        // A finality (or empty) means the latest block
+        let params = match BlockReference::parse(&block_id) {
+            Ok(BlockReference::Finality(_)) => json!([null]),
+            Ok(BlockReference::BlockId(id)) => json!([id]),
+            Err(e) => return (String::new(), e.to_string()),
         };
 
         match self.proxy.call_method("gas_price", params) {
@@ -780,3 +992,207 @@ pub fn add_rpc_to_linker<T: Send + 'static>(
 ) -> anyhow::Result<()> {
     near::rpc::api::add_to_linker(linker, get_state)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near::rpc::api::{Host, ViewCall};
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    /// Minimal JSON-RPC node: `call_function` returns the method name as the result
+    /// bytes, except method `broken`, which gets HTTP 500. Returns the URL and a counter
+    /// of requests served.
+    fn spawn_mock_rpc() -> (String, Arc<AtomicU32>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicU32::new(0));
+        let served = requests.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                served.fetch_add(1, Ordering::SeqCst);
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" {
+                        break;
+                    }
+                    if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0u8; content_length];
+                reader.read_exact(&mut body).unwrap();
+                let request: Value = serde_json::from_slice(&body).unwrap();
+                let method = request["params"]["method_name"].as_str().unwrap_or_default();
+
+                // token.testnet answers NEP-141 views with JSON, other contracts echo the method
+                let payload = match (request["params"]["account_id"].as_str(), method) {
+                    (Some("token.testnet"), "ft_balance_of") => json!("1000000").to_string(),
+                    (Some("token.testnet"), "ft_metadata") => json!({ "symbol": "TKN", "decimals": 6 }).to_string(),
+                    _ => method.to_string(),
+                };
+
+                let (status, response) = if method == "broken" {
+                    ("500 Internal Server Error", "node unavailable".to_string())
+                } else {
+                    let bytes: Vec<u8> = payload.bytes().collect();
+                    ("200 OK", json!({ "jsonrpc": "2.0", "id": "proxy", "result": { "result": bytes } }).to_string())
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    response.len(),
+                    response
+                )
+                .unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    fn view_call(method_name: &str) -> ViewCall {
+        ViewCall {
+            contract_id: "prices.testnet".to_string(),
+            method_name: method_name.to_string(),
+            args_json: "{}".to_string(),
+            finality: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_batch_view_keeps_input_order() {
+        let (url, _) = spawn_mock_rpc();
+        let mut state = RpcHostState::new(RpcProxy::new(&url, 100, false, None).unwrap());
+
+        let methods: Vec<String> = (0..20).map(|i| format!("price_{}", i)).collect();
+        let calls = methods.iter().map(|m| view_call(m)).collect();
+
+        let results = state.batch_view(calls);
+        assert_eq!(results.len(), 20);
+        for (result, method) in results.iter().zip(&methods) {
+            assert_eq!(&result.result, method);
+            assert_eq!(result.error, None);
+        }
+        assert_eq!(state.proxy.get_call_count(), 20);
+    }
+
+    #[test]
+    fn test_batch_view_errors_are_per_call() {
+        let (url, _) = spawn_mock_rpc();
+        let mut state = RpcHostState::new(RpcProxy::new(&url, 100, false, None).unwrap());
+
+        let results = state.batch_view(vec![view_call("ft_balance_of"), view_call("broken"), view_call("ft_metadata")]);
+        assert_eq!(results[0].result, "ft_balance_of");
+        assert!(results[1].error.as_deref().unwrap().contains("500"));
+        assert_eq!(results[2].result, "ft_metadata");
+        assert_eq!(results[2].error, None);
+    }
+
+    #[test]
+    fn test_batch_view_respects_call_limit() {
+        let (url, _) = spawn_mock_rpc();
+        let mut state = RpcHostState::new(RpcProxy::new(&url, 2, false, None).unwrap());
+
+        let results = state.batch_view(vec![view_call("a"), view_call("b"), view_call("c")]);
+        let limited = results
+            .iter()
+            .filter(|r| r.error.as_deref().is_some_and(|e| e.contains("rate limit")))
+            .count();
+        assert_eq!(limited, 1);
+    }
+
+    #[test]
+    fn test_block_pinned_views_are_served_from_cache() {
+        let (url, requests) = spawn_mock_rpc();
+        let cache = Arc::new(RpcCache::new(1, Duration::from_secs(60)));
+        let mut state = RpcHostState::new(RpcProxy::new(&url, 100, false, None).unwrap().with_cache(Some(cache.clone())));
+
+        for _ in 0..3 {
+            let (result, error) = state.view("prices.testnet".into(), "ft_metadata".into(), "{}".into(), "180000000".into());
+            assert_eq!((result.as_str(), error.as_str()), ("ft_metadata", ""));
+        }
+        // Final block moves: never cached
+        state.view("prices.testnet".into(), "ft_metadata".into(), "{}".into(), "final".into());
+        state.view("prices.testnet".into(), "ft_metadata".into(), "{}".into(), "final".into());
+
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+        assert_eq!((cache.stats().hits, cache.stats().misses), (2, 1));
+        // Hits still count against the per-execution limit
+        assert_eq!(state.proxy.get_call_count(), 5);
+    }
+
+    #[test]
+    fn test_block_reference_parsing() {
+        let hash = "9BfCJBnQqGvJBZs8K3cS4DkbhJk4UXcXg7mTm3rz7xnb";
+        assert_eq!(BlockReference::parse("").unwrap(), BlockReference::Finality("final"));
+        assert_eq!(BlockReference::parse("final").unwrap(), BlockReference::Finality("final"));
+        assert_eq!(BlockReference::parse("optimistic").unwrap(), BlockReference::Finality("optimistic"));
+        assert_eq!(BlockReference::parse("near-final").unwrap(), BlockReference::Finality("near-final"));
+        assert_eq!(BlockReference::parse("12345").unwrap(), BlockReference::BlockId(json!(12345)));
+        assert_eq!(BlockReference::parse(hash).unwrap(), BlockReference::BlockId(json!(hash)));
+
+        for invalid in ["Final", "latest", "-1", "abc", "0x1234"] {
+            let err = BlockReference::parse(invalid).unwrap_err().to_string();
+            assert!(err.contains(invalid), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_wait_until_validation() {
+        assert_eq!(parse_wait_until("", "FINAL").unwrap(), "FINAL");
+        assert_eq!(parse_wait_until("INCLUDED_FINAL", "FINAL").unwrap(), "INCLUDED_FINAL");
+        assert!(parse_wait_until("final", "FINAL").is_err());
+    }
+
+    #[test]
+    fn test_unknown_finality_is_rejected_without_rpc_call() {
+        let (url, requests) = spawn_mock_rpc();
+        let mut state = RpcHostState::new(RpcProxy::new(&url, 100, false, None).unwrap());
+
+        let (result, error) = state.view("prices.testnet".into(), "ft_metadata".into(), "{}".into(), "latest".into());
+        assert!(result.is_empty());
+        assert!(error.contains("Invalid finality or block 'latest'"), "{}", error);
+        let (_, error) = state.view_account("prices.testnet".into(), "finalized".into());
+        assert!(!error.is_empty());
+
+        assert_eq!(requests.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_ft_views_return_parsed_results() {
+        let (url, _) = spawn_mock_rpc();
+        let mut state = RpcHostState::new(RpcProxy::new(&url, 100, false, None).unwrap());
+
+        let (balance, error) = state.ft_balance_of("token.testnet".into(), "alice.testnet".into(), String::new());
+        assert_eq!((balance.as_str(), error.as_str()), ("1000000", ""));
+
+        let (metadata, error) = state.ft_metadata("token.testnet".into(), "final".into());
+        assert!(error.is_empty(), "{}", error);
+        let metadata: Value = serde_json::from_str(&metadata).unwrap();
+        assert_eq!(metadata["decimals"], 6);
+
+        // A contract whose result isn't the expected JSON is an error, not a raw string
+        let (balance, error) = state.ft_balance_of("other.testnet".into(), "alice.testnet".into(), String::new());
+        assert!(balance.is_empty());
+        assert!(error.contains("other.testnet.ft_balance_of"), "{}", error);
+    }
+
+    #[test]
+    fn test_view_result_bytes_reports_contract_errors() {
+        let ok = json!({ "result": { "result": [34, 49, 34], "logs": [] } });
+        assert_eq!(view_result_bytes(&ok).unwrap(), b"\"1\"");
+
+        let legacy = json!({ "result": { "error": "wasm execution failed with error: MethodNotFound" } });
+        assert!(view_result_bytes(&legacy).unwrap_err().to_string().contains("MethodNotFound"));
+
+        let handler_error = json!({
+            "error": { "name": "HANDLER_ERROR", "cause": { "name": "UNKNOWN_ACCOUNT", "info": {} }, "data": "account nope.testnet does not exist" }
+        });
+        assert!(view_result_bytes(&handler_error).unwrap_err().to_string().contains("nope.testnet"));
+    }
+}
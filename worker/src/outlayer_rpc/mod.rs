@@ -26,10 +26,12 @@
 //! - Transaction methods can be disabled via config
 //! - WASM must provide its own signing keys for transactions
 
+pub mod cache;
 pub mod methods;
 //pub mod host_functions;  // Old async implementation
 pub mod host_functions_sync;  // Working sync implementation
 
+pub use cache::{RpcCache, RpcCacheStats};
 pub use host_functions_sync::{add_rpc_to_linker, RpcHostState};
 
 use anyhow::{Context, Result};
@@ -39,6 +41,7 @@ use std::time::Duration;
 use tracing::{debug, warn};
 
 use crate::config::RpcProxyConfig;
+use crate::metrics::{metrics, RpcStatus};
 
 /// RPC Proxy client with rate limiting
 pub struct RpcProxy {
@@ -50,6 +53,8 @@ pub struct RpcProxy {
     config: RpcProxyConfig,
     /// Call counter for rate limiting (per execution)
     call_count: Arc<AtomicU32>,
+    /// Cache for block-pinned results, shared by all executions
+    cache: Option<Arc<RpcCache>>,
 }
 
 impl RpcProxy {
@@ -72,9 +77,21 @@ impl RpcProxy {
             rpc_url,
             config,
             call_count: Arc::new(AtomicU32::new(0)),
+            cache: None,
         })
     }
 
+    /// Cache block-pinned results in `cache`
+    pub fn with_cache(mut self, cache: Arc<RpcCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Result cache, if enabled
+    pub fn cache(&self) -> Option<Arc<RpcCache>> {
+        self.cache.clone()
+    }
+
     /// Reset call counter (call at start of each WASM execution)
     #[allow(dead_code)]
     pub fn reset_call_count(&self) {
@@ -162,6 +179,16 @@ impl RpcProxy {
             );
         }
 
+        let cache_key = self.cache.as_ref().and_then(|_| cache::cache_key(method, &params));
+        if let (Some(cache), Some(key)) = (&self.cache, &cache_key) {
+            if let Some(response) = cache.get(key) {
+                self.check_rate_limit()?;
+                debug!("RPC cache hit: {}", key);
+                metrics().record_rpc_request(method, RpcStatus::Cached);
+                return Ok(response);
+            }
+        }
+
         let request = serde_json::json!({
             "jsonrpc": "2.0",
             "id": "proxy",
@@ -170,10 +197,20 @@ impl RpcProxy {
         });
 
         let request_body = serde_json::to_string(&request)?;
-        let response_body = self.send_rpc_request(&request_body).await?;
+        let response_body = self.send_rpc_request(&request_body).await;
+        let response = response_body.and_then(|body| {
+            serde_json::from_str::<serde_json::Value>(&body).context("Failed to parse RPC response as JSON")
+        });
+        let status = match &response {
+            Ok(response) if response.get("error").is_none() => RpcStatus::Ok,
+            _ => RpcStatus::Error,
+        };
+        metrics().record_rpc_request(method, status);
+        let response = response?;
 
-        let response: serde_json::Value = serde_json::from_str(&response_body)
-            .context("Failed to parse RPC response as JSON")?;
+        if let (Some(cache), Some(key)) = (&self.cache, cache_key) {
+            cache.put(key, &response);
+        }
 
         // Check for RPC error
         if let Some(error) = response.get("error") {
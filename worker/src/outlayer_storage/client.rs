@@ -12,6 +12,55 @@ use sha2::{Digest, Sha256};
 use std::time::Duration;
 use tracing::{debug, error, warn};
 
+/// Maximum `limit` accepted by `list_keys_paged`
+pub const MAX_LIST_PAGE_SIZE: u32 = 1000;
+
+/// Maximum number of entries in a single `get_many` / `set_many` batch
+///
+/// `MAX-BATCH-SIZE` in wit/deps/storage.wit; the SDK has the same constant.
+pub const MAX_BATCH_SIZE: usize = 100;
+
+/// Coordinator reads `get_many` keeps in flight at once
+const MAX_BATCH_GET_CONCURRENCY: usize = 8;
+
+/// Live snapshots kept per account; taking another one evicts the oldest
+pub const MAX_LIVE_SNAPSHOTS: usize = 8;
+
+/// Maximum number of keys captured by one snapshot
+pub const MAX_SNAPSHOT_KEYS: usize = 1000;
+
+/// Maximum total size (keys plus values) captured by one snapshot
+///
+/// A snapshot is stored as a single value, so it is bounded like one.
+pub const MAX_SNAPSHOT_BYTES: usize = 4 * 1024 * 1024;
+
+/// Snapshots expire this long after they were taken
+pub const SNAPSHOT_TTL_SECONDS: u64 = 3600;
+
+/// Largest value `append` may produce; an append that would exceed it fails without writing
+pub const MAX_APPEND_VALUE_SIZE: usize = 1024 * 1024;
+
+/// Reserved key prefix for snapshot data; never captured, restored or listed
+const SNAPSHOT_KEY_PREFIX: &str = "__outlayer_snapshot:";
+
+/// The coordinator doesn't serve a storage route this worker calls
+///
+/// Raised when the route answers 404/405, so a guest gets a clear error
+/// instead of a generic HTTP failure or an empty result.
+#[derive(Debug, thiserror::Error)]
+#[error("{route} is not supported by this coordinator")]
+pub struct UnsupportedByCoordinator {
+    pub route: &'static str,
+}
+
+/// Atomic writes were requested on a worker without STORAGE_TRANSACTIONS_ENABLED
+///
+/// The message is part of the `set-many` contract in wit/deps/storage.wit; the
+/// SDK matches on it to report `BatchError::Unsupported`.
+#[derive(Debug, thiserror::Error)]
+#[error("storage transactions are not enabled on this worker")]
+pub struct TransactionsDisabled;
+
 /// Storage client configuration
 #[derive(Clone)]
 pub struct StorageConfig {
@@ -33,6 +82,10 @@ pub struct StorageConfig {
     pub tee_mode: String,
     /// Keystore TEE session ID (set after challenge-response registration)
     pub keystore_tee_session_id: Option<String>,
+    /// Whether txn-* host functions may be used (coordinator supports /storage/transaction)
+    pub transactions_enabled: bool,
+    /// Whether set-with-ttl may be used (coordinator stores and honours `expires_at`)
+    pub ttl_enabled: bool,
 }
 
 /// Attestation for keystore requests
@@ -186,29 +239,75 @@ impl StorageClient {
 
     /// Set a storage key-value pair
     pub fn set(&self, key: &str, value: &[u8]) -> Result<()> {
+        check_user_key(key)?;
         self.set_for_account(key, value, &self.config.account_id)
     }
 
     /// Set a storage key-value pair for a specific account
     pub fn set_for_account(&self, key: &str, value: &[u8], account_id: &str) -> Result<()> {
+        self.set_for_account_with_expiry(key, value, account_id, None)
+    }
+
+    /// Set a storage key-value pair that expires after `ttl_seconds`
+    pub fn set_with_ttl(&self, key: &str, value: &[u8], ttl_seconds: u64) -> Result<()> {
+        check_user_key(key)?;
+        self.check_ttl_enabled()?;
+        let expires_at = expiry_after(ttl_seconds, unix_now())?;
+        self.set_for_account_with_expiry(key, value, &self.config.account_id, Some(expires_at))
+    }
+
+    /// Set encrypted worker storage that expires after `ttl_seconds`
+    pub fn set_worker_with_ttl(&self, key: &str, value: &[u8], ttl_seconds: u64) -> Result<()> {
+        self.check_ttl_enabled()?;
+        let expires_at = expiry_after(ttl_seconds, unix_now())?;
+        self.set_for_account_with_expiry(key, value, "@worker", Some(expires_at))
+    }
+
+    /// Reject TTL writes unless the coordinator is known to store `expires_at` (STORAGE_TTL_ENABLED)
+    ///
+    /// A coordinator that ignores the field would keep the value forever while
+    /// the guest believes it expires.
+    fn check_ttl_enabled(&self) -> Result<()> {
+        if !self.config.ttl_enabled {
+            anyhow::bail!("storage TTL is not enabled on this worker");
+        }
+        Ok(())
+    }
+
+    /// Set a storage key-value pair, optionally expiring at `expires_at` (unix seconds)
+    fn set_for_account_with_expiry(
+        &self,
+        key: &str,
+        value: &[u8],
+        account_id: &str,
+        expires_at: Option<u64>,
+    ) -> Result<()> {
         // Encrypt via keystore
         let encrypted = self.encrypt_via_keystore(key, value, account_id)?;
 
         debug!(
-            "storage_set: key_hash={}, account={}, value_size={}",
+            "storage_set: key_hash={}, account={}, value_size={}, expires_at={:?}",
             encrypted.key_hash,
             account_id,
-            value.len()
+            value.len(),
+            expires_at
         );
 
-        // Store in coordinator
+        self.store_encrypted(&encrypted, account_id, expires_at)
+    }
+
+    /// Store already-encrypted data in coordinator
+    ///
+    /// `expires_at: None` clears any expiry left by a previous TTL write.
+    fn store_encrypted(&self, encrypted: &EncryptedData, account_id: &str, expires_at: Option<u64>) -> Result<()> {
         let body = serde_json::json!({
             "project_uuid": &self.config.project_uuid,
             "wasm_hash": self.config.wasm_hash,
             "account_id": account_id,
-            "key_hash": encrypted.key_hash,
-            "encrypted_key": encrypted.encrypted_key,
-            "encrypted_value": encrypted.encrypted_value,
+            "key_hash": &encrypted.key_hash,
+            "encrypted_key": &encrypted.encrypted_key,
+            "encrypted_value": &encrypted.encrypted_value,
+            "expires_at": expires_at,
         });
 
         let response = self
@@ -268,6 +367,8 @@ impl StorageClient {
             exists: bool,
             encrypted_key: Option<Vec<u8>>,
             encrypted_value: Option<Vec<u8>>,
+            #[serde(default)]
+            expires_at: Option<u64>,
         }
 
         let resp: GetResponse = response.json().context("Failed to parse storage get response")?;
@@ -276,6 +377,11 @@ impl StorageClient {
             return Ok(None);
         }
 
+        if is_expired(resp.expires_at, unix_now()) {
+            self.reclaim_expired(&key_hash, account_id);
+            return Ok(None);
+        }
+
         match (resp.encrypted_key, resp.encrypted_value) {
             (Some(enc_key), Some(enc_value)) => {
                 // Decrypt via keystore
@@ -286,6 +392,124 @@ impl StorageClient {
         }
     }
 
+    /// Get several storage values, preserving input order (`None` for missing keys)
+    ///
+    /// The coordinator has no batch read, so keys are fetched concurrently, at most
+    /// `MAX_BATCH_GET_CONCURRENCY` at a time. The first failed read fails the batch.
+    pub fn get_many(&self, keys: &[String]) -> Result<Vec<Option<Vec<u8>>>> {
+        check_batch_size(keys.len())?;
+        debug!("storage_get_many: keys={}", keys.len());
+
+        let mut values = Vec::with_capacity(keys.len());
+        for chunk in keys.chunks(MAX_BATCH_GET_CONCURRENCY) {
+            std::thread::scope(|scope| -> Result<()> {
+                let handles: Vec<_> = chunk.iter().map(|key| scope.spawn(move || self.get(key))).collect();
+                for handle in handles {
+                    let value = handle
+                        .join()
+                        .unwrap_or_else(|_| Err(anyhow::anyhow!("storage read panicked")))?;
+                    values.push(value);
+                }
+                Ok(())
+            })?;
+        }
+        Ok(values)
+    }
+
+    /// Set several storage key-value pairs atomically
+    ///
+    /// Committed through [`Self::commit_transaction`], so either every pair lands
+    /// or none does; a key listed twice keeps its last value. Needs
+    /// STORAGE_TRANSACTIONS_ENABLED and fails with [`TransactionsDisabled`] otherwise.
+    pub fn set_many(&self, entries: &[(String, Vec<u8>)]) -> Result<()> {
+        check_batch_size(entries.len())?;
+        if !self.config.transactions_enabled {
+            return Err(TransactionsDisabled.into());
+        }
+
+        let writes: std::collections::BTreeMap<_, _> = entries
+            .iter()
+            .map(|(key, value)| (key.clone(), Some(value.clone())))
+            .collect();
+        let writes: Vec<_> = writes.into_iter().collect();
+
+        debug!(
+            "storage_set_many: entries={}, keys={}, account={}",
+            entries.len(),
+            writes.len(),
+            self.config.account_id
+        );
+        self.commit_transaction(&writes)
+    }
+
+    /// Whether WASM may open storage transactions (STORAGE_TRANSACTIONS_ENABLED)
+    pub fn transactions_enabled(&self) -> bool {
+        self.config.transactions_enabled
+    }
+
+    /// Apply a transaction's buffered writes (`None` = delete) in one coordinator call
+    ///
+    /// Every value is encrypted before the request is sent, and the coordinator applies
+    /// the whole set in a single database transaction: either every write lands or none
+    /// does. Written keys have no TTL.
+    pub fn commit_transaction(&self, writes: &[(String, Option<Vec<u8>>)]) -> Result<()> {
+        check_batch_size(writes.len())?;
+        writes.iter().try_for_each(|(key, _)| check_user_key(key))?;
+        if writes.is_empty() {
+            return Ok(());
+        }
+        let account_id = &self.config.account_id;
+
+        let mut sets = Vec::new();
+        let mut deletes = Vec::new();
+        for (key, value) in writes {
+            match value {
+                Some(value) => {
+                    let encrypted = self.encrypt_via_keystore(key, value, account_id)?;
+                    sets.push(serde_json::json!({
+                        "key_hash": encrypted.key_hash,
+                        "encrypted_key": encrypted.encrypted_key,
+                        "encrypted_value": encrypted.encrypted_value,
+                    }));
+                }
+                None => deletes.push(self.hash_key(key)),
+            }
+        }
+
+        debug!(
+            "storage_commit_transaction: sets={}, deletes={}, account={}",
+            sets.len(),
+            deletes.len(),
+            account_id
+        );
+
+        let body = serde_json::json!({
+            "project_uuid": &self.config.project_uuid,
+            "wasm_hash": self.config.wasm_hash,
+            "account_id": account_id,
+            "sets": sets,
+            "deletes": deletes,
+        });
+
+        let response = self
+            .client
+            .post(format!("{}/storage/transaction", self.config.coordinator_url))
+            .header("Authorization", format!("Bearer {}", self.config.coordinator_token))
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .context("Failed to send storage transaction request")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().unwrap_or_default();
+            error!("Storage transaction failed: {} - {}", status, error_text);
+            anyhow::bail!("Storage transaction failed: {} - {}", status, error_text);
+        }
+
+        Ok(())
+    }
+
     /// Check if a key exists
     pub fn has(&self, key: &str) -> Result<bool> {
         let key_hash = self.hash_key(key);
@@ -312,19 +536,25 @@ impl StorageClient {
         #[derive(Deserialize)]
         struct HasResponse {
             exists: bool,
+            #[serde(default)]
+            expires_at: Option<u64>,
         }
 
-        let resp: HasResponse = response.json().unwrap_or(HasResponse { exists: false });
-        Ok(resp.exists)
+        let resp: HasResponse = response.json().unwrap_or(HasResponse { exists: false, expires_at: None });
+        Ok(resp.exists && !is_expired(resp.expires_at, unix_now()))
     }
 
     /// Delete a key
     pub fn delete(&self, key: &str) -> Result<bool> {
-        let key_hash = self.hash_key(key);
+        check_user_key(key)?;
+        self.delete_by_hash(&self.hash_key(key), &self.config.account_id)
+    }
 
+    /// Delete an entry by key hash for a specific account
+    fn delete_by_hash(&self, key_hash: &str, account_id: &str) -> Result<bool> {
         let body = serde_json::json!({
             "project_uuid": &self.config.project_uuid,
-            "account_id": self.config.account_id,
+            "account_id": account_id,
             "key_hash": key_hash,
         });
 
@@ -340,8 +570,328 @@ impl StorageClient {
         Ok(response.status().is_success())
     }
 
+    /// Lazily delete an entry found expired on read (failures are retried on the next read)
+    fn reclaim_expired(&self, key_hash: &str, account_id: &str) {
+        debug!("storage: reclaiming expired key_hash={}, account={}", key_hash, account_id);
+        if let Err(e) = self.delete_by_hash(key_hash, account_id) {
+            warn!("Failed to reclaim expired storage entry {}: {}", key_hash, e);
+        }
+    }
+
+    /// Remaining lifetime of a key in seconds
+    pub fn ttl(&self, key: &str) -> Result<Option<u64>> {
+        self.ttl_for_account(key, &self.config.account_id)
+    }
+
+    /// Remaining lifetime of an encrypted worker storage key in seconds
+    pub fn ttl_worker(&self, key: &str) -> Result<Option<u64>> {
+        self.ttl_for_account(key, "@worker")
+    }
+
+    /// Remaining lifetime of a key for a specific account
+    ///
+    /// `None` if the key doesn't exist, has no TTL, or has already expired.
+    fn ttl_for_account(&self, key: &str, account_id: &str) -> Result<Option<u64>> {
+        let key_hash = self.hash_key(key);
+
+        let body = serde_json::json!({
+            "project_uuid": &self.config.project_uuid,
+            "account_id": account_id,
+            "key_hash": key_hash,
+        });
+
+        let response = self
+            .client
+            .post(format!("{}/storage/get", self.config.coordinator_url))
+            .header("Authorization", format!("Bearer {}", self.config.coordinator_token))
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .context("Failed to send storage get request for ttl")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().unwrap_or_default();
+            anyhow::bail!("Storage get failed: {} - {}", status, error_text);
+        }
+
+        #[derive(Deserialize)]
+        struct TtlResponse {
+            exists: bool,
+            #[serde(default)]
+            expires_at: Option<u64>,
+        }
+
+        let resp: TtlResponse = response.json().context("Failed to parse storage get response")?;
+
+        if !resp.exists {
+            return Ok(None);
+        }
+
+        let now = unix_now();
+        if is_expired(resp.expires_at, now) {
+            self.reclaim_expired(&key_hash, account_id);
+            return Ok(None);
+        }
+
+        Ok(remaining_ttl(resp.expires_at, now))
+    }
+
+    // ==================== Usage ====================
+
+    /// Storage used by the current account and by the project's worker storage
+    ///
+    /// Sizes come from coordinator metadata (encrypted key + encrypted value), so
+    /// nothing is decrypted.
+    pub fn usage(&self) -> Result<StorageUsage> {
+        let project = self.usage_for_account(&self.config.account_id)?;
+        let worker = self.usage_for_account("@worker")?;
+        Ok(StorageUsage::combine(&project, &worker))
+    }
+
+    /// Entry count and stored bytes of one account in the project
+    fn usage_for_account(&self, account_id: &str) -> Result<AccountUsage> {
+        let body = serde_json::json!({
+            "project_uuid": &self.config.project_uuid,
+            "account_id": account_id,
+        });
+
+        let response = self
+            .client
+            .post(format!("{}/storage/usage", self.config.coordinator_url))
+            .header("Authorization", format!("Bearer {}", self.config.coordinator_token))
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .context("Failed to send storage usage request")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            check_route_served(status, "POST /storage/usage")?;
+            let error_text = response.text().unwrap_or_default();
+            anyhow::bail!("Storage usage failed: {} - {}", status, error_text);
+        }
+
+        let usage: AccountUsage = response.json().context("Failed to parse storage usage response")?;
+        debug!(
+            "storage_usage: account={}, keys={}, bytes={}",
+            account_id, usage.key_count, usage.total_bytes
+        );
+        Ok(usage)
+    }
+
+    /// Stored size of a key (encrypted key + encrypted value)
+    ///
+    /// `None` if the key doesn't exist or has expired.
+    pub fn key_bytes(&self, key: &str) -> Result<Option<u64>> {
+        let key_hash = self.hash_key(key);
+        let account_id = &self.config.account_id;
+
+        let body = serde_json::json!({
+            "project_uuid": &self.config.project_uuid,
+            "account_id": account_id,
+            "key_hash": key_hash,
+        });
+
+        let response = self
+            .client
+            .post(format!("{}/storage/get", self.config.coordinator_url))
+            .header("Authorization", format!("Bearer {}", self.config.coordinator_token))
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .context("Failed to send storage get request for key_bytes")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().unwrap_or_default();
+            anyhow::bail!("Storage get failed: {} - {}", status, error_text);
+        }
+
+        #[derive(Deserialize)]
+        struct SizeResponse {
+            exists: bool,
+            encrypted_key: Option<Vec<u8>>,
+            encrypted_value: Option<Vec<u8>>,
+            #[serde(default)]
+            expires_at: Option<u64>,
+        }
+
+        let resp: SizeResponse = response.json().context("Failed to parse storage get response")?;
+
+        if !resp.exists {
+            return Ok(None);
+        }
+        if is_expired(resp.expires_at, unix_now()) {
+            self.reclaim_expired(&key_hash, account_id);
+            return Ok(None);
+        }
+
+        match (resp.encrypted_key, resp.encrypted_value) {
+            (Some(enc_key), Some(enc_value)) => Ok(Some((enc_key.len() + enc_value.len()) as u64)),
+            _ => Ok(None),
+        }
+    }
+
+    // ==================== Snapshots ====================
+
+    /// Capture every key under `prefix` so it can be restored later
+    ///
+    /// The snapshot is stored as one encrypted entry that expires after
+    /// [`SNAPSHOT_TTL_SECONDS`]. At most [`MAX_LIVE_SNAPSHOTS`] are kept per
+    /// account; taking another one drops the oldest. Capturing more than
+    /// [`MAX_SNAPSHOT_KEYS`] keys or [`MAX_SNAPSHOT_BYTES`] fails.
+    ///
+    /// Returns the snapshot id.
+    pub fn snapshot(&self, prefix: &str) -> Result<String> {
+        if prefix.starts_with(SNAPSHOT_KEY_PREFIX) {
+            anyhow::bail!("snapshot: prefix '{}' is reserved", SNAPSHOT_KEY_PREFIX);
+        }
+
+        let keys = self.list_key_names_complete(prefix)?;
+        if keys.len() > MAX_SNAPSHOT_KEYS {
+            anyhow::bail!(
+                "snapshot: {} keys under prefix '{}' (max {})",
+                keys.len(),
+                prefix,
+                MAX_SNAPSHOT_KEYS
+            );
+        }
+
+        let mut entries = Vec::with_capacity(keys.len());
+        let mut captured_bytes = 0;
+        for key in keys {
+            // Keys deleted since listing are simply not part of the snapshot
+            if let Some(value) = self.get(&key)? {
+                captured_bytes += key.len() + value.len();
+                check_snapshot_bytes(captured_bytes, prefix)?;
+                entries.push((key, base64_encode(&value)));
+            }
+        }
+
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let now = unix_now();
+        let expires_at = now + SNAPSHOT_TTL_SECONDS;
+        let data = SnapshotData {
+            prefix: prefix.to_string(),
+            entries,
+        };
+        let data = serde_json::to_vec(&data).context("Failed to serialize snapshot")?;
+
+        let account_id = &self.config.account_id;
+        self.set_for_account_with_expiry(&snapshot_key(&id), &data, account_id, Some(expires_at))?;
+
+        let (mut index, evicted) = prune_snapshot_index(self.load_snapshot_index()?, now, MAX_LIVE_SNAPSHOTS - 1);
+        for old in evicted {
+            debug!("storage_snapshot: evicting snapshot {}", old.id);
+            self.delete_by_hash(&self.hash_key(&snapshot_key(&old.id)), account_id)?;
+        }
+        index.push(SnapshotMeta {
+            id: id.clone(),
+            prefix: prefix.to_string(),
+            created_at: now,
+            expires_at,
+        });
+        self.set_for_account(&snapshot_key("index"), &serde_json::to_vec(&index)?, account_id)?;
+
+        debug!(
+            "storage_snapshot: id={}, prefix={}, bytes={}, live={}",
+            id,
+            prefix,
+            data.len(),
+            index.len()
+        );
+        Ok(id)
+    }
+
+    /// Revert every key under the snapshot's prefix to its captured value
+    ///
+    /// Keys created after the snapshot are deleted. All values are encrypted
+    /// before the first write, so a keystore failure leaves storage untouched.
+    /// Restored keys have no TTL. The snapshot stays valid until it expires.
+    ///
+    /// Not atomic: deletes and writes go to the coordinator one key at a time
+    /// (a snapshot can hold more keys than one transaction), so a failure midway
+    /// leaves some keys restored and others not. Restoring is idempotent;
+    /// calling it again with the same snapshot finishes the job.
+    pub fn restore(&self, snapshot_id: &str) -> Result<()> {
+        if snapshot_id.is_empty() || !snapshot_id.chars().all(|c| c.is_ascii_hexdigit()) {
+            anyhow::bail!("restore: invalid snapshot id '{}'", snapshot_id);
+        }
+
+        let data = self
+            .get(&snapshot_key(snapshot_id))?
+            .with_context(|| format!("restore: snapshot '{}' not found or expired", snapshot_id))?;
+        let data: SnapshotData = serde_json::from_slice(&data).context("restore: corrupted snapshot")?;
+        let entries = data
+            .entries
+            .into_iter()
+            .map(|(key, value)| Ok((key, base64_decode(&value)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let current = self.list_key_names_complete(&data.prefix)?;
+        let (to_delete, to_write) = plan_restore(current, entries);
+
+        let account_id = &self.config.account_id;
+        let encrypted = to_write
+            .iter()
+            .map(|(key, value)| self.encrypt_via_keystore(key, value, account_id))
+            .collect::<Result<Vec<_>>>()?;
+
+        debug!(
+            "storage_restore: id={}, prefix={}, delete={}, write={}",
+            snapshot_id,
+            data.prefix,
+            to_delete.len(),
+            encrypted.len()
+        );
+
+        for key in &to_delete {
+            self.delete_by_hash(&self.hash_key(key), account_id)
+                .with_context(|| format!("restore: failed to delete '{}'", key))?;
+        }
+        for entry in &encrypted {
+            self.store_encrypted(entry, account_id, None)
+                .context("restore: write failed")?;
+        }
+
+        Ok(())
+    }
+
+    /// Live snapshots of the current account (missing index = none)
+    fn load_snapshot_index(&self) -> Result<Vec<SnapshotMeta>> {
+        let Some(bytes) = self.get(&snapshot_key("index"))? else {
+            return Ok(Vec::new());
+        };
+        Ok(serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+            warn!("Corrupted snapshot index, starting over: {}", e);
+            Vec::new()
+        }))
+    }
+
     /// List keys with optional prefix filter
     pub fn list_keys(&self, prefix: &str) -> Result<String> {
+        let keys = self.list_key_names(prefix)?;
+        serde_json::to_string(&keys).context("Failed to serialize keys")
+    }
+
+    /// Plaintext keys matching `prefix` (reserved snapshot keys excluded)
+    ///
+    /// Best effort: a failed listing reads as no keys and keys that fail to
+    /// decrypt are skipped.
+    fn list_key_names(&self, prefix: &str) -> Result<Vec<String>> {
+        self.fetch_key_names(prefix, false)
+    }
+
+    /// [`Self::list_key_names`] that fails instead of returning a partial listing
+    ///
+    /// Snapshot and restore need every key: restore deletes whatever the snapshot
+    /// didn't capture, so a key missing from either listing would be lost.
+    fn list_key_names_complete(&self, prefix: &str) -> Result<Vec<String>> {
+        self.fetch_key_names(prefix, true)
+    }
+
+    fn fetch_key_names(&self, prefix: &str, complete: bool) -> Result<Vec<String>> {
         // Note: prefix filtering requires decryption of all keys first,
         // then we filter client-side after decryption
 
@@ -360,7 +910,12 @@ impl StorageClient {
             .context("Failed to send storage list request")?;
 
         if !response.status().is_success() {
-            return Ok("[]".to_string());
+            if complete {
+                let status = response.status();
+                let error_text = response.text().unwrap_or_default();
+                anyhow::bail!("Storage list failed: {} - {}", status, error_text);
+            }
+            return Ok(Vec::new());
         }
 
         #[derive(Deserialize)]
@@ -374,16 +929,27 @@ impl StorageClient {
             key_hash: String,
             encrypted_key: Vec<u8>,
             encrypted_value: Vec<u8>,
+            #[serde(default)]
+            expires_at: Option<u64>,
         }
 
-        let resp: ListResponse = response.json().unwrap_or(ListResponse { keys: vec![] });
+        let resp: ListResponse = match response.json() {
+            Ok(resp) => resp,
+            Err(e) if complete => return Err(e).context("Failed to parse storage list response"),
+            Err(_) => ListResponse { keys: vec![] },
+        };
 
         // Decrypt all keys via keystore and filter by prefix
         let mut decrypted_keys: Vec<String> = Vec::new();
         let started = std::time::Instant::now();
         const LIST_KEYS_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(25);
         let total_keys = resp.keys.len();
+        let now = unix_now();
         for key_info in resp.keys {
+            // Expired entries are skipped without decrypting (reclaimed on next get)
+            if is_expired(key_info.expires_at, now) {
+                continue;
+            }
             if started.elapsed() > LIST_KEYS_TIMEOUT {
                 warn!(
                     "list_keys timed out after {}s ({}/{} keys)",
@@ -398,50 +964,132 @@ impl StorageClient {
             match self.decrypt_via_keystore(&key_info.encrypted_key, &key_info.encrypted_value, &self.config.account_id) {
                 Ok(decrypted) => {
                     // Apply prefix filter after decryption
-                    if prefix.is_empty() || decrypted.key.starts_with(prefix) {
+                    if (prefix.is_empty() || decrypted.key.starts_with(prefix))
+                        && !decrypted.key.starts_with(SNAPSHOT_KEY_PREFIX)
+                    {
                         decrypted_keys.push(decrypted.key);
                     }
                 }
+                Err(e) if complete => {
+                    return Err(e).context("Failed to decrypt key during list");
+                }
                 Err(e) => {
                     warn!("Failed to decrypt key during list: {}", e);
                 }
             }
         }
 
-        serde_json::to_string(&decrypted_keys).context("Failed to serialize keys")
+        Ok(decrypted_keys)
     }
 
-    /// Get value from a specific WASM version (for migration)
-    pub fn get_by_version(&self, key: &str, wasm_hash: &str) -> Result<Option<Vec<u8>>> {
-        let key_hash = self.hash_key(key);
+    /// List one page of keys matching `prefix`, continuing after `cursor`
+    ///
+    /// Entries are walked in key-hash order and the cursor is the hash of the last
+    /// examined entry, so pages stay consistent when keys are added or removed
+    /// between calls. Only the entries on the page are decrypted.
+    ///
+    /// Returns `(keys, next_cursor)`; `next_cursor` is `None` once the listing is exhausted.
+    pub fn list_keys_paged(
+        &self,
+        prefix: &str,
+        cursor: Option<&str>,
+        limit: u32,
+    ) -> Result<(Vec<String>, Option<String>)> {
+        if limit == 0 || limit > MAX_LIST_PAGE_SIZE {
+            anyhow::bail!("list_keys_paged: limit must be between 1 and {}", MAX_LIST_PAGE_SIZE);
+        }
 
-        let body = serde_json::json!({
-            "wasm_hash": wasm_hash,
-            "account_id": self.config.account_id,
-            "key_hash": key_hash,
-        });
+        let url = format!(
+            "{}/storage/list?account_id={}&project_uuid={}",
+            self.config.coordinator_url,
+            self.config.account_id,
+            self.config.project_uuid
+        );
 
         let response = self
             .client
-            .post(format!("{}/storage/get-by-version", self.config.coordinator_url))
+            .get(&url)
             .header("Authorization", format!("Bearer {}", self.config.coordinator_token))
-            .header("Content-Type", "application/json")
-            .json(&body)
             .send()
-            .context("Failed to send storage get-by-version request")?;
+            .context("Failed to send storage list request")?;
 
         if !response.status().is_success() {
-            return Ok(None);
+            let status = response.status();
+            let error_text = response.text().unwrap_or_default();
+            error!("Storage list failed: {} - {}", status, error_text);
+            anyhow::bail!("Storage list failed: {} - {}", status, error_text);
         }
 
         #[derive(Deserialize)]
-        struct GetResponse {
-            exists: bool,
-            encrypted_key: Option<Vec<u8>>,
-            encrypted_value: Option<Vec<u8>>,
+        struct ListResponse {
+            keys: Vec<StoredKey>,
         }
 
-        let resp: GetResponse = response.json().context("Failed to parse response")?;
+        let mut resp: ListResponse = response.json().context("Failed to parse storage list response")?;
+
+        let now = unix_now();
+        resp.keys.retain(|entry| !is_expired(entry.expires_at, now));
+
+        debug!(
+            "storage_list_keys_paged: entries={}, cursor={:?}, limit={}",
+            resp.keys.len(),
+            cursor,
+            limit
+        );
+
+        // Stop early on slow keystores; the cursor lets the caller pick up where we stopped
+        let started = std::time::Instant::now();
+        const PAGE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(25);
+
+        Ok(select_page(
+            resp.keys,
+            prefix,
+            cursor,
+            limit as usize,
+            |entry| {
+                match self.decrypt_via_keystore(&entry.encrypted_key, &entry.encrypted_value, &self.config.account_id) {
+                    Ok(decrypted) => Some(decrypted.key),
+                    Err(e) => {
+                        warn!("Failed to decrypt key during paged list: {}", e);
+                        None
+                    }
+                }
+            },
+            || started.elapsed() > PAGE_TIMEOUT,
+        ))
+    }
+
+    /// Get value from a specific WASM version (for migration)
+    pub fn get_by_version(&self, key: &str, wasm_hash: &str) -> Result<Option<Vec<u8>>> {
+        let key_hash = self.hash_key(key);
+
+        let body = serde_json::json!({
+            "wasm_hash": wasm_hash,
+            "account_id": self.config.account_id,
+            "key_hash": key_hash,
+        });
+
+        let response = self
+            .client
+            .post(format!("{}/storage/get-by-version", self.config.coordinator_url))
+            .header("Authorization", format!("Bearer {}", self.config.coordinator_token))
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .context("Failed to send storage get-by-version request")?;
+
+        if !response.status().is_success() {
+            return Ok(None);
+        }
+
+        #[derive(Deserialize)]
+        struct GetResponse {
+            exists: bool,
+            encrypted_key: Option<Vec<u8>>,
+            encrypted_value: Option<Vec<u8>>,
+        }
+
+        let resp: GetResponse = response.json().context("Failed to parse response")?;
 
         if !resp.exists {
             return Ok(None);
@@ -542,18 +1190,19 @@ impl StorageClient {
             self.set_for_account(key, value, "@worker")
         } else {
             // Public: store plaintext directly (no keystore)
-            self.set_public(key, value)
+            self.set_public(key, value, None)
         }
     }
 
-    /// Set public storage (plaintext, no encryption)
-    fn set_public(&self, key: &str, value: &[u8]) -> Result<()> {
+    /// Set public storage (plaintext, no encryption), optionally expiring at `expires_at`
+    fn set_public(&self, key: &str, value: &[u8], expires_at: Option<u64>) -> Result<()> {
         let key_hash = self.hash_key(key);
 
         debug!(
-            "storage_set_public: key_hash={}, value_size={}",
+            "storage_set_public: key_hash={}, value_size={}, expires_at={:?}",
             key_hash,
-            value.len()
+            value.len(),
+            expires_at
         );
 
         // Store plaintext key and value directly (no keystore encryption)
@@ -565,6 +1214,7 @@ impl StorageClient {
             "encrypted_key": key.as_bytes(),      // plaintext key
             "encrypted_value": value,              // plaintext value
             "is_encrypted": false,
+            "expires_at": expires_at,
         });
 
         let response = self
@@ -604,6 +1254,37 @@ impl StorageClient {
 
     /// Get worker storage from own project (handles both encrypted and public)
     fn get_worker_own(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.get_worker_entry(key)?.map(|entry| entry.value))
+    }
+
+    /// Whether a worker key of this project is public, `None` if it doesn't exist
+    pub fn worker_visibility(&self, key: &str) -> Result<Option<Visibility>> {
+        Ok(self.get_worker_entry(key)?.map(|entry| entry.visibility))
+    }
+
+    /// Re-store an existing worker key as public or encrypted, keeping its value and expiry
+    ///
+    /// Returns false if the key doesn't exist. Read and write are separate requests, so
+    /// a concurrent write to the same key in between is overwritten.
+    pub fn set_worker_visibility(&self, key: &str, visibility: Visibility) -> Result<bool> {
+        let Some(entry) = self.get_worker_entry(key)? else {
+            return Ok(false);
+        };
+        if entry.visibility == visibility {
+            return Ok(true);
+        }
+
+        debug!("storage_set_worker_visibility: {:?} -> {:?}", entry.visibility, visibility);
+
+        match visibility {
+            Visibility::Public => self.set_public(key, &entry.value, entry.expires_at)?,
+            Visibility::Private => self.set_for_account_with_expiry(key, &entry.value, "@worker", entry.expires_at)?,
+        }
+        Ok(true)
+    }
+
+    /// Read a worker key of this project, decrypting it if needed
+    fn get_worker_entry(&self, key: &str) -> Result<Option<WorkerEntry>> {
         let key_hash = self.hash_key(key);
 
         debug!("storage_get_worker_own: key_hash={}", key_hash);
@@ -637,29 +1318,37 @@ impl StorageClient {
             encrypted_value: Option<Vec<u8>>,
             #[serde(default = "default_true")]
             is_encrypted: bool,
+            #[serde(default)]
+            expires_at: Option<u64>,
         }
 
-        fn default_true() -> bool { true }
-
         let resp: GetResponse = response.json().context("Failed to parse storage get response")?;
 
         if !resp.exists {
             return Ok(None);
         }
 
-        match (resp.encrypted_key, resp.encrypted_value) {
-            (Some(enc_key), Some(enc_value)) => {
-                if resp.is_encrypted {
-                    // Encrypted: decrypt via keystore
-                    let decrypted = self.decrypt_via_keystore(&enc_key, &enc_value, "@worker")?;
-                    Ok(Some(decrypted.value))
-                } else {
-                    // Public: value is plaintext
-                    Ok(Some(enc_value))
-                }
-            }
-            _ => Ok(None),
+        if is_expired(resp.expires_at, unix_now()) {
+            self.reclaim_expired(&key_hash, "@worker");
+            return Ok(None);
         }
+
+        let (Some(enc_key), Some(enc_value)) = (resp.encrypted_key, resp.encrypted_value) else {
+            return Ok(None);
+        };
+        let (value, visibility) = if resp.is_encrypted {
+            // Encrypted: decrypt via keystore
+            let decrypted = self.decrypt_via_keystore(&enc_key, &enc_value, "@worker")?;
+            (decrypted.value, Visibility::Private)
+        } else {
+            // Public: value is plaintext
+            (enc_value, Visibility::Public)
+        };
+        Ok(Some(WorkerEntry {
+            value,
+            visibility,
+            expires_at: resp.expires_at,
+        }))
     }
 
     /// Get public worker storage from another project by UUID
@@ -704,29 +1393,89 @@ impl StorageClient {
         struct GetPublicResponse {
             exists: bool,
             value: Option<Vec<u8>>,
+            #[serde(default)]
+            expires_at: Option<u64>,
         }
 
         let resp: GetPublicResponse = response.json().context("Failed to parse storage get-public response")?;
 
-        if !resp.exists {
+        // Expired entries read as missing; the owning project reclaims them on its own reads
+        if !resp.exists || is_expired(resp.expires_at, unix_now()) {
             return Ok(None);
         }
 
         Ok(resp.value)
     }
 
+    /// List one page of public worker keys of another project, continuing after `cursor`
+    ///
+    /// Coordinator `/storage/list-public` only returns entries stored with
+    /// `is_encrypted=false`; entries flagged encrypted or whose key doesn't hash to its
+    /// `key_hash` are dropped here as well, so ciphertext never reaches the guest.
+    /// Paging works like [`Self::list_keys_paged`].
+    pub fn list_public_keys(
+        &self,
+        project_uuid: &str,
+        prefix: &str,
+        cursor: Option<&str>,
+        limit: u32,
+    ) -> Result<(Vec<String>, Option<String>)> {
+        if limit == 0 || limit > MAX_LIST_PAGE_SIZE {
+            anyhow::bail!("list_public_keys: limit must be between 1 and {}", MAX_LIST_PAGE_SIZE);
+        }
+
+        let response = self
+            .client
+            .get(format!("{}/storage/list-public", self.config.coordinator_url))
+            .query(&[("project_uuid", project_uuid)])
+            .header("Authorization", format!("Bearer {}", self.config.coordinator_token))
+            .send()
+            .context("Failed to send storage list-public request")?;
+
+        // A project without public keys is an empty 200 response; 404/405 means the route is missing
+        if !response.status().is_success() {
+            let status = response.status();
+            check_route_served(status, "GET /storage/list-public")?;
+            let error_text = response.text().unwrap_or_default();
+            error!("Storage list-public failed: {} - {}", status, error_text);
+            anyhow::bail!("Storage list-public failed: {} - {}", status, error_text);
+        }
+
+        #[derive(Deserialize)]
+        struct ListPublicResponse {
+            keys: Vec<PublicStoredKey>,
+        }
+
+        let resp: ListPublicResponse = response.json().context("Failed to parse storage list-public response")?;
+
+        debug!(
+            "storage_list_public_keys: project_uuid={}, entries={}, cursor={:?}, limit={}",
+            project_uuid,
+            resp.keys.len(),
+            cursor,
+            limit
+        );
+
+        Ok(select_public_page(resp.keys, prefix, cursor, limit as usize, unix_now()))
+    }
+
     // ==================== Conditional Write Operations ====================
 
     /// Set a key only if it doesn't already exist
     /// Returns true if value was inserted, false if key already existed
     pub fn set_if_absent(&self, key: &str, value: &[u8]) -> Result<bool> {
+        check_user_key(key)?;
+        self.set_if_absent_for_account(key, value, &self.config.account_id, true)
+    }
+
+    fn set_if_absent_for_account(&self, key: &str, value: &[u8], account_id: &str, retry_if_expired: bool) -> Result<bool> {
         // Encrypt via keystore
-        let encrypted = self.encrypt_via_keystore(key, value, &self.config.account_id)?;
+        let encrypted = self.encrypt_via_keystore(key, value, account_id)?;
 
         debug!(
             "storage_set_if_absent: key_hash={}, account={}, value_size={}",
             encrypted.key_hash,
-            self.config.account_id,
+            account_id,
             value.len()
         );
 
@@ -734,7 +1483,7 @@ impl StorageClient {
         let body = serde_json::json!({
             "project_uuid": &self.config.project_uuid,
             "wasm_hash": self.config.wasm_hash,
-            "account_id": self.config.account_id,
+            "account_id": account_id,
             "key_hash": encrypted.key_hash,
             "encrypted_key": encrypted.encrypted_key,
             "encrypted_value": encrypted.encrypted_value,
@@ -761,18 +1510,35 @@ impl StorageClient {
         }
 
         let resp: SetIfAbsentResponse = response.json().context("Failed to parse set-if-absent response")?;
+
+        // An expired entry still occupies the key until reclaimed; get() reclaims it
+        if !resp.inserted && retry_if_expired && self.get_for_account(key, account_id)?.is_none() {
+            return self.set_if_absent_for_account(key, value, account_id, false);
+        }
+
         Ok(resp.inserted)
     }
 
     /// Set a key only if current value equals expected (compare-and-swap)
     /// Returns (success, current_value) where current_value is provided for retry on failure
     pub fn set_if_equals(&self, key: &str, expected: &[u8], new_value: &[u8]) -> Result<(bool, Option<Vec<u8>>)> {
+        check_user_key(key)?;
+        self.set_if_equals_for_account(key, expected, new_value, &self.config.account_id)
+    }
+
+    fn set_if_equals_for_account(
+        &self,
+        key: &str,
+        expected: &[u8],
+        new_value: &[u8],
+        account_id: &str,
+    ) -> Result<(bool, Option<Vec<u8>>)> {
         // First, get current encrypted value to pass to coordinator
         let key_hash = self.hash_key(key);
 
         let get_body = serde_json::json!({
             "project_uuid": &self.config.project_uuid,
-            "account_id": self.config.account_id,
+            "account_id": account_id,
             "key_hash": key_hash,
         });
 
@@ -809,7 +1575,7 @@ impl StorageClient {
         };
 
         // Decrypt current value to compare with expected
-        let decrypted = self.decrypt_via_keystore(&current_enc_key, &current_enc_value, &self.config.account_id)?;
+        let decrypted = self.decrypt_via_keystore(&current_enc_key, &current_enc_value, account_id)?;
 
         if decrypted.value != expected {
             // Current value doesn't match expected - return current value for retry
@@ -817,16 +1583,18 @@ impl StorageClient {
         }
 
         // Values match - encrypt new value and try to update
-        let new_encrypted = self.encrypt_via_keystore(key, new_value, &self.config.account_id)?;
+        let new_encrypted = self.encrypt_via_keystore(key, new_value, account_id)?;
 
         let update_body = serde_json::json!({
             "project_uuid": &self.config.project_uuid,
             "wasm_hash": self.config.wasm_hash,
-            "account_id": self.config.account_id,
+            "account_id": account_id,
             "key_hash": key_hash,
             "expected_encrypted_value": current_enc_value,
             "new_encrypted_key": new_encrypted.encrypted_key,
             "new_encrypted_value": new_encrypted.encrypted_value,
+            // Plain set semantics: a CAS write clears any previous TTL
+            "expires_at": null,
         });
 
         let update_response = self
@@ -858,7 +1626,7 @@ impl StorageClient {
         } else {
             // Concurrent modification - decrypt current value for retry
             if let (Some(enc_key), Some(enc_value)) = (update_resp.current_encrypted_key, update_resp.current_encrypted_value) {
-                let current = self.decrypt_via_keystore(&enc_key, &enc_value, &self.config.account_id)?;
+                let current = self.decrypt_via_keystore(&enc_key, &enc_value, account_id)?;
                 Ok((false, Some(current.value)))
             } else {
                 Ok((false, None))
@@ -866,6 +1634,127 @@ impl StorageClient {
         }
     }
 
+    /// Delete a key only if its current value equals expected
+    /// Returns true if the key was deleted, false if it was absent or held a different value
+    pub fn compare_and_delete(&self, key: &str, expected: &[u8]) -> Result<bool> {
+        check_user_key(key)?;
+        self.compare_and_delete_for_account(key, expected, &self.config.account_id)
+    }
+
+    /// Delete a worker storage key only if its current value equals expected
+    pub fn compare_and_delete_worker(&self, key: &str, expected: &[u8]) -> Result<bool> {
+        self.compare_and_delete_for_account(key, expected, "@worker")
+    }
+
+    /// Conditional delete for a specific account
+    ///
+    /// The plaintext comparison happens here; the coordinator then deletes only
+    /// if the stored ciphertext is still the one that was compared, so a write
+    /// landing in between makes the delete fail instead of removing the new value.
+    fn compare_and_delete_for_account(&self, key: &str, expected: &[u8], account_id: &str) -> Result<bool> {
+        let key_hash = self.hash_key(key);
+
+        debug!(
+            "storage_compare_and_delete: key_hash={}, account={}, expected_size={}",
+            key_hash,
+            account_id,
+            expected.len()
+        );
+
+        let get_body = serde_json::json!({
+            "project_uuid": &self.config.project_uuid,
+            "account_id": account_id,
+            "key_hash": key_hash,
+        });
+
+        let get_response = self
+            .client
+            .post(format!("{}/storage/get", self.config.coordinator_url))
+            .header("Authorization", format!("Bearer {}", self.config.coordinator_token))
+            .header("Content-Type", "application/json")
+            .json(&get_body)
+            .send()
+            .context("Failed to send storage get request for compare_and_delete")?;
+
+        if !get_response.status().is_success() {
+            anyhow::bail!("Storage get failed during compare_and_delete");
+        }
+
+        #[derive(Deserialize)]
+        struct GetResponse {
+            exists: bool,
+            encrypted_key: Option<Vec<u8>>,
+            encrypted_value: Option<Vec<u8>>,
+            #[serde(default = "default_true")]
+            is_encrypted: bool,
+            #[serde(default)]
+            expires_at: Option<u64>,
+        }
+
+        fn default_true() -> bool { true }
+
+        let get_resp: GetResponse = get_response.json().context("Failed to parse get response")?;
+
+        if !get_resp.exists {
+            return Ok(false);
+        }
+
+        if is_expired(get_resp.expires_at, unix_now()) {
+            self.reclaim_expired(&key_hash, account_id);
+            return Ok(false);
+        }
+
+        let (current_enc_key, current_enc_value) = match (get_resp.encrypted_key, get_resp.encrypted_value) {
+            (Some(k), Some(v)) => (k, v),
+            _ => return Ok(false),
+        };
+
+        // Public worker entries are stored as plaintext
+        let current = if get_resp.is_encrypted {
+            self.decrypt_via_keystore(&current_enc_key, &current_enc_value, account_id)?.value
+        } else {
+            current_enc_value.clone()
+        };
+
+        if current != expected {
+            return Ok(false);
+        }
+
+        let delete_body = serde_json::json!({
+            "project_uuid": &self.config.project_uuid,
+            "account_id": account_id,
+            "key_hash": key_hash,
+            "expected_encrypted_value": current_enc_value,
+        });
+
+        let delete_response = self
+            .client
+            .post(format!("{}/storage/delete-if-equals", self.config.coordinator_url))
+            .header("Authorization", format!("Bearer {}", self.config.coordinator_token))
+            .header("Content-Type", "application/json")
+            .json(&delete_body)
+            .send()
+            .context("Failed to send storage delete-if-equals request")?;
+
+        if !delete_response.status().is_success() {
+            let status = delete_response.status();
+            check_route_served(status, "POST /storage/delete-if-equals")?;
+            let error_text = delete_response.text().unwrap_or_default();
+            anyhow::bail!("Storage delete-if-equals failed: {} - {}", status, error_text);
+        }
+
+        #[derive(Deserialize)]
+        struct DeleteIfEqualsResponse {
+            deleted: bool,
+        }
+
+        let resp: DeleteIfEqualsResponse = delete_response
+            .json()
+            .context("Failed to parse delete-if-equals response")?;
+
+        Ok(resp.deleted)
+    }
+
     /// Atomically increment a numeric value
     /// If key doesn't exist, creates it with delta as initial value
     /// Returns the new value after increment
@@ -925,6 +1814,340 @@ impl StorageClient {
         // decrement(delta) is just increment(-delta)
         self.increment(key, -delta)
     }
+
+    /// Atomically append `data` to a value, creating the key if absent
+    /// Returns the total value length after the append
+    pub fn append(&self, key: &str, data: &[u8]) -> Result<u64> {
+        check_user_key(key)?;
+        self.append_for_account(key, data, &self.config.account_id)
+    }
+
+    /// Atomically append `data` to an encrypted worker storage value
+    pub fn append_worker(&self, key: &str, data: &[u8]) -> Result<u64> {
+        self.append_for_account(key, data, "@worker")
+    }
+
+    /// Append for a specific account
+    ///
+    /// Values are encrypted as a whole, so the full value is rewritten; the same
+    /// compare-and-swap retry loop as `increment` keeps concurrent appends from
+    /// losing each other's data.
+    fn append_for_account(&self, key: &str, data: &[u8], account_id: &str) -> Result<u64> {
+        const MAX_RETRIES: usize = 5;
+
+        for attempt in 0..MAX_RETRIES {
+            let current = self.get_for_account(key, account_id)?;
+            if data.is_empty() {
+                return Ok(current.map_or(0, |value| value.len() as u64));
+            }
+            let combined = appended(current.as_deref(), data)?;
+
+            let stored = match &current {
+                None => self.set_if_absent_for_account(key, &combined, account_id, true)?,
+                Some(current) => self.set_if_equals_for_account(key, current, &combined, account_id)?.0,
+            };
+            if stored {
+                debug!("append: key={} account={} new_len={}", key, account_id, combined.len());
+                return Ok(combined.len() as u64);
+            }
+            debug!("append: concurrent modification detected, retrying (attempt {})", attempt + 1);
+        }
+
+        anyhow::bail!("append: max retries ({}) exceeded for key={}", MAX_RETRIES, key)
+    }
+
+    /// Read up to `len` bytes of a value starting at `offset`
+    /// Empty if the key doesn't exist or `offset` is past the end
+    pub fn read_range(&self, key: &str, offset: u64, len: u64) -> Result<Vec<u8>> {
+        let value = self.get(key)?.unwrap_or_default();
+        Ok(byte_range(&value, offset, len).to_vec())
+    }
+}
+
+/// `current` followed by `data`, refusing to grow past [`MAX_APPEND_VALUE_SIZE`]
+fn appended(current: Option<&[u8]>, data: &[u8]) -> Result<Vec<u8>> {
+    let current = current.unwrap_or_default();
+    let total = current.len() + data.len();
+    if total > MAX_APPEND_VALUE_SIZE {
+        anyhow::bail!(
+            "append: value would grow to {} bytes (max {}); start a new key to continue the log",
+            total,
+            MAX_APPEND_VALUE_SIZE
+        );
+    }
+    let mut combined = Vec::with_capacity(total);
+    combined.extend_from_slice(current);
+    combined.extend_from_slice(data);
+    Ok(combined)
+}
+
+/// Bytes `offset..offset + len` of `value`, clamped to its end
+fn byte_range(value: &[u8], offset: u64, len: u64) -> &[u8] {
+    let start = usize::try_from(offset).unwrap_or(usize::MAX).min(value.len());
+    let end = start.saturating_add(usize::try_from(len).unwrap_or(usize::MAX)).min(value.len());
+    &value[start..end]
+}
+
+/// Reject batches larger than [`MAX_BATCH_SIZE`]
+fn check_batch_size(size: usize) -> Result<()> {
+    if size > MAX_BATCH_SIZE {
+        anyhow::bail!("Storage batch too large: {} entries (max {})", size, MAX_BATCH_SIZE);
+    }
+    Ok(())
+}
+
+/// Current unix time in seconds
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Expiry timestamp for an entry written at `now` with the given TTL
+fn expiry_after(ttl_seconds: u64, now: u64) -> Result<u64> {
+    if ttl_seconds == 0 {
+        anyhow::bail!("ttl_seconds must be greater than 0");
+    }
+    now.checked_add(ttl_seconds).context("ttl_seconds is too large")
+}
+
+/// Entries expire at `expires_at` (inclusive)
+fn is_expired(expires_at: Option<u64>, now: u64) -> bool {
+    matches!(expires_at, Some(t) if t <= now)
+}
+
+/// Seconds left before expiry (`None` for entries without TTL or already expired)
+fn remaining_ttl(expires_at: Option<u64>, now: u64) -> Option<u64> {
+    expires_at.filter(|&t| t > now).map(|t| t - now)
+}
+
+/// Fail with [`UnsupportedByCoordinator`] if `status` says the route doesn't exist
+fn check_route_served(status: reqwest::StatusCode, route: &'static str) -> Result<()> {
+    if status == reqwest::StatusCode::NOT_FOUND || status == reqwest::StatusCode::METHOD_NOT_ALLOWED {
+        return Err(UnsupportedByCoordinator { route }.into());
+    }
+    Ok(())
+}
+
+/// Reject guest writes to reserved snapshot keys
+///
+/// Snapshot data and the snapshot index live in the caller's own storage; a
+/// write there could forge a snapshot or reset the index past MAX_LIVE_SNAPSHOTS.
+fn check_user_key(key: &str) -> Result<()> {
+    if key.starts_with(SNAPSHOT_KEY_PREFIX) {
+        anyhow::bail!("keys starting with '{}' are reserved", SNAPSHOT_KEY_PREFIX);
+    }
+    Ok(())
+}
+
+/// Reject a snapshot whose captured keys and values exceed [`MAX_SNAPSHOT_BYTES`]
+fn check_snapshot_bytes(captured_bytes: usize, prefix: &str) -> Result<()> {
+    if captured_bytes > MAX_SNAPSHOT_BYTES {
+        anyhow::bail!(
+            "snapshot: keys under prefix '{}' exceed {} bytes",
+            prefix,
+            MAX_SNAPSHOT_BYTES
+        );
+    }
+    Ok(())
+}
+
+/// Storage key holding snapshot `id` (or the snapshot index)
+fn snapshot_key(id: &str) -> String {
+    format!("{}{}", SNAPSHOT_KEY_PREFIX, id)
+}
+
+/// Captured keys and values (base64) of one snapshot
+#[derive(Serialize, Deserialize)]
+struct SnapshotData {
+    prefix: String,
+    entries: Vec<(String, String)>,
+}
+
+/// Snapshot index entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SnapshotMeta {
+    id: String,
+    prefix: String,
+    created_at: u64,
+    expires_at: u64,
+}
+
+/// Drop expired snapshots, then the oldest ones beyond `keep`
+///
+/// Returns `(kept, evicted)`; expired snapshots are already gone from storage
+/// and are not reported as evicted.
+fn prune_snapshot_index(
+    mut index: Vec<SnapshotMeta>,
+    now: u64,
+    keep: usize,
+) -> (Vec<SnapshotMeta>, Vec<SnapshotMeta>) {
+    index.retain(|s| s.expires_at > now);
+    index.sort_by_key(|s| s.created_at);
+    let evicted = index.drain(..index.len().saturating_sub(keep)).collect();
+    (index, evicted)
+}
+
+/// Work needed to turn `current` keys back into the snapshot `entries`
+///
+/// Returns `(keys to delete, entries to write)`.
+fn plan_restore(current: Vec<String>, entries: Vec<(String, Vec<u8>)>) -> (Vec<String>, Vec<(String, Vec<u8>)>) {
+    let captured: std::collections::HashSet<&str> = entries.iter().map(|(k, _)| k.as_str()).collect();
+    let to_delete = current
+        .into_iter()
+        .filter(|key| !captured.contains(key.as_str()))
+        .collect();
+    (to_delete, entries)
+}
+
+/// Encrypted entry as returned by coordinator `/storage/list`
+#[derive(Deserialize)]
+struct StoredKey {
+    key_hash: String,
+    encrypted_key: Vec<u8>,
+    /// Not needed for listing; `/storage/list-public` may omit it
+    #[serde(default)]
+    encrypted_value: Vec<u8>,
+    /// Expiry (unix seconds) for entries written with a TTL
+    #[serde(default)]
+    expires_at: Option<u64>,
+}
+
+/// Whether a worker key is readable by other projects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    /// Stored plaintext (`is_encrypted=false`)
+    Public,
+    /// Encrypted via the keystore
+    Private,
+}
+
+/// Worker key of the current project, decrypted
+struct WorkerEntry {
+    value: Vec<u8>,
+    visibility: Visibility,
+    expires_at: Option<u64>,
+}
+
+/// Entry as returned by coordinator `/storage/list-public`
+#[derive(Deserialize)]
+struct PublicStoredKey {
+    #[serde(flatten)]
+    entry: StoredKey,
+    /// Missing flag is treated as encrypted
+    #[serde(default = "default_true")]
+    is_encrypted: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Plaintext key of a public entry, `None` unless it is flagged public and its key
+/// bytes hash to `key_hash`
+fn public_key_name(entry: &PublicStoredKey) -> Option<String> {
+    if entry.is_encrypted {
+        return None;
+    }
+    let key = String::from_utf8(entry.entry.encrypted_key.clone()).ok()?;
+    (hex::encode(Sha256::digest(key.as_bytes())) == entry.entry.key_hash).then_some(key)
+}
+
+/// Pick one page of public keys, skipping expired and non-public entries
+fn select_public_page(
+    entries: Vec<PublicStoredKey>,
+    prefix: &str,
+    cursor: Option<&str>,
+    limit: usize,
+    now: u64,
+) -> (Vec<String>, Option<String>) {
+    let entries = entries
+        .into_iter()
+        .filter(|e| !is_expired(e.entry.expires_at, now) && public_key_name(e).is_some())
+        .map(|e| e.entry)
+        .collect();
+    // Keys of the remaining entries are plaintext
+    select_page(
+        entries,
+        prefix,
+        cursor,
+        limit,
+        |e| String::from_utf8(e.encrypted_key.clone()).ok(),
+        || false,
+    )
+}
+
+/// Pick one page of plaintext keys from a listing, continuing after `cursor`
+///
+/// `decrypt` yields the plaintext key (`None` skips the entry). `should_stop` is
+/// checked after each examined entry; stopping early still returns a valid cursor.
+fn select_page(
+    mut entries: Vec<StoredKey>,
+    prefix: &str,
+    cursor: Option<&str>,
+    limit: usize,
+    mut decrypt: impl FnMut(&StoredKey) -> Option<String>,
+    mut should_stop: impl FnMut() -> bool,
+) -> (Vec<String>, Option<String>) {
+    entries.sort_by(|a, b| a.key_hash.cmp(&b.key_hash));
+    if let Some(cursor) = cursor {
+        entries.retain(|e| e.key_hash.as_str() > cursor);
+    }
+
+    let mut keys = Vec::new();
+    let mut examined = 0;
+    for entry in &entries {
+        if keys.len() >= limit || (examined > 0 && should_stop()) {
+            break;
+        }
+        examined += 1;
+        if let Some(key) = decrypt(entry) {
+            if (prefix.is_empty() || key.starts_with(prefix)) && !key.starts_with(SNAPSHOT_KEY_PREFIX) {
+                keys.push(key);
+            }
+        }
+    }
+
+    let next_cursor = if examined < entries.len() {
+        Some(entries[examined - 1].key_hash.clone())
+    } else {
+        None
+    };
+    (keys, next_cursor)
+}
+
+/// Storage consumed by a project, in bytes stored by the coordinator
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StorageUsage {
+    /// Entries of the current account plus worker storage
+    pub key_count: u64,
+    /// `project_bytes + worker_bytes`
+    pub total_bytes: u64,
+    /// Worker storage (`@worker` account) of the project
+    pub worker_bytes: u64,
+    /// Storage of the current account in the project
+    pub project_bytes: u64,
+}
+
+impl StorageUsage {
+    fn combine(project: &AccountUsage, worker: &AccountUsage) -> Self {
+        Self {
+            key_count: project.key_count.saturating_add(worker.key_count),
+            total_bytes: project.total_bytes.saturating_add(worker.total_bytes),
+            worker_bytes: worker.total_bytes,
+            project_bytes: project.total_bytes,
+        }
+    }
+}
+
+/// Usage of one account as reported by the coordinator
+#[derive(Debug, Default, Deserialize)]
+struct AccountUsage {
+    #[serde(default)]
+    key_count: u64,
+    #[serde(default)]
+    total_bytes: u64,
 }
 
 /// Encrypted data from keystore
@@ -952,3 +2175,390 @@ fn base64_decode(data: &str) -> Result<Vec<u8>> {
         .decode(data)
         .context("Invalid base64")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fake listing entry whose "ciphertext" is the plaintext key
+    fn entry(key: &str) -> StoredKey {
+        StoredKey {
+            key_hash: hex::encode(Sha256::digest(key.as_bytes())),
+            encrypted_key: key.as_bytes().to_vec(),
+            encrypted_value: Vec::new(),
+            expires_at: None,
+        }
+    }
+
+    fn listing(keys: &[&str]) -> Vec<StoredKey> {
+        keys.iter().copied().map(entry).collect()
+    }
+
+    #[test]
+    fn batch_size_matches_wit() {
+        let wit = include_str!("../../wit/deps/storage.wit");
+        assert!(wit.contains(&format!("MAX-BATCH-SIZE = {}:", MAX_BATCH_SIZE)));
+    }
+
+    #[test]
+    fn transactions_disabled_message_matches_wit() {
+        let wit = include_str!("../../wit/deps/storage.wit");
+        assert!(wit.contains(&format!("\"{}\"", TransactionsDisabled)));
+    }
+
+    fn page(keys: &[&str], prefix: &str, cursor: Option<&str>, limit: usize) -> (Vec<String>, Option<String>) {
+        select_page(
+            listing(keys),
+            prefix,
+            cursor,
+            limit,
+            |e| Some(String::from_utf8(e.encrypted_key.clone()).unwrap()),
+            || false,
+        )
+    }
+
+    fn collect_all(keys: &[&str], prefix: &str, limit: usize) -> Vec<String> {
+        let mut all = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let (keys, next) = page(keys, prefix, cursor.as_deref(), limit);
+            all.extend(keys);
+            match next {
+                Some(c) => cursor = Some(c),
+                None => break,
+            }
+        }
+        all.sort();
+        all
+    }
+
+    #[test]
+    fn test_paged_listing_visits_every_key_once() {
+        let keys: Vec<String> = (0..25).map(|i| format!("user:{}", i)).collect();
+        let refs: Vec<&str> = keys.iter().map(String::as_str).collect();
+
+        let mut expected = keys.clone();
+        expected.sort();
+        assert_eq!(collect_all(&refs, "", 7), expected);
+        assert_eq!(collect_all(&refs, "", 1000), expected);
+
+        let (first, next) = page(&refs, "", None, 10);
+        assert_eq!(first.len(), 10);
+        assert!(next.is_some());
+    }
+
+    #[test]
+    fn test_public_listing_only_returns_public_keys() {
+        let hash = |key: &str| hex::encode(Sha256::digest(key.as_bytes()));
+        let response = serde_json::json!([
+            { "key_hash": hash("price:ETH"), "encrypted_key": b"price:ETH", "is_encrypted": false },
+            { "key_hash": hash("price:BTC"), "encrypted_key": b"price:BTC", "encrypted_value": b"1", "is_encrypted": false },
+            { "key_hash": hash("config"), "encrypted_key": b"config", "is_encrypted": false },
+            // Encrypted, flag missing, hash of another key, expired
+            { "key_hash": hash("price:SOL"), "encrypted_key": b"price:SOL", "is_encrypted": true },
+            { "key_hash": hash("price:DOT"), "encrypted_key": b"price:DOT" },
+            { "key_hash": hash("price:ETH"), "encrypted_key": b"price:XRP", "is_encrypted": false },
+            { "key_hash": hash("price:OLD"), "encrypted_key": b"price:OLD", "is_encrypted": false, "expires_at": 50 },
+        ]);
+        let entries: Vec<PublicStoredKey> = serde_json::from_value(response).unwrap();
+
+        let (mut keys, cursor) = select_public_page(entries, "price:", None, 10, 100);
+        keys.sort();
+        assert_eq!(keys, vec!["price:BTC", "price:ETH"]);
+        assert_eq!(cursor, None);
+    }
+
+    #[test]
+    fn test_public_listing_pages() {
+        let keys = ["a:1", "a:2", "a:3", "b:1", "a:4"];
+        let public = || {
+            keys.iter()
+                .map(|k| PublicStoredKey {
+                    entry: entry(k),
+                    is_encrypted: false,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut all = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let (page, next) = select_public_page(public(), "a:", cursor.as_deref(), 2, 0);
+            assert!(page.len() <= 2);
+            all.extend(page);
+            match next {
+                Some(c) => cursor = Some(c),
+                None => break,
+            }
+        }
+        all.sort();
+        assert_eq!(all, vec!["a:1", "a:2", "a:3", "a:4"]);
+    }
+
+    #[test]
+    fn test_paged_listing_applies_prefix() {
+        let all = collect_all(&["a:1", "b:1", "a:2", "b:2", "a:3"], "a:", 2);
+        assert_eq!(all, vec!["a:1", "a:2", "a:3"]);
+    }
+
+    #[test]
+    fn test_cursor_stable_across_inserts_and_deletes() {
+        let before = ["k1", "k2", "k3", "k4", "k5", "k6"];
+        let (first, cursor) = page(&before, "", None, 3);
+        let cursor = cursor.unwrap();
+
+        // Remove a key from the first page and one not yet listed, add a new key
+        let removed_later = before.iter().find(|k| !first.contains(&k.to_string())).unwrap();
+        let mut after: Vec<&str> = before
+            .iter()
+            .copied()
+            .filter(|k| *k != first[0] && k != removed_later)
+            .collect();
+        after.push("k7");
+
+        let (second, next) = page(&after, "", Some(&cursor), 100);
+        assert!(next.is_none());
+
+        // No key from the first page repeats, and every surviving unlisted key shows up
+        for key in &second {
+            assert!(!first.contains(key), "{} listed twice", key);
+        }
+        for key in before.iter().filter(|k| !first.contains(&k.to_string()) && *k != removed_later) {
+            assert!(second.contains(&key.to_string()), "{} skipped", key);
+        }
+    }
+
+    #[test]
+    fn test_early_stop_returns_resumable_cursor() {
+        let keys = ["x1", "x2", "x3", "x4"];
+        let mut calls = 0;
+        let (first, cursor) = select_page(
+            listing(&keys),
+            "",
+            None,
+            10,
+            |e| Some(String::from_utf8(e.encrypted_key.clone()).unwrap()),
+            || {
+                calls += 1;
+                calls > 1
+            },
+        );
+        assert_eq!(first.len(), 2);
+
+        let (rest, next) = page(&keys, "", cursor.as_deref(), 10);
+        assert!(next.is_none());
+        let mut all: Vec<String> = first.into_iter().chain(rest).collect();
+        all.sort();
+        assert_eq!(all, vec!["x1", "x2", "x3", "x4"]);
+    }
+
+    #[test]
+    fn test_ttl_expiry() {
+        let now = 1_700_000_000;
+
+        assert!(expiry_after(0, now).is_err());
+        assert!(expiry_after(u64::MAX, now).is_err());
+        let expires_at = expiry_after(60, now).unwrap();
+        assert_eq!(expires_at, now + 60);
+
+        // Entries without TTL never expire
+        assert!(!is_expired(None, now));
+        assert_eq!(remaining_ttl(None, now), None);
+
+        assert!(!is_expired(Some(expires_at), now + 59));
+        assert_eq!(remaining_ttl(Some(expires_at), now + 59), Some(1));
+
+        // Expiry is inclusive: at expires_at the entry is already gone
+        assert!(is_expired(Some(expires_at), now + 60));
+        assert_eq!(remaining_ttl(Some(expires_at), now + 60), None);
+    }
+
+    #[test]
+    fn test_list_entry_expiry_is_optional() {
+        let entry: StoredKey = serde_json::from_str(
+            r#"{"key_hash": "ab", "encrypted_key": [1], "encrypted_value": [2]}"#,
+        )
+        .unwrap();
+        assert_eq!(entry.expires_at, None);
+
+        let entry: StoredKey = serde_json::from_str(
+            r#"{"key_hash": "ab", "encrypted_key": [1], "encrypted_value": [2], "expires_at": 1700000060}"#,
+        )
+        .unwrap();
+        assert_eq!(entry.expires_at, Some(1_700_000_060));
+    }
+
+    fn meta(id: &str, created_at: u64) -> SnapshotMeta {
+        SnapshotMeta {
+            id: id.to_string(),
+            prefix: "state:".to_string(),
+            created_at,
+            expires_at: created_at + SNAPSHOT_TTL_SECONDS,
+        }
+    }
+
+    /// Apply a restore plan to an in-memory store
+    fn restore_into(store: &mut std::collections::BTreeMap<String, Vec<u8>>, prefix: &str, entries: Vec<(String, Vec<u8>)>) {
+        let current = store.keys().filter(|k| k.starts_with(prefix)).cloned().collect();
+        let (to_delete, to_write) = plan_restore(current, entries);
+        for key in to_delete {
+            store.remove(&key);
+        }
+        store.extend(to_write);
+    }
+
+    #[test]
+    fn test_snapshot_restore_reverts_exactly() {
+        let mut store: std::collections::BTreeMap<String, Vec<u8>> = [
+            ("state:a", b"1".to_vec()),
+            ("state:b", b"2".to_vec()),
+            ("other:x", b"keep".to_vec()),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+
+        // Snapshot of "state:" round-trips through its stored encoding
+        let data = SnapshotData {
+            prefix: "state:".to_string(),
+            entries: store
+                .iter()
+                .filter(|(k, _)| k.starts_with("state:"))
+                .map(|(k, v)| (k.clone(), base64_encode(v)))
+                .collect(),
+        };
+        let data: SnapshotData = serde_json::from_slice(&serde_json::to_vec(&data).unwrap()).unwrap();
+        let captured: Vec<(String, Vec<u8>)> = data
+            .entries
+            .into_iter()
+            .map(|(k, v)| (k, base64_decode(&v).unwrap()))
+            .collect();
+        let before = store.clone();
+
+        // Mutate: change, delete, add inside the prefix and touch a key outside it
+        store.insert("state:a".to_string(), b"100".to_vec());
+        store.remove("state:b");
+        store.insert("state:c".to_string(), b"new".to_vec());
+        store.insert("other:x".to_string(), b"changed".to_vec());
+
+        restore_into(&mut store, &data.prefix, captured);
+
+        let mut expected = before;
+        expected.insert("other:x".to_string(), b"changed".to_vec()); // outside the prefix: untouched
+        assert_eq!(store, expected);
+    }
+
+    #[test]
+    fn test_interrupted_restore_completes_on_retry() {
+        let captured = vec![("state:a".to_string(), b"1".to_vec()), ("state:b".to_string(), b"2".to_vec())];
+        let mut store: std::collections::BTreeMap<String, Vec<u8>> = [
+            ("state:a", b"100".to_vec()),
+            ("state:c", b"new".to_vec()),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+
+        // First attempt failed after the delete and one write
+        store.remove("state:c");
+        store.insert("state:a".to_string(), b"1".to_vec());
+
+        restore_into(&mut store, "state:", captured.clone());
+        let restored = store.clone();
+        restore_into(&mut store, "state:", captured);
+        assert_eq!(store, restored);
+        assert_eq!(store.get("state:b").map(Vec::as_slice), Some(b"2".as_slice()));
+        assert!(!store.contains_key("state:c"));
+    }
+
+    #[test]
+    fn test_snapshot_size_capped() {
+        assert!(check_snapshot_bytes(MAX_SNAPSHOT_BYTES, "state:").is_ok());
+        let err = check_snapshot_bytes(MAX_SNAPSHOT_BYTES + 1, "state:").unwrap_err();
+        assert!(err.to_string().contains("exceed"));
+    }
+
+    #[test]
+    fn test_snapshot_index_bounded_and_expiring() {
+        let now = 1_700_000_000;
+        let index = vec![
+            meta("expired", now - SNAPSHOT_TTL_SECONDS - 1),
+            meta("old", now - 30),
+            meta("mid", now - 20),
+            meta("new", now - 10),
+        ];
+
+        // Expired entries are dropped silently, the oldest live ones are evicted
+        let (kept, evicted) = prune_snapshot_index(index.clone(), now, 2);
+        assert_eq!(kept.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(), vec!["mid", "new"]);
+        assert_eq!(evicted.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(), vec!["old"]);
+
+        let (kept, evicted) = prune_snapshot_index(index, now, MAX_LIVE_SNAPSHOTS - 1);
+        assert_eq!(kept.len(), 3);
+        assert!(evicted.is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_keys_reserved_for_writes() {
+        assert!(check_user_key("state:a").is_ok());
+        assert!(check_user_key("__outlayer_snapshot:index").is_err());
+        assert!(check_user_key("__outlayer_snapshot:abc").is_err());
+    }
+
+    #[test]
+    fn test_snapshot_keys_hidden_from_listing() {
+        let (keys, _) = page(&["state:a", "__outlayer_snapshot:index", "__outlayer_snapshot:abc"], "", None, 10);
+        assert_eq!(keys, vec!["state:a".to_string()]);
+    }
+
+    #[test]
+    fn test_append_respects_max_value_size() {
+        assert_eq!(appended(None, b"first").unwrap(), b"first");
+        assert_eq!(appended(Some(b"a,".as_slice()), b"b").unwrap(), b"a,b");
+
+        let full = vec![0u8; MAX_APPEND_VALUE_SIZE - 1];
+        assert_eq!(appended(Some(full.as_slice()), b"x").unwrap().len(), MAX_APPEND_VALUE_SIZE);
+        let err = appended(Some(full.as_slice()), b"xy").unwrap_err();
+        assert!(err.to_string().contains("max 1048576"), "unexpected error: {}", err);
+    }
+
+    #[test]
+    fn test_usage_splits_project_and_worker_bytes() {
+        let project: AccountUsage = serde_json::from_str(r#"{"key_count": 3, "total_bytes": 1200}"#).unwrap();
+        let worker: AccountUsage = serde_json::from_str("{}").unwrap();
+        assert_eq!(
+            StorageUsage::combine(&project, &worker),
+            StorageUsage {
+                key_count: 3,
+                total_bytes: 1200,
+                worker_bytes: 0,
+                project_bytes: 1200,
+            }
+        );
+
+        let worker = AccountUsage { key_count: 2, total_bytes: 300 };
+        let usage = StorageUsage::combine(&project, &worker);
+        assert_eq!((usage.key_count, usage.total_bytes, usage.worker_bytes), (5, 1500, 300));
+    }
+
+    #[test]
+    fn test_missing_route_is_unsupported() {
+        for status in [reqwest::StatusCode::NOT_FOUND, reqwest::StatusCode::METHOD_NOT_ALLOWED] {
+            let err = check_route_served(status, "POST /storage/usage").unwrap_err();
+            let unsupported = err.downcast_ref::<UnsupportedByCoordinator>().unwrap();
+            assert_eq!(unsupported.route, "POST /storage/usage");
+            assert_eq!(err.to_string(), "POST /storage/usage is not supported by this coordinator");
+        }
+        assert!(check_route_served(reqwest::StatusCode::INTERNAL_SERVER_ERROR, "POST /storage/usage").is_ok());
+    }
+
+    #[test]
+    fn test_byte_range_clamps_to_value() {
+        let log = b"0123456789";
+        assert_eq!(byte_range(log, 0, 4), b"0123");
+        assert_eq!(byte_range(log, 6, 100), b"6789");
+        assert_eq!(byte_range(log, 10, 1), b"");
+        assert_eq!(byte_range(log, u64::MAX, u64::MAX), b"");
+        assert_eq!(byte_range(b"", 0, 10), b"");
+    }
+}
@@ -1,12 +1,18 @@
 //! Storage host functions for WASM components
 //!
 //! Implements the `near:storage/api` WIT interface.
+//!
+//! The current interface (`@0.2.0`) is linked through bindgen. Components built against
+//! `@0.1.0` import a smaller function set under a different name, so that set is also
+//! registered by hand with `func_wrap`, forwarding to the same implementation.
 
 use anyhow::Result;
 use tracing::debug;
 use wasmtime::component::Linker;
+use wasmtime::StoreContextMut;
 
-use super::client::{StorageClient, StorageConfig};
+use super::client::{StorageClient, StorageConfig, TransactionsDisabled, Visibility};
+use super::transaction::Transactions;
 
 // Generate bindings from WIT (storage is now separate package near:storage)
 wasmtime::component::bindgen!({
@@ -17,6 +23,8 @@ wasmtime::component::bindgen!({
 /// Host state for storage functions
 pub struct StorageHostState {
     client: StorageClient,
+    /// Open transactions; dropped uncommitted when the execution ends
+    transactions: Transactions,
 }
 
 impl StorageHostState {
@@ -24,12 +32,15 @@ impl StorageHostState {
     #[allow(dead_code)]
     pub fn new(config: StorageConfig) -> Result<Self> {
         let client = StorageClient::new(config)?;
-        Ok(Self { client })
+        Ok(Self::from_client(client))
     }
 
     /// Create new storage host state from existing client
     pub fn from_client(client: StorageClient) -> Self {
-        Self { client }
+        Self {
+            client,
+            transactions: Transactions::default(),
+        }
     }
 }
 
@@ -69,6 +80,30 @@ impl near::storage::api::Host for StorageHostState {
         }
     }
 
+    fn get_many(&mut self, keys: Vec<String>) -> (Vec<Option<Vec<u8>>>, String) {
+        debug!("storage::get_many keys={}", keys.len());
+        match self.client.get_many(&keys) {
+            Ok(values) => (values, String::new()),
+            Err(e) => (Vec::new(), e.to_string()),
+        }
+    }
+
+    fn set_many(&mut self, entries: Vec<(String, Vec<u8>)>) -> String {
+        debug!("storage::set_many entries={}", entries.len());
+        match self.client.set_many(&entries) {
+            Ok(()) => String::new(),
+            Err(e) => format!("{:#}", e),
+        }
+    }
+
+    fn list_keys_paged(&mut self, prefix: String, cursor: Option<String>, limit: u32) -> (Vec<String>, Option<String>, String) {
+        debug!("storage::list_keys_paged prefix={}, cursor={:?}, limit={}", prefix, cursor, limit);
+        match self.client.list_keys_paged(&prefix, cursor.as_deref(), limit) {
+            Ok((keys, next_cursor)) => (keys, next_cursor, String::new()),
+            Err(e) => (Vec::new(), None, e.to_string()),
+        }
+    }
+
     fn set_worker(&mut self, key: String, value: Vec<u8>, is_encrypted: Option<bool>) -> String {
         let encrypted = is_encrypted.unwrap_or(true);
         debug!("storage::set_worker key={}, value_len={}, is_encrypted={}", key, value.len(), encrypted);
@@ -87,6 +122,120 @@ impl near::storage::api::Host for StorageHostState {
         }
     }
 
+    fn get_worker_visibility(&mut self, key: String) -> (Option<bool>, String) {
+        debug!("storage::get_worker_visibility key={}", key);
+        match self.client.worker_visibility(&key) {
+            Ok(visibility) => (visibility.map(|v| v == Visibility::Private), String::new()),
+            Err(e) => (None, e.to_string()),
+        }
+    }
+
+    fn set_worker_visibility(&mut self, key: String, is_encrypted: bool) -> (bool, String) {
+        debug!("storage::set_worker_visibility key={}, is_encrypted={}", key, is_encrypted);
+        let visibility = if is_encrypted { Visibility::Private } else { Visibility::Public };
+        match self.client.set_worker_visibility(&key, visibility) {
+            Ok(existed) => (existed, String::new()),
+            Err(e) => (false, e.to_string()),
+        }
+    }
+
+    fn list_public_keys(
+        &mut self,
+        project_uuid: String,
+        prefix: String,
+        cursor: Option<String>,
+        limit: u32,
+    ) -> (Vec<String>, Option<String>, String) {
+        debug!(
+            "storage::list_public_keys project_uuid={}, prefix={}, cursor={:?}, limit={}",
+            project_uuid, prefix, cursor, limit
+        );
+        match self.client.list_public_keys(&project_uuid, &prefix, cursor.as_deref(), limit) {
+            Ok((keys, next_cursor)) => (keys, next_cursor, String::new()),
+            Err(e) => (Vec::new(), None, e.to_string()),
+        }
+    }
+
+    fn set_with_ttl(&mut self, key: String, value: Vec<u8>, ttl_seconds: u64) -> String {
+        debug!("storage::set_with_ttl key={}, value_len={}, ttl={}s", key, value.len(), ttl_seconds);
+        match self.client.set_with_ttl(&key, &value, ttl_seconds) {
+            Ok(()) => String::new(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn set_worker_with_ttl(&mut self, key: String, value: Vec<u8>, ttl_seconds: u64) -> String {
+        debug!("storage::set_worker_with_ttl key={}, value_len={}, ttl={}s", key, value.len(), ttl_seconds);
+        match self.client.set_worker_with_ttl(&key, &value, ttl_seconds) {
+            Ok(()) => String::new(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn ttl(&mut self, key: String) -> (Option<u64>, String) {
+        debug!("storage::ttl key={}", key);
+        match self.client.ttl(&key) {
+            Ok(ttl) => (ttl, String::new()),
+            Err(e) => (None, e.to_string()),
+        }
+    }
+
+    fn ttl_worker(&mut self, key: String) -> (Option<u64>, String) {
+        debug!("storage::ttl_worker key={}", key);
+        match self.client.ttl_worker(&key) {
+            Ok(ttl) => (ttl, String::new()),
+            Err(e) => (None, e.to_string()),
+        }
+    }
+
+    fn snapshot(&mut self, prefix: String) -> (String, String) {
+        debug!("storage::snapshot prefix={}", prefix);
+        match self.client.snapshot(&prefix) {
+            Ok(id) => (id, String::new()),
+            Err(e) => (String::new(), e.to_string()),
+        }
+    }
+
+    fn restore(&mut self, snapshot_id: String) -> String {
+        debug!("storage::restore snapshot_id={}", snapshot_id);
+        match self.client.restore(&snapshot_id) {
+            Ok(()) => String::new(),
+            Err(e) => format!("{:#}", e),
+        }
+    }
+
+    fn usage(&mut self) -> (near::storage::api::StorageUsage, String) {
+        debug!("storage::usage");
+        match self.client.usage() {
+            Ok(usage) => (
+                near::storage::api::StorageUsage {
+                    key_count: usage.key_count,
+                    total_bytes: usage.total_bytes,
+                    worker_bytes: usage.worker_bytes,
+                    project_bytes: usage.project_bytes,
+                },
+                String::new(),
+            ),
+            Err(e) => (
+                near::storage::api::StorageUsage {
+                    key_count: 0,
+                    total_bytes: 0,
+                    worker_bytes: 0,
+                    project_bytes: 0,
+                },
+                e.to_string(),
+            ),
+        }
+    }
+
+    fn key_bytes(&mut self, key: String) -> (Option<u64>, String) {
+        debug!("storage::key_bytes key={}", key);
+        match self.client.key_bytes(&key) {
+            Ok(bytes) => (bytes, String::new()),
+            Err(e) => (None, e.to_string()),
+        }
+    }
+
     fn get_by_version(&mut self, key: String, wasm_hash: String) -> (Vec<u8>, String) {
         debug!("storage::get_by_version key={}, wasm_hash={}", key, wasm_hash);
         match self.client.get_by_version(&key, &wasm_hash) {
@@ -130,6 +279,22 @@ impl near::storage::api::Host for StorageHostState {
         }
     }
 
+    fn compare_and_delete(&mut self, key: String, expected: Vec<u8>) -> (bool, String) {
+        debug!("storage::compare_and_delete key={}, expected_len={}", key, expected.len());
+        match self.client.compare_and_delete(&key, &expected) {
+            Ok(deleted) => (deleted, String::new()),
+            Err(e) => (false, e.to_string()),
+        }
+    }
+
+    fn compare_and_delete_worker(&mut self, key: String, expected: Vec<u8>) -> (bool, String) {
+        debug!("storage::compare_and_delete_worker key={}, expected_len={}", key, expected.len());
+        match self.client.compare_and_delete_worker(&key, &expected) {
+            Ok(deleted) => (deleted, String::new()),
+            Err(e) => (false, e.to_string()),
+        }
+    }
+
     fn increment(&mut self, key: String, delta: i64) -> (i64, String) {
         debug!("storage::increment key={}, delta={}", key, delta);
         match self.client.increment(&key, delta) {
@@ -145,12 +310,176 @@ impl near::storage::api::Host for StorageHostState {
             Err(e) => (0, e.to_string()),
         }
     }
+
+    // ==================== Append-Only Values ====================
+
+    fn append(&mut self, key: String, data: Vec<u8>) -> (u64, String) {
+        debug!("storage::append key={}, data_len={}", key, data.len());
+        match self.client.append(&key, &data) {
+            Ok(new_len) => (new_len, String::new()),
+            Err(e) => (0, e.to_string()),
+        }
+    }
+
+    fn append_worker(&mut self, key: String, data: Vec<u8>) -> (u64, String) {
+        debug!("storage::append_worker key={}, data_len={}", key, data.len());
+        match self.client.append_worker(&key, &data) {
+            Ok(new_len) => (new_len, String::new()),
+            Err(e) => (0, e.to_string()),
+        }
+    }
+
+    fn read_range(&mut self, key: String, offset: u64, len: u64) -> (Vec<u8>, String) {
+        debug!("storage::read_range key={}, offset={}, len={}", key, offset, len);
+        match self.client.read_range(&key, offset, len) {
+            Ok(bytes) => (bytes, String::new()),
+            Err(e) => (Vec::new(), e.to_string()),
+        }
+    }
+
+    // ==================== Transactions ====================
+
+    fn txn_begin(&mut self) -> (u32, String) {
+        if !self.client.transactions_enabled() {
+            return (0, TransactionsDisabled.to_string());
+        }
+        match self.transactions.begin() {
+            Ok(txn) => {
+                debug!("storage::txn_begin txn={}", txn);
+                (txn, String::new())
+            }
+            Err(e) => (0, e.to_string()),
+        }
+    }
+
+    fn txn_set(&mut self, txn: u32, key: String, value: Vec<u8>) -> String {
+        debug!("storage::txn_set txn={}, key={}, value_len={}", txn, key, value.len());
+        match self.transactions.get_mut(txn).and_then(|t| t.set(key, value)) {
+            Ok(()) => String::new(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn txn_delete(&mut self, txn: u32, key: String) -> String {
+        debug!("storage::txn_delete txn={}, key={}", txn, key);
+        match self.transactions.get_mut(txn).and_then(|t| t.delete(key)) {
+            Ok(()) => String::new(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn txn_get(&mut self, txn: u32, key: String) -> (Option<Vec<u8>>, String) {
+        debug!("storage::txn_get txn={}, key={}", txn, key);
+        let buffered = match self.transactions.get(txn) {
+            Ok(t) => t.buffered(&key).map(|value| value.map(<[u8]>::to_vec)),
+            Err(e) => return (None, e.to_string()),
+        };
+        if let Some(value) = buffered {
+            return (value, String::new());
+        }
+        match self.client.get(&key) {
+            Ok(value) => (value, String::new()),
+            Err(e) => (None, e.to_string()),
+        }
+    }
+
+    fn txn_commit(&mut self, txn: u32) -> String {
+        let writes = match self.transactions.close(txn) {
+            Ok(t) => t.into_writes(),
+            Err(e) => return e.to_string(),
+        };
+        debug!("storage::txn_commit txn={}, keys={}", txn, writes.len());
+        match self.client.commit_transaction(&writes) {
+            Ok(()) => String::new(),
+            Err(e) => format!("{:#}", e),
+        }
+    }
+
+    fn txn_rollback(&mut self, txn: u32) {
+        debug!("storage::txn_rollback txn={}", txn);
+        // Rolling back an unknown or already closed handle is a no-op
+        let _ = self.transactions.close(txn);
+    }
 }
 
+/// Previous storage interface, still linked for components built against it
+pub const STORAGE_INTERFACE_V0_1: &str = "near:storage/api@0.1.0";
+
 /// Add storage host functions to a wasmtime component linker
+///
+/// Links both `near:storage/api@0.2.0` and the `@0.1.0` subset.
 pub fn add_storage_to_linker<T: Send + 'static>(
     linker: &mut Linker<T>,
     get_state: impl Fn(&mut T) -> &mut StorageHostState + Send + Sync + Copy + 'static,
 ) -> Result<()> {
-    near::storage::api::add_to_linker(linker, get_state)
+    near::storage::api::add_to_linker(linker, get_state)?;
+    add_storage_v0_1_to_linker(linker, get_state)
+}
+
+/// Register the `near:storage/api@0.1.0` functions
+fn add_storage_v0_1_to_linker<T: Send + 'static>(
+    linker: &mut Linker<T>,
+    get_state: impl Fn(&mut T) -> &mut StorageHostState + Send + Sync + Copy + 'static,
+) -> Result<()> {
+    use near::storage::api::Host;
+
+    let mut instance = linker.instance(STORAGE_INTERFACE_V0_1)?;
+    instance.func_wrap("set", move |mut store: StoreContextMut<'_, T>, (key, value): (String, Vec<u8>)| {
+        Ok((get_state(store.data_mut()).set(key, value),))
+    })?;
+    instance.func_wrap("get", move |mut store: StoreContextMut<'_, T>, (key,): (String,)| {
+        Ok((get_state(store.data_mut()).get(key),))
+    })?;
+    instance.func_wrap("has", move |mut store: StoreContextMut<'_, T>, (key,): (String,)| {
+        Ok((get_state(store.data_mut()).has(key),))
+    })?;
+    instance.func_wrap("delete", move |mut store: StoreContextMut<'_, T>, (key,): (String,)| {
+        Ok((get_state(store.data_mut()).delete(key),))
+    })?;
+    instance.func_wrap("list-keys", move |mut store: StoreContextMut<'_, T>, (prefix,): (String,)| {
+        Ok((get_state(store.data_mut()).list_keys(prefix),))
+    })?;
+    instance.func_wrap(
+        "set-if-absent",
+        move |mut store: StoreContextMut<'_, T>, (key, value): (String, Vec<u8>)| {
+            Ok((get_state(store.data_mut()).set_if_absent(key, value),))
+        },
+    )?;
+    instance.func_wrap(
+        "set-if-equals",
+        move |mut store: StoreContextMut<'_, T>, (key, expected, new_value): (String, Vec<u8>, Vec<u8>)| {
+            Ok((get_state(store.data_mut()).set_if_equals(key, expected, new_value),))
+        },
+    )?;
+    instance.func_wrap("increment", move |mut store: StoreContextMut<'_, T>, (key, delta): (String, i64)| {
+        Ok((get_state(store.data_mut()).increment(key, delta),))
+    })?;
+    instance.func_wrap("decrement", move |mut store: StoreContextMut<'_, T>, (key, delta): (String, i64)| {
+        Ok((get_state(store.data_mut()).decrement(key, delta),))
+    })?;
+    instance.func_wrap(
+        "set-worker",
+        move |mut store: StoreContextMut<'_, T>, (key, value, is_encrypted): (String, Vec<u8>, Option<bool>)| {
+            Ok((get_state(store.data_mut()).set_worker(key, value, is_encrypted),))
+        },
+    )?;
+    instance.func_wrap(
+        "get-worker",
+        move |mut store: StoreContextMut<'_, T>, (key, project): (String, Option<String>)| {
+            Ok((get_state(store.data_mut()).get_worker(key, project),))
+        },
+    )?;
+    instance.func_wrap(
+        "get-by-version",
+        move |mut store: StoreContextMut<'_, T>, (key, wasm_hash): (String, String)| {
+            Ok((get_state(store.data_mut()).get_by_version(key, wasm_hash),))
+        },
+    )?;
+    instance.func_wrap("clear-all", move |mut store: StoreContextMut<'_, T>, (): ()| {
+        Ok((get_state(store.data_mut()).clear_all(),))
+    })?;
+    instance.func_wrap("clear-version", move |mut store: StoreContextMut<'_, T>, (wasm_hash,): (String,)| {
+        Ok((get_state(store.data_mut()).clear_version(wasm_hash),))
+    })?;
+    Ok(())
 }
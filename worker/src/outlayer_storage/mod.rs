@@ -31,6 +31,7 @@
 
 pub mod client;
 pub mod host_functions;
+pub mod transaction;
 
 pub use client::{StorageClient, StorageConfig};
 pub use host_functions::{add_storage_to_linker, StorageHostState};
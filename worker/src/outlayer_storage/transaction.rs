@@ -0,0 +1,161 @@
+//! Host-side storage transactions
+//!
+//! A transaction buffers writes and deletes in worker memory under a handle. Reads
+//! through the handle see the buffered state first, then fall back to storage. On commit
+//! the buffer is sent to the coordinator as one write that is applied all-or-nothing;
+//! on rollback (or when the execution ends) it is simply dropped.
+//!
+//! Nothing is locked while a transaction is open: other executions don't see its writes
+//! until commit, keys it read may change underneath it, and the last commit wins.
+
+use anyhow::Result;
+use std::collections::{BTreeMap, HashMap};
+
+use super::client::MAX_BATCH_SIZE;
+
+/// Maximum number of keys one transaction may write or delete
+pub const MAX_TXN_KEYS: usize = MAX_BATCH_SIZE;
+
+/// Transactions one execution may keep open at the same time
+pub const MAX_OPEN_TXNS: usize = 16;
+
+/// Buffered writes of one transaction, one entry per key (`None` = delete)
+#[derive(Debug, Default)]
+pub struct Transaction {
+    writes: BTreeMap<String, Option<Vec<u8>>>,
+}
+
+impl Transaction {
+    /// Buffer a write; a later write to the same key replaces it
+    pub fn set(&mut self, key: String, value: Vec<u8>) -> Result<()> {
+        self.buffer(key, Some(value))
+    }
+
+    /// Buffer a delete
+    pub fn delete(&mut self, key: String) -> Result<()> {
+        self.buffer(key, None)
+    }
+
+    /// Buffered state of `key`: `None` if the transaction hasn't touched it,
+    /// `Some(None)` if it was deleted
+    pub fn buffered(&self, key: &str) -> Option<Option<&[u8]>> {
+        self.writes.get(key).map(|value| value.as_deref())
+    }
+
+    /// Buffered writes in key order, consuming the transaction
+    pub fn into_writes(self) -> Vec<(String, Option<Vec<u8>>)> {
+        self.writes.into_iter().collect()
+    }
+
+    fn buffer(&mut self, key: String, value: Option<Vec<u8>>) -> Result<()> {
+        if !self.writes.contains_key(&key) && self.writes.len() >= MAX_TXN_KEYS {
+            anyhow::bail!("Transaction too large: more than {} keys", MAX_TXN_KEYS);
+        }
+        self.writes.insert(key, value);
+        Ok(())
+    }
+}
+
+/// Open transactions of one execution, by handle
+#[derive(Debug, Default)]
+pub struct Transactions {
+    open: HashMap<u32, Transaction>,
+    /// Last handle given out; handles are never reused within an execution
+    last_handle: u32,
+}
+
+impl Transactions {
+    /// Open a new transaction and return its handle
+    pub fn begin(&mut self) -> Result<u32> {
+        if self.open.len() >= MAX_OPEN_TXNS {
+            anyhow::bail!("Too many open transactions (max {})", MAX_OPEN_TXNS);
+        }
+        let Some(handle) = self.last_handle.checked_add(1) else {
+            anyhow::bail!("Transaction handles exhausted");
+        };
+        self.last_handle = handle;
+        self.open.insert(handle, Transaction::default());
+        Ok(handle)
+    }
+
+    pub fn get(&self, handle: u32) -> Result<&Transaction> {
+        self.open
+            .get(&handle)
+            .ok_or_else(|| anyhow::anyhow!("Unknown or closed transaction {}", handle))
+    }
+
+    pub fn get_mut(&mut self, handle: u32) -> Result<&mut Transaction> {
+        self.open
+            .get_mut(&handle)
+            .ok_or_else(|| anyhow::anyhow!("Unknown or closed transaction {}", handle))
+    }
+
+    /// Close a transaction, returning its buffered writes
+    pub fn close(&mut self, handle: u32) -> Result<Transaction> {
+        self.open
+            .remove(&handle)
+            .ok_or_else(|| anyhow::anyhow!("Unknown or closed transaction {}", handle))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_see_buffered_writes() {
+        let mut txn = Transaction::default();
+        assert_eq!(txn.buffered("bid:1"), None);
+
+        txn.set("bid:1".to_string(), b"100".to_vec()).unwrap();
+        assert_eq!(txn.buffered("bid:1"), Some(Some(b"100".as_slice())));
+
+        txn.set("bid:1".to_string(), b"90".to_vec()).unwrap();
+        txn.delete("ask:1".to_string()).unwrap();
+        assert_eq!(txn.buffered("bid:1"), Some(Some(b"90".as_slice())));
+        assert_eq!(txn.buffered("ask:1"), Some(None));
+
+        assert_eq!(
+            txn.into_writes(),
+            vec![
+                ("ask:1".to_string(), None),
+                ("bid:1".to_string(), Some(b"90".to_vec())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_key_limit_counts_distinct_keys() {
+        let mut txn = Transaction::default();
+        for i in 0..MAX_TXN_KEYS {
+            txn.set(format!("k{}", i), vec![1]).unwrap();
+        }
+        // Rewriting a buffered key doesn't count as a new one
+        txn.delete("k0".to_string()).unwrap();
+        assert!(txn.set("one-too-many".to_string(), vec![1]).is_err());
+    }
+
+    #[test]
+    fn test_closed_handles_are_rejected() {
+        let mut txns = Transactions::default();
+        let first = txns.begin().unwrap();
+        let second = txns.begin().unwrap();
+        assert_ne!(first, second);
+
+        txns.get_mut(first).unwrap().set("a".to_string(), vec![1]).unwrap();
+        assert_eq!(txns.close(first).unwrap().into_writes().len(), 1);
+        assert!(txns.get(first).is_err());
+        assert!(txns.close(first).is_err());
+        assert!(txns.get(second).is_ok());
+    }
+
+    #[test]
+    fn test_open_transactions_are_bounded() {
+        let mut txns = Transactions::default();
+        let handles: Vec<u32> = (0..MAX_OPEN_TXNS).map(|_| txns.begin().unwrap()).collect();
+        assert!(txns.begin().is_err());
+
+        txns.close(handles[0]).unwrap();
+        assert!(txns.begin().is_ok());
+    }
+}
@@ -0,0 +1,295 @@
+//! Pre-flight TEE diagnostics (`--check-measurements` / `CHECK_MEASUREMENTS=true`)
+//!
+//! Unapproved measurements otherwise only show up at registration, after the whole
+//! startup sequence. This mode generates a TDX quote the same way registration does,
+//! prints its MRTD and RTMR0-3, asks the register contract whether they are approved and,
+//! if not, shows which fields differ from each approved entry. It then fetches the
+//! collateral from Phala and checks the register contract has it for this platform.
+//! The worker exits afterwards without registering or polling for jobs.
+
+use anyhow::{Context, Result};
+use near_jsonrpc_client::{methods, JsonRpcClient};
+use near_primitives::types::{AccountId, BlockReference, Finality};
+use near_primitives::views::QueryRequest;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use tracing::{error, info, warn};
+
+use crate::collateral_fetcher::CollateralInfo;
+use crate::config::Config;
+use crate::tdx_attestation::{extract_all_measurements_from_quote_hex, TdxClient, TdxMeasurements};
+
+/// Register contract `ApprovedMeasurements`
+#[derive(Debug, Clone, Deserialize)]
+struct ApprovedMeasurements {
+    mrtd: String,
+    rtmr0: String,
+    rtmr1: String,
+    rtmr2: String,
+    rtmr3: String,
+}
+
+/// Register contract `ApprovedMeasurementEntry`
+#[derive(Debug, Clone, Deserialize)]
+struct ApprovedMeasurementEntry {
+    measurements: ApprovedMeasurements,
+    label: String,
+    /// Block timestamp (ns)
+    expires_at: Option<u64>,
+}
+
+/// True if the worker was started in pre-flight mode
+pub fn requested() -> bool {
+    std::env::args().skip(1).any(|arg| arg == "--check-measurements")
+        || std::env::var("CHECK_MEASUREMENTS").is_ok_and(|v| v == "true" || v == "1")
+}
+
+/// Run the diagnostics; fails if the measurements are not approved
+pub async fn run(config: &Config) -> Result<()> {
+    let register_contract_id = &config.operator_account_id;
+    let rpc_client = JsonRpcClient::connect(&config.near_rpc_url);
+    let tdx_client = TdxClient::new(config.tee_mode.clone());
+
+    info!("🩺 Pre-flight TEE check against register contract {}", register_contract_id);
+
+    let quote_hex = tdx_client
+        .generate_registration_quote(&[0u8; 32])
+        .await
+        .context("Failed to generate TDX quote")?;
+    let measurements = extract_all_measurements_from_quote_hex(&quote_hex)
+        .context("Quote is too short to contain TDX measurements (not running in a TDX VM?)")?;
+
+    info!("📏 Measurements of this worker:");
+    info!("   MRTD:  {}", measurements.mrtd);
+    info!("   RTMR0: {}", measurements.rtmr0);
+    info!("   RTMR1: {}", measurements.rtmr1);
+    info!("   RTMR2: {}", measurements.rtmr2);
+    info!("   RTMR3: {}", measurements.rtmr3);
+
+    let approved: bool = view(
+        &rpc_client,
+        register_contract_id,
+        "is_measurements_approved",
+        serde_json::json!({
+            "measurements": {
+                "mrtd": measurements.mrtd,
+                "rtmr0": measurements.rtmr0,
+                "rtmr1": measurements.rtmr1,
+                "rtmr2": measurements.rtmr2,
+                "rtmr3": measurements.rtmr3,
+            }
+        }),
+    )
+    .await?;
+
+    if approved {
+        info!("✅ Measurements are approved");
+    } else {
+        error!("❌ Measurements are NOT approved");
+        let entries: Vec<ApprovedMeasurementEntry> =
+            view(&rpc_client, register_contract_id, "get_approved_measurements", serde_json::json!({})).await?;
+        report_mismatches(&measurements, &entries);
+        error!(
+            "   To approve, run: near call {} add_approved_measurements '{{\"measurements\":{{\"mrtd\":\"{}\",\"rtmr0\":\"{}\",\"rtmr1\":\"{}\",\"rtmr2\":\"{}\",\"rtmr3\":\"{}\"}}}}' --accountId <owner>",
+            register_contract_id,
+            measurements.mrtd,
+            measurements.rtmr0,
+            measurements.rtmr1,
+            measurements.rtmr2,
+            measurements.rtmr3
+        );
+    }
+
+    // Collateral problems don't change the verdict, they are reported for the operator
+    check_collateral(&tdx_client, &rpc_client, register_contract_id).await;
+
+    if !approved {
+        anyhow::bail!("Worker measurements are not approved on {}", register_contract_id);
+    }
+    Ok(())
+}
+
+fn report_mismatches(measurements: &TdxMeasurements, entries: &[ApprovedMeasurementEntry]) {
+    if entries.is_empty() {
+        error!("   The register contract has no approved measurements");
+        return;
+    }
+    let now_ns = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64;
+
+    error!("   Compared with the {} approved entries:", entries.len());
+    for (index, entry) in entries.iter().enumerate() {
+        let expired = if entry.expires_at.is_some_and(|t| t <= now_ns) {
+            " (expired)"
+        } else {
+            ""
+        };
+        let mismatched = mismatched_fields(measurements, &entry.measurements);
+        if mismatched.is_empty() {
+            error!("   [{}] {}{}: all fields match", index, entry.label, expired);
+            continue;
+        }
+        error!(
+            "   [{}] {}{}: {} differ",
+            index,
+            entry.label,
+            expired,
+            mismatched.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(", ")
+        );
+        for (name, approved) in mismatched {
+            error!("       {:<5} approved {}", name, approved);
+        }
+    }
+}
+
+/// Fields of `approved` that differ from the worker's measurements, with the approved value
+fn mismatched_fields<'a>(
+    measurements: &TdxMeasurements,
+    approved: &'a ApprovedMeasurements,
+) -> Vec<(&'static str, &'a str)> {
+    [
+        ("MRTD", &measurements.mrtd, &approved.mrtd),
+        ("RTMR0", &measurements.rtmr0, &approved.rtmr0),
+        ("RTMR1", &measurements.rtmr1, &approved.rtmr1),
+        ("RTMR2", &measurements.rtmr2, &approved.rtmr2),
+        ("RTMR3", &measurements.rtmr3, &approved.rtmr3),
+    ]
+    .into_iter()
+    .filter(|(_, ours, theirs)| !ours.eq_ignore_ascii_case(theirs))
+    .map(|(name, _, theirs)| (name, theirs.as_str()))
+    .collect()
+}
+
+async fn check_collateral(tdx_client: &TdxClient, rpc_client: &JsonRpcClient, register_contract_id: &AccountId) {
+    let collateral_json = match crate::generate_dummy_quote_and_fetch_collateral(tdx_client).await {
+        Ok(json) => json,
+        Err(e) => {
+            warn!("⚠️  Failed to fetch collateral from Phala: {:#}", e);
+            return;
+        }
+    };
+    info!("📋 Collateral from Phala:");
+    info!("{}", collateral_json);
+
+    let info = match CollateralInfo::parse(&collateral_json) {
+        Ok(info) => info,
+        Err(e) => {
+            warn!("⚠️  Could not read the collateral's platform: {:#}", e);
+            return;
+        }
+    };
+    info!("   FMSPC: {}, nextUpdate: {}", info.fmspc, info.next_update);
+
+    let on_chain: Vec<String> =
+        match view(rpc_client, register_contract_id, "get_collaterals", serde_json::json!({})).await {
+            Ok(collaterals) => collaterals,
+            Err(e) => {
+                warn!("⚠️  Failed to read collaterals from {}: {:#}", register_contract_id, e);
+                return;
+            }
+        };
+    let slot = on_chain.iter().enumerate().find_map(|(index, json)| {
+        CollateralInfo::parse(json)
+            .ok()
+            .filter(|slot| slot.fmspc == info.fmspc)
+            .map(|slot| (index, slot.next_update))
+    });
+    match slot {
+        Some((index, next_update)) => info!(
+            "✅ Register contract has collateral for this platform in slot {} (nextUpdate {})",
+            index, next_update
+        ),
+        None => warn!(
+            "⚠️  Register contract has no collateral for FMSPC {}: cache it with update_collateral (index {})",
+            info.fmspc,
+            on_chain.len()
+        ),
+    }
+}
+
+/// Call a view method at final finality and parse its JSON result
+async fn view<T: DeserializeOwned>(
+    rpc_client: &JsonRpcClient,
+    account_id: &AccountId,
+    method_name: &str,
+    args: serde_json::Value,
+) -> Result<T> {
+    let request = methods::query::RpcQueryRequest {
+        block_reference: BlockReference::Finality(Finality::Final),
+        request: QueryRequest::CallFunction {
+            account_id: account_id.clone(),
+            method_name: method_name.to_string(),
+            args: args.to_string().into_bytes().into(),
+        },
+    };
+
+    let response = rpc_client
+        .call(request)
+        .await
+        .with_context(|| format!("Failed to call {}", method_name))?;
+
+    if let near_jsonrpc_primitives::types::query::QueryResponseKind::CallResult(result) = response.kind {
+        serde_json::from_slice(&result.result).with_context(|| format!("Failed to parse {} result", method_name))
+    } else {
+        anyhow::bail!("Unexpected response kind from {}", method_name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measurements(fill: char) -> TdxMeasurements {
+        let value = fill.to_string().repeat(96);
+        TdxMeasurements {
+            mrtd: value.clone(),
+            rtmr0: value.clone(),
+            rtmr1: value.clone(),
+            rtmr2: value.clone(),
+            rtmr3: value,
+        }
+    }
+
+    fn approved(m: &TdxMeasurements) -> ApprovedMeasurements {
+        ApprovedMeasurements {
+            mrtd: m.mrtd.clone(),
+            rtmr0: m.rtmr0.clone(),
+            rtmr1: m.rtmr1.clone(),
+            rtmr2: m.rtmr2.clone(),
+            rtmr3: m.rtmr3.clone(),
+        }
+    }
+
+    #[test]
+    fn test_mismatched_fields() {
+        let ours = measurements('a');
+        assert!(mismatched_fields(&ours, &approved(&ours)).is_empty());
+        assert!(mismatched_fields(&ours, &approved(&measurements('A'))).is_empty());
+
+        let mut other = approved(&ours);
+        other.rtmr0 = "b".repeat(96);
+        other.rtmr3 = "c".repeat(96);
+        let mismatched = mismatched_fields(&ours, &other);
+        assert_eq!(
+            mismatched.iter().map(|(name, _)| *name).collect::<Vec<_>>(),
+            vec!["RTMR0", "RTMR3"]
+        );
+        assert_eq!(mismatched[1].1, other.rtmr3);
+    }
+
+    #[test]
+    fn test_parses_contract_entries() {
+        let entries: Vec<ApprovedMeasurementEntry> = serde_json::from_value(serde_json::json!([{
+            "measurements": { "mrtd": "aa", "rtmr0": "bb", "rtmr1": "cc", "rtmr2": "dd", "rtmr3": "ee" },
+            "label": "v1.2.0",
+            "added_at": 1,
+            "expires_at": null
+        }]))
+        .unwrap();
+        assert_eq!(entries[0].label, "v1.2.0");
+        assert_eq!(entries[0].measurements.rtmr3, "ee");
+        assert_eq!(entries[0].expires_at, None);
+    }
+}
@@ -0,0 +1,200 @@
+//! Execution-result signatures for external verifiers
+//!
+//! Every result submitted to the contract carries a signature by the worker's
+//! registered operator key over `(request_id, input_hash, output_hash,
+//! worker_pubkey)`. Anyone can check "this output came from worker W for this
+//! input" with a plain signature verification, without parsing TDX quotes.
+//!
+//! ## Canonical signing format
+//!
+//! The signed message is the concatenation of:
+//!
+//! | Field           | Encoding                                                   |
+//! |-----------------|------------------------------------------------------------|
+//! | domain          | ASCII `outlayer:execution-result:v1` (no terminator)       |
+//! | `request_id`    | u64, little-endian (8 bytes)                               |
+//! | `input_hash`    | SHA-256 of the request's `input_data` UTF-8 bytes (32 bytes) |
+//! | `output_hash`   | SHA-256 of the output as returned by the contract (32 bytes) |
+//! | `worker_pubkey` | u32 little-endian length + NEAR string form (`ed25519:...`)  |
+//!
+//! `output_hash` uses the same convention as TDX attestations: SHA-256 of the
+//! compact JSON the contract returns (text and base64 output as a JSON string,
+//! bytes/CBOR/MessagePack as a base64 JSON string, JSON as-is), or SHA-256 of
+//! `[EXECUTION-FAILED]` when there is no output.
+//!
+//! The signature is the raw key-type signature over the message (no pre-hash),
+//! in NEAR string form (`ed25519:<base58>` or `ml-dsa-65:<base58>`). Hashes are
+//! hex-encoded in the submitted [`ResultSignature`].
+
+use anyhow::{Context, Result};
+use near_crypto::{PublicKey, SecretKey, Signature};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::api_client::ExecutionOutput;
+
+/// Domain separator prefixed to every signed result
+pub const RESULT_SIGNATURE_DOMAIN: &[u8] = b"outlayer:execution-result:v1";
+
+/// Output hash preimage when the execution produced no output
+const NO_OUTPUT_SENTINEL: &[u8] = b"[EXECUTION-FAILED]";
+
+/// Signature over an execution result, submitted alongside it to the contract
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResultSignature {
+    /// Worker key that signed, NEAR string form
+    pub worker_public_key: String,
+    /// Hex SHA-256 of the input data
+    pub input_hash: String,
+    /// Hex SHA-256 of the output as returned by the contract
+    pub output_hash: String,
+    /// Signature over the canonical message, NEAR string form
+    pub signature: String,
+}
+
+/// SHA-256 of the input data passed to the WASM
+pub fn input_hash(input_data: &str) -> [u8; 32] {
+    Sha256::digest(input_data.as_bytes()).into()
+}
+
+/// SHA-256 of the output as the contract returns it to the caller
+pub fn output_hash(output: Option<&ExecutionOutput>) -> [u8; 32] {
+    match output {
+        Some(output) => {
+            let json = serde_json::to_string(&output.to_contract_json())
+                .unwrap_or_else(|_| "null".to_string());
+            Sha256::digest(json.as_bytes()).into()
+        }
+        None => Sha256::digest(NO_OUTPUT_SENTINEL).into(),
+    }
+}
+
+/// Canonical message signed for a result (see module docs)
+pub fn signing_message(
+    request_id: u64,
+    input_hash: &[u8; 32],
+    output_hash: &[u8; 32],
+    worker_public_key: &PublicKey,
+) -> Vec<u8> {
+    let pubkey = worker_public_key.to_string();
+    let mut message = Vec::with_capacity(RESULT_SIGNATURE_DOMAIN.len() + 76 + pubkey.len());
+    message.extend_from_slice(RESULT_SIGNATURE_DOMAIN);
+    message.extend_from_slice(&request_id.to_le_bytes());
+    message.extend_from_slice(input_hash);
+    message.extend_from_slice(output_hash);
+    message.extend_from_slice(&(pubkey.len() as u32).to_le_bytes());
+    message.extend_from_slice(pubkey.as_bytes());
+    message
+}
+
+/// Sign the result of `request_id` with the worker key
+pub fn sign_result(
+    secret_key: &SecretKey,
+    request_id: u64,
+    input_data: &str,
+    output: Option<&ExecutionOutput>,
+) -> ResultSignature {
+    let public_key = secret_key.public_key();
+    let input_hash = input_hash(input_data);
+    let output_hash = output_hash(output);
+    let message = signing_message(request_id, &input_hash, &output_hash, &public_key);
+
+    ResultSignature {
+        worker_public_key: public_key.to_string(),
+        input_hash: hex::encode(input_hash),
+        output_hash: hex::encode(output_hash),
+        signature: secret_key.sign(&message).to_string(),
+    }
+}
+
+/// Check that `signature` covers this request, input and output
+///
+/// Recomputes both hashes from the data rather than trusting the hex fields,
+/// so a tampered output or input fails verification.
+pub fn verify_result(
+    signature: &ResultSignature,
+    request_id: u64,
+    input_data: &str,
+    output: Option<&ExecutionOutput>,
+) -> Result<()> {
+    let public_key: PublicKey = signature
+        .worker_public_key
+        .parse()
+        .context("Invalid worker public key")?;
+    let sig: Signature = signature.signature.parse().context("Invalid signature encoding")?;
+
+    let input_hash = input_hash(input_data);
+    let output_hash = output_hash(output);
+    if hex::encode(input_hash) != signature.input_hash {
+        anyhow::bail!("input_hash does not match the input data");
+    }
+    if hex::encode(output_hash) != signature.output_hash {
+        anyhow::bail!("output_hash does not match the output");
+    }
+
+    let message = signing_message(request_id, &input_hash, &output_hash, &public_key);
+    if !sig.verify(&message, &public_key) {
+        anyhow::bail!("signature does not verify for {}", signature.worker_public_key);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_crypto::KeyType;
+
+    #[test]
+    fn test_signature_verifies_and_detects_tampering() {
+        let key = SecretKey::from_random(KeyType::ED25519);
+        let output = ExecutionOutput::Json(serde_json::json!({"price": 42}));
+        let sig = sign_result(&key, 7, r#"{"pair":"ETH/USD"}"#, Some(&output));
+
+        assert_eq!(sig.worker_public_key, key.public_key().to_string());
+        verify_result(&sig, 7, r#"{"pair":"ETH/USD"}"#, Some(&output)).unwrap();
+
+        // Tampered output, input or request id
+        let forged = ExecutionOutput::Json(serde_json::json!({"price": 43}));
+        assert!(verify_result(&sig, 7, r#"{"pair":"ETH/USD"}"#, Some(&forged)).is_err());
+        assert!(verify_result(&sig, 7, r#"{"pair":"BTC/USD"}"#, Some(&output)).is_err());
+        assert!(verify_result(&sig, 8, r#"{"pair":"ETH/USD"}"#, Some(&output)).is_err());
+
+        // Hex fields rewritten to match forged output: signature no longer covers them
+        let mut rewritten = sig.clone();
+        rewritten.output_hash = hex::encode(output_hash(Some(&forged)));
+        assert!(verify_result(&rewritten, 7, r#"{"pair":"ETH/USD"}"#, Some(&forged)).is_err());
+
+        // Claimed by a different worker
+        let mut other = sig;
+        other.worker_public_key = SecretKey::from_random(KeyType::ED25519).public_key().to_string();
+        assert!(verify_result(&other, 7, r#"{"pair":"ETH/USD"}"#, Some(&output)).is_err());
+    }
+
+    #[test]
+    fn test_failed_execution_and_mldsa_key() {
+        let key = SecretKey::from_random(KeyType::MLDSA65);
+        let sig = sign_result(&key, 1, "", None);
+        assert_eq!(sig.output_hash, hex::encode(Sha256::digest(b"[EXECUTION-FAILED]")));
+        verify_result(&sig, 1, "", None).unwrap();
+        assert!(verify_result(&sig, 1, "", Some(&ExecutionOutput::Text(String::new()))).is_err());
+    }
+
+    #[test]
+    fn test_signing_message_layout() {
+        let key = SecretKey::from_seed(KeyType::ED25519, "outlayer");
+        let pubkey = key.public_key();
+        let message = signing_message(0x0102, &[0xaa; 32], &[0xbb; 32], &pubkey);
+
+        let domain_len = RESULT_SIGNATURE_DOMAIN.len();
+        assert_eq!(&message[..domain_len], b"outlayer:execution-result:v1");
+        assert_eq!(&message[domain_len..domain_len + 8], &[0x02, 0x01, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(&message[domain_len + 8..domain_len + 40], &[0xaa; 32]);
+        assert_eq!(&message[domain_len + 40..domain_len + 72], &[0xbb; 32]);
+        let pubkey_str = pubkey.to_string();
+        assert_eq!(
+            &message[domain_len + 72..domain_len + 76],
+            &(pubkey_str.len() as u32).to_le_bytes()
+        );
+        assert_eq!(&message[domain_len + 76..], pubkey_str.as_bytes());
+    }
+}
@@ -0,0 +1,101 @@
+//! Graceful shutdown on SIGTERM / Ctrl-C
+//!
+//! A container stop sends SIGTERM. Instead of dying mid-submission (which leaves the
+//! request unresolved and the caller's deposit locked until it goes stale), the worker
+//! stops claiming new jobs, lets the job in hand finish — result submitted and job
+//! completed — and exits. Whatever is still running when `SHUTDOWN_GRACE_PERIOD_SECONDS`
+//! runs out is abandoned and logged.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+/// Shutdown request shared by the signal handler and the worker loop
+#[derive(Default)]
+pub struct Shutdown {
+    requested: AtomicBool,
+    notify: Notify,
+}
+
+impl Shutdown {
+    /// Shutdown flag set by a task listening for SIGTERM and Ctrl-C
+    pub fn listen() -> Arc<Self> {
+        let shutdown = Arc::new(Self::default());
+        let handle = shutdown.clone();
+        tokio::spawn(async move {
+            let signal = wait_for_signal().await;
+            info!("🛑 {} received, finishing the current job before exiting", signal);
+            handle.request();
+        });
+        shutdown
+    }
+
+    pub fn request(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once shutdown has been requested (immediately if it already was)
+    pub async fn requested(&self) {
+        // Created before the check, so a request in between still wakes us
+        let notified = self.notify.notified();
+        if self.is_requested() {
+            return;
+        }
+        notified.await;
+    }
+}
+
+/// Name of the first shutdown signal received
+#[cfg(unix)]
+async fn wait_for_signal() -> &'static str {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    match signal(SignalKind::terminate()) {
+        Ok(mut sigterm) => tokio::select! {
+            _ = sigterm.recv() => "SIGTERM",
+            _ = tokio::signal::ctrl_c() => "Ctrl-C",
+        },
+        Err(e) => {
+            warn!("Failed to install SIGTERM handler, only Ctrl-C triggers a graceful shutdown: {}", e);
+            let _ = tokio::signal::ctrl_c().await;
+            "Ctrl-C"
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() -> &'static str {
+    let _ = tokio::signal::ctrl_c().await;
+    "Ctrl-C"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_requested_wakes_waiters() {
+        let shutdown = Arc::new(Shutdown::default());
+        assert!(!shutdown.is_requested());
+
+        let waiter = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.requested().await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!waiter.is_finished());
+
+        shutdown.request();
+        tokio::time::timeout(Duration::from_secs(1), waiter).await.unwrap().unwrap();
+
+        // Waiting after the request returns at once
+        tokio::time::timeout(Duration::from_millis(10), shutdown.requested()).await.unwrap();
+    }
+}
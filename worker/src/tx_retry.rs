@@ -0,0 +1,261 @@
+//! Retries for NEAR transaction submission
+//!
+//! Submitting an execution result is the critical path of every request, and the RPC
+//! fails it for reasons that go away on their own: a timeout, a dropped connection, or a
+//! nonce race with another transaction signed by the same key. Those are retried here with
+//! exponential backoff and jitter; anything else (missing access key, invalid arguments,
+//! ...) is returned on the first attempt.
+//!
+//! A timed-out transaction may still land, so transient errors rebroadcast the same signed
+//! transaction: it has the same hash and can only execute once. Only a nonce error signs a
+//! new transaction, with the nonce fetched again from RPC.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use rand::Rng;
+use std::time::Duration;
+use tracing::warn;
+
+/// Backoff policy for transaction submission
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(8),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before retry `attempt` (0-based): `base_delay * 2^attempt` capped at `max_delay`,
+    /// jittered into its upper half so workers sharing a key don't retry in lockstep
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempt).unwrap_or(u32::MAX);
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        let half = delay / 2;
+        half + (delay - half).mul_f64(rand::thread_rng().gen::<f64>())
+    }
+}
+
+/// How a failed submission is handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubmitErrorKind {
+    /// Nonce already used: sign again with a fresh nonce
+    InvalidNonce,
+    /// Timeout or connection problem: rebroadcast the same transaction
+    Transient,
+    /// Retrying won't help
+    Fatal,
+}
+
+/// Classify an RPC error from signing or broadcasting a transaction
+pub fn classify_submit_error(err: &anyhow::Error) -> SubmitErrorKind {
+    // Debug output includes the whole context chain and the RPC error variant names
+    let message = format!("{:?}", err).to_lowercase();
+
+    if message.contains("invalidnonce") || message.contains("nonce too small") {
+        return SubmitErrorKind::InvalidNonce;
+    }
+
+    const TRANSIENT: &[&str] = &[
+        "timed out",
+        "timeout",
+        "connection reset",
+        "connection refused",
+        "connection closed",
+        "error sending request",
+        "transport error",
+        "503 service unavailable",
+        "502 bad gateway",
+    ];
+    if TRANSIENT.iter().any(|pattern| message.contains(pattern)) {
+        return SubmitErrorKind::Transient;
+    }
+
+    SubmitErrorKind::Fatal
+}
+
+/// One transaction that can be signed and broadcast
+#[async_trait]
+pub trait TxSubmitter: Sync {
+    type Tx: Clone + Send + Sync;
+    type Outcome: Send;
+
+    /// Build and sign the transaction with the current nonce from RPC
+    async fn sign(&self) -> Result<Self::Tx>;
+
+    /// Broadcast and wait for the outcome
+    async fn broadcast(&self, tx: Self::Tx) -> Result<Self::Outcome>;
+}
+
+/// Sign and broadcast with `policy`; see the module docs for what is retried
+pub async fn submit_with_retry<S: TxSubmitter>(
+    submitter: &S,
+    policy: &RetryPolicy,
+    label: &str,
+) -> Result<S::Outcome> {
+    let mut signed: Option<S::Tx> = None;
+    let mut attempt = 0;
+
+    loop {
+        let result = match &signed {
+            Some(tx) => submitter.broadcast(tx.clone()).await,
+            None => match submitter.sign().await {
+                Ok(tx) => {
+                    signed = Some(tx.clone());
+                    submitter.broadcast(tx).await
+                }
+                Err(e) => Err(e),
+            },
+        };
+
+        let err = match result {
+            Ok(outcome) => return Ok(outcome),
+            Err(e) => e,
+        };
+
+        let kind = classify_submit_error(&err);
+        if kind == SubmitErrorKind::Fatal {
+            return Err(err);
+        }
+        if attempt >= policy.max_retries {
+            return Err(err.context(format!("{} failed after {} attempts", label, attempt + 1)));
+        }
+        if kind == SubmitErrorKind::InvalidNonce {
+            signed = None;
+        }
+
+        let delay = policy.delay(attempt);
+        warn!(
+            "⚠️  {} failed ({:?}, attempt {}/{}), retrying in {} ms: {:#}",
+            label,
+            kind,
+            attempt + 1,
+            policy.max_retries + 1,
+            delay.as_millis(),
+            err
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+    use std::sync::Mutex;
+
+    /// Stand-in for NEAR RPC: signs with an increasing nonce and fails broadcasts
+    /// with the scripted errors before succeeding
+    struct MockRpc {
+        nonce: AtomicU64,
+        signs: AtomicU32,
+        broadcasts: AtomicU32,
+        failures: Mutex<VecDeque<&'static str>>,
+    }
+
+    impl MockRpc {
+        fn new(failures: &[&'static str]) -> Self {
+            Self {
+                nonce: AtomicU64::new(100),
+                signs: AtomicU32::new(0),
+                broadcasts: AtomicU32::new(0),
+                failures: Mutex::new(failures.iter().copied().collect()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl TxSubmitter for MockRpc {
+        type Tx = u64;
+        type Outcome = u64;
+
+        async fn sign(&self) -> Result<u64> {
+            self.signs.fetch_add(1, Ordering::SeqCst);
+            Ok(self.nonce.fetch_add(1, Ordering::SeqCst) + 1)
+        }
+
+        async fn broadcast(&self, tx: u64) -> Result<u64> {
+            self.broadcasts.fetch_add(1, Ordering::SeqCst);
+            match self.failures.lock().unwrap().pop_front() {
+                Some(failure) => anyhow::bail!("{}", failure),
+                None => Ok(tx),
+            }
+        }
+    }
+
+    fn fast_policy(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(4),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transient_failures_rebroadcast_same_tx() {
+        let rpc = MockRpc::new(&[
+            "NEAR RPC broadcast timed out",
+            "transport error: error sending request: connection reset by peer",
+        ]);
+
+        let nonce = submit_with_retry(&rpc, &fast_policy(3), "resolve_execution").await.unwrap();
+        assert_eq!(nonce, 101);
+        assert_eq!(rpc.signs.load(Ordering::SeqCst), 1);
+        assert_eq!(rpc.broadcasts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_nonce_error_signs_again() {
+        let rpc = MockRpc::new(&["handler error: InvalidTransaction { context: InvalidNonce { tx_nonce: 101, ak_nonce: 101 } }"]);
+
+        let nonce = submit_with_retry(&rpc, &fast_policy(3), "resolve_execution").await.unwrap();
+        assert_eq!(nonce, 102);
+        assert_eq!(rpc.signs.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_fatal_error_fails_fast() {
+        let rpc = MockRpc::new(&["handler error: InvalidAccessKeyError(AccessKeyNotFound)"]);
+
+        let err = submit_with_retry(&rpc, &fast_policy(3), "resolve_execution").await.unwrap_err();
+        assert!(err.to_string().contains("AccessKeyNotFound"));
+        assert_eq!(rpc.broadcasts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_retries() {
+        let rpc = MockRpc::new(&["timed out"; 5]);
+
+        let err = submit_with_retry(&rpc, &fast_policy(2), "resolve_execution").await.unwrap_err();
+        assert!(err.to_string().contains("failed after 3 attempts"), "unexpected error: {:#}", err);
+        assert_eq!(rpc.broadcasts.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_delay_grows_and_is_capped() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+        };
+        for _ in 0..20 {
+            let first = policy.delay(0);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+            let third = policy.delay(2);
+            assert!(third >= Duration::from_millis(200) && third <= Duration::from_millis(400));
+            assert!(policy.delay(30) <= Duration::from_secs(1));
+        }
+    }
+}
@@ -5,13 +5,35 @@
 //!
 //! Security: Each cached file stores its expected checksum and verifies it
 //! before returning, preventing cache corruption or tampering.
+//!
+//! ## Compile index
+//!
+//! When enabled via [`WasmCache::enable_compile_index`], the cache also keeps a
+//! persisted index mapping compilation source checksums (repo:commit:target)
+//! to the WASM checksum they produced, so a restarted worker can reuse its
+//! previous builds without asking the coordinator or recompiling.
+//!
+//! Files:
+//! - `compile_index.json` - source checksum -> artifact (WASM checksum, timestamps)
+//! - `compile_index.sig` - ed25519 signature of the index file
+//!
+//! Content checksums (see `compiler::Compiler::compute_content_checksum`) are indexed
+//! alongside: a source entry built from a known tree links to its content entry, so a
+//! new commit with the same tree resolves to the WASM already built.
+//!
+//! Unlike `{checksum}.wasm` files, an index entry can't be verified by hashing
+//! (the source isn't available), and the cache may live in /tmp which WASI
+//! components can write to. The index is therefore signed with the worker key
+//! and discarded on signature mismatch.
 
 use anyhow::{Context, Result};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier};
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::PathBuf;
-use std::time::Instant;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use tracing::{debug, info, warn};
 
 /// Cache entry metadata
@@ -38,6 +60,24 @@ pub struct WasmCache {
     entries: HashMap<String, CacheEntry>,
     /// Current total size
     total_size: u64,
+    /// Signing key for the compile index (None = compile index disabled)
+    index_key: Option<SigningKey>,
+    /// Compile index: source checksum -> compiled artifact
+    compiled: BTreeMap<String, CompiledArtifact>,
+}
+
+/// WASM produced by compiling a source checksum
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+struct CompiledArtifact {
+    /// SHA256 of the WASM bytes (name of the `{checksum}.wasm` file)
+    wasm_checksum: String,
+    /// Coordinator `created_at` of the WASM, if known
+    created_at: Option<String>,
+    /// When this worker cached the artifact (unix seconds)
+    cached_at: u64,
+    /// Content checksum of the source tree (source entries whose tree was hashed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content_checksum: Option<String>,
 }
 
 impl WasmCache {
@@ -63,6 +103,8 @@ impl WasmCache {
             max_size_bytes,
             entries: HashMap::new(),
             total_size: 0,
+            index_key: None,
+            compiled: BTreeMap::new(),
         };
 
         // Load existing cache entries from disk
@@ -146,6 +188,187 @@ impl WasmCache {
         Ok(())
     }
 
+    /// Enable the persisted compile index, signed with the worker's ed25519 key
+    ///
+    /// Loads `compile_index.json` from the cache directory. A missing, unsigned
+    /// or unparsable index is discarded (affected sources are simply recompiled).
+    /// Called once the operator key is known, i.e. after worker registration.
+    pub fn enable_compile_index(&mut self, secret_key_bytes: &[u8; 32]) {
+        self.index_key = Some(SigningKey::from_bytes(secret_key_bytes));
+        self.compiled.clear();
+        self.load_compile_index();
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.dir.join("compile_index.json")
+    }
+
+    fn index_sig_path(&self) -> PathBuf {
+        self.dir.join("compile_index.sig")
+    }
+
+    /// Load the compile index from disk, dropping entries whose WASM is gone
+    fn load_compile_index(&mut self) {
+        let Some(key) = &self.index_key else {
+            return;
+        };
+
+        let index_bytes = match fs::read(self.index_path()) {
+            Ok(b) => b,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+            Err(e) => {
+                warn!("Failed to read compile index: {}, starting empty", e);
+                return;
+            }
+        };
+
+        let signature = fs::read(self.index_sig_path())
+            .ok()
+            .and_then(|b| <[u8; 64]>::try_from(b.as_slice()).ok())
+            .map(|b| Signature::from_bytes(&b));
+        let signed = signature
+            .map(|sig| key.verifying_key().verify(&index_bytes, &sig).is_ok())
+            .unwrap_or(false);
+        if !signed {
+            warn!("⚠️ Compile index signature missing or invalid, discarding index");
+            self.remove_compile_index();
+            return;
+        }
+
+        let index: BTreeMap<String, CompiledArtifact> = match serde_json::from_slice(&index_bytes) {
+            Ok(index) => index,
+            Err(e) => {
+                warn!("Failed to parse compile index: {}, discarding index", e);
+                self.remove_compile_index();
+                return;
+            }
+        };
+
+        let total = index.len();
+        self.compiled = index
+            .into_iter()
+            .filter(|(_, artifact)| self.entries.contains_key(&artifact.wasm_checksum))
+            .collect();
+
+        info!(
+            "📦 Loaded compile index: {} entries ({} dropped, WASM no longer cached)",
+            self.compiled.len(),
+            total - self.compiled.len()
+        );
+
+        if self.compiled.len() != total {
+            self.save_compile_index();
+        }
+    }
+
+    /// Persist the compile index (write-then-rename, then sign)
+    fn save_compile_index(&self) {
+        let Some(key) = &self.index_key else {
+            return;
+        };
+
+        let result = (|| -> Result<()> {
+            let index_bytes = serde_json::to_vec(&self.compiled)?;
+            let tmp_path = self.dir.join("compile_index.json.tmp");
+            fs::write(&tmp_path, &index_bytes)
+                .with_context(|| format!("Failed to write {:?}", tmp_path))?;
+            fs::rename(&tmp_path, self.index_path())
+                .with_context(|| format!("Failed to replace {:?}", self.index_path()))?;
+            fs::write(self.index_sig_path(), key.sign(&index_bytes).to_bytes())
+                .with_context(|| format!("Failed to write {:?}", self.index_sig_path()))?;
+            Ok(())
+        })();
+
+        if let Err(e) = result {
+            warn!("Failed to save compile index: {}", e);
+        }
+    }
+
+    fn remove_compile_index(&self) {
+        let _ = fs::remove_file(self.index_path());
+        let _ = fs::remove_file(self.index_sig_path());
+    }
+
+    /// Get the WASM previously compiled for `source_checksum`
+    ///
+    /// # Returns
+    /// * `Some((bytes, created_at))` - Cached WASM (hash verified) and its coordinator `created_at`
+    /// * `None` - Not in the index, index disabled, or WASM evicted/corrupted (recompile)
+    pub fn get_compiled(&mut self, source_checksum: &str) -> Option<(Vec<u8>, Option<String>)> {
+        let artifact = self.compiled.get(source_checksum)?.clone();
+
+        match self.get(&artifact.wasm_checksum) {
+            Some(bytes) => {
+                debug!(
+                    "✅ Compile cache hit: {} -> {}",
+                    source_checksum, artifact.wasm_checksum
+                );
+                Some((bytes, artifact.created_at))
+            }
+            None => {
+                warn!(
+                    "Compiled WASM for {} is missing or corrupted, will recompile",
+                    source_checksum
+                );
+                self.compiled.remove(source_checksum);
+                self.save_compile_index();
+                None
+            }
+        }
+    }
+
+    /// Store compiled WASM and record it under `source_checksum` in the compile index
+    ///
+    /// No-op for the index when it isn't enabled (the WASM itself is still cached).
+    pub fn put_compiled(
+        &mut self,
+        source_checksum: &str,
+        bytes: &[u8],
+        created_at: Option<String>,
+    ) -> Result<()> {
+        let wasm_checksum = Self::compute_hash(bytes);
+        self.put(&wasm_checksum, bytes)?;
+
+        // put() skips files larger than the whole cache
+        if self.index_key.is_none() || !self.entries.contains_key(&wasm_checksum) {
+            return Ok(());
+        }
+
+        let cached_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        self.compiled.insert(
+            source_checksum.to_string(),
+            CompiledArtifact {
+                wasm_checksum,
+                created_at,
+                cached_at,
+                content_checksum: None,
+            },
+        );
+        self.save_compile_index();
+
+        Ok(())
+    }
+
+    /// Record that `source_checksum` was built from the tree with `content_checksum`
+    ///
+    /// Indexes the source entry's WASM under the content checksum too, so other commits
+    /// of the same tree reuse it. No-op unless the source entry exists.
+    pub fn link_content(&mut self, source_checksum: &str, content_checksum: &str) {
+        let Some(artifact) = self.compiled.get_mut(source_checksum) else {
+            return;
+        };
+        artifact.content_checksum = Some(content_checksum.to_string());
+        let content_entry = CompiledArtifact {
+            content_checksum: None,
+            ..artifact.clone()
+        };
+        self.compiled.insert(content_checksum.to_string(), content_entry);
+        self.save_compile_index();
+    }
+
     /// Get WASM from cache if available and valid
     ///
     /// # Arguments
@@ -402,4 +625,111 @@ mod tests {
         // Third should be there
         assert!(cache.get(&checksum3).is_some());
     }
+
+    const INDEX_KEY: [u8; 32] = [7u8; 32];
+    const SOURCE: &str = "source-checksum-repo-commit-target";
+
+    fn open_indexed(dir: &TempDir) -> WasmCache {
+        let mut cache = WasmCache::new(dir.path().to_path_buf(), 10).unwrap();
+        cache.enable_compile_index(&INDEX_KEY);
+        cache
+    }
+
+    #[test]
+    fn test_compile_index_survives_restart() {
+        let temp_dir = TempDir::new().unwrap();
+        let wasm = create_test_wasm();
+
+        {
+            let mut cache = open_indexed(&temp_dir);
+            assert!(cache.get_compiled(SOURCE).is_none());
+            cache
+                .put_compiled(SOURCE, &wasm, Some("2025-01-01T00:00:00Z".to_string()))
+                .unwrap();
+        }
+
+        // Simulated restart: fresh cache over the same directory
+        let mut cache = open_indexed(&temp_dir);
+        let (bytes, created_at) = cache.get_compiled(SOURCE).expect("compile cache should hit");
+        assert_eq!(bytes, wasm);
+        assert_eq!(created_at.as_deref(), Some("2025-01-01T00:00:00Z"));
+    }
+
+    #[test]
+    fn test_compile_index_corrupted_wasm_forces_recompile() {
+        let temp_dir = TempDir::new().unwrap();
+        let wasm = create_test_wasm();
+        open_indexed(&temp_dir).put_compiled(SOURCE, &wasm, None).unwrap();
+
+        let mut cache = open_indexed(&temp_dir);
+        let path = temp_dir
+            .path()
+            .join(format!("{}.wasm", WasmCache::compute_hash(&wasm)));
+        fs::write(&path, b"tampered").unwrap();
+
+        assert!(cache.get_compiled(SOURCE).is_none());
+        // Entry was dropped from the persisted index as well
+        assert!(open_indexed(&temp_dir).get_compiled(SOURCE).is_none());
+    }
+
+    #[test]
+    fn test_compile_index_rejects_tampered_or_garbage_index() {
+        let temp_dir = TempDir::new().unwrap();
+        let wasm = create_test_wasm();
+        let other = vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x01];
+        {
+            let mut cache = open_indexed(&temp_dir);
+            cache.put_compiled(SOURCE, &wasm, None).unwrap();
+            cache.put(&WasmCache::compute_hash(&other), &other).unwrap();
+        }
+
+        // Point the source at a different (valid) WASM without re-signing
+        let index_path = temp_dir.path().join("compile_index.json");
+        let index = fs::read_to_string(&index_path).unwrap().replace(
+            &WasmCache::compute_hash(&wasm),
+            &WasmCache::compute_hash(&other),
+        );
+        fs::write(&index_path, index).unwrap();
+        assert!(open_indexed(&temp_dir).get_compiled(SOURCE).is_none());
+
+        // Garbage index: starts empty, no panic
+        fs::write(&index_path, b"{not json").unwrap();
+        let mut cache = open_indexed(&temp_dir);
+        assert!(cache.get_compiled(SOURCE).is_none());
+        cache.put_compiled(SOURCE, &wasm, None).unwrap();
+        assert!(open_indexed(&temp_dir).get_compiled(SOURCE).is_some());
+    }
+
+    #[test]
+    fn test_content_link_reused_across_sources() {
+        const CONTENT: &str = "tree-content-checksum";
+        let temp_dir = TempDir::new().unwrap();
+        let wasm = create_test_wasm();
+        {
+            let mut cache = open_indexed(&temp_dir);
+            // Unknown source: nothing to link
+            cache.link_content(SOURCE, CONTENT);
+            assert!(cache.get_compiled(CONTENT).is_none());
+
+            cache.put_compiled(SOURCE, &wasm, None).unwrap();
+            cache.link_content(SOURCE, CONTENT);
+        }
+
+        // Survives a restart, like source entries
+        let mut cache = open_indexed(&temp_dir);
+        let (bytes, _) = cache.get_compiled(CONTENT).expect("content entry should hit");
+        assert_eq!(bytes, wasm);
+    }
+
+    #[test]
+    fn test_compile_index_wrong_key_discarded() {
+        let temp_dir = TempDir::new().unwrap();
+        open_indexed(&temp_dir)
+            .put_compiled(SOURCE, &create_test_wasm(), None)
+            .unwrap();
+
+        let mut cache = WasmCache::new(temp_dir.path().to_path_buf(), 10).unwrap();
+        cache.enable_compile_index(&[9u8; 32]);
+        assert!(cache.get_compiled(SOURCE).is_none());
+    }
 }
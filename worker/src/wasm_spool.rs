@@ -0,0 +1,128 @@
+//! Spooling of downloaded WASM artifacts
+//!
+//! Most artifacts are small and are simply buffered in memory. Once a download grows past
+//! the threshold, the buffer is written to an unlinked temp file, the rest of the stream is
+//! appended there, and the file is memory-mapped. The executor and caches still see one
+//! contiguous `&[u8]` (so hashing and compilation cover the full artifact), but the pages are
+//! file-backed and can be evicted instead of counting against the worker's heap.
+//!
+//! The spool directory must not be under /tmp: WASI P2 guests can see /tmp. Callers use a
+//! subdirectory of the WASM cache dir.
+
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use futures_util::{Stream, StreamExt};
+use std::ops::Deref;
+use std::path::Path;
+use tokio::io::AsyncWriteExt;
+
+/// WASM binary, either on the heap or mapped from a spooled temp file
+pub enum WasmBytes {
+    Memory(Vec<u8>),
+    Mapped(memmap2::Mmap),
+}
+
+impl Deref for WasmBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            WasmBytes::Memory(bytes) => bytes,
+            WasmBytes::Mapped(map) => map,
+        }
+    }
+}
+
+impl From<Vec<u8>> for WasmBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        WasmBytes::Memory(bytes)
+    }
+}
+
+/// Collect a download stream, spilling to a temp file in `spool_dir` past `threshold` bytes
+pub async fn spool_wasm_stream<S>(stream: S, threshold: usize, spool_dir: &Path) -> Result<WasmBytes>
+where
+    S: Stream<Item = Result<Bytes>>,
+{
+    futures_util::pin_mut!(stream);
+    let mut buffer = Vec::new();
+    let mut file: Option<tokio::fs::File> = None;
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        match file.as_mut() {
+            Some(file) => file.write_all(&chunk).await.context("Failed to write WASM spool file")?,
+            None => {
+                buffer.extend_from_slice(&chunk);
+                if buffer.len() > threshold {
+                    std::fs::create_dir_all(spool_dir)
+                        .with_context(|| format!("Failed to create WASM spool dir {}", spool_dir.display()))?;
+                    // Unlinked on creation: nothing to clean up if the worker dies mid-download
+                    let mut spool = tokio::fs::File::from_std(
+                        tempfile::tempfile_in(spool_dir).context("Failed to create WASM spool file")?,
+                    );
+                    spool.write_all(&buffer).await.context("Failed to write WASM spool file")?;
+                    buffer = Vec::new();
+                    file = Some(spool);
+                }
+            }
+        }
+    }
+
+    let Some(mut file) = file else {
+        return Ok(WasmBytes::Memory(buffer));
+    };
+    file.flush().await.context("Failed to flush WASM spool file")?;
+    let file = file.into_std().await;
+    // SAFETY: the file is unlinked and only reachable through this handle, so nothing can
+    // truncate or modify it while the map is alive.
+    let map = unsafe { memmap2::Mmap::map(&file) }.context("Failed to map WASM spool file")?;
+    Ok(WasmBytes::Mapped(map))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunked(data: &[u8], chunk_size: usize) -> impl Stream<Item = Result<Bytes>> {
+        let chunks: Vec<Result<Bytes>> = data
+            .chunks(chunk_size)
+            .map(|c| Ok(Bytes::copy_from_slice(c)))
+            .collect();
+        futures_util::stream::iter(chunks)
+    }
+
+    #[tokio::test]
+    async fn test_small_download_stays_in_memory() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = vec![7u8; 1000];
+
+        let bytes = spool_wasm_stream(chunked(&data, 100), 4096, dir.path()).await.unwrap();
+        assert!(matches!(bytes, WasmBytes::Memory(_)));
+        assert_eq!(&*bytes, &data[..]);
+    }
+
+    #[tokio::test]
+    async fn test_large_download_is_spooled_and_mapped() {
+        let dir = tempfile::tempdir().unwrap();
+        let data: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+
+        let bytes = spool_wasm_stream(chunked(&data, 333), 4096, &dir.path().join("spool"))
+            .await
+            .unwrap();
+        assert!(matches!(bytes, WasmBytes::Mapped(_)));
+        assert_eq!(&*bytes, &data[..]);
+    }
+
+    #[tokio::test]
+    async fn test_stream_error_is_returned() {
+        let dir = tempfile::tempdir().unwrap();
+        let stream = futures_util::stream::iter(vec![
+            Ok(Bytes::from_static(b"\0asm")),
+            Err(anyhow::anyhow!("connection reset")),
+        ]);
+
+        let err = spool_wasm_stream(stream, 4096, dir.path()).await.err().unwrap();
+        assert!(err.to_string().contains("connection reset"));
+    }
+}
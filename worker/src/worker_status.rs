@@ -0,0 +1,198 @@
+//! Live worker state shared between the job loop and the heartbeat task
+//!
+//! The worker loop records which job it is processing and how many claimed
+//! jobs are still pending; the heartbeat task snapshots this every interval so
+//! the coordinator can route around busy workers. Compile tasks run alongside
+//! the loop, so several batches can be in flight; the current job is whichever
+//! batch started one last.
+//!
+//! Keystore health and operator key allowance need network round-trips, so a
+//! separate probe task records them here and heartbeats report the last result;
+//! a slow keystore or RPC never delays a heartbeat.
+
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::api_client::HeartbeatStatus;
+
+/// Job-level worker state, cheap to share via `Arc`
+#[derive(Debug, Default)]
+pub struct WorkerStatus {
+    /// Job currently being processed (0 = idle)
+    current_job_id: AtomicI64,
+    /// Jobs claimed from the coordinator and not finished yet
+    in_flight: AtomicU64,
+    /// Last keystore health probe succeeded (false until the first probe)
+    keystore_healthy: AtomicBool,
+    /// Last probed operator key allowance (None = full access, unknown or not probed yet)
+    allowance_remaining: Mutex<Option<u128>>,
+}
+
+impl WorkerStatus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a batch of claimed jobs
+    ///
+    /// The returned guard releases whatever is still in flight when dropped,
+    /// so early returns and iteration timeouts don't leave stale counts.
+    pub fn claim(&self, count: usize) -> ClaimedJobs<'_> {
+        self.in_flight.fetch_add(count as u64, Ordering::Relaxed);
+        ClaimedJobs {
+            status: self,
+            remaining: count as u64,
+            current: None,
+        }
+    }
+
+    /// Job currently being processed
+    pub fn current_job_id(&self) -> Option<i64> {
+        match self.current_job_id.load(Ordering::Relaxed) {
+            0 => None,
+            id => Some(id),
+        }
+    }
+
+    /// Number of claimed jobs not finished yet (including the current one)
+    pub fn in_flight_count(&self) -> u64 {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Record the result of a keystore / allowance probe
+    pub fn record_probe(&self, keystore_healthy: bool, allowance_remaining: Option<u128>) {
+        self.keystore_healthy.store(keystore_healthy, Ordering::Relaxed);
+        *self.allowance_remaining.lock().unwrap() = allowance_remaining;
+    }
+
+    /// Snapshot for the heartbeat payload, with the last probed health and allowance
+    pub fn heartbeat_status(&self, capabilities: Vec<String>) -> HeartbeatStatus {
+        HeartbeatStatus {
+            capabilities,
+            current_job_id: self.current_job_id(),
+            in_flight_count: self.in_flight_count(),
+            keystore_healthy: self.keystore_healthy.load(Ordering::Relaxed),
+            allowance_remaining: self.allowance_remaining.lock().unwrap().map(|a| a.to_string()),
+            rpc_cache: None,
+        }
+    }
+}
+
+/// Jobs claimed in one worker iteration
+pub struct ClaimedJobs<'a> {
+    status: &'a WorkerStatus,
+    remaining: u64,
+    current: Option<i64>,
+}
+
+impl ClaimedJobs<'_> {
+    /// Mark `job_id` as the job being processed; the previous job counts as finished
+    pub fn start(&mut self, job_id: i64) {
+        self.finish_current();
+        self.current = Some(job_id);
+        self.status.current_job_id.store(job_id, Ordering::Relaxed);
+    }
+
+    fn finish_current(&mut self) {
+        if self.current.take().is_some() && self.remaining > 0 {
+            self.remaining -= 1;
+            self.status.in_flight.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Drop for ClaimedJobs<'_> {
+    fn drop(&mut self) {
+        self.status.in_flight.fetch_sub(self.remaining, Ordering::Relaxed);
+        // Only clear our own job: another batch may have started one since
+        if let Some(job_id) = self.current {
+            let _ = self
+                .status
+                .current_job_id
+                .compare_exchange(job_id, 0, Ordering::Relaxed, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heartbeat_reflects_current_job() {
+        let status = WorkerStatus::new();
+        let caps = vec!["compilation".to_string(), "execution".to_string()];
+
+        let idle = serde_json::to_value(status.heartbeat_status(caps.clone())).unwrap();
+        assert_eq!(idle["current_job_id"], serde_json::Value::Null);
+        assert_eq!(idle["in_flight_count"], 0);
+        // Not probed yet
+        assert_eq!(idle["keystore_healthy"], false);
+        assert_eq!(idle["allowance_remaining"], serde_json::Value::Null);
+
+        {
+            let mut jobs = status.claim(2);
+            jobs.start(41);
+            status.record_probe(false, Some(250_000_000_000_000_000_000_000));
+
+            let busy = serde_json::to_value(status.heartbeat_status(caps.clone())).unwrap();
+            assert_eq!(busy["capabilities"], serde_json::json!(["compilation", "execution"]));
+            assert_eq!(busy["current_job_id"], 41);
+            assert_eq!(busy["in_flight_count"], 2);
+            assert_eq!(busy["keystore_healthy"], false);
+            assert_eq!(busy["allowance_remaining"], "250000000000000000000000");
+
+            jobs.start(42);
+            assert_eq!(status.current_job_id(), Some(42));
+            assert_eq!(status.in_flight_count(), 1);
+        }
+
+        // Guard dropped (iteration finished or aborted): worker is idle again
+        assert_eq!(status.current_job_id(), None);
+        assert_eq!(status.in_flight_count(), 0);
+    }
+
+    #[test]
+    fn test_finished_batch_keeps_other_batch_current() {
+        let status = WorkerStatus::new();
+        let mut compile = status.claim(2);
+        compile.start(7);
+        let mut execute = status.claim(1);
+        execute.start(9);
+        assert_eq!(status.current_job_id(), Some(9));
+        assert_eq!(status.in_flight_count(), 3);
+
+        // The compile task finishing must not mark the running execute job idle
+        drop(compile);
+        assert_eq!(status.current_job_id(), Some(9));
+        assert_eq!(status.in_flight_count(), 1);
+
+        drop(execute);
+        assert_eq!(status.current_job_id(), None);
+        assert_eq!(status.in_flight_count(), 0);
+    }
+
+    #[test]
+    fn test_heartbeat_reports_last_probe() {
+        let status = WorkerStatus::new();
+        status.record_probe(true, Some(5));
+        let first = status.heartbeat_status(Vec::new());
+        assert!(first.keystore_healthy);
+        assert_eq!(first.allowance_remaining.as_deref(), Some("5"));
+
+        // A later failed probe replaces the cached values
+        status.record_probe(false, None);
+        let second = status.heartbeat_status(Vec::new());
+        assert!(!second.keystore_healthy);
+        assert_eq!(second.allowance_remaining, None);
+    }
+
+    #[test]
+    fn test_claim_released_without_starting() {
+        let status = WorkerStatus::new();
+        let jobs = status.claim(3);
+        assert_eq!(status.in_flight_count(), 3);
+        drop(jobs);
+        assert_eq!(status.in_flight_count(), 0);
+    }
+}
@@ -2,7 +2,7 @@
 /// Run with: cargo test --test component_tests
 
 use offchainvm_worker::executor::Executor;
-use offchainvm_worker::api_client::ResourceLimits;
+use offchainvm_worker::api_client::{ResourceLimits, DEFAULT_MAX_LOG_BYTES, DEFAULT_MAX_OUTPUT_BYTES};
 
 #[tokio::test]
 async fn test_executor_with_minimal_wasm() {
@@ -17,6 +17,8 @@ async fn test_executor_with_minimal_wasm() {
         max_instructions: 1_000_000,
         max_memory_mb: 16,
         max_execution_seconds: 5,
+        max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+        max_log_bytes: DEFAULT_MAX_LOG_BYTES,
     };
 
     let input = vec![];
@@ -24,7 +26,7 @@ async fn test_executor_with_minimal_wasm() {
     let response_format = ResponseFormat::Text;
 
     // Should succeed (no functions to execute, but valid WASM)
-    let result = executor.execute(&wasm, None, &input, &limits, None, None, &response_format, None, None, None).await;
+    let result = executor.execute(&wasm, None, &input, &limits, None, None, &response_format, None, None, None, None).await;
 
     // Minimal WASM has no export, so execution will fail with specific error
     // But WASM parsing should succeed
@@ -40,13 +42,15 @@ async fn test_executor_with_invalid_wasm() {
         max_instructions: 1_000_000,
         max_memory_mb: 16,
         max_execution_seconds: 5,
+        max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+        max_log_bytes: DEFAULT_MAX_LOG_BYTES,
     };
 
     let input = vec![];
     use offchainvm_worker::api_client::ResponseFormat;
     let response_format = ResponseFormat::Text;
 
-    let result = executor.execute(&invalid_wasm, None, &input, &limits, None, None, &response_format, None, None, None).await;
+    let result = executor.execute(&invalid_wasm, None, &input, &limits, None, None, &response_format, None, None, None, None).await;
 
     // Should fail to parse - executor.execute() returns Ok(ExecutionResult)
     // but ExecutionResult.success should be false
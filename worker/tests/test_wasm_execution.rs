@@ -31,7 +31,7 @@ async fn test_wasm_execution() {
 
     // Create executor
     use offchainvm_worker::executor::Executor;
-    use offchainvm_worker::api_client::ResourceLimits;
+    use offchainvm_worker::api_client::{ResourceLimits, DEFAULT_MAX_LOG_BYTES, DEFAULT_MAX_OUTPUT_BYTES};
 
     let executor = Executor::new(10_000_000_000, true); // 10B instructions, print stderr
 
@@ -39,6 +39,8 @@ async fn test_wasm_execution() {
         max_instructions: 10_000_000_000,
         max_memory_mb: 128,
         max_execution_seconds: 60,
+        max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+        max_log_bytes: DEFAULT_MAX_LOG_BYTES,
     };
 
     // Create valid JSON input for random-ark example
@@ -52,7 +54,7 @@ async fn test_wasm_execution() {
     use offchainvm_worker::api_client::ResponseFormat;
     let response_format = ResponseFormat::Text;
 
-    match executor.execute(&wasm_bytes, None, &input_data, &resource_limits, None, None, &response_format, None, None, None).await {
+    match executor.execute(&wasm_bytes, None, &input_data, &resource_limits, None, None, &response_format, None, None, None, None).await {
         Ok(result) => {
             println!("✅ Execution result:");
             println!("   Success: {}", result.success);
@@ -74,6 +76,52 @@ async fn test_wasm_execution() {
     }
 }
 
+#[tokio::test]
+async fn test_heap_addresses_identical_across_executions() {
+    let test_wasm_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .join("wasi-examples/determinism-ark/target/wasm32-wasip1/release/determinism-ark.wasm");
+
+    if !test_wasm_path.exists() {
+        panic!(
+            "Test WASM not found! Build it first:\n\
+             cd ../wasi-examples/determinism-ark && ./build.sh"
+        );
+    }
+    let wasm_bytes = std::fs::read(&test_wasm_path).expect("Failed to read test WASM");
+
+    use offchainvm_worker::api_client::{ExecutionOutput, ResourceLimits, ResponseFormat, DEFAULT_MAX_LOG_BYTES, DEFAULT_MAX_OUTPUT_BYTES};
+    use offchainvm_worker::executor::Executor;
+
+    let resource_limits = ResourceLimits {
+        max_instructions: 1_000_000_000,
+        max_memory_mb: 16,
+        max_execution_seconds: 10,
+        max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+        max_log_bytes: DEFAULT_MAX_LOG_BYTES,
+    };
+
+    // Separate executors: nothing but the WASM and input carries over between runs
+    let mut outputs = Vec::new();
+    for _ in 0..2 {
+        let executor = Executor::new(1_000_000_000, false);
+        let result = executor
+            .execute(&wasm_bytes, None, b"same input", &resource_limits, None, None, &ResponseFormat::Text, None, None, None, None)
+            .await
+            .expect("Executor error");
+        assert!(result.success, "execution failed: {:?}", result.error);
+        match result.output {
+            Some(ExecutionOutput::Text(text)) => outputs.push(text),
+            other => panic!("unexpected output: {:?}", other),
+        }
+    }
+
+    println!("Heap addresses: {}", outputs[0]);
+    assert!(outputs[0].starts_with("box=0x"), "unexpected output: {}", outputs[0]);
+    assert_eq!(outputs[0], outputs[1]);
+}
+
 #[tokio::test]
 async fn test_minimal_wasm() {
     // Test with minimal valid WASM module
@@ -83,7 +131,7 @@ async fn test_minimal_wasm() {
     ];
 
     use offchainvm_worker::executor::Executor;
-    use offchainvm_worker::api_client::ResourceLimits;
+    use offchainvm_worker::api_client::{ResourceLimits, DEFAULT_MAX_LOG_BYTES, DEFAULT_MAX_OUTPUT_BYTES};
 
     let executor = Executor::new(1_000_000, false);
 
@@ -91,13 +139,15 @@ async fn test_minimal_wasm() {
         max_instructions: 1_000_000,
         max_memory_mb: 1,
         max_execution_seconds: 1,
+        max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+        max_log_bytes: DEFAULT_MAX_LOG_BYTES,
     };
 
     println!("⚙️  Testing minimal WASM...");
     use offchainvm_worker::api_client::ResponseFormat;
     let response_format = ResponseFormat::Text;
 
-    match executor.execute(&minimal_wasm, None, &[], &resource_limits, None, None, &response_format, None, None, None).await {
+    match executor.execute(&minimal_wasm, None, &[], &resource_limits, None, None, &response_format, None, None, None, None).await {
         Ok(result) => {
             println!("Result: success={}, error={:?}", result.success, result.error);
         }